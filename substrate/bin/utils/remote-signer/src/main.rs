@@ -0,0 +1,108 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Reference remote signer for nodes started with `--keystore-remote`.
+//!
+//! Holds the keys in a local keystore and signs on behalf of the connected nodes, refusing to
+//! sign consensus messages which would get the validator slashed.
+
+use clap::Parser;
+use sc_keystore::{
+	remote::{RemoteSigner, SignerAddress, SignerListener, SlashingProtection, TlsConfig},
+	LocalKeystore,
+};
+use sp_core::crypto::SecretString;
+use std::{error::Error, fs, path::PathBuf, sync::Arc};
+
+/// Sign on behalf of Substrate based nodes using the keys of a local keystore.
+#[derive(Debug, Parser)]
+#[command(name = "remote-signer", version)]
+struct Cli {
+	/// Address to listen on.
+	///
+	/// Either `unix:<PATH>` to listen on a Unix domain socket or `<HOST>:<PORT>` to listen on a
+	/// TCP socket. TCP requires mutual TLS, configured using `--tls-ca`, `--tls-cert` and
+	/// `--tls-key`.
+	#[arg(long, value_name = "ADDRESS")]
+	listen: String,
+
+	/// PEM encoded certificate of the authority that signed the certificates of the nodes and of
+	/// the signer.
+	#[arg(long, value_name = "PATH", requires_all = &["tls_cert", "tls_key"])]
+	tls_ca: Option<PathBuf>,
+
+	/// PEM encoded certificate chain presented by the signer to the nodes.
+	#[arg(long, value_name = "PATH", requires = "tls_ca")]
+	tls_cert: Option<PathBuf>,
+
+	/// PEM encoded private key of `--tls-cert`.
+	#[arg(long, value_name = "PATH", requires = "tls_ca")]
+	tls_key: Option<PathBuf>,
+
+	/// Path of the keystore holding the keys.
+	#[arg(long, value_name = "PATH")]
+	keystore_path: PathBuf,
+
+	/// Use interactive shell for entering the password used by the keystore.
+	#[arg(long, conflicts_with = "password_filename")]
+	password_interactive: bool,
+
+	/// File that contains the password used by the keystore.
+	#[arg(long, value_name = "PATH")]
+	password_filename: Option<PathBuf>,
+
+	/// File storing the consensus messages signed so far.
+	///
+	/// It must be kept across restarts, without it conflicting messages could be signed.
+	#[arg(long, value_name = "PATH")]
+	slashing_protection_path: PathBuf,
+
+	/// Insert the keys sent by the nodes, e.g. via their `author_insertKey` RPC.
+	///
+	/// The secret URIs of these keys are known to the nodes.
+	#[arg(long)]
+	allow_key_insertion: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+	let cli = Cli::parse();
+
+	let password = if cli.password_interactive {
+		Some(SecretString::new(rpassword::prompt_password("Keystore password: ")?))
+	} else if let Some(file) = &cli.password_filename {
+		Some(SecretString::new(fs::read_to_string(file)?))
+	} else {
+		None
+	};
+	let keystore = LocalKeystore::open(cli.keystore_path, password)?;
+	let slashing_protection = SlashingProtection::open(cli.slashing_protection_path)?;
+
+	let tls = match (cli.tls_ca, cli.tls_cert, cli.tls_key) {
+		(Some(ca_certificate), Some(certificate), Some(private_key)) =>
+			Some(TlsConfig { ca_certificate, certificate, private_key }),
+		_ => None,
+	};
+	let listener = SignerListener::bind(&SignerAddress::new(&cli.listen, tls)?)?;
+	log::info!("Listening on {}", cli.listen);
+
+	let signer = RemoteSigner::new(keystore, slashing_protection)
+		.with_key_insertion(cli.allow_key_insertion);
+	Arc::new(signer).serve(listener)?;
+	Ok(())
+}
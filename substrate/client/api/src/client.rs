@@ -247,6 +247,14 @@ pub struct IoInfo {
 	pub state_writes_cache: u64,
 	/// State write (trie nodes) to backend db.
 	pub state_writes_nodes: u64,
+	/// Trie nodes found in the shared trie cache.
+	pub trie_cache_node_hits: u64,
+	/// Trie nodes looked up in the shared trie cache.
+	pub trie_cache_node_fetch_attempts: u64,
+	/// Values found in the shared trie cache.
+	pub trie_cache_value_hits: u64,
+	/// Values looked up in the shared trie cache.
+	pub trie_cache_value_fetch_attempts: u64,
 }
 
 /// Usage statistics for running client instance.
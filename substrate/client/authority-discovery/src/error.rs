@@ -81,4 +81,7 @@ pub enum Error {
 
 	#[error("Unknown authority.")]
 	UnknownAuthority,
+
+	#[error("Failed to parse the peer id of a sentry.")]
+	ParsingSentryPeerId(sc_network_types::multihash::Error),
 }
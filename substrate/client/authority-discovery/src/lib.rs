@@ -40,7 +40,7 @@ use futures::{
 	Stream,
 };
 
-use sc_network::{event::DhtEvent, Multiaddr};
+use sc_network::{config::SentryTopology, event::DhtEvent, Multiaddr};
 use sc_network_types::PeerId;
 use sp_authority_discovery::AuthorityId;
 use sp_blockchain::HeaderBackend;
@@ -84,6 +84,19 @@ pub struct WorkerConfig {
 	/// discovery DHT record.
 	pub public_addresses: Vec<Multiaddr>,
 
+	/// Peer IDs of the sentry nodes protecting this validator.
+	///
+	/// If not empty, the authority discovery DHT record lists these peer IDs instead of the
+	/// node's own addresses. The sentries publish their addresses themselves, see
+	/// [`WorkerConfig::publish_sentry_record`].
+	pub sentries: Vec<PeerId>,
+
+	/// Publish the addresses of this node in a DHT record signed with its network key, through
+	/// which other nodes reach the validators hidden behind it.
+	///
+	/// Defaults to `false`.
+	pub publish_sentry_record: bool,
+
 	/// Reject authority discovery records that are not signed by their network identity (PeerId)
 	///
 	/// Defaults to `false` to provide compatibility with old versions
@@ -109,11 +122,33 @@ impl Default for WorkerConfig {
 			max_query_interval: Duration::from_secs(10 * 60),
 			publish_non_global_ips: true,
 			public_addresses: Vec::new(),
+			sentries: Vec::new(),
+			publish_sentry_record: false,
 			strict_record_validation: false,
 		}
 	}
 }
 
+impl WorkerConfig {
+	/// Configure the worker for the position of the node in a sentry topology.
+	///
+	/// A private validator publishes the peer IDs of its sentries instead of its own addresses,
+	/// while a sentry publishes a record with its own addresses.
+	pub fn with_sentry_topology(mut self, sentry_topology: &SentryTopology) -> Self {
+		match sentry_topology {
+			SentryTopology::None => {},
+			SentryTopology::PrivateValidator { sentries } => {
+				self.sentries = sentries.iter().map(|sentry| sentry.peer_id).collect();
+			},
+			SentryTopology::Sentry { .. } => {
+				self.publish_sentry_record = true;
+			},
+		}
+
+		self
+	}
+}
+
 /// Create a new authority discovery [`Worker`] and [`Service`].
 ///
 /// See the struct documentation of each for more details.
@@ -22,13 +22,18 @@ use crate::{
 		tests::{TestApi, TestNetwork},
 		Role,
 	},
+	WorkerConfig,
 };
 
 use futures::{channel::mpsc::channel, executor::LocalPool, task::LocalSpawn};
 use sc_network_types::ed25519;
 use std::{collections::HashSet, sync::Arc};
 
-use sc_network::{multiaddr::Protocol, Multiaddr, PeerId};
+use sc_network::{
+	config::{MultiaddrWithPeerId, SentryTopology},
+	multiaddr::Protocol,
+	Multiaddr, PeerId,
+};
 use sp_authority_discovery::AuthorityId;
 use sp_core::crypto::key_types;
 use sp_keystore::{testing::MemoryKeystore, Keystore};
@@ -103,3 +108,21 @@ fn cryptos_are_compatible() {
 	));
 	assert!(libp2p_public.verify(message, sp_core_signature.as_ref()));
 }
+
+#[test]
+fn sentry_topology_configures_worker() {
+	let sentry: MultiaddrWithPeerId =
+		"/ip4/127.0.0.1/tcp/30333/p2p/12D3KooWEBo1HUPQJwiBmM5kSeg4XgiVxEArArQdDarYEsGxMfbS"
+			.parse()
+			.unwrap();
+
+	let config = WorkerConfig::default()
+		.with_sentry_topology(&SentryTopology::PrivateValidator { sentries: vec![sentry.clone()] });
+	assert_eq!(config.sentries, vec![sentry.peer_id]);
+	assert!(!config.publish_sentry_record);
+
+	let config = WorkerConfig::default()
+		.with_sentry_topology(&SentryTopology::Sentry { validators: vec![sentry] });
+	assert!(config.sentries.is_empty());
+	assert!(config.publish_sentry_record);
+}
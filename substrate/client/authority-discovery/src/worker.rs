@@ -82,6 +82,12 @@ const MAX_ADDRESSES_TO_PUBLISH: usize = 32;
 /// Maximum number of in-flight DHT lookups at any given point in time.
 const MAX_IN_FLIGHT_LOOKUPS: usize = 8;
 
+/// Maximum number of sentries looked up per authority. Additional sentries are discarded.
+const MAX_SENTRIES_PER_AUTHORITY: usize = 8;
+
+/// Prefix of the DHT keys sentry nodes publish their records under.
+const SENTRY_RECORD_KEY_PREFIX: &[u8] = b"sentry";
+
 /// Role an authority discovery [`Worker`] can run as.
 pub enum Role {
 	/// Publish own addresses and discover addresses of others.
@@ -116,6 +122,10 @@ pub enum Role {
 ///    network peerset.
 ///
 ///    5. Allow querying of the collected addresses via the [`crate::Service`].
+///
+/// A validator hidden behind sentry nodes doesn't publish its addresses. Instead its record lists
+/// the peer ids of its sentries, each of which publishes its own addresses in a record signed with
+/// its network key. Discovering such a validator takes a second lookup per sentry.
 pub struct Worker<Client, Block: BlockT, DhtEventStream> {
 	/// Channel receiver for messages send by a [`crate::Service`].
 	from_service: Fuse<mpsc::Receiver<ServicetoWorkerMsg>>,
@@ -148,6 +158,12 @@ pub struct Worker<Client, Block: BlockT, DhtEventStream> {
 	/// discovery DHT record.
 	public_addresses: LinkedHashSet<Multiaddr>,
 
+	/// Same value as in the configuration.
+	sentries: Vec<PeerId>,
+
+	/// Same value as in the configuration.
+	publish_sentry_record: bool,
+
 	/// Same value as in the configuration.
 	strict_record_validation: bool,
 
@@ -176,6 +192,15 @@ pub struct Worker<Client, Block: BlockT, DhtEventStream> {
 	/// that.
 	last_known_records: HashMap<KademliaKey, RecordInfo>,
 
+	/// Set of lookups of sentry records, by the key the sentry publishes its record under.
+	sentry_lookups: HashMap<KademliaKey, PeerId>,
+
+	/// Sentries listed in the last known record of authorities hidden behind sentry nodes.
+	sentries_by_authority: HashMap<AuthorityId, Vec<PeerId>>,
+
+	/// Last known record of each sentry, verified against the network key of the sentry.
+	sentry_records: HashMap<PeerId, SentryRecordInfo>,
+
 	addr_cache: addr_cache::AddrCache,
 
 	metrics: Option<Metrics>,
@@ -199,6 +224,14 @@ struct RecordInfo {
 	record: Record,
 }
 
+#[derive(Debug, Clone)]
+struct SentryRecordInfo {
+	/// Time since UNIX_EPOCH in nanoseconds.
+	creation_time: u128,
+	/// Addresses of the sentry.
+	addresses: Vec<Multiaddr>,
+}
+
 /// Wrapper for [`AuthorityDiscoveryApi`](sp_authority_discovery::AuthorityDiscoveryApi). Can be
 /// be implemented by any struct without dependency on the runtime.
 #[async_trait::async_trait]
@@ -297,6 +330,8 @@ where
 			latest_published_kad_keys: HashSet::new(),
 			publish_non_global_ips: config.publish_non_global_ips,
 			public_addresses,
+			sentries: config.sentries,
+			publish_sentry_record: config.publish_sentry_record,
 			strict_record_validation: config.strict_record_validation,
 			query_interval,
 			pending_lookups: Vec::new(),
@@ -308,6 +343,9 @@ where
 			warn_public_addresses: false,
 			phantom: PhantomData,
 			last_known_records: HashMap::new(),
+			sentry_lookups: HashMap::new(),
+			sentries_by_authority: HashMap::new(),
+			sentry_records: HashMap::new(),
 		}
 	}
 
@@ -469,14 +507,25 @@ where
 	/// If `only_if_changed` is true, the function has no effect if the list of keys to publish
 	/// is equal to `self.latest_published_keys`.
 	async fn publish_ext_addresses(&mut self, only_if_changed: bool) -> Result<()> {
+		// The record of a sentry doesn't depend on the keys in the keystore.
+		if self.publish_sentry_record && !only_if_changed {
+			self.publish_sentry_addresses()?;
+		}
+
 		let key_store = match &self.role {
 			Role::PublishAndDiscover(key_store) => key_store,
 			Role::Discover => return Ok(()),
 		}
 		.clone();
 
-		let addresses = serialize_addresses(self.addresses_to_publish());
-		if addresses.is_empty() {
+		// A validator hidden behind sentry nodes must never reveal its own addresses. It only
+		// lists its sentries, which publish their addresses themselves.
+		let (addresses, sentries) = if self.sentries.is_empty() {
+			(serialize_addresses(self.addresses_to_publish()), Vec::new())
+		} else {
+			(Vec::new(), self.sentries.iter().map(|sentry| sentry.to_bytes()).collect())
+		};
+		if addresses.is_empty() && sentries.is_empty() {
 			trace!(
 				target: LOG_TARGET,
 				"No addresses to publish. Skipping publication."
@@ -515,19 +564,29 @@ where
 				.set(addresses.len().try_into().unwrap_or(std::u64::MAX));
 		}
 
-		let serialized_record = serialize_authority_record(addresses, Some(build_creation_time()))?;
-		let peer_signature = sign_record_with_peer_id(&serialized_record, &self.network)?;
+		let serialized_record =
+			serialize_authority_record(addresses, sentries, Some(build_creation_time()))?;
+		// A record listing sentries doesn't contain any address of the local network identity.
+		let peer_signature = if self.sentries.is_empty() {
+			Some(sign_record_with_peer_id(&serialized_record, &self.network)?)
+		} else {
+			None
+		};
 
 		let keys_vec = keys.iter().cloned().collect::<Vec<_>>();
 
 		let kv_pairs = sign_record_with_authority_ids(
 			serialized_record,
-			Some(peer_signature),
+			peer_signature,
 			key_store.as_ref(),
 			keys_vec,
 		)?;
 
 		self.latest_published_kad_keys = kv_pairs.iter().map(|(k, _)| k.clone()).collect();
+		if self.publish_sentry_record {
+			self.latest_published_kad_keys
+				.insert(hash_sentry_peer_id(&self.network.local_peer_id()));
+		}
 
 		for (key, value) in kv_pairs.into_iter() {
 			self.network.put_value(key, value);
@@ -538,6 +597,35 @@ where
 		Ok(())
 	}
 
+	/// Publish the addresses of this sentry node, signed with its network key.
+	fn publish_sentry_addresses(&mut self) -> Result<()> {
+		let addresses = serialize_addresses(self.addresses_to_publish());
+		if addresses.is_empty() {
+			trace!(
+				target: LOG_TARGET,
+				"No sentry addresses to publish. Skipping publication."
+			);
+
+			self.publish_interval.set_to_start();
+			return Ok(())
+		}
+
+		let serialized_record =
+			serialize_authority_record(addresses, Vec::new(), Some(build_creation_time()))?;
+		let peer_signature = sign_record_with_peer_id(&serialized_record, &self.network)?;
+		let signed_record = schema::SignedSentryRecord {
+			record: serialized_record,
+			peer_signature: Some(peer_signature),
+		}
+		.encode_to_vec();
+
+		let key = hash_sentry_peer_id(&self.network.local_peer_id());
+		self.latest_published_kad_keys.insert(key.clone());
+		self.network.put_value(key, signed_record);
+
+		Ok(())
+	}
+
 	async fn refill_pending_lookups_queue(&mut self) -> Result<()> {
 		let best_hash = self.client.best_hash().await?;
 
@@ -570,6 +658,11 @@ where
 		self.last_known_records.retain(|k, value| {
 			self.known_authorities.contains_key(k) && !value.record.is_expired(now)
 		});
+		self.sentries_by_authority.retain(|authority, _| {
+			self.known_authorities.contains_key(&hash_authority_id(authority.as_ref()))
+		});
+		let known_sentries = self.sentries_by_authority.values().flatten().collect::<HashSet<_>>();
+		self.sentry_records.retain(|sentry, _| known_sentries.contains(sentry));
 
 		authorities.shuffle(&mut thread_rng());
 		self.pending_lookups = authorities;
@@ -577,6 +670,7 @@ where
 		// query interval ticks are far enough apart for all lookups to succeed.
 		self.in_flight_lookups.clear();
 		self.known_lookups.clear();
+		self.sentry_lookups.clear();
 
 		if let Some(metrics) = &self.metrics {
 			metrics
@@ -628,7 +722,9 @@ where
 					metrics.dht_event_received.with_label_values(&["value_not_found"]).inc();
 				}
 
-				if self.in_flight_lookups.remove(&hash).is_some() {
+				if self.in_flight_lookups.remove(&hash).is_some() ||
+					self.sentry_lookups.remove(&hash).is_some()
+				{
 					debug!(target: LOG_TARGET, "Value for hash '{:?}' not found on Dht.", hash)
 				} else {
 					debug!(
@@ -692,6 +788,13 @@ where
 	) -> Result<()> {
 		let publisher = publisher.ok_or(Error::MissingPublisher)?;
 
+		// Sentry nodes publish their records under a key derived from their own peer id.
+		if record_key == hash_sentry_peer_id(&publisher) {
+			self.check_sentry_record_signed_with_network_key(record_value.as_slice(), publisher)?;
+			self.network.store_record(record_key, record_value, Some(publisher), expires);
+			return Ok(())
+		}
+
 		// Make sure we don't ever work with an outdated set of authorities
 		// and that we do not update known_authorithies too often.
 		let best_hash = self.client.best_hash().await?;
@@ -720,21 +823,20 @@ where
 			self.known_authorities.get(&record_key).ok_or(Error::UnknownAuthority)?;
 		let signed_record =
 			Self::check_record_signed_with_authority_id(record_value.as_slice(), authority_id)?;
-		self.check_record_signed_with_network_key(
-			&signed_record.record,
-			signed_record.peer_signature,
-			publisher,
-			authority_id,
-		)?;
+		let authority_record = schema::AuthorityRecord::decode(signed_record.record.as_slice())
+			.map_err(Error::DecodingProto)?;
+
+		// A record listing sentries doesn't contain any address of the publisher.
+		if authority_record.sentries.is_empty() {
+			self.check_record_signed_with_network_key(
+				&signed_record.record,
+				signed_record.peer_signature,
+				publisher,
+				authority_id,
+			)?;
+		}
 
-		let records_creation_time: u128 =
-			schema::AuthorityRecord::decode(signed_record.record.as_slice())
-				.map_err(Error::DecodingProto)?
-				.creation_time
-				.map(|creation_time| {
-					u128::decode(&mut &creation_time.timestamp[..]).unwrap_or_default()
-				})
-				.unwrap_or_default(); // 0 is a sane default for records that do not have creation time present.
+		let records_creation_time = record_creation_time(&authority_record);
 
 		let current_record_info = self.last_known_records.get(&record_key);
 		// If record creation time is older than the current record creation time,
@@ -780,16 +882,7 @@ where
 		authority_id: &AuthorityId,
 	) -> Result<()> {
 		if let Some(peer_signature) = peer_signature {
-			match self.network.verify(
-				remote_peer_id.into(),
-				&peer_signature.public_key,
-				&peer_signature.signature,
-				record,
-			) {
-				Ok(true) => {},
-				Ok(false) => return Err(Error::VerifyingDhtPayload),
-				Err(error) => return Err(Error::ParsingLibp2pIdentity(error)),
-			}
+			self.check_peer_signature(record, peer_signature, remote_peer_id)?;
 		} else if self.strict_record_validation {
 			return Err(Error::MissingPeerIdSignature)
 		} else {
@@ -801,10 +894,48 @@ where
 		Ok(())
 	}
 
+	fn check_sentry_record_signed_with_network_key(
+		&self,
+		record: &[u8],
+		sentry: PeerId,
+	) -> Result<schema::AuthorityRecord> {
+		let signed_record =
+			schema::SignedSentryRecord::decode(record).map_err(Error::DecodingProto)?;
+
+		// Unlike authority records, sentry records are only signed with the network key.
+		let peer_signature = signed_record.peer_signature.ok_or(Error::MissingPeerIdSignature)?;
+		self.check_peer_signature(&signed_record.record, peer_signature, sentry)?;
+
+		schema::AuthorityRecord::decode(signed_record.record.as_slice())
+			.map_err(Error::DecodingProto)
+	}
+
+	fn check_peer_signature(
+		&self,
+		record: &Vec<u8>,
+		peer_signature: PeerSignature,
+		remote_peer_id: PeerId,
+	) -> Result<()> {
+		match self.network.verify(
+			remote_peer_id.into(),
+			&peer_signature.public_key,
+			&peer_signature.signature,
+			record,
+		) {
+			Ok(true) => Ok(()),
+			Ok(false) => Err(Error::VerifyingDhtPayload),
+			Err(error) => Err(Error::ParsingLibp2pIdentity(error)),
+		}
+	}
+
 	fn handle_dht_value_found_event(&mut self, peer_record: PeerRecord) -> Result<()> {
 		// Ensure `values` is not empty and all its keys equal.
 		let remote_key = peer_record.record.key.clone();
 
+		if let Some(sentry) = self.sentry_lookups.get(&remote_key).copied() {
+			return self.handle_sentry_record_found(sentry, peer_record)
+		}
+
 		let authority_id: AuthorityId =
 			if let Some(authority_id) = self.in_flight_lookups.remove(&remote_key) {
 				self.known_lookups.insert(remote_key.clone(), authority_id.clone());
@@ -826,13 +957,21 @@ where
 		let authority_record =
 			schema::AuthorityRecord::decode(record.as_slice()).map_err(Error::DecodingProto)?;
 
-		let records_creation_time: u128 = authority_record
-			.creation_time
-			.as_ref()
-			.map(|creation_time| {
-				u128::decode(&mut &creation_time.timestamp[..]).unwrap_or_default()
-			})
-			.unwrap_or_default(); // 0 is a sane default for records that do not have creation time present.
+		let records_creation_time = record_creation_time(&authority_record);
+
+		let answering_peer_id = peer_record.peer.map(|peer| peer.into());
+
+		if !authority_record.sentries.is_empty() {
+			return self.handle_sentry_authority_record_found(
+				authority_id,
+				authority_record.sentries,
+				RecordInfo {
+					creation_time: records_creation_time,
+					peers_with_record: answering_peer_id.into_iter().collect(),
+					record: peer_record.record,
+				},
+			)
+		}
 
 		let addresses: Vec<Multiaddr> = authority_record
 			.addresses
@@ -841,11 +980,6 @@ where
 			.collect::<std::result::Result<_, _>>()
 			.map_err(Error::ParsingMultiaddress)?;
 
-		let get_peer_id = |a: &Multiaddr| match a.iter().last() {
-			Some(multiaddr::Protocol::P2p(key)) => PeerId::from_multihash(key).ok(),
-			_ => None,
-		};
-
 		// Ignore [`Multiaddr`]s without [`PeerId`] or with own addresses.
 		let addresses: Vec<Multiaddr> = addresses
 			.into_iter()
@@ -870,8 +1004,6 @@ where
 		let remote_addresses: Vec<Multiaddr> =
 			addresses.into_iter().take(MAX_ADDRESSES_PER_AUTHORITY).collect();
 
-		let answering_peer_id = peer_record.peer.map(|peer| peer.into());
-
 		let addr_cache_needs_update = self.handle_new_record(
 			&authority_id,
 			remote_key.clone(),
@@ -893,6 +1025,120 @@ where
 		Ok(())
 	}
 
+	// Handles receiving the record of an authority hidden behind sentry nodes, looking up the
+	// records of its sentries.
+	fn handle_sentry_authority_record_found(
+		&mut self,
+		authority_id: AuthorityId,
+		sentries: Vec<Vec<u8>>,
+		new_record: RecordInfo,
+	) -> Result<()> {
+		let local_peer_id = self.network.local_peer_id();
+
+		let sentries = sentries
+			.iter()
+			.map(|sentry| PeerId::from_bytes(sentry))
+			.collect::<std::result::Result<Vec<_>, _>>()
+			.map_err(Error::ParsingSentryPeerId)?
+			.into_iter()
+			.filter(|sentry| *sentry != local_peer_id)
+			.take(MAX_SENTRIES_PER_AUTHORITY)
+			.collect::<Vec<_>>();
+
+		if !self.handle_new_record(&authority_id, new_record.record.key.clone(), new_record) {
+			return Ok(())
+		}
+
+		debug!(
+			target: LOG_TARGET,
+			"Found authority {:?} behind sentries {:?}", authority_id, sentries,
+		);
+
+		for sentry in sentries.iter() {
+			let key = hash_sentry_peer_id(sentry);
+			if self.sentry_lookups.insert(key.clone(), *sentry).is_none() {
+				self.network.get_value(&key);
+			}
+		}
+
+		self.sentries_by_authority.insert(authority_id.clone(), sentries);
+		self.update_sentry_addresses(&authority_id);
+
+		Ok(())
+	}
+
+	// Handles receiving the record of a sentry, caching its addresses for all the authorities
+	// hidden behind it.
+	fn handle_sentry_record_found(
+		&mut self,
+		sentry: PeerId,
+		peer_record: PeerRecord,
+	) -> Result<()> {
+		let sentry_record = self.check_sentry_record_signed_with_network_key(
+			peer_record.record.value.as_slice(),
+			sentry,
+		)?;
+
+		let creation_time = record_creation_time(&sentry_record);
+		if let Some(current_record_info) = self.sentry_records.get(&sentry) {
+			if creation_time < current_record_info.creation_time {
+				debug!(
+					target: LOG_TARGET,
+					"Found old record for sentry {:?} received record creation time {:?} current record creation time {:?}",
+					sentry, creation_time, current_record_info.creation_time,
+				);
+				return Ok(())
+			}
+		}
+
+		// Only the addresses of the sentry itself are signed by it.
+		let addresses = sentry_record
+			.addresses
+			.into_iter()
+			.map(|a| a.try_into())
+			.collect::<std::result::Result<Vec<Multiaddr>, _>>()
+			.map_err(Error::ParsingMultiaddress)?
+			.into_iter()
+			.filter(|a| get_peer_id(a) == Some(sentry))
+			.take(MAX_ADDRESSES_PER_AUTHORITY)
+			.collect();
+
+		self.sentry_records.insert(sentry, SentryRecordInfo { creation_time, addresses });
+
+		let authorities = self
+			.sentries_by_authority
+			.iter()
+			.filter(|(_, sentries)| sentries.contains(&sentry))
+			.map(|(authority_id, _)| authority_id.clone())
+			.collect::<Vec<_>>();
+		for authority_id in authorities.iter() {
+			self.update_sentry_addresses(authority_id);
+		}
+
+		Ok(())
+	}
+
+	// Caches the addresses of the known sentries of an authority hidden behind sentry nodes.
+	fn update_sentry_addresses(&mut self, authority_id: &AuthorityId) {
+		let Some(sentries) = self.sentries_by_authority.get(authority_id) else { return };
+
+		let addresses = sentries
+			.iter()
+			.filter_map(|sentry| self.sentry_records.get(sentry))
+			.flat_map(|sentry_record| sentry_record.addresses.iter().cloned())
+			.take(MAX_ADDRESSES_PER_AUTHORITY)
+			.collect::<Vec<_>>();
+
+		if !addresses.is_empty() {
+			self.addr_cache.insert(authority_id.clone(), addresses);
+			if let Some(metrics) = &self.metrics {
+				metrics
+					.known_authorities_count
+					.set(self.addr_cache.num_authority_ids().try_into().unwrap_or(std::u64::MAX));
+			}
+		}
+	}
+
 	// Handles receiving a new DHT record for the authorithy.
 	// Returns true if the record was new, false if the record was older than the current one.
 	fn handle_new_record(
@@ -1040,6 +1286,26 @@ fn hash_authority_id(id: &[u8]) -> KademliaKey {
 	KademliaKey::new(&Code::Sha2_256.digest(id).digest())
 }
 
+fn hash_sentry_peer_id(peer_id: &PeerId) -> KademliaKey {
+	let key = [SENTRY_RECORD_KEY_PREFIX, &peer_id.to_bytes()[..]].concat();
+	KademliaKey::new(&Code::Sha2_256.digest(&key).digest())
+}
+
+fn get_peer_id(address: &Multiaddr) -> Option<PeerId> {
+	match address.iter().last() {
+		Some(multiaddr::Protocol::P2p(key)) => PeerId::from_multihash(key).ok(),
+		_ => None,
+	}
+}
+
+fn record_creation_time(record: &schema::AuthorityRecord) -> u128 {
+	record
+		.creation_time
+		.as_ref()
+		.map(|creation_time| u128::decode(&mut &creation_time.timestamp[..]).unwrap_or_default())
+		.unwrap_or_default() // 0 is a sane default for records that do not have creation time present.
+}
+
 // Makes sure all values are the same and returns it
 //
 // Returns Err(_) if not all values are equal. Returns Ok(None) if there are
@@ -1069,11 +1335,12 @@ fn build_creation_time() -> schema::TimestampInfo {
 
 fn serialize_authority_record(
 	addresses: Vec<Vec<u8>>,
+	sentries: Vec<Vec<u8>>,
 	creation_time: Option<schema::TimestampInfo>,
 ) -> Result<Vec<u8>> {
 	let mut serialized_record = vec![];
 
-	schema::AuthorityRecord { addresses, creation_time }
+	schema::AuthorityRecord { addresses, creation_time, sentries }
 		.encode(&mut serialized_record)
 		.map_err(Error::EncodingProto)?;
 	Ok(serialized_record)
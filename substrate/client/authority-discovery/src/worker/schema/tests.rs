@@ -109,6 +109,7 @@ fn v1_decodes_v3() {
 	let record_v3 = AuthorityRecord {
 		addresses: vec_addresses.clone(),
 		creation_time: Some(TimestampInfo { timestamp: Encode::encode(&55) }),
+		sentries: vec![],
 	};
 	let mut vec_record_v3 = vec![];
 	record_v3.encode(&mut vec_record_v3).unwrap();
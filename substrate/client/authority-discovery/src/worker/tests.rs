@@ -289,9 +289,12 @@ fn build_dht_event<Signer: NetworkSigner>(
 	network: Option<&Signer>,
 	creation_time: Option<schema::TimestampInfo>,
 ) -> Vec<(KademliaKey, Vec<u8>)> {
-	let serialized_record =
-		serialize_authority_record(serialize_addresses(addresses.into_iter()), creation_time)
-			.unwrap();
+	let serialized_record = serialize_authority_record(
+		serialize_addresses(addresses.into_iter()),
+		vec![],
+		creation_time,
+	)
+	.unwrap();
 
 	let peer_signature = network.map(|n| sign_record_with_peer_id(&serialized_record, n).unwrap());
 	let kv_pairs = sign_record_with_authority_ids(
@@ -629,6 +632,17 @@ impl DhtValueFoundTester {
 		&mut self,
 		strict_record_validation: bool,
 		values: Vec<(KademliaKey, Vec<u8>)>,
+	) -> (Option<HashSet<Multiaddr>>, Option<Arc<TestNetwork>>) {
+		self.process_value_found_with_config(
+			WorkerConfig { strict_record_validation, ..Default::default() },
+			values,
+		)
+	}
+
+	fn process_value_found_with_config(
+		&mut self,
+		config: WorkerConfig,
+		values: Vec<(KademliaKey, Vec<u8>)>,
 	) -> (Option<HashSet<Multiaddr>>, Option<Arc<TestNetwork>>) {
 		let (_dht_event_tx, dht_event_rx) = channel(1);
 		let local_test_api =
@@ -648,7 +662,7 @@ impl DhtValueFoundTester {
 				Box::pin(dht_event_rx),
 				Role::PublishAndDiscover(Arc::new(local_key_store)),
 				None,
-				WorkerConfig { strict_record_validation, ..Default::default() },
+				config,
 			));
 			(self.local_worker.as_mut().unwrap(), Some(local_network))
 		};
@@ -1087,6 +1101,198 @@ fn addresses_to_publish_respects_existing_p2p_protocol() {
 	);
 }
 
+#[test]
+fn private_validator_publishes_sentries() {
+	let (_dht_event_tx, dht_event_rx) = channel(1000);
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+	let sentries = vec![PeerId::random(), PeerId::random()];
+	let key_store = MemoryKeystore::new();
+	let public = key_store.sr25519_generate_new(key_types::AUTHORITY_DISCOVERY, None).unwrap();
+
+	let (_to_worker, from_service) = mpsc::channel(0);
+	let mut worker = Worker::new(
+		from_service,
+		Arc::new(TestApi { authorities: vec![public.into()] }),
+		network.clone(),
+		Box::pin(dht_event_rx),
+		Role::PublishAndDiscover(key_store.into()),
+		None,
+		WorkerConfig { sentries: sentries.clone(), ..Default::default() },
+	);
+
+	block_on(worker.publish_ext_addresses(false)).unwrap();
+
+	let (key, value) = network.put_value_call.lock().unwrap().pop().unwrap();
+	assert_eq!(hash_authority_id(public.as_slice()), key);
+
+	let signed_record = schema::SignedAuthorityRecord::decode(value.as_slice()).unwrap();
+	assert!(signed_record.peer_signature.is_none());
+
+	let record = schema::AuthorityRecord::decode(signed_record.record.as_slice()).unwrap();
+	assert!(record.addresses.is_empty(), "Expected the validator not to publish its addresses.");
+	assert_eq!(
+		sentries.iter().map(|sentry| sentry.to_bytes()).collect::<Vec<_>>(),
+		record.sentries,
+	);
+}
+
+#[test]
+fn resolve_authority_behind_sentries() {
+	sp_tracing::try_init_simple();
+
+	let new_worker = |network: Arc<TestNetwork>, authorities, role, config| {
+		let (_dht_event_tx, dht_event_rx) = channel(1000);
+		let (_to_worker, from_service) = mpsc::channel(0);
+		Worker::new(
+			from_service,
+			Arc::new(TestApi { authorities }),
+			network,
+			Box::pin(dht_event_rx),
+			role,
+			None,
+			config,
+		)
+	};
+	let sentry_config = || WorkerConfig { publish_sentry_record: true, ..Default::default() };
+	let published =
+		|network: &Arc<TestNetwork>| network.put_value_call.lock().unwrap().pop().unwrap();
+	let value_found = |(key, value): (KademliaKey, Vec<u8>)| PeerRecord {
+		peer: None,
+		record: Record { key, value, publisher: None, expires: None },
+	};
+
+	// Two sentries and a rogue node publishing their own addresses.
+	let sentry_networks: Vec<Arc<TestNetwork>> =
+		(0..3).map(|_| Arc::new(Default::default())).collect();
+	let sentry_records = sentry_networks
+		.iter()
+		.map(|network| {
+			let mut worker = new_worker(network.clone(), vec![], Role::Discover, sentry_config());
+			block_on(worker.publish_ext_addresses(false)).unwrap();
+			published(network)
+		})
+		.collect::<Vec<_>>();
+	let sentries =
+		sentry_networks[..2].iter().map(|network| network.local_peer_id()).collect::<Vec<_>>();
+
+	// The validator hidden behind the first two sentries.
+	let validator_network: Arc<TestNetwork> = Arc::new(Default::default());
+	let validator_key_store = MemoryKeystore::new();
+	let validator: AuthorityId = validator_key_store
+		.sr25519_generate_new(key_types::AUTHORITY_DISCOVERY, None)
+		.unwrap()
+		.into();
+	let mut worker = new_worker(
+		validator_network.clone(),
+		vec![validator.clone()],
+		Role::PublishAndDiscover(validator_key_store.into()),
+		WorkerConfig { sentries: sentries.clone(), ..Default::default() },
+	);
+	block_on(worker.publish_ext_addresses(false)).unwrap();
+	let validator_record = published(&validator_network);
+
+	// A third node, unaware of the sentry topology, resolving the validator.
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+	let mut worker = new_worker(
+		network.clone(),
+		vec![validator.clone()],
+		Role::Discover,
+		WorkerConfig { strict_record_validation: true, ..Default::default() },
+	);
+	block_on(worker.refill_pending_lookups_queue()).unwrap();
+	worker.start_new_lookups();
+
+	worker.handle_dht_value_found_event(value_found(validator_record)).unwrap();
+	let sentry_keys = sentries.iter().map(hash_sentry_peer_id).collect::<Vec<_>>();
+	assert_eq!(
+		sentry_keys.iter().collect::<HashSet<_>>(),
+		network.get_value_call.lock().unwrap()[1..].iter().collect::<HashSet<_>>(),
+		"Expected a lookup for each sentry.",
+	);
+	assert!(worker.addr_cache.get_addresses_by_authority_id(&validator).is_none());
+
+	// The record of the rogue node is not accepted for a sentry.
+	let (_, rogue_value) = sentry_records[2].clone();
+	assert!(matches!(
+		worker.handle_dht_value_found_event(value_found((sentry_keys[0].clone(), rogue_value))),
+		Err(Error::VerifyingDhtPayload)
+	));
+	assert!(worker.addr_cache.get_addresses_by_authority_id(&validator).is_none());
+
+	for sentry_record in sentry_records[..2].iter().cloned() {
+		worker.handle_dht_value_found_event(value_found(sentry_record)).unwrap();
+	}
+
+	let expected_addresses = sentry_networks[..2]
+		.iter()
+		.flat_map(|network| {
+			network
+				.external_addresses()
+				.into_iter()
+				.map(|address| address.with(multiaddr::Protocol::P2p(network.peer_id.into())))
+		})
+		.collect::<HashSet<_>>();
+	assert_eq!(
+		Some(&expected_addresses),
+		worker.addr_cache.get_addresses_by_authority_id(&validator),
+	);
+	for sentry in sentries.iter() {
+		assert_eq!(
+			Some(&HashSet::from([validator.clone()])),
+			worker.addr_cache.get_authority_ids_by_peer_id(sentry),
+		);
+	}
+}
+
+#[test]
+fn store_sentry_record_only_from_the_sentry() {
+	let sentry_network: Arc<TestNetwork> = Arc::new(Default::default());
+	let sentry = sentry_network.local_peer_id();
+
+	let (_dht_event_tx, dht_event_rx) = channel(1000);
+	let (_to_worker, from_service) = mpsc::channel(0);
+	let mut worker = Worker::new(
+		from_service,
+		Arc::new(TestApi { authorities: vec![] }),
+		sentry_network.clone(),
+		Box::pin(dht_event_rx),
+		Role::Discover,
+		None,
+		WorkerConfig { publish_sentry_record: true, ..Default::default() },
+	);
+	block_on(worker.publish_ext_addresses(false)).unwrap();
+	let (key, value) = sentry_network.put_value_call.lock().unwrap().pop().unwrap();
+	assert_eq!(hash_sentry_peer_id(&sentry), key);
+	assert!(worker.latest_published_kad_keys.contains(&key));
+
+	let (_dht_event_tx, dht_event_rx) = channel(1000);
+	let (_to_worker, from_service) = mpsc::channel(0);
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+	let mut worker = Worker::new(
+		from_service,
+		Arc::new(TestApi { authorities: vec![] }),
+		network.clone(),
+		Box::pin(dht_event_rx),
+		Role::Discover,
+		None,
+		Default::default(),
+	);
+
+	block_on(async {
+		let rogue_publisher = Some(PeerId::random());
+		assert!(matches!(
+			worker
+				.handle_put_record_requested(key.clone(), value.clone(), rogue_publisher, None)
+				.await,
+			Err(Error::UnknownAuthority)
+		));
+		assert!(network.store_value_call.lock().unwrap().is_empty());
+
+		worker.handle_put_record_requested(key, value, Some(sentry), None).await.unwrap();
+		assert_eq!(network.store_value_call.lock().unwrap().len(), 1);
+	});
+}
+
 #[test]
 fn lookup_throttling() {
 	let remote_multiaddr = {
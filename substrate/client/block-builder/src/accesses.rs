@@ -0,0 +1,232 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Recording of the storage accessed by the extrinsics of a block.
+
+use super::BlockBuilderApi;
+use sp_api::{ApiExt, Core, ProvideRuntimeApi};
+use sp_blockchain::{ApplyExtrinsicFailed, Error};
+use sp_core::traits::CallContext;
+use sp_runtime::{
+	legacy,
+	traits::{Block as BlockT, Header as HeaderT},
+	DigestItem,
+};
+use sp_state_machine::{QualifiedKey, StorageAccesses};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The storage accessed while executing a block, split by the phases of the execution.
+#[derive(Debug, Default, Clone)]
+pub struct BlockStorageAccesses {
+	/// The accesses of `initialize_block`.
+	pub initialization: StorageAccesses,
+	/// The accesses of every extrinsic, in the order of the block.
+	pub extrinsics: Vec<StorageAccesses>,
+	/// The accesses of `finalize_block`.
+	pub finalization: StorageAccesses,
+}
+
+/// The extrinsics of a block which accessed a storage key.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KeyUsage {
+	/// The indices of the extrinsics which read the key.
+	pub readers: BTreeSet<u32>,
+	/// The indices of the extrinsics which wrote the key.
+	pub writers: BTreeSet<u32>,
+}
+
+impl KeyUsage {
+	/// The number of distinct extrinsics which accessed the key.
+	pub fn extrinsics(&self) -> usize {
+		self.readers.union(&self.writers).count()
+	}
+}
+
+/// An extrinsic of a block which depends on or overwrites the writes of an earlier extrinsic.
+///
+/// Two such extrinsics can not be executed in parallel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contention {
+	/// The index of the later extrinsic.
+	pub extrinsic: u32,
+	/// The index of the earlier extrinsic.
+	pub writer: u32,
+	/// The keys written by the earlier extrinsic and accessed by the later one.
+	///
+	/// This can be empty if the conflict is caused by a cleared prefix or by calculating the
+	/// storage root.
+	pub keys: Vec<QualifiedKey>,
+}
+
+impl BlockStorageAccesses {
+	/// The keys accessed by more than one extrinsic, the most contended ones first.
+	pub fn hot_keys(&self) -> Vec<(QualifiedKey, KeyUsage)> {
+		let mut usage = BTreeMap::<QualifiedKey, KeyUsage>::new();
+		for (index, accesses) in (0u32..).zip(&self.extrinsics) {
+			for key in accesses.reads() {
+				usage.entry(key.clone()).or_default().readers.insert(index);
+			}
+			for key in accesses.writes() {
+				usage.entry(key.clone()).or_default().writers.insert(index);
+			}
+		}
+
+		let mut hot_keys =
+			usage.into_iter().filter(|(_, usage)| usage.extrinsics() > 1).collect::<Vec<_>>();
+		hot_keys.sort_by(|(_, a), (_, b)| {
+			b.writers.len().cmp(&a.writers.len()).then(b.extrinsics().cmp(&a.extrinsics()))
+		});
+		hot_keys
+	}
+
+	/// The extrinsics which depend on or overwrite the writes of earlier extrinsics.
+	///
+	/// See [`StorageAccesses::conflicts_with`].
+	pub fn contention(&self) -> Vec<Contention> {
+		let mut contention = Vec::new();
+		for (extrinsic, accesses) in (0u32..).zip(&self.extrinsics) {
+			for (writer, earlier) in (0u32..).zip(&self.extrinsics[..extrinsic as usize]) {
+				if accesses.conflicts_with(earlier) {
+					let keys = accesses.conflicting_keys(earlier).cloned().collect();
+					contention.push(Contention { extrinsic, writer, keys });
+				}
+			}
+		}
+		contention
+	}
+}
+
+/// Execute `block` on top of its parent and record the storage accessed by each of its
+/// extrinsics.
+///
+/// The block is executed through the [`BlockBuilderApi`] like it was when being built, so its
+/// storage root and its seal are not checked.
+pub fn record_storage_accesses<Block, C>(
+	client: &C,
+	block: Block,
+) -> Result<BlockStorageAccesses, Error>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: BlockBuilderApi<Block> + ApiExt<Block>,
+{
+	let (mut header, extrinsics) = block.deconstruct();
+	let parent_hash = *header.parent_hash();
+	// The seal is added after the block was built, the runtime never saw it.
+	header.digest_mut().logs.retain(|item| !matches!(item, DigestItem::Seal(_, _)));
+
+	let mut api = client.runtime_api();
+	api.set_call_context(CallContext::Onchain);
+
+	let core_version = api
+		.api_version::<dyn Core<Block>>(parent_hash)?
+		.ok_or_else(|| Error::VersionInvalid("Core".to_string()))?;
+	let bb_version = api
+		.api_version::<dyn BlockBuilderApi<Block>>(parent_hash)?
+		.ok_or_else(|| Error::VersionInvalid("BlockBuilderApi".to_string()))?;
+
+	let start_recording =
+		|| api.with_overlayed_changes(|changes| changes.start_recording_accesses());
+	let take_recorded = || {
+		api.with_overlayed_changes(|changes| changes.take_recorded_accesses())
+			.flatten()
+			.unwrap_or_default()
+	};
+
+	start_recording();
+	if core_version >= 5 {
+		api.initialize_block(parent_hash, &header)?;
+	} else {
+		#[allow(deprecated)]
+		api.initialize_block_before_version_5(parent_hash, &header)?;
+	}
+	let initialization = take_recorded();
+
+	let extrinsics = extrinsics
+		.into_iter()
+		.map(|xt| {
+			start_recording();
+			let result = if bb_version < 6 {
+				#[allow(deprecated)]
+				api.apply_extrinsic_before_version_6(parent_hash, xt)
+					.map(legacy::byte_sized_error::convert_to_latest)
+			} else {
+				api.apply_extrinsic(parent_hash, xt)
+			};
+			result?.map_err(ApplyExtrinsicFailed::Validity)?;
+
+			Ok(take_recorded())
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+
+	start_recording();
+	api.finalize_block(parent_hash)?;
+	let finalization = take_recorded();
+
+	Ok(BlockStorageAccesses { initialization, extrinsics, finalization })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BlockBuilderBuilder;
+	use sp_blockchain::HeaderBackend;
+	use substrate_test_runtime_client::{runtime::ExtrinsicBuilder, DefaultTestClientBuilderExt};
+
+	#[test]
+	fn storage_accesses_are_recorded_per_extrinsic() {
+		let client = substrate_test_runtime_client::TestClientBuilder::new().build();
+		let genesis_hash = client.info().best_hash;
+
+		let mut block_builder = BlockBuilderBuilder::new(&client)
+			.on_parent_block(genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap();
+		for xt in [
+			ExtrinsicBuilder::new_storage_change(b"a".to_vec(), Some(b"1".to_vec())).build(),
+			ExtrinsicBuilder::new_storage_change(b"b".to_vec(), Some(b"2".to_vec())).build(),
+			ExtrinsicBuilder::new_storage_change(b"a".to_vec(), None).build(),
+		] {
+			block_builder.push(xt).unwrap();
+		}
+		let block = block_builder.build().unwrap().block;
+
+		let accesses = record_storage_accesses(&client, block).unwrap();
+		assert_eq!(accesses.extrinsics.len(), 3);
+
+		let key_a = (None, b"a".to_vec());
+		let key_b = (None, b"b".to_vec());
+		assert!(accesses.extrinsics[0].writes().any(|key| *key == key_a));
+		assert!(accesses.extrinsics[1].writes().any(|key| *key == key_b));
+		assert!(!accesses.extrinsics[1].writes().any(|key| *key == key_a));
+
+		let hot_keys = accesses.hot_keys();
+		let (_, usage) = hot_keys.iter().find(|(key, _)| *key == key_a).unwrap();
+		assert_eq!(usage.writers, BTreeSet::from([0, 2]));
+		assert!(!hot_keys.iter().any(|(key, _)| *key == key_b));
+
+		let contention = accesses.contention();
+		let overwrites_a = contention.iter().find(|c| c.extrinsic == 2 && c.writer == 0).unwrap();
+		assert!(overwrites_a.keys.contains(&key_a));
+		assert!(contention
+			.iter()
+			.filter(|c| c.extrinsic == 1)
+			.all(|c| !c.keys.contains(&key_a) && !c.keys.contains(&key_b)));
+	}
+}
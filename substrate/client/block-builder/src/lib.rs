@@ -23,6 +23,9 @@
 //!
 //! The block builder utility is used in the node as an abstraction over the runtime api to
 //! initialize a block, to push extrinsics and to finalize a block.
+//!
+//! [`record_storage_accesses`] records the storage keys accessed by every extrinsic of an existing
+//! block, to find the keys which prevent its extrinsics from being executed in parallel.
 
 #![warn(missing_docs)]
 
@@ -42,8 +45,13 @@ use sp_runtime::{
 use std::marker::PhantomData;
 
 pub use sp_block_builder::BlockBuilder as BlockBuilderApi;
+pub use sp_state_machine::{QualifiedKey, StorageAccesses};
 use sp_trie::proof_size_extension::ProofSizeExt;
 
+mod accesses;
+
+pub use accesses::{record_storage_accesses, BlockStorageAccesses, Contention, KeyUsage};
+
 /// A builder for creating an instance of [`BlockBuilder`].
 pub struct BlockBuilderBuilder<'a, B, C> {
 	call_api_at: &'a C,
@@ -49,7 +49,10 @@ pub const DEFAULT_WASMTIME_INSTANTIATION_STRATEGY: WasmtimeInstantiationStrategy
 #[derive(Debug, Clone, Copy, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum WasmExecutionMethod {
-	/// Uses an interpreter which now is deprecated.
+	/// Uses the wasmi interpreter.
+	///
+	/// This is a lot slower than `compiled`, but doesn't need to generate machine code at
+	/// runtime, so it can be used on hosts where JIT compilation is forbidden.
 	#[clap(name = "interpreted-i-know-what-i-do")]
 	Interpreted,
 	/// Uses a compiled runtime.
@@ -72,9 +75,7 @@ pub fn execution_method_from_cli(
 	instantiation_strategy: WasmtimeInstantiationStrategy,
 ) -> sc_service::config::WasmExecutionMethod {
 	if let WasmExecutionMethod::Interpreted = execution_method {
-		log::warn!(
-			"`interpreted-i-know-what-i-do` is deprecated and will be removed in the future. Defaults to `compiled` execution mode."
-		);
+		return sc_service::config::WasmExecutionMethod::Interpreted
 	}
 
 	sc_service::config::WasmExecutionMethod::Compiled {
@@ -100,12 +101,17 @@ pub const DEFAULT_WASM_EXECUTION_METHOD: WasmExecutionMethod = WasmExecutionMeth
 pub enum TracingReceiver {
 	/// Output the tracing records using the log.
 	Log,
+	/// Aggregate the tracing records into a profile of folded stacks.
+	Profile,
 }
 
 impl Into<sc_tracing::TracingReceiver> for TracingReceiver {
 	fn into(self) -> sc_tracing::TracingReceiver {
 		match self {
 			TracingReceiver::Log => sc_tracing::TracingReceiver::Log,
+			TracingReceiver::Profile => sc_tracing::TracingReceiver::Profile(
+				sc_tracing::profiler::DEFAULT_PROFILE_PATH.into(),
+			),
 		}
 	}
 }
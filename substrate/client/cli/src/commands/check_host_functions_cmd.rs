@@ -0,0 +1,386 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `check-host-functions` subcommand
+
+use crate::error;
+use clap::Parser;
+use sc_executor::{
+	read_embedded_version, sp_wasm_interface::Signature, HostFunctions, RuntimeVersion,
+};
+use sc_executor_common::runtime_blob::RuntimeBlob;
+use sp_core::hexdisplay::HexDisplay;
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+/// The `check-host-functions` command used to check whether a runtime can be executed by this
+/// node.
+///
+/// Lists the host functions imported by the runtime and compares them to the host functions
+/// provided by this node. Optionally the runtime APIs of the runtime are compared to the ones of
+/// the currently enacted runtime.
+#[derive(Debug, Clone, Parser)]
+pub struct CheckHostFunctionsCmd {
+	/// Path to the runtime to check, either a Wasm or a PolkaVM blob.
+	///
+	/// Checking PolkaVM runtimes requires `SUBSTRATE_ENABLE_POLKAVM=1` to be set.
+	#[arg(value_name = "PATH")]
+	pub runtime: PathBuf,
+
+	/// Path to the currently enacted runtime.
+	///
+	/// If given, the runtime versions of both runtimes are compared.
+	#[arg(long, value_name = "PATH")]
+	pub current_runtime: Option<PathBuf>,
+
+	/// Also list the imported host functions which are provided by this node.
+	#[arg(long)]
+	pub verbose: bool,
+}
+
+impl CheckHostFunctionsCmd {
+	/// Run the command with the host functions `H` of this node.
+	pub fn run<H: HostFunctions>(&self) -> error::Result<()> {
+		let blob = read_runtime(&self.runtime)?;
+		let host_functions = H::host_functions()
+			.into_iter()
+			.map(|function| (function.name(), function.signature()))
+			.collect::<Vec<_>>();
+
+		let mut report = CompatibilityReport {
+			host_functions: check_imports(blob.imported_functions(), &host_functions),
+			runtime_apis: Vec::new(),
+			verbose: self.verbose,
+		};
+
+		if let Some(current_runtime) = &self.current_runtime {
+			let current = read_runtime(current_runtime)?;
+
+			report.runtime_apis = match (embedded_version(&current)?, embedded_version(&blob)?) {
+				(Some(current), Some(new)) => check_runtime_version(&current, &new),
+				_ => vec![RuntimeVersionCheck::NotEmbedded],
+			};
+		}
+
+		print!("{}", report);
+
+		if report.is_compatible() {
+			Ok(())
+		} else {
+			Err("The runtime is not compatible with this node".into())
+		}
+	}
+}
+
+fn read_runtime(path: &PathBuf) -> error::Result<RuntimeBlob> {
+	RuntimeBlob::uncompress_if_needed(&std::fs::read(path)?)
+		.map_err(|e| error::Error::Input(format!("Invalid runtime {}: {}", path.display(), e)))
+}
+
+fn embedded_version(blob: &RuntimeBlob) -> error::Result<Option<RuntimeVersion>> {
+	read_embedded_version(blob)
+		.map_err(|e| error::Error::Input(format!("Invalid runtime version: {}", e)))
+}
+
+/// Splits the name of a host function into its base name and version.
+///
+/// Host functions declared with `sp-runtime-interface` are named
+/// `ext_<interface>_<function>_version_<version>`.
+fn split_version(name: &str) -> Option<(&str, u32)> {
+	let (base, version) = name.rsplit_once("_version_")?;
+	Some((base, version.parse().ok()?))
+}
+
+/// The result of checking a single imported host function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostFunctionCheck {
+	/// The host function is provided by this node.
+	Provided,
+	/// The host function is provided, but a newer version of it exists.
+	Deprecated { latest_version: u32 },
+	/// The host function isn't provided by this node.
+	///
+	/// Contains the versions of the function this node provides instead.
+	Missing { provided_versions: Vec<u32> },
+	/// The host function is provided with a different signature.
+	SignatureMismatch { imported: Signature, provided: Signature },
+}
+
+impl HostFunctionCheck {
+	fn is_compatible(&self) -> bool {
+		matches!(self, Self::Provided | Self::Deprecated { .. })
+	}
+}
+
+/// Compare the imported functions of a runtime to the `host_functions` of this node.
+fn check_imports(
+	imports: Vec<(String, Option<Signature>)>,
+	host_functions: &[(&str, Signature)],
+) -> Vec<(String, HostFunctionCheck)> {
+	let mut versions = BTreeMap::<&str, Vec<u32>>::new();
+	for (name, _) in host_functions {
+		if let Some((base, version)) = split_version(name) {
+			versions.entry(base).or_default().push(version);
+		}
+	}
+
+	let versions_of = |name: &str| {
+		let mut provided = split_version(name)
+			.and_then(|(base, _)| versions.get(base))
+			.cloned()
+			.unwrap_or_default();
+		provided.sort();
+		provided
+	};
+
+	imports
+		.into_iter()
+		.map(|(name, imported)| {
+			let provided = host_functions.iter().find(|(host_name, _)| *host_name == name);
+
+			let check = match (provided, imported) {
+				(None, _) => HostFunctionCheck::Missing { provided_versions: versions_of(&name) },
+				(Some((_, provided)), Some(imported)) if *provided != imported =>
+					HostFunctionCheck::SignatureMismatch { imported, provided: provided.clone() },
+				(Some(_), _) => match (split_version(&name), versions_of(&name).last()) {
+					(Some((_, version)), Some(&latest_version)) if latest_version > version =>
+						HostFunctionCheck::Deprecated { latest_version },
+					_ => HostFunctionCheck::Provided,
+				},
+			};
+
+			(name, check)
+		})
+		.collect()
+}
+
+/// The result of comparing the runtime version of a runtime to the current one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RuntimeVersionCheck {
+	/// One of the runtimes doesn't embed its runtime version.
+	NotEmbedded,
+	/// The `spec_name` of the runtimes differs.
+	SpecNameChanged { current: String, new: String },
+	/// The `spec_version` didn't increase.
+	SpecVersionNotIncreased { current: u32, new: u32 },
+	/// A runtime API was removed.
+	ApiRemoved { id: [u8; 8], version: u32 },
+	/// The version of a runtime API changed.
+	ApiVersionChanged { id: [u8; 8], current: u32, new: u32 },
+	/// A runtime API was added.
+	ApiAdded { id: [u8; 8], version: u32 },
+}
+
+impl RuntimeVersionCheck {
+	fn is_compatible(&self) -> bool {
+		!matches!(
+			self,
+			Self::SpecNameChanged { .. } |
+				Self::SpecVersionNotIncreased { .. } |
+				Self::ApiRemoved { .. }
+		)
+	}
+}
+
+/// Compare the runtime version of a `new` runtime to the `current` one.
+fn check_runtime_version(
+	current: &RuntimeVersion,
+	new: &RuntimeVersion,
+) -> Vec<RuntimeVersionCheck> {
+	let mut checks = Vec::new();
+
+	if current.spec_name != new.spec_name {
+		checks.push(RuntimeVersionCheck::SpecNameChanged {
+			current: current.spec_name.to_string(),
+			new: new.spec_name.to_string(),
+		});
+	}
+
+	if new.spec_version <= current.spec_version {
+		checks.push(RuntimeVersionCheck::SpecVersionNotIncreased {
+			current: current.spec_version,
+			new: new.spec_version,
+		});
+	}
+
+	for (id, version) in current.apis.iter() {
+		match new.api_version(id) {
+			None => checks.push(RuntimeVersionCheck::ApiRemoved { id: *id, version: *version }),
+			Some(new) if new != *version => checks.push(RuntimeVersionCheck::ApiVersionChanged {
+				id: *id,
+				current: *version,
+				new,
+			}),
+			Some(_) => {},
+		}
+	}
+
+	for (id, version) in new.apis.iter() {
+		if !current.has_api_with(id, |_| true) {
+			checks.push(RuntimeVersionCheck::ApiAdded { id: *id, version: *version });
+		}
+	}
+
+	checks
+}
+
+struct CompatibilityReport {
+	host_functions: Vec<(String, HostFunctionCheck)>,
+	runtime_apis: Vec<RuntimeVersionCheck>,
+	verbose: bool,
+}
+
+impl CompatibilityReport {
+	fn is_compatible(&self) -> bool {
+		self.host_functions.iter().all(|(_, check)| check.is_compatible()) &&
+			self.runtime_apis.iter().all(RuntimeVersionCheck::is_compatible)
+	}
+}
+
+impl fmt::Display for CompatibilityReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Imported host functions: {}", self.host_functions.len())?;
+
+		for (name, check) in &self.host_functions {
+			match check {
+				HostFunctionCheck::Provided if self.verbose => writeln!(f, "  ok          {}", name)?,
+				HostFunctionCheck::Provided => {},
+				HostFunctionCheck::Deprecated { latest_version } => writeln!(
+					f,
+					"  deprecated  {} (the node provides version {})",
+					name, latest_version
+				)?,
+				HostFunctionCheck::Missing { provided_versions } if provided_versions.is_empty() =>
+					writeln!(f, "  MISSING     {}", name)?,
+				HostFunctionCheck::Missing { provided_versions } => writeln!(
+					f,
+					"  MISSING     {} (the node provides versions {:?})",
+					name, provided_versions
+				)?,
+				HostFunctionCheck::SignatureMismatch { imported, provided } => writeln!(
+					f,
+					"  MISMATCH    {} (imported as {:?}, provided as {:?})",
+					name, imported, provided
+				)?,
+			}
+		}
+
+		if !self.runtime_apis.is_empty() {
+			writeln!(f, "Runtime version:")?;
+		}
+
+		for check in &self.runtime_apis {
+			match check {
+				RuntimeVersionCheck::NotEmbedded => writeln!(
+					f,
+					"  skipped     the runtime version isn't embedded into both runtimes"
+				)?,
+				RuntimeVersionCheck::SpecNameChanged { current, new } =>
+					writeln!(f, "  INVALID     spec_name changed from {} to {}", current, new)?,
+				RuntimeVersionCheck::SpecVersionNotIncreased { current, new } => writeln!(
+					f,
+					"  INVALID     spec_version {} isn't greater than {}",
+					new, current
+				)?,
+				RuntimeVersionCheck::ApiRemoved { id, version } => writeln!(
+					f,
+					"  REMOVED     api 0x{} version {}",
+					HexDisplay::from(id),
+					version
+				)?,
+				RuntimeVersionCheck::ApiVersionChanged { id, current, new } => writeln!(
+					f,
+					"  changed     api 0x{} version {} -> {}",
+					HexDisplay::from(id),
+					current,
+					new
+				)?,
+				RuntimeVersionCheck::ApiAdded { id, version } => writeln!(
+					f,
+					"  added       api 0x{} version {}",
+					HexDisplay::from(id),
+					version
+				)?,
+			}
+		}
+
+		writeln!(
+			f,
+			"The runtime is {}compatible with this node",
+			if self.is_compatible() { "" } else { "NOT " }
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_executor::sp_wasm_interface::ValueType;
+
+	fn signature(args: &'static [ValueType]) -> Signature {
+		Signature::new(args, None)
+	}
+
+	#[test]
+	fn split_version_works() {
+		assert_eq!(split_version("ext_storage_get_version_1"), Some(("ext_storage_get", 1)));
+		assert_eq!(split_version("ext_storage_get_version_x"), None);
+		assert_eq!(split_version("ext_storage_get"), None);
+	}
+
+	#[test]
+	fn check_imports_works() {
+		let host_functions = [
+			("ext_storage_root_version_1", signature(&[])),
+			("ext_storage_root_version_2", signature(&[ValueType::I32])),
+			("ext_misc_print_version_1", signature(&[ValueType::I64])),
+		];
+		let imports = vec![
+			("ext_storage_root_version_1".into(), Some(signature(&[]))),
+			("ext_storage_root_version_3".into(), None),
+			("ext_misc_print_version_1".into(), Some(signature(&[ValueType::I32]))),
+			("ext_misc_unknown_version_1".into(), None),
+			("ext_storage_root_version_2".into(), None),
+		];
+
+		assert_eq!(
+			check_imports(imports, &host_functions),
+			vec![
+				(
+					"ext_storage_root_version_1".into(),
+					HostFunctionCheck::Deprecated { latest_version: 2 }
+				),
+				(
+					"ext_storage_root_version_3".into(),
+					HostFunctionCheck::Missing { provided_versions: vec![1, 2] }
+				),
+				(
+					"ext_misc_print_version_1".into(),
+					HostFunctionCheck::SignatureMismatch {
+						imported: signature(&[ValueType::I32]),
+						provided: signature(&[ValueType::I64]),
+					}
+				),
+				(
+					"ext_misc_unknown_version_1".into(),
+					HostFunctionCheck::Missing { provided_versions: vec![] }
+				),
+				("ext_storage_root_version_2".into(), HostFunctionCheck::Provided),
+			]
+		);
+	}
+}
@@ -0,0 +1,108 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{BlockNumberOrHash, ImportParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::{backend::Backend, BlockBackend, HeaderBackend};
+use sc_executor::RuntimeVersionOf;
+use sp_core::traits::CodeExecutor;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use std::{fmt::Debug, path::PathBuf, str::FromStr, sync::Arc};
+
+/// The `compare-block` command used to re-execute a block with the Wasm and the PolkaVM build of
+/// a runtime.
+///
+/// Both builds execute the block on top of the same parent state. Their storage roots, storage
+/// changes, events, weight and host calls are compared and the first divergence is reported.
+#[derive(Debug, Clone, Parser)]
+pub struct CompareBlockCmd {
+	/// Block hash or number.
+	#[arg(value_name = "HASH or NUMBER")]
+	pub input: BlockNumberOrHash,
+
+	/// Path to the PolkaVM build of the runtime.
+	///
+	/// Executing PolkaVM runtimes requires `SUBSTRATE_ENABLE_POLKAVM=1` to be set.
+	#[arg(long, value_name = "PATH")]
+	pub polkavm_runtime: PathBuf,
+
+	/// Path to the Wasm build of the runtime.
+	///
+	/// Defaults to the `:code` stored in the state of the block's parent.
+	#[arg(long, value_name = "PATH")]
+	pub wasm_runtime: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl CompareBlockCmd {
+	/// Run the compare-block command
+	pub async fn run<B, BA, C, E>(
+		&self,
+		client: Arc<C>,
+		backend: Arc<BA>,
+		executor: E,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: Backend<B>,
+		C: BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+		E: CodeExecutor + RuntimeVersionOf,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		let polkavm_code = std::fs::read(&self.polkavm_runtime)?;
+		let wasm_code = self.wasm_runtime.as_ref().map(std::fs::read).transpose()?;
+
+		let comparison = sc_service::chain_ops::compare_block_execution(
+			client,
+			backend,
+			executor,
+			self.input.parse()?,
+			wasm_code,
+			polkavm_code,
+		)?;
+		println!("{}", comparison);
+
+		if comparison.is_identical() {
+			Ok(())
+		} else {
+			Err("The Wasm and the PolkaVM execution of the block diverged".into())
+		}
+	}
+}
+
+impl CliConfiguration for CompareBlockCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
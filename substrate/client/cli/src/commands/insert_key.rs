@@ -22,10 +22,11 @@ use crate::{
 	utils, with_crypto_scheme, CryptoScheme, Error, KeystoreParams, SharedParams, SubstrateCli,
 };
 use clap::Parser;
-use sc_keystore::LocalKeystore;
+use sc_keystore::{LocalKeystore, RemoteKeystore};
 use sc_service::config::{BasePath, KeystoreConfig};
 use sp_core::crypto::{KeyTypeId, SecretString};
 use sp_keystore::KeystorePtr;
+use std::sync::Arc;
 
 /// The `insert` command
 #[derive(Debug, Clone, Parser)]
@@ -72,7 +73,12 @@ impl InsertKeyCmd {
 				let keystore: KeystorePtr = LocalKeystore::open(path, password)?.into();
 				(keystore, public)
 			},
-			_ => unreachable!("keystore_config always returns path and password; qed"),
+			KeystoreConfig::Remote(address) => {
+				let public = with_crypto_scheme!(self.scheme, to_vec(&suri, None))?;
+				let keystore: KeystorePtr = Arc::new(RemoteKeystore::connect(address)?);
+				(keystore, public)
+			},
+			_ => unreachable!("keystore_config always returns a path or a remote signer; qed"),
 		};
 
 		let key_type =
@@ -80,6 +80,14 @@ pub struct ImportParams {
 	/// Providing `0` will disable the cache.
 	#[arg(long, value_name = "Bytes", default_value_t = 1024 * 1024 * 1024)]
 	pub trie_cache_size: usize,
+
+	/// Specify the maximum size of the state cache entries persisted across restarts.
+	///
+	/// On shutdown the most recently used entries of the state cache are written to disk and
+	/// they are loaded back on the next start, if the best block didn't change in the meantime.
+	/// Providing `0` will disable the persistence.
+	#[arg(long, value_name = "Bytes", default_value_t = 0)]
+	pub trie_cache_persistence_size: usize,
 }
 
 impl ImportParams {
@@ -92,6 +100,15 @@ impl ImportParams {
 		}
 	}
 
+	/// Specify the maximum size of the trie cache entries persisted across restarts.
+	pub fn trie_cache_persistence_size(&self) -> Option<usize> {
+		if self.trie_cache_persistence_size == 0 {
+			None
+		} else {
+			Some(self.trie_cache_persistence_size)
+		}
+	}
+
 	/// Get the WASM execution method from the parameters
 	pub fn wasm_method(&self) -> sc_service::config::WasmExecutionMethod {
 		self.execution_strategies.check_usage_and_print_deprecation_warning();
@@ -18,7 +18,7 @@
 
 use crate::{error, error::Result};
 use clap::Args;
-use sc_service::config::KeystoreConfig;
+use sc_service::config::{KeystoreConfig, SignerAddress, TlsConfig};
 use sp_core::crypto::SecretString;
 use std::{
 	fs,
@@ -56,6 +56,40 @@ pub struct KeystoreParams {
 		conflicts_with_all = &["password_interactive", "password"]
 	)]
 	pub password_filename: Option<PathBuf>,
+
+	/// Use a remote signer holding the keys instead of the local keystore.
+	///
+	/// Either `unix:<PATH>` to connect to a Unix domain socket or `<HOST>:<PORT>` to connect over
+	/// TCP. TCP connections require mutual TLS, configured using `--keystore-remote-tls-ca`,
+	/// `--keystore-remote-tls-cert` and `--keystore-remote-tls-key`.
+	#[arg(
+		long,
+		value_name = "ADDRESS",
+		conflicts_with_all = &[
+			"keystore_path",
+			"password_interactive",
+			"password",
+			"password_filename",
+		]
+	)]
+	pub keystore_remote: Option<String>,
+
+	/// PEM encoded certificate of the authority that signed the certificates of the node and of
+	/// the remote signer.
+	#[arg(
+		long,
+		value_name = "PATH",
+		requires_all = &["keystore_remote", "keystore_remote_tls_cert", "keystore_remote_tls_key"]
+	)]
+	pub keystore_remote_tls_ca: Option<PathBuf>,
+
+	/// PEM encoded certificate chain presented by the node to the remote signer.
+	#[arg(long, value_name = "PATH", requires = "keystore_remote_tls_ca")]
+	pub keystore_remote_tls_cert: Option<PathBuf>,
+
+	/// PEM encoded private key of `--keystore-remote-tls-cert`.
+	#[arg(long, value_name = "PATH", requires = "keystore_remote_tls_ca")]
+	pub keystore_remote_tls_key: Option<PathBuf>,
 }
 
 /// Parse a secret string, returning a displayable error.
@@ -66,6 +100,10 @@ pub fn secret_string_from_str(s: &str) -> std::result::Result<SecretString, Stri
 impl KeystoreParams {
 	/// Get the keystore configuration for the parameters
 	pub fn keystore_config(&self, config_dir: &Path) -> Result<KeystoreConfig> {
+		if let Some(address) = &self.keystore_remote {
+			return Ok(KeystoreConfig::Remote(self.signer_address(address)?))
+		}
+
 		let password = if self.password_interactive {
 			Some(SecretString::new(input_keystore_password()?))
 		} else if let Some(ref file) = self.password_filename {
@@ -83,6 +121,24 @@ impl KeystoreParams {
 		Ok(KeystoreConfig::Path { path, password })
 	}
 
+	/// Get the address of the remote signer.
+	fn signer_address(&self, address: &str) -> Result<SignerAddress> {
+		let tls = match (
+			&self.keystore_remote_tls_ca,
+			&self.keystore_remote_tls_cert,
+			&self.keystore_remote_tls_key,
+		) {
+			(Some(ca_certificate), Some(certificate), Some(private_key)) => Some(TlsConfig {
+				ca_certificate: ca_certificate.clone(),
+				certificate: certificate.clone(),
+				private_key: private_key.clone(),
+			}),
+			_ => None,
+		};
+
+		Ok(SignerAddress::new(address, tls)?)
+	}
+
 	/// helper method to fetch password from `KeyParams` or read from stdin
 	pub fn read_password(&self) -> error::Result<Option<SecretString>> {
 		let (password_interactive, password) = (self.password_interactive, self.password.clone());
@@ -23,7 +23,8 @@ use crate::{
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, RateLimitConfig, SentryTopology,
+		SetConfig, TrafficRecorderConfig, TransportConfig, WebSocketTlsConfig,
 	},
 	multiaddr::Protocol,
 };
@@ -31,7 +32,11 @@ use sc_service::{
 	config::{Multiaddr, MultiaddrWithPeerId},
 	ChainSpec, ChainType,
 };
-use std::{borrow::Cow, num::NonZeroUsize, path::PathBuf};
+use std::{
+	borrow::Cow,
+	num::{NonZeroU32, NonZeroU64, NonZeroUsize},
+	path::PathBuf,
+};
 
 /// Parameters used to create the network configuration.
 #[derive(Debug, Clone, Args)]
@@ -54,6 +59,20 @@ pub struct NetworkParams {
 	#[arg(long)]
 	pub reserved_only: bool,
 
+	/// Run as a validator hidden behind the given sentry nodes.
+	///
+	/// The node only connects to its sentries, is never advertised in the DHT and relies on the
+	/// sentries to relay gossip on its behalf. Requires the libp2p network backend.
+	#[arg(long, value_name = "ADDR", num_args = 1.., conflicts_with_all = &["sentry"])]
+	pub sentry_nodes: Vec<MultiaddrWithPeerId>,
+
+	/// Run as a sentry node protecting the given validators.
+	///
+	/// The validators are always kept connected and their gossip is relayed to the rest of the
+	/// network.
+	#[arg(long, value_name = "ADDR", num_args = 1..)]
+	pub sentry: Vec<MultiaddrWithPeerId>,
+
 	/// Public address that other nodes will use to connect to this node.
 	///
 	/// This can be used if there's a proxy in front of this node.
@@ -68,6 +87,17 @@ pub struct NetworkParams {
 	#[arg(long, value_name = "LISTEN_ADDR", num_args = 1..)]
 	pub listen_addr: Vec<Multiaddr>,
 
+	/// PEM encoded certificate chain presented to peers connecting to a `/wss` listen address.
+	///
+	/// Browsers only connect if the certificate is valid for the domain name of the address
+	/// they dial, so a `/dns/.../tcp/.../wss` address should be passed with `--public-addr`.
+	#[arg(long, value_name = "PATH", requires = "wss_private_key")]
+	pub wss_certificate: Option<PathBuf>,
+
+	/// PEM encoded private key of the `--wss-certificate`.
+	#[arg(long, value_name = "PATH", requires = "wss_certificate")]
+	pub wss_private_key: Option<PathBuf>,
+
 	/// Specify p2p protocol TCP port.
 	#[arg(long, value_name = "PORT", conflicts_with_all = &[ "listen_addr" ])]
 	pub port: Option<u16>,
@@ -170,6 +200,64 @@ pub struct NetworkParams {
 	#[arg(long, value_name = "COUNT", default_value_t = 64)]
 	pub max_blocks_per_request: u32,
 
+	/// Maximum number of requests per second a single peer can send on each request-response
+	/// protocol, e.g. block, state, warp sync or light client requests.
+	///
+	/// Requests exceeding the limit are refused and the reputation of the peer is decreased.
+	/// Peers can send up to twice as many requests in a burst.
+	#[arg(long, value_name = "COUNT")]
+	pub max_requests_per_peer: Option<NonZeroU32>,
+
+	/// Maximum number of requests per second a single peer can send on a single protocol.
+	///
+	/// Overrides `--max-requests-per-peer` for the protocol. Expected format is
+	/// `PROTOCOL=COUNT`, where `PROTOCOL` is the full name of the request-response protocol,
+	/// e.g. `--protocol-requests-per-peer /<genesis-hash>/sync/2=20`.
+	#[arg(long, value_name = "PROTOCOL=COUNT", value_parser = parse_protocol_limit::<NonZeroU32>)]
+	pub protocol_requests_per_peer: Vec<(String, NonZeroU32)>,
+
+	/// Maximum upload bandwidth, in KiB/s, spent on answering requests of other peers.
+	///
+	/// Once exhausted, requests are refused until the quota has been replenished.
+	#[arg(long, value_name = "KIB_PER_SEC")]
+	pub max_request_upload_bandwidth: Option<NonZeroU64>,
+
+	/// Maximum upload bandwidth, in KiB/s, spent on answering requests of a single protocol.
+	///
+	/// Expected format is `PROTOCOL=KIB_PER_SEC`, where `PROTOCOL` is the full name of the
+	/// request-response protocol, e.g. `--protocol-upload-bandwidth /<genesis-hash>/state/2=1024`.
+	#[arg(
+		long,
+		value_name = "PROTOCOL=KIB_PER_SEC",
+		value_parser = parse_protocol_limit::<NonZeroU64>
+	)]
+	pub protocol_upload_bandwidth: Vec<(String, NonZeroU64)>,
+
+	/// Maximum download bandwidth, in KiB/s, spent on receiving requests of other peers.
+	///
+	/// Once exhausted, requests are refused until the quota has been replenished.
+	#[arg(long, value_name = "KIB_PER_SEC")]
+	pub max_request_download_bandwidth: Option<NonZeroU64>,
+
+	/// Maximum download bandwidth, in KiB/s, spent on receiving requests of a single protocol.
+	///
+	/// Expected format is `PROTOCOL=KIB_PER_SEC`, where `PROTOCOL` is the full name of the
+	/// request-response protocol, e.g. `--protocol-download-bandwidth /<genesis-hash>/light/2=64`.
+	#[arg(
+		long,
+		value_name = "PROTOCOL=KIB_PER_SEC",
+		value_parser = parse_protocol_limit::<NonZeroU64>
+	)]
+	pub protocol_download_bandwidth: Vec<(String, NonZeroU64)>,
+
+	/// Record all notification and request-response messages to rotating files in the given
+	/// directory.
+	///
+	/// Recordings can be replayed with the harness in `sc-network-test`. This is a debugging
+	/// aid and considerably increases disk usage.
+	#[arg(long, value_name = "PATH")]
+	pub record_network_traffic: Option<PathBuf>,
+
 	/// Network backend used for P2P networking.
 	///
 	/// litep2p network backend is considered experimental and isn't as stable as the libp2p
@@ -261,6 +349,7 @@ impl NetworkParams {
 			default_peers_set_num_full: self.in_peers + self.out_peers,
 			listen_addresses,
 			public_addresses,
+			websocket_tls: self.websocket_tls(),
 			node_key,
 			node_name: node_name.to_string(),
 			client_version: client_id.to_string(),
@@ -275,10 +364,73 @@ impl NetworkParams {
 			kademlia_disjoint_query_paths: self.kademlia_disjoint_query_paths,
 			kademlia_replication_factor: self.kademlia_replication_factor,
 			ipfs_server: self.ipfs_server,
+			request_rate_limits: self.request_rate_limits(),
+			traffic_recorder: self.record_network_traffic.clone().map(TrafficRecorderConfig::new),
+			sentry_topology: self.sentry_topology(),
 			sync_mode: self.sync.into(),
 			network_backend: self.network_backend.map(Into::into),
 		}
 	}
+
+	/// TLS certificate of `/wss` listen addresses configured by the cli parameters.
+	fn websocket_tls(&self) -> Option<WebSocketTlsConfig> {
+		Some(WebSocketTlsConfig {
+			certificate_chain: self.wss_certificate.clone()?,
+			private_key: self.wss_private_key.clone()?,
+		})
+	}
+
+	/// Sentry topology configured by the cli parameters.
+	fn sentry_topology(&self) -> SentryTopology {
+		if !self.sentry_nodes.is_empty() {
+			SentryTopology::PrivateValidator { sentries: self.sentry_nodes.clone() }
+		} else if !self.sentry.is_empty() {
+			SentryTopology::Sentry { validators: self.sentry.clone() }
+		} else {
+			SentryTopology::None
+		}
+	}
+
+	/// Request rate limits and bandwidth quotas configured by the cli parameters.
+	fn request_rate_limits(&self) -> RateLimitConfig {
+		let kib_to_bytes = |kib: NonZeroU64| {
+			kib.saturating_mul(NonZeroU64::new(1024).expect("1024 is non-zero; qed"))
+		};
+
+		RateLimitConfig {
+			max_requests_per_peer: self.max_requests_per_peer,
+			protocol_requests_per_peer: self.protocol_requests_per_peer.iter().cloned().collect(),
+			max_upload_bandwidth: self.max_request_upload_bandwidth.map(kib_to_bytes),
+			protocol_upload_bandwidth: self
+				.protocol_upload_bandwidth
+				.iter()
+				.map(|(protocol, kib)| (protocol.clone(), kib_to_bytes(*kib)))
+				.collect(),
+			max_download_bandwidth: self.max_request_download_bandwidth.map(kib_to_bytes),
+			protocol_download_bandwidth: self
+				.protocol_download_bandwidth
+				.iter()
+				.map(|(protocol, kib)| (protocol.clone(), kib_to_bytes(*kib)))
+				.collect(),
+		}
+	}
+}
+
+/// Parse a `PROTOCOL=LIMIT` pair of the per-protocol request limits.
+fn parse_protocol_limit<T>(s: &str) -> Result<(String, T), String>
+where
+	T: std::str::FromStr,
+	T::Err: std::fmt::Display,
+{
+	let (protocol, limit) =
+		s.split_once('=').ok_or_else(|| format!("expected `PROTOCOL=LIMIT`, got `{s}`"))?;
+
+	if protocol.is_empty() {
+		return Err("protocol name must not be empty".into())
+	}
+
+	let limit = limit.parse().map_err(|e| format!("invalid limit `{limit}`: {e}"))?;
+	Ok((protocol.to_string(), limit))
 }
 
 #[cfg(test)]
@@ -325,6 +477,115 @@ mod tests {
 		assert_eq!(expected, params.network_params.reserved_nodes);
 	}
 
+	#[test]
+	fn request_rate_limits() {
+		let params = Cli::try_parse_from([
+			"",
+			"--max-requests-per-peer",
+			"10",
+			"--max-request-upload-bandwidth",
+			"2048",
+			"--max-request-download-bandwidth",
+			"4096",
+			"--protocol-upload-bandwidth",
+			"/abcd/state/2=512",
+			"--protocol-upload-bandwidth",
+			"/abcd/sync/warp=256",
+			"--protocol-download-bandwidth",
+			"/abcd/light/2=64",
+			"--protocol-requests-per-peer",
+			"/abcd/sync/2=20",
+			"--protocol-requests-per-peer",
+			"/abcd/state/2=2",
+		])
+		.expect("Parses network params");
+
+		let config = params.network_params.request_rate_limits();
+		assert_eq!(config.max_requests_per_peer, NonZeroU32::new(10));
+		assert_eq!(config.max_upload_bandwidth, NonZeroU64::new(2048 * 1024));
+		assert_eq!(config.max_download_bandwidth, NonZeroU64::new(4096 * 1024));
+		assert_eq!(
+			config.protocol_upload_bandwidth,
+			[
+				("/abcd/state/2".to_string(), NonZeroU64::new(512 * 1024).unwrap()),
+				("/abcd/sync/warp".to_string(), NonZeroU64::new(256 * 1024).unwrap()),
+			]
+			.into_iter()
+			.collect(),
+		);
+		assert_eq!(
+			config.protocol_download_bandwidth,
+			[("/abcd/light/2".to_string(), NonZeroU64::new(64 * 1024).unwrap())]
+				.into_iter()
+				.collect(),
+		);
+		assert_eq!(
+			config.protocol_requests_per_peer,
+			[
+				("/abcd/sync/2".to_string(), NonZeroU32::new(20).unwrap()),
+				("/abcd/state/2".to_string(), NonZeroU32::new(2).unwrap()),
+			]
+			.into_iter()
+			.collect(),
+		);
+
+		assert!(Cli::try_parse_from(["", "--protocol-upload-bandwidth", "/state/2"]).is_err());
+		assert!(Cli::try_parse_from(["", "--protocol-upload-bandwidth", "=1"]).is_err());
+		assert!(Cli::try_parse_from(["", "--protocol-upload-bandwidth", "/state/2=0"]).is_err());
+		assert!(Cli::try_parse_from(["", "--protocol-download-bandwidth", "/light/2=x"]).is_err());
+		assert!(Cli::try_parse_from(["", "--protocol-requests-per-peer", "/sync/2=0"]).is_err());
+	}
+
+	#[test]
+	fn sentry_topology() {
+		let sentry =
+			"/ip4/0.0.0.0/tcp/501/p2p/12D3KooWEBo1HUPQJwiBmM5kSeg4XgiVxEArArQdDarYEsGxMfbS";
+		let expected = vec![MultiaddrWithPeerId::try_from(sentry.to_string()).unwrap()];
+
+		let params =
+			Cli::try_parse_from(["", "--sentry-nodes", sentry]).expect("Parses network params");
+		assert_eq!(
+			params.network_params.sentry_topology(),
+			SentryTopology::PrivateValidator { sentries: expected.clone() },
+		);
+
+		let params = Cli::try_parse_from(["", "--sentry", sentry]).expect("Parses network params");
+		assert_eq!(
+			params.network_params.sentry_topology(),
+			SentryTopology::Sentry { validators: expected },
+		);
+
+		let params = Cli::try_parse_from([""]).expect("Parses network params");
+		assert_eq!(params.network_params.sentry_topology(), SentryTopology::None);
+
+		assert!(Cli::try_parse_from(["", "--sentry-nodes", sentry, "--sentry", sentry]).is_err());
+	}
+
+	#[test]
+	fn websocket_tls() {
+		let params = Cli::try_parse_from([
+			"",
+			"--wss-certificate",
+			"/etc/node/wss.pem",
+			"--wss-private-key",
+			"/etc/node/wss.key",
+		])
+		.expect("Parses network params");
+		assert_eq!(
+			params.network_params.websocket_tls(),
+			Some(WebSocketTlsConfig {
+				certificate_chain: "/etc/node/wss.pem".into(),
+				private_key: "/etc/node/wss.key".into(),
+			}),
+		);
+
+		let params = Cli::try_parse_from([""]).expect("Parses network params");
+		assert_eq!(params.network_params.websocket_tls(), None);
+
+		assert!(Cli::try_parse_from(["", "--wss-certificate", "/etc/node/wss.pem"]).is_err());
+		assert!(Cli::try_parse_from(["", "--wss-private-key", "/etc/node/wss.key"]).is_err());
+	}
+
 	#[test]
 	fn sync_ignores_case() {
 		let params = Cli::try_parse_from(["", "--sync", "wArP"]).expect("Parses network params");
@@ -89,6 +89,14 @@ pub struct SharedParams {
 	/// Receiver to process tracing messages.
 	#[arg(long, value_name = "RECEIVER", value_enum, ignore_case = true, default_value_t = TracingReceiver::Log)]
 	pub tracing_receiver: TracingReceiver,
+
+	/// Path of the profile written by the `profile` tracing receiver.
+	///
+	/// The profile contains folded stacks, as consumed by `inferno-flamegraph` or
+	/// `flamegraph.pl`. A summary with the count, total and self time of every frame is written
+	/// next to it with `summary` as extension.
+	#[arg(long, value_name = "PATH", default_value = sc_tracing::profiler::DEFAULT_PROFILE_PATH)]
+	pub tracing_profile_path: PathBuf,
 }
 
 impl SharedParams {
@@ -138,7 +146,11 @@ impl SharedParams {
 
 	/// Receiver to process tracing messages.
 	pub fn tracing_receiver(&self) -> sc_service::TracingReceiver {
-		self.tracing_receiver.into()
+		match self.tracing_receiver {
+			TracingReceiver::Profile =>
+				sc_service::TracingReceiver::Profile(self.tracing_profile_path.clone()),
+			receiver => receiver.into(),
+		}
 	}
 
 	/// Comma separated list of targets for tracing.
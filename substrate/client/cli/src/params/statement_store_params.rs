@@ -0,0 +1,124 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use clap::Args;
+use sc_statement_store::Options;
+use sp_statement_store::Channel;
+
+/// Parameters used to create the statement store configuration.
+#[derive(Debug, Clone, Args)]
+pub struct StatementStoreParams {
+	/// Maximum number of statements in the statement store.
+	///
+	/// Once the limit is reached, lower-priority statements are evicted.
+	#[arg(long, value_name = "COUNT")]
+	pub statement_store_max_count: Option<usize>,
+
+	/// Maximum number of kilobytes of statement data stored in the statement store.
+	///
+	/// Once the limit is reached, lower-priority statements are evicted.
+	#[arg(long, value_name = "COUNT")]
+	pub statement_store_max_kbytes: Option<usize>,
+
+	/// Number of seconds statements are kept in the statement store for.
+	///
+	/// By default statements are kept until they are evicted, removed or expire.
+	#[arg(long, value_name = "SECONDS")]
+	pub statement_retention: Option<u64>,
+
+	/// Number of seconds statements posted to a channel are kept in the statement store for.
+	///
+	/// Takes precedence over `--statement-retention` for that channel. Can be passed multiple
+	/// times, as `--statement-channel-retention 0x<32-byte channel>=<SECONDS>`.
+	#[arg(long, value_name = "CHANNEL=SECONDS", value_parser = parse_channel_retention)]
+	pub statement_channel_retention: Vec<(Channel, u64)>,
+}
+
+impl StatementStoreParams {
+	/// Create the statement store `Options` from the cli parameters.
+	pub fn statement_store(&self) -> Options {
+		let mut options = Options::default();
+
+		if let Some(max_count) = self.statement_store_max_count {
+			options = options.with_max_total_statements(max_count);
+		}
+		if let Some(max_kbytes) = self.statement_store_max_kbytes {
+			options = options.with_max_total_size(max_kbytes * 1024);
+		}
+		if let Some(retention) = self.statement_retention {
+			options = options.with_retention(retention);
+		}
+
+		self.statement_channel_retention
+			.iter()
+			.fold(options, |options, (channel, retention)| {
+				options.with_channel_retention(*channel, *retention)
+			})
+	}
+}
+
+/// Parse a `0x<32-byte channel>=<SECONDS>` channel retention period.
+fn parse_channel_retention(s: &str) -> Result<(Channel, u64), String> {
+	let (channel, retention) = s
+		.split_once('=')
+		.ok_or_else(|| format!("expected `<CHANNEL>=<SECONDS>`, found `{s}`"))?;
+
+	let channel = array_bytes::hex2array::<_, 32>(channel)
+		.map_err(|error| format!("invalid channel `{channel}`: {error:?}"))?;
+	let retention = retention
+		.parse()
+		.map_err(|error| format!("invalid retention period `{retention}`: {error}"))?;
+
+	Ok((channel, retention))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+
+	#[derive(Parser)]
+	struct Cli {
+		#[clap(flatten)]
+		statement_store: StatementStoreParams,
+	}
+
+	#[test]
+	fn channel_retention_is_parsed() {
+		let channel = format!("0x{}", "01".repeat(32));
+		let cli = Cli::try_parse_from([
+			"",
+			"--statement-retention",
+			"100",
+			"--statement-channel-retention",
+			&format!("{channel}=10"),
+		])
+		.unwrap();
+
+		assert_eq!(cli.statement_store.statement_retention, Some(100));
+		assert_eq!(cli.statement_store.statement_channel_retention, vec![([1; 32], 10)]);
+	}
+
+	#[test]
+	fn invalid_channel_retention_is_rejected() {
+		let missing_retention = format!("0x{}", "01".repeat(32));
+		for invalid in ["0x01=10", missing_retention.as_str(), "=10"] {
+			assert!(Cli::try_parse_from(["", "--statement-channel-retention", invalid]).is_err());
+		}
+	}
+}
@@ -23,7 +23,7 @@ use crate::{
 	LOG_TARGET,
 };
 use codec::Codec;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use prometheus_endpoint::Registry;
 use sc_client_api::{backend::AuxStore, BlockOf, UsageProvider};
 use sc_consensus::{
@@ -32,11 +32,14 @@ use sc_consensus::{
 };
 use sc_consensus_slots::{check_equivocation, CheckedHeader, InherentDataProviderExt};
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_DEBUG, CONSENSUS_TRACE};
+use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_blockchain::HeaderBackend;
-use sp_consensus::Error as ConsensusError;
-use sp_consensus_aura::{inherents::AuraInherentData, AuraApi};
+use sp_consensus::{BlockOrigin, Error as ConsensusError};
+use sp_consensus_aura::{
+	inherents::AuraInherentData, AuraApi, AuraEquivocationApi, EquivocationProof,
+};
 use sp_consensus_slots::Slot;
 use sp_core::crypto::Pair;
 use sp_inherents::{CreateInherentDataProviders, InherentDataProvider as _};
@@ -46,11 +49,15 @@ use sp_runtime::{
 };
 use std::{fmt::Debug, marker::PhantomData, sync::Arc};
 
+/// Proof of an equivocation by an Aura block author.
+type AuraEquivocationProof<B, P> = EquivocationProof<<B as BlockT>::Header, AuthorityId<P>>;
+
 /// check a header has been signed by the right key. If the slot is too far in the future, an error
 /// will be returned. If it's successful, returns the pre-header and the digest item
 /// containing the seal.
 ///
-/// This digest item will always return `Some` when used with `as_aura_seal`.
+/// This digest item will always return `Some` when used with `as_aura_seal`. If the slot author
+/// is equivocating, the proof of the equivocation is returned alongside it.
 fn check_header<C, B: BlockT, P: Pair>(
 	client: &C,
 	slot_now: Slot,
@@ -58,7 +65,10 @@ fn check_header<C, B: BlockT, P: Pair>(
 	hash: B::Hash,
 	authorities: &[AuthorityId<P>],
 	check_for_equivocation: CheckForEquivocation,
-) -> Result<CheckedHeader<B::Header, (Slot, DigestItem)>, Error<B>>
+) -> Result<
+	CheckedHeader<B::Header, (Slot, DigestItem, Option<AuraEquivocationProof<B, P>>)>,
+	Error<B>,
+>
 where
 	P::Public: Codec,
 	P::Signature: Codec,
@@ -71,6 +81,7 @@ where
 		Ok((header, slot, seal)) => {
 			let expected_author = crate::standalone::slot_author::<P>(slot, &authorities);
 			let should_equiv_check = check_for_equivocation.check_for_equivocation();
+			let mut equivocation = None;
 			if let (true, Some(expected)) = (should_equiv_check, expected_author) {
 				if let Some(equivocation_proof) =
 					check_equivocation(client, slot_now, slot, &header, expected)
@@ -83,10 +94,11 @@ where
 						equivocation_proof.first_header.hash(),
 						equivocation_proof.second_header.hash(),
 					);
+					equivocation = Some(equivocation_proof);
 				}
 			}
 
-			Ok(CheckedHeader::Checked(header, (slot, seal)))
+			Ok(CheckedHeader::Checked(header, (slot, seal, equivocation)))
 		},
 		Err(SealVerificationError::Deferred(header, slot)) =>
 			Ok(CheckedHeader::Deferred(header, slot)),
@@ -162,8 +174,12 @@ where
 	}
 }
 
-#[async_trait::async_trait]
-impl<B: BlockT, C, P, CIDP> Verifier<B> for AuraVerifier<C, P, CIDP, NumberFor<B>>
+/// Verifies an Aura block, returning the block ready for import together with the proof of
+/// any equivocation committed by its author.
+async fn verify_block<B: BlockT, C, P, CIDP>(
+	verifier: &AuraVerifier<C, P, CIDP, NumberFor<B>>,
+	mut block: BlockImportParams<B>,
+) -> Result<(BlockImportParams<B>, Option<AuraEquivocationProof<B, P>>), String>
 where
 	C: ProvideRuntimeApi<B> + Send + Sync + sc_client_api::backend::AuxStore,
 	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>> + ApiExt<B>,
@@ -173,76 +189,73 @@ where
 	CIDP: CreateInherentDataProviders<B, ()> + Send + Sync,
 	CIDP::InherentDataProviders: InherentDataProviderExt + Send + Sync,
 {
-	async fn verify(
-		&self,
-		mut block: BlockImportParams<B>,
-	) -> Result<BlockImportParams<B>, String> {
-		// Skip checks that include execution, if being told so or when importing only state.
-		//
-		// This is done for example when gap syncing and it is expected that the block after the gap
-		// was checked/chosen properly, e.g. by warp syncing to this block using a finality proof.
-		// Or when we are importing state only and can not verify the seal.
-		if block.with_state() || block.state_action.skip_execution_checks() {
-			// When we are importing only the state of a block, it will be the best block.
-			block.fork_choice = Some(ForkChoiceStrategy::Custom(block.with_state()));
-
-			return Ok(block)
-		}
+	// Skip checks that include execution, if being told so or when importing only state.
+	//
+	// This is done for example when gap syncing and it is expected that the block after the gap
+	// was checked/chosen properly, e.g. by warp syncing to this block using a finality proof.
+	// Or when we are importing state only and can not verify the seal.
+	if block.with_state() || block.state_action.skip_execution_checks() {
+		// When we are importing only the state of a block, it will be the best block.
+		block.fork_choice = Some(ForkChoiceStrategy::Custom(block.with_state()));
+
+		return Ok((block, None))
+	}
 
-		let hash = block.header.hash();
-		let parent_hash = *block.header.parent_hash();
-		let authorities = authorities(
-			self.client.as_ref(),
-			parent_hash,
-			*block.header.number(),
-			&self.compatibility_mode,
-		)
-		.map_err(|e| format!("Could not fetch authorities at {:?}: {}", parent_hash, e))?;
-
-		let create_inherent_data_providers = self
-			.create_inherent_data_providers
-			.create_inherent_data_providers(parent_hash, ())
-			.await
-			.map_err(|e| Error::<B>::Client(sp_blockchain::Error::Application(e)))?;
-
-		let mut inherent_data = create_inherent_data_providers
-			.create_inherent_data()
-			.await
-			.map_err(Error::<B>::Inherent)?;
-
-		let slot_now = create_inherent_data_providers.slot();
-
-		// we add one to allow for some small drift.
-		// FIXME #1019 in the future, alter this queue to allow deferring of
-		// headers
-		let checked_header = check_header::<C, B, P>(
-			&self.client,
-			slot_now + 1,
-			block.header,
-			hash,
-			&authorities[..],
-			self.check_for_equivocation,
-		)
-		.map_err(|e| e.to_string())?;
-		match checked_header {
-			CheckedHeader::Checked(pre_header, (slot, seal)) => {
-				// if the body is passed through, we need to use the runtime
-				// to check that the internally-set timestamp in the inherents
-				// actually matches the slot set in the seal.
-				if let Some(inner_body) = block.body.take() {
-					let new_block = B::new(pre_header.clone(), inner_body);
-
-					inherent_data.aura_replace_inherent_data(slot);
-
-					// skip the inherents verification if the runtime API is old or not expected to
-					// exist.
-					if self
-						.client
-						.runtime_api()
-						.has_api_with::<dyn BlockBuilderApi<B>, _>(parent_hash, |v| v >= 2)
-						.map_err(|e| e.to_string())?
-					{
-						self.check_inherents(
+	let hash = block.header.hash();
+	let parent_hash = *block.header.parent_hash();
+	let authorities = authorities(
+		verifier.client.as_ref(),
+		parent_hash,
+		*block.header.number(),
+		&verifier.compatibility_mode,
+	)
+	.map_err(|e| format!("Could not fetch authorities at {:?}: {}", parent_hash, e))?;
+
+	let create_inherent_data_providers = verifier
+		.create_inherent_data_providers
+		.create_inherent_data_providers(parent_hash, ())
+		.await
+		.map_err(|e| Error::<B>::Client(sp_blockchain::Error::Application(e)))?;
+
+	let mut inherent_data = create_inherent_data_providers
+		.create_inherent_data()
+		.await
+		.map_err(Error::<B>::Inherent)?;
+
+	let slot_now = create_inherent_data_providers.slot();
+
+	// we add one to allow for some small drift.
+	// FIXME #1019 in the future, alter this queue to allow deferring of
+	// headers
+	let checked_header = check_header::<C, B, P>(
+		&verifier.client,
+		slot_now + 1,
+		block.header,
+		hash,
+		&authorities[..],
+		verifier.check_for_equivocation,
+	)
+	.map_err(|e| e.to_string())?;
+	match checked_header {
+		CheckedHeader::Checked(pre_header, (slot, seal, equivocation)) => {
+			// if the body is passed through, we need to use the runtime
+			// to check that the internally-set timestamp in the inherents
+			// actually matches the slot set in the seal.
+			if let Some(inner_body) = block.body.take() {
+				let new_block = B::new(pre_header.clone(), inner_body);
+
+				inherent_data.aura_replace_inherent_data(slot);
+
+				// skip the inherents verification if the runtime API is old or not expected to
+				// exist.
+				if verifier
+					.client
+					.runtime_api()
+					.has_api_with::<dyn BlockBuilderApi<B>, _>(parent_hash, |v| v >= 2)
+					.map_err(|e| e.to_string())?
+				{
+					verifier
+						.check_inherents(
 							new_block.clone(),
 							parent_hash,
 							inherent_data,
@@ -250,40 +263,211 @@ where
 						)
 						.await
 						.map_err(|e| e.to_string())?;
-					}
-
-					let (_, inner_body) = new_block.deconstruct();
-					block.body = Some(inner_body);
 				}
 
-				trace!(target: LOG_TARGET, "Checked {:?}; importing.", pre_header);
-				telemetry!(
-					self.telemetry;
-					CONSENSUS_TRACE;
-					"aura.checked_and_importing";
-					"pre_header" => ?pre_header,
-				);
+				let (_, inner_body) = new_block.deconstruct();
+				block.body = Some(inner_body);
+			}
 
-				block.header = pre_header;
-				block.post_digests.push(seal);
-				block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
-				block.post_hash = Some(hash);
+			trace!(target: LOG_TARGET, "Checked {:?}; importing.", pre_header);
+			telemetry!(
+				verifier.telemetry;
+				CONSENSUS_TRACE;
+				"aura.checked_and_importing";
+				"pre_header" => ?pre_header,
+			);
 
-				Ok(block)
-			},
-			CheckedHeader::Deferred(a, b) => {
-				debug!(target: LOG_TARGET, "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
-				telemetry!(
-					self.telemetry;
-					CONSENSUS_DEBUG;
-					"aura.header_too_far_in_future";
-					"hash" => ?hash,
-					"a" => ?a,
-					"b" => ?b,
-				);
-				Err(format!("Header {:?} rejected: too far in the future", hash))
+			block.header = pre_header;
+			block.post_digests.push(seal);
+			block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+			block.post_hash = Some(hash);
+
+			Ok((block, equivocation))
+		},
+		CheckedHeader::Deferred(a, b) => {
+			debug!(target: LOG_TARGET, "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
+			telemetry!(
+				verifier.telemetry;
+				CONSENSUS_DEBUG;
+				"aura.header_too_far_in_future";
+				"hash" => ?hash,
+				"a" => ?a,
+				"b" => ?b,
+			);
+			Err(format!("Header {:?} rejected: too far in the future", hash))
+		},
+	}
+}
+
+#[async_trait::async_trait]
+impl<B: BlockT, C, P, CIDP> Verifier<B> for AuraVerifier<C, P, CIDP, NumberFor<B>>
+where
+	C: ProvideRuntimeApi<B> + Send + Sync + sc_client_api::backend::AuxStore,
+	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>> + ApiExt<B>,
+	P: Pair,
+	P::Public: Codec + Debug,
+	P::Signature: Codec,
+	CIDP: CreateInherentDataProviders<B, ()> + Send + Sync,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + Sync,
+{
+	async fn verify(&self, block: BlockImportParams<B>) -> Result<BlockImportParams<B>, String> {
+		verify_block(self, block).await.map(|(block, _)| block)
+	}
+}
+
+/// A verifier for Aura blocks that also reports equivocations to the runtime.
+///
+/// Behaves exactly like the wrapped [`AuraVerifier`], but every equivocation it detects is
+/// submitted as an unsigned report through [`AuraEquivocationApi`]. Reports are skipped when
+/// the runtime doesn't expose that API, when the offender's key ownership proof can't be
+/// generated and during the initial sync, as equivocations seen then are most likely stale.
+///
+/// When the runtime allows multiple blocks per slot (`AllowMultipleBlocksPerSlot` in
+/// `pallet-aura`), only equivocations between blocks built on the same parent are reported,
+/// since blocks authored on top of each other in the same slot are expected then.
+pub struct ReportingAuraVerifier<C, P, CIDP, B: BlockT> {
+	verifier: AuraVerifier<C, P, CIDP, NumberFor<B>>,
+	offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+	allow_multiple_blocks_per_slot: bool,
+}
+
+impl<C, P, CIDP, B: BlockT> ReportingAuraVerifier<C, P, CIDP, B> {
+	/// Wrap the given `verifier`, submitting equivocation reports through the offchain
+	/// transaction pool created by `offchain_tx_pool_factory`.
+	///
+	/// `allow_multiple_blocks_per_slot` must match the `AllowMultipleBlocksPerSlot`
+	/// configuration of the runtime.
+	pub fn new(
+		verifier: AuraVerifier<C, P, CIDP, NumberFor<B>>,
+		offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+		allow_multiple_blocks_per_slot: bool,
+	) -> Self {
+		Self { verifier, offchain_tx_pool_factory, allow_multiple_blocks_per_slot }
+	}
+}
+
+impl<C, P, CIDP, B> ReportingAuraVerifier<C, P, CIDP, B>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+	C::Api: AuraEquivocationApi<B, AuthorityId<P>> + ApiExt<B>,
+	P: Pair,
+	P::Public: Codec + Debug,
+{
+	fn report_equivocation(
+		&self,
+		equivocation_proof: AuraEquivocationProof<B, P>,
+	) -> Result<(), Error<B>> {
+		let client = &self.verifier.client;
+
+		// blocks authored on top of each other in the same slot aren't an equivocation when
+		// multiple blocks per slot are allowed, the runtime would reject the report.
+		if self.allow_multiple_blocks_per_slot &&
+			equivocation_proof.first_header.parent_hash() !=
+				equivocation_proof.second_header.parent_hash()
+		{
+			debug!(
+				target: LOG_TARGET,
+				"Not reporting blocks authored on top of each other in slot {}.",
+				equivocation_proof.slot,
+			);
+			return Ok(())
+		}
+
+		// get the best block on which we will build and send the equivocation report.
+		let best_hash = client.info().best_hash;
+
+		let has_api = client
+			.runtime_api()
+			.has_api::<dyn AuraEquivocationApi<B, AuthorityId<P>>>(best_hash)
+			.map_err(Error::RuntimeApi)?;
+		if !has_api {
+			debug!(target: LOG_TARGET, "Runtime doesn't support equivocation reporting.");
+			return Ok(())
+		}
+
+		// generate a key ownership proof. we start by trying to generate the
+		// key ownership proof at the parent of the equivocating header, this
+		// will make sure that proof generation is successful since it happens
+		// while the offender is still part of the authority set. if generation
+		// on the parent header fails we try with best block as well.
+		let generate_key_owner_proof = |at_hash: B::Hash| {
+			client
+				.runtime_api()
+				.generate_key_ownership_proof(
+					at_hash,
+					equivocation_proof.slot,
+					equivocation_proof.offender.clone(),
+				)
+				.map_err(Error::RuntimeApi)
+		};
+
+		let parent_hash = *equivocation_proof.second_header.parent_hash();
+		let key_owner_proof = match generate_key_owner_proof(parent_hash)? {
+			Some(proof) => proof,
+			None => match generate_key_owner_proof(best_hash)? {
+				Some(proof) => proof,
+				None => {
+					debug!(
+						target: LOG_TARGET,
+						"Equivocation offender is not part of the authority set."
+					);
+					return Ok(())
+				},
 			},
+		};
+
+		let offender = equivocation_proof.offender.clone();
+
+		// submit equivocation report at best block.
+		let mut runtime_api = client.runtime_api();
+
+		// Register the offchain tx pool to be able to use it from the runtime.
+		runtime_api
+			.register_extension(self.offchain_tx_pool_factory.offchain_transaction_pool(best_hash));
+
+		runtime_api
+			.submit_report_equivocation_unsigned_extrinsic(
+				best_hash,
+				equivocation_proof,
+				key_owner_proof,
+			)
+			.map_err(Error::RuntimeApi)?;
+
+		info!(target: LOG_TARGET, "Submitted equivocation report for author {:?}", offender);
+
+		Ok(())
+	}
+}
+
+#[async_trait::async_trait]
+impl<B: BlockT, C, P, CIDP> Verifier<B> for ReportingAuraVerifier<C, P, CIDP, B>
+where
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + sc_client_api::backend::AuxStore,
+	C::Api: BlockBuilderApi<B>
+		+ AuraApi<B, AuthorityId<P>>
+		+ AuraEquivocationApi<B, AuthorityId<P>>
+		+ ApiExt<B>,
+	P: Pair,
+	P::Public: Codec + Debug,
+	P::Signature: Codec,
+	CIDP: CreateInherentDataProviders<B, ()> + Send + Sync,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + Sync,
+{
+	async fn verify(&self, block: BlockImportParams<B>) -> Result<BlockImportParams<B>, String> {
+		let (block, equivocation) = verify_block(&self.verifier, block).await?;
+
+		// don't report any equivocations during initial sync
+		// as they are most likely stale.
+		if let (Some(equivocation_proof), false) =
+			(equivocation, block.origin == BlockOrigin::NetworkInitialSync)
+		{
+			if let Err(err) = self.report_equivocation(equivocation_proof) {
+				warn!(target: LOG_TARGET, "Error reporting Aura equivocation: {}", err);
+			}
 		}
+
+		Ok(block)
 	}
 }
 
@@ -28,6 +28,11 @@
 //! Blocks from future steps will be either deferred or rejected depending on how
 //! far in the future they are.
 //!
+//! Blocks are imported with the longest chain fork choice. Other fork choice rules
+//! can be applied by wrapping the block import in a
+//! [`ForkChoiceBlockImport`](sc_consensus::ForkChoiceBlockImport), see for example
+//! [`standalone::preferred_authors_rule`].
+//!
 //! NOTE: Aura itself is designed to be generic over the crypto used.
 #![forbid(missing_docs, unsafe_code)]
 use std::{fmt::Debug, marker::PhantomData, pin::Pin, sync::Arc};
@@ -39,7 +44,7 @@ use sc_client_api::{backend::AuxStore, BlockOf};
 use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction};
 use sc_consensus_slots::{
 	BackoffAuthoringBlocksStrategy, InherentDataProviderExt, SimpleSlotWorkerToSlotWorker,
-	SlotInfo, StorageChanges,
+	SlotDiagnostics, SlotInfo, StorageChanges,
 };
 use sc_telemetry::TelemetryHandle;
 use sp_api::{Core, ProvideRuntimeApi};
@@ -47,10 +52,10 @@ use sp_application_crypto::AppPublic;
 use sp_blockchain::HeaderBackend;
 use sp_consensus::{BlockOrigin, Environment, Error as ConsensusError, Proposer, SelectChain};
 use sp_consensus_slots::Slot;
-use sp_core::crypto::Pair;
+use sp_core::{crypto::Pair, hexdisplay::HexDisplay};
 use sp_inherents::CreateInherentDataProviders;
 use sp_keystore::KeystorePtr;
-use sp_runtime::traits::{Block as BlockT, Header, Member, NumberFor};
+use sp_runtime::traits::{Block as BlockT, Header, Member, NumberFor, Zero};
 
 mod import_queue;
 pub mod standalone;
@@ -58,14 +63,14 @@ pub mod standalone;
 pub use crate::standalone::{find_pre_digest, slot_duration};
 pub use import_queue::{
 	build_verifier, import_queue, AuraVerifier, BuildVerifierParams, CheckForEquivocation,
-	ImportQueueParams,
+	ImportQueueParams, ReportingAuraVerifier,
 };
-pub use sc_consensus_slots::SlotProportion;
+pub use sc_consensus_slots::{SlotDiagnostics, SlotProportion};
 pub use sp_consensus::SyncOracle;
 pub use sp_consensus_aura::{
 	digests::CompatibleDigestItem,
 	inherents::{InherentDataProvider, InherentType as AuraInherent, INHERENT_IDENTIFIER},
-	AuraApi, ConsensusLog, SlotDuration, AURA_ENGINE_ID,
+	AuraApi, AuraEquivocationApi, ConsensusLog, SlotDuration, AURA_ENGINE_ID,
 };
 
 const LOG_TARGET: &str = "aura";
@@ -110,7 +115,7 @@ impl<N> Default for CompatibilityMode<N> {
 }
 
 /// Parameters of [`start_aura`].
-pub struct StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, N> {
+pub struct StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, N, H> {
 	/// The duration of a slot.
 	pub slot_duration: SlotDuration,
 	/// The client to interact with the chain.
@@ -148,6 +153,8 @@ pub struct StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, N> {
 	///
 	/// If in doubt, use `Default::default()`.
 	pub compatibility_mode: CompatibilityMode<N>,
+	/// Diagnostics recording the timings and outcome of the handled slots, if any.
+	pub slot_diagnostics: Option<SlotDiagnostics<H>>,
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
@@ -168,7 +175,8 @@ pub fn start_aura<P, B, C, SC, I, PF, SO, L, CIDP, BS, Error>(
 		max_block_proposal_slot_portion,
 		telemetry,
 		compatibility_mode,
-	}: StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, NumberFor<B>>,
+		slot_diagnostics,
+	}: StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, NumberFor<B>, B::Hash>,
 ) -> Result<impl Future<Output = ()>, ConsensusError>
 where
 	P: Pair,
@@ -201,6 +209,7 @@ where
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		compatibility_mode,
+		slot_diagnostics,
 	});
 
 	Ok(sc_consensus_slots::start_slot_worker(
@@ -213,7 +222,7 @@ where
 }
 
 /// Parameters of [`build_aura_worker`].
-pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N> {
+pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N, H> {
 	/// The client to interact with the chain.
 	pub client: Arc<C>,
 	/// The block import.
@@ -245,6 +254,8 @@ pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N> {
 	///
 	/// If in doubt, use `Default::default()`.
 	pub compatibility_mode: CompatibilityMode<N>,
+	/// Diagnostics recording the timings and outcome of the handled slots, if any.
+	pub slot_diagnostics: Option<SlotDiagnostics<H>>,
 }
 
 /// Build the aura worker.
@@ -264,7 +275,8 @@ pub fn build_aura_worker<P, B, C, PF, I, SO, L, BS, Error>(
 		telemetry,
 		force_authoring,
 		compatibility_mode,
-	}: BuildAuraWorkerParams<C, I, PF, SO, L, BS, NumberFor<B>>,
+		slot_diagnostics,
+	}: BuildAuraWorkerParams<C, I, PF, SO, L, BS, NumberFor<B>, B::Hash>,
 ) -> impl sc_consensus_slots::SimpleSlotWorker<
 	B,
 	Proposer = PF::Proposer,
@@ -302,11 +314,12 @@ where
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		compatibility_mode,
+		slot_diagnostics,
 		_phantom: PhantomData::<fn() -> P>,
 	}
 }
 
-struct AuraWorker<C, E, I, P, SO, L, BS, N> {
+struct AuraWorker<C, E, I, P, SO, L, BS, N, H> {
 	client: Arc<C>,
 	block_import: I,
 	env: E,
@@ -319,12 +332,13 @@ struct AuraWorker<C, E, I, P, SO, L, BS, N> {
 	max_block_proposal_slot_portion: Option<SlotProportion>,
 	telemetry: Option<TelemetryHandle>,
 	compatibility_mode: CompatibilityMode<N>,
+	slot_diagnostics: Option<SlotDiagnostics<H>>,
 	_phantom: PhantomData<fn() -> P>,
 }
 
 #[async_trait::async_trait]
 impl<B, C, E, I, P, Error, SO, L, BS> sc_consensus_slots::SimpleSlotWorker<B>
-	for AuraWorker<C, E, I, P, SO, L, BS, NumberFor<B>>
+	for AuraWorker<C, E, I, P, SO, L, BS, NumberFor<B>, B::Hash>
 where
 	B: BlockT,
 	C: ProvideRuntimeApi<B> + BlockOf + HeaderBackend<B> + Sync,
@@ -443,6 +457,28 @@ where
 		self.telemetry.clone()
 	}
 
+	fn slot_diagnostics(&self) -> Option<&SlotDiagnostics<B::Hash>> {
+		self.slot_diagnostics.as_ref()
+	}
+
+	fn slot_author(&self, slot: Slot, authorities: &Self::AuxData) -> Option<String> {
+		crate::standalone::slot_author::<P>(slot, authorities)
+			.map(|author| format!("0x{}", HexDisplay::from(&author.as_ref())))
+	}
+
+	fn block_author(
+		&self,
+		header: &B::Header,
+		authorities: &Self::AuxData,
+	) -> Option<(Slot, String)> {
+		if header.number().is_zero() {
+			return None
+		}
+
+		let slot = find_pre_digest::<B, P::Signature>(header).ok()?;
+		self.slot_author(slot, authorities).map(|author| (slot, author))
+	}
+
 	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> std::time::Duration {
 		let parent_slot = find_pre_digest::<B, P::Signature>(&slot_info.chain_head).ok();
 
@@ -481,6 +517,9 @@ pub enum Error<B: BlockT> {
 	/// Client Error
 	#[error(transparent)]
 	Client(sp_blockchain::Error),
+	/// Runtime Api error.
+	#[error(transparent)]
+	RuntimeApi(sp_api::ApiError),
 	/// Unknown inherent error for identifier
 	#[error("Unknown inherent error for identifier: {}", String::from_utf8_lossy(.0))]
 	UnknownInherentError(sp_inherents::InherentIdentifier),
@@ -758,6 +797,7 @@ mod tests {
 					max_block_proposal_slot_portion: None,
 					telemetry: None,
 					compatibility_mode: CompatibilityMode::None,
+					slot_diagnostics: None,
 				})
 				.expect("Starts aura"),
 			);
@@ -810,6 +850,7 @@ mod tests {
 			block_proposal_slot_portion: SlotProportion::new(0.5),
 			max_block_proposal_slot_portion: None,
 			compatibility_mode: Default::default(),
+			slot_diagnostics: None,
 			_phantom: PhantomData::<fn() -> AuthorityPair>,
 		};
 
@@ -860,6 +901,7 @@ mod tests {
 			block_proposal_slot_portion: SlotProportion::new(0.5),
 			max_block_proposal_slot_portion: None,
 			compatibility_mode: Default::default(),
+			slot_diagnostics: None,
 			_phantom: PhantomData::<fn() -> AuthorityPair>,
 		};
 
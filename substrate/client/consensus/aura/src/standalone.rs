@@ -18,13 +18,14 @@
 
 //! Standalone functions used within the implementation of Aura.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use log::trace;
 
 use codec::Codec;
 
 use sc_client_api::UsageProvider;
+use sc_consensus::PreferredAuthorsRule;
 use sp_api::{Core, ProvideRuntimeApi};
 use sp_application_crypto::{AppCrypto, AppPublic};
 use sp_blockchain::Result as CResult;
@@ -249,6 +250,32 @@ where
 		.ok_or(ConsensusError::InvalidAuthoritiesSet)
 }
 
+/// Fork choice rule preferring the chains whose head was authored by one of the `preferred`
+/// authorities, to be applied with a [`ForkChoiceBlockImport`](sc_consensus::ForkChoiceBlockImport).
+///
+/// Blocks whose author can't be determined are not preferred.
+pub fn preferred_authors_rule<P, B, C>(
+	client: Arc<C>,
+	preferred: Vec<AuthorityId<P>>,
+) -> PreferredAuthorsRule<impl Fn(&B::Header) -> bool + Send + Sync>
+where
+	P: Pair,
+	P::Public: Codec + Debug,
+	P::Signature: Codec,
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + Send + Sync,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+{
+	PreferredAuthorsRule::new(move |header: &B::Header| {
+		let Ok(slot) = find_pre_digest::<B, P::Signature>(header) else { return false };
+		let Ok(authorities) = fetch_authorities(&*client, *header.parent_hash()) else {
+			return false
+		};
+
+		slot_author::<P>(slot, &authorities).is_some_and(|author| preferred.contains(author))
+	})
+}
+
 /// Errors in slot and seal verification.
 #[derive(Debug, thiserror::Error)]
 pub enum SealVerificationError<Header> {
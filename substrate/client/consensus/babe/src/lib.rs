@@ -97,6 +97,7 @@ use sc_consensus::{
 		BlockCheckParams, BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult,
 		StateAction,
 	},
+	fork_choice::{ForkChoiceBlock, ForkChoiceRule, HeaviestChainRule, LongestChainRule, Then},
 	import_queue::{BasicQueue, BoxJustificationImport, DefaultImportQueue, Verifier},
 };
 use sc_consensus_epochs::{
@@ -104,7 +105,7 @@ use sc_consensus_epochs::{
 };
 use sc_consensus_slots::{
 	check_equivocation, BackoffAuthoringBlocksStrategy, CheckedHeader, InherentDataProviderExt,
-	SlotInfo, StorageChanges,
+	SlotDiagnostics, SlotInfo, StorageChanges,
 };
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_DEBUG, CONSENSUS_TRACE};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
@@ -118,7 +119,7 @@ use sp_blockchain::{
 use sp_consensus::{BlockOrigin, Environment, Error as ConsensusError, Proposer, SelectChain};
 use sp_consensus_babe::inherents::BabeInherentData;
 use sp_consensus_slots::Slot;
-use sp_core::traits::SpawnEssentialNamed;
+use sp_core::{crypto::ByteArray, hexdisplay::HexDisplay, traits::SpawnEssentialNamed};
 use sp_inherents::{CreateInherentDataProviders, InherentData, InherentDataProvider};
 use sp_keystore::KeystorePtr;
 use sp_runtime::{
@@ -127,7 +128,7 @@ use sp_runtime::{
 	DigestItem,
 };
 
-pub use sc_consensus_slots::SlotProportion;
+pub use sc_consensus_slots::{SlotDiagnostics, SlotProportion};
 pub use sp_consensus::SyncOracle;
 pub use sp_consensus_babe::{
 	digests::{
@@ -457,6 +458,9 @@ pub struct BabeParams<B: BlockT, C, SC, E, I, SO, L, CIDP, BS> {
 
 	/// Handle use to report telemetries.
 	pub telemetry: Option<TelemetryHandle>,
+
+	/// Diagnostics recording the timings and outcome of the handled slots, if any.
+	pub slot_diagnostics: Option<SlotDiagnostics<B::Hash>>,
 }
 
 /// Start the babe worker.
@@ -476,6 +480,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CIDP, BS, L, Error>(
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		telemetry,
+		slot_diagnostics,
 	}: BabeParams<B, C, SC, E, I, SO, L, CIDP, BS>,
 ) -> Result<BabeWorker<B>, ConsensusError>
 where
@@ -515,6 +520,7 @@ where
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		telemetry,
+		slot_diagnostics,
 	};
 
 	info!(target: LOG_TARGET, "👶 Starting BABE Authorship worker");
@@ -712,6 +718,7 @@ struct BabeSlotWorker<B: BlockT, C, E, I, SO, L, BS> {
 	block_proposal_slot_portion: SlotProportion,
 	max_block_proposal_slot_portion: Option<SlotProportion>,
 	telemetry: Option<TelemetryHandle>,
+	slot_diagnostics: Option<SlotDiagnostics<B::Hash>>,
 }
 
 #[async_trait::async_trait]
@@ -882,6 +889,47 @@ where
 		self.telemetry.clone()
 	}
 
+	fn slot_diagnostics(&self) -> Option<&SlotDiagnostics<B::Hash>> {
+		self.slot_diagnostics.as_ref()
+	}
+
+	// NOTE: primary slot assignments are private, so only the secondary slot author is known
+	// ahead of the slot. Since any authority may claim the slot through a primary assignment, the
+	// slot is only considered missed if no block was authored in it at all.
+	fn slot_author(&self, slot: Slot, epoch_descriptor: &Self::AuxData) -> Option<String> {
+		let epoch_changes = self.epoch_changes.shared_data();
+		let epoch = epoch_changes
+			.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.config, slot))?;
+		let epoch = epoch.as_ref();
+
+		if !epoch.config.allowed_slots.is_secondary_plain_slots_allowed() &&
+			!epoch.config.allowed_slots.is_secondary_vrf_slots_allowed()
+		{
+			return None
+		}
+
+		authorship::secondary_slot_author(slot, &epoch.authorities, epoch.randomness)
+			.map(authority_label)
+	}
+
+	fn block_author(
+		&self,
+		header: &B::Header,
+		epoch_descriptor: &Self::AuxData,
+	) -> Option<(Slot, String)> {
+		if header.number().is_zero() {
+			return None
+		}
+
+		let pre_digest = find_pre_digest::<B>(header).ok()?;
+		let epoch_changes = self.epoch_changes.shared_data();
+		let epoch = epoch_changes
+			.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.config, slot))?;
+		let (author, _) = epoch.as_ref().authorities.get(pre_digest.authority_index() as usize)?;
+
+		Some((pre_digest.slot(), authority_label(author)))
+	}
+
 	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> Duration {
 		let parent_slot = find_pre_digest::<B>(&slot_info.chain_head).ok().map(|d| d.slot());
 
@@ -896,6 +944,11 @@ where
 	}
 }
 
+// The label identifying the given authority in the slot diagnostics.
+fn authority_label(authority: &AuthorityId) -> String {
+	format!("0x{}", HexDisplay::from(&authority.to_raw_vec()))
+}
+
 /// Extract the BABE pre digest from the given header. Pre-runtime digests are
 /// mandatory, the function will return `Err` if none is found.
 pub fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<PreDigest, Error<B>> {
@@ -1303,6 +1356,7 @@ pub struct BabeBlockImport<Block: BlockT, Client, I> {
 	client: Arc<Client>,
 	epoch_changes: SharedEpochChanges<Block, Epoch>,
 	config: BabeConfiguration,
+	fork_choice: Arc<dyn ForkChoiceRule<Block, BabeBlockWeight>>,
 }
 
 impl<Block: BlockT, I: Clone, Client> Clone for BabeBlockImport<Block, Client, I> {
@@ -1312,6 +1366,7 @@ impl<Block: BlockT, I: Clone, Client> Clone for BabeBlockImport<Block, Client, I
 			client: self.client.clone(),
 			epoch_changes: self.epoch_changes.clone(),
 			config: self.config.clone(),
+			fork_choice: self.fork_choice.clone(),
 		}
 	}
 }
@@ -1323,7 +1378,21 @@ impl<Block: BlockT, Client, I> BabeBlockImport<Block, Client, I> {
 		block_import: I,
 		config: BabeConfiguration,
 	) -> Self {
-		BabeBlockImport { client, inner: block_import, epoch_changes, config }
+		// The default fork choice rule is that we pick the heaviest chain (i.e.
+		// more primary blocks), if there's a tie we go with the longest chain.
+		let fork_choice = Arc::new(Then::new(HeaviestChainRule, LongestChainRule));
+
+		BabeBlockImport { client, inner: block_import, epoch_changes, config, fork_choice }
+	}
+
+	/// Use the given fork choice rule instead of the default one, which picks the heaviest chain
+	/// (i.e. more primary blocks) and breaks ties with the longest chain.
+	pub fn with_fork_choice(
+		mut self,
+		fork_choice: Arc<dyn ForkChoiceRule<Block, BabeBlockWeight>>,
+	) -> Self {
+		self.fork_choice = fork_choice;
+		self
 	}
 }
 
@@ -1636,33 +1705,42 @@ where
 					.extend(values.iter().map(|(k, v)| (k.to_vec(), Some(v.to_vec()))))
 			});
 
-			// The fork choice rule is that we pick the heaviest chain (i.e.
-			// more primary blocks), if there's a tie we go with the longest
-			// chain.
 			block.fork_choice = {
-				let (last_best, last_best_number) = (info.best_hash, info.best_number);
-
-				let last_best_weight = if &last_best == block.header.parent_hash() {
-					// the parent=genesis case is already covered for loading parent weight,
-					// so we don't need to cover again here.
-					parent_weight
-				} else {
-					aux_schema::load_block_weight(&*self.client, last_best)
-						.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
-						.ok_or_else(|| {
-							ConsensusError::ChainLookup(
-								"No block weight for parent header.".to_string(),
-							)
-						})?
+				let last_best = info.best_hash;
+
+				let (last_best_header, last_best_weight) =
+					if &last_best == block.header.parent_hash() {
+						// the parent=genesis case is already covered for loading parent weight,
+						// so we don't need to cover again here.
+						(parent_header.clone(), parent_weight)
+					} else {
+						let header = self
+							.client
+							.header(last_best)
+							.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+							.ok_or_else(|| {
+								ConsensusError::ChainLookup("No header for best block.".to_string())
+							})?;
+						let weight = aux_schema::load_block_weight(&*self.client, last_best)
+							.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+							.ok_or_else(|| {
+								ConsensusError::ChainLookup(
+									"No block weight for parent header.".to_string(),
+								)
+							})?;
+
+						(header, weight)
+					};
+
+				let candidate =
+					ForkChoiceBlock { hash, header: &block.header, weight: total_weight };
+				let best = ForkChoiceBlock {
+					hash: last_best,
+					header: &last_best_header,
+					weight: last_best_weight,
 				};
 
-				Some(ForkChoiceStrategy::Custom(if total_weight > last_best_weight {
-					true
-				} else if total_weight == last_best_weight {
-					number > last_best_number
-				} else {
-					false
-				}))
+				Some(ForkChoiceStrategy::Custom(self.fork_choice.is_new_best(&candidate, &best)?))
 			};
 
 			// Release the mutex, but it stays locked
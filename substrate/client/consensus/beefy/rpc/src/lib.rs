@@ -45,6 +45,9 @@ use sc_consensus_beefy::communication::notification::{
 };
 
 mod notification;
+mod relay;
+
+pub use relay::{BeefyRelay, BeefyRelayApiClient, BeefyRelayApiServer, RelayProof};
 
 #[derive(Debug, thiserror::Error)]
 /// Top-level error type for the RPC handler
@@ -55,6 +58,12 @@ pub enum Error {
 	/// The BEEFY RPC background task failed to spawn.
 	#[error("BEEFY RPC background task failed to spawn")]
 	RpcTaskFailure(#[from] SpawnError),
+	/// The requested block is not yet finalized by BEEFY.
+	#[error("Block is not finalized by BEEFY yet")]
+	BlockNotFinalized,
+	/// Generating one of the relay proofs failed.
+	#[error("Failed to generate proof: {0}")]
+	ProofGeneration(String),
 }
 
 /// The error codes returned by jsonrpc.
@@ -63,6 +72,10 @@ pub enum ErrorCode {
 	NotReady = 1,
 	/// Returned on BEEFY RPC background task failure.
 	TaskFailure = 2,
+	/// Returned when the requested block is not finalized by BEEFY.
+	NotFinalized = 3,
+	/// Returned when generating a relay proof failed.
+	ProofGeneration = 4,
 }
 
 impl From<Error> for ErrorCode {
@@ -70,6 +83,8 @@ impl From<Error> for ErrorCode {
 		match error {
 			Error::EndpointNotReady => ErrorCode::NotReady,
 			Error::RpcTaskFailure(_) => ErrorCode::TaskFailure,
+			Error::BlockNotFinalized => ErrorCode::NotFinalized,
+			Error::ProofGeneration(_) => ErrorCode::ProofGeneration,
 		}
 	}
 }
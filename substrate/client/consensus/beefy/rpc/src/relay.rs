@@ -0,0 +1,567 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! RPC API for bridge relayers.
+//!
+//! Relaying a block to a BEEFY light client requires the latest BEEFY finality proof, an MMR
+//! proof of the block's leaf against the MMR root signed in that finality proof and merkle proofs
+//! of the signing authorities against the authority set commitment of `pallet-beefy-mmr`.
+//! [`BeefyRelay`] bundles all of them in a single [`RelayProof`].
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::proc_macros::rpc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use mmr_rpc::LeavesProof;
+use pallet_beefy_mmr::BeefyMmrApi;
+use sc_client_api::BlockBackend;
+use sc_consensus_beefy::{
+	communication::notification::BeefyVersionedFinalityProofStream,
+	justification::BeefyVersionedFinalityProof,
+};
+use sc_rpc::SubscriptionTaskExecutor;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_application_crypto::RuntimeAppPublic;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_beefy::{AuthorityIdBound, MmrRootHash, VersionedFinalityProof, BEEFY_ENGINE_ID};
+use sp_core::{
+	offchain::{storage::OffchainDb, OffchainDbExt, OffchainStorage},
+	Bytes,
+};
+use sp_mmr_primitives::MmrApi;
+use sp_runtime::traits::{Block as BlockT, NumberFor, One};
+
+use crate::Error;
+
+/// Everything a bridge relayer needs to prove a block to a BEEFY light client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayProof<BlockHash> {
+	/// Hash of the block the BEEFY commitment was signed for.
+	pub commitment_block_hash: BlockHash,
+	/// SCALE-encoded `sp_consensus_beefy::VersionedFinalityProof` of the commitment.
+	pub finality_proof: Bytes,
+	/// The MMR leaf of the target block, proven against the MMR root of the commitment.
+	pub mmr_proof: LeavesProof<BlockHash>,
+	/// SCALE-encoded `BeefyAuthoritySet` of the authorities that signed the commitment.
+	pub authority_set: Bytes,
+	/// SCALE-encoded `Vec<AuthorityMerkleProof>` for every authority that signed the
+	/// commitment, proven against the `keyset_commitment` of `authority_set`.
+	pub authority_proofs: Bytes,
+}
+
+/// Provides RPC methods for bridge relayers.
+#[rpc(client, server)]
+pub trait BeefyRelayApi<BlockHash, BlockNumber> {
+	/// Generates a [`RelayProof`] for the given block, using the latest BEEFY finality proof
+	/// seen by this client.
+	///
+	/// Until a finality proof has been seen, e.g. right after a restart, the first BEEFY
+	/// justification stored in the database at or above the given block is used instead.
+	///
+	/// The block must be finalized by BEEFY, i.e. it must not be higher than the block of the
+	/// latest BEEFY commitment.
+	#[method(name = "beefy_generateRelayProof")]
+	fn generate_relay_proof(
+		&self,
+		block_number: BlockNumber,
+	) -> Result<RelayProof<BlockHash>, Error>;
+}
+
+/// Implements the [`BeefyRelayApiServer`] RPC trait.
+pub struct BeefyRelay<Block: BlockT, Client, AuthorityId: AuthorityIdBound, S> {
+	client: Arc<Client>,
+	offchain_db: OffchainDb<S>,
+	latest_finality_proof: Arc<RwLock<Option<BeefyVersionedFinalityProof<Block, AuthorityId>>>>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, Client, AuthorityId, S> BeefyRelay<Block, Client, AuthorityId, S>
+where
+	Block: BlockT,
+	AuthorityId: AuthorityIdBound,
+	<AuthorityId as RuntimeAppPublic>::Signature: Send + Sync,
+{
+	/// Creates a new BEEFY relay RPC handler instance.
+	///
+	/// The `offchain_storage` is needed to generate MMR proofs.
+	pub fn new(
+		client: Arc<Client>,
+		offchain_storage: S,
+		finality_proof_stream: BeefyVersionedFinalityProofStream<Block, AuthorityId>,
+		executor: SubscriptionTaskExecutor,
+	) -> Self {
+		let latest_finality_proof = Arc::new(RwLock::new(None));
+
+		let stream = finality_proof_stream.subscribe(100_000);
+		let closure_clone = latest_finality_proof.clone();
+		let future = stream.for_each(move |finality_proof| {
+			let async_clone = closure_clone.clone();
+			async move { *async_clone.write() = Some(finality_proof) }
+		});
+
+		executor.spawn("substrate-rpc-subscription", Some("rpc"), future.map(drop).boxed());
+		Self {
+			client,
+			offchain_db: OffchainDb::new(offchain_storage),
+			latest_finality_proof,
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<Block, Client, AuthorityId, S> BeefyRelay<Block, Client, AuthorityId, S>
+where
+	Block: BlockT,
+	Client: HeaderBackend<Block> + BlockBackend<Block>,
+	AuthorityId: AuthorityIdBound,
+{
+	/// Loads the first BEEFY finality proof imported at or above `block_number`, up to the last
+	/// finalized block.
+	///
+	/// BEEFY justifications are only stored once they have been verified on import, so they are
+	/// decoded without being verified again.
+	fn stored_finality_proof(
+		&self,
+		block_number: NumberFor<Block>,
+	) -> Result<Option<BeefyVersionedFinalityProof<Block, AuthorityId>>, Error> {
+		let finalized_number = self.client.info().finalized_number;
+		let mut number = block_number;
+		while number <= finalized_number {
+			let hash = self
+				.client
+				.hash(number)
+				.map_err(proof_generation_error)?
+				.ok_or_else(|| Error::ProofGeneration("Unknown finalized block".into()))?;
+			let encoded = self
+				.client
+				.justifications(hash)
+				.map_err(proof_generation_error)?
+				.and_then(|justifications| justifications.into_justification(BEEFY_ENGINE_ID));
+			if let Some(encoded) = encoded {
+				return Decode::decode(&mut &*encoded).map(Some).map_err(proof_generation_error)
+			}
+			number += One::one();
+		}
+
+		Ok(None)
+	}
+}
+
+impl<Block, Client, AuthorityId, S> BeefyRelayApiServer<Block::Hash, NumberFor<Block>>
+	for BeefyRelay<Block, Client, AuthorityId, S>
+where
+	Block: BlockT,
+	Client: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: MmrApi<Block, MmrRootHash, NumberFor<Block>> + BeefyMmrApi<Block, MmrRootHash>,
+	AuthorityId: AuthorityIdBound,
+	<AuthorityId as RuntimeAppPublic>::Signature: Send + Sync,
+	S: OffchainStorage + 'static,
+{
+	fn generate_relay_proof(
+		&self,
+		block_number: NumberFor<Block>,
+	) -> Result<RelayProof<Block::Hash>, Error> {
+		let latest_finality_proof = self.latest_finality_proof.read().clone();
+		let finality_proof = match latest_finality_proof {
+			Some(finality_proof) => finality_proof,
+			None => self.stored_finality_proof(block_number)?.ok_or(Error::EndpointNotReady)?,
+		};
+		let commitment = match finality_proof {
+			VersionedFinalityProof::V1(ref signed_commitment) => &signed_commitment.commitment,
+		};
+		if block_number > commitment.block_number {
+			return Err(Error::BlockNotFinalized)
+		}
+
+		let commitment_block_hash = self
+			.client
+			.hash(commitment.block_number)
+			.map_err(proof_generation_error)?
+			.ok_or_else(|| Error::ProofGeneration("Unknown commitment block".into()))?;
+
+		// the MMR proof is generated against the MMR root at the commitment block, which is the
+		// one signed in the commitment payload.
+		let mmr_proof = {
+			let mut api = self.client.runtime_api();
+			let best_hash = self.client.info().best_hash;
+			api.register_extension(OffchainDbExt::new(self.offchain_db.clone()));
+
+			let (leaves, proof) = api
+				.generate_proof(best_hash, vec![block_number], Some(commitment.block_number))
+				.map_err(proof_generation_error)?
+				.map_err(proof_generation_error)?;
+			LeavesProof::new(best_hash, leaves, proof)
+		};
+
+		let api = self.client.runtime_api();
+		let supports_authority_proofs = api
+			.has_api_with::<dyn BeefyMmrApi<Block, MmrRootHash>, _>(commitment_block_hash, |v| {
+				v >= 2
+			})
+			.map_err(proof_generation_error)?;
+		if !supports_authority_proofs {
+			return Err(Error::ProofGeneration(
+				"Runtime doesn't support authority set merkle proofs".into(),
+			))
+		}
+
+		let authority_set =
+			api.authority_set_proof(commitment_block_hash).map_err(proof_generation_error)?;
+		if authority_set.id != commitment.validator_set_id {
+			return Err(Error::ProofGeneration(format!(
+				"Authority set {} at the commitment block doesn't match the signing set {}",
+				authority_set.id, commitment.validator_set_id,
+			)))
+		}
+
+		let authority_proofs = api
+			.authority_set_merkle_proofs(commitment_block_hash, signer_indices(&finality_proof))
+			.map_err(proof_generation_error)?;
+
+		Ok(RelayProof {
+			commitment_block_hash,
+			finality_proof: finality_proof.encode().into(),
+			mmr_proof,
+			authority_set: authority_set.encode().into(),
+			authority_proofs: authority_proofs.encode().into(),
+		})
+	}
+}
+
+/// Returns the indices of the authorities that signed the commitment of the given finality proof.
+fn signer_indices<N, S>(finality_proof: &VersionedFinalityProof<N, S>) -> Vec<u32> {
+	match finality_proof {
+		VersionedFinalityProof::V1(signed_commitment) => signed_commitment
+			.signatures
+			.iter()
+			.enumerate()
+			.filter(|(_, signature)| signature.is_some())
+			.map(|(index, _)| index as u32)
+			.collect(),
+	}
+}
+
+fn proof_generation_error(err: impl std::fmt::Debug) -> Error {
+	Error::ProofGeneration(format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use pallet_beefy_mmr::AuthorityMerkleProof;
+	use sc_block_builder::BlockBuilderBuilder;
+	use sc_consensus_beefy::communication::notification::BeefyVersionedFinalityProofSender;
+	use sp_api::ApiRef;
+	use sp_blockchain::{BlockStatus, Info};
+	use sp_consensus::BlockOrigin;
+	use sp_consensus_beefy::{
+		ecdsa_crypto, known_payloads, mmr::BeefyAuthoritySet, Commitment, Payload, SignedCommitment,
+	};
+	use sp_core::offchain::testing::TestPersistentOffchainDB;
+	use sp_mmr_primitives as mmr;
+	use sp_runtime::{generic::SignedBlock, Justifications};
+	use substrate_test_runtime_client::{
+		runtime::{Block, BlockNumber, Extrinsic, Hash, Header},
+		ClientBlockImportExt,
+	};
+
+	struct TestClient(substrate_test_runtime_client::TestClient);
+
+	impl HeaderBackend<Block> for TestClient {
+		fn header(&self, hash: Hash) -> sp_blockchain::Result<Option<Header>> {
+			self.0.header(hash)
+		}
+
+		fn info(&self) -> Info<Block> {
+			self.0.info()
+		}
+
+		fn status(&self, hash: Hash) -> sp_blockchain::Result<BlockStatus> {
+			self.0.status(hash)
+		}
+
+		fn number(&self, hash: Hash) -> sp_blockchain::Result<Option<BlockNumber>> {
+			self.0.number(hash)
+		}
+
+		fn hash(&self, number: BlockNumber) -> sp_blockchain::Result<Option<Hash>> {
+			self.0.hash(number)
+		}
+	}
+
+	impl BlockBackend<Block> for TestClient {
+		fn block_body(&self, hash: Hash) -> sp_blockchain::Result<Option<Vec<Extrinsic>>> {
+			self.0.block_body(hash)
+		}
+
+		fn block_indexed_body(&self, hash: Hash) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
+			self.0.block_indexed_body(hash)
+		}
+
+		fn block(&self, hash: Hash) -> sp_blockchain::Result<Option<SignedBlock<Block>>> {
+			self.0.block(hash)
+		}
+
+		fn block_status(&self, hash: Hash) -> sp_blockchain::Result<sp_consensus::BlockStatus> {
+			self.0.block_status(hash)
+		}
+
+		fn justifications(&self, hash: Hash) -> sp_blockchain::Result<Option<Justifications>> {
+			self.0.justifications(hash)
+		}
+
+		fn block_hash(&self, number: BlockNumber) -> sp_blockchain::Result<Option<Hash>> {
+			self.0.block_hash(number)
+		}
+
+		fn indexed_transaction(&self, hash: Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			self.0.indexed_transaction(hash)
+		}
+
+		fn requires_full_sync(&self) -> bool {
+			self.0.requires_full_sync()
+		}
+	}
+
+	#[derive(Clone)]
+	struct TestApi;
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = TestApi;
+
+		fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+			TestApi.into()
+		}
+	}
+
+	fn test_leaves() -> Vec<mmr::EncodableOpaqueLeaf> {
+		vec![mmr::EncodableOpaqueLeaf(vec![1, 2, 3])]
+	}
+
+	fn test_leaf_proof() -> mmr::LeafProof<MmrRootHash> {
+		mmr::LeafProof { leaf_indices: vec![0], leaf_count: 1, items: vec![] }
+	}
+
+	fn test_authority_set() -> BeefyAuthoritySet<MmrRootHash> {
+		BeefyAuthoritySet { id: 0, len: 4, keyset_commitment: MmrRootHash::repeat_byte(1) }
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl mmr::MmrApi<Block, MmrRootHash, BlockNumber> for TestApi {
+			fn mmr_root() -> Result<MmrRootHash, mmr::Error> {
+				Err(mmr::Error::PalletNotIncluded)
+			}
+
+			fn mmr_leaf_count() -> Result<mmr::LeafIndex, mmr::Error> {
+				Err(mmr::Error::PalletNotIncluded)
+			}
+
+			fn generate_proof(
+				_block_numbers: Vec<BlockNumber>,
+				_best_known_block_number: Option<BlockNumber>,
+			) -> Result<(Vec<mmr::EncodableOpaqueLeaf>, mmr::LeafProof<MmrRootHash>), mmr::Error> {
+				Ok((test_leaves(), test_leaf_proof()))
+			}
+
+			fn verify_proof(
+				_leaves: Vec<mmr::EncodableOpaqueLeaf>,
+				_proof: mmr::LeafProof<MmrRootHash>,
+			) -> Result<(), mmr::Error> {
+				Err(mmr::Error::PalletNotIncluded)
+			}
+
+			fn verify_proof_stateless(
+				_root: MmrRootHash,
+				_leaves: Vec<mmr::EncodableOpaqueLeaf>,
+				_proof: mmr::LeafProof<MmrRootHash>,
+			) -> Result<(), mmr::Error> {
+				Err(mmr::Error::PalletNotIncluded)
+			}
+		}
+
+		impl BeefyMmrApi<Block, MmrRootHash> for TestApi {
+			fn authority_set_proof() -> BeefyAuthoritySet<MmrRootHash> {
+				test_authority_set()
+			}
+
+			fn next_authority_set_proof() -> BeefyAuthoritySet<MmrRootHash> {
+				Default::default()
+			}
+
+			fn authority_set_merkle_proofs(
+				authority_indices: Vec<u32>,
+			) -> Vec<AuthorityMerkleProof<MmrRootHash>> {
+				authority_indices
+					.into_iter()
+					.map(|leaf_index| AuthorityMerkleProof {
+						leaf_index,
+						leaf: vec![leaf_index as u8],
+						proof: vec![],
+					})
+					.collect()
+			}
+		}
+	}
+
+	type TestRelay =
+		BeefyRelay<Block, TestClient, ecdsa_crypto::AuthorityId, TestPersistentOffchainDB>;
+
+	fn setup_relay(
+		client: substrate_test_runtime_client::TestClient,
+	) -> (TestRelay, BeefyVersionedFinalityProofSender<Block, ecdsa_crypto::AuthorityId>) {
+		let (finality_proof_sender, finality_proof_stream) =
+			BeefyVersionedFinalityProofStream::<Block, ecdsa_crypto::AuthorityId>::channel();
+
+		let relay = BeefyRelay::new(
+			Arc::new(TestClient(client)),
+			TestPersistentOffchainDB::new(),
+			finality_proof_stream,
+			sc_rpc::testing::test_executor(),
+		);
+
+		(relay, finality_proof_sender)
+	}
+
+	fn commitment(block_number: BlockNumber) -> Commitment<BlockNumber> {
+		let payload = Payload::from_single_entry(known_payloads::MMR_ROOT_ID, vec![]);
+		Commitment { payload, block_number, validator_set_id: 0 }
+	}
+
+	fn finality_proof(
+		block_number: BlockNumber,
+	) -> BeefyVersionedFinalityProof<Block, ecdsa_crypto::AuthorityId> {
+		SignedCommitment {
+			commitment: commitment(block_number),
+			signatures: vec![None, Some(Default::default()), None, Some(Default::default())],
+		}
+		.into()
+	}
+
+	/// Calls `generate_relay_proof` until the relay has received the BEEFY finality proof.
+	async fn generate_relay_proof_when_ready(
+		relay: &TestRelay,
+		block_number: BlockNumber,
+	) -> Result<RelayProof<Hash>, Error> {
+		let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+		while std::time::Instant::now() < deadline {
+			match relay.generate_relay_proof(block_number) {
+				Err(Error::EndpointNotReady) => (),
+				result => return result,
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+		}
+
+		panic!("Deadline reached while waiting for the BEEFY finality proof to be received.");
+	}
+
+	fn expected_relay_proof(
+		commitment_block_hash: Hash,
+		best_hash: Hash,
+		finality_proof: BeefyVersionedFinalityProof<Block, ecdsa_crypto::AuthorityId>,
+	) -> RelayProof<Hash> {
+		let authority_proofs: Vec<_> = [1, 3]
+			.into_iter()
+			.map(|leaf_index| AuthorityMerkleProof::<MmrRootHash> {
+				leaf_index,
+				leaf: vec![leaf_index as u8],
+				proof: vec![],
+			})
+			.collect();
+
+		RelayProof {
+			commitment_block_hash,
+			finality_proof: finality_proof.encode().into(),
+			mmr_proof: LeavesProof::new(best_hash, test_leaves(), test_leaf_proof()),
+			authority_set: test_authority_set().encode().into(),
+			authority_proofs: authority_proofs.encode().into(),
+		}
+	}
+
+	#[tokio::test]
+	async fn relay_proof_requires_beefy_finality_proof() {
+		let (relay, finality_proof_sender) = setup_relay(substrate_test_runtime_client::new());
+
+		assert!(matches!(relay.generate_relay_proof(0), Err(Error::EndpointNotReady)));
+
+		let r: Result<(), ()> = finality_proof_sender.notify(|| Ok(finality_proof(5)));
+		r.unwrap();
+
+		// blocks above the commitment can't be proven
+		assert!(matches!(
+			generate_relay_proof_when_ready(&relay, 6).await,
+			Err(Error::BlockNotFinalized)
+		));
+	}
+
+	#[tokio::test]
+	async fn relay_proof_works() {
+		let client = substrate_test_runtime_client::new();
+		let genesis_hash = client.info().genesis_hash;
+		let (relay, finality_proof_sender) = setup_relay(client);
+
+		let r: Result<(), ()> = finality_proof_sender.notify(|| Ok(finality_proof(0)));
+		r.unwrap();
+
+		assert_eq!(
+			generate_relay_proof_when_ready(&relay, 0).await.unwrap(),
+			expected_relay_proof(genesis_hash, genesis_hash, finality_proof(0)),
+		);
+	}
+
+	#[tokio::test]
+	async fn relay_proof_falls_back_to_stored_justifications() {
+		let client = substrate_test_runtime_client::new();
+		let block = BlockBuilderBuilder::new(&client)
+			.on_parent_block(client.info().genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		let block_hash = block.hash();
+		let justifications = Justifications::from((BEEFY_ENGINE_ID, finality_proof(1).encode()));
+		client.import_justified(BlockOrigin::Own, block, justifications).await.unwrap();
+
+		let (relay, _finality_proof_sender) = setup_relay(client);
+
+		// the genesis block is proven with the justification of its descendant.
+		for block_number in [0, 1] {
+			assert_eq!(
+				relay.generate_relay_proof(block_number).unwrap(),
+				expected_relay_proof(block_hash, block_hash, finality_proof(1)),
+			);
+		}
+		assert!(matches!(relay.generate_relay_proof(2), Err(Error::EndpointNotReady)));
+	}
+
+	#[test]
+	fn signer_indices_works() {
+		assert_eq!(signer_indices(&finality_proof(5)), vec![1, 3]);
+	}
+}
@@ -19,9 +19,9 @@ use codec::Decode;
 use log::warn;
 
 use sp_application_crypto::{key_types::BEEFY as BEEFY_KEY_TYPE, AppCrypto, RuntimeAppPublic};
+use sp_core::ecdsa;
 #[cfg(feature = "bls-experimental")]
 use sp_core::ecdsa_bls381;
-use sp_core::{ecdsa, keccak_256};
 
 use sp_keystore::KeystorePtr;
 use std::marker::PhantomData;
@@ -82,18 +82,17 @@ impl<AuthorityId: AuthorityIdBound> BeefyKeystore<AuthorityId> {
 	) -> Result<<AuthorityId as RuntimeAppPublic>::Signature, error::Error> {
 		let store = self.0.clone().ok_or_else(|| error::Error::Keystore("no Keystore".into()))?;
 
-		// ECDSA should use ecdsa_sign_prehashed since it needs to be hashed by keccak_256 instead
-		// of blake2. As such we need to deal with producing the signatures case-by-case
+		// ECDSA should use ecdsa_sign_with_keccak256 since it needs to be hashed by keccak_256
+		// instead of blake2. As such we need to deal with producing the signatures case-by-case
 		let signature_byte_array: Vec<u8> = match <AuthorityId as AppCrypto>::CRYPTO_ID {
 			ecdsa::CRYPTO_ID => {
-				let msg_hash = keccak_256(message);
 				let public: ecdsa::Public = ecdsa::Public::try_from(public.as_slice()).unwrap();
 
 				let sig = store
-					.ecdsa_sign_prehashed(BEEFY_KEY_TYPE, &public, &msg_hash)
+					.ecdsa_sign_with_keccak256(BEEFY_KEY_TYPE, &public, message)
 					.map_err(|e| error::Error::Keystore(e.to_string()))?
 					.ok_or_else(|| {
-						error::Error::Signature("ecdsa_sign_prehashed() failed".to_string())
+						error::Error::Signature("ecdsa_sign_with_keccak256() failed".to_string())
 					})?;
 				let sig_ref: &[u8] = sig.as_ref();
 				sig_ref.to_vec()
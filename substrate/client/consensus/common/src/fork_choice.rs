@@ -0,0 +1,447 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Fork choice rules shared by the consensus engines.
+//!
+//! A [`ForkChoiceRule`] decides whether a block being imported should become the new best block,
+//! by comparing it to the current best block. Consensus engines which track a weight for each
+//! chain (e.g. the number of primary blocks in BABE or the total difficulty in PoW) pass it along
+//! with the blocks, other engines use `()` as weight and can apply a rule to their imports through
+//! a [`ForkChoiceBlockImport`].
+//!
+//! Rules can be combined with [`Then`], the second rule being used to break the ties of the first
+//! one.
+
+use futures::lock::Mutex;
+use sp_blockchain::{lowest_common_ancestor, HeaderBackend, HeaderMetadata};
+use sp_consensus::Error as ConsensusError;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use std::{cmp::Ordering, sync::Arc};
+
+use crate::{BlockCheckParams, BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult};
+
+/// A block compared by a [`ForkChoiceRule`].
+pub struct ForkChoiceBlock<'a, B: BlockT, W> {
+	/// The hash of the block.
+	pub hash: B::Hash,
+	/// The header of the block.
+	pub header: &'a B::Header,
+	/// The weight of the chain ending at the block, as tracked by the consensus engine.
+	pub weight: W,
+}
+
+impl<'a, B: BlockT, W> ForkChoiceBlock<'a, B, W> {
+	/// The number of the block.
+	pub fn number(&self) -> NumberFor<B> {
+		*self.header.number()
+	}
+}
+
+/// A fork choice rule, comparing the chains ending at two blocks.
+pub trait ForkChoiceRule<B: BlockT, W>: Send + Sync {
+	/// Compare the chain ending at the block being imported to the chain ending at the current
+	/// best block. The imported block becomes the new best block if its chain is greater.
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError>;
+
+	/// Whether the block being imported should become the new best block.
+	fn is_new_best(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<bool, ConsensusError> {
+		Ok(self.compare(candidate, best)? == Ordering::Greater)
+	}
+}
+
+impl<B: BlockT, W, R: ForkChoiceRule<B, W> + ?Sized> ForkChoiceRule<B, W> for Arc<R> {
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		(**self).compare(candidate, best)
+	}
+}
+
+/// Prefer the longest chain.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LongestChainRule;
+
+impl<B: BlockT, W> ForkChoiceRule<B, W> for LongestChainRule {
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		Ok(candidate.number().cmp(&best.number()))
+	}
+}
+
+/// Prefer the heaviest chain, according to the weight tracked by the consensus engine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeaviestChainRule;
+
+impl<B: BlockT, W: Ord> ForkChoiceRule<B, W> for HeaviestChainRule {
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		Ok(candidate.weight.cmp(&best.weight))
+	}
+}
+
+/// Prefer chains containing a given block, e.g. a checkpoint.
+pub struct ContainsBlockRule<B: BlockT, Client> {
+	client: Arc<Client>,
+	hash: B::Hash,
+	number: NumberFor<B>,
+}
+
+impl<B: BlockT, Client> ContainsBlockRule<B, Client> {
+	/// Create a new rule preferring the chains containing the given block.
+	pub fn new(client: Arc<Client>, hash: B::Hash, number: NumberFor<B>) -> Self {
+		ContainsBlockRule { client, hash, number }
+	}
+}
+
+impl<B, Client> ContainsBlockRule<B, Client>
+where
+	B: BlockT,
+	Client: HeaderMetadata<B, Error = sp_blockchain::Error>,
+{
+	fn contains<W>(&self, block: &ForkChoiceBlock<B, W>) -> Result<bool, ConsensusError> {
+		match block.number().cmp(&self.number) {
+			Ordering::Less => return Ok(false),
+			Ordering::Equal => return Ok(block.hash == self.hash),
+			Ordering::Greater => {},
+		}
+
+		// the preferred block may not be imported yet
+		if self.client.header_metadata(self.hash).is_err() {
+			return Ok(false)
+		}
+
+		// the block itself may not be imported yet, so start from its parent
+		let ancestor =
+			lowest_common_ancestor(&*self.client, *block.header.parent_hash(), self.hash)
+				.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?;
+
+		Ok(ancestor.hash == self.hash)
+	}
+}
+
+impl<B, W, Client> ForkChoiceRule<B, W> for ContainsBlockRule<B, Client>
+where
+	B: BlockT,
+	Client: HeaderMetadata<B, Error = sp_blockchain::Error> + Send + Sync,
+{
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		Ok(self.contains(candidate)?.cmp(&self.contains(best)?))
+	}
+}
+
+/// Prefer chains whose head was authored by a preferred set of authors.
+///
+/// The author of a block is specific to the consensus engine, the given function tells whether a
+/// header was authored by a preferred author.
+pub struct PreferredAuthorsRule<F>(F);
+
+impl<F> PreferredAuthorsRule<F> {
+	/// Create a new rule preferring the blocks for which `is_preferred` returns `true`.
+	pub fn new(is_preferred: F) -> Self {
+		PreferredAuthorsRule(is_preferred)
+	}
+}
+
+impl<B, W, F> ForkChoiceRule<B, W> for PreferredAuthorsRule<F>
+where
+	B: BlockT,
+	F: Fn(&B::Header) -> bool + Send + Sync,
+{
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		Ok((self.0)(candidate.header).cmp(&(self.0)(best.header)))
+	}
+}
+
+/// A rule using another rule to break its ties.
+#[derive(Debug, Clone, Copy)]
+pub struct Then<A, R> {
+	first: A,
+	tiebreak: R,
+}
+
+impl<A, R> Then<A, R> {
+	/// Combine the `first` rule with the `tiebreak` rule, used when `first` can't decide.
+	pub fn new(first: A, tiebreak: R) -> Self {
+		Then { first, tiebreak }
+	}
+}
+
+impl<B, W, A, R> ForkChoiceRule<B, W> for Then<A, R>
+where
+	B: BlockT,
+	A: ForkChoiceRule<B, W>,
+	R: ForkChoiceRule<B, W>,
+{
+	fn compare(
+		&self,
+		candidate: &ForkChoiceBlock<B, W>,
+		best: &ForkChoiceBlock<B, W>,
+	) -> Result<Ordering, ConsensusError> {
+		match self.first.compare(candidate, best)? {
+			Ordering::Equal => self.tiebreak.compare(candidate, best),
+			ordering => Ok(ordering),
+		}
+	}
+}
+
+/// Block import applying a fork choice rule to the blocks imported with the
+/// [`ForkChoiceStrategy::LongestChain`] strategy, for consensus engines that don't track a chain
+/// weight. Blocks imported with a custom fork choice are passed through unchanged.
+///
+/// The imports of the block import and its clones are serialized, so that the best block can't
+/// change between the evaluation of the rule and the import of the block. All the imports of the
+/// node should hence go through it.
+pub struct ForkChoiceBlockImport<B: BlockT, I, Client> {
+	inner: I,
+	client: Arc<Client>,
+	rule: Arc<dyn ForkChoiceRule<B, ()>>,
+	import_lock: Arc<Mutex<()>>,
+}
+
+impl<B: BlockT, I: Clone, Client> Clone for ForkChoiceBlockImport<B, I, Client> {
+	fn clone(&self) -> Self {
+		ForkChoiceBlockImport {
+			inner: self.inner.clone(),
+			client: self.client.clone(),
+			rule: self.rule.clone(),
+			import_lock: self.import_lock.clone(),
+		}
+	}
+}
+
+impl<B: BlockT, I, Client> ForkChoiceBlockImport<B, I, Client> {
+	/// Wrap the given block import, applying the given fork choice rule.
+	pub fn new(inner: I, client: Arc<Client>, rule: Arc<dyn ForkChoiceRule<B, ()>>) -> Self {
+		ForkChoiceBlockImport { inner, client, rule, import_lock: Arc::new(Mutex::new(())) }
+	}
+}
+
+impl<B, I, Client> ForkChoiceBlockImport<B, I, Client>
+where
+	B: BlockT,
+	Client: HeaderBackend<B>,
+{
+	// whether the block should become the new best block according to the rule
+	fn is_new_best(&self, block: &BlockImportParams<B>) -> Result<bool, ConsensusError> {
+		let best_hash = self.client.info().best_hash;
+		let best_header = self
+			.client
+			.header(best_hash)
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.ok_or_else(|| {
+				ConsensusError::ChainLookup(format!("Missing header for best block {}", best_hash))
+			})?;
+
+		let candidate =
+			ForkChoiceBlock { hash: block.post_hash(), header: &block.header, weight: () };
+		let best = ForkChoiceBlock { hash: best_hash, header: &best_header, weight: () };
+
+		self.rule.is_new_best(&candidate, &best)
+	}
+}
+
+#[async_trait::async_trait]
+impl<B, I, Client> BlockImport<B> for ForkChoiceBlockImport<B, I, Client>
+where
+	B: BlockT,
+	I: BlockImport<B> + Send + Sync,
+	I::Error: Into<ConsensusError>,
+	Client: HeaderBackend<B> + Send + Sync,
+{
+	type Error = ConsensusError;
+
+	async fn check_block(&self, block: BlockCheckParams<B>) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block).await.map_err(Into::into)
+	}
+
+	async fn import_block(
+		&self,
+		mut block: BlockImportParams<B>,
+	) -> Result<ImportResult, Self::Error> {
+		let _import_guard = self.import_lock.lock().await;
+
+		if block.fork_choice == Some(ForkChoiceStrategy::LongestChain) {
+			block.fork_choice = Some(ForkChoiceStrategy::Custom(self.is_new_best(&block)?));
+		}
+
+		self.inner.import_block(block).await.map_err(Into::into)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_blockchain::CachedHeaderMetadata;
+	use sp_runtime::traits::Hash;
+	use sp_test_primitives::{Block, Header};
+	use std::collections::HashMap;
+
+	struct TestChain(HashMap<<Block as BlockT>::Hash, Header>);
+
+	impl TestChain {
+		fn new(genesis: &Header) -> Self {
+			TestChain([(genesis.hash(), genesis.clone())].into_iter().collect())
+		}
+
+		// build a chain of `length` blocks on top of `parent`, returning their headers
+		fn extend(&mut self, parent: &Header, length: u64, fork: u8) -> Vec<Header> {
+			let mut headers = Vec::new();
+			let mut parent = parent.clone();
+			for _ in 0..length {
+				let header = Header::new(
+					parent.number + 1,
+					<Header as HeaderT>::Hashing::hash(&[fork]),
+					Default::default(),
+					parent.hash(),
+					Default::default(),
+				);
+				self.0.insert(header.hash(), header.clone());
+				headers.push(header.clone());
+				parent = header;
+			}
+			headers
+		}
+	}
+
+	impl HeaderMetadata<Block> for TestChain {
+		type Error = sp_blockchain::Error;
+
+		fn header_metadata(
+			&self,
+			hash: <Block as BlockT>::Hash,
+		) -> Result<CachedHeaderMetadata<Block>, Self::Error> {
+			self.0
+				.get(&hash)
+				.map(CachedHeaderMetadata::from)
+				.ok_or_else(|| sp_blockchain::Error::UnknownBlock(hash.to_string()))
+		}
+
+		fn insert_header_metadata(
+			&self,
+			_hash: <Block as BlockT>::Hash,
+			_metadata: CachedHeaderMetadata<Block>,
+		) {
+		}
+
+		fn remove_header_metadata(&self, _hash: <Block as BlockT>::Hash) {}
+	}
+
+	fn block<W>(header: &Header, weight: W) -> ForkChoiceBlock<Block, W> {
+		ForkChoiceBlock { hash: header.hash(), header, weight }
+	}
+
+	fn genesis() -> Header {
+		Header::new(
+			0,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	#[test]
+	fn heaviest_chain_breaks_ties_with_longest_chain() {
+		let genesis = genesis();
+		let mut chain = TestChain::new(&genesis);
+		let short = chain.extend(&genesis, 2, 0);
+		let long = chain.extend(&genesis, 3, 1);
+		let (short, long) = (short.last().unwrap(), long.last().unwrap());
+
+		let rule = Then::new(HeaviestChainRule, LongestChainRule);
+
+		assert!(rule.is_new_best(&block(short, 2u32), &block(long, 1)).unwrap());
+		assert!(!rule.is_new_best(&block(short, 1u32), &block(long, 2)).unwrap());
+		assert!(rule.is_new_best(&block(long, 1u32), &block(short, 1)).unwrap());
+		assert!(!rule.is_new_best(&block(short, 1u32), &block(long, 1)).unwrap());
+		assert!(!rule.is_new_best(&block(long, 1u32), &block(long, 1)).unwrap());
+	}
+
+	#[test]
+	fn chains_containing_block_are_preferred() {
+		let genesis = genesis();
+		let mut chain = TestChain::new(&genesis);
+		let checkpointed = chain.extend(&genesis, 3, 0);
+		let other = chain.extend(&genesis, 5, 1);
+		let checkpoint = &checkpointed[1];
+
+		let chain = Arc::new(chain);
+		let rule = Then::new(
+			ContainsBlockRule::new(chain.clone(), checkpoint.hash(), checkpoint.number),
+			LongestChainRule,
+		);
+
+		// a longer chain not containing the checkpoint isn't preferred
+		assert!(!rule.is_new_best(&block(&other[4], ()), &block(&checkpointed[2], ())).unwrap());
+		assert!(rule.is_new_best(&block(&checkpointed[2], ()), &block(&other[4], ())).unwrap());
+		assert!(rule.is_new_best(&block(checkpoint, ()), &block(&other[4], ())).unwrap());
+		assert!(!rule.is_new_best(&block(&checkpointed[0], ()), &block(&other[0], ())).unwrap());
+
+		// chains which don't contain the checkpoint fall back to the longest chain
+		assert!(rule.is_new_best(&block(&other[2], ()), &block(&other[1], ())).unwrap());
+
+		// an unknown checkpoint isn't contained by any chain
+		let rule =
+			Then::new(ContainsBlockRule::new(chain, Default::default(), 1), LongestChainRule);
+		assert!(rule.is_new_best(&block(&other[4], ()), &block(&checkpointed[2], ())).unwrap());
+	}
+
+	#[test]
+	fn preferred_authors_are_preferred() {
+		let genesis = genesis();
+		let mut chain = TestChain::new(&genesis);
+		let preferred = chain.extend(&genesis, 2, 0);
+		let other = chain.extend(&genesis, 3, 1);
+
+		let preferred_extrinsics_root = preferred[0].extrinsics_root;
+		let rule = Then::new(
+			PreferredAuthorsRule::new(move |header: &Header| {
+				header.extrinsics_root == preferred_extrinsics_root
+			}),
+			LongestChainRule,
+		);
+
+		assert!(rule.is_new_best(&block(&preferred[1], ()), &block(&other[2], ())).unwrap());
+		assert!(!rule.is_new_best(&block(&other[2], ()), &block(&preferred[1], ())).unwrap());
+		assert!(rule.is_new_best(&block(&other[2], ()), &block(&other[1], ())).unwrap());
+	}
+}
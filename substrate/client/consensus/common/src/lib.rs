@@ -19,6 +19,7 @@
 //! Collection of common consensus specific implementations
 
 pub mod block_import;
+pub mod fork_choice;
 pub mod import_queue;
 pub mod metrics;
 
@@ -27,6 +28,10 @@ pub use block_import::{
 	ImportedAux, ImportedState, JustificationImport, JustificationSyncLink, StateAction,
 	StorageChanges,
 };
+pub use fork_choice::{
+	ContainsBlockRule, ForkChoiceBlock, ForkChoiceBlockImport, ForkChoiceRule, HeaviestChainRule,
+	LongestChainRule, PreferredAuthorsRule, Then,
+};
 pub use import_queue::{
 	import_single_block, BasicQueue, BlockImportError, BlockImportStatus, BoxBlockImport,
 	BoxJustificationImport, DefaultImportQueue, ImportQueue, IncomingBlock, Link, Verifier,
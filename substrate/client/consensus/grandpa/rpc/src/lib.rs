@@ -36,8 +36,11 @@ mod report;
 use error::Error;
 use finality::{EncodedFinalityProof, RpcFinalityProofProvider};
 use notification::JustificationNotification;
-use report::{ReportAuthoritySet, ReportVoterState, ReportedRoundStates};
-use sc_consensus_grandpa::GrandpaJustificationStream;
+use report::{
+	ReportAuthoritySet, ReportVoterState, ReportedFinalityStall, ReportedRoundStates,
+	ReportedRoundVotes,
+};
+use sc_consensus_grandpa::{GrandpaJustificationStream, SharedFinalityStallStatus};
 use sc_rpc::{
 	utils::{BoundedVecDeque, PendingSubscription},
 	SubscriptionTaskExecutor,
@@ -52,6 +55,15 @@ pub trait GrandpaApi<Notification, Hash, Number> {
 	#[method(name = "grandpa_roundState")]
 	async fn round_state(&self) -> Result<ReportedRoundStates, Error>;
 
+	/// Returns the votes seen from each voter of the current authority set in the current best
+	/// round, as well as in the ongoing background rounds.
+	#[method(name = "grandpa_roundVotes")]
+	async fn round_votes(&self) -> Result<ReportedRoundVotes, Error>;
+
+	/// Returns the diagnosis of the current finality stall, or `None` if finality isn't stalled.
+	#[method(name = "grandpa_finalityStall")]
+	async fn finality_stall(&self) -> Result<Option<ReportedFinalityStall<Hash, Number>>, Error>;
+
 	/// Returns the block most recently finalized by Grandpa, alongside
 	/// side its justification.
 	#[subscription(
@@ -74,6 +86,7 @@ pub struct Grandpa<AuthoritySet, VoterState, Block: BlockT, ProofProvider> {
 	voter_state: VoterState,
 	justification_stream: GrandpaJustificationStream<Block>,
 	finality_proof_provider: Arc<ProofProvider>,
+	finality_stall_status: SharedFinalityStallStatus<Block>,
 }
 impl<AuthoritySet, VoterState, Block: BlockT, ProofProvider>
 	Grandpa<AuthoritySet, VoterState, Block, ProofProvider>
@@ -85,8 +98,16 @@ impl<AuthoritySet, VoterState, Block: BlockT, ProofProvider>
 		voter_state: VoterState,
 		justification_stream: GrandpaJustificationStream<Block>,
 		finality_proof_provider: Arc<ProofProvider>,
+		finality_stall_status: SharedFinalityStallStatus<Block>,
 	) -> Self {
-		Self { executor, authority_set, voter_state, justification_stream, finality_proof_provider }
+		Self {
+			executor,
+			authority_set,
+			voter_state,
+			justification_stream,
+			finality_proof_provider,
+			finality_stall_status,
+		}
 	}
 }
 
@@ -104,6 +125,16 @@ where
 		ReportedRoundStates::from(&self.authority_set, &self.voter_state)
 	}
 
+	async fn round_votes(&self) -> Result<ReportedRoundVotes, Error> {
+		ReportedRoundVotes::from(&self.authority_set, &self.voter_state)
+	}
+
+	async fn finality_stall(
+		&self,
+	) -> Result<Option<ReportedFinalityStall<Block::Hash, NumberFor<Block>>>, Error> {
+		self.finality_stall_status.current().map(TryInto::try_into).transpose()
+	}
+
 	fn subscribe_justifications(&self, pending: PendingSubscriptionSink) {
 		let stream = self.justification_stream.subscribe(100_000).map(
 			|x: sc_consensus_grandpa::GrandpaJustification<Block>| {
@@ -266,6 +297,7 @@ mod tests {
 			voter_state,
 			justification_stream,
 			finality_proof_provider,
+			SharedFinalityStallStatus::empty(),
 		)
 		.into_rpc();
 
@@ -282,6 +314,36 @@ mod tests {
 		assert_eq!(expected_response, response);
 	}
 
+	#[tokio::test]
+	async fn round_votes_rpc_handler() {
+		let (rpc, _) = setup_io_handler(TestVoterState);
+		let expected_response = "{\"jsonrpc\":\"2.0\",\"id\":0,\"result\":{\
+			\"setId\":1,\
+			\"best\":{\"round\":2,\"votes\":[\
+				{\"id\":\"5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT\",\"prevoted\":true,\"precommitted\":false},\
+				{\"id\":\"5C7LYpP2ZH3tpKbvVvwiVe54AapxErdPBbvkYhe6y9ZBkqWt\",\"prevoted\":false,\"precommitted\":false}\
+			]},\
+			\"background\":[{\"round\":1,\"votes\":[\
+				{\"id\":\"5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT\",\"prevoted\":true,\"precommitted\":true},\
+				{\"id\":\"5C7LYpP2ZH3tpKbvVvwiVe54AapxErdPBbvkYhe6y9ZBkqWt\",\"prevoted\":true,\"precommitted\":true}\
+			]}]\
+		}}".to_string();
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_roundVotes","params":[],"id":0}"#;
+		let (response, _) = rpc.raw_json_request(&request, 1).await.unwrap();
+		assert_eq!(expected_response, response);
+	}
+
+	#[tokio::test]
+	async fn finality_stall_rpc_handler() {
+		let (rpc, _) = setup_io_handler(TestVoterState);
+		let expected_response = r#"{"jsonrpc":"2.0","id":0,"result":null}"#;
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_finalityStall","params":[],"id":0}"#;
+		let (response, _) = rpc.raw_json_request(&request, 1).await.unwrap();
+		assert_eq!(expected_response, response);
+	}
+
 	#[tokio::test]
 	async fn working_rpc_handler() {
 		let (rpc, _) = setup_io_handler(TestVoterState);
@@ -24,7 +24,10 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use sc_consensus_grandpa::{report, AuthorityId, SharedAuthoritySet, SharedVoterState};
+use sc_consensus_grandpa::{
+	report, AuthorityId, FinalityStallDiagnosis, SharedAuthoritySet, SharedVoterState,
+};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 use crate::error::Error;
 
@@ -148,3 +151,128 @@ impl ReportedRoundStates {
 		Ok(Self { set_id, best, background })
 	}
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VoterVotes {
+	id: AuthorityId,
+	prevoted: bool,
+	precommitted: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoundVotes {
+	round: u32,
+	votes: Vec<VoterVotes>,
+}
+
+impl RoundVotes {
+	fn from(
+		round: u64,
+		round_state: &report::RoundState<AuthorityId>,
+		voters: &BTreeSet<AuthorityId>,
+	) -> Result<Self, Error> {
+		sc_consensus_grandpa::RoundVotes::from_round_state(round, round_state, voters).try_into()
+	}
+}
+
+impl TryFrom<sc_consensus_grandpa::RoundVotes> for RoundVotes {
+	type Error = Error;
+
+	fn try_from(round_votes: sc_consensus_grandpa::RoundVotes) -> Result<Self, Error> {
+		Ok(Self {
+			round: round_votes.round.try_into()?,
+			votes: round_votes
+				.votes
+				.into_iter()
+				.map(|votes| VoterVotes {
+					id: votes.id,
+					prevoted: votes.prevoted,
+					precommitted: votes.precommitted,
+				})
+				.collect(),
+		})
+	}
+}
+
+/// The votes seen from each voter of the current set in the current best round, as well as in
+/// the background rounds, in a form suitable for serialization.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedRoundVotes {
+	set_id: u32,
+	best: RoundVotes,
+	background: Vec<RoundVotes>,
+}
+
+impl ReportedRoundVotes {
+	pub fn from<AuthoritySet, VoterState>(
+		authority_set: &AuthoritySet,
+		voter_state: &VoterState,
+	) -> Result<Self, Error>
+	where
+		AuthoritySet: ReportAuthoritySet,
+		VoterState: ReportVoterState,
+	{
+		let voter_state = voter_state.get().ok_or(Error::EndpointNotReady)?;
+
+		let (set_id, current_voters) = authority_set.get();
+		let set_id =
+			u32::try_from(set_id).map_err(|_| Error::AuthoritySetIdReportedAsUnreasonablyLarge)?;
+		let current_voters: BTreeSet<_> = current_voters.into_iter().collect();
+
+		let best = {
+			let (round, round_state) = &voter_state.best_round;
+			RoundVotes::from(*round, round_state, &current_voters)?
+		};
+
+		let background = voter_state
+			.background_rounds
+			.iter()
+			.map(|(round, round_state)| RoundVotes::from(*round, round_state, &current_voters))
+			.collect::<Result<Vec<_>, Error>>()?;
+
+		Ok(Self { set_id, best, background })
+	}
+}
+
+/// The diagnosis of the current finality stall in a form suitable for serialization.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedFinalityStall<Hash, Number> {
+	timestamp: u64,
+	stalled_for: u64,
+	finalized_hash: Hash,
+	finalized_number: Number,
+	best_hash: Hash,
+	best_number: Number,
+	set_id: u32,
+	best: Option<RoundVotes>,
+	background: Vec<RoundVotes>,
+}
+
+impl<Block: BlockT> TryFrom<FinalityStallDiagnosis<Block>>
+	for ReportedFinalityStall<Block::Hash, NumberFor<Block>>
+{
+	type Error = Error;
+
+	fn try_from(diagnosis: FinalityStallDiagnosis<Block>) -> Result<Self, Error> {
+		Ok(Self {
+			timestamp: diagnosis.timestamp,
+			stalled_for: diagnosis.stalled_for,
+			finalized_hash: diagnosis.finalized.0,
+			finalized_number: diagnosis.finalized.1,
+			best_hash: diagnosis.best.0,
+			best_number: diagnosis.best.1,
+			set_id: u32::try_from(diagnosis.set_id)
+				.map_err(|_| Error::AuthoritySetIdReportedAsUnreasonablyLarge)?,
+			best: diagnosis.best_round.map(TryInto::try_into).transpose()?,
+			background: diagnosis
+				.background_rounds
+				.into_iter()
+				.map(TryInto::try_into)
+				.collect::<Result<Vec<_>, Error>>()?,
+		})
+	}
+}
@@ -38,6 +38,7 @@ use crate::{
 		CompletedRound, CompletedRounds, CurrentRounds, HasVoted, SharedVoterSetState,
 		VoterSetState,
 	},
+	stall::FinalityStallDiagnosis,
 	GrandpaJustification, NewAuthoritySet, LOG_TARGET,
 };
 
@@ -46,6 +47,7 @@ const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
 const CONCLUDED_ROUNDS: &[u8] = b"grandpa_concluded_rounds";
 const AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
 const BEST_JUSTIFICATION: &[u8] = b"grandpa_best_justification";
+const STALL_DIAGNOSIS_KEY: &[u8] = b"grandpa_stall_diagnosis";
 
 const CURRENT_VERSION: u32 = 3;
 
@@ -471,6 +473,26 @@ where
 	load_decode::<_, GrandpaJustification<Block>>(backend, BEST_JUSTIFICATION)
 }
 
+/// Write the diagnosis of the latest finality stall.
+pub(crate) fn write_stall_diagnosis<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	diagnosis: &FinalityStallDiagnosis<Block>,
+) -> ClientResult<()> {
+	backend.insert_aux(&[(STALL_DIAGNOSIS_KEY, diagnosis.encode().as_slice())], &[])
+}
+
+/// Fetch the diagnosis of the latest finality stall detected by the
+/// [`run_finality_stall_detector`](crate::run_finality_stall_detector) task, if any.
+pub fn finality_stall_diagnosis<B, Block>(
+	backend: &B,
+) -> ClientResult<Option<FinalityStallDiagnosis<Block>>>
+where
+	B: AuxStore,
+	Block: BlockT,
+{
+	load_decode::<_, FinalityStallDiagnosis<Block>>(backend, STALL_DIAGNOSIS_KEY)
+}
+
 /// Write voter set state.
 pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	backend: &B,
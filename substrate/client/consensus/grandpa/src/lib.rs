@@ -122,12 +122,13 @@ mod import;
 mod justification;
 mod notification;
 mod observer;
+mod stall;
 mod until_imported;
 mod voting_rule;
 pub mod warp_proof;
 
 pub use authorities::{AuthoritySet, AuthoritySetChanges, SharedAuthoritySet};
-pub use aux_schema::best_justification;
+pub use aux_schema::{best_justification, finality_stall_diagnosis};
 pub use communication::grandpa_protocol_name::standard_name as protocol_standard_name;
 pub use finality_grandpa::voter::report;
 pub use finality_proof::{FinalityProof, FinalityProofError, FinalityProofProvider};
@@ -135,9 +136,13 @@ pub use import::{find_forced_change, find_scheduled_change, GrandpaBlockImport};
 pub use justification::GrandpaJustification;
 pub use notification::{GrandpaJustificationSender, GrandpaJustificationStream};
 pub use observer::run_grandpa_observer;
+pub use stall::{
+	run_finality_stall_detector, FinalityStallConfig, FinalityStallDiagnosis, RoundVotes,
+	SharedFinalityStallStatus, VoterVotes,
+};
 pub use voting_rule::{
-	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRuleResult,
-	VotingRulesBuilder,
+	BeforeBestBlockBy, MinimumBlockAge, RuntimeFinalityDelay, RuntimeUpgradeConfirmations,
+	ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRuleResult, VotingRulesBuilder,
 };
 
 use aux_schema::PersistentData;
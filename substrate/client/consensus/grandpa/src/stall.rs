@@ -0,0 +1,450 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Detection of GRANDPA finality stalls.
+//!
+//! The [`run_finality_stall_detector`] task periodically compares the best and finalized blocks
+//! of the chain. Once finality hasn't advanced for too long, or the best chain has grown too far
+//! ahead of it, the stall is reported through logs and Prometheus metrics, and a diagnosis of the
+//! voting rounds, listing the votes seen from each voter of the current set, is persisted in the
+//! aux-db and exposed through a [`SharedFinalityStallStatus`].
+
+use std::{
+	sync::Arc,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use codec::{Decode, Encode};
+use log::{debug, info, warn};
+use parking_lot::RwLock;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+
+use sc_client_api::backend::AuxStore;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_grandpa::{AuthorityId, RoundNumber, SetId};
+use sp_runtime::{
+	traits::{Block as BlockT, NumberFor, Saturating, UniqueSaturatedInto},
+	SaturatedConversion,
+};
+
+use crate::{aux_schema, report, SharedAuthoritySet, SharedVoterState, LOG_TARGET};
+
+/// Configuration of the finality stall detection.
+#[derive(Debug, Clone)]
+pub struct FinalityStallConfig {
+	/// Finality is considered stalled once the best block is this many blocks ahead of the last
+	/// finalized block.
+	pub max_unfinalized_blocks: u32,
+	/// Finality is considered stalled once the finalized block hasn't advanced for this long.
+	pub max_stall_duration: Duration,
+	/// Interval at which the chain is checked for stalls.
+	pub check_interval: Duration,
+}
+
+impl Default for FinalityStallConfig {
+	fn default() -> Self {
+		FinalityStallConfig {
+			max_unfinalized_blocks: 256,
+			max_stall_duration: Duration::from_secs(10 * 60),
+			check_interval: Duration::from_secs(12),
+		}
+	}
+}
+
+/// The votes seen from a voter in a round.
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq)]
+pub struct VoterVotes {
+	/// The voter.
+	pub id: AuthorityId,
+	/// Whether a prevote from the voter has been seen.
+	pub prevoted: bool,
+	/// Whether a precommit from the voter has been seen.
+	pub precommitted: bool,
+}
+
+/// The votes seen from each voter of the current set in a round.
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq)]
+pub struct RoundVotes {
+	/// The round number.
+	pub round: RoundNumber,
+	/// The votes of each voter.
+	pub votes: Vec<VoterVotes>,
+}
+
+impl RoundVotes {
+	/// Build the votes of the given voters from the state of a round.
+	pub fn from_round_state<'a>(
+		round: RoundNumber,
+		round_state: &report::RoundState<AuthorityId>,
+		voters: impl IntoIterator<Item = &'a AuthorityId>,
+	) -> Self {
+		let votes = voters
+			.into_iter()
+			.map(|id| VoterVotes {
+				id: id.clone(),
+				prevoted: round_state.prevote_ids.contains(id),
+				precommitted: round_state.precommit_ids.contains(id),
+			})
+			.collect();
+
+		RoundVotes { round, votes }
+	}
+
+	/// The voters which haven't prevoted in the round.
+	pub fn missing_prevotes(&self) -> impl Iterator<Item = &AuthorityId> {
+		self.votes.iter().filter(|votes| !votes.prevoted).map(|votes| &votes.id)
+	}
+
+	/// The voters which haven't precommitted in the round.
+	pub fn missing_precommits(&self) -> impl Iterator<Item = &AuthorityId> {
+		self.votes.iter().filter(|votes| !votes.precommitted).map(|votes| &votes.id)
+	}
+}
+
+/// Diagnosis of a finality stall.
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+pub struct FinalityStallDiagnosis<Block: BlockT> {
+	/// Unix timestamp in milliseconds at which the diagnosis was made.
+	pub timestamp: u64,
+	/// For how long, in seconds, the finalized block hasn't advanced.
+	pub stalled_for: u64,
+	/// The last finalized block.
+	pub finalized: (Block::Hash, NumberFor<Block>),
+	/// The best block.
+	pub best: (Block::Hash, NumberFor<Block>),
+	/// The id of the current authority set.
+	pub set_id: SetId,
+	/// The votes of the best round, if the voter is running.
+	pub best_round: Option<RoundVotes>,
+	/// The votes of the background rounds.
+	pub background_rounds: Vec<RoundVotes>,
+}
+
+/// The current finality stall, if any, shared for querying.
+pub struct SharedFinalityStallStatus<Block: BlockT> {
+	inner: Arc<RwLock<Option<FinalityStallDiagnosis<Block>>>>,
+}
+
+impl<Block: BlockT> SharedFinalityStallStatus<Block> {
+	/// Create a new `SharedFinalityStallStatus` instance, with finality not stalled.
+	pub fn empty() -> Self {
+		SharedFinalityStallStatus { inner: Arc::new(RwLock::new(None)) }
+	}
+
+	/// Get the diagnosis of the current finality stall, or `None` if finality isn't stalled.
+	pub fn current(&self) -> Option<FinalityStallDiagnosis<Block>> {
+		self.inner.read().clone()
+	}
+
+	fn set(&self, diagnosis: Option<FinalityStallDiagnosis<Block>>) {
+		*self.inner.write() = diagnosis;
+	}
+}
+
+impl<Block: BlockT> Clone for SharedFinalityStallStatus<Block> {
+	fn clone(&self) -> Self {
+		SharedFinalityStallStatus { inner: self.inner.clone() }
+	}
+}
+
+struct Metrics {
+	stalled: Gauge<U64>,
+	unfinalized_blocks: Gauge<U64>,
+	stalls: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			stalled: register(
+				Gauge::new(
+					"substrate_finality_grandpa_stalled",
+					"Whether GRANDPA finality is currently considered stalled.",
+				)?,
+				registry,
+			)?,
+			unfinalized_blocks: register(
+				Gauge::new(
+					"substrate_finality_grandpa_unfinalized_blocks",
+					"Number of blocks between the best and the last finalized block.",
+				)?,
+				registry,
+			)?,
+			stalls: register(
+				Counter::new(
+					"substrate_finality_grandpa_stalls_total",
+					"Total number of GRANDPA finality stalls detected.",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Outcome of a stall check.
+#[derive(Debug, PartialEq)]
+enum StallCheck {
+	/// Finality is advancing.
+	Progressing,
+	/// Finality is stalled, `new` tells whether the stall has just been detected.
+	Stalled { new: bool, stalled_for: Duration },
+	/// Finality has advanced after a stall.
+	Recovered,
+}
+
+/// Tracks the progress of finality across checks.
+struct StallTracker<N> {
+	max_unfinalized_blocks: N,
+	max_stall_duration: Duration,
+	last_finalized: Option<N>,
+	last_progress: Instant,
+	stalled: bool,
+}
+
+impl<N: Copy + Ord + Saturating> StallTracker<N> {
+	fn new(max_unfinalized_blocks: N, max_stall_duration: Duration, now: Instant) -> Self {
+		StallTracker {
+			max_unfinalized_blocks,
+			max_stall_duration,
+			last_finalized: None,
+			last_progress: now,
+			stalled: false,
+		}
+	}
+
+	fn check(&mut self, now: Instant, best: N, finalized: N) -> StallCheck {
+		if self.last_finalized < Some(finalized) {
+			self.last_finalized = Some(finalized);
+			self.last_progress = now;
+
+			if std::mem::take(&mut self.stalled) {
+				return StallCheck::Recovered
+			}
+		}
+
+		let stalled_for = now.saturating_duration_since(self.last_progress);
+		if best.saturating_sub(finalized) < self.max_unfinalized_blocks &&
+			stalled_for < self.max_stall_duration
+		{
+			return StallCheck::Progressing
+		}
+
+		let new = !std::mem::replace(&mut self.stalled, true);
+		StallCheck::Stalled { new, stalled_for }
+	}
+}
+
+fn diagnose<Block: BlockT>(
+	info: &sp_blockchain::Info<Block>,
+	stalled_for: Duration,
+	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	voter_state: &SharedVoterState,
+) -> FinalityStallDiagnosis<Block> {
+	let voters = authority_set.current_authorities();
+	let voters = voters.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+	let voter_state = voter_state.voter_state();
+
+	let best_round = voter_state.as_ref().map(|voter_state| {
+		let (round, round_state) = &voter_state.best_round;
+		RoundVotes::from_round_state(*round, round_state, &voters)
+	});
+	let background_rounds = voter_state
+		.iter()
+		.flat_map(|voter_state| voter_state.background_rounds.iter())
+		.map(|(round, round_state)| RoundVotes::from_round_state(*round, round_state, &voters))
+		.collect();
+
+	let timestamp = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|now| now.as_millis().saturated_into())
+		.unwrap_or_default();
+
+	FinalityStallDiagnosis {
+		timestamp,
+		stalled_for: stalled_for.as_secs(),
+		finalized: (info.finalized_hash, info.finalized_number),
+		best: (info.best_hash, info.best_number),
+		set_id: authority_set.set_id(),
+		best_round,
+		background_rounds,
+	}
+}
+
+fn format_voters<'a>(voters: impl Iterator<Item = &'a AuthorityId>) -> String {
+	voters.map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Run the finality stall detector.
+///
+/// While finality is stalled, the diagnosis of the stall is updated on every check, written to
+/// the aux-db (see [`finality_stall_diagnosis`](crate::finality_stall_diagnosis)) and made
+/// available through the given `status`. The last diagnosis is kept in the aux-db once finality
+/// recovers.
+pub async fn run_finality_stall_detector<Block, Client>(
+	client: Arc<Client>,
+	authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	voter_state: SharedVoterState,
+	status: SharedFinalityStallStatus<Block>,
+	config: FinalityStallConfig,
+	prometheus_registry: Option<Registry>,
+) where
+	Block: BlockT,
+	Client: HeaderBackend<Block> + AuxStore,
+{
+	let metrics = prometheus_registry.as_ref().and_then(|registry| {
+		Metrics::register(registry)
+			.map_err(|err| debug!(target: LOG_TARGET, "Failed to register metrics: {}", err))
+			.ok()
+	});
+
+	let mut tracker = StallTracker::new(
+		config.max_unfinalized_blocks.into(),
+		config.max_stall_duration,
+		Instant::now(),
+	);
+
+	loop {
+		futures_timer::Delay::new(config.check_interval).await;
+
+		let info = client.info();
+		let unfinalized_blocks = info.best_number.saturating_sub(info.finalized_number);
+		if let Some(metrics) = &metrics {
+			metrics.unfinalized_blocks.set(unfinalized_blocks.unique_saturated_into());
+		}
+
+		match tracker.check(Instant::now(), info.best_number, info.finalized_number) {
+			StallCheck::Progressing => {},
+			StallCheck::Recovered => {
+				info!(
+					target: LOG_TARGET,
+					"✅ GRANDPA finality recovered at block #{} ({})",
+					info.finalized_number,
+					info.finalized_hash,
+				);
+
+				status.set(None);
+				if let Some(metrics) = &metrics {
+					metrics.stalled.set(0);
+				}
+			},
+			StallCheck::Stalled { new, stalled_for } => {
+				let diagnosis = diagnose(&info, stalled_for, &authority_set, &voter_state);
+
+				if new {
+					match &diagnosis.best_round {
+						Some(votes) => warn!(
+							target: LOG_TARGET,
+							"⚠️ GRANDPA finality stalled at block #{} for {}s, {} blocks behind the \
+							 best block. Round {} of set {} is missing prevotes from [{}] and \
+							 precommits from [{}]",
+							info.finalized_number,
+							diagnosis.stalled_for,
+							unfinalized_blocks,
+							votes.round,
+							diagnosis.set_id,
+							format_voters(votes.missing_prevotes()),
+							format_voters(votes.missing_precommits()),
+						),
+						None => warn!(
+							target: LOG_TARGET,
+							"⚠️ GRANDPA finality stalled at block #{} for {}s, {} blocks behind the \
+							 best block. The voter isn't running",
+							info.finalized_number,
+							diagnosis.stalled_for,
+							unfinalized_blocks,
+						),
+					}
+
+					if let Some(metrics) = &metrics {
+						metrics.stalled.set(1);
+						metrics.stalls.inc();
+					}
+				}
+
+				if let Err(err) = aux_schema::write_stall_diagnosis(&*client, &diagnosis) {
+					warn!(target: LOG_TARGET, "Failed to persist finality stall diagnosis: {}", err);
+				}
+
+				status.set(Some(diagnosis));
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::ByteArray;
+	use std::collections::HashSet;
+
+	#[test]
+	fn stall_is_detected_by_unfinalized_blocks() {
+		let now = Instant::now();
+		let mut tracker = StallTracker::new(10u64, Duration::from_secs(60), now);
+
+		assert_eq!(tracker.check(now, 9, 0), StallCheck::Progressing);
+		assert_eq!(
+			tracker.check(now, 10, 0),
+			StallCheck::Stalled { new: true, stalled_for: Duration::ZERO },
+		);
+		assert_eq!(
+			tracker.check(now, 11, 0),
+			StallCheck::Stalled { new: false, stalled_for: Duration::ZERO },
+		);
+		assert_eq!(tracker.check(now, 11, 5), StallCheck::Recovered);
+		assert_eq!(tracker.check(now, 12, 5), StallCheck::Progressing);
+	}
+
+	#[test]
+	fn stall_is_detected_by_duration() {
+		let start = Instant::now();
+		let mut tracker = StallTracker::new(10u64, Duration::from_secs(60), start);
+
+		assert_eq!(tracker.check(start, 1, 0), StallCheck::Progressing);
+		assert_eq!(tracker.check(start + Duration::from_secs(59), 2, 0), StallCheck::Progressing);
+		assert_eq!(
+			tracker.check(start + Duration::from_secs(60), 2, 0),
+			StallCheck::Stalled { new: true, stalled_for: Duration::from_secs(60) },
+		);
+
+		// finality advancing resets the stall duration
+		assert_eq!(tracker.check(start + Duration::from_secs(61), 3, 1), StallCheck::Recovered);
+		assert_eq!(tracker.check(start + Duration::from_secs(120), 3, 1), StallCheck::Progressing);
+	}
+
+	#[test]
+	fn round_votes_report_missing_voters() {
+		let voter = |n| AuthorityId::from_slice(&[n; 32]).unwrap();
+		let voters = [voter(1), voter(2), voter(3)];
+
+		let round_state = report::RoundState {
+			total_weight: 3_u64.try_into().unwrap(),
+			threshold_weight: 3_u64.try_into().unwrap(),
+			prevote_current_weight: 2.into(),
+			prevote_ids: [voter(1), voter(2)].into_iter().collect::<HashSet<_>>(),
+			precommit_current_weight: 1.into(),
+			precommit_ids: [voter(1)].into_iter().collect::<HashSet<_>>(),
+		};
+
+		let votes = RoundVotes::from_round_state(4, &round_state, &voters);
+
+		assert_eq!(votes.round, 4);
+		assert_eq!(votes.missing_prevotes().collect::<Vec<_>>(), vec![&voter(3)]);
+		assert_eq!(votes.missing_precommits().collect::<Vec<_>>(), vec![&voter(2), &voter(3)]);
+	}
+}
@@ -22,12 +22,23 @@
 //! restrictions that are taken into account by the GRANDPA environment when
 //! selecting a finality target to vote on.
 
-use std::{future::Future, pin::Pin, sync::Arc};
+use std::{
+	future::Future,
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use dyn_clone::DynClone;
+use log::debug;
 
 use sc_client_api::blockchain::HeaderBackend;
-use sp_runtime::traits::{Block as BlockT, Header, NumberFor, One, Zero};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_consensus_grandpa::{GrandpaFinalityDelayApi, CLIENT_LOG_TARGET as LOG_TARGET};
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor, One, Zero},
+	DigestItem,
+};
 
 /// A future returned by a `VotingRule` to restrict a given vote, if any restriction is necessary.
 pub type VotingRuleResult<Block> =
@@ -166,6 +177,174 @@ where
 	}
 }
 
+/// A custom voting rule that never votes for blocks whose timestamp is less than the given
+/// amount of time in the past, giving operators a window to react before blocks are finalized.
+///
+/// The timestamp of a block is given by `block_timestamp` as the duration since the UNIX epoch,
+/// e.g. derived from the slot in the consensus pre-digest. Since it only depends on the block,
+/// the age is the same for every voter and survives restarts. Blocks without a known timestamp
+/// are treated as too new.
+#[derive(Clone)]
+pub struct MinimumBlockAge<F> {
+	min_age: Duration,
+	block_timestamp: F,
+}
+
+impl<F> MinimumBlockAge<F> {
+	/// Create a new rule that only votes for blocks at least `min_age` old, as determined by
+	/// the given `block_timestamp` function.
+	pub fn new(min_age: Duration, block_timestamp: F) -> Self {
+		MinimumBlockAge { min_age, block_timestamp }
+	}
+}
+
+impl<Block, B, F> VotingRule<Block, B> for MinimumBlockAge<F>
+where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	F: Fn(&Block::Header) -> Option<Duration> + Clone + Send + Sync,
+{
+	fn restrict_vote(
+		&self,
+		backend: Arc<B>,
+		base: &Block::Header,
+		_best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> VotingRuleResult<Block> {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+		// walk backwards until we find a block that is old enough, or reach the base
+		let mut target = current_target.clone();
+		while *target.number() > *base.number() {
+			let old_enough = (self.block_timestamp)(&target)
+				.map_or(false, |timestamp| now.saturating_sub(timestamp) >= self.min_age);
+
+			if old_enough {
+				break
+			}
+
+			target = match backend.header(*target.parent_hash()).ok().flatten() {
+				Some(parent) => parent,
+				None => return Box::pin(async { None }),
+			};
+		}
+
+		let res =
+			(target.hash() != current_target.hash()).then(|| (target.hash(), *target.number()));
+
+		Box::pin(std::future::ready(res))
+	}
+}
+
+/// A custom voting rule that doesn't vote for blocks enacting a runtime upgrade until they have
+/// been built upon by at least N blocks, in which case it votes for the parent of the upgrade
+/// block instead.
+///
+/// Runtime upgrades are detected through the [`DigestItem::RuntimeEnvironmentUpdated`] digest
+/// item, and confirmations are counted on the chain of the best target.
+#[derive(Clone)]
+pub struct RuntimeUpgradeConfirmations<N>(pub N);
+
+impl<Block, B> VotingRule<Block, B> for RuntimeUpgradeConfirmations<NumberFor<Block>>
+where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: Arc<B>,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> VotingRuleResult<Block> {
+		use sp_arithmetic::traits::Saturating;
+
+		// upgrades enacted at or below this number already have enough confirmations
+		let confirmed = best_target.number().saturating_sub(self.0);
+
+		// find the lowest unconfirmed upgrade above the base
+		let mut restricted = None;
+		let mut header = current_target.clone();
+		while *header.number() > *base.number() && *header.number() > confirmed {
+			if header
+				.digest()
+				.logs()
+				.iter()
+				.any(|log| matches!(log, DigestItem::RuntimeEnvironmentUpdated))
+			{
+				restricted = Some((*header.parent_hash(), *header.number() - One::one()));
+			}
+
+			header = match backend.header(*header.parent_hash()).ok().flatten() {
+				Some(parent) => parent,
+				None => break,
+			};
+		}
+
+		Box::pin(std::future::ready(restricted))
+	}
+}
+
+/// A custom voting rule that keeps our vote behind the best block by the number of blocks
+/// returned by the runtime through [`GrandpaFinalityDelayApi`], allowing the finality lag to
+/// be adjusted on-chain.
+///
+/// The delay is queried at the best target and applied as with [`BeforeBestBlockBy`]. Votes
+/// are not restricted if the runtime doesn't implement the API or doesn't return a delay.
+pub struct RuntimeFinalityDelay<C> {
+	client: Arc<C>,
+}
+
+impl<C> RuntimeFinalityDelay<C> {
+	/// Create a new rule querying the finality delay through the given client.
+	pub fn new(client: Arc<C>) -> Self {
+		RuntimeFinalityDelay { client }
+	}
+}
+
+impl<C> Clone for RuntimeFinalityDelay<C> {
+	fn clone(&self) -> Self {
+		RuntimeFinalityDelay { client: self.client.clone() }
+	}
+}
+
+impl<Block, B, C> VotingRule<Block, B> for RuntimeFinalityDelay<C>
+where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	C: ProvideRuntimeApi<Block> + Send + Sync,
+	C::Api: GrandpaFinalityDelayApi<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: Arc<B>,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> VotingRuleResult<Block> {
+		let at = best_target.hash();
+		let runtime_api = self.client.runtime_api();
+
+		let delay = runtime_api
+			.has_api::<dyn GrandpaFinalityDelayApi<Block>>(at)
+			.and_then(|has_api| if has_api { runtime_api.finality_delay(at) } else { Ok(None) });
+
+		match delay {
+			Ok(Some(delay)) =>
+				BeforeBestBlockBy(delay).restrict_vote(backend, base, best_target, current_target),
+			Ok(None) => Box::pin(async { None }),
+			Err(err) => {
+				debug!(
+					target: LOG_TARGET,
+					"Failed to query finality delay from runtime at {:?}: {}", at, err,
+				);
+
+				Box::pin(async { None })
+			},
+		}
+	}
+}
+
 // walk backwards until we find the target block
 fn find_target<Block, B>(
 	backend: &B,
@@ -335,7 +514,7 @@ mod tests {
 	use sp_runtime::traits::Header as _;
 
 	use substrate_test_runtime_client::{
-		runtime::{Block, Header},
+		runtime::{Block, Hash, Header},
 		Backend, Client, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClientBuilder,
 		TestClientBuilderExt,
 	};
@@ -451,4 +630,194 @@ mod tests {
 			assert_eq!(number, expected, "best = {}, lag = 2, base = {}", best_number, i);
 		}
 	}
+
+	fn import_blocks(client: &Arc<Client<Backend>>, n: usize) -> Vec<Hash> {
+		let mut hashes = Vec::with_capacity(n);
+		for _ in 0..n {
+			let block = BlockBuilderBuilder::new(&**client)
+				.on_parent_block(client.chain_info().best_hash)
+				.with_parent_block_number(client.chain_info().best_number)
+				.build()
+				.unwrap()
+				.build()
+				.unwrap()
+				.block;
+			hashes.push(block.hash());
+
+			futures::executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		}
+
+		hashes
+	}
+
+	#[test]
+	fn minimum_block_age_holds_back_new_blocks() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let hashes = import_blocks(&client, 5);
+
+		let genesis = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let best = client.header(client.info().best_hash).unwrap().unwrap();
+
+		// block #n was produced (5 - n) minutes ago
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+		let block_timestamp =
+			move |header: &Header| Some(now - Duration::from_secs(60 * (5 - header.number)));
+
+		// blocks #1 and #2 are at least three minutes old
+		let rule = MinimumBlockAge::new(Duration::from_secs(180), block_timestamp);
+		let (hash, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &genesis, &best, &best))
+				.unwrap();
+
+		assert_eq!(hash, hashes[1]);
+		assert_eq!(number, 2);
+
+		// none of the blocks are old enough, so we should vote for the base
+		let rule = MinimumBlockAge::new(Duration::from_secs(3600), block_timestamp);
+		let (hash, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &genesis, &best, &best))
+				.unwrap();
+
+		assert_eq!(hash, genesis.hash());
+		assert_eq!(number, 0);
+
+		// blocks without a timestamp are never old enough
+		let rule = MinimumBlockAge::new(Duration::ZERO, |_: &Header| -> Option<Duration> { None });
+		let (_, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &genesis, &best, &best))
+				.unwrap();
+
+		assert_eq!(number, 0);
+
+		// all blocks are old enough without a minimum age
+		let rule = MinimumBlockAge::new(Duration::ZERO, block_timestamp);
+		assert!(futures::executor::block_on(rule.restrict_vote(
+			client.clone(),
+			&genesis,
+			&best,
+			&best,
+		))
+		.is_none());
+	}
+
+	#[derive(Clone)]
+	struct TestApi {
+		finality_delay: Option<u64>,
+	}
+
+	struct RuntimeApi {
+		inner: TestApi,
+	}
+
+	impl ProvideRuntimeApi<Block> for TestApi {
+		type Api = RuntimeApi;
+
+		fn runtime_api(&self) -> sp_api::ApiRef<'_, Self::Api> {
+			RuntimeApi { inner: self.clone() }.into()
+		}
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl GrandpaFinalityDelayApi<Block> for RuntimeApi {
+			fn finality_delay(&self) -> Option<u64> {
+				self.inner.finality_delay
+			}
+		}
+	}
+
+	#[test]
+	fn runtime_finality_delay_follows_the_runtime() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let hashes = import_blocks(&client, 10);
+
+		let genesis = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let best = client.header(client.info().best_hash).unwrap().unwrap();
+
+		// the runtime asks voters to stay 4 blocks behind the best block
+		let rule = RuntimeFinalityDelay::new(Arc::new(TestApi { finality_delay: Some(4) }));
+		let (hash, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &genesis, &best, &best))
+				.unwrap();
+
+		assert_eq!(hash, hashes[5]);
+		assert_eq!(number, 6);
+
+		// the delay is cut off at the base
+		let base = client.header(hashes[7]).unwrap().unwrap();
+		let (_, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &base, &best, &best))
+				.unwrap();
+
+		assert_eq!(number, 8);
+
+		// votes are not restricted without a delay
+		let rule = RuntimeFinalityDelay::new(Arc::new(TestApi { finality_delay: None }));
+		assert!(futures::executor::block_on(rule.restrict_vote(
+			client.clone(),
+			&genesis,
+			&best,
+			&best,
+		))
+		.is_none());
+	}
+
+	#[test]
+	fn runtime_upgrade_confirmations_restricts_to_upgrade_parent() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let mut hashes = Vec::with_capacity(10);
+
+		for i in 1..=10 {
+			// enact a runtime upgrade in block #6
+			let mut digest = sp_runtime::Digest::default();
+			if i == 6 {
+				digest.push(DigestItem::RuntimeEnvironmentUpdated);
+			}
+
+			let block = BlockBuilderBuilder::new(&*client)
+				.on_parent_block(client.chain_info().best_hash)
+				.with_parent_block_number(client.chain_info().best_number)
+				.with_inherent_digests(digest)
+				.build()
+				.unwrap()
+				.build()
+				.unwrap()
+				.block;
+			hashes.push(block.hash());
+
+			futures::executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		}
+
+		let genesis = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let best = client.header(client.info().best_hash).unwrap().unwrap();
+
+		// the upgrade only has 4 confirmations, so we should vote for its parent
+		let rule = RuntimeUpgradeConfirmations(5);
+		let (hash, number) =
+			futures::executor::block_on(rule.restrict_vote(client.clone(), &genesis, &best, &best))
+				.unwrap();
+
+		assert_eq!(hash, hashes[4]);
+		assert_eq!(number, 5);
+
+		// the upgrade is confirmed
+		let rule = RuntimeUpgradeConfirmations(4);
+		assert!(futures::executor::block_on(rule.restrict_vote(
+			client.clone(),
+			&genesis,
+			&best,
+			&best,
+		))
+		.is_none());
+
+		// the upgrade is below the base
+		let rule = RuntimeUpgradeConfirmations(5);
+		let base = client.header(hashes[6]).unwrap().unwrap();
+		assert!(futures::executor::block_on(rule.restrict_vote(
+			client.clone(),
+			&base,
+			&best,
+			&best,
+		))
+		.is_none());
+	}
 }
@@ -25,6 +25,7 @@ use sp_runtime::{traits::Block as BlockT, Digest};
 
 pub mod aura;
 pub mod babe;
+pub mod multi_producer;
 pub mod timestamp;
 
 /// Consensus data provider, manual seal uses this trait object for authoring blocks valid
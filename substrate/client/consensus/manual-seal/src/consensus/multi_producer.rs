@@ -0,0 +1,137 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Multi-producer consensus data provider, allows several manual seal nodes to author blocks
+//! for the same network.
+//!
+//! Every node runs its own manual seal authorship task, driven over RPC by an external
+//! controller which decides which node authors which block, and on top of which parent.
+//! Authored blocks are sealed with a [`MultiProducerSeal`] signed by the producer key and telling
+//! the other nodes whether to finalize the block when importing it. Importing nodes only honour
+//! that request for the producers they were configured with, see
+//! [`multi_producer_import_queue`](crate::multi_producer_import_queue).
+
+use crate::{ConsensusDataProvider, Error, MANUAL_SEAL_ENGINE_ID};
+use codec::{Decode, Encode};
+use sc_consensus::BlockImportParams;
+use sp_core::{crypto::KeyTypeId, sr25519, Pair};
+use sp_inherents::InherentData;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	Digest, DigestItem,
+};
+use std::marker::PhantomData;
+
+/// Key type of the keys used by multi-producer nodes to sign their seals.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"mnsl");
+
+/// Seal appended to the blocks authored by a multi-producer manual seal node.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct MultiProducerSeal {
+	/// Key of the node that authored the block.
+	pub producer: sr25519::Public,
+	/// Whether nodes importing the block should finalize it.
+	pub finalize: bool,
+	/// Signature by `producer` of the pre-hash of the block and `finalize`.
+	pub signature: sr25519::Signature,
+}
+
+impl MultiProducerSeal {
+	/// Convert the seal into a digest item.
+	pub fn into_digest_item(self) -> DigestItem {
+		DigestItem::Seal(MANUAL_SEAL_ENGINE_ID, self.encode())
+	}
+
+	/// Try to decode the seal from a digest item.
+	pub fn from_digest_item(item: &DigestItem) -> Option<Self> {
+		item.seal_try_to(&MANUAL_SEAL_ENGINE_ID)
+	}
+
+	/// Check the seal signature against the hash of the block header without the seal.
+	pub fn verify<H: Encode>(&self, pre_hash: &H) -> bool {
+		let payload = signing_payload(pre_hash, self.finalize);
+		sr25519::Pair::verify(&self.signature, payload, &self.producer)
+	}
+}
+
+/// Payload signed by the producer of a block.
+fn signing_payload<H: Encode>(pre_hash: &H, finalize: bool) -> Vec<u8> {
+	(pre_hash, finalize).encode()
+}
+
+/// Remove the [`MultiProducerSeal`] from the header, if the block was sealed by a multi-producer
+/// node.
+pub fn take_seal<H: HeaderT>(header: &mut H) -> Option<MultiProducerSeal> {
+	let seal = header.digest().logs().last().and_then(MultiProducerSeal::from_digest_item)?;
+	header.digest_mut().pop();
+	Some(seal)
+}
+
+/// Consensus data provider for multi-producer manual seal networks.
+pub struct MultiProducerConsensusDataProvider<B, P> {
+	/// shared reference to keystore
+	keystore: KeystorePtr,
+	/// key of this node, of type [`KEY_TYPE`]
+	producer: sr25519::Public,
+	/// phantom data for required generics
+	_phantom: PhantomData<(B, P)>,
+}
+
+impl<B, P> MultiProducerConsensusDataProvider<B, P> {
+	/// Creates a new instance of the [`MultiProducerConsensusDataProvider`] for the node
+	/// identified by `producer`, whose secret key has to be in `keystore`.
+	pub fn new(keystore: KeystorePtr, producer: sr25519::Public) -> Self {
+		Self { keystore, producer, _phantom: PhantomData }
+	}
+}
+
+impl<B, P> ConsensusDataProvider<B> for MultiProducerConsensusDataProvider<B, P>
+where
+	B: BlockT,
+	P: Send + Sync,
+{
+	type Proof = P;
+
+	fn create_digest(
+		&self,
+		_parent: &B::Header,
+		_inherents: &InherentData,
+	) -> Result<Digest, Error> {
+		Ok(Digest::default())
+	}
+
+	fn append_block_import(
+		&self,
+		_parent: &B::Header,
+		params: &mut BlockImportParams<B>,
+		_inherents: &InherentData,
+		_proof: Self::Proof,
+	) -> Result<(), Error> {
+		let finalize = params.finalized;
+		let payload = signing_payload(&params.header.hash(), finalize);
+		let signature = self
+			.keystore
+			.sr25519_sign(KEY_TYPE, &self.producer, &payload)
+			.map_err(|e| Error::StringError(format!("Failed to sign seal: {}", e)))?
+			.ok_or_else(|| Error::StringError("Producer key is not in the keystore".into()))?;
+		let seal = MultiProducerSeal { producer: self.producer, finalize, signature };
+		params.post_digests.push(seal.into_digest_item());
+		Ok(())
+	}
+}
@@ -32,10 +32,13 @@ use sc_consensus::{
 };
 use sp_blockchain::HeaderBackend;
 use sp_consensus::{Environment, Proposer, SelectChain};
-use sp_core::traits::SpawnNamed;
+use sp_core::{sr25519, traits::SpawnNamed};
 use sp_inherents::CreateInherentDataProviders;
-use sp_runtime::{traits::Block as BlockT, ConsensusEngineId};
-use std::{marker::PhantomData, sync::Arc, time::Duration};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	ConsensusEngineId,
+};
+use std::{collections::HashSet, marker::PhantomData, sync::Arc, time::Duration};
 
 mod error;
 mod finalize_block;
@@ -59,8 +62,14 @@ const LOG_TARGET: &str = "manual-seal";
 /// The `ConsensusEngineId` of Manual Seal.
 pub const MANUAL_SEAL_ENGINE_ID: ConsensusEngineId = [b'm', b'a', b'n', b'l'];
 
-/// The verifier for the manual seal engine; instantly finalizes.
-struct ManualSealVerifier;
+/// The verifier for the manual seal engine.
+///
+/// Blocks are not finalized on import, unless multi-producer mode is enabled and the block was
+/// sealed by an allowed producer asking for it, see [`consensus::multi_producer`].
+struct ManualSealVerifier {
+	/// Producers whose finalize requests are honoured, `None` if multi-producer mode is disabled.
+	producers: Option<HashSet<sr25519::Public>>,
+}
 
 #[async_trait::async_trait]
 impl<B: BlockT> Verifier<B> for ManualSealVerifier {
@@ -70,6 +79,17 @@ impl<B: BlockT> Verifier<B> for ManualSealVerifier {
 	) -> Result<BlockImportParams<B>, String> {
 		block.finalized = false;
 		block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		let Some(producers) = &self.producers else { return Ok(block) };
+
+		let hash = block.header.hash();
+		if let Some(seal) = consensus::multi_producer::take_seal(&mut block.header) {
+			if !seal.verify(&block.header.hash()) {
+				return Err(format!("Invalid multi-producer seal on block {:?}", hash))
+			}
+			block.finalized = seal.finalize && producers.contains(&seal.producer);
+			block.post_digests.push(seal.into_digest_item());
+			block.post_hash = Some(hash);
+		}
 		Ok(block)
 	}
 }
@@ -83,7 +103,23 @@ pub fn import_queue<Block>(
 where
 	Block: BlockT,
 {
-	BasicQueue::new(ManualSealVerifier, block_import, None, spawner, registry)
+	BasicQueue::new(ManualSealVerifier { producers: None }, block_import, None, spawner, registry)
+}
+
+/// Instantiate the import queue for a multi-producer manual seal network.
+///
+/// Blocks sealed by one of `producers` are finalized on import if the producer asked for it.
+pub fn multi_producer_import_queue<Block>(
+	block_import: BoxBlockImport<Block>,
+	spawner: &impl sp_core::traits::SpawnEssentialNamed,
+	registry: Option<&Registry>,
+	producers: impl IntoIterator<Item = sr25519::Public>,
+) -> BasicQueue<Block>
+where
+	Block: BlockT,
+{
+	let verifier = ManualSealVerifier { producers: Some(producers.into_iter().collect()) };
+	BasicQueue::new(verifier, block_import, None, spawner, registry)
 }
 
 /// Params required to start the manual sealing authorship task.
@@ -193,6 +229,42 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 				})
 				.await;
 			},
+			EngineCommand::CreateFork { create_empty, parent_hash, length, mut sender } => {
+				let mut fork = Vec::new();
+				let mut parent_hash = parent_hash;
+				let mut result = match length {
+					1..=rpc::MAX_FORK_LENGTH => Ok(()),
+					_ => Err(Error::StringError(format!(
+						"Fork length must be between 1 and {}",
+						rpc::MAX_FORK_LENGTH
+					))),
+				};
+				while result.is_ok() && fork.len() < length as usize {
+					let (block_sender, block_receiver) = futures::channel::oneshot::channel();
+					seal_block(SealBlockParams {
+						sender: Some(block_sender),
+						parent_hash: Some(parent_hash),
+						finalize: false,
+						create_empty,
+						env: &mut env,
+						select_chain: &select_chain,
+						block_import: &mut block_import,
+						consensus_data_provider: consensus_data_provider.as_deref(),
+						pool: pool.clone(),
+						client: client.clone(),
+						create_inherent_data_providers: &create_inherent_data_providers,
+					})
+					.await;
+					match block_receiver.await.map_err(Error::from).and_then(|created| created) {
+						Ok(created) => {
+							parent_hash = created.hash;
+							fork.push(created);
+						},
+						Err(e) => result = Err(e),
+					}
+				}
+				rpc::send_result(&mut sender, result.map(|_| fork));
+			},
 			EngineCommand::FinalizeBlock { hash, sender, justification } => {
 				let justification = justification.map(|j| (MANUAL_SEAL_ENGINE_ID, j));
 				finalize_block(FinalizeBlockParams {
@@ -800,4 +872,192 @@ mod tests {
 		let header = client.header(created_block.hash).unwrap().unwrap();
 		assert_eq!(header.number, 1);
 	}
+
+	#[tokio::test]
+	async fn manual_seal_create_fork() {
+		let builder = TestClientBuilder::new();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let genesis_hash = client.info().genesis_hash;
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(),
+			true.into(),
+			api(),
+			None,
+			RevalidationType::Full,
+			spawner.clone(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		));
+		let env = ProposerFactory::new(spawner.clone(), client.clone(), pool.clone(), None, None);
+
+		let (mut sink, commands_stream) = futures::channel::mpsc::channel(1024);
+
+		// spawn the background authorship task
+		tokio::spawn(run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env,
+			client: client.clone(),
+			pool: pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: None,
+			create_inherent_data_providers: |_, _| async { Ok(()) },
+		}));
+
+		// build the best chain: genesis <- 1 <- 2
+		let mut best = Vec::new();
+		for _ in 0..2 {
+			let (tx, rx) = futures::channel::oneshot::channel();
+			sink.send(EngineCommand::SealNewBlock {
+				parent_hash: None,
+				sender: Some(tx),
+				create_empty: true,
+				finalize: false,
+			})
+			.await
+			.unwrap();
+			best.push(rx.await.unwrap().unwrap());
+		}
+
+		// fork off block 1: 1 <- 2' <- 3'
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::CreateFork {
+			create_empty: true,
+			parent_hash: best[0].hash,
+			length: 2,
+			sender: Some(tx),
+		})
+		.await
+		.unwrap();
+		let fork = rx.await.unwrap().unwrap();
+
+		assert_eq!(fork.len(), 2);
+		assert_ne!(fork[0].hash, best[1].hash);
+		// the first fork block has the same height as the best block, the second one overtakes it.
+		assert!(!fork[0].aux.is_new_best);
+		assert!(fork[1].aux.is_new_best);
+		let header = client.header(fork[1].hash).unwrap().unwrap();
+		assert_eq!(header.number, 3);
+		assert_eq!(*client.header(fork[0].hash).unwrap().unwrap().parent_hash(), best[0].hash);
+		assert_eq!(client.info().best_hash, fork[1].hash);
+
+		// empty and too long forks are rejected
+		for length in [0, rpc::MAX_FORK_LENGTH + 1] {
+			let (tx, rx) = futures::channel::oneshot::channel();
+			sink.send(EngineCommand::CreateFork {
+				create_empty: true,
+				parent_hash: best[0].hash,
+				length,
+				sender: Some(tx),
+			})
+			.await
+			.unwrap();
+			assert!(rx.await.unwrap().is_err());
+		}
+		assert_eq!(client.info().best_hash, fork[1].hash);
+	}
+
+	#[tokio::test]
+	async fn multi_producer_blocks_are_finalized_on_import() {
+		use consensus::multi_producer::{
+			MultiProducerConsensusDataProvider, MultiProducerSeal, KEY_TYPE,
+		};
+		use sc_client_api::BlockBackend;
+		use sp_consensus::BlockOrigin;
+		use sp_keystore::{testing::MemoryKeystore, Keystore};
+
+		let builder = TestClientBuilder::new();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let other_client = Arc::new(TestClientBuilder::new().build());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let genesis_hash = client.info().genesis_hash;
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(),
+			true.into(),
+			api(),
+			None,
+			RevalidationType::Full,
+			spawner.clone(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		));
+		let env = ProposerFactory::new(spawner.clone(), client.clone(), pool.clone(), None, None);
+		let keystore = Arc::new(MemoryKeystore::new());
+		let producer = keystore.sr25519_generate_new(KEY_TYPE, None).unwrap();
+		let other_producer = keystore.sr25519_generate_new(KEY_TYPE, None).unwrap();
+
+		let (mut sink, commands_stream) = futures::channel::mpsc::channel(1024);
+
+		// spawn the background authorship task of the producer
+		tokio::spawn(run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env,
+			client: client.clone(),
+			pool: pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(MultiProducerConsensusDataProvider::new(
+				keystore.clone(),
+				producer,
+			))),
+			create_inherent_data_providers: |_, _| async { Ok(()) },
+		}));
+
+		let mut created = Vec::new();
+		for finalize in [false, true] {
+			let (tx, rx) = futures::channel::oneshot::channel();
+			sink.send(EngineCommand::SealNewBlock {
+				parent_hash: None,
+				sender: Some(tx),
+				create_empty: true,
+				finalize,
+			})
+			.await
+			.unwrap();
+			created.push(rx.await.unwrap().unwrap());
+		}
+
+		let disabled = ManualSealVerifier { producers: None };
+		let unknown = ManualSealVerifier { producers: Some([other_producer].into()) };
+		let allowed = ManualSealVerifier { producers: Some([producer].into()) };
+
+		for (created, finalize) in created.iter().zip([false, true]) {
+			let block = client.block(created.hash).unwrap().unwrap().block;
+			let seal_item = block.header.digest().logs().last().unwrap();
+			let seal = MultiProducerSeal::from_digest_item(seal_item).unwrap();
+			assert_eq!((seal.producer, seal.finalize), (producer, finalize));
+
+			let (header, body) = block.deconstruct();
+			let import_params = |header| {
+				let mut params = BlockImportParams::new(BlockOrigin::NetworkBroadcast, header);
+				params.body = Some(body.clone());
+				params
+			};
+
+			// finalize requests are ignored unless the producer is allowed
+			assert!(!disabled.verify(import_params(header.clone())).await.unwrap().finalized);
+			assert!(!unknown.verify(import_params(header.clone())).await.unwrap().finalized);
+
+			// a seal whose finalize flag was tampered with is rejected
+			let forged = MultiProducerSeal { finalize: !finalize, ..seal.clone() };
+			let mut tampered = header.clone();
+			tampered.digest_mut().pop();
+			tampered.digest_mut().push(forged.into_digest_item());
+			assert!(allowed.verify(import_params(tampered)).await.is_err());
+
+			// import the block on another node
+			let params = allowed.verify(import_params(header)).await.unwrap();
+			assert_eq!(params.finalized, finalize);
+			assert_eq!(params.post_hash(), created.hash);
+			other_client.import_block(params).await.unwrap();
+		}
+
+		assert_eq!(other_client.info().best_hash, created[1].hash);
+		assert_eq!(other_client.info().finalized_hash, created[1].hash);
+	}
 }
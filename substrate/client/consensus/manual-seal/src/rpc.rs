@@ -31,6 +31,9 @@ use sp_runtime::EncodedJustification;
 /// Sender passed to the authorship task to report errors or successes.
 pub type Sender<T> = Option<oneshot::Sender<std::result::Result<T, Error>>>;
 
+/// Maximum number of blocks a single [`EngineCommand::CreateFork`] may create.
+pub const MAX_FORK_LENGTH: u32 = 64;
+
 /// Message sent to the background authorship task, usually by RPC.
 pub enum EngineCommand<Hash> {
 	/// Tells the engine to propose a new block
@@ -50,6 +53,21 @@ pub enum EngineCommand<Hash> {
 		/// sender to report errors/success to the rpc.
 		sender: Sender<CreatedBlock<Hash>>,
 	},
+	/// Tells the engine to build a chain of `length` blocks on top of `parent_hash`, which does
+	/// not need to be the best block.
+	///
+	/// The created blocks are not finalized. `length` is capped to [`MAX_FORK_LENGTH`].
+	CreateFork {
+		/// if true, empty blocks(without extrinsics) will be created.
+		/// otherwise, will return Error::EmptyTransactionPool.
+		create_empty: bool,
+		/// the parent hash of the first block of the fork
+		parent_hash: Hash,
+		/// number of blocks to create
+		length: u32,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<Vec<CreatedBlock<Hash>>>,
+	},
 	/// Tells the engine to finalize the block with the supplied hash
 	FinalizeBlock {
 		/// hash of the block
@@ -73,6 +91,16 @@ pub trait ManualSealApi<Hash> {
 		parent_hash: Option<Hash>,
 	) -> Result<CreatedBlock<Hash>, Error>;
 
+	/// Instructs the manual-seal authorship task to create a chain of blocks on top of the given
+	/// parent, which does not need to be the best block.
+	#[method(name = "engine_createFork")]
+	async fn create_fork(
+		&self,
+		parent_hash: Hash,
+		length: u32,
+		create_empty: bool,
+	) -> Result<Vec<CreatedBlock<Hash>>, Error>;
+
 	/// Instructs the manual-seal authorship task to finalize a block
 	#[method(name = "engine_finalizeBlock")]
 	async fn finalize_block(
@@ -132,6 +160,26 @@ impl<Hash: Send + 'static> ManualSealApiServer<Hash> for ManualSeal<Hash> {
 		}
 	}
 
+	async fn create_fork(
+		&self,
+		parent_hash: Hash,
+		length: u32,
+		create_empty: bool,
+	) -> Result<Vec<CreatedBlock<Hash>>, Error> {
+		let mut sink = self.import_block_channel.clone();
+		let (sender, receiver) = oneshot::channel();
+		let command =
+			EngineCommand::CreateFork { create_empty, parent_hash, length, sender: Some(sender) };
+
+		sink.send(command).await?;
+
+		match receiver.await {
+			Ok(Ok(rx)) => Ok(rx),
+			Ok(Err(e)) => Err(e.into()),
+			Err(e) => Err(e.into()),
+		}
+	}
+
 	async fn finalize_block(
 		&self,
 		hash: Hash,
@@ -0,0 +1,522 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Difficulty adjustment algorithms for PoW.
+//!
+//! The algorithms implement [`DifficultyAdjustment`], computing the next difficulty from the
+//! timestamps and difficulties of the most recent blocks. [`DifficultyCalculator`] collects
+//! those from the chain, reading the difficulties stored in [`PowAux`], and can be used to
+//! implement [`PowAlgorithm::difficulty`](crate::PowAlgorithm::difficulty).
+
+use parking_lot::Mutex;
+use sc_client_api::backend::AuxStore;
+use sp_blockchain::HeaderBackend;
+use sp_core::U256;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, Zero};
+use std::sync::Arc;
+
+use crate::{find_pre_digest, multi_algorithm::AlgorithmId, Error, PowAux};
+
+/// Timestamp and difficulty of a block, used to compute the difficulty of its descendants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockDifficulty {
+	/// Timestamp of the block, in milliseconds.
+	pub timestamp: u64,
+	/// Difficulty of the block.
+	pub difficulty: U256,
+}
+
+/// A difficulty adjustment algorithm.
+pub trait DifficultyAdjustment {
+	/// Number of most recent blocks needed to compute the next difficulty.
+	fn window(&self) -> usize;
+
+	/// Compute the difficulty of the next block, given the most recent blocks ordered from the
+	/// oldest to the newest.
+	///
+	/// `blocks` contains at least two and at most [`Self::window`] entries.
+	fn next_difficulty(&self, blocks: &[BlockDifficulty]) -> U256;
+}
+
+/// Bound on solve times, relative to the target block time, limiting the impact of blocks with
+/// manipulated timestamps.
+const MAX_SOLVE_TIME_FACTOR: u64 = 6;
+
+// the time spent mining the block `next`, bounded to `[1, MAX_SOLVE_TIME_FACTOR * target]`
+fn solve_time(previous: &BlockDifficulty, next: &BlockDifficulty, target: u64) -> u64 {
+	next.timestamp
+		.saturating_sub(previous.timestamp)
+		.clamp(1, target.saturating_mul(MAX_SOLVE_TIME_FACTOR).max(1))
+}
+
+/// Linearly weighted moving average difficulty adjustment.
+///
+/// The solve times of the last `window` blocks are averaged, giving linearly more weight to
+/// the most recent ones, and compared to the target block time to scale the average difficulty.
+#[derive(Clone, Copy, Debug)]
+pub struct Lwma {
+	/// Target time between blocks, in milliseconds.
+	pub target_block_time: u64,
+	/// Number of solve times taken into account.
+	pub window: usize,
+}
+
+impl DifficultyAdjustment for Lwma {
+	fn window(&self) -> usize {
+		self.window + 1
+	}
+
+	fn next_difficulty(&self, blocks: &[BlockDifficulty]) -> U256 {
+		let target = self.target_block_time;
+		let mut weighted_solve_times = U256::zero();
+		let mut total_difficulty = U256::zero();
+
+		for (weight, pair) in blocks.windows(2).enumerate() {
+			let solve_time = solve_time(&pair[0], &pair[1], target);
+			weighted_solve_times += U256::from(weight as u64 + 1) * U256::from(solve_time);
+			total_difficulty = total_difficulty.saturating_add(pair[1].difficulty);
+		}
+
+		// next = avg(difficulty) * target / lwma(solve_time), with the sum of the weights
+		// being n * (n + 1) / 2
+		let n = U256::from(blocks.len() as u64 - 1);
+		let next = total_difficulty.saturating_mul(U256::from(target)).saturating_mul(n + 1) /
+			(weighted_solve_times * 2);
+
+		next.max(U256::one())
+	}
+}
+
+/// Per-block exponential moving average difficulty adjustment.
+///
+/// Every block adjusts the difficulty of its parent depending on how far its solve time was from
+/// the target block time, with `smoothing` controlling how quickly the difficulty reacts.
+#[derive(Clone, Copy, Debug)]
+pub struct Ema {
+	/// Target time between blocks, in milliseconds.
+	pub target_block_time: u64,
+	/// Number of blocks over which the adjustments are smoothed, must be at least 2.
+	pub smoothing: u64,
+}
+
+impl DifficultyAdjustment for Ema {
+	fn window(&self) -> usize {
+		2
+	}
+
+	fn next_difficulty(&self, blocks: &[BlockDifficulty]) -> U256 {
+		let target = self.target_block_time;
+		let smoothing = self.smoothing.max(2);
+		let (previous, last) = (&blocks[blocks.len() - 2], &blocks[blocks.len() - 1]);
+		let solve_time = solve_time(previous, last, target);
+
+		// next = difficulty * n * target / ((n - 1) * target + solve_time)
+		let next = last.difficulty.saturating_mul(U256::from(smoothing) * U256::from(target)) /
+			(U256::from(smoothing - 1) * U256::from(target) + U256::from(solve_time));
+
+		next.max(U256::one())
+	}
+}
+
+/// Something that returns the timestamp of a block, in milliseconds.
+pub trait BlockTimestamp<B: BlockT> {
+	/// Returns the timestamp of the given block.
+	fn timestamp(&self, header: &B::Header) -> Result<u64, Error<B>>;
+}
+
+impl<B, F> BlockTimestamp<B> for F
+where
+	B: BlockT,
+	F: Fn(&B::Header) -> Result<u64, Error<B>>,
+{
+	fn timestamp(&self, header: &B::Header) -> Result<u64, Error<B>> {
+		(self)(header)
+	}
+}
+
+/// Computes the difficulty of new blocks using a [`DifficultyAdjustment`] algorithm over the
+/// most recent blocks of the chain.
+///
+/// The difficulty of the blocks is read from [`PowAux`], and their timestamp from the given
+/// [`BlockTimestamp`]. As long as there are not enough blocks to apply the algorithm the initial
+/// difficulty is used.
+pub struct DifficultyCalculator<B: BlockT, C, DA, T> {
+	client: Arc<C>,
+	adjustment: DA,
+	timestamp: T,
+	initial_difficulty: U256,
+	algorithm: Option<AlgorithmId>,
+	max_lookback: usize,
+	cache: Mutex<Option<(B::Hash, U256)>>,
+}
+
+impl<B, C, DA, T> DifficultyCalculator<B, C, DA, T>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + AuxStore,
+	DA: DifficultyAdjustment,
+	T: BlockTimestamp<B>,
+{
+	/// Create a new difficulty calculator.
+	pub fn new(client: Arc<C>, adjustment: DA, timestamp: T, initial_difficulty: U256) -> Self {
+		let max_lookback = adjustment.window();
+
+		DifficultyCalculator {
+			client,
+			adjustment,
+			timestamp,
+			initial_difficulty,
+			algorithm: None,
+			max_lookback,
+			cache: Mutex::new(None),
+		}
+	}
+
+	/// Only take into account the blocks mined with the given algorithm of a
+	/// [`MultiAlgorithm`](crate::MultiAlgorithm), looking back at most `max_lookback` blocks.
+	pub fn for_algorithm(mut self, algorithm: AlgorithmId, max_lookback: usize) -> Self {
+		self.algorithm = Some(algorithm);
+		self.max_lookback = max_lookback.max(self.adjustment.window());
+		self
+	}
+
+	/// Compute the difficulty of a child of the given block.
+	pub fn difficulty(&self, parent: B::Hash) -> Result<U256, Error<B>> {
+		if let Some((hash, difficulty)) = *self.cache.lock() {
+			if hash == parent {
+				return Ok(difficulty)
+			}
+		}
+
+		let blocks = self.recent_blocks(parent)?;
+		let difficulty = if blocks.len() < 2 {
+			self.initial_difficulty
+		} else {
+			self.adjustment.next_difficulty(&blocks)
+		};
+
+		*self.cache.lock() = Some((parent, difficulty));
+
+		Ok(difficulty)
+	}
+
+	// collect the blocks taken into account by the adjustment, from the oldest to the newest
+	fn recent_blocks(&self, parent: B::Hash) -> Result<Vec<BlockDifficulty>, Error<B>> {
+		let window = self.adjustment.window();
+		let mut blocks = Vec::with_capacity(window);
+		let mut hash = parent;
+
+		for _ in 0..self.max_lookback {
+			if blocks.len() == window {
+				break
+			}
+
+			let header = self
+				.client
+				.header(hash)
+				.map_err(Error::Client)?
+				.ok_or_else(|| Error::Other(format!("Header {:?} not found", hash)))?;
+
+			// the genesis block isn't mined
+			if header.number().is_zero() {
+				break
+			}
+
+			let mined_with_algorithm = match self.algorithm {
+				Some(algorithm) => {
+					let pre_digest = find_pre_digest::<B>(&header)?;
+					pre_digest.and_then(|pre_digest| pre_digest.first().copied()) == Some(algorithm)
+				},
+				None => true,
+			};
+
+			if mined_with_algorithm {
+				let aux = PowAux::<U256>::read::<_, B>(self.client.as_ref(), &hash)?;
+				blocks.push(BlockDifficulty {
+					timestamp: self.timestamp.timestamp(&header)?,
+					difficulty: aux.difficulty,
+				});
+			}
+
+			hash = *header.parent_hash();
+		}
+
+		blocks.reverse();
+
+		Ok(blocks)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{aux_key, encode_pre_digest};
+	use codec::Encode;
+	use sp_blockchain::{BlockStatus, Info};
+	use sp_consensus_pow::POW_ENGINE_ID;
+	use sp_runtime::testing::{
+		Block as RawBlock, Digest, DigestItem, Header, MockCallU64, TestXt, H256,
+	};
+	use std::collections::HashMap;
+
+	type Block = RawBlock<TestXt<MockCallU64, ()>>;
+
+	const TARGET: u64 = 6_000;
+
+	#[derive(Default)]
+	struct TestClient {
+		headers: HashMap<H256, Header>,
+		aux: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+	}
+
+	impl TestClient {
+		// a client with only the genesis block
+		fn new() -> (Self, Header) {
+			let genesis = Header {
+				parent_hash: Default::default(),
+				number: 0,
+				state_root: Default::default(),
+				extrinsics_root: Default::default(),
+				digest: Default::default(),
+			};
+			let mut client = TestClient::default();
+			client.headers.insert(genesis.hash(), genesis.clone());
+
+			(client, genesis)
+		}
+
+		// import a child of `parent` mined with the given difficulty and algorithm
+		fn mine(&mut self, parent: &Header, difficulty: u64, algo: Option<AlgorithmId>) -> Header {
+			let log = algo
+				.map(|algo| DigestItem::PreRuntime(POW_ENGINE_ID, encode_pre_digest(algo, None)));
+			let header = Header {
+				parent_hash: parent.hash(),
+				number: parent.number + 1,
+				state_root: Default::default(),
+				extrinsics_root: Default::default(),
+				digest: Digest { logs: log.into_iter().collect() },
+			};
+
+			let aux = PowAux { difficulty: U256::from(difficulty), total_difficulty: U256::zero() };
+			self.aux.lock().insert(aux_key(&header.hash()), aux.encode());
+			self.headers.insert(header.hash(), header.clone());
+
+			header
+		}
+	}
+
+	impl HeaderBackend<Block> for TestClient {
+		fn header(&self, hash: H256) -> sp_blockchain::Result<Option<Header>> {
+			Ok(self.headers.get(&hash).cloned())
+		}
+
+		// the best block is the highest one, and only the genesis block is finalized
+		fn info(&self) -> Info<Block> {
+			let genesis_hash =
+				self.hash(0).unwrap().expect("the genesis block is always known; qed");
+			let best = self.headers.values().max_by_key(|header| header.number);
+			let parents =
+				self.headers.values().map(|header| header.parent_hash).collect::<Vec<_>>();
+
+			Info {
+				best_hash: best.map_or(genesis_hash, Header::hash),
+				best_number: best.map_or(0, |header| header.number),
+				genesis_hash,
+				finalized_hash: genesis_hash,
+				finalized_number: 0,
+				finalized_state: Some((genesis_hash, 0)),
+				number_leaves: self.headers.keys().filter(|hash| !parents.contains(hash)).count(),
+				block_gap: None,
+			}
+		}
+
+		fn status(&self, hash: H256) -> sp_blockchain::Result<BlockStatus> {
+			match self.headers.contains_key(&hash) {
+				true => Ok(BlockStatus::InChain),
+				false => Ok(BlockStatus::Unknown),
+			}
+		}
+
+		fn number(&self, hash: H256) -> sp_blockchain::Result<Option<u64>> {
+			Ok(self.headers.get(&hash).map(|header| header.number))
+		}
+
+		fn hash(&self, number: u64) -> sp_blockchain::Result<Option<H256>> {
+			Ok(self.headers.values().find(|header| header.number == number).map(Header::hash))
+		}
+	}
+
+	impl AuxStore for TestClient {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(
+			&self,
+			insert: I,
+			delete: D,
+		) -> sp_blockchain::Result<()> {
+			let mut aux = self.aux.lock();
+			aux.extend(insert.into_iter().map(|(key, value)| (key.to_vec(), value.to_vec())));
+			for key in delete {
+				aux.remove(*key);
+			}
+
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.aux.lock().get(key).cloned())
+		}
+	}
+
+	// blocks are mined every half target block time
+	fn timestamp(header: &Header) -> Result<u64, Error<Block>> {
+		Ok(header.number * TARGET / 2)
+	}
+
+	// an EMA calculator with an initial difficulty of 42
+	fn calculator(
+		client: &Arc<TestClient>,
+	) -> DifficultyCalculator<Block, TestClient, Ema, impl BlockTimestamp<Block>> {
+		let ema = Ema { target_block_time: TARGET, smoothing: 10 };
+		DifficultyCalculator::new(client.clone(), ema, timestamp, U256::from(42))
+	}
+
+	fn blocks(solve_times: &[u64], difficulty: u64) -> Vec<BlockDifficulty> {
+		let mut timestamp = 0;
+		let mut blocks = vec![BlockDifficulty { timestamp, difficulty: difficulty.into() }];
+
+		for solve_time in solve_times {
+			timestamp += solve_time;
+			blocks.push(BlockDifficulty { timestamp, difficulty: difficulty.into() });
+		}
+
+		blocks
+	}
+
+	#[test]
+	fn lwma_keeps_difficulty_on_target() {
+		let lwma = Lwma { target_block_time: TARGET, window: 10 };
+		let blocks = blocks(&[TARGET; 10], 1_000_000);
+
+		assert_eq!(lwma.window(), 11);
+		assert_eq!(lwma.next_difficulty(&blocks), U256::from(1_000_000));
+	}
+
+	#[test]
+	fn lwma_adjusts_to_solve_times() {
+		let lwma = Lwma { target_block_time: TARGET, window: 10 };
+
+		// blocks twice as fast as the target double the difficulty
+		let fast = blocks(&[TARGET / 2; 10], 1_000_000);
+		assert_eq!(lwma.next_difficulty(&fast), U256::from(2_000_000));
+
+		// blocks twice as slow as the target halve the difficulty
+		let slow = blocks(&[TARGET * 2; 10], 1_000_000);
+		assert_eq!(lwma.next_difficulty(&slow), U256::from(500_000));
+
+		// the most recent solve times have more weight
+		let recently_fast = blocks(&[TARGET, TARGET, TARGET, TARGET / 2, TARGET / 2], 1_000_000);
+		let recently_slow = blocks(&[TARGET / 2, TARGET / 2, TARGET, TARGET, TARGET], 1_000_000);
+		assert!(lwma.next_difficulty(&recently_fast) > lwma.next_difficulty(&recently_slow));
+	}
+
+	#[test]
+	fn lwma_bounds_solve_times() {
+		let lwma = Lwma { target_block_time: TARGET, window: 2 };
+
+		// timestamps going backwards and huge gaps are clamped
+		let mut blocks = blocks(&[TARGET, TARGET], 1_000_000);
+		blocks[2].timestamp = 0;
+		assert!(lwma.next_difficulty(&blocks) > U256::from(1_000_000));
+
+		let gap = blocks(&[TARGET * 100, TARGET * 100], 1_000_000);
+		assert_eq!(lwma.next_difficulty(&gap), U256::from(1_000_000) / 6);
+	}
+
+	#[test]
+	fn ema_adjusts_to_solve_times() {
+		let ema = Ema { target_block_time: TARGET, smoothing: 10 };
+
+		assert_eq!(ema.next_difficulty(&blocks(&[TARGET], 1_000_000)), U256::from(1_000_000));
+
+		// difficulty = 1_000_000 * 10 * target / (9 * target + target / 2)
+		assert_eq!(ema.next_difficulty(&blocks(&[TARGET / 2], 1_000_000)), U256::from(1_052_631));
+
+		// difficulty = 1_000_000 * 10 * target / (9 * target + 2 * target)
+		assert_eq!(ema.next_difficulty(&blocks(&[TARGET * 2], 1_000_000)), U256::from(909_090));
+	}
+
+	#[test]
+	fn difficulty_never_drops_to_zero() {
+		let lwma = Lwma { target_block_time: TARGET, window: 2 };
+		let ema = Ema { target_block_time: TARGET, smoothing: 2 };
+		let blocks = blocks(&[TARGET * 6, TARGET * 6], 1);
+
+		assert_eq!(lwma.next_difficulty(&blocks), U256::one());
+		assert_eq!(ema.next_difficulty(&blocks), U256::one());
+	}
+
+	#[test]
+	fn calculator_uses_initial_difficulty_until_enough_blocks() {
+		let (mut client, genesis) = TestClient::new();
+		let block_1 = client.mine(&genesis, 1_000_000, None);
+		let calculator = calculator(&Arc::new(client));
+
+		assert_eq!(calculator.difficulty(genesis.hash()).unwrap(), U256::from(42));
+		assert_eq!(calculator.difficulty(block_1.hash()).unwrap(), U256::from(42));
+	}
+
+	#[test]
+	fn calculator_adjusts_to_chain() {
+		let (mut client, genesis) = TestClient::new();
+		let block_1 = client.mine(&genesis, 1_000_000, None);
+		let block_2 = client.mine(&block_1, 1_000_000, None);
+		assert_eq!(client.info().best_hash, block_2.hash());
+		assert_eq!(client.info().number_leaves, 1);
+
+		let calculator = calculator(&Arc::new(client));
+
+		// difficulty = 1_000_000 * 10 * target / (9 * target + target / 2)
+		assert_eq!(calculator.difficulty(block_2.hash()).unwrap(), U256::from(1_052_631));
+	}
+
+	#[test]
+	fn calculator_filters_blocks_by_algorithm() {
+		let (mut client, genesis) = TestClient::new();
+		let block_1 = client.mine(&genesis, 1_000_000, Some(0));
+		let block_2 = client.mine(&block_1, 5, Some(1));
+		let block_3 = client.mine(&block_2, 1_000_000, Some(0));
+		let block_4 = client.mine(&block_3, 5, Some(1));
+		let client = Arc::new(client);
+		let difficulty = |algorithm, max_lookback| {
+			let calculator = calculator(&client).for_algorithm(algorithm, max_lookback);
+			calculator.difficulty(block_4.hash()).unwrap()
+		};
+
+		// the blocks of each algorithm are mined on target
+		assert_eq!(difficulty(0, 10), U256::from(1_000_000));
+		assert_eq!(difficulty(1, 10), U256::from(5));
+
+		// only one block of algorithm 0 is within the lookback
+		assert_eq!(difficulty(0, 2), U256::from(42));
+	}
+}
@@ -38,10 +38,21 @@
 //! for the auxiliary storage. It is also possible to just use the runtime
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
+//!
+//! Reusable difficulty adjustment algorithms working over [`PowAux`] are provided in the
+//! [`difficulty`] module, [`MultiAlgorithm`] allows mining with several hashing algorithms on
+//! the same chain, and [`run_stratum_server`] exposes a [`MiningHandle`] to external miners.
 
+pub mod difficulty;
+mod multi_algorithm;
+mod stratum;
 mod worker;
 
-pub use crate::worker::{MiningBuild, MiningHandle, MiningMetadata};
+pub use crate::{
+	multi_algorithm::{encode_pre_digest, split_pre_digest, AlgorithmId, MultiAlgorithm},
+	stratum::{run_stratum_server, StratumConfig},
+	worker::{MiningBuild, MiningHandle, MiningMetadata},
+};
 
 use crate::worker::UntilImportedOrTimeout;
 use codec::{Decode, Encode};
@@ -51,7 +62,8 @@ use prometheus_endpoint::Registry;
 use sc_client_api::{self, backend::AuxStore, BlockOf, BlockchainEvents};
 use sc_consensus::{
 	BasicQueue, BlockCheckParams, BlockImport, BlockImportParams, BoxBlockImport,
-	BoxJustificationImport, ForkChoiceStrategy, ImportResult, Verifier,
+	BoxJustificationImport, ForkChoiceBlock, ForkChoiceRule, ForkChoiceStrategy, HeaviestChainRule,
+	ImportResult, Verifier,
 };
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
@@ -178,6 +190,18 @@ pub trait PowAlgorithm<B: BlockT> {
 	/// This function will be called twice during the import process, so the implementation
 	/// should be properly cached.
 	fn difficulty(&self, parent: B::Hash) -> Result<Self::Difficulty, Error<B>>;
+	/// Get the next block's difficulty, given the pre-runtime digest of the block.
+	///
+	/// This allows the difficulty to depend on the pre-runtime digest, e.g. when several mining
+	/// algorithms with separate difficulties are used on the same chain. By default the
+	/// pre-runtime digest is ignored and [`Self::difficulty`] is used.
+	fn pre_digest_difficulty(
+		&self,
+		parent: B::Hash,
+		_pre_digest: Option<&[u8]>,
+	) -> Result<Self::Difficulty, Error<B>> {
+		self.difficulty(parent)
+	}
 	/// Verify that the seal is valid against given pre hash when parent block is not yet imported.
 	///
 	/// None means that preliminary verify is not available for this algorithm.
@@ -209,16 +233,17 @@ pub trait PowAlgorithm<B: BlockT> {
 }
 
 /// A block importer for PoW.
-pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CIDP> {
+pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm: PowAlgorithm<B>, CIDP> {
 	algorithm: Algorithm,
 	inner: I,
 	select_chain: S,
 	client: Arc<C>,
 	create_inherent_data_providers: Arc<CIDP>,
 	check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
+	fork_choice: Arc<dyn ForkChoiceRule<B, Algorithm::Difficulty>>,
 }
 
-impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CIDP> Clone
+impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: PowAlgorithm<B> + Clone, CIDP> Clone
 	for PowBlockImport<B, I, C, S, Algorithm, CIDP>
 {
 	fn clone(&self) -> Self {
@@ -229,6 +254,7 @@ impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CIDP> Clone
 			client: self.client.clone(),
 			create_inherent_data_providers: self.create_inherent_data_providers.clone(),
 			check_inherents_after: self.check_inherents_after,
+			fork_choice: self.fork_choice.clone(),
 		}
 	}
 }
@@ -259,9 +285,20 @@ where
 			check_inherents_after,
 			select_chain,
 			create_inherent_data_providers: Arc::new(create_inherent_data_providers),
+			fork_choice: Arc::new(HeaviestChainRule),
 		}
 	}
 
+	/// Use the given fork choice rule instead of the default one, which picks the chain with the
+	/// highest total difficulty. Ties of the rule are broken with [`PowAlgorithm::break_tie`].
+	pub fn with_fork_choice(
+		mut self,
+		fork_choice: Arc<dyn ForkChoiceRule<B, Algorithm::Difficulty>>,
+	) -> Self {
+		self.fork_choice = fork_choice;
+		self
+	}
+
 	async fn check_inherents(
 		&self,
 		block: B,
@@ -353,13 +390,16 @@ where
 		let intermediate = block
 			.remove_intermediate::<PowIntermediate<Algorithm::Difficulty>>(INTERMEDIATE_KEY)?;
 
+		let pre_hash = block.header.hash();
+		let pre_digest = find_pre_digest::<B>(&block.header)?;
+
 		let difficulty = match intermediate.difficulty {
 			Some(difficulty) => difficulty,
-			None => self.algorithm.difficulty(parent_hash)?,
+			None => self
+				.algorithm
+				.pre_digest_difficulty(parent_hash, pre_digest.as_ref().map(|v| &v[..]))?,
 		};
 
-		let pre_hash = block.header.hash();
-		let pre_digest = find_pre_digest::<B>(&block.header)?;
 		if !self.algorithm.verify(
 			&BlockId::hash(parent_hash),
 			&pre_hash,
@@ -376,18 +416,28 @@ where
 		let key = aux_key(&block.post_hash());
 		block.auxiliary.push((key, Some(aux.encode())));
 		if block.fork_choice.is_none() {
-			block.fork_choice = Some(ForkChoiceStrategy::Custom(
-				match aux.total_difficulty.cmp(&best_aux.total_difficulty) {
-					Ordering::Less => false,
-					Ordering::Greater => true,
-					Ordering::Equal => {
-						let best_inner_seal =
-							fetch_seal::<B>(best_header.digest().logs.last(), best_hash)?;
-
-						self.algorithm.break_tie(&best_inner_seal, &inner_seal)
-					},
+			let candidate = ForkChoiceBlock {
+				hash: block.post_hash(),
+				header: &block.header,
+				weight: aux.total_difficulty,
+			};
+			let best = ForkChoiceBlock {
+				hash: best_hash,
+				header: &best_header,
+				weight: best_aux.total_difficulty,
+			};
+
+			let is_new_best = match self.fork_choice.compare(&candidate, &best)? {
+				Ordering::Less => false,
+				Ordering::Greater => true,
+				Ordering::Equal => {
+					let best_inner_seal =
+						fetch_seal::<B>(best_header.digest().logs.last(), best_hash)?;
+
+					self.algorithm.break_tie(&best_inner_seal, &inner_seal)
 				},
-			));
+			};
+			block.fork_choice = Some(ForkChoiceStrategy::Custom(is_new_best));
 		}
 
 		self.inner.import_block(block).await.map_err(Into::into)
@@ -550,7 +600,8 @@ where
 			// The worker is locked for the duration of the whole proposing period. Within this
 			// period, the mining target is outdated and useless anyway.
 
-			let difficulty = match algorithm.difficulty(best_hash) {
+			let difficulty = match algorithm.pre_digest_difficulty(best_hash, pre_runtime.as_deref())
+			{
 				Ok(x) => x,
 				Err(err) => {
 					warn!(
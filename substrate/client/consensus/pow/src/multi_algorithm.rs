@@ -0,0 +1,205 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Support for mining with several hashing algorithms on the same chain.
+//!
+//! The algorithm used to mine a block is identified by the first byte of its PoW pre-runtime
+//! digest, the remaining bytes being passed as pre-runtime digest to the algorithm itself. Each
+//! algorithm computes its own difficulty, e.g. using a
+//! [`DifficultyCalculator`](crate::difficulty::DifficultyCalculator) restricted to the blocks
+//! mined with it.
+
+use codec::{Decode, Encode};
+use sp_consensus_pow::{Seal, TotalDifficulty};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+use crate::{Error, PowAlgorithm};
+
+/// Identifier of an algorithm of a [`MultiAlgorithm`].
+pub type AlgorithmId = u8;
+
+/// Build the pre-runtime digest of a block mined with the given algorithm.
+pub fn encode_pre_digest(algorithm: AlgorithmId, inner: Option<&[u8]>) -> Vec<u8> {
+	let mut pre_digest = vec![algorithm];
+	pre_digest.extend_from_slice(inner.unwrap_or_default());
+	pre_digest
+}
+
+/// Split the pre-runtime digest of a block into the identifier of the algorithm used to mine it
+/// and the pre-runtime digest of the algorithm, if any.
+pub fn split_pre_digest(pre_digest: Option<&[u8]>) -> Option<(AlgorithmId, Option<&[u8]>)> {
+	let (algorithm, inner) = pre_digest?.split_first()?;
+	Some((*algorithm, (!inner.is_empty()).then_some(inner)))
+}
+
+/// A PoW algorithm combining several algorithms with separate difficulties.
+///
+/// The algorithms are identified by their index. As the total difficulty of the chain sums the
+/// difficulties of all the algorithms, these should be expressed in comparable units.
+pub struct MultiAlgorithm<B: BlockT, D> {
+	algorithms: Arc<Vec<Box<dyn PowAlgorithm<B, Difficulty = D> + Send + Sync>>>,
+}
+
+impl<B: BlockT, D> MultiAlgorithm<B, D> {
+	/// Create a new multi-algorithm from the given algorithms, identified by their index.
+	pub fn new(algorithms: Vec<Box<dyn PowAlgorithm<B, Difficulty = D> + Send + Sync>>) -> Self {
+		MultiAlgorithm { algorithms: Arc::new(algorithms) }
+	}
+
+	// find the algorithm a block was mined with, returning its pre-runtime digest
+	fn algorithm<'a>(
+		&self,
+		pre_digest: Option<&'a [u8]>,
+	) -> Result<(&(dyn PowAlgorithm<B, Difficulty = D> + Send + Sync), Option<&'a [u8]>), Error<B>>
+	{
+		let (id, inner) = split_pre_digest(pre_digest).ok_or_else(|| {
+			Error::Other("Mining algorithm missing from pre-runtime digest".into())
+		})?;
+		let algorithm = self
+			.algorithms
+			.get(id as usize)
+			.ok_or_else(|| Error::Other(format!("Unknown mining algorithm {}", id)))?;
+
+		Ok((algorithm.as_ref(), inner))
+	}
+}
+
+impl<B: BlockT, D> Clone for MultiAlgorithm<B, D> {
+	fn clone(&self) -> Self {
+		MultiAlgorithm { algorithms: self.algorithms.clone() }
+	}
+}
+
+impl<B, D> PowAlgorithm<B> for MultiAlgorithm<B, D>
+where
+	B: BlockT,
+	D: TotalDifficulty + Default + Encode + Decode + Ord + Clone + Copy,
+{
+	type Difficulty = D;
+
+	fn difficulty(&self, _parent: B::Hash) -> Result<Self::Difficulty, Error<B>> {
+		Err(Error::Other(
+			"The difficulty depends on the mining algorithm given in the pre-runtime digest".into(),
+		))
+	}
+
+	fn pre_digest_difficulty(
+		&self,
+		parent: B::Hash,
+		pre_digest: Option<&[u8]>,
+	) -> Result<Self::Difficulty, Error<B>> {
+		let (algorithm, pre_digest) = self.algorithm(pre_digest)?;
+		algorithm.pre_digest_difficulty(parent, pre_digest)
+	}
+
+	fn verify(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		pre_digest: Option<&[u8]>,
+		seal: &Seal,
+		difficulty: Self::Difficulty,
+	) -> Result<bool, Error<B>> {
+		let (algorithm, pre_digest) = self.algorithm(pre_digest)?;
+		algorithm.verify(parent, pre_hash, pre_digest, seal, difficulty)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::U256;
+	use sp_runtime::testing::{Block as RawBlock, MockCallU64, TestXt, H256};
+
+	type Block = RawBlock<TestXt<MockCallU64, ()>>;
+
+	// accepts the seals made of its difficulty followed by the pre-runtime digest
+	struct TestAlgorithm(u8);
+
+	impl PowAlgorithm<Block> for TestAlgorithm {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: H256) -> Result<U256, Error<Block>> {
+			Ok(self.0.into())
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &H256,
+			pre_digest: Option<&[u8]>,
+			seal: &Seal,
+			difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			let expected = encode_pre_digest(self.0, pre_digest);
+			Ok(difficulty == U256::from(self.0) && seal == &expected)
+		}
+	}
+
+	fn multi_algorithm() -> MultiAlgorithm<Block, U256> {
+		MultiAlgorithm::new(vec![Box::new(TestAlgorithm(1)), Box::new(TestAlgorithm(2))])
+	}
+
+	#[test]
+	fn pre_digest_roundtrips() {
+		let pre_digest = encode_pre_digest(3, Some(b"graffiti"));
+		assert_eq!(split_pre_digest(Some(&pre_digest)), Some((3, Some(&b"graffiti"[..]))));
+
+		let pre_digest = encode_pre_digest(1, None);
+		assert_eq!(split_pre_digest(Some(&pre_digest)), Some((1, None)));
+
+		assert_eq!(split_pre_digest(Some(&[])), None);
+		assert_eq!(split_pre_digest(None), None);
+	}
+
+	#[test]
+	fn verify_dispatches_on_pre_digest() {
+		let multi = multi_algorithm();
+		let verify = |pre_digest: &[u8], seal: &[u8], difficulty: u8| {
+			let parent = BlockId::Number(0);
+			let seal = seal.to_vec();
+			multi.verify(&parent, &H256::zero(), Some(pre_digest), &seal, difficulty.into())
+		};
+
+		// the algorithm identifier is stripped from the pre-runtime digest
+		let pre_digest = encode_pre_digest(1, Some(b"nonce"));
+		assert!(verify(&pre_digest, b"\x02nonce", 2).unwrap());
+		assert!(!verify(&pre_digest, b"\x01nonce", 1).unwrap());
+
+		let pre_digest = encode_pre_digest(0, None);
+		assert!(verify(&pre_digest, &[1], 1).unwrap());
+		assert!(!verify(&pre_digest, &[1], 2).unwrap());
+
+		// unknown and missing algorithms are rejected
+		assert!(verify(&encode_pre_digest(2, None), &[3], 3).is_err());
+		assert!(verify(&[], &[1], 1).is_err());
+	}
+
+	#[test]
+	fn difficulty_depends_on_pre_digest() {
+		let multi = multi_algorithm();
+		let parent = H256::zero();
+
+		assert!(multi.difficulty(parent).is_err());
+		assert_eq!(multi.pre_digest_difficulty(parent, Some(&[0])).unwrap(), U256::from(1));
+		assert_eq!(multi.pre_digest_difficulty(parent, Some(&[1, 42])).unwrap(), U256::from(2));
+		assert!(multi.pre_digest_difficulty(parent, Some(&[2])).is_err());
+		assert!(multi.pre_digest_difficulty(parent, None).is_err());
+	}
+}
@@ -0,0 +1,544 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Stratum-compatible mining server on top of a [`MiningHandle`].
+//!
+//! Miners connect over TCP and exchange newline delimited JSON-RPC messages:
+//!
+//! - `mining.subscribe` subscribes to the mining jobs, which are then pushed with
+//!   `mining.notify` notifications with parameters `[job_id, best_hash, pre_hash, pre_runtime,
+//!   difficulty, clean_jobs]`. Hashes and the pre-runtime digest are hex encoded, the difficulty is
+//!   hex encoded using the SCALE codec.
+//! - `mining.authorize` with parameters `[worker, password]` authorizes a worker configured in
+//!   [`StratumConfig::workers`] on the connection.
+//! - `mining.submit` with parameters `[worker, job_id, seal]` submits a hex encoded seal for the
+//!   given job on behalf of an authorized worker, returning whether the mined block was imported.
+//!
+//! Requests are limited to 16 KiB, and connections not sending any request
+//! for [`StratumConfig::idle_timeout`] are closed.
+
+use codec::Encode;
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sp_core::{
+	bytes::{from_hex, to_hex},
+	hashing::blake2_256,
+};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	collections::{HashMap, HashSet},
+	net::SocketAddr,
+	sync::Arc,
+	time::Duration,
+};
+use tokio::{
+	io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+	net::TcpListener,
+	sync::broadcast,
+	time::Instant,
+};
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+use crate::{worker::Version, MiningHandle, MiningMetadata, PowAlgorithm, LOG_TARGET};
+
+/// Interval at which the mining handle is polled for new jobs.
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Stratum error code for stale or unknown jobs.
+const JOB_NOT_FOUND: i64 = 21;
+/// Stratum error code for other errors.
+const OTHER_ERROR: i64 = 20;
+/// Stratum error code for submissions of unauthorized workers.
+const UNAUTHORIZED_WORKER: i64 = 24;
+
+/// Maximum length of a request, in bytes.
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+/// Configuration of the Stratum server.
+#[derive(Clone, Debug)]
+pub struct StratumConfig {
+	/// Address to listen on.
+	pub listen_addr: SocketAddr,
+	/// Maximum number of simultaneous miner connections, further connections are refused.
+	pub max_connections: usize,
+	/// Time after which connections not sending any request are closed.
+	pub idle_timeout: Duration,
+	/// Passwords of the workers allowed to submit seals, by worker name.
+	pub workers: HashMap<String, String>,
+}
+
+/// A mining job sent to the miners.
+#[derive(Clone)]
+struct Job {
+	id: u64,
+	notification: String,
+}
+
+#[derive(Deserialize)]
+struct Request {
+	id: Value,
+	method: String,
+	#[serde(default)]
+	params: Vec<Value>,
+}
+
+fn response(id: Value, result: Result<Value, (i64, String)>) -> String {
+	let response = match result {
+		Ok(result) => json!({ "id": id, "result": result, "error": null }),
+		Err((code, message)) => json!({ "id": id, "result": null, "error": [code, message, null] }),
+	};
+
+	response.to_string()
+}
+
+fn job_notification<H: AsRef<[u8]>, D: Encode>(id: u64, metadata: &MiningMetadata<H, D>) -> String {
+	json!({
+		"id": null,
+		"method": "mining.notify",
+		"params": [
+			format!("{:x}", id),
+			to_hex(metadata.best_hash.as_ref(), false),
+			to_hex(metadata.pre_hash.as_ref(), false),
+			metadata.pre_runtime.as_ref().map(|pre_runtime| to_hex(pre_runtime, false)),
+			to_hex(&metadata.difficulty.encode(), false),
+			true,
+		],
+	})
+	.to_string()
+}
+
+// check the worker name and password of a `mining.authorize` request
+fn authorize(params: &[Value], workers: &HashMap<String, String>) -> Option<String> {
+	let worker = params.first().and_then(Value::as_str)?;
+	let password = params.get(1).and_then(Value::as_str).unwrap_or_default();
+	let expected = workers.get(worker)?;
+
+	passwords_match(expected, password).then(|| worker.to_owned())
+}
+
+// compare the passwords in constant time, hashing them first so that neither their contents
+// nor their lengths can be inferred from the time it takes
+fn passwords_match(expected: &str, password: &str) -> bool {
+	let expected = blake2_256(expected.as_bytes());
+	let password = blake2_256(password.as_bytes());
+
+	let diff = expected.iter().zip(password.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+	std::hint::black_box(diff) == 0
+}
+
+// parse the job id and seal of a `mining.submit` request
+fn parse_submit(params: &[Value]) -> Result<(u64, Vec<u8>), (i64, String)> {
+	let invalid = |what: &str| (OTHER_ERROR, format!("Invalid {}", what));
+
+	let job_id = params.get(1).and_then(Value::as_str).ok_or_else(|| invalid("job id"))?;
+	let job_id = u64::from_str_radix(job_id, 16).map_err(|_| invalid("job id"))?;
+	let seal = params.get(2).and_then(Value::as_str).ok_or_else(|| invalid("seal"))?;
+	let seal = from_hex(seal).map_err(|_| invalid("seal"))?;
+
+	Ok((job_id, seal))
+}
+
+/// Run a Stratum-compatible mining server with the given configuration, allowing external
+/// miners to mine blocks built by the given mining handle.
+pub async fn run_stratum_server<Block, Algorithm, L, Proof>(
+	config: StratumConfig,
+	handle: MiningHandle<Block, Algorithm, L, Proof>,
+) -> std::io::Result<()>
+where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + Sync + 'static,
+	Algorithm::Difficulty: Send + 'static,
+	L: sc_consensus::JustificationSyncLink<Block> + 'static,
+	Proof: Send + 'static,
+{
+	let listener = TcpListener::bind(config.listen_addr).await?;
+	info!(target: LOG_TARGET, "⛏  Stratum server listening on {}", config.listen_addr);
+
+	let workers = Arc::new(config.workers);
+
+	let (jobs_tx, _) = broadcast::channel(16);
+	let current_job = Arc::new(Mutex::new(None::<Job>));
+	let mut last_version = None::<Version>;
+	let mut next_job_id = 0u64;
+	let mut connections = FuturesUnordered::new();
+	let mut job_poll = tokio::time::interval(JOB_POLL_INTERVAL);
+
+	loop {
+		tokio::select! {
+			accepted = listener.accept() => match accepted {
+				Ok((stream, peer)) => {
+					if connections.len() >= config.max_connections {
+						debug!(target: LOG_TARGET, "Refusing stratum connection from {}", peer);
+						continue
+					}
+
+					debug!(target: LOG_TARGET, "Stratum connection from {}", peer);
+					connections.push(handle_connection(
+						stream,
+						handle.clone(),
+						workers.clone(),
+						current_job.clone(),
+						jobs_tx.subscribe(),
+						config.idle_timeout,
+					));
+				},
+				Err(err) => warn!(target: LOG_TARGET, "Failed to accept stratum connection: {}", err),
+			},
+			_ = job_poll.tick() => {
+				let version = handle.version();
+				if last_version == Some(version) {
+					continue
+				}
+				last_version = Some(version);
+
+				let job = handle.metadata().map(|metadata| {
+					next_job_id += 1;
+					Job { id: next_job_id, notification: job_notification(next_job_id, &metadata) }
+				});
+
+				*current_job.lock() = job.clone();
+				if let Some(job) = job {
+					// there may be no subscribed miners
+					let _ = jobs_tx.send(job);
+				}
+			},
+			Some(()) = connections.next(), if !connections.is_empty() => {},
+		}
+	}
+}
+
+async fn handle_connection<S, Block, Algorithm, L, Proof>(
+	stream: S,
+	handle: MiningHandle<Block, Algorithm, L, Proof>,
+	workers: Arc<HashMap<String, String>>,
+	current_job: Arc<Mutex<Option<Job>>>,
+	mut jobs: broadcast::Receiver<Job>,
+	idle_timeout: Duration,
+) where
+	S: AsyncRead + AsyncWrite,
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + Sync + 'static,
+	Algorithm::Difficulty: Send + 'static,
+	L: sc_consensus::JustificationSyncLink<Block> + 'static,
+	Proof: Send + 'static,
+{
+	let (reader, mut writer) = tokio::io::split(stream);
+	let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(MAX_LINE_LENGTH));
+	let mut subscribed = false;
+	let mut authorized = HashSet::new();
+	let idle = tokio::time::sleep(idle_timeout);
+	tokio::pin!(idle);
+
+	loop {
+		let messages = tokio::select! {
+			line = lines.next() => match line {
+				Some(Ok(line)) => {
+					idle.as_mut().reset(Instant::now() + idle_timeout);
+
+					let request = match serde_json::from_str::<Request>(&line) {
+						Ok(request) => request,
+						Err(err) => {
+							debug!(target: LOG_TARGET, "Invalid stratum request: {}", err);
+							break
+						},
+					};
+
+					match request.method.as_str() {
+						"mining.subscribe" => {
+							subscribed = true;
+							let job = current_job.lock().clone();
+							std::iter::once(response(request.id, Ok(json!(true))))
+								.chain(job.map(|job| job.notification))
+								.collect::<Vec<_>>()
+						},
+						"mining.authorize" => {
+							let worker = authorize(&request.params, &workers);
+							let result = json!(worker.is_some());
+							authorized.extend(worker);
+							vec![response(request.id, Ok(result))]
+						},
+						"mining.submit" => {
+							let worker = request.params.first().and_then(Value::as_str);
+							let is_authorized =
+								worker.is_some_and(|worker| authorized.contains(worker));
+							let result = if is_authorized {
+								submit(&request.params, &handle, &current_job).await
+							} else {
+								Err((UNAUTHORIZED_WORKER, "Unauthorized worker".into()))
+							};
+							vec![response(request.id, result)]
+						},
+						method => vec![response(
+							request.id,
+							Err((OTHER_ERROR, format!("Unknown method {}", method))),
+						)],
+					}
+				},
+				Some(Err(err)) => {
+					debug!(target: LOG_TARGET, "Invalid stratum request: {}", err);
+					break
+				},
+				None => break,
+			},
+			_ = &mut idle => {
+				debug!(target: LOG_TARGET, "Closing idle stratum connection");
+				break
+			},
+			job = jobs.recv(), if subscribed => match job {
+				Ok(job) => vec![job.notification],
+				Err(broadcast::error::RecvError::Lagged(_)) => continue,
+				Err(broadcast::error::RecvError::Closed) => break,
+			},
+		};
+
+		for message in messages {
+			if writer.write_all(format!("{}\n", message).as_bytes()).await.is_err() {
+				return
+			}
+		}
+	}
+}
+
+async fn submit<Block, Algorithm, L, Proof>(
+	params: &[Value],
+	handle: &MiningHandle<Block, Algorithm, L, Proof>,
+	current_job: &Mutex<Option<Job>>,
+) -> Result<Value, (i64, String)>
+where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + Sync + 'static,
+	Algorithm::Difficulty: Send + 'static,
+	L: sc_consensus::JustificationSyncLink<Block> + 'static,
+	Proof: Send + 'static,
+{
+	let (job_id, seal) = parse_submit(params)?;
+
+	if current_job.lock().as_ref().map(|job| job.id) != Some(job_id) {
+		return Err((JOB_NOT_FOUND, "Job not found".into()))
+	}
+
+	// importing the block holds non-`Send` locks, so it's done on a blocking thread
+	let handle = handle.clone();
+	let imported =
+		tokio::task::spawn_blocking(move || futures::executor::block_on(handle.submit(seal)))
+			.await
+			.map_err(|err| (OTHER_ERROR, err.to_string()))?;
+
+	Ok(json!(imported))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Error;
+	use sc_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult};
+	use sp_consensus_pow::Seal;
+	use sp_core::U256;
+	use sp_runtime::{
+		generic::BlockId,
+		testing::{Block as RawBlock, MockCallU64, TestXt, H256},
+	};
+	use std::future::Future;
+	use tokio::io::{AsyncBufReadExt, BufReader, DuplexStream};
+
+	type Block = RawBlock<TestXt<MockCallU64, ()>>;
+
+	struct TestAlgorithm;
+
+	impl PowAlgorithm<Block> for TestAlgorithm {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: H256) -> Result<U256, Error<Block>> {
+			Ok(U256::one())
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &H256,
+			_pre_digest: Option<&[u8]>,
+			_seal: &Seal,
+			_difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			Ok(true)
+		}
+	}
+
+	struct TestBlockImport;
+
+	#[async_trait::async_trait]
+	impl BlockImport<Block> for TestBlockImport {
+		type Error = sp_consensus::Error;
+
+		async fn check_block(
+			&self,
+			_block: BlockCheckParams<Block>,
+		) -> Result<ImportResult, Self::Error> {
+			Ok(ImportResult::imported(false))
+		}
+
+		async fn import_block(
+			&self,
+			_block: BlockImportParams<Block>,
+		) -> Result<ImportResult, Self::Error> {
+			Ok(ImportResult::imported(false))
+		}
+	}
+
+	const JOB: u64 = 26;
+
+	// serve a connection of a worker `alice` with password `secret`, the current job being `JOB`
+	fn connection(stream: DuplexStream, idle_timeout: Duration) -> impl Future<Output = ()> {
+		let handle = MiningHandle::<_, _, _, ()>::new(TestAlgorithm, Box::new(TestBlockImport), ());
+		let workers = Arc::new(HashMap::from([("alice".to_owned(), "secret".to_owned())]));
+		let job = Job { id: JOB, notification: "notification".into() };
+		let (jobs_tx, jobs) = broadcast::channel(1);
+
+		async move {
+			let current_job = Arc::new(Mutex::new(Some(job)));
+			handle_connection(stream, handle, workers, current_job, jobs, idle_timeout).await;
+			drop(jobs_tx);
+		}
+	}
+
+	// send the given lines and close the connection, returning the lines received in response
+	async fn exchange(requests: &[String]) -> Vec<String> {
+		let (client, server) = tokio::io::duplex(4 * MAX_LINE_LENGTH);
+		let (reader, mut writer) = tokio::io::split(client);
+
+		let client = async move {
+			for request in requests {
+				writer.write_all(format!("{}\n", request).as_bytes()).await.unwrap();
+			}
+			writer.shutdown().await.unwrap();
+
+			let mut lines = BufReader::new(reader).lines();
+			let mut responses = Vec::new();
+			while let Some(line) = lines.next_line().await.unwrap() {
+				responses.push(line);
+			}
+			responses
+		};
+
+		let ((), responses) = tokio::join!(connection(server, Duration::from_secs(60)), client);
+		responses
+	}
+
+	fn request(id: u64, method: &str, params: Value) -> String {
+		json!({ "id": id, "method": method, "params": params }).to_string()
+	}
+
+	#[test]
+	fn job_notification_is_hex_encoded() {
+		let metadata = MiningMetadata {
+			best_hash: [1u8; 2],
+			pre_hash: [2u8; 2],
+			pre_runtime: Some(vec![3]),
+			difficulty: 4u32,
+		};
+
+		let notification: Value = serde_json::from_str(&job_notification(26, &metadata)).unwrap();
+
+		assert_eq!(notification["method"], "mining.notify");
+		assert_eq!(
+			notification["params"],
+			json!(["1a", "0x0101", "0x0202", "0x03", "0x04000000", true]),
+		);
+	}
+
+	#[test]
+	fn submit_params_are_parsed() {
+		let params = [json!("worker"), json!("1a"), json!("0x0102")];
+		assert_eq!(parse_submit(&params), Ok((26, vec![1, 2])));
+
+		let params = [json!("worker"), json!("not a job"), json!("0x0102")];
+		assert_eq!(parse_submit(&params), Err((OTHER_ERROR, "Invalid job id".into())));
+
+		let params = [json!("worker"), json!("1a")];
+		assert_eq!(parse_submit(&params), Err((OTHER_ERROR, "Invalid seal".into())));
+	}
+
+	#[test]
+	fn passwords_are_compared_exactly() {
+		assert!(passwords_match("secret", "secret"));
+		assert!(!passwords_match("secret", "secre"));
+		assert!(!passwords_match("secret", "secret "));
+		assert!(!passwords_match("secret", ""));
+	}
+
+	#[tokio::test]
+	async fn subscribe_sends_current_job() {
+		let responses = exchange(&[request(1, "mining.subscribe", json!([]))]).await;
+
+		assert_eq!(responses, vec![response(json!(1), Ok(json!(true))), "notification".into()]);
+	}
+
+	#[tokio::test]
+	async fn only_authorized_workers_submit() {
+		let job = format!("{:x}", JOB);
+		let responses = exchange(&[
+			request(1, "mining.submit", json!(["alice", job, "0x01"])),
+			request(2, "mining.authorize", json!(["alice", "wrong"])),
+			request(3, "mining.authorize", json!(["mallory", "secret"])),
+			request(4, "mining.authorize", json!(["alice", "secret"])),
+			request(5, "mining.submit", json!(["mallory", job, "0x01"])),
+			request(6, "mining.submit", json!(["alice", "1", "0x01"])),
+			request(7, "mining.submit", json!(["alice", job, "0x01"])),
+		])
+		.await;
+
+		let unauthorized = Err((UNAUTHORIZED_WORKER, "Unauthorized worker".into()));
+		assert_eq!(
+			responses,
+			vec![
+				response(json!(1), unauthorized.clone()),
+				response(json!(2), Ok(json!(false))),
+				response(json!(3), Ok(json!(false))),
+				response(json!(4), Ok(json!(true))),
+				response(json!(5), unauthorized),
+				response(json!(6), Err((JOB_NOT_FOUND, "Job not found".into()))),
+				// nothing is being mined, so the seal isn't imported
+				response(json!(7), Ok(json!(false))),
+			],
+		);
+	}
+
+	#[tokio::test]
+	async fn oversized_request_closes_connection() {
+		let responses = exchange(&[
+			"x".repeat(MAX_LINE_LENGTH + 1),
+			request(1, "mining.authorize", json!(["alice", "secret"])),
+		])
+		.await;
+
+		assert!(responses.is_empty());
+	}
+
+	#[tokio::test]
+	async fn idle_connection_is_closed() {
+		let (_client, server) = tokio::io::duplex(MAX_LINE_LENGTH);
+		let connection = connection(server, Duration::from_millis(10));
+
+		tokio::time::timeout(Duration::from_secs(10), connection)
+			.await
+			.expect("idle connections are closed after the timeout; qed");
+	}
+}
@@ -0,0 +1,722 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Types and functions related to authority selection and slot claiming.
+
+use std::{
+	future::Future,
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+	time::Duration,
+};
+
+use codec::Encode;
+use futures::prelude::*;
+use log::{debug, error, info, warn};
+
+use sc_client_api::{backend::AuxStore, BlockchainEvents};
+use sc_consensus::{BlockImport, BlockImportParams, StateAction};
+use sc_consensus_epochs::{
+	descendent_query, EpochIdentifier, EpochIdentifierPosition, SharedEpochChanges,
+	ViableEpochDescriptor,
+};
+use sc_consensus_slots::{InherentDataProviderExt, SlotInfo, StorageChanges};
+use sc_telemetry::TelemetryHandle;
+use sc_transaction_pool_api::OffchainTransactionPoolFactory;
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::AppCrypto;
+use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
+use sp_consensus::{BlockOrigin, Environment, Error as ConsensusError, Proposer, SelectChain};
+use sp_consensus_slots::{Slot, SlotDuration};
+use sp_core::{
+	crypto::{ByteArray, Wraps},
+	ed25519, Pair,
+};
+use sp_inherents::CreateInherentDataProviders;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor, One},
+	DigestItem,
+};
+
+use crate::{
+	aux_schema, find_next_epoch_digest, find_slot_claim, slot_claim_sign_data,
+	ticket_claim_message, vrf, AuthorityId, AuthorityIndex, Epoch, SassafrasApi,
+	SassafrasIntermediate, SassafrasLink, SlotClaim, TicketBody, TicketClaim, TicketEnvelope,
+	TicketId, INTERMEDIATE_KEY, LOG_TARGET,
+};
+
+/// Get the index of the authority entitled to claim `slot` when no ticket is associated to it.
+pub(crate) fn secondary_authority_index(slot: Slot, epoch: &Epoch) -> AuthorityIndex {
+	let hash = (epoch.randomness, slot).using_encoded(sp_crypto_hashing::blake2_64);
+	(u64::from_le_bytes(hash) % epoch.authorities.len() as u64) as AuthorityIndex
+}
+
+/// Try to claim an epoch slot.
+///
+/// If `maybe_ticket` is `Some` the slot is a primary slot and can be claimed only by the owner
+/// of the ticket, i.e. if the ticket secrets are found in [`Epoch::tickets_aux`]. Otherwise the
+/// slot is a secondary slot which can be claimed only by the authority at
+/// [`secondary_authority_index`].
+///
+/// Returns the slot claim together with the identifier of the authority claiming the slot.
+pub fn claim_slot(
+	slot: Slot,
+	epoch: &Epoch,
+	maybe_ticket: Option<(TicketId, TicketBody)>,
+	keystore: &KeystorePtr,
+) -> Option<(SlotClaim, AuthorityId)> {
+	if epoch.authorities.is_empty() {
+		return None
+	}
+
+	let (authority_idx, erased_pair) = match &maybe_ticket {
+		Some((ticket_id, ticket_body)) => {
+			debug!(target: LOG_TARGET, "[TRY PRIMARY (slot {slot}, tkt = {ticket_id:032x})]");
+			let (authority_idx, erased_seed) = epoch.tickets_aux.get(ticket_id)?;
+			let erased_pair = ed25519::Pair::from_seed(erased_seed);
+			if erased_pair.public() != ticket_body.erased_public {
+				warn!(target: LOG_TARGET, "Ticket {ticket_id:032x} erased key mismatch");
+				return None
+			}
+			(*authority_idx, Some(erased_pair))
+		},
+		None => {
+			debug!(target: LOG_TARGET, "[TRY SECONDARY (slot {slot})]");
+			(secondary_authority_index(slot, epoch), None)
+		},
+	};
+
+	let authority_id = epoch.authorities.get(authority_idx as usize)?;
+	if !keystore.has_keys(&[(authority_id.to_raw_vec(), AuthorityId::ID)]) {
+		return None
+	}
+
+	let ticket_claim = match (&maybe_ticket, erased_pair) {
+		(Some((_, ticket_body)), Some(erased_pair)) => {
+			let message = ticket_claim_message(&epoch.randomness, slot, epoch.index, authority_idx);
+			let erased_signature = erased_pair.sign(&message);
+			let data = vrf::ticket_claim_sign_data(
+				&epoch.randomness,
+				ticket_body.attempt_idx,
+				epoch.index,
+				slot,
+			);
+			let ticket_signature = keystore
+				.bandersnatch_vrf_sign(AuthorityId::ID, authority_id.as_ref(), &data)
+				.ok()
+				.flatten()?;
+			Some(TicketClaim { erased_signature, ticket_signature })
+		},
+		_ => None,
+	};
+
+	let ticket_body = maybe_ticket.as_ref().map(|(_, body)| body);
+	let data = slot_claim_sign_data(&epoch.randomness, slot, epoch.index, ticket_body);
+	let vrf_signature = keystore
+		.bandersnatch_vrf_sign(AuthorityId::ID, authority_id.as_ref(), &data)
+		.ok()
+		.flatten()?;
+
+	let claim = SlotClaim { authority_idx, slot, vrf_signature, ticket_claim };
+	Some((claim, authority_id.clone()))
+}
+
+/// Generate the tickets for the given epoch.
+///
+/// Tickets are generated for all the epoch authorities we have the keys for. Only the tickets
+/// with an identifier below the epoch threshold are returned. The secrets required to later claim
+/// the tickets are stored in [`Epoch::tickets_aux`].
+pub fn generate_epoch_tickets(
+	epoch: &mut Epoch,
+	keystore: &KeystorePtr,
+	ring_ctx: &vrf::RingContext,
+) -> Vec<TicketEnvelope> {
+	let mut tickets = Vec::new();
+	let mut tickets_aux = Vec::new();
+
+	let threshold = sp_consensus_sassafras::ticket_id_threshold(
+		epoch.config.redundancy_factor,
+		epoch.length,
+		epoch.config.attempts_number,
+		epoch.authorities.len() as u32,
+	);
+	debug!(target: LOG_TARGET, "Tickets threshold: {threshold:032x}");
+
+	let pks: Vec<_> = epoch.authorities.iter().map(|a| *a.as_inner_ref()).collect();
+
+	for (authority_idx, authority_id) in epoch.authorities.iter().enumerate() {
+		if !keystore.has_keys(&[(authority_id.to_raw_vec(), AuthorityId::ID)]) {
+			continue
+		}
+
+		debug!(target: LOG_TARGET, ">>> Generating new ring prover key...");
+		let prover = ring_ctx.prover(&pks, authority_idx);
+		debug!(target: LOG_TARGET, ">>> ...done");
+
+		for attempt_idx in 0..epoch.config.attempts_number {
+			let vrf_input = vrf::ticket_id_input(&epoch.randomness, attempt_idx, epoch.index);
+
+			let Ok(Some(pre_output)) = keystore.bandersnatch_vrf_pre_output(
+				AuthorityId::ID,
+				authority_id.as_ref(),
+				&vrf_input,
+			) else {
+				continue
+			};
+
+			let ticket_id = vrf::make_ticket_id(&pre_output);
+			if ticket_id >= threshold {
+				continue
+			}
+
+			// The erased key is a one time key used to claim the ticket and is forgotten once
+			// the slot has been claimed.
+			let (erased_pair, erased_seed) = ed25519::Pair::generate();
+			// The revealed key is deterministically derived from the ticket VRF output and thus
+			// can be recomputed by the ticket owner only.
+			let revealed_seed = sp_crypto_hashing::blake2_256(
+				&[b"sassafras-revealed".as_slice(), &pre_output.make_bytes()].concat(),
+			);
+			let revealed_public = ed25519::Pair::from_seed(&revealed_seed).public();
+
+			let body =
+				TicketBody { attempt_idx, erased_public: erased_pair.public(), revealed_public };
+
+			debug!(target: LOG_TARGET, ">>> Creating ring proof for attempt {}", attempt_idx);
+			let sign_data = vrf::ticket_body_sign_data(&body, vrf_input);
+
+			let Ok(Some(signature)) = keystore.bandersnatch_ring_vrf_sign(
+				AuthorityId::ID,
+				authority_id.as_ref(),
+				&sign_data,
+				&prover,
+			) else {
+				continue
+			};
+			debug!(target: LOG_TARGET, ">>> ...done");
+
+			tickets.push(TicketEnvelope { body, signature });
+			tickets_aux.push((ticket_id, (authority_idx as AuthorityIndex, erased_seed)));
+		}
+	}
+
+	epoch.tickets_aux.extend(tickets_aux);
+	tickets
+}
+
+struct SassafrasSlotWorker<B: BlockT, C, E, I, SO, L> {
+	client: Arc<C>,
+	block_import: I,
+	env: E,
+	sync_oracle: SO,
+	justification_sync_link: L,
+	force_authoring: bool,
+	keystore: KeystorePtr,
+	epoch_changes: SharedEpochChanges<B, Epoch>,
+	genesis_config: sp_consensus_sassafras::Epoch,
+	block_proposal_slot_portion: crate::SlotProportion,
+	max_block_proposal_slot_portion: Option<crate::SlotProportion>,
+	telemetry: Option<TelemetryHandle>,
+}
+
+#[async_trait::async_trait]
+impl<B, C, E, I, ER, SO, L> sc_consensus_slots::SimpleSlotWorker<B>
+	for SassafrasSlotWorker<B, C, E, I, SO, L>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + HeaderMetadata<B, Error = ClientError>,
+	C::Api: SassafrasApi<B>,
+	E: Environment<B, Error = ER> + Send + Sync,
+	E::Proposer: Proposer<B, Error = ER>,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SO: sp_consensus::SyncOracle + Send + Clone + Sync,
+	L: sc_consensus::JustificationSyncLink<B>,
+	ER: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
+{
+	type Claim = (SlotClaim, AuthorityId);
+	type SyncOracle = SO;
+	type JustificationSyncLink = L;
+	type CreateProposer =
+		Pin<Box<dyn Future<Output = Result<E::Proposer, ConsensusError>> + Send + 'static>>;
+	type Proposer = E::Proposer;
+	type BlockImport = I;
+	type AuxData = ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>;
+
+	fn logging_target(&self) -> &'static str {
+		LOG_TARGET
+	}
+
+	fn block_import(&mut self) -> &mut Self::BlockImport {
+		&mut self.block_import
+	}
+
+	fn aux_data(&self, parent: &B::Header, slot: Slot) -> Result<Self::AuxData, ConsensusError> {
+		self.epoch_changes
+			.shared_data()
+			.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&parent.hash(),
+				*parent.number(),
+				slot,
+			)
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.ok_or(ConsensusError::InvalidAuthoritiesSet)
+	}
+
+	fn authorities_len(&self, epoch_descriptor: &Self::AuxData) -> Option<usize> {
+		self.epoch_changes
+			.shared_data()
+			.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.genesis_config, slot))
+			.map(|epoch| epoch.as_ref().authorities.len())
+	}
+
+	async fn claim_slot(
+		&mut self,
+		parent_header: &B::Header,
+		slot: Slot,
+		epoch_descriptor: &ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>,
+	) -> Option<Self::Claim> {
+		debug!(target: LOG_TARGET, "Attempting to claim slot {}", slot);
+
+		// Get the next slot ticket from the runtime.
+		let maybe_ticket = self
+			.client
+			.runtime_api()
+			.slot_ticket(parent_header.hash(), slot)
+			.ok()
+			.unwrap_or_default();
+
+		let claim = claim_slot(
+			slot,
+			self.epoch_changes
+				.shared_data()
+				.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.genesis_config, slot))?
+				.as_ref(),
+			maybe_ticket,
+			&self.keystore,
+		);
+
+		if claim.is_some() {
+			debug!(target: LOG_TARGET, "Claimed slot {}", slot);
+		}
+
+		claim
+	}
+
+	fn pre_digest_data(&self, _slot: Slot, (claim, _): &Self::Claim) -> Vec<DigestItem> {
+		vec![DigestItem::from(claim)]
+	}
+
+	async fn block_import_params(
+		&self,
+		header: B::Header,
+		header_hash: &B::Hash,
+		body: Vec<B::Extrinsic>,
+		storage_changes: StorageChanges<B>,
+		(_, public): Self::Claim,
+		epoch_descriptor: Self::AuxData,
+	) -> Result<BlockImportParams<B>, ConsensusError> {
+		let signature: crate::AuthoritySignature = self
+			.keystore
+			.bandersnatch_sign(AuthorityId::ID, public.as_ref(), header_hash.as_ref())
+			.map_err(|e| ConsensusError::CannotSign(format!("{}. Key: {:?}", e, public)))?
+			.ok_or_else(|| {
+				ConsensusError::CannotSign(format!(
+					"Could not find key in keystore. Key: {:?}",
+					public
+				))
+			})?
+			.into();
+
+		let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+		import_block.post_digests.push(DigestItem::from(&signature));
+		import_block.body = Some(body);
+		import_block.state_action =
+			StateAction::ApplyChanges(sc_consensus::StorageChanges::Changes(storage_changes));
+		import_block
+			.insert_intermediate(INTERMEDIATE_KEY, SassafrasIntermediate::<B> { epoch_descriptor });
+
+		Ok(import_block)
+	}
+
+	fn force_authoring(&self) -> bool {
+		self.force_authoring
+	}
+
+	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
+		&mut self.sync_oracle
+	}
+
+	fn justification_sync_link(&mut self) -> &mut Self::JustificationSyncLink {
+		&mut self.justification_sync_link
+	}
+
+	fn proposer(&mut self, block: &B::Header) -> Self::CreateProposer {
+		Box::pin(self.env.init(block).map_err(|e| ConsensusError::ClientImport(e.to_string())))
+	}
+
+	fn telemetry(&self) -> Option<TelemetryHandle> {
+		self.telemetry.clone()
+	}
+
+	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> Duration {
+		let parent_slot = find_slot_claim::<B>(&slot_info.chain_head).ok().map(|claim| claim.slot);
+
+		sc_consensus_slots::proposing_remaining_duration(
+			parent_slot,
+			slot_info,
+			&self.block_proposal_slot_portion,
+			self.max_block_proposal_slot_portion.as_ref(),
+			sc_consensus_slots::SlotLenienceType::Exponential,
+			self.logging_target(),
+		)
+	}
+}
+
+/// Generate and submit the tickets for the next epoch each time its parameters are announced.
+async fn start_tickets_worker<B, C, SC>(
+	client: Arc<C>,
+	keystore: KeystorePtr,
+	epoch_changes: SharedEpochChanges<B, Epoch>,
+	select_chain: SC,
+	offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+) where
+	B: BlockT,
+	C: BlockchainEvents<B> + ProvideRuntimeApi<B> + AuxStore,
+	C::Api: SassafrasApi<B>,
+	SC: SelectChain<B> + 'static,
+{
+	let mut notifications = client.import_notification_stream();
+
+	while let Some(notification) = notifications.next().await {
+		let epoch_desc = match find_next_epoch_digest::<B>(&notification.header) {
+			Ok(Some(desc)) => desc,
+			Err(err) => {
+				warn!(target: LOG_TARGET, "Error fetching next epoch digest: {}", err);
+				continue
+			},
+			_ => continue,
+		};
+
+		debug!(target: LOG_TARGET, "Loading epoch data for {:?}", epoch_desc);
+
+		let number = *notification.header.number();
+		let position = if number == One::one() {
+			EpochIdentifierPosition::Genesis1
+		} else {
+			EpochIdentifierPosition::Regular
+		};
+		let epoch_identifier = EpochIdentifier { position, hash: notification.hash, number };
+
+		let maybe_epoch = epoch_changes.shared_data().epoch(&epoch_identifier).cloned();
+		let mut epoch = match maybe_epoch {
+			Some(epoch) => epoch,
+			None => {
+				warn!(
+					target: LOG_TARGET,
+					"Unexpected missing epoch data for {:?}", epoch_identifier
+				);
+				continue
+			},
+		};
+
+		// Get the best block on which we will publish the tickets.
+		let best_hash = match select_chain.best_chain().await {
+			Ok(header) => header.hash(),
+			Err(err) => {
+				error!(target: LOG_TARGET, "Error fetching best chain block id: {}", err);
+				continue
+			},
+		};
+
+		let ring_ctx = match client.runtime_api().ring_context(best_hash) {
+			Ok(Some(ctx)) => ctx,
+			Ok(None) => {
+				info!(target: LOG_TARGET, "Ring context not initialized yet");
+				continue
+			},
+			Err(err) => {
+				error!(target: LOG_TARGET, "Unable to read ring context: {}", err);
+				continue
+			},
+		};
+
+		let tickets = generate_epoch_tickets(&mut epoch, &keystore, &ring_ctx);
+		if tickets.is_empty() {
+			continue
+		}
+
+		// Register the offchain tx pool to be able to use it from the runtime.
+		let mut runtime_api = client.runtime_api();
+		runtime_api
+			.register_extension(offchain_tx_pool_factory.offchain_transaction_pool(best_hash));
+
+		let err = match runtime_api.submit_tickets_unsigned_extrinsic(best_hash, tickets) {
+			Err(err) => Some(err.to_string()),
+			Ok(false) => Some("Unknown reason".to_string()),
+			_ => None,
+		};
+
+		match err {
+			None => {
+				// Cache tickets secret in the epoch changes tree.
+				let mut shared_data = epoch_changes.shared_data();
+				if let Some(target_epoch) = shared_data.epoch_mut(&epoch_identifier) {
+					target_epoch.tickets_aux = epoch.tickets_aux;
+				}
+				let res = aux_schema::write_epoch_changes::<B, _, _>(&shared_data, |insert| {
+					client.insert_aux(insert, [])
+				});
+				if let Err(err) = res {
+					warn!(target: LOG_TARGET, "Unable to persist tickets secrets: {}", err);
+				}
+			},
+			Some(err) => {
+				error!(target: LOG_TARGET, "Unable to submit tickets: {}", err);
+			},
+		}
+	}
+}
+
+/// Parameters for Sassafras.
+pub struct SassafrasParams<B: BlockT, C, SC, EN, I, SO, L, CIDP> {
+	/// The client to use
+	pub client: Arc<C>,
+	/// The keystore that manages the keys of the node.
+	pub keystore: KeystorePtr,
+	/// The chain selection strategy
+	pub select_chain: SC,
+	/// The environment we are producing blocks for.
+	pub env: EN,
+	/// The underlying block-import object to supply our produced blocks to.
+	/// This must be a `SassafrasBlockImport` or a wrapper of it, otherwise
+	/// critical consensus logic will be omitted.
+	pub block_import: I,
+	/// A sync oracle
+	pub sync_oracle: SO,
+	/// Hook into the sync module to control the justification sync process.
+	pub justification_sync_link: L,
+	/// Something that can create the inherent data providers.
+	pub create_inherent_data_providers: CIDP,
+	/// Force authoring of blocks even if we are offline
+	pub force_authoring: bool,
+	/// State shared between the import queue and the authoring logic.
+	pub sassafras_link: SassafrasLink<B>,
+	/// The slot duration.
+	pub slot_duration: SlotDuration,
+	/// The proportion of the slot dedicated to proposing.
+	pub block_proposal_slot_portion: crate::SlotProportion,
+	/// The maximum proportion of the slot dedicated to proposing with any lenience factor applied
+	/// due to no blocks being produced.
+	pub max_block_proposal_slot_portion: Option<crate::SlotProportion>,
+	/// Handle use to report telemetries.
+	pub telemetry: Option<TelemetryHandle>,
+	/// The offchain transaction pool factory.
+	///
+	/// Will be used when submitting the epoch tickets.
+	pub offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+}
+
+/// Start the Sassafras worker.
+pub fn start_sassafras<B, C, SC, EN, I, SO, L, CIDP, ER>(
+	SassafrasParams {
+		client,
+		keystore,
+		select_chain,
+		env,
+		block_import,
+		sync_oracle,
+		justification_sync_link,
+		create_inherent_data_providers,
+		force_authoring,
+		sassafras_link,
+		slot_duration,
+		block_proposal_slot_portion,
+		max_block_proposal_slot_portion,
+		telemetry,
+		offchain_tx_pool_factory,
+	}: SassafrasParams<B, C, SC, EN, I, SO, L, CIDP>,
+) -> Result<SassafrasWorker, ConsensusError>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>
+		+ HeaderBackend<B>
+		+ HeaderMetadata<B, Error = ClientError>
+		+ BlockchainEvents<B>
+		+ AuxStore
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: SassafrasApi<B>,
+	SC: SelectChain<B> + 'static,
+	EN: Environment<B, Error = ER> + Send + Sync + 'static,
+	EN::Proposer: Proposer<B, Error = ER>,
+	I: BlockImport<B, Error = ConsensusError> + Send + Sync + 'static,
+	SO: sp_consensus::SyncOracle + Send + Sync + Clone + 'static,
+	L: sc_consensus::JustificationSyncLink<B> + 'static,
+	CIDP: CreateInherentDataProviders<B, ()> + Send + Sync + 'static,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send,
+	ER: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
+{
+	info!(target: LOG_TARGET, "🍁 Starting Sassafras Authorship worker");
+
+	let slot_worker = SassafrasSlotWorker {
+		client: client.clone(),
+		block_import,
+		env,
+		sync_oracle: sync_oracle.clone(),
+		justification_sync_link,
+		force_authoring,
+		keystore: keystore.clone(),
+		epoch_changes: sassafras_link.epoch_changes.clone(),
+		genesis_config: sassafras_link.genesis_config,
+		block_proposal_slot_portion,
+		max_block_proposal_slot_portion,
+		telemetry,
+	};
+
+	let slot_worker = sc_consensus_slots::start_slot_worker(
+		slot_duration,
+		select_chain.clone(),
+		sc_consensus_slots::SimpleSlotWorkerToSlotWorker(slot_worker),
+		sync_oracle,
+		create_inherent_data_providers,
+	);
+
+	let tickets_worker = start_tickets_worker(
+		client,
+		keystore,
+		sassafras_link.epoch_changes,
+		select_chain,
+		offchain_tx_pool_factory,
+	);
+
+	let inner = future::select(Box::pin(slot_worker), Box::pin(tickets_worker));
+
+	Ok(SassafrasWorker { inner: Box::pin(inner.map(|_| ())) })
+}
+
+/// Worker for Sassafras which implements `Future<Output=()>`. This must be polled.
+#[must_use]
+pub struct SassafrasWorker {
+	inner: Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
+}
+
+impl Future for SassafrasWorker {
+	type Output = ();
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::AuthorityPair;
+	use sp_consensus_sassafras::EpochConfiguration;
+	use sp_core::crypto::VrfPublic;
+	use sp_keystore::{testing::MemoryKeystore, Keystore};
+
+	fn create_epoch(keystore: &MemoryKeystore) -> Epoch {
+		let authority =
+			keystore.bandersnatch_generate_new(AuthorityId::ID, Some("//Alice")).unwrap();
+		let other = AuthorityPair::from_string("//Bob", None).unwrap().public();
+		sp_consensus_sassafras::Epoch {
+			index: 1,
+			start: 6.into(),
+			length: 6,
+			randomness: [3; 32],
+			authorities: vec![authority.into(), other],
+			config: EpochConfiguration { redundancy_factor: 2, attempts_number: 8 },
+		}
+		.into()
+	}
+
+	#[test]
+	fn claim_secondary_slots() {
+		let keystore = MemoryKeystore::new();
+		let epoch = create_epoch(&keystore);
+		let keystore: KeystorePtr = keystore.into();
+
+		for slot in *epoch.start..*epoch.start + epoch.length as u64 {
+			let slot = Slot::from(slot);
+			let expected_idx = secondary_authority_index(slot, &epoch);
+			match claim_slot(slot, &epoch, None, &keystore) {
+				Some((claim, id)) => {
+					assert_eq!(expected_idx, 0);
+					assert_eq!(claim.authority_idx, 0);
+					assert!(claim.ticket_claim.is_none());
+					let data = slot_claim_sign_data(&epoch.randomness, slot, epoch.index, None);
+					assert!(id.as_inner_ref().vrf_verify(&data, &claim.vrf_signature));
+				},
+				None => assert_eq!(expected_idx, 1),
+			}
+		}
+	}
+
+	#[test]
+	fn generate_and_claim_tickets() {
+		let keystore = MemoryKeystore::new();
+		let mut epoch = create_epoch(&keystore);
+		let keystore: KeystorePtr = keystore.into();
+		let ring_ctx = vrf::RingContext::new_testing();
+
+		let tickets = generate_epoch_tickets(&mut epoch, &keystore, &ring_ctx);
+		assert!(!tickets.is_empty());
+		assert_eq!(tickets.len(), epoch.tickets_aux.len());
+
+		let pks: Vec<_> = epoch.authorities.iter().map(|a| *a.as_inner_ref()).collect();
+		let verifier = ring_ctx.verifier(&pks);
+		for ticket in &tickets {
+			let input =
+				vrf::ticket_id_input(&epoch.randomness, ticket.body.attempt_idx, epoch.index);
+			let sign_data = vrf::ticket_body_sign_data(&ticket.body, input);
+			assert!(ticket.signature.ring_vrf_verify(&sign_data, &verifier));
+		}
+
+		// Claim a slot using one of our tickets.
+		let ticket = tickets[0].clone();
+		let ticket_id = vrf::make_ticket_id(&ticket.signature.pre_output);
+		let slot = epoch.start + 1;
+		let (claim, id) =
+			claim_slot(slot, &epoch, Some((ticket_id, ticket.body.clone())), &keystore).unwrap();
+		let ticket_claim = claim.ticket_claim.unwrap();
+		let message =
+			ticket_claim_message(&epoch.randomness, slot, epoch.index, claim.authority_idx);
+		assert!(ed25519::Pair::verify(
+			&ticket_claim.erased_signature,
+			&message,
+			&ticket.body.erased_public
+		));
+		let data = vrf::ticket_claim_sign_data(
+			&epoch.randomness,
+			ticket.body.attempt_idx,
+			epoch.index,
+			slot,
+		);
+		assert!(id.as_inner_ref().vrf_verify(&data, &ticket_claim.ticket_signature));
+		assert_eq!(vrf::make_ticket_id(&ticket_claim.ticket_signature.pre_output), ticket_id);
+
+		// Tickets we don't own can't be claimed.
+		epoch.tickets_aux.clear();
+		assert!(claim_slot(slot, &epoch, Some((ticket_id, ticket.body)), &keystore).is_none());
+	}
+}
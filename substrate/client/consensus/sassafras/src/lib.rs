@@ -0,0 +1,416 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Sassafras
+//!
+//! Sassafras is a slot-based block production mechanism which assigns each slot of an epoch to
+//! at most one authority, using a ring-VRF based lottery.
+//!
+//! Once the parameters of epoch N+1 are announced (i.e. during epoch N), each authority generates
+//! a number of tickets for epoch N+1 using the epoch randomness and its own VRF key. Tickets whose
+//! identifier is below a threshold computed from the epoch configuration are anonymously submitted
+//! on-chain together with a ring-VRF proof that they were produced by a member of the epoch
+//! authorities set. The runtime sorts the submitted tickets and associates them to the slots of
+//! epoch N+1 following an "outside-in" strategy.
+//!
+//! When authoring, a node claims a slot (primary claim) if the ticket associated to it is one of
+//! the tickets it has submitted. The claim includes a signature produced with the ephemeral key
+//! committed in the ticket body and a VRF signature of the ticket identifier input produced with
+//! the authority key, which together prove ownership of the ticket. Slots without an
+//! associated ticket are claimed (secondary claim) by the authority at index:
+//!
+//! `blake2_64(epoch_randomness ++ slot_number) % authorities_len`.
+//!
+//! The fork choice rule is weight-based, where weight equals the number of primary blocks in the
+//! chain. We will pick the heaviest chain (more primary blocks) and will go with the longest one
+//! in case of a tie.
+//!
+//! An in-depth description of the protocol can be found here:
+//! <https://research.web3.foundation/Polkadot/protocols/block-production/SASSAFRAS>
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use std::{
+	collections::BTreeMap,
+	ops::{Deref, DerefMut},
+};
+
+use codec::{Decode, Encode};
+use log::{debug, trace};
+
+use sc_client_api::{backend::AuxStore, UsageProvider};
+use sc_consensus_epochs::Epoch as EpochT;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::Result as ClientResult;
+use sp_consensus_slots::Slot;
+use sp_runtime::{
+	generic::OpaqueDigestItemId,
+	traits::{Block as BlockT, Header, Zero},
+};
+
+pub use sc_consensus_slots::SlotProportion;
+pub use sp_consensus::SyncOracle;
+pub use sp_consensus_sassafras::{
+	digests::{ConsensusLog, NextEpochDescriptor, SlotClaim},
+	vrf, AuthorityId, AuthorityIndex, AuthorityPair, AuthoritySignature, EpochConfiguration,
+	Randomness, SassafrasApi, SassafrasBlockWeight, TicketBody, TicketClaim, TicketEnvelope,
+	TicketId, SASSAFRAS_ENGINE_ID,
+};
+
+pub use authorship::{start_sassafras, SassafrasParams, SassafrasWorker};
+pub use block_import::{block_import, SassafrasBlockImport};
+pub use verification::{import_queue, ImportQueueParams, SassafrasVerifier};
+
+mod block_import;
+#[cfg(test)]
+mod tests;
+mod verification;
+
+pub mod authorship;
+pub mod aux_schema;
+
+const LOG_TARGET: &str = "sassafras";
+
+/// Secret seed of the ephemeral key committed in a ticket body as `erased_public`.
+pub type ErasedSeed = [u8; 32];
+
+/// Sassafras epoch information.
+///
+/// Wraps the runtime epoch information together with the secrets of the tickets submitted by the
+/// local authorities for the epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct Epoch {
+	inner: sp_consensus_sassafras::Epoch,
+	/// Secrets of the tickets submitted by the local authorities, indexed by ticket identifier.
+	pub tickets_aux: BTreeMap<TicketId, (AuthorityIndex, ErasedSeed)>,
+}
+
+impl Deref for Epoch {
+	type Target = sp_consensus_sassafras::Epoch;
+
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+impl DerefMut for Epoch {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.inner
+	}
+}
+
+impl From<sp_consensus_sassafras::Epoch> for Epoch {
+	fn from(epoch: sp_consensus_sassafras::Epoch) -> Self {
+		Epoch { inner: epoch, tickets_aux: Default::default() }
+	}
+}
+
+impl EpochT for Epoch {
+	type NextEpochDescriptor = NextEpochDescriptor;
+	type Slot = Slot;
+
+	fn increment(&self, descriptor: NextEpochDescriptor) -> Epoch {
+		sp_consensus_sassafras::Epoch {
+			index: self.index + 1,
+			start: self.start + self.length as u64,
+			length: self.length,
+			randomness: descriptor.randomness,
+			authorities: descriptor.authorities,
+			config: descriptor.config.unwrap_or(self.config),
+		}
+		.into()
+	}
+
+	fn start_slot(&self) -> Slot {
+		self.start
+	}
+
+	fn end_slot(&self) -> Slot {
+		self.start + self.length as u64
+	}
+}
+
+impl Epoch {
+	/// Create the genesis epoch (epoch #0).
+	///
+	/// This is defined to start at the slot of the first block, so that has to be provided.
+	pub fn genesis(config: &sp_consensus_sassafras::Epoch, slot: Slot) -> Epoch {
+		sp_consensus_sassafras::Epoch { index: 0, start: slot, ..config.clone() }.into()
+	}
+
+	/// Clone and tweak epoch information to refer to the specified slot.
+	///
+	/// All the information which depends on the slot value is recomputed and assigned
+	/// to the returned epoch instance.
+	///
+	/// The `slot` must be greater than or equal the original epoch start slot,
+	/// if is less this operation is equivalent to a simple clone.
+	pub fn clone_for_slot(&self, slot: Slot) -> Epoch {
+		let mut epoch = self.clone();
+
+		let skipped_epochs = *slot.saturating_sub(self.start) / self.length as u64;
+
+		let index = epoch.index.checked_add(skipped_epochs).expect(
+			"epoch number is u64; it should be strictly smaller than number of slots; \
+				slots relate in some way to wall clock time; \
+				if u64 is not enough we should crash for safety; qed.",
+		);
+
+		let start = skipped_epochs
+			.checked_mul(epoch.length as u64)
+			.and_then(|skipped_slots| epoch.start.checked_add(skipped_slots))
+			.expect(
+				"slot number is u64; it should relate in some way to wall clock time; \
+				 if u64 is not enough we should crash for safety; qed.",
+			);
+
+		epoch.index = index;
+		epoch.start = start.into();
+		// Tickets are not valid for any other epoch.
+		if skipped_epochs > 0 {
+			epoch.tickets_aux.clear();
+		}
+
+		epoch
+	}
+}
+
+/// Errors encountered by the Sassafras authorship task.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<B: BlockT> {
+	/// Multiple Sassafras pre-runtime digests
+	#[error("Multiple Sassafras pre-runtime digests, rejecting!")]
+	MultiplePreRuntimeDigests,
+	/// No Sassafras pre-runtime digest found
+	#[error("No Sassafras pre-runtime digest found")]
+	NoPreRuntimeDigest,
+	/// Multiple Sassafras epoch change digests
+	#[error("Multiple Sassafras epoch change digests, rejecting!")]
+	MultipleEpochChangeDigests,
+	/// Could not fetch epoch
+	#[error("Could not fetch epoch at {0:?}")]
+	FetchEpoch(B::Hash),
+	/// Header rejected: too far in the future
+	#[error("Header {0:?} rejected: too far in the future")]
+	TooFarInFuture(B::Hash),
+	/// Parent unavailable. Cannot import
+	#[error("Parent ({0}) of {1} unavailable. Cannot import")]
+	ParentUnavailable(B::Hash, B::Hash),
+	/// Slot number must increase
+	#[error("Slot number must increase: parent slot: {0}, this slot: {1}")]
+	SlotMustIncrease(Slot, Slot),
+	/// Header has a bad seal
+	#[error("Header {0:?} has a bad seal")]
+	HeaderBadSeal(B::Hash),
+	/// Header is unsealed
+	#[error("Header {0:?} is unsealed")]
+	HeaderUnsealed(B::Hash),
+	/// Slot author not found
+	#[error("Slot author not found")]
+	SlotAuthorNotFound,
+	/// Bad signature
+	#[error("Bad signature on {0:?}")]
+	BadSignature(B::Hash),
+	/// Invalid author: Expected secondary author
+	#[error("Invalid author: Expected secondary author index: {0}, got: {1}.")]
+	InvalidAuthor(AuthorityIndex, AuthorityIndex),
+	/// Slot is associated to a ticket but the claim is not a primary claim
+	#[error("Expected a ticket claim for slot {0}")]
+	ExpectedTicketClaim(Slot),
+	/// Slot is not associated to any ticket but the claim is a primary claim
+	#[error("Unexpected ticket claim for slot {0}")]
+	UnexpectedTicketClaim(Slot),
+	/// Ticket claim signature is not produced by the ticket erased key
+	#[error("Bad ticket claim signature on {0:?}")]
+	BadTicketClaim(B::Hash),
+	/// Claimed ticket was not generated by the claiming authority
+	#[error("Claimed ticket not owned by the block author on {0:?}")]
+	TicketNotOwned(B::Hash),
+	/// VRF verification failed
+	#[error("VRF verification failed")]
+	VrfVerificationFailed,
+	/// Could not fetch parent header
+	#[error("Could not fetch parent header: {0}")]
+	FetchParentHeader(sp_blockchain::Error),
+	/// Expected epoch change to happen.
+	#[error("Expected epoch change to happen at {0:?}, s{1}")]
+	ExpectedEpochChange(B::Hash, Slot),
+	/// Unexpected epoch change
+	#[error("Unexpected epoch change")]
+	UnexpectedEpochChange,
+	/// Parent block has no associated weight
+	#[error("Parent block of {0} has no associated weight")]
+	ParentBlockNoAssociatedWeight(B::Hash),
+	/// Check inherents error
+	#[error("Checking inherents failed: {0}")]
+	CheckInherents(sp_inherents::Error),
+	/// Unhandled check inherents error
+	#[error("Checking inherents unhandled error: {}", String::from_utf8_lossy(.0))]
+	CheckInherentsUnhandled(sp_inherents::InherentIdentifier),
+	/// Create inherents error.
+	#[error("Creating inherents failed: {0}")]
+	CreateInherents(sp_inherents::Error),
+	/// Client error
+	#[error(transparent)]
+	Client(sp_blockchain::Error),
+	/// Runtime Api error.
+	#[error(transparent)]
+	RuntimeApi(sp_api::ApiError),
+	/// Fork tree error
+	#[error(transparent)]
+	ForkTree(Box<fork_tree::Error<sp_blockchain::Error>>),
+}
+
+impl<B: BlockT> From<Error<B>> for String {
+	fn from(error: Error<B>) -> String {
+		error.to_string()
+	}
+}
+
+fn sassafras_err<B: BlockT>(error: Error<B>) -> Error<B> {
+	debug!(target: LOG_TARGET, "{}", error);
+	error
+}
+
+/// Intermediate value passed to block importer.
+pub struct SassafrasIntermediate<B: BlockT> {
+	/// The epoch descriptor.
+	pub epoch_descriptor: sc_consensus_epochs::ViableEpochDescriptor<
+		B::Hash,
+		sp_runtime::traits::NumberFor<B>,
+		Epoch,
+	>,
+}
+
+/// Intermediate key for Sassafras engine.
+pub static INTERMEDIATE_KEY: &[u8] = b"sass1";
+
+/// Read the genesis epoch configuration from the runtime state at the last finalized block.
+pub fn finalized_configuration<B: BlockT, C>(
+	client: &C,
+) -> ClientResult<sp_consensus_sassafras::Epoch>
+where
+	C: AuxStore + ProvideRuntimeApi<B> + UsageProvider<B>,
+	C::Api: SassafrasApi<B>,
+{
+	let info = client.usage_info().chain;
+	let hash = info.finalized_state.map(|(hash, _)| hash).unwrap_or_else(|| {
+		debug!(target: LOG_TARGET, "No finalized state is available. Reading config from genesis");
+		info.genesis_hash
+	});
+
+	Ok(client.runtime_api().current_epoch(hash)?)
+}
+
+/// Message signed with the ticket erased key to claim the ticket's slot.
+///
+/// The claiming authority index is included so that the signature can't be replayed by
+/// another authority.
+fn ticket_claim_message(
+	randomness: &Randomness,
+	slot: Slot,
+	epoch: u64,
+	authority_idx: AuthorityIndex,
+) -> Vec<u8> {
+	(b"sassafras-claim", randomness, slot, epoch, authority_idx).encode()
+}
+
+/// Data to be signed with the authority VRF key to claim a slot.
+///
+/// For primary claims the ticket body is included as additional data.
+fn slot_claim_sign_data(
+	randomness: &Randomness,
+	slot: Slot,
+	epoch: u64,
+	ticket_body: Option<&TicketBody>,
+) -> vrf::VrfSignData {
+	let mut sign_data = vrf::slot_claim_sign_data(randomness, slot, epoch);
+	if let Some(body) = ticket_body {
+		sign_data.aux_data = body.encode();
+	}
+	sign_data
+}
+
+/// Extract the Sassafras slot claim from the given header.
+///
+/// Slot claim digest is mandatory, the function will return `Err` if none is found.
+pub fn find_slot_claim<B: BlockT>(header: &B::Header) -> Result<SlotClaim, Error<B>> {
+	if header.number().is_zero() {
+		// Genesis block doesn't contain a slot claim so let's generate a
+		// dummy one to not break any invariants in the rest of the code.
+		use sp_core::crypto::{Pair, VrfSecret};
+		let pair = sp_core::bandersnatch::Pair::from_seed(&[0u8; 32]);
+		let data = vrf::slot_claim_sign_data(&Default::default(), 0.into(), 0);
+		return Ok(SlotClaim {
+			authority_idx: 0,
+			slot: 0.into(),
+			vrf_signature: pair.vrf_sign(&data),
+			ticket_claim: None,
+		})
+	}
+
+	let mut claim: Option<_> = None;
+	for log in header.digest().logs() {
+		trace!(target: LOG_TARGET, "Checking log {:?}, looking for slot claim digest", log);
+		match (SlotClaim::try_from(log), claim.is_some()) {
+			(Ok(_), true) => return Err(sassafras_err(Error::MultiplePreRuntimeDigests)),
+			(Err(_), _) => trace!(target: LOG_TARGET, "Ignoring digest not meant for us"),
+			(Ok(c), false) => claim = Some(c),
+		}
+	}
+	claim.ok_or_else(|| sassafras_err(Error::NoPreRuntimeDigest))
+}
+
+/// Extract the Sassafras epoch change digest from the given header, if it exists.
+fn find_next_epoch_digest<B: BlockT>(
+	header: &B::Header,
+) -> Result<Option<NextEpochDescriptor>, Error<B>> {
+	let mut epoch_digest: Option<_> = None;
+	for log in header.digest().logs() {
+		trace!(target: LOG_TARGET, "Checking log {:?}, looking for epoch change digest.", log);
+		let log = log.try_to::<ConsensusLog>(OpaqueDigestItemId::Consensus(&SASSAFRAS_ENGINE_ID));
+		match (log, epoch_digest.is_some()) {
+			(Some(ConsensusLog::NextEpochData(_)), true) =>
+				return Err(sassafras_err(Error::MultipleEpochChangeDigests)),
+			(Some(ConsensusLog::NextEpochData(epoch)), false) => epoch_digest = Some(epoch),
+			_ => trace!(target: LOG_TARGET, "Ignoring digest not meant for us"),
+		}
+	}
+
+	Ok(epoch_digest)
+}
+
+/// State that must be shared between the import queue and the authoring logic.
+#[derive(Clone)]
+pub struct SassafrasLink<B: BlockT> {
+	epoch_changes: sc_consensus_epochs::SharedEpochChanges<B, Epoch>,
+	genesis_config: sp_consensus_sassafras::Epoch,
+}
+
+impl<B: BlockT> SassafrasLink<B> {
+	/// Get the epoch changes of this link.
+	pub fn epoch_changes(&self) -> &sc_consensus_epochs::SharedEpochChanges<B, Epoch> {
+		&self.epoch_changes
+	}
+
+	/// Get the genesis epoch configuration of this link.
+	pub fn genesis_config(&self) -> &sp_consensus_sassafras::Epoch {
+		&self.genesis_config
+	}
+}
@@ -0,0 +1,691 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sassafras verification and import testing.
+
+use super::*;
+
+use std::{collections::HashMap, sync::Arc};
+
+use futures::executor::block_on;
+use parking_lot::Mutex;
+
+use sc_client_api::{OnFinalityAction, OnImportAction, PreCommitActions};
+use sc_consensus::{
+	import_queue::{import_single_block, BlockImportError, BlockImportStatus, IncomingBlock},
+	BlockCheckParams, BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult,
+};
+use sc_consensus_epochs::descendent_query;
+use sp_api::ApiRef;
+use sp_application_crypto::AppCrypto;
+use sp_blockchain::{BlockStatus, CachedHeaderMetadata, HeaderBackend, HeaderMetadata, Info};
+use sp_consensus::Error as ConsensusError;
+use sp_consensus_sassafras::{EpochConfiguration, EquivocationProof, OpaqueKeyOwnershipProof};
+use sp_core::{ed25519, Pair};
+use sp_inherents::{CheckInherentsResult, InherentData, InherentDataProvider, InherentIdentifier};
+use sp_keystore::{testing::MemoryKeystore, Keystore, KeystorePtr};
+use sp_runtime::{ApplyExtrinsicResult, DigestItem};
+use substrate_test_runtime_client::runtime::{Block, Hash, Header as TestHeader};
+
+use crate::authorship::{claim_slot, generate_epoch_tickets, secondary_authority_index};
+
+const EPOCH_LENGTH: u32 = 6;
+
+const GENESIS_SLOT: u64 = 10;
+
+#[derive(Clone)]
+struct TestApi {
+	genesis_config: sp_consensus_sassafras::Epoch,
+	tickets: Arc<Mutex<HashMap<Slot, (TicketId, TicketBody)>>>,
+}
+
+struct RuntimeApi {
+	inner: TestApi,
+}
+
+sp_api::mock_impl_runtime_apis! {
+	impl SassafrasApi<Block> for RuntimeApi {
+		fn ring_context() -> Option<vrf::RingContext> {
+			None
+		}
+
+		fn submit_tickets_unsigned_extrinsic(_tickets: Vec<TicketEnvelope>) -> bool {
+			false
+		}
+
+		fn slot_ticket_id(&self, slot: Slot) -> Option<TicketId> {
+			self.inner.tickets.lock().get(&slot).map(|(id, _)| *id)
+		}
+
+		fn slot_ticket(&self, slot: Slot) -> Option<(TicketId, TicketBody)> {
+			self.inner.tickets.lock().get(&slot).cloned()
+		}
+
+		fn current_epoch(&self) -> sp_consensus_sassafras::Epoch {
+			self.inner.genesis_config.clone()
+		}
+
+		fn next_epoch(&self) -> sp_consensus_sassafras::Epoch {
+			self.inner.genesis_config.clone()
+		}
+
+		fn generate_key_ownership_proof(
+			_authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof> {
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: EquivocationProof<TestHeader>,
+			_key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> bool {
+			false
+		}
+	}
+
+	impl sp_block_builder::BlockBuilder<Block> for RuntimeApi {
+		fn apply_extrinsic(_extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
+			Ok(Ok(()))
+		}
+
+		fn finalize_block() -> TestHeader {
+			unimplemented!("Not required in tests")
+		}
+
+		fn inherent_extrinsics(_data: InherentData) -> Vec<<Block as BlockT>::Extrinsic> {
+			Vec::new()
+		}
+
+		fn check_inherents(_block: Block, _data: InherentData) -> CheckInherentsResult {
+			CheckInherentsResult::new()
+		}
+	}
+}
+
+/// In-memory chain of headers, only the genesis block is finalized.
+struct TestClient {
+	api: TestApi,
+	genesis_hash: Hash,
+	headers: Mutex<HashMap<Hash, TestHeader>>,
+	best: Mutex<(Hash, u64)>,
+	aux: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl TestClient {
+	fn new(api: TestApi) -> Self {
+		let genesis = TestHeader::new(
+			0,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let genesis_hash = genesis.hash();
+		TestClient {
+			api,
+			genesis_hash,
+			headers: Mutex::new([(genesis_hash, genesis)].into()),
+			best: Mutex::new((genesis_hash, 0)),
+			aux: Default::default(),
+		}
+	}
+}
+
+impl ProvideRuntimeApi<Block> for TestClient {
+	type Api = RuntimeApi;
+
+	fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+		RuntimeApi { inner: self.api.clone() }.into()
+	}
+}
+
+impl HeaderBackend<Block> for TestClient {
+	fn header(&self, hash: Hash) -> sp_blockchain::Result<Option<TestHeader>> {
+		Ok(self.headers.lock().get(&hash).cloned())
+	}
+
+	fn info(&self) -> Info<Block> {
+		let (best_hash, best_number) = *self.best.lock();
+		Info {
+			best_hash,
+			best_number,
+			genesis_hash: self.genesis_hash,
+			finalized_hash: self.genesis_hash,
+			finalized_number: 0,
+			finalized_state: Some((self.genesis_hash, 0)),
+			number_leaves: 1,
+			block_gap: None,
+		}
+	}
+
+	fn status(&self, hash: Hash) -> sp_blockchain::Result<BlockStatus> {
+		Ok(match self.headers.lock().contains_key(&hash) {
+			true => BlockStatus::InChain,
+			false => BlockStatus::Unknown,
+		})
+	}
+
+	fn number(&self, hash: Hash) -> sp_blockchain::Result<Option<u64>> {
+		Ok(self.headers.lock().get(&hash).map(|header| header.number))
+	}
+
+	fn hash(&self, number: u64) -> sp_blockchain::Result<Option<Hash>> {
+		let headers = self.headers.lock();
+		Ok(headers.values().find(|header| header.number == number).map(|header| header.hash()))
+	}
+}
+
+impl HeaderMetadata<Block> for TestClient {
+	type Error = sp_blockchain::Error;
+
+	fn header_metadata(&self, hash: Hash) -> Result<CachedHeaderMetadata<Block>, Self::Error> {
+		self.headers
+			.lock()
+			.get(&hash)
+			.map(CachedHeaderMetadata::from)
+			.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{hash:?}")))
+	}
+
+	fn insert_header_metadata(&self, _: Hash, _: CachedHeaderMetadata<Block>) {}
+
+	fn remove_header_metadata(&self, _: Hash) {}
+}
+
+impl AuxStore for TestClient {
+	fn insert_aux<
+		'a,
+		'b: 'a,
+		'c: 'a,
+		I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+		D: IntoIterator<Item = &'a &'b [u8]>,
+	>(
+		&self,
+		insert: I,
+		delete: D,
+	) -> sp_blockchain::Result<()> {
+		let mut aux = self.aux.lock();
+		for (key, value) in insert {
+			aux.insert(key.to_vec(), value.to_vec());
+		}
+		for key in delete {
+			aux.remove(*key);
+		}
+		Ok(())
+	}
+
+	fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		Ok(self.aux.lock().get(key).cloned())
+	}
+}
+
+impl PreCommitActions<Block> for TestClient {
+	fn register_import_action(&self, _: OnImportAction<Block>) {}
+
+	fn register_finality_action(&self, _: OnFinalityAction<Block>) {}
+}
+
+/// Inner block import writing the imported headers and auxiliary data to the client.
+struct TestBlockImport(Arc<TestClient>);
+
+#[async_trait::async_trait]
+impl BlockImport<Block> for TestBlockImport {
+	type Error = ConsensusError;
+
+	async fn check_block(
+		&self,
+		_block: BlockCheckParams<Block>,
+	) -> Result<ImportResult, Self::Error> {
+		Ok(ImportResult::imported(false))
+	}
+
+	async fn import_block(
+		&self,
+		block: BlockImportParams<Block>,
+	) -> Result<ImportResult, Self::Error> {
+		let client = &self.0;
+		let (hash, header) = (block.post_hash(), block.post_header());
+		let is_new_best = matches!(block.fork_choice, Some(ForkChoiceStrategy::Custom(true)));
+
+		let mut aux = client.aux.lock();
+		for (key, value) in block.auxiliary {
+			match value {
+				Some(value) => aux.insert(key, value),
+				None => aux.remove(&key),
+			};
+		}
+		if is_new_best {
+			*client.best.lock() = (hash, header.number);
+		}
+		client.headers.lock().insert(hash, header);
+
+		Ok(ImportResult::imported(is_new_best))
+	}
+}
+
+/// Inherent data provider only exposing the current slot.
+struct SlotProvider(Slot);
+
+impl Deref for SlotProvider {
+	type Target = Slot;
+
+	fn deref(&self) -> &Slot {
+		&self.0
+	}
+}
+
+#[async_trait::async_trait]
+impl InherentDataProvider for SlotProvider {
+	async fn provide_inherent_data(&self, _: &mut InherentData) -> Result<(), sp_inherents::Error> {
+		Ok(())
+	}
+
+	async fn try_handle_error(
+		&self,
+		_: &InherentIdentifier,
+		_: &[u8],
+	) -> Option<Result<(), sp_inherents::Error>> {
+		None
+	}
+}
+
+struct TestEnv {
+	client: Arc<TestClient>,
+	block_import: SassafrasBlockImport<Block, TestClient, TestBlockImport>,
+	link: SassafrasLink<Block>,
+	keystore: KeystorePtr,
+}
+
+impl TestEnv {
+	fn new() -> Self {
+		let keystore = MemoryKeystore::new();
+		let authorities = ["//Alice", "//Bob"]
+			.into_iter()
+			.map(|seed| keystore.bandersnatch_generate_new(AuthorityId::ID, Some(seed)).unwrap())
+			.map(Into::into)
+			.collect();
+		let genesis_config = sp_consensus_sassafras::Epoch {
+			index: 0,
+			start: 0.into(),
+			length: EPOCH_LENGTH,
+			randomness: [3; 32],
+			authorities,
+			config: EpochConfiguration { redundancy_factor: 2, attempts_number: 8 },
+		};
+
+		let api = TestApi { genesis_config: genesis_config.clone(), tickets: Default::default() };
+		let client = Arc::new(TestClient::new(api));
+		let (block_import, link) =
+			block_import(genesis_config, TestBlockImport(client.clone()), client.clone()).unwrap();
+
+		TestEnv { client, block_import, link, keystore: keystore.into() }
+	}
+
+	/// Epoch used to verify the child of `parent_hash` claiming `slot`.
+	fn epoch_for_child_of(&self, parent_hash: Hash, slot: Slot) -> Epoch {
+		let parent_number = self.client.number(parent_hash).unwrap().unwrap();
+		let epoch_changes = self.link.epoch_changes.shared_data();
+		let descriptor = epoch_changes
+			.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&parent_hash,
+				parent_number,
+				slot,
+			)
+			.unwrap()
+			.unwrap();
+		epoch_changes
+			.viable_epoch(&descriptor, |slot| Epoch::genesis(&self.link.genesis_config, slot))
+			.unwrap()
+			.into_cloned_inner()
+	}
+
+	/// Build a header on top of `parent_hash` carrying the given claim and sealed by `author`.
+	fn make_header(
+		&self,
+		parent_hash: Hash,
+		claim: &SlotClaim,
+		next_epoch: Option<NextEpochDescriptor>,
+		author: &AuthorityId,
+	) -> TestHeader {
+		let parent_number = self.client.number(parent_hash).unwrap().unwrap();
+		let mut header = TestHeader::new(
+			parent_number + 1,
+			Default::default(),
+			Default::default(),
+			parent_hash,
+			Default::default(),
+		);
+		header.digest_mut().push(DigestItem::from(claim));
+		if let Some(descriptor) = next_epoch {
+			let log = ConsensusLog::NextEpochData(descriptor);
+			header.digest_mut().push(DigestItem::Consensus(SASSAFRAS_ENGINE_ID, log.encode()));
+		}
+		let signature: AuthoritySignature = self
+			.keystore
+			.bandersnatch_sign(AuthorityId::ID, author.as_ref(), header.hash().as_ref())
+			.unwrap()
+			.unwrap()
+			.into();
+		header.digest_mut().push(DigestItem::from(&signature));
+		header
+	}
+
+	/// Build a secondary block on top of `parent_hash`.
+	fn secondary_header(
+		&self,
+		parent_hash: Hash,
+		slot: u64,
+		next_epoch: Option<NextEpochDescriptor>,
+	) -> TestHeader {
+		let slot = slot.into();
+		let epoch = self.epoch_for_child_of(parent_hash, slot);
+		let (claim, author) = claim_slot(slot, &epoch, None, &self.keystore).unwrap();
+		self.make_header(parent_hash, &claim, next_epoch, &author)
+	}
+
+	/// Verify and import the given header as done by the import queue.
+	fn import(
+		&mut self,
+		header: TestHeader,
+		slot_now: u64,
+	) -> Result<BlockImportStatus<u64>, BlockImportError> {
+		let create_inherent_data_providers = move |_: Hash, _: ()| async move {
+			Ok::<_, Box<dyn std::error::Error + Send + Sync>>((SlotProvider(slot_now.into()),))
+		};
+		let verifier = SassafrasVerifier::new(
+			self.client.clone(),
+			create_inherent_data_providers,
+			self.link.clone(),
+			None,
+		);
+		let block = IncomingBlock {
+			hash: header.hash(),
+			header: Some(header),
+			body: None,
+			indexed_body: None,
+			justifications: None,
+			origin: None,
+			allow_missing_state: false,
+			skip_execution: false,
+			import_existing: false,
+			state: None,
+		};
+		block_on(import_single_block(
+			&mut self.block_import,
+			BlockOrigin::NetworkBroadcast,
+			block,
+			&verifier,
+		))
+	}
+}
+
+fn next_epoch_descriptor(env: &TestEnv, randomness: Randomness) -> NextEpochDescriptor {
+	NextEpochDescriptor {
+		randomness,
+		authorities: env.link.genesis_config.authorities.clone(),
+		config: None,
+	}
+}
+
+fn pre_hash(header: &TestHeader) -> Hash {
+	let mut header = header.clone();
+	header.digest_mut().pop();
+	header.hash()
+}
+
+fn assert_verification_failed(
+	result: Result<BlockImportStatus<u64>, BlockImportError>,
+	error: Error<Block>,
+) {
+	match result {
+		Err(BlockImportError::VerificationFailed(_, msg)) => assert_eq!(msg, error.to_string()),
+		other => panic!("Unexpected import result: {other:?}"),
+	}
+}
+
+fn assert_import_failed(
+	result: Result<BlockImportStatus<u64>, BlockImportError>,
+	error: Error<Block>,
+) {
+	match result {
+		Err(BlockImportError::Other(ConsensusError::ClientImport(msg))) =>
+			assert_eq!(msg, error.to_string()),
+		other => panic!("Unexpected import result: {other:?}"),
+	}
+}
+
+/// Import the first block of the chain (announcing the next epoch) and return its hash.
+fn import_first_block(env: &mut TestEnv) -> Hash {
+	let genesis_hash = env.client.genesis_hash;
+	let descriptor = next_epoch_descriptor(env, [4; 32]);
+	let header = env.secondary_header(genesis_hash, GENESIS_SLOT, Some(descriptor));
+	let hash = header.hash();
+	assert!(matches!(
+		env.import(header, GENESIS_SLOT),
+		Ok(BlockImportStatus::ImportedUnknown(1, _, _))
+	));
+	hash
+}
+
+/// Register the tickets of the local authorities for the given epoch in the runtime, assigning
+/// them to the epoch slots in order.
+fn setup_tickets(env: &TestEnv, epoch: &mut Epoch) -> Vec<(TicketId, TicketBody)> {
+	let ring_ctx = vrf::RingContext::new_testing();
+	let tickets: Vec<_> = generate_epoch_tickets(epoch, &env.keystore, &ring_ctx)
+		.into_iter()
+		.map(|ticket| (vrf::make_ticket_id(&ticket.signature.pre_output), ticket.body))
+		.collect();
+	assert!(!tickets.is_empty());
+	let mut slot_tickets = env.client.api.tickets.lock();
+	for (offset, ticket) in tickets.iter().enumerate().take(epoch.length as usize) {
+		slot_tickets.insert(epoch.start + offset as u64, ticket.clone());
+	}
+	tickets
+}
+
+#[test]
+fn import_secondary_and_primary_blocks() {
+	let mut env = TestEnv::new();
+	let hash1 = import_first_block(&mut env);
+	assert_eq!(aux_schema::load_block_weight(&*env.client, hash1).unwrap(), Some(0));
+
+	let slot = Slot::from(GENESIS_SLOT + 1);
+	let mut epoch = env.epoch_for_child_of(hash1, slot);
+	setup_tickets(&env, &mut epoch);
+	let ticket = env.client.api.tickets.lock().get(&slot).cloned();
+	let (claim, author) = claim_slot(slot, &epoch, ticket, &env.keystore).unwrap();
+	assert!(claim.ticket_claim.is_some());
+
+	let header = env.make_header(hash1, &claim, None, &author);
+	let hash2 = header.hash();
+	assert!(matches!(env.import(header, *slot), Ok(BlockImportStatus::ImportedUnknown(2, _, _))));
+	assert_eq!(aux_schema::load_block_weight(&*env.client, hash2).unwrap(), Some(1));
+	assert_eq!(env.client.info().best_hash, hash2);
+}
+
+#[test]
+fn reject_slot_claims_not_matching_the_ticket() {
+	let mut env = TestEnv::new();
+	let hash1 = import_first_block(&mut env);
+
+	let slot = Slot::from(GENESIS_SLOT + 1);
+	let mut epoch = env.epoch_for_child_of(hash1, slot);
+	setup_tickets(&env, &mut epoch);
+
+	// Secondary claim for a slot associated to a ticket.
+	let header = env.secondary_header(hash1, *slot, None);
+	assert_verification_failed(env.import(header, *slot), Error::ExpectedTicketClaim(slot));
+
+	// Primary claim for a slot without any ticket.
+	let (ticket_id, ticket_body) = env.client.api.tickets.lock().remove(&slot).unwrap();
+	let ticket = Some((ticket_id, ticket_body));
+	let (claim, author) = claim_slot(slot, &epoch, ticket, &env.keystore).unwrap();
+	let header = env.make_header(hash1, &claim, None, &author);
+	assert_verification_failed(env.import(header, *slot), Error::UnexpectedTicketClaim(slot));
+
+	// Secondary claim by the wrong authority.
+	let claim_idx = 1 - secondary_authority_index(slot, &epoch);
+	let author = epoch.authorities[claim_idx as usize].clone();
+	let data = slot_claim_sign_data(&epoch.randomness, slot, epoch.index, None);
+	let vrf_signature =
+		env.keystore.bandersnatch_vrf_sign(AuthorityId::ID, author.as_ref(), &data).unwrap();
+	let claim = SlotClaim {
+		authority_idx: claim_idx,
+		slot,
+		vrf_signature: vrf_signature.unwrap(),
+		ticket_claim: None,
+	};
+	let header = env.make_header(hash1, &claim, None, &author);
+	assert_verification_failed(
+		env.import(header, *slot),
+		Error::InvalidAuthor(1 - claim_idx, claim_idx),
+	);
+}
+
+#[test]
+fn reject_ticket_claimed_by_another_authority() {
+	let mut env = TestEnv::new();
+	let hash1 = import_first_block(&mut env);
+
+	let slot = Slot::from(GENESIS_SLOT + 1);
+	let mut epoch = env.epoch_for_child_of(hash1, slot);
+	setup_tickets(&env, &mut epoch);
+	let (ticket_id, ticket_body) = env.client.api.tickets.lock().get(&slot).cloned().unwrap();
+	let (claim, _) =
+		claim_slot(slot, &epoch, Some((ticket_id, ticket_body.clone())), &env.keystore).unwrap();
+	let owner_claim = claim.ticket_claim.unwrap();
+
+	// Another authority produces a claim for the same slot using the published ticket claim.
+	let keystore = env.keystore.clone();
+	let other_idx = 1 - claim.authority_idx;
+	let other = epoch.authorities[other_idx as usize].clone();
+	let make_claim = |ticket_claim: TicketClaim| {
+		let data = slot_claim_sign_data(&epoch.randomness, slot, epoch.index, Some(&ticket_body));
+		let vrf_signature =
+			keystore.bandersnatch_vrf_sign(AuthorityId::ID, other.as_ref(), &data).unwrap();
+		SlotClaim {
+			authority_idx: other_idx,
+			slot,
+			vrf_signature: vrf_signature.unwrap(),
+			ticket_claim: Some(ticket_claim),
+		}
+	};
+
+	// The erased signature is bound to the claiming authority index.
+	let header = env.make_header(hash1, &make_claim(owner_claim.clone()), None, &other);
+	let hash = pre_hash(&header);
+	assert_verification_failed(env.import(header, *slot), Error::BadTicketClaim(hash));
+
+	// Even knowing the erased secret, the ticket identifier can't be reproduced with the
+	// VRF key of a different authority.
+	let (_, erased_seed) = epoch.tickets_aux[&ticket_id];
+	let message = ticket_claim_message(&epoch.randomness, slot, epoch.index, other_idx);
+	let data =
+		vrf::ticket_claim_sign_data(&epoch.randomness, ticket_body.attempt_idx, epoch.index, slot);
+	let ticket_signature =
+		keystore.bandersnatch_vrf_sign(AuthorityId::ID, other.as_ref(), &data).unwrap();
+	let ticket_claim = TicketClaim {
+		erased_signature: ed25519::Pair::from_seed(&erased_seed).sign(&message),
+		ticket_signature: ticket_signature.unwrap(),
+	};
+	let header = env.make_header(hash1, &make_claim(ticket_claim), None, &other);
+	let hash = pre_hash(&header);
+	assert_verification_failed(env.import(header, *slot), Error::TicketNotOwned(hash));
+
+	// Re-using the ticket signature of the owner doesn't help either.
+	let ticket_claim = TicketClaim {
+		erased_signature: ed25519::Pair::from_seed(&erased_seed).sign(&message),
+		ticket_signature: owner_claim.ticket_signature,
+	};
+	let header = env.make_header(hash1, &make_claim(ticket_claim), None, &other);
+	let hash = pre_hash(&header);
+	assert_verification_failed(env.import(header, *slot), Error::TicketNotOwned(hash));
+}
+
+#[test]
+fn reject_bad_seal() {
+	let mut env = TestEnv::new();
+	let genesis_hash = env.client.genesis_hash;
+	let slot = Slot::from(GENESIS_SLOT);
+
+	let epoch = env.epoch_for_child_of(genesis_hash, slot);
+	let (claim, author) = claim_slot(slot, &epoch, None, &env.keystore).unwrap();
+	let other = epoch.authorities.iter().find(|id| **id != author).unwrap().clone();
+	let descriptor = next_epoch_descriptor(&env, [4; 32]);
+	let header = env.make_header(genesis_hash, &claim, Some(descriptor), &other);
+	let hash = pre_hash(&header);
+	assert_verification_failed(env.import(header, *slot), Error::BadSignature(hash));
+}
+
+#[test]
+fn defer_blocks_from_the_future() {
+	let mut env = TestEnv::new();
+	let genesis_hash = env.client.genesis_hash;
+	let descriptor = next_epoch_descriptor(&env, [4; 32]);
+	let header = env.secondary_header(genesis_hash, GENESIS_SLOT + 2, Some(descriptor.clone()));
+	let hash = header.hash();
+	assert_verification_failed(env.import(header, GENESIS_SLOT), Error::TooFarInFuture(hash));
+
+	// One slot of drift is tolerated.
+	let header = env.secondary_header(genesis_hash, GENESIS_SLOT + 1, Some(descriptor));
+	assert!(env.import(header, GENESIS_SLOT).is_ok());
+}
+
+#[test]
+fn epoch_change_digests() {
+	let mut env = TestEnv::new();
+	let genesis_hash = env.client.genesis_hash;
+
+	// The first block must announce the next epoch.
+	let header = env.secondary_header(genesis_hash, GENESIS_SLOT, None);
+	let hash = header.hash();
+	assert_import_failed(
+		env.import(header, GENESIS_SLOT),
+		Error::ExpectedEpochChange(hash, GENESIS_SLOT.into()),
+	);
+	let hash1 = import_first_block(&mut env);
+
+	// Blocks within the same epoch must not announce a new one.
+	let descriptor = next_epoch_descriptor(&env, [5; 32]);
+	let header = env.secondary_header(hash1, GENESIS_SLOT + 1, Some(descriptor.clone()));
+	assert_import_failed(env.import(header, GENESIS_SLOT + 1), Error::UnexpectedEpochChange);
+
+	// The first block of the next epoch must announce the one after.
+	let slot = GENESIS_SLOT + EPOCH_LENGTH as u64;
+	let header = env.secondary_header(hash1, slot, None);
+	let hash = header.hash();
+	assert_import_failed(env.import(header, slot), Error::ExpectedEpochChange(hash, slot.into()));
+
+	let header = env.secondary_header(hash1, slot, Some(descriptor));
+	let hash2 = header.hash();
+	assert!(env.import(header, slot).is_ok());
+
+	// Children of the last block are verified under the epoch announced by the first one.
+	let epoch = env.epoch_for_child_of(hash2, (slot + 1).into());
+	assert_eq!(epoch.index, 1);
+	assert_eq!(epoch.start, slot);
+	assert_eq!(epoch.randomness, [4; 32]);
+
+	// Skipped epochs re-use the data of the last announced epoch.
+	let slot = slot + 3 * EPOCH_LENGTH as u64;
+	let epoch = env.epoch_for_child_of(hash2, slot.into());
+	assert_eq!(epoch.index, 2);
+	assert_eq!(epoch.randomness, [5; 32]);
+	let header = env.secondary_header(hash2, slot, Some(next_epoch_descriptor(&env, [6; 32])));
+	assert!(env.import(header, slot).is_ok());
+}
@@ -0,0 +1,459 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verification for Sassafras headers.
+
+use std::sync::Arc;
+
+use log::{debug, trace};
+use prometheus_endpoint::Registry;
+
+use sc_client_api::backend::AuxStore;
+use sc_consensus::{
+	import_queue::{BasicQueue, BoxJustificationImport, DefaultImportQueue, Verifier},
+	BlockImport, BlockImportParams,
+};
+use sc_consensus_epochs::{descendent_query, SharedEpochChanges};
+use sc_consensus_slots::{CheckedHeader, InherentDataProviderExt};
+use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_DEBUG, CONSENSUS_TRACE};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::{HeaderBackend, HeaderMetadata, Result as ClientResult};
+use sp_consensus::Error as ConsensusError;
+use sp_consensus_slots::Slot;
+use sp_core::{
+	crypto::{VrfPublic, Wraps},
+	ed25519,
+	traits::SpawnEssentialNamed,
+	Pair,
+};
+use sp_inherents::{CreateInherentDataProviders, InherentData, InherentDataProvider};
+use sp_runtime::{
+	traits::{Block as BlockT, Header},
+	DigestItem,
+};
+
+use crate::{
+	authorship::secondary_authority_index, find_slot_claim, sassafras_err, slot_claim_sign_data,
+	ticket_claim_message, vrf, AuthorityId, AuthorityPair, AuthoritySignature, Epoch, Error,
+	SassafrasApi, SassafrasIntermediate, SassafrasLink, SlotClaim, TicketBody, TicketId,
+	INTERMEDIATE_KEY, LOG_TARGET,
+};
+
+/// Sassafras verification parameters
+struct VerificationParams<'a, B: 'a + BlockT> {
+	/// The header being verified.
+	header: B::Header,
+	/// The slot claim of the header being verified.
+	claim: &'a SlotClaim,
+	/// The slot number of the current time.
+	slot_now: Slot,
+	/// Epoch descriptor of the epoch this block _should_ be under, if it's valid.
+	epoch: &'a Epoch,
+	/// Ticket associated to the claimed slot, if any.
+	ticket: Option<(TicketId, TicketBody)>,
+}
+
+/// Verified information
+struct VerifiedHeaderInfo {
+	/// Authority index.
+	authority_id: AuthorityId,
+	/// Seal found within the header.
+	seal: DigestItem,
+}
+
+/// Check a header has been signed by the right key. If the slot is too far in
+/// the future, an error will be returned. If successful, returns the pre-header
+/// and the digest item containing the seal.
+///
+/// The seal must be the last digest. Otherwise, the whole header is considered
+/// unsigned. This is required for security and must not be changed.
+///
+/// The given header can either be from a primary or secondary slot assignment,
+/// with each having different validation logic.
+fn check_header<B: BlockT + Sized>(
+	params: VerificationParams<B>,
+) -> Result<CheckedHeader<B::Header, VerifiedHeaderInfo>, Error<B>> {
+	let VerificationParams { mut header, claim, slot_now, epoch, ticket } = params;
+
+	trace!(target: LOG_TARGET, "Checking header");
+	let seal = header
+		.digest_mut()
+		.pop()
+		.ok_or_else(|| sassafras_err(Error::HeaderUnsealed(header.hash())))?;
+
+	let signature = AuthoritySignature::try_from(&seal)
+		.map_err(|_| sassafras_err(Error::HeaderBadSeal(header.hash())))?;
+
+	// The pre-hash of the header doesn't include the seal and that's what we sign.
+	let pre_hash = header.hash();
+
+	if claim.slot > slot_now {
+		header.digest_mut().push(seal);
+		return Ok(CheckedHeader::Deferred(header, claim.slot))
+	}
+
+	let Some(authority_id) = epoch.authorities.get(claim.authority_idx as usize) else {
+		return Err(sassafras_err(Error::SlotAuthorNotFound))
+	};
+
+	// Check header signature (aka the Seal)
+
+	if !AuthorityPair::verify(&signature, pre_hash, authority_id) {
+		return Err(sassafras_err(Error::BadSignature(pre_hash)))
+	}
+
+	// Optionally check ticket ownership
+
+	match (&ticket, &claim.ticket_claim) {
+		(Some((ticket_id, ticket_body)), Some(ticket_claim)) => {
+			debug!(
+				target: LOG_TARGET,
+				"Verifying primary block #{} at slot: {}",
+				header.number(),
+				claim.slot,
+			);
+			let message = ticket_claim_message(
+				&epoch.randomness,
+				claim.slot,
+				epoch.index,
+				claim.authority_idx,
+			);
+			if !ed25519::Pair::verify(
+				&ticket_claim.erased_signature,
+				&message,
+				&ticket_body.erased_public,
+			) {
+				return Err(sassafras_err(Error::BadTicketClaim(pre_hash)))
+			}
+			// The erased key is published on chain together with the ticket, so its signature
+			// alone doesn't tie the ticket to the author. Only the authority which generated
+			// the ticket can reproduce its identifier pre-output.
+			let data = vrf::ticket_claim_sign_data(
+				&epoch.randomness,
+				ticket_body.attempt_idx,
+				epoch.index,
+				claim.slot,
+			);
+			if !authority_id.as_inner_ref().vrf_verify(&data, &ticket_claim.ticket_signature) ||
+				vrf::make_ticket_id(&ticket_claim.ticket_signature.pre_output) != *ticket_id
+			{
+				return Err(sassafras_err(Error::TicketNotOwned(pre_hash)))
+			}
+		},
+		(None, None) => {
+			debug!(
+				target: LOG_TARGET,
+				"Verifying secondary block #{} at slot: {}",
+				header.number(),
+				claim.slot,
+			);
+			let idx = secondary_authority_index(claim.slot, epoch);
+			if idx != claim.authority_idx {
+				return Err(sassafras_err(Error::InvalidAuthor(idx, claim.authority_idx)))
+			}
+		},
+		(Some(_), None) => return Err(sassafras_err(Error::ExpectedTicketClaim(claim.slot))),
+		(None, Some(_)) => return Err(sassafras_err(Error::UnexpectedTicketClaim(claim.slot))),
+	}
+
+	// Check slot-vrf proof
+
+	let data = slot_claim_sign_data(
+		&epoch.randomness,
+		claim.slot,
+		epoch.index,
+		ticket.as_ref().map(|(_, body)| body),
+	);
+	if !authority_id.as_inner_ref().vrf_verify(&data, &claim.vrf_signature) {
+		return Err(sassafras_err(Error::VrfVerificationFailed))
+	}
+
+	let info = VerifiedHeaderInfo { authority_id: authority_id.clone(), seal };
+
+	Ok(CheckedHeader::Checked(header, info))
+}
+
+/// A verifier for Sassafras blocks.
+pub struct SassafrasVerifier<Block: BlockT, Client, CIDP> {
+	client: Arc<Client>,
+	create_inherent_data_providers: CIDP,
+	genesis_config: sp_consensus_sassafras::Epoch,
+	epoch_changes: SharedEpochChanges<Block, Epoch>,
+	telemetry: Option<TelemetryHandle>,
+}
+
+impl<Block: BlockT, Client, CIDP> SassafrasVerifier<Block, Client, CIDP> {
+	/// Create a new verifier sharing the epoch changes of the given link.
+	pub(crate) fn new(
+		client: Arc<Client>,
+		create_inherent_data_providers: CIDP,
+		link: SassafrasLink<Block>,
+		telemetry: Option<TelemetryHandle>,
+	) -> Self {
+		SassafrasVerifier {
+			client,
+			create_inherent_data_providers,
+			genesis_config: link.genesis_config,
+			epoch_changes: link.epoch_changes,
+			telemetry,
+		}
+	}
+}
+
+impl<Block, Client, CIDP> SassafrasVerifier<Block, Client, CIDP>
+where
+	Block: BlockT,
+	Client: ProvideRuntimeApi<Block>,
+	Client::Api: BlockBuilderApi<Block>,
+	CIDP: CreateInherentDataProviders<Block, ()>,
+{
+	async fn check_inherents(
+		&self,
+		block: Block,
+		at_hash: Block::Hash,
+		inherent_data: InherentData,
+		create_inherent_data_providers: CIDP::InherentDataProviders,
+	) -> Result<(), Error<Block>> {
+		let inherent_res = self
+			.client
+			.runtime_api()
+			.check_inherents(at_hash, block, inherent_data)
+			.map_err(Error::RuntimeApi)?;
+
+		if !inherent_res.ok() {
+			for (i, e) in inherent_res.into_errors() {
+				match create_inherent_data_providers.try_handle_error(&i, &e).await {
+					Some(res) => res.map_err(Error::CheckInherents)?,
+					None => return Err(Error::CheckInherentsUnhandled(i)),
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[async_trait::async_trait]
+impl<Block, Client, CIDP> Verifier<Block> for SassafrasVerifier<Block, Client, CIDP>
+where
+	Block: BlockT,
+	Client: HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ HeaderBackend<Block>
+		+ ProvideRuntimeApi<Block>
+		+ Send
+		+ Sync
+		+ AuxStore,
+	Client::Api: BlockBuilderApi<Block> + SassafrasApi<Block>,
+	CIDP: CreateInherentDataProviders<Block, ()> + Send + Sync,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + Sync,
+{
+	async fn verify(
+		&self,
+		mut block: BlockImportParams<Block>,
+	) -> Result<BlockImportParams<Block>, String> {
+		trace!(
+			target: LOG_TARGET,
+			"Verifying origin: {:?} header: {:?} justification(s): {:?} body: {:?}",
+			block.origin,
+			block.header,
+			block.justifications,
+			block.body,
+		);
+
+		let hash = block.header.hash();
+		let parent_hash = *block.header.parent_hash();
+
+		let info = self.client.info();
+		let number = *block.header.number();
+
+		if info.block_gap.map_or(false, |gap| gap.start <= number && number <= gap.end) ||
+			block.with_state()
+		{
+			// Verification for imported blocks is skipped in two cases:
+			// 1. When importing blocks below the last finalized block during network initial
+			//    synchronization.
+			// 2. When importing whole state we don't calculate epoch descriptor, but rather read it
+			//    from the state after import. We also skip all verifications because there's no
+			//    parent state and we trust the sync module to verify that the state is correct and
+			//    finalized.
+			return Ok(block)
+		}
+
+		let create_inherent_data_providers = self
+			.create_inherent_data_providers
+			.create_inherent_data_providers(parent_hash, ())
+			.await
+			.map_err(|e| Error::<Block>::Client(ConsensusError::from(e).into()))?;
+
+		let slot_now = create_inherent_data_providers.slot();
+
+		let parent_header_metadata =
+			self.client.header_metadata(parent_hash).map_err(Error::<Block>::FetchParentHeader)?;
+
+		let claim = find_slot_claim::<Block>(&block.header)?;
+
+		// Get the ticket associated to the slot (if any) from the parent state.
+		let ticket = self
+			.client
+			.runtime_api()
+			.slot_ticket(parent_hash, claim.slot)
+			.map_err(Error::<Block>::RuntimeApi)?;
+
+		let (checked_header, epoch_descriptor) = {
+			let epoch_changes = self.epoch_changes.shared_data();
+			let epoch_descriptor = epoch_changes
+				.epoch_descriptor_for_child_of(
+					descendent_query(&*self.client),
+					&parent_hash,
+					parent_header_metadata.number,
+					claim.slot,
+				)
+				.map_err(|e| Error::<Block>::ForkTree(Box::new(e)))?
+				.ok_or(Error::<Block>::FetchEpoch(parent_hash))?;
+			let viable_epoch = epoch_changes
+				.viable_epoch(&epoch_descriptor, |slot| Epoch::genesis(&self.genesis_config, slot))
+				.ok_or(Error::<Block>::FetchEpoch(parent_hash))?;
+
+			// We add one to the current slot to allow for some small drift.
+			let v_params = VerificationParams {
+				header: block.header.clone(),
+				claim: &claim,
+				slot_now: slot_now + 1,
+				epoch: viable_epoch.as_ref(),
+				ticket,
+			};
+
+			(check_header::<Block>(v_params)?, epoch_descriptor)
+		};
+
+		match checked_header {
+			CheckedHeader::Checked(pre_header, verified_info) => {
+				if let Some(inner_body) = block.body {
+					let new_block = Block::new(pre_header.clone(), inner_body);
+					if !block.state_action.skip_execution_checks() {
+						// Sassafras doesn't require any slot inherent, thus the inherent data
+						// is checked as provided by the inherent data providers.
+						let inherent_data = create_inherent_data_providers
+							.create_inherent_data()
+							.await
+							.map_err(Error::<Block>::CreateInherents)?;
+
+						self.check_inherents(
+							new_block.clone(),
+							parent_hash,
+							inherent_data,
+							create_inherent_data_providers,
+						)
+						.await?;
+					}
+
+					let (_, inner_body) = new_block.deconstruct();
+					block.body = Some(inner_body);
+				}
+
+				trace!(target: LOG_TARGET, "Checked {:?}; importing.", pre_header);
+				telemetry!(
+					self.telemetry;
+					CONSENSUS_TRACE;
+					"sassafras.checked_and_importing";
+					"pre_header" => ?pre_header,
+					"author" => ?verified_info.authority_id,
+				);
+
+				block.header = pre_header;
+				block.post_digests.push(verified_info.seal);
+				block.insert_intermediate(
+					INTERMEDIATE_KEY,
+					SassafrasIntermediate::<Block> { epoch_descriptor },
+				);
+				block.post_hash = Some(hash);
+
+				Ok(block)
+			},
+			CheckedHeader::Deferred(a, b) => {
+				debug!(target: LOG_TARGET, "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
+				telemetry!(
+					self.telemetry;
+					CONSENSUS_DEBUG;
+					"sassafras.header_too_far_in_future";
+					"hash" => ?hash, "a" => ?a, "b" => ?b
+				);
+				Err(Error::<Block>::TooFarInFuture(hash).into())
+			},
+		}
+	}
+}
+
+/// Parameters passed to [`import_queue`].
+pub struct ImportQueueParams<'a, Block: BlockT, BI, Client, CIDP, Spawn> {
+	/// The Sassafras link that is created by [`crate::block_import`].
+	pub link: SassafrasLink<Block>,
+	/// The block import that should be wrapped.
+	pub block_import: BI,
+	/// Optional justification import.
+	pub justification_import: Option<BoxJustificationImport<Block>>,
+	/// The client to interact with the internals of the node.
+	pub client: Arc<Client>,
+	/// Used to crate the inherent data providers.
+	///
+	/// These inherent data providers are then used to create the inherent data that is
+	/// passed to the `check_inherents` runtime call.
+	pub create_inherent_data_providers: CIDP,
+	/// Spawner for spawning futures.
+	pub spawner: &'a Spawn,
+	/// Registry for prometheus metrics.
+	pub registry: Option<&'a Registry>,
+	/// Optional telemetry handle to report telemetry events.
+	pub telemetry: Option<TelemetryHandle>,
+}
+
+/// Start an import queue for the Sassafras consensus algorithm.
+///
+/// The block import object provided must be the `SassafrasBlockImport` or a wrapper
+/// of it, otherwise crucial import logic will be omitted.
+pub fn import_queue<Block: BlockT, Client, BI, CIDP, Spawn>(
+	ImportQueueParams {
+		link,
+		block_import,
+		justification_import,
+		client,
+		create_inherent_data_providers,
+		spawner,
+		registry,
+		telemetry,
+	}: ImportQueueParams<'_, Block, BI, Client, CIDP, Spawn>,
+) -> ClientResult<DefaultImportQueue<Block>>
+where
+	BI: BlockImport<Block, Error = ConsensusError> + Send + Sync + 'static,
+	Client: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ AuxStore
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: BlockBuilderApi<Block> + SassafrasApi<Block> + ApiExt<Block>,
+	CIDP: CreateInherentDataProviders<Block, ()> + Send + Sync + 'static,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + Sync,
+	Spawn: SpawnEssentialNamed,
+{
+	let verifier = SassafrasVerifier::new(client, create_inherent_data_providers, link, telemetry);
+
+	Ok(BasicQueue::new(verifier, Box::new(block_import), justification_import, spawner, registry))
+}
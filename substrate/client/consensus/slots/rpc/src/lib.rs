@@ -0,0 +1,175 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for the slot diagnostics of slot-based consensus algorithms.
+
+use std::collections::BTreeMap;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, Extensions};
+use serde::{Deserialize, Serialize};
+
+use sc_consensus_slots::SlotDiagnostics;
+use sc_rpc_api::check_if_safe;
+
+/// Provides rpc methods for inspecting the slots handled by the local slot worker.
+#[rpc(client, server)]
+pub trait SlotsApi {
+	/// Returns a summary of the recorded slots.
+	#[method(name = "slots_summary", with_extensions)]
+	fn summary(&self) -> RpcResult<SlotsSummary>;
+
+	/// Returns the recorded slots that were missed by the authority expected to author them,
+	/// as observed by the local node, from the oldest to the most recent one.
+	#[method(name = "slots_missedSlots", with_extensions)]
+	fn missed_slots(&self) -> RpcResult<Vec<MissedSlot>>;
+}
+
+/// Provides RPC methods for inspecting the slots handled by the local slot worker.
+pub struct Slots<Hash> {
+	diagnostics: SlotDiagnostics<Hash>,
+}
+
+impl<Hash> Slots<Hash> {
+	/// Creates a new instance of the slots RPC handler, serving the given diagnostics.
+	pub fn new(diagnostics: SlotDiagnostics<Hash>) -> Self {
+		Self { diagnostics }
+	}
+}
+
+impl<Hash> SlotsApiServer for Slots<Hash>
+where
+	Hash: Clone + Send + Sync + 'static,
+{
+	fn summary(&self, ext: &Extensions) -> RpcResult<SlotsSummary> {
+		check_if_safe(ext)?;
+
+		let summary = self.diagnostics.summary();
+
+		Ok(SlotsSummary {
+			slots: summary.slots,
+			authored: summary.authored,
+			deadline_missed: summary.deadline_missed,
+			failed: summary.failed,
+			skipped: summary.skipped,
+			average_proposing_ms: summary
+				.average_proposing
+				.map(|average| average.as_millis() as u64),
+			missed_by: summary.missed_by,
+		})
+	}
+
+	fn missed_slots(&self, ext: &Extensions) -> RpcResult<Vec<MissedSlot>> {
+		check_if_safe(ext)?;
+
+		Ok(self
+			.diagnostics
+			.missed_slots()
+			.into_iter()
+			.map(|(slot, expected_author)| MissedSlot { slot: *slot, expected_author })
+			.collect())
+	}
+}
+
+/// A summary of the slots recorded by the local slot worker.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotsSummary {
+	/// Number of slots recorded.
+	pub slots: usize,
+	/// Number of slots in which we authored a block.
+	pub authored: usize,
+	/// Number of slots in which we didn't produce a block before the deadline.
+	pub deadline_missed: usize,
+	/// Number of slots in which block production failed.
+	pub failed: usize,
+	/// Number of slots we skipped or backed off from.
+	pub skipped: usize,
+	/// Average time spent proposing the blocks we authored, in milliseconds.
+	pub average_proposing_ms: Option<u64>,
+	/// Number of slots missed per authority, as observed locally.
+	pub missed_by: BTreeMap<String, usize>,
+}
+
+/// A slot missed by the authority expected to author it, as observed locally.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissedSlot {
+	/// The slot number.
+	pub slot: u64,
+	/// The authority expected to author the slot.
+	pub expected_author: String,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_rpc_api::DenyUnsafe;
+	use std::time::Instant;
+
+	fn test_slots_rpc_module() -> Slots<u64> {
+		let diagnostics = SlotDiagnostics::new(None).unwrap();
+
+		for slot in 1..=3u64 {
+			diagnostics.slot_started(slot.into(), Instant::now());
+			diagnostics.note_expected_author(slot.into(), format!("author-{}", slot % 2));
+		}
+
+		// the chain progressed from slot #1 to slot #3, slot #2 was missed
+		diagnostics.note_head(1.into(), "author-1".into());
+		diagnostics.note_head(3.into(), "author-1".into());
+
+		Slots::new(diagnostics)
+	}
+
+	#[tokio::test]
+	async fn missed_slots_works() {
+		let mut api = test_slots_rpc_module().into_rpc();
+		api.extensions_mut().insert(DenyUnsafe::No);
+
+		let request = r#"{"jsonrpc":"2.0","id":1,"method":"slots_missedSlots","params":[]}"#;
+		let (response, _) = api.raw_json_request(request, 1).await.unwrap();
+		let expected =
+			r#"{"jsonrpc":"2.0","id":1,"result":[{"slot":2,"expectedAuthor":"author-0"}]}"#;
+
+		assert_eq!(response, expected);
+	}
+
+	#[tokio::test]
+	async fn summary_works() {
+		let mut api = test_slots_rpc_module().into_rpc();
+		api.extensions_mut().insert(DenyUnsafe::No);
+
+		let request = r#"{"jsonrpc":"2.0","id":1,"method":"slots_summary","params":[]}"#;
+		let (response, _) = api.raw_json_request(request, 1).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","id":1,"result":{"slots":3,"authored":0,"deadlineMissed":0,"failed":0,"skipped":0,"averageProposingMs":null,"missedBy":{"author-0":1}}}"#;
+
+		assert_eq!(response, expected);
+	}
+
+	#[tokio::test]
+	async fn slots_rpc_is_unsafe() {
+		let mut api = test_slots_rpc_module().into_rpc();
+		api.extensions_mut().insert(DenyUnsafe::Yes);
+
+		let request = r#"{"jsonrpc":"2.0","method":"slots_missedSlots","params":[],"id":1}"#;
+		let (response, _) = api.raw_json_request(request, 1).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"RPC call is unsafe to be called externally"}}"#;
+
+		assert_eq!(response, expected);
+	}
+}
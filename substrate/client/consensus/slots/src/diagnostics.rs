@@ -0,0 +1,526 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Diagnostics for the slots handled by a [`SimpleSlotWorker`](crate::SimpleSlotWorker).
+//!
+//! For every slot the worker records when the slot started, how long each phase of block
+//! production took and what the outcome was for the local node. Additionally, the authors of
+//! the blocks found at the head of the chain are tracked, which allows detecting the slots
+//! missed by other authorities as observed by this node.
+//!
+//! The records are exposed through Prometheus metrics, a summary that can be periodically
+//! logged with [`SlotDiagnostics::run_informant`], and the RPC methods of the
+//! `sc-consensus-slots-rpc` crate.
+
+use futures_timer::Delay;
+use log::info;
+use parking_lot::Mutex;
+use prometheus_endpoint::{
+	register, CounterVec, Histogram, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry,
+	U64,
+};
+use sp_consensus_slots::Slot;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fmt,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use crate::LOG_TARGET;
+
+/// The default number of slots kept by [`SlotDiagnostics`].
+pub const DEFAULT_DIAGNOSTICS_CAPACITY: usize = 256;
+
+/// A phase of block production timed by the slot diagnostics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotPhase {
+	/// Creation of the inherent data.
+	InherentData,
+	/// Proposing the block.
+	Proposing,
+	/// Importing the block.
+	Import,
+}
+
+impl SlotPhase {
+	fn as_str(&self) -> &'static str {
+		match self {
+			SlotPhase::InherentData => "inherent_data",
+			SlotPhase::Proposing => "proposing",
+			SlotPhase::Import => "import",
+		}
+	}
+}
+
+/// The outcome of a slot for the local node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlotOutcome<Hash> {
+	/// The slot is still being handled.
+	Pending,
+	/// The slot was skipped without trying to claim it, e.g. because there was no time left or
+	/// we were waiting for the network.
+	Skipped,
+	/// We weren't eligible to author a block in the slot.
+	NotClaimed,
+	/// We claimed the slot but backed off from authoring a block.
+	BackedOff,
+	/// The block wasn't produced before the proposing deadline.
+	DeadlineMissed,
+	/// Block production failed.
+	Failed,
+	/// We authored and imported a block with the given hash.
+	Authored(Hash),
+}
+
+impl<Hash> SlotOutcome<Hash> {
+	fn as_str(&self) -> &'static str {
+		match self {
+			SlotOutcome::Pending => "pending",
+			SlotOutcome::Skipped => "skipped",
+			SlotOutcome::NotClaimed => "not_claimed",
+			SlotOutcome::BackedOff => "backed_off",
+			SlotOutcome::DeadlineMissed => "deadline_missed",
+			SlotOutcome::Failed => "failed",
+			SlotOutcome::Authored(_) => "authored",
+		}
+	}
+}
+
+/// Diagnostics recorded for a single slot.
+#[derive(Clone, Debug)]
+pub struct SlotRecord<Hash> {
+	/// The slot number.
+	pub slot: Slot,
+	/// The instant at which the slot started.
+	pub started_at: Instant,
+	/// How late the slot worker was woken up after the slot started.
+	pub start_delay: Duration,
+	/// Time spent creating the inherent data.
+	pub inherent_data: Option<Duration>,
+	/// Time spent proposing the block.
+	pub proposing: Option<Duration>,
+	/// Time spent importing the block.
+	pub import: Option<Duration>,
+	/// Time since the start of the slot at which the authored block was imported, and thus
+	/// announced to the network.
+	pub announced: Option<Duration>,
+	/// The outcome of the slot for the local node.
+	pub outcome: SlotOutcome<Hash>,
+	/// The authority expected to author the slot, if it is known ahead of time.
+	pub expected_author: Option<String>,
+	/// The author of the block observed at the head of the chain for this slot.
+	pub observed_author: Option<String>,
+	/// Whether the chain has progressed past this slot without a block authored in it.
+	pub missed: bool,
+}
+
+impl<Hash> SlotRecord<Hash> {
+	fn new(slot: Slot, started_at: Instant) -> Self {
+		SlotRecord {
+			slot,
+			started_at,
+			start_delay: started_at.elapsed(),
+			inherent_data: None,
+			proposing: None,
+			import: None,
+			announced: None,
+			outcome: SlotOutcome::Pending,
+			expected_author: None,
+			observed_author: None,
+			missed: false,
+		}
+	}
+}
+
+/// A summary of the slots recorded by [`SlotDiagnostics`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotSummary {
+	/// Number of slots recorded.
+	pub slots: usize,
+	/// Number of slots in which we authored a block.
+	pub authored: usize,
+	/// Number of slots in which we didn't produce a block before the deadline.
+	pub deadline_missed: usize,
+	/// Number of slots in which block production failed.
+	pub failed: usize,
+	/// Number of slots we skipped or backed off from.
+	pub skipped: usize,
+	/// Average time spent proposing the blocks we authored.
+	pub average_proposing: Option<Duration>,
+	/// Number of slots missed per authority, as observed locally.
+	pub missed_by: BTreeMap<String, usize>,
+}
+
+impl fmt::Display for SlotSummary {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} slots, {} authored, {} missed deadline, {} failed, {} skipped",
+			self.slots, self.authored, self.deadline_missed, self.failed, self.skipped,
+		)?;
+
+		if let Some(average_proposing) = self.average_proposing {
+			write!(f, ", avg proposing {}ms", average_proposing.as_millis())?;
+		}
+
+		if !self.missed_by.is_empty() {
+			let missed_by = self
+				.missed_by
+				.iter()
+				.map(|(author, missed)| format!("{author} ({missed})"))
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			write!(f, ", missed by {missed_by}")?;
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone)]
+struct Metrics {
+	phase_duration: HistogramVec,
+	start_delay: Histogram,
+	outcomes: CounterVec<U64>,
+	observed_authored: CounterVec<U64>,
+	observed_missed: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			phase_duration: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"substrate_slots_phase_duration_seconds",
+						"Time spent in each phase of block production",
+					),
+					&["phase"],
+				)?,
+				registry,
+			)?,
+			start_delay: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"substrate_slots_start_delay_seconds",
+					"Delay between the start of a slot and the slot worker handling it",
+				))?,
+				registry,
+			)?,
+			outcomes: register(
+				CounterVec::new(
+					Opts::new("substrate_slots_outcome_total", "Outcome of the handled slots"),
+					&["outcome"],
+				)?,
+				registry,
+			)?,
+			observed_authored: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_slots_observed_authored_total",
+						"Slots observed to be authored, per authority",
+					),
+					&["authority"],
+				)?,
+				registry,
+			)?,
+			observed_missed: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_slots_observed_missed_total",
+						"Slots observed to be missed, per authority",
+					),
+					&["authority"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+struct Inner<Hash> {
+	records: VecDeque<SlotRecord<Hash>>,
+	capacity: usize,
+	last_head_slot: Option<Slot>,
+}
+
+impl<Hash> Inner<Hash> {
+	fn record_mut(&mut self, slot: Slot) -> Option<&mut SlotRecord<Hash>> {
+		self.records.iter_mut().rev().find(|record| record.slot == slot)
+	}
+}
+
+/// A handle to the slot diagnostics of a slot worker.
+///
+/// The handle can be cheaply cloned, all clones refer to the same records.
+pub struct SlotDiagnostics<Hash> {
+	inner: Arc<Mutex<Inner<Hash>>>,
+	metrics: Option<Metrics>,
+}
+
+impl<Hash> Clone for SlotDiagnostics<Hash> {
+	fn clone(&self) -> Self {
+		SlotDiagnostics { inner: self.inner.clone(), metrics: self.metrics.clone() }
+	}
+}
+
+impl<Hash: Clone> SlotDiagnostics<Hash> {
+	/// Create new slot diagnostics keeping the last [`DEFAULT_DIAGNOSTICS_CAPACITY`] slots,
+	/// registering the metrics in the given registry.
+	pub fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		Self::with_capacity(DEFAULT_DIAGNOSTICS_CAPACITY, registry)
+	}
+
+	/// Create new slot diagnostics keeping the last `capacity` slots, registering the metrics
+	/// in the given registry.
+	pub fn with_capacity(
+		capacity: usize,
+		registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		let metrics = registry.map(Metrics::register).transpose()?;
+		let inner =
+			Inner { records: VecDeque::with_capacity(capacity), capacity, last_head_slot: None };
+
+		Ok(SlotDiagnostics { inner: Arc::new(Mutex::new(inner)), metrics })
+	}
+
+	/// Start recording the given slot, which started at `started_at`.
+	pub fn slot_started(&self, slot: Slot, started_at: Instant) {
+		let record = SlotRecord::new(slot, started_at);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.start_delay.observe(record.start_delay.as_secs_f64());
+		}
+
+		let mut inner = self.inner.lock();
+		if inner.records.len() >= inner.capacity {
+			inner.records.pop_front();
+		}
+		inner.records.push_back(record);
+	}
+
+	/// Note the time spent in the given phase of block production for the given slot.
+	pub fn note_phase(&self, slot: Slot, phase: SlotPhase, duration: Duration) {
+		if let Some(metrics) = &self.metrics {
+			metrics
+				.phase_duration
+				.with_label_values(&[phase.as_str()])
+				.observe(duration.as_secs_f64());
+		}
+
+		let mut inner = self.inner.lock();
+		let Some(record) = inner.record_mut(slot) else { return };
+
+		match phase {
+			SlotPhase::InherentData => record.inherent_data = Some(duration),
+			SlotPhase::Proposing => record.proposing = Some(duration),
+			SlotPhase::Import => {
+				record.import = Some(duration);
+				record.announced = Some(record.started_at.elapsed());
+			},
+		}
+	}
+
+	/// Note the outcome of the given slot for the local node.
+	pub fn note_outcome(&self, slot: Slot, outcome: SlotOutcome<Hash>) {
+		if let Some(metrics) = &self.metrics {
+			metrics.outcomes.with_label_values(&[outcome.as_str()]).inc();
+		}
+
+		if let Some(record) = self.inner.lock().record_mut(slot) {
+			record.outcome = outcome;
+		}
+	}
+
+	/// Note the authority expected to author the given slot.
+	pub fn note_expected_author(&self, slot: Slot, author: String) {
+		if let Some(record) = self.inner.lock().record_mut(slot) {
+			record.expected_author = Some(author);
+		}
+	}
+
+	/// Note the block at the head of the chain, authored by `author` in the given slot.
+	///
+	/// All the recorded slots between the previous head and this one which had an expected
+	/// author are considered missed.
+	pub fn note_head(&self, slot: Slot, author: String) {
+		let mut inner = self.inner.lock();
+
+		// the same head is noted on every slot until a new block is imported, and we don't
+		// account for reorgs to lower slots
+		if inner.last_head_slot.map_or(false, |last| last >= slot) {
+			return
+		}
+
+		let last_head_slot = inner.last_head_slot.replace(slot);
+		for record in inner.records.iter_mut().filter(|record| {
+			last_head_slot.map_or(true, |last| record.slot > last) && record.slot <= slot
+		}) {
+			if record.slot == slot {
+				if let Some(metrics) = &self.metrics {
+					metrics.observed_authored.with_label_values(&[author.as_str()]).inc();
+				}
+
+				record.observed_author = Some(author.clone());
+			} else if let Some(expected_author) = &record.expected_author {
+				if let Some(metrics) = &self.metrics {
+					metrics.observed_missed.with_label_values(&[expected_author.as_str()]).inc();
+				}
+
+				record.missed = true;
+			}
+		}
+	}
+
+	/// Returns the recorded slots, from the oldest to the most recent one.
+	pub fn records(&self) -> Vec<SlotRecord<Hash>> {
+		self.inner.lock().records.iter().cloned().collect()
+	}
+
+	/// Returns the recorded slots that were missed, along with the authority expected to
+	/// author them, from the oldest to the most recent one.
+	pub fn missed_slots(&self) -> Vec<(Slot, String)> {
+		self.inner
+			.lock()
+			.records
+			.iter()
+			.filter(|record| record.missed)
+			.filter_map(|record| Some((record.slot, record.expected_author.clone()?)))
+			.collect()
+	}
+
+	/// Returns a summary of the recorded slots.
+	pub fn summary(&self) -> SlotSummary {
+		let inner = self.inner.lock();
+		let mut summary = SlotSummary { slots: inner.records.len(), ..Default::default() };
+		let mut total_proposing = Duration::ZERO;
+
+		for record in inner.records.iter() {
+			match record.outcome {
+				SlotOutcome::Authored(_) => {
+					summary.authored += 1;
+					total_proposing += record.proposing.unwrap_or_default();
+				},
+				SlotOutcome::DeadlineMissed => summary.deadline_missed += 1,
+				SlotOutcome::Failed => summary.failed += 1,
+				SlotOutcome::Skipped | SlotOutcome::BackedOff => summary.skipped += 1,
+				SlotOutcome::Pending | SlotOutcome::NotClaimed => {},
+			}
+
+			if let (true, Some(expected_author)) = (record.missed, &record.expected_author) {
+				*summary.missed_by.entry(expected_author.clone()).or_default() += 1;
+			}
+		}
+
+		if summary.authored > 0 {
+			summary.average_proposing = Some(total_proposing / summary.authored as u32);
+		}
+
+		summary
+	}
+
+	/// Periodically log a summary of the recorded slots, the returned future never resolves.
+	pub async fn run_informant(self, period: Duration) {
+		loop {
+			Delay::new(period).await;
+
+			info!(target: LOG_TARGET, "🎰 Slots summary: {}", self.summary());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_are_bounded_by_capacity() {
+		let diagnostics = SlotDiagnostics::<u64>::with_capacity(2, None).unwrap();
+
+		for slot in 0..5u64 {
+			diagnostics.slot_started(slot.into(), Instant::now());
+		}
+
+		let slots = diagnostics.records().into_iter().map(|r| r.slot).collect::<Vec<_>>();
+		assert_eq!(slots, vec![Slot::from(3), Slot::from(4)]);
+	}
+
+	#[test]
+	fn missed_slots_are_detected_when_head_advances() {
+		let diagnostics = SlotDiagnostics::<u64>::new(None).unwrap();
+
+		for slot in 1..=4u64 {
+			diagnostics.slot_started(slot.into(), Instant::now());
+			diagnostics.note_expected_author(slot.into(), format!("author-{}", slot % 2));
+		}
+
+		diagnostics.note_head(1.into(), "author-1".into());
+		// noting the same head again doesn't change anything
+		diagnostics.note_head(1.into(), "author-1".into());
+		diagnostics.note_head(4.into(), "author-0".into());
+
+		let records = diagnostics.records();
+		assert_eq!(records[0].observed_author.as_deref(), Some("author-1"));
+		assert!(!records[0].missed);
+		assert!(records[1].missed);
+		assert!(records[2].missed);
+		assert!(!records[3].missed);
+
+		assert_eq!(
+			diagnostics.missed_slots(),
+			vec![(Slot::from(2), "author-0".to_string()), (Slot::from(3), "author-1".to_string())],
+		);
+
+		let summary = diagnostics.summary();
+		assert_eq!(summary.slots, 4);
+		assert_eq!(
+			summary.missed_by.into_iter().collect::<Vec<_>>(),
+			vec![("author-0".to_string(), 1), ("author-1".to_string(), 1)],
+		);
+	}
+
+	#[test]
+	fn summary_counts_outcomes() {
+		let diagnostics = SlotDiagnostics::new(None).unwrap();
+
+		let outcomes = [
+			SlotOutcome::Authored(1u64),
+			SlotOutcome::DeadlineMissed,
+			SlotOutcome::Failed,
+			SlotOutcome::Skipped,
+			SlotOutcome::BackedOff,
+			SlotOutcome::NotClaimed,
+		];
+
+		for (slot, outcome) in outcomes.into_iter().enumerate() {
+			let slot = Slot::from(slot as u64);
+			diagnostics.slot_started(slot, Instant::now());
+			diagnostics.note_phase(slot, SlotPhase::Proposing, Duration::from_millis(100));
+			diagnostics.note_outcome(slot, outcome);
+		}
+
+		let summary = diagnostics.summary();
+		assert_eq!(summary.slots, 6);
+		assert_eq!(summary.authored, 1);
+		assert_eq!(summary.deadline_missed, 1);
+		assert_eq!(summary.failed, 1);
+		assert_eq!(summary.skipped, 2);
+		assert_eq!(summary.average_proposing, Some(Duration::from_millis(100)));
+	}
+}
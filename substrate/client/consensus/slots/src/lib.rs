@@ -26,9 +26,13 @@
 #![warn(missing_docs)]
 
 mod aux_schema;
+mod diagnostics;
 mod slots;
 
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use diagnostics::{
+	SlotDiagnostics, SlotOutcome, SlotPhase, SlotRecord, SlotSummary, DEFAULT_DIAGNOSTICS_CAPACITY,
+};
 use slots::Slots;
 pub use slots::{time_until_next_slot, SlotInfo};
 
@@ -175,6 +179,29 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Returns a [`TelemetryHandle`] if any.
 	fn telemetry(&self) -> Option<TelemetryHandle>;
 
+	/// Returns the [`SlotDiagnostics`] recording the slots handled by this worker, if any.
+	fn slot_diagnostics(&self) -> Option<&SlotDiagnostics<B::Hash>> {
+		None
+	}
+
+	/// Returns the authority expected to author the given slot, if it is known ahead of time.
+	///
+	/// Only used for slot diagnostics, by default this function returns `None`.
+	fn slot_author(&self, _slot: Slot, _aux_data: &Self::AuxData) -> Option<String> {
+		None
+	}
+
+	/// Returns the slot the given block was authored in, and its author.
+	///
+	/// Only used for slot diagnostics, by default this function returns `None`.
+	fn block_author(
+		&self,
+		_header: &B::Header,
+		_aux_data: &Self::AuxData,
+	) -> Option<(Slot, String)> {
+		None
+	}
+
 	/// Remaining duration for proposing.
 	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> Duration;
 
@@ -189,9 +216,27 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		let slot = slot_info.slot;
 		let telemetry = self.telemetry();
 		let log_target = self.logging_target();
+		let diagnostics = self.slot_diagnostics().cloned();
+
+		let inherent_data_start = Instant::now();
+		let Some(inherent_data) =
+			Self::create_inherent_data(&slot_info, &log_target, end_proposing_at).await
+		else {
+			if let Some(diagnostics) = &diagnostics {
+				let outcome = if Instant::now() >= end_proposing_at {
+					SlotOutcome::DeadlineMissed
+				} else {
+					SlotOutcome::Failed
+				};
+				diagnostics.note_outcome(slot, outcome);
+			}
 
-		let inherent_data =
-			Self::create_inherent_data(&slot_info, &log_target, end_proposing_at).await?;
+			return None
+		};
+
+		if let Some(diagnostics) = &diagnostics {
+			diagnostics.note_phase(slot, SlotPhase::InherentData, inherent_data_start.elapsed());
+		}
 
 		let proposing_remaining_duration =
 			end_proposing_at.saturating_duration_since(Instant::now());
@@ -209,6 +254,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			)
 			.map_err(|e| sp_consensus::Error::ClientImport(e.to_string()));
 
+		let proposing_start = Instant::now();
 		let proposal = match futures::future::select(
 			proposing,
 			Delay::new(proposing_remaining_duration),
@@ -219,6 +265,10 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Either::Left((Err(err), _)) => {
 				warn!(target: log_target, "Proposing failed: {}", err);
 
+				if let Some(diagnostics) = &diagnostics {
+					diagnostics.note_outcome(slot, SlotOutcome::Failed);
+				}
+
 				return None
 			},
 			Either::Right(_) => {
@@ -239,10 +289,18 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slot" => *slot,
 				);
 
+				if let Some(diagnostics) = &diagnostics {
+					diagnostics.note_outcome(slot, SlotOutcome::DeadlineMissed);
+				}
+
 				return None
 			},
 		};
 
+		if let Some(diagnostics) = &diagnostics {
+			diagnostics.note_phase(slot, SlotPhase::Proposing, proposing_start.elapsed());
+		}
+
 		Some(proposal)
 	}
 
@@ -293,6 +351,18 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		let slot = slot_info.slot;
 		let telemetry = self.telemetry();
 		let logging_target = self.logging_target();
+		let diagnostics = self.slot_diagnostics().cloned();
+		let note_outcome = |outcome| {
+			if let Some(diagnostics) = &diagnostics {
+				diagnostics.note_outcome(slot, outcome);
+			}
+		};
+
+		if let Some(diagnostics) = &diagnostics {
+			let started_at =
+				slot_info.ends_at.checked_sub(slot_info.duration).unwrap_or_else(Instant::now);
+			diagnostics.slot_started(slot, started_at);
+		}
 
 		let proposing_remaining_duration = self.proposing_remaining_duration(&slot_info);
 
@@ -301,6 +371,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 				target: logging_target,
 				"Skipping proposal slot {} since there's no time left to propose", slot,
 			);
+			note_outcome(SlotOutcome::Skipped);
 
 			return None
 		} else {
@@ -324,6 +395,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slot" => ?slot_info.chain_head.hash(),
 					"err" => ?err,
 				);
+				note_outcome(SlotOutcome::Failed);
 
 				return None
 			},
@@ -331,6 +403,16 @@ pub trait SimpleSlotWorker<B: BlockT> {
 
 		self.notify_slot(&slot_info.chain_head, slot, &aux_data);
 
+		if let Some(diagnostics) = &diagnostics {
+			if let Some(author) = self.slot_author(slot, &aux_data) {
+				diagnostics.note_expected_author(slot, author);
+			}
+
+			if let Some((head_slot, author)) = self.block_author(&slot_info.chain_head, &aux_data) {
+				diagnostics.note_head(head_slot, author);
+			}
+		}
+
 		let authorities_len = self.authorities_len(&aux_data);
 
 		if !self.force_authoring() &&
@@ -344,13 +426,20 @@ pub trait SimpleSlotWorker<B: BlockT> {
 				"slots.skipping_proposal_slot";
 				"authorities_len" => authorities_len,
 			);
+			note_outcome(SlotOutcome::Skipped);
 
 			return None
 		}
 
-		let claim = self.claim_slot(&slot_info.chain_head, slot, &aux_data).await?;
+		let Some(claim) = self.claim_slot(&slot_info.chain_head, slot, &aux_data).await else {
+			note_outcome(SlotOutcome::NotClaimed);
+
+			return None
+		};
 
 		if self.should_backoff(slot, &slot_info.chain_head) {
+			note_outcome(SlotOutcome::BackedOff);
+
 			return None
 		}
 
@@ -370,6 +459,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slot" => *slot,
 					"err" => ?err
 				);
+				note_outcome(SlotOutcome::Failed);
 
 				return None
 			},
@@ -397,6 +487,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Ok(bi) => bi,
 			Err(err) => {
 				warn!(target: logging_target, "Failed to create block import params: {}", err);
+				note_outcome(SlotOutcome::Failed);
 
 				return None
 			},
@@ -420,8 +511,14 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		);
 
 		let header = block_import_params.post_header();
+		let import_start = Instant::now();
 		match self.block_import().import_block(block_import_params).await {
 			Ok(res) => {
+				if let Some(diagnostics) = &diagnostics {
+					diagnostics.note_phase(slot, SlotPhase::Import, import_start.elapsed());
+				}
+				note_outcome(SlotOutcome::Authored(header.hash()));
+
 				res.handle_justification(
 					&header.hash(),
 					*header.number(),
@@ -441,6 +538,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"hash" => ?parent_hash,
 					"err" => ?err,
 				);
+				note_outcome(SlotOutcome::Failed);
 			},
 		}
 
@@ -91,7 +91,10 @@ use sp_state_machine::{
 	OffchainChangesCollection, StateMachineStats, StorageCollection, StorageIterator, StorageKey,
 	StorageValue, UsageInfo as StateUsageInfo,
 };
-use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, MerkleValue, PrefixedMemoryDB};
+use sp_trie::{
+	cache::{SharedTrieCache, TrieCacheSnapshot},
+	prefixed_key, MemoryDB, MerkleValue, PrefixedMemoryDB,
+};
 use utils::BLOCK_GAP_CURRENT_VERSION;
 
 // Re-export the Database trait so that one can pass an implementation of it.
@@ -304,6 +307,10 @@ pub struct DatabaseSettings {
 	///
 	/// If `None` is given, the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Persistence of the hottest trie cache entries across restarts.
+	///
+	/// Ignored if the trie cache is disabled.
+	pub trie_cache_persistence: Option<TrieCachePersistence>,
 	/// Requested state pruning mode.
 	pub state_pruning: Option<PruningMode>,
 	/// Where to find the database.
@@ -314,6 +321,19 @@ pub struct DatabaseSettings {
 	pub blocks_pruning: BlocksPruning,
 }
 
+/// Trie cache persistence settings.
+///
+/// On shutdown the hottest entries of the trie cache are written to [`Self::path`]. They are
+/// loaded back on startup if the best block didn't change in the meantime, so that the node
+/// doesn't need to warm up its cache from scratch.
+#[derive(Debug, Clone)]
+pub struct TrieCachePersistence {
+	/// The file the trie cache entries are persisted to.
+	pub path: PathBuf,
+	/// The maximum size in bytes of the persisted trie cache entries.
+	pub maximum_size: usize,
+}
+
 /// Block pruning settings.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlocksPruning {
@@ -1115,6 +1135,7 @@ pub struct Backend<Block: BlockT> {
 	state_usage: Arc<StateUsageStats>,
 	genesis_state: RwLock<Option<Arc<DbGenesisStorage<Block>>>>,
 	shared_trie_cache: Option<sp_trie::cache::SharedTrieCache<HashingFor<Block>>>,
+	trie_cache_persistence: Option<TrieCachePersistence>,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -1168,6 +1189,7 @@ impl<Block: BlockT> Backend<Block> {
 		};
 		let db_setting = DatabaseSettings {
 			trie_cache_maximum_size: Some(16 * 1024 * 1024),
+			trie_cache_persistence: None,
 			state_pruning: Some(state_pruning),
 			source: DatabaseSource::Custom { db, require_create_flag: true },
 			blocks_pruning,
@@ -1238,6 +1260,7 @@ impl<Block: BlockT> Backend<Block> {
 			shared_trie_cache: config.trie_cache_maximum_size.map(|maximum_size| {
 				SharedTrieCache::new(sp_trie::cache::CacheSize::new(maximum_size))
 			}),
+			trie_cache_persistence: config.trie_cache_persistence.clone(),
 		};
 
 		// Older DB versions have no last state key. Check if the state is available and set it.
@@ -1260,9 +1283,97 @@ impl<Block: BlockT> Backend<Block> {
 
 		db.commit(db_init_transaction)?;
 
+		backend.import_trie_cache_snapshot();
+
 		Ok(backend)
 	}
 
+	/// Preload the shared trie cache with the entries persisted on the last shutdown.
+	fn import_trie_cache_snapshot(&self) {
+		let (Some(cache), Some(persistence)) =
+			(&self.shared_trie_cache, &self.trie_cache_persistence)
+		else {
+			return
+		};
+
+		let bytes = match std::fs::read(&persistence.path) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+			Err(e) => {
+				warn!(
+					target: "db",
+					"Failed to read trie cache snapshot from {}: {}",
+					persistence.path.display(),
+					e,
+				);
+				return
+			},
+		};
+
+		let snapshot = match TrieCacheSnapshot::from_bytes::<HashingFor<Block>>(&bytes) {
+			Ok(snapshot) => snapshot,
+			Err(e) => {
+				warn!(target: "db", "Ignoring trie cache snapshot: {}", e);
+				return
+			},
+		};
+
+		let best_hash = self.blockchain.info().best_hash;
+		let storage_root = match self.blockchain.header(best_hash) {
+			Ok(Some(header)) => *header.state_root(),
+			_ => return,
+		};
+
+		let (nodes, values) = cache.import_snapshot(snapshot, &storage_root);
+		debug!(
+			target: "db",
+			"Preloaded the trie cache at {} with {} nodes and {} values",
+			best_hash,
+			nodes,
+			values,
+		);
+	}
+
+	/// Persist the hottest entries of the shared trie cache, to be imported on the next start.
+	fn export_trie_cache_snapshot(&self) {
+		let (Some(cache), Some(persistence)) =
+			(&self.shared_trie_cache, &self.trie_cache_persistence)
+		else {
+			return
+		};
+
+		let best_hash = self.blockchain.info().best_hash;
+		let storage_root = match self.blockchain.header(best_hash) {
+			Ok(Some(header)) => *header.state_root(),
+			_ => return,
+		};
+
+		let snapshot = cache.export_snapshot(storage_root, persistence.maximum_size);
+		let bytes = snapshot.to_bytes::<HashingFor<Block>>();
+
+		// Write to a temporary file first, to never leave a partially written snapshot behind.
+		let tmp_path = persistence.path.with_extension("tmp");
+		let result = std::fs::write(&tmp_path, &bytes)
+			.and_then(|_| std::fs::rename(&tmp_path, &persistence.path));
+
+		match result {
+			Ok(()) => debug!(
+				target: "db",
+				"Persisted {} trie cache nodes and {} values ({} bytes) at {}",
+				snapshot.nodes_len(),
+				snapshot.values_len(),
+				bytes.len(),
+				best_hash,
+			),
+			Err(e) => warn!(
+				target: "db",
+				"Failed to write trie cache snapshot to {}: {}",
+				persistence.path.display(),
+				e,
+			),
+		}
+	}
+
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -2238,6 +2349,8 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		let state_cache = MemorySize::from_bytes(
 			self.shared_trie_cache.as_ref().map_or(0, |c| c.used_memory_size()),
 		);
+		let trie_cache_stats =
+			self.shared_trie_cache.as_ref().map(|c| c.stats()).unwrap_or_default();
 
 		Some(UsageInfo {
 			memory: MemoryInfo { state_cache, database_cache },
@@ -2253,6 +2366,10 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				state_writes_cache: state_stats.overlay_writes.ops,
 				state_reads_cache: state_stats.cache_reads.ops,
 				state_writes_nodes: state_stats.nodes_writes.ops,
+				trie_cache_node_hits: trie_cache_stats.node_hits,
+				trie_cache_node_fetch_attempts: trie_cache_stats.node_fetch_attempts,
+				trie_cache_value_hits: trie_cache_stats.value_hits,
+				trie_cache_value_fetch_attempts: trie_cache_stats.value_fetch_attempts,
 			},
 		})
 	}
@@ -2595,6 +2712,12 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 
 impl<Block: BlockT> sc_client_api::backend::LocalBackend<Block> for Backend<Block> {}
 
+impl<Block: BlockT> Drop for Backend<Block> {
+	fn drop(&mut self) {
+		self.export_trie_cache_snapshot();
+	}
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
 	use super::*;
@@ -2769,6 +2892,7 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(
 			DatabaseSettings {
 				trie_cache_maximum_size: Some(16 * 1024 * 1024),
+				trie_cache_persistence: None,
 				state_pruning: Some(PruningMode::blocks_pruning(1)),
 				source: DatabaseSource::Custom { db: backing, require_create_flag: false },
 				blocks_pruning: BlocksPruning::KeepFinalized,
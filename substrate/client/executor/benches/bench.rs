@@ -34,6 +34,7 @@ use std::sync::{
 #[derive(Clone)]
 enum Method {
 	Compiled { instantiation_strategy: InstantiationStrategy, precompile: bool },
+	Interpreted,
 }
 
 // This is just a bog-standard Kusama runtime with an extra
@@ -92,6 +93,16 @@ fn initialize(
 			}
 			.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) })
 		},
+		Method::Interpreted => {
+			let config = sc_executor_wasmi::Config {
+				allow_missing_func_imports,
+				heap_alloc_strategy: DEFAULT_HEAP_ALLOC_STRATEGY,
+				deterministic_stack_limit: None,
+			};
+
+			sc_executor_wasmi::create_runtime::<sp_io::SubstrateHostFunctions>(blob, config)
+				.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) })
+		},
 	}
 	.unwrap()
 }
@@ -200,6 +211,7 @@ fn bench_call_instance(c: &mut Criterion) {
 				precompile: true,
 			},
 		),
+		("interpreted", Method::Interpreted),
 	];
 
 	let runtimes = [("kusama_runtime", kusama_runtime()), ("test_runtime", test_runtime())];
@@ -18,9 +18,10 @@
 
 use crate::{error::WasmError, wasm_runtime::HeapAllocStrategy};
 use polkavm::ArcBytes;
+use sp_wasm_interface::Signature;
 use wasm_instrument::parity_wasm::elements::{
 	deserialize_buffer, serialize, ExportEntry, External, Internal, MemorySection, MemoryType,
-	Module, Section,
+	Module, Section, Type, ValueType,
 };
 
 /// A program blob containing a Substrate runtime.
@@ -188,6 +189,39 @@ impl RuntimeBlob {
 			.map(|cs| cs.payload())
 	}
 
+	/// Returns the names of all functions imported by this program.
+	///
+	/// For WASM programs the signature of each import is returned as well, unless it uses value
+	/// types that aren't supported by the host. PolkaVM programs don't carry any signatures.
+	pub fn imported_functions(&self) -> Vec<(String, Option<Signature>)> {
+		match self.0 {
+			BlobKind::WebAssembly(ref raw_module) => {
+				let types =
+					raw_module.type_section().map(|section| section.types()).unwrap_or_default();
+
+				raw_module
+					.import_section()
+					.map(|section| section.entries())
+					.unwrap_or_default()
+					.iter()
+					.filter_map(|entry| match entry.external() {
+						External::Function(type_index) => Some((
+							entry.field().to_owned(),
+							types.get(*type_index as usize).and_then(signature_of),
+						)),
+						_ => None,
+					})
+					.collect()
+			},
+			BlobKind::PolkaVM((ref blob, _)) => blob
+				.imports()
+				.iter()
+				.flatten()
+				.map(|symbol| (String::from_utf8_lossy(symbol.as_bytes()).into_owned(), None))
+				.collect(),
+		}
+	}
+
 	/// Consumes this runtime blob and serializes it.
 	pub fn serialize(self) -> Vec<u8> {
 		match self.0 {
@@ -232,3 +266,28 @@ impl RuntimeBlob {
 		}
 	}
 }
+
+fn signature_of(ty: &Type) -> Option<Signature> {
+	let Type::Function(function) = ty;
+
+	let args = function.params().iter().map(|ty| value_type_of(*ty)).collect::<Option<Vec<_>>>()?;
+	let return_value = match function.results() {
+		[] => None,
+		[ty] => Some(value_type_of(*ty)?),
+		_ => return None,
+	};
+
+	Some(Signature::new(args, return_value))
+}
+
+fn value_type_of(ty: ValueType) -> Option<sp_wasm_interface::ValueType> {
+	Some(match ty {
+		ValueType::I32 => sp_wasm_interface::ValueType::I32,
+		ValueType::I64 => sp_wasm_interface::ValueType::I64,
+		ValueType::F32 => sp_wasm_interface::ValueType::F32,
+		ValueType::F64 => sp_wasm_interface::ValueType::F64,
+		// `V128` only exists with the `simd` feature of `parity-wasm`.
+		#[allow(unreachable_patterns)]
+		_ => return None,
+	})
+}
@@ -19,7 +19,7 @@
 use crate::{
 	error::{Error, Result},
 	wasm_runtime::{RuntimeCache, WasmExecutionMethod},
-	RuntimeVersionOf,
+	PrecompileRuntime, RuntimeVersionOf,
 };
 
 use std::{
@@ -315,6 +315,18 @@ impl<H> WasmExecutor<H> {
 	pub fn allow_missing_host_functions(&mut self, allow_missing_host_functions: bool) {
 		self.allow_missing_host_functions = allow_missing_host_functions
 	}
+
+	/// The heap allocation strategy for onchain calls into `runtime_code`.
+	fn on_chain_heap_alloc_strategy(&self, runtime_code: &RuntimeCode) -> HeapAllocStrategy {
+		if self.ignore_onchain_heap_pages {
+			self.default_onchain_heap_alloc_strategy
+		} else {
+			runtime_code
+				.heap_pages
+				.map(|h| HeapAllocStrategy::Static { extra_pages: h as _ })
+				.unwrap_or_else(|| self.default_onchain_heap_alloc_strategy)
+		}
+	}
 }
 
 impl<H> WasmExecutor<H>
@@ -507,19 +519,11 @@ where
 			%method,
 			"Executing function",
 		);
-
-		let on_chain_heap_alloc_strategy = if self.ignore_onchain_heap_pages {
-			self.default_onchain_heap_alloc_strategy
-		} else {
-			runtime_code
-				.heap_pages
-				.map(|h| HeapAllocStrategy::Static { extra_pages: h as _ })
-				.unwrap_or_else(|| self.default_onchain_heap_alloc_strategy)
-		};
+		let _span = tracing::trace_span!(target: "runtime_api", "call", method).entered();
 
 		let heap_alloc_strategy = match context {
 			CallContext::Offchain => self.default_offchain_heap_alloc_strategy,
-			CallContext::Onchain => on_chain_heap_alloc_strategy,
+			CallContext::Onchain => self.on_chain_heap_alloc_strategy(runtime_code),
 		};
 
 		let result = self.with_instance(
@@ -544,19 +548,10 @@ where
 		ext: &mut dyn Externalities,
 		runtime_code: &RuntimeCode,
 	) -> Result<RuntimeVersion> {
-		let on_chain_heap_pages = if self.ignore_onchain_heap_pages {
-			self.default_onchain_heap_alloc_strategy
-		} else {
-			runtime_code
-				.heap_pages
-				.map(|h| HeapAllocStrategy::Static { extra_pages: h as _ })
-				.unwrap_or_else(|| self.default_onchain_heap_alloc_strategy)
-		};
-
 		self.with_instance(
 			runtime_code,
 			ext,
-			on_chain_heap_pages,
+			self.on_chain_heap_alloc_strategy(runtime_code),
 			|_module, _instance, version, _ext| {
 				Ok(version.cloned().ok_or_else(|| Error::ApiError("Unknown version".into())))
 			},
@@ -564,6 +559,25 @@ where
 	}
 }
 
+impl<H> PrecompileRuntime for WasmExecutor<H>
+where
+	H: HostFunctions,
+{
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &RuntimeCode,
+	) -> Result<bool> {
+		self.cache.precompile::<H>(
+			runtime_code,
+			ext,
+			self.method,
+			self.on_chain_heap_alloc_strategy(runtime_code),
+			self.allow_missing_host_functions,
+		)
+	}
+}
+
 /// A generic `CodeExecutor` implementation that uses a delegate to determine wasm code equivalence
 /// and dispatch to native code when possible, falling back on `WasmExecutor` when not.
 #[deprecated(
@@ -651,6 +665,17 @@ impl<D: NativeExecutionDispatch> RuntimeVersionOf for NativeElseWasmExecutor<D>
 	}
 }
 
+#[allow(deprecated)]
+impl<D: NativeExecutionDispatch> PrecompileRuntime for NativeElseWasmExecutor<D> {
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &RuntimeCode,
+	) -> Result<bool> {
+		self.wasm.precompile_runtime(ext, runtime_code)
+	}
+}
+
 #[allow(deprecated)]
 impl<D: NativeExecutionDispatch> GetNativeVersion for NativeElseWasmExecutor<D> {
 	fn native_version(&self) -> &NativeVersion {
@@ -677,6 +702,7 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeElseWasmExecut
 			function = %method,
 			"Executing function",
 		);
+		let _span = tracing::trace_span!(target: "runtime_api", "call", method).entered();
 
 		let on_chain_heap_alloc_strategy = if self.wasm.ignore_onchain_heap_pages {
 			self.wasm.default_onchain_heap_alloc_strategy
@@ -16,7 +16,6 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use assert_matches::assert_matches;
 use codec::{Decode, Encode};
 use sc_executor_common::{
 	error::Error,
@@ -79,6 +78,12 @@ macro_rules! test_wasm_execution {
 					instantiation_strategy: sc_executor_wasmtime::InstantiationStrategy::Pooling
 				});
 			}
+
+			#[test]
+			fn [<$method_name _interpreted>]() {
+				let _ = sp_tracing::try_init_simple();
+				$method_name(WasmExecutionMethod::Interpreted);
+			}
 		}
 	};
 }
@@ -119,7 +124,7 @@ fn call_not_existing_function(wasm_method: WasmExecutionMethod) {
 	match call_in_wasm("test_calling_missing_external", &[], wasm_method, &mut ext).unwrap_err() {
 		Error::AbortedDueToTrap(error) => {
 			let expected = match wasm_method {
-				WasmExecutionMethod::Compiled { .. } =>
+				WasmExecutionMethod::Compiled { .. } | WasmExecutionMethod::Interpreted =>
 					"call to a missing function env:missing_external",
 			};
 			assert_eq!(error.message, expected);
@@ -138,7 +143,7 @@ fn call_yet_another_not_existing_function(wasm_method: WasmExecutionMethod) {
 	{
 		Error::AbortedDueToTrap(error) => {
 			let expected = match wasm_method {
-				WasmExecutionMethod::Compiled { .. } =>
+				WasmExecutionMethod::Compiled { .. } | WasmExecutionMethod::Interpreted =>
 					"call to a missing function env:yet_another_missing_external",
 			};
 			assert_eq!(error.message, expected);
@@ -428,9 +433,7 @@ fn should_trap_when_heap_exhausted(wasm_method: WasmExecutionMethod) {
 		.unwrap_err();
 
 	match err {
-		Error::AbortedDueToTrap(error)
-			if matches!(wasm_method, WasmExecutionMethod::Compiled { .. }) =>
-		{
+		Error::AbortedDueToTrap(error) => {
 			assert_eq!(
 				error.message,
 				r#"host code panicked while being called by the runtime: Failed to allocate memory: "Allocator ran out of space""#
@@ -728,7 +731,7 @@ fn unreachable_intrinsic(wasm_method: WasmExecutionMethod) {
 	match call_in_wasm("test_unreachable_intrinsic", &[], wasm_method, &mut ext).unwrap_err() {
 		Error::AbortedDueToTrap(error) => {
 			let expected = match wasm_method {
-				WasmExecutionMethod::Compiled { .. } =>
+				WasmExecutionMethod::Compiled { .. } | WasmExecutionMethod::Interpreted =>
 					"wasm trap: wasm `unreachable` instruction executed",
 			};
 			assert_eq!(error.message, expected);
@@ -754,9 +757,7 @@ fn return_huge_len(wasm_method: WasmExecutionMethod) {
 	let mut ext = ext.ext();
 
 	match call_in_wasm("test_return_huge_len", &[], wasm_method, &mut ext).unwrap_err() {
-		Error::OutputExceedsBounds => {
-			assert_matches!(wasm_method, WasmExecutionMethod::Compiled { .. });
-		},
+		Error::OutputExceedsBounds => (),
 		error => panic!("unexpected error: {:?}", error),
 	}
 }
@@ -780,9 +781,7 @@ fn return_max_memory_offset_plus_one(wasm_method: WasmExecutionMethod) {
 	match call_in_wasm("test_return_max_memory_offset_plus_one", &[], wasm_method, &mut ext)
 		.unwrap_err()
 	{
-		Error::OutputExceedsBounds => {
-			assert_matches!(wasm_method, WasmExecutionMethod::Compiled { .. });
-		},
+		Error::OutputExceedsBounds => (),
 		error => panic!("unexpected error: {:?}", error),
 	}
 }
@@ -793,9 +792,7 @@ fn return_overflow(wasm_method: WasmExecutionMethod) {
 	let mut ext = ext.ext();
 
 	match call_in_wasm("test_return_overflow", &[], wasm_method, &mut ext).unwrap_err() {
-		Error::OutputExceedsBounds => {
-			assert_matches!(wasm_method, WasmExecutionMethod::Compiled { .. });
-		},
+		Error::OutputExceedsBounds => (),
 		error => panic!("unexpected error: {:?}", error),
 	}
 }
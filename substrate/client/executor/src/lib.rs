@@ -64,6 +64,18 @@ pub trait RuntimeVersionOf {
 	) -> error::Result<RuntimeVersion>;
 }
 
+/// Prepares runtimes ahead of their first call.
+pub trait PrecompileRuntime {
+	/// Prepare the runtime for the given `runtime_code` and keep it cached for later calls.
+	///
+	/// Returns `false` if the runtime was already prepared.
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &sp_core::traits::RuntimeCode,
+	) -> error::Result<bool>;
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -48,6 +48,12 @@ pub enum WasmExecutionMethod {
 		/// The instantiation strategy to use.
 		instantiation_strategy: sc_executor_wasmtime::InstantiationStrategy,
 	},
+	/// Uses the wasmi interpreter.
+	///
+	/// This is a lot slower than [`Self::Compiled`], but doesn't generate any machine code at
+	/// runtime. Useful on hosts where JIT compilation isn't allowed and for re-executing
+	/// blocks with a second, independent engine.
+	Interpreted,
 }
 
 impl Default for WasmExecutionMethod {
@@ -166,6 +172,8 @@ pub struct RuntimeCache {
 	runtimes: Mutex<LruMap<VersionedRuntimeId, Arc<VersionedRuntime>>>,
 	/// The size of the instances cache for each runtime.
 	max_runtime_instances: usize,
+	/// The number of runtimes kept in [`Self::runtimes`].
+	runtime_cache_size: u32,
 	cache_path: Option<PathBuf>,
 }
 
@@ -185,8 +193,13 @@ impl RuntimeCache {
 		cache_path: Option<PathBuf>,
 		runtime_cache_size: u8,
 	) -> RuntimeCache {
-		let cap = ByLength::new(runtime_cache_size.max(1) as u32);
-		RuntimeCache { runtimes: Mutex::new(LruMap::new(cap)), max_runtime_instances, cache_path }
+		let runtime_cache_size = runtime_cache_size.max(1) as u32;
+		RuntimeCache {
+			runtimes: Mutex::new(LruMap::new(ByLength::new(runtime_cache_size))),
+			max_runtime_instances,
+			runtime_cache_size,
+			cache_path,
+		}
 	}
 
 	/// Prepares a WASM module instance and executes given function for it.
@@ -284,6 +297,82 @@ impl RuntimeCache {
 
 		Ok(versioned_runtime.with_instance(ext, f))
 	}
+
+	/// Prepares the runtime for `runtime_code` ahead of time and stores it in the cache.
+	///
+	/// This allows to compile a runtime that is expected to be enacted soon, so that the first
+	/// call into it doesn't pay for its compilation. In contrast to [`Self::with_instance`], the
+	/// cache isn't locked while the runtime is prepared.
+	///
+	/// The most recently used runtime, i.e. the one of the current `:code`, is never evicted to
+	/// make room for the precompiled runtime. Thus nothing is precompiled if the cache can't hold
+	/// at least two runtimes.
+	///
+	/// Returns `false` if the runtime was already in the cache or wasn't precompiled.
+	pub fn precompile<H>(
+		&self,
+		runtime_code: &RuntimeCode,
+		ext: &mut dyn Externalities,
+		wasm_method: WasmExecutionMethod,
+		heap_alloc_strategy: HeapAllocStrategy,
+		allow_missing_func_imports: bool,
+	) -> Result<bool, Error>
+	where
+		H: HostFunctions,
+	{
+		let versioned_runtime_id = VersionedRuntimeId {
+			code_hash: runtime_code.hash.clone(),
+			heap_alloc_strategy,
+			wasm_method,
+		};
+
+		if self.runtime_cache_size < 2 {
+			tracing::debug!(
+				target: "wasm-runtime",
+				"Not precompiling runtime, the runtime cache can't hold more than one runtime.",
+			);
+			return Ok(false)
+		}
+
+		if self.runtimes.lock().peek(&versioned_runtime_id).is_some() {
+			return Ok(false)
+		}
+
+		let code = runtime_code.fetch_runtime_code().ok_or(WasmError::CodeNotFound)?;
+
+		let time = std::time::Instant::now();
+
+		let versioned_runtime = create_versioned_wasm_runtime::<H>(
+			&code,
+			ext,
+			wasm_method,
+			heap_alloc_strategy,
+			allow_missing_func_imports,
+			self.max_runtime_instances,
+			self.cache_path.as_deref(),
+		)?;
+
+		tracing::debug!(
+			target: "wasm-runtime",
+			"Precompiled runtime version {:?} in {} ms.",
+			versioned_runtime.version,
+			time.elapsed().as_millis(),
+		);
+
+		let mut runtimes = self.runtimes.lock();
+		if runtimes.peek(&versioned_runtime_id).is_none() {
+			let current = runtimes.iter().next().map(|(id, _)| id.clone());
+			runtimes.insert(versioned_runtime_id, Arc::new(versioned_runtime));
+
+			// Keep the current runtime the most recently used one, so that it is not evicted by the
+			// next precompiled runtime.
+			if let Some(current) = current {
+				runtimes.get(&current);
+			}
+		}
+
+		Ok(true)
+	}
 }
 
 /// Create a wasm runtime with the given `code`.
@@ -322,6 +411,15 @@ where
 				},
 			)
 			.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) }),
+		WasmExecutionMethod::Interpreted => sc_executor_wasmi::create_runtime::<H>(
+			blob,
+			sc_executor_wasmi::Config {
+				allow_missing_func_imports,
+				heap_alloc_strategy,
+				deterministic_stack_limit: None,
+			},
+		)
+		.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) }),
 	}
 }
 
@@ -532,6 +630,87 @@ mod tests {
 		assert_eq!(4, version.system_version);
 	}
 
+	#[test]
+	fn precompile_inserts_runtime_into_cache() {
+		let code = substrate_test_runtime::wasm_binary_unwrap();
+		let code_fetcher = sp_core::traits::WrappedRuntimeCode(code.into());
+		let runtime_code =
+			RuntimeCode { code_fetcher: &code_fetcher, heap_pages: None, hash: vec![1, 2, 3] };
+
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+
+		let cache = RuntimeCache::new(2, None, 4);
+		let precompile = |ext: &mut dyn Externalities| {
+			cache.precompile::<sp_io::SubstrateHostFunctions>(
+				&runtime_code,
+				ext,
+				WasmExecutionMethod::default(),
+				sc_executor_common::wasm_runtime::DEFAULT_HEAP_ALLOC_STRATEGY,
+				false,
+			)
+		};
+
+		assert!(precompile(&mut ext).unwrap());
+		assert!(!precompile(&mut ext).unwrap());
+	}
+
+	#[test]
+	fn precompile_never_evicts_the_current_runtime() {
+		let code = substrate_test_runtime::wasm_binary_unwrap();
+		let code_fetcher = sp_core::traits::WrappedRuntimeCode(code.into());
+
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+
+		let cache = RuntimeCache::new(2, None, 2);
+		let mut precompile = |hash: u8| {
+			let runtime_code =
+				RuntimeCode { code_fetcher: &code_fetcher, heap_pages: None, hash: vec![hash] };
+			cache
+				.precompile::<sp_io::SubstrateHostFunctions>(
+					&runtime_code,
+					&mut ext,
+					WasmExecutionMethod::default(),
+					sc_executor_common::wasm_runtime::DEFAULT_HEAP_ALLOC_STRATEGY,
+					false,
+				)
+				.unwrap()
+		};
+
+		// `1` is the current runtime, the later ones are evicting each other.
+		assert!(precompile(1));
+		assert!(precompile(2));
+		assert!(precompile(3));
+		assert!(!precompile(1));
+		assert!(precompile(2));
+	}
+
+	#[test]
+	fn precompile_requires_room_for_two_runtimes() {
+		let code = substrate_test_runtime::wasm_binary_unwrap();
+		let code_fetcher = sp_core::traits::WrappedRuntimeCode(code.into());
+		let runtime_code =
+			RuntimeCode { code_fetcher: &code_fetcher, heap_pages: None, hash: vec![1, 2, 3] };
+
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+
+		let cache = RuntimeCache::new(2, None, 1);
+		let precompiled = cache
+			.precompile::<sp_io::SubstrateHostFunctions>(
+				&runtime_code,
+				&mut ext,
+				WasmExecutionMethod::default(),
+				sc_executor_common::wasm_runtime::DEFAULT_HEAP_ALLOC_STRATEGY,
+				false,
+			)
+			.unwrap();
+
+		assert!(!precompiled);
+		assert!(cache.runtimes.lock().is_empty());
+	}
+
 	#[test]
 	fn embed_runtime_version_works() {
 		let wasm = sp_maybe_compressed_blob::decompress(
@@ -554,10 +733,8 @@ mod tests {
 			.expect("Embedding works");
 
 		let blob = RuntimeBlob::new(&embedded).expect("Embedded blob is valid");
-		let read_version = read_embedded_version(&blob)
-			.ok()
-			.flatten()
-			.expect("Reading embedded version works");
+		let read_version =
+			read_embedded_version(&blob).ok().flatten().expect("Reading embedded version works");
 
 		assert_eq!(runtime_version, read_version);
 	}
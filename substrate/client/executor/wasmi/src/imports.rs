@@ -0,0 +1,193 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{host::HostContext, runtime::StoreData};
+use sc_executor_common::error::WasmError;
+use sp_wasm_interface::{Function, HostFunctions, Signature, Value, ValueType};
+use std::{collections::HashMap, panic::AssertUnwindSafe};
+use wasmi::{
+	core::{ValType, F32, F64},
+	Caller, ExternType, FuncType, Linker, Module, Val,
+};
+
+/// Goes over all imports of a module and prepares the given linker for instantiation of the module.
+/// Returns an error if there are imports that cannot be satisfied.
+pub(crate) fn prepare_imports<H>(
+	linker: &mut Linker<StoreData>,
+	module: &Module,
+	allow_missing_func_imports: bool,
+) -> Result<(), WasmError>
+where
+	H: HostFunctions,
+{
+	let host_functions: HashMap<&str, &'static dyn Function> =
+		H::host_functions().into_iter().map(|function| (function.name(), function)).collect();
+
+	let mut missing_func_imports = Vec::new();
+	for import_ty in module.imports() {
+		let name = import_ty.name();
+
+		if import_ty.module() != "env" {
+			return Err(WasmError::Other(format!(
+				"host doesn't provide any imports from non-env module: {}:{}",
+				import_ty.module(),
+				name,
+			)))
+		}
+
+		let func_ty = match import_ty.ty() {
+			ExternType::Func(func_ty) => func_ty.clone(),
+			_ =>
+				return Err(WasmError::Other(format!(
+					"host doesn't provide any non function imports: {}:{}",
+					import_ty.module(),
+					name,
+				))),
+		};
+
+		let Some(function) = host_functions.get(name).copied() else {
+			missing_func_imports.push((name.to_owned(), func_ty));
+			continue
+		};
+
+		if func_ty != into_func_type(&function.signature()) {
+			return Err(WasmError::Other(format!(
+				"function signature mismatch for the host function '{}:{}': expected {:?}, got {:?}",
+				import_ty.module(),
+				name,
+				function.signature(),
+				func_ty,
+			)))
+		}
+
+		linker
+			.func_new("env", name, func_ty, move |caller, params, results| {
+				call_host_function(caller, function, params, results)
+			})
+			.map_err(|error| {
+				WasmError::Other(format!(
+					"failed to register host function '{}' with the WASM linker: {}",
+					name, error
+				))
+			})?;
+	}
+
+	if !missing_func_imports.is_empty() {
+		if allow_missing_func_imports {
+			for (name, func_ty) in missing_func_imports {
+				let error = format!("call to a missing function env:{}", name);
+				log::debug!("Missing import: '{}' {:?}", name, func_ty);
+				linker
+					.func_new("env", &name, func_ty, move |_, _, _| {
+						Err(wasmi::Error::new(error.clone()))
+					})
+					.expect(
+						"adding a missing import stub can only fail when the item already \
+						 exists, and it is missing here; qed",
+					);
+			}
+		} else {
+			let names = missing_func_imports
+				.iter()
+				.map(|(name, _)| format!("'env:{}'", name))
+				.collect::<Vec<_>>()
+				.join(", ");
+			return Err(WasmError::Other(format!(
+				"runtime requires function imports which are not present on the host: {}",
+				names
+			)))
+		}
+	}
+
+	Ok(())
+}
+
+/// Calls the given host function with the arguments passed by the runtime.
+///
+/// Panics inside of the host function are caught and turned into an error, the same way the
+/// statically registered host functions of the wasmtime executor handle them.
+fn call_host_function(
+	mut caller: Caller<'_, StoreData>,
+	function: &'static dyn Function,
+	params: &[Val],
+	results: &mut [Val],
+) -> Result<(), wasmi::Error> {
+	let mut args = params.iter().map(|param| {
+		from_val(param).expect("the import signature was checked against the host function; qed")
+	});
+
+	let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+		function.execute(&mut HostContext { caller: &mut caller }, &mut args)
+	}));
+
+	let value = match result {
+		Ok(Ok(value)) => value,
+		Ok(Err(error)) => return Err(wasmi::Error::new(error)),
+		Err(panic) => {
+			let message = if let Some(message) = panic.downcast_ref::<String>() {
+				format!("host code panicked while being called by the runtime: {}", message)
+			} else if let Some(message) = panic.downcast_ref::<&'static str>() {
+				format!("host code panicked while being called by the runtime: {}", message)
+			} else {
+				"host code panicked while being called by the runtime".to_owned()
+			};
+			return Err(wasmi::Error::new(message))
+		},
+	};
+
+	if let (Some(value), Some(result)) = (value, results.first_mut()) {
+		*result = into_val(value);
+	}
+
+	Ok(())
+}
+
+fn into_val_type(value_type: ValueType) -> ValType {
+	match value_type {
+		ValueType::I32 => ValType::I32,
+		ValueType::I64 => ValType::I64,
+		ValueType::F32 => ValType::F32,
+		ValueType::F64 => ValType::F64,
+	}
+}
+
+fn into_func_type(signature: &Signature) -> FuncType {
+	FuncType::new(
+		signature.args.iter().copied().map(into_val_type),
+		signature.return_value.map(into_val_type),
+	)
+}
+
+fn into_val(value: Value) -> Val {
+	match value {
+		Value::I32(value) => Val::I32(value),
+		Value::I64(value) => Val::I64(value),
+		Value::F32(value) => Val::F32(F32::from_bits(value)),
+		Value::F64(value) => Val::F64(F64::from_bits(value)),
+	}
+}
+
+fn from_val(value: &Val) -> Option<Value> {
+	match value {
+		Val::I32(value) => Some(Value::I32(*value)),
+		Val::I64(value) => Some(Value::I64(*value)),
+		Val::F32(value) => Some(Value::F32(value.to_bits())),
+		Val::F64(value) => Some(Value::F64(value.to_bits())),
+		_ => None,
+	}
+}
@@ -0,0 +1,37 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Defines a `WasmRuntime` that uses the wasmi interpreter to execute.
+//!
+//! The interpreter is considerably slower than the wasmtime based executor, but it doesn't need
+//! to generate any machine code at runtime. This makes it usable on hosts where JIT compilation
+//! is forbidden and as a second, independent engine to re-execute blocks with.
+
+mod host;
+mod imports;
+mod runtime;
+mod util;
+
+#[cfg(test)]
+mod tests;
+
+pub use runtime::{create_runtime, Config, WasmiInstance, WasmiRuntime};
+pub use sc_executor_common::{
+	runtime_blob::RuntimeBlob,
+	wasm_runtime::{HeapAllocStrategy, WasmModule},
+};
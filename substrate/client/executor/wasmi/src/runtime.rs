@@ -0,0 +1,293 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Defines the interpreted Wasm runtime that uses wasmi internally.
+
+use crate::{
+	host::HostState,
+	util::{self, MemoryWrapper},
+};
+
+use sc_allocator::{AllocationStats, FreeingBumpHeapAllocator};
+use sc_executor_common::{
+	error::{Error, MessageWithBacktrace, Result, WasmError},
+	runtime_blob::RuntimeBlob,
+	util::checked_range,
+	wasm_runtime::{HeapAllocStrategy, WasmInstance, WasmModule},
+};
+use sp_runtime_interface::unpack_ptr_and_len;
+use sp_wasm_interface::{HostFunctions, Pointer, WordSize};
+use std::sync::Arc;
+use wasmi::{AsContext, Engine, Instance, Linker, Memory, Module, StackLimits, TypedFunc};
+
+/// The initial height of the value stack, in number of values.
+const INITIAL_VALUE_STACK_HEIGHT: usize = 1024;
+
+/// The maximum height of the value stack, in number of values.
+const MAX_VALUE_STACK_HEIGHT: usize = 1024 * 1024;
+
+/// The maximum depth of nested calls.
+///
+/// The default of wasmi is way too small for the runtimes we execute, so this is picked to be
+/// in the same ballpark as the call depth that the 1MiB native stack of wasmtime allows.
+const MAX_RECURSION_DEPTH: usize = 64 * 1024;
+
+#[derive(Default)]
+pub(crate) struct StoreData {
+	/// This will only be set when we call into the runtime.
+	pub(crate) host_state: Option<HostState>,
+	/// This will be always set once the store is initialized.
+	pub(crate) memory: Option<Memory>,
+}
+
+impl StoreData {
+	/// Returns a mutable reference to the host state.
+	pub fn host_state_mut(&mut self) -> Option<&mut HostState> {
+		self.host_state.as_mut()
+	}
+
+	/// Returns the host memory.
+	pub fn memory(&self) -> Memory {
+		self.memory.expect("memory is always set; qed")
+	}
+}
+
+pub(crate) type Store = wasmi::Store<StoreData>;
+
+/// Configuration of the wasmi runtime.
+#[derive(Clone)]
+pub struct Config {
+	/// The WebAssembly standard requires all imports of an instantiated module to be resolved,
+	/// otherwise, the instantiation fails. If this option is set to `true`, then this behavior is
+	/// overridden and imports that are requested by the module and not provided by the host
+	/// functions will be resolved using stubs. These stubs will trap upon a call.
+	pub allow_missing_func_imports: bool,
+
+	/// The heap allocation strategy to use.
+	pub heap_alloc_strategy: HeapAllocStrategy,
+
+	/// Specifying `Some` will instrument the code to count the number of logical values on the
+	/// stack and trap once the given limit is exceeded.
+	///
+	/// This is the same instrumentation as used by the wasmtime executor, so both executors will
+	/// reach a stack overflow at exactly the same point when configured with the same limit.
+	pub deterministic_stack_limit: Option<u32>,
+}
+
+/// A `WasmModule` implementation using wasmi to interpret the runtime module.
+///
+/// Since the module is only validated and translated into the internal bytecode of wasmi, no
+/// machine code is ever generated.
+pub struct WasmiRuntime {
+	engine: Engine,
+	module: Arc<Module>,
+	linker: Arc<Linker<StoreData>>,
+}
+
+impl WasmModule for WasmiRuntime {
+	fn new_instance(&self) -> Result<Box<dyn WasmInstance>> {
+		Ok(Box::new(WasmiInstance {
+			engine: self.engine.clone(),
+			module: self.module.clone(),
+			linker: self.linker.clone(),
+		}))
+	}
+}
+
+/// A `WasmInstance` implementation that instantiates the interpreted module from scratch for
+/// every call.
+pub struct WasmiInstance {
+	engine: Engine,
+	module: Arc<Module>,
+	linker: Arc<Linker<StoreData>>,
+}
+
+impl WasmiInstance {
+	fn call_impl(
+		&mut self,
+		method: &str,
+		data: &[u8],
+		allocation_stats: &mut Option<AllocationStats>,
+	) -> Result<Vec<u8>> {
+		let mut store = Store::new(&self.engine, Default::default());
+		let instance = self
+			.linker
+			.instantiate(&mut store, &self.module)
+			.map_err(|error| {
+				WasmError::Other(format!(
+					"failed to instantiate a new WASM module instance: {}",
+					error,
+				))
+			})?
+			.ensure_no_start(&mut store)
+			.map_err(|_| Error::RuntimeHasStartFn)?;
+
+		let memory = instance
+			.get_memory(&store, "memory")
+			.ok_or_else(|| Error::from("memory is not exported under `memory` name"))?;
+		store.data_mut().memory = Some(memory);
+
+		let heap_base = extract_heap_base(&instance, &store)?;
+		let entrypoint = instance
+			.get_typed_func::<(i32, i32), i64>(&store, method)
+			.map_err(|_| Error::from(format!("Exported method {} is not found", method)))?;
+		let allocator = FreeingBumpHeapAllocator::new(heap_base);
+
+		perform_call(data, &mut store, entrypoint, allocator, allocation_stats)
+	}
+}
+
+impl WasmInstance for WasmiInstance {
+	fn call_with_allocation_stats(
+		&mut self,
+		method: &str,
+		data: &[u8],
+	) -> (Result<Vec<u8>>, Option<AllocationStats>) {
+		let mut allocation_stats = None;
+		let result = self.call_impl(method, data, &mut allocation_stats);
+		(result, allocation_stats)
+	}
+}
+
+/// Create a new `WasmiRuntime` given the code. This function only validates the code and
+/// translates it into the bytecode of the interpreter, which is considerably cheaper than
+/// compiling it to machine code.
+///
+/// The `H` generic parameter is used to statically pass a set of host functions which are exposed
+/// to the runtime.
+pub fn create_runtime<H>(
+	mut blob: RuntimeBlob,
+	config: Config,
+) -> std::result::Result<WasmiRuntime, WasmError>
+where
+	H: HostFunctions,
+{
+	if let Some(logical_max) = config.deterministic_stack_limit {
+		blob = blob.inject_stack_depth_metering(logical_max)?;
+	}
+
+	// Just like for wasmtime we let the interpreter create the memory for us, which saves us from
+	// having to deal with imported memories.
+	blob.convert_memory_import_into_export()?;
+	blob.setup_memory_according_to_heap_alloc_strategy(config.heap_alloc_strategy)?;
+
+	let stack_limits =
+		StackLimits::new(INITIAL_VALUE_STACK_HEIGHT, MAX_VALUE_STACK_HEIGHT, MAX_RECURSION_DEPTH)
+			.map_err(|e| WasmError::Other(format!("invalid stack limits: {}", e)))?;
+
+	let mut wasmi_config = wasmi::Config::default();
+	wasmi_config
+		.set_stack_limits(stack_limits)
+		// Be clear and specific about the extensions we support, these match what the wasmtime
+		// executor enables by default.
+		.wasm_multi_value(false)
+		.wasm_bulk_memory(false)
+		.wasm_reference_types(false);
+
+	let engine = Engine::new(&wasmi_config);
+	let module = Module::new(&engine, &blob.serialize())
+		.map_err(|e| WasmError::Other(format!("cannot create module: {}", e)))?;
+
+	let mut linker = Linker::new(&engine);
+	crate::imports::prepare_imports::<H>(&mut linker, &module, config.allow_missing_func_imports)?;
+
+	Ok(WasmiRuntime { engine, module: Arc::new(module), linker: Arc::new(linker) })
+}
+
+/// Reads `__heap_base: i32` global variable and returns it.
+///
+/// If it doesn't exist, not a global or of not i32 type returns an error.
+fn extract_heap_base(instance: &Instance, store: &Store) -> Result<u32> {
+	let heap_base = instance
+		.get_global(store, "__heap_base")
+		.ok_or_else(|| Error::from("__heap_base is not found"))?
+		.get(store)
+		.i32()
+		.ok_or_else(|| Error::from("__heap_base is not a i32"))?;
+
+	Ok(heap_base as u32)
+}
+
+fn perform_call(
+	data: &[u8],
+	store: &mut Store,
+	entrypoint: TypedFunc<(i32, i32), i64>,
+	mut allocator: FreeingBumpHeapAllocator,
+	allocation_stats: &mut Option<AllocationStats>,
+) -> Result<Vec<u8>> {
+	let (data_ptr, data_len) = inject_input_data(store, &mut allocator, data)?;
+
+	let host_state = HostState::new(allocator);
+
+	// Set the host state before calling into wasm.
+	store.data_mut().host_state = Some(host_state);
+
+	let ret = entrypoint
+		.call(&mut *store, (u32::from(data_ptr) as i32, data_len as i32))
+		.map(|ret| unpack_ptr_and_len(ret as u64));
+
+	// Reset the host state
+	let mut host_state = store.data_mut().host_state.take().expect(
+		"the host state is always set before calling into WASM so it can't be None here; qed",
+	);
+	*allocation_stats = Some(host_state.allocation_stats());
+
+	let (output_ptr, output_len) = ret.map_err(|error| {
+		if let Some(message) = host_state.take_panic_message() {
+			return Error::AbortedDueToPanic(MessageWithBacktrace { message, backtrace: None })
+		}
+
+		// Report traps the same way as the wasmtime executor does, so that the errors of both
+		// executors can be compared with each other.
+		let message = match error.as_trap_code() {
+			Some(trap_code) => format!("wasm trap: {}", trap_code.trap_message()),
+			None => error.to_string(),
+		};
+		Error::AbortedDueToTrap(MessageWithBacktrace { message, backtrace: None })
+	})?;
+
+	extract_output_data(store, output_ptr, output_len)
+}
+
+fn inject_input_data(
+	store: &mut Store,
+	allocator: &mut FreeingBumpHeapAllocator,
+	data: &[u8],
+) -> Result<(Pointer<u8>, WordSize)> {
+	let memory = store.data().memory();
+	let data_len = data.len() as WordSize;
+	let data_ptr = allocator.allocate(&mut MemoryWrapper(&memory, &mut *store), data_len)?;
+	util::write_memory_from(&mut *store, data_ptr, data)?;
+	Ok((data_ptr, data_len))
+}
+
+fn extract_output_data(store: &Store, output_ptr: u32, output_len: u32) -> Result<Vec<u8>> {
+	// Do a length check before allocating. The returned output should not be bigger than the
+	// available WASM memory. Otherwise, a malicious parachain can trigger a large allocation,
+	// potentially causing memory exhaustion.
+	//
+	// Get the size of the WASM memory in bytes.
+	let memory_size = store.as_context().data().memory().data(store).len();
+	if checked_range(output_ptr as usize, output_len as usize, memory_size).is_none() {
+		Err(Error::OutputExceedsBounds)?
+	}
+	let mut output = vec![0; output_len as usize];
+
+	util::read_memory_into(store, Pointer::new(output_ptr), &mut output)?;
+	Ok(output)
+}
@@ -0,0 +1,219 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use codec::{Decode as _, Encode as _};
+use sc_executor_common::{
+	error::{Error, WasmError},
+	runtime_blob::RuntimeBlob,
+	wasm_runtime::{HeapAllocStrategy, WasmModule, DEFAULT_HEAP_ALLOC_STRATEGY},
+};
+use sc_runtime_test::wasm_binary_unwrap;
+
+type HostFunctions = sp_io::SubstrateHostFunctions;
+
+struct RuntimeBuilder {
+	code: Option<String>,
+	allow_missing_func_imports: bool,
+	heap_pages: HeapAllocStrategy,
+}
+
+impl RuntimeBuilder {
+	fn new() -> Self {
+		Self {
+			code: None,
+			allow_missing_func_imports: true,
+			heap_pages: DEFAULT_HEAP_ALLOC_STRATEGY,
+		}
+	}
+
+	fn use_wat(mut self, code: &str) -> Self {
+		self.code = Some(code.to_owned());
+		self
+	}
+
+	fn allow_missing_func_imports(mut self, allow_missing_func_imports: bool) -> Self {
+		self.allow_missing_func_imports = allow_missing_func_imports;
+		self
+	}
+
+	fn heap_alloc_strategy(mut self, heap_pages: HeapAllocStrategy) -> Self {
+		self.heap_pages = heap_pages;
+		self
+	}
+
+	fn try_build(self) -> Result<crate::WasmiRuntime, WasmError> {
+		let wasm = match self.code {
+			None => wasm_binary_unwrap().to_vec(),
+			Some(ref wat) => wat::parse_str(wat).expect("wat parsing failed"),
+		};
+
+		let blob = RuntimeBlob::uncompress_if_needed(&wasm)
+			.expect("failed to create a runtime blob out of test runtime");
+
+		crate::create_runtime::<HostFunctions>(
+			blob,
+			crate::Config {
+				allow_missing_func_imports: self.allow_missing_func_imports,
+				heap_alloc_strategy: self.heap_pages,
+				deterministic_stack_limit: None,
+			},
+		)
+	}
+
+	fn build(self) -> impl WasmModule {
+		self.try_build().expect("cannot create runtime")
+	}
+}
+
+const MISSING_IMPORT_WAT: &str = r#"
+	(module
+	  (import "env" "missing_external" (func $missing_external))
+	  (memory $0 1)
+	  (export "memory" (memory $0))
+	  (global (export "__heap_base") i32 (i32.const 0))
+	  (func (export "main")
+	    (param i32 i32) (result i64)
+	    (call $missing_external)
+	    (i64.const 0)
+	  )
+	)
+"#;
+
+#[test]
+fn test_fp_f32add() {
+	let runtime = RuntimeBuilder::new().build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	let params = (f32::to_le_bytes(1.5), f32::to_le_bytes(2.25)).encode();
+	let raw_result = instance.call_export("test_fp_f32add", &params).unwrap();
+	let res = f32::from_le_bytes(<[u8; 4]>::decode(&mut &raw_result[..]).unwrap());
+	assert_eq!(res, 3.75);
+}
+
+#[test]
+fn test_traps_are_reported_like_wasmtime() {
+	let runtime = RuntimeBuilder::new()
+		.use_wat(
+			r#"
+				(module
+				  (memory $0 1)
+				  (export "memory" (memory $0))
+				  (global (export "__heap_base") i32 (i32.const 0))
+				  (func (export "main")
+				    (param i32 i32) (result i64)
+				    unreachable
+				  )
+				)
+			"#,
+		)
+		.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	match instance.call_export("main", &[]).unwrap_err() {
+		Error::AbortedDueToTrap(error) => {
+			let expected = "wasm trap: wasm `unreachable` instruction executed";
+			assert_eq!(error.message, expected);
+		},
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+#[test]
+fn test_missing_func_imports_are_stubbed() {
+	let runtime = RuntimeBuilder::new().use_wat(MISSING_IMPORT_WAT).build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	match instance.call_export("main", &[]).unwrap_err() {
+		Error::AbortedDueToTrap(error) => {
+			assert_eq!(error.message, "call to a missing function env:missing_external");
+		},
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+#[test]
+fn test_missing_func_imports_are_rejected() {
+	let error = RuntimeBuilder::new()
+		.use_wat(MISSING_IMPORT_WAT)
+		.allow_missing_func_imports(false)
+		.try_build()
+		.err()
+		.expect("the runtime requires a function the host doesn't provide");
+
+	assert_eq!(
+		error.to_string(),
+		"Other: runtime requires function imports which are not present on the host: \
+		 'env:missing_external'",
+	);
+}
+
+#[test]
+fn test_host_function_signature_mismatch_is_rejected() {
+	let error = RuntimeBuilder::new()
+		.use_wat(
+			r#"
+				(module
+				  (import "env" "ext_allocator_malloc_version_1" (func (param i64) (result i32)))
+				  (memory $0 1)
+				  (export "memory" (memory $0))
+				  (global (export "__heap_base") i32 (i32.const 0))
+				)
+			"#,
+		)
+		.try_build()
+		.err()
+		.expect("the import doesn't match the signature of the host function");
+
+	assert!(error.to_string().contains("function signature mismatch"));
+}
+
+#[test]
+fn test_max_memory_pages() {
+	fn call(heap_alloc_strategy: HeapAllocStrategy, expect_grow: bool) {
+		let wat = format!(
+			r#"
+				(module
+				  (memory $0 1 10)
+				  (export "memory" (memory $0))
+				  (global (export "__heap_base") i32 (i32.const 0))
+				  (func (export "main")
+				    (param i32 i32) (result i64)
+				    (if
+				      (i32.eq
+				        (i32.ne (memory.grow (i32.const 9)) (i32.const -1))
+				        (i32.const {})
+				      )
+				      (then (unreachable))
+				    )
+				    (i64.const 0)
+				  )
+				)
+			"#,
+			!expect_grow as u32,
+		);
+
+		let runtime =
+			RuntimeBuilder::new().use_wat(&wat).heap_alloc_strategy(heap_alloc_strategy).build();
+		let mut instance = runtime.new_instance().unwrap();
+		instance.call_export("main", &[]).unwrap();
+	}
+
+	call(HeapAllocStrategy::Dynamic { maximum_pages: Some(10) }, true);
+	call(HeapAllocStrategy::Dynamic { maximum_pages: Some(5) }, false);
+	call(HeapAllocStrategy::Static { extra_pages: 10 }, false);
+}
@@ -26,6 +26,8 @@ use std::io;
 /// Local keystore implementation
 mod local;
 pub use local::LocalKeystore;
+pub mod remote;
+pub use remote::RemoteKeystore;
 pub use sp_keystore::Keystore;
 
 /// Keystore error.
@@ -55,6 +57,9 @@ pub enum Error {
 	/// Keystore unavailable
 	#[error("Keystore unavailable")]
 	Unavailable,
+	/// Invalid address of a remote signer
+	#[error("Invalid remote signer address: {0}")]
+	InvalidSignerAddress(String),
 }
 
 /// Keystore Result
@@ -69,6 +74,7 @@ impl From<Error> for TraitError {
 			Error::Unavailable => TraitError::Unavailable,
 			Error::Io(e) => TraitError::Other(e.to_string()),
 			Error::Json(e) => TraitError::Other(e.to_string()),
+			Error::InvalidSignerAddress(e) => TraitError::Other(e),
 		}
 	}
 }
@@ -41,6 +41,10 @@ sp_keystore::bls_experimental_enabled! {
 use sp_core::{bls381, ecdsa_bls381, KeccakHasher};
 }
 
+sp_keystore::p256_experimental_enabled! {
+use sp_core::p256;
+}
+
 use crate::{Error, Result};
 
 /// A local based keystore that is either memory-based or filesystem-based.
@@ -274,6 +278,32 @@ impl Keystore for LocalKeystore {
 		Ok(sig)
 	}
 
+	sp_keystore::p256_experimental_enabled! {
+		fn p256_public_keys(&self, key_type: KeyTypeId) -> Vec<p256::Public> {
+			self.public_keys::<p256::Pair>(key_type)
+		}
+
+		/// Generate a new pair compatible with the 'p256' signature scheme.
+		///
+		/// If `[seed]` is `Some` then the key will be ephemeral and stored in memory.
+		fn p256_generate_new(
+			&self,
+			key_type: KeyTypeId,
+			seed: Option<&str>,
+		) -> std::result::Result<p256::Public, TraitError> {
+			self.generate_new::<p256::Pair>(key_type, seed)
+		}
+
+		fn p256_sign(
+			&self,
+			key_type: KeyTypeId,
+			public: &p256::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<p256::Signature>, TraitError> {
+			self.sign::<p256::Pair>(key_type, public, msg)
+		}
+	}
+
 	sp_keystore::bandersnatch_experimental_enabled! {
 		fn bandersnatch_public_keys(&self, key_type: KeyTypeId) -> Vec<bandersnatch::Public> {
 			self.public_keys::<bandersnatch::Pair>(key_type)
@@ -803,6 +833,22 @@ mod tests {
 		assert_eq!(store.sr25519_public_keys(TEST_KEY_TYPE).len(), 2);
 	}
 
+	#[test]
+	#[cfg(feature = "p256-experimental")]
+	fn p256_keys_are_persisted() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = LocalKeystore::open(temp_dir.path(), None).unwrap();
+		let public = store.p256_generate_new(TEST_KEY_TYPE, None).unwrap();
+
+		drop(store);
+		let store = LocalKeystore::open(temp_dir.path(), None).unwrap();
+		assert_eq!(store.p256_public_keys(TEST_KEY_TYPE), vec![public]);
+
+		let msg = b"passkey";
+		let sig = store.p256_sign(TEST_KEY_TYPE, &public, msg).unwrap().unwrap();
+		assert!(p256::Pair::verify(&sig, msg, &public));
+	}
+
 	#[test]
 	#[cfg(target_family = "unix")]
 	fn uses_correct_file_permissions_on_unix() {
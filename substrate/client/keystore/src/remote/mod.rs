@@ -0,0 +1,601 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A [`Keystore`] forwarding all requests to a remote signer.
+//!
+//! Validators may not want to keep their session keys on the host of the node. Instead the keys
+//! can be held by a [`RemoteSigner`] in a separate process, possibly on another machine, which
+//! the node reaches through a [`RemoteKeystore`]. The signer checks every consensus message with
+//! its [`SlashingProtection`] before signing it.
+
+mod protocol;
+mod signer;
+mod slashing_protection;
+mod transport;
+
+pub use protocol::{RemoteError, Request, Response};
+pub use signer::RemoteSigner;
+pub use slashing_protection::SlashingProtection;
+pub use transport::{SignerAddress, SignerListener, Stream, TlsConfig};
+
+use crate::{Error, Result};
+use codec::Decode;
+use parking_lot::Mutex;
+use protocol::{read_message, write_message};
+use sp_core::{
+	crypto::{ByteArray, CryptoTypeId, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{Error as TraitError, Keystore};
+use std::time::Duration;
+
+sp_keystore::bandersnatch_experimental_enabled! {
+use sp_core::bandersnatch;
+}
+
+sp_keystore::bls_experimental_enabled! {
+use sp_core::{bls381, ecdsa_bls381};
+}
+
+sp_keystore::p256_experimental_enabled! {
+use sp_core::p256;
+}
+
+/// Log target of the remote keystore and signer.
+const LOG_TARGET: &str = "remote-keystore";
+
+/// The time after which a request to the signer is considered to have failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum number of idle connections kept open to the signer.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// A [`Keystore`] forwarding all requests to a [`RemoteSigner`].
+///
+/// Concurrent requests are sent on separate connections, which are re-established if they were
+/// lost. Each request blocks the calling thread until the signer responded, at most for
+/// `REQUEST_TIMEOUT` per attempt. While the signer can't be reached, requests fail with
+/// [`TraitError::Unavailable`] and no keys are reported.
+pub struct RemoteKeystore {
+	address: SignerAddress,
+	connections: Mutex<Vec<Box<dyn Stream>>>,
+}
+
+impl RemoteKeystore {
+	/// Connect to the signer at `address`.
+	pub fn connect(address: SignerAddress) -> Result<Self> {
+		let mut stream = address.connect(REQUEST_TIMEOUT)?;
+		// Exchange a first message, to fail early if e.g. the TLS handshake is rejected.
+		write_message(&mut stream, &Request::HasKeys { keys: Vec::new() })?;
+		match read_message::<Response>(&mut stream)? {
+			Response::HasKeys(_) => {},
+			_ => return Err(Error::Unavailable),
+		}
+
+		Ok(Self { address, connections: Mutex::new(vec![stream]) })
+	}
+
+	/// Send `request` to the signer and wait for its response.
+	///
+	/// The request is sent on an idle connection, or on a new one if all of them are in use, so
+	/// concurrent requests don't wait for each other. The lock is only held to take or return a
+	/// connection and never during I/O. The request is sent a second time on a new connection
+	/// if the first attempt failed.
+	fn request(&self, request: Request) -> std::result::Result<Response, TraitError> {
+		let mut error = None;
+		for _ in 0..2 {
+			let idle = self.connections.lock().pop();
+			let mut stream = match idle.map_or_else(|| self.address.connect(REQUEST_TIMEOUT), Ok) {
+				Ok(stream) => stream,
+				Err(e) => {
+					error = Some(e);
+					continue
+				},
+			};
+
+			match write_message(&mut stream, &request).and_then(|_| read_message(&mut stream)) {
+				Ok(response) => {
+					let mut connections = self.connections.lock();
+					if connections.len() < MAX_IDLE_CONNECTIONS {
+						connections.push(stream);
+					}
+					drop(connections);
+
+					return match response {
+						Response::Error(e) => Err(e.into()),
+						response => Ok(response),
+					}
+				},
+				Err(e) => {
+					// The other idle connections most likely failed as well, e.g. because the
+					// signer restarted.
+					self.connections.lock().clear();
+					error = Some(e);
+				},
+			}
+		}
+
+		log::warn!(
+			target: LOG_TARGET,
+			"Remote signer at {:?} is unavailable: {:?}",
+			self.address,
+			error,
+		);
+		Err(TraitError::Unavailable)
+	}
+
+	fn public_keys<T: ByteArray>(&self, crypto: CryptoTypeId, key_type: KeyTypeId) -> Vec<T> {
+		match self.request(Request::PublicKeys { crypto, key_type }) {
+			Ok(Response::PublicKeys(keys)) =>
+				keys.iter().filter_map(|public| T::from_slice(public).ok()).collect(),
+			Ok(response) => {
+				log::warn!(target: LOG_TARGET, "{}", unexpected(response));
+				Vec::new()
+			},
+			Err(_) => Vec::new(),
+		}
+	}
+
+	fn generate_new<T: ByteArray>(
+		&self,
+		crypto: CryptoTypeId,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<T, TraitError> {
+		let seed = seed.map(Into::into);
+		match self.request(Request::GenerateNew { crypto, key_type, seed })? {
+			Response::Public(public) => T::from_slice(&public)
+				.map_err(|_| TraitError::Other("Invalid public key returned by the signer".into())),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn sign<T: Decode>(
+		&self,
+		crypto: CryptoTypeId,
+		key_type: KeyTypeId,
+		public: &impl ByteArray,
+		message: &[u8],
+	) -> std::result::Result<Option<T>, TraitError> {
+		let public = public.to_raw_vec();
+		let message = message.to_vec();
+		signature(self.request(Request::Sign { crypto, key_type, public, message })?)
+	}
+}
+
+/// Decode the signature of a response.
+fn signature<T: Decode>(response: Response) -> std::result::Result<Option<T>, TraitError> {
+	match response {
+		Response::Signature(signature) => signature
+			.map(|signature| T::decode(&mut &signature[..]))
+			.transpose()
+			.map_err(|_| TraitError::Other("Invalid signature returned by the signer".into())),
+		response => Err(unexpected(response)),
+	}
+}
+
+fn unexpected(response: Response) -> TraitError {
+	TraitError::Other(format!("Unexpected response of the remote signer: {response:?}"))
+}
+
+impl Keystore for RemoteKeystore {
+	fn insert(
+		&self,
+		key_type: KeyTypeId,
+		suri: &str,
+		public: &[u8],
+	) -> std::result::Result<(), ()> {
+		let request = Request::Insert { key_type, suri: suri.into(), public: public.to_vec() };
+		match self.request(request) {
+			Ok(Response::Inserted) => Ok(()),
+			_ => Err(()),
+		}
+	}
+
+	fn keys(&self, key_type: KeyTypeId) -> std::result::Result<Vec<Vec<u8>>, TraitError> {
+		match self.request(Request::Keys { key_type })? {
+			Response::PublicKeys(keys) => Ok(keys),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		match self.request(Request::HasKeys { keys: public_keys.to_vec() }) {
+			Ok(Response::HasKeys(has_keys)) => has_keys,
+			_ => false,
+		}
+	}
+
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(sr25519::CRYPTO_ID, key_type)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, TraitError> {
+		self.generate_new(sr25519::CRYPTO_ID, key_type, seed)
+	}
+
+	fn sr25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<sr25519::Signature>, TraitError> {
+		self.sign(sr25519::CRYPTO_ID, key_type, public, msg)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		data: &sr25519::vrf::VrfSignData,
+	) -> std::result::Result<Option<sr25519::vrf::VrfSignature>, TraitError> {
+		let request = Request::Sr25519VrfSign {
+			key_type,
+			public: public.to_raw_vec(),
+			input: data.as_ref().data().clone(),
+			extra: data.extra().map(|extra| extra.data().clone()),
+		};
+		match self.request(request)? {
+			Response::VrfSignature(signature) => Ok(signature),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn sr25519_vrf_pre_output(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		input: &sr25519::vrf::VrfInput,
+	) -> std::result::Result<Option<sr25519::vrf::VrfPreOutput>, TraitError> {
+		let request = Request::Sr25519VrfPreOutput {
+			key_type,
+			public: public.to_raw_vec(),
+			input: input.data().clone(),
+		};
+		match self.request(request)? {
+			Response::VrfPreOutput(pre_output) => Ok(pre_output),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(ed25519::CRYPTO_ID, key_type)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, TraitError> {
+		self.generate_new(ed25519::CRYPTO_ID, key_type, seed)
+	}
+
+	fn ed25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ed25519::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<ed25519::Signature>, TraitError> {
+		self.sign(ed25519::CRYPTO_ID, key_type, public, msg)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(ecdsa::CRYPTO_ID, key_type)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, TraitError> {
+		self.generate_new(ecdsa::CRYPTO_ID, key_type, seed)
+	}
+
+	fn ecdsa_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<ecdsa::Signature>, TraitError> {
+		self.sign(ecdsa::CRYPTO_ID, key_type, public, msg)
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> std::result::Result<Option<ecdsa::Signature>, TraitError> {
+		let public = public.to_raw_vec();
+		signature(self.request(Request::EcdsaSignPrehashed { key_type, public, message: *msg })?)
+	}
+
+	sp_keystore::p256_experimental_enabled! {
+		fn p256_public_keys(&self, key_type: KeyTypeId) -> Vec<p256::Public> {
+			self.public_keys(p256::CRYPTO_ID, key_type)
+		}
+
+		fn p256_generate_new(
+			&self,
+			key_type: KeyTypeId,
+			seed: Option<&str>,
+		) -> std::result::Result<p256::Public, TraitError> {
+			self.generate_new(p256::CRYPTO_ID, key_type, seed)
+		}
+
+		fn p256_sign(
+			&self,
+			key_type: KeyTypeId,
+			public: &p256::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<p256::Signature>, TraitError> {
+			self.sign(p256::CRYPTO_ID, key_type, public, msg)
+		}
+	}
+
+	fn ecdsa_sign_with_keccak256(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<ecdsa::Signature>, TraitError> {
+		let request = Request::EcdsaSignWithKeccak256 {
+			key_type,
+			public: public.to_raw_vec(),
+			message: msg.to_vec(),
+		};
+		signature(self.request(request)?)
+	}
+
+	sp_keystore::bandersnatch_experimental_enabled! {
+		fn bandersnatch_public_keys(&self, key_type: KeyTypeId) -> Vec<bandersnatch::Public> {
+			self.public_keys(bandersnatch::CRYPTO_ID, key_type)
+		}
+
+		fn bandersnatch_generate_new(
+			&self,
+			key_type: KeyTypeId,
+			seed: Option<&str>,
+		) -> std::result::Result<bandersnatch::Public, TraitError> {
+			self.generate_new(bandersnatch::CRYPTO_ID, key_type, seed)
+		}
+
+		fn bandersnatch_sign(
+			&self,
+			key_type: KeyTypeId,
+			public: &bandersnatch::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<bandersnatch::Signature>, TraitError> {
+			self.sign(bandersnatch::CRYPTO_ID, key_type, public, msg)
+		}
+
+		// The bandersnatch VRF inputs can't be transferred to the signer.
+		fn bandersnatch_vrf_sign(
+			&self,
+			key_type: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfSignData,
+		) -> std::result::Result<Option<bandersnatch::vrf::VrfSignature>, TraitError> {
+			Err(TraitError::KeyNotSupported(key_type))
+		}
+
+		fn bandersnatch_vrf_pre_output(
+			&self,
+			key_type: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfInput,
+		) -> std::result::Result<Option<bandersnatch::vrf::VrfPreOutput>, TraitError> {
+			Err(TraitError::KeyNotSupported(key_type))
+		}
+
+		fn bandersnatch_ring_vrf_sign(
+			&self,
+			key_type: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfSignData,
+			_: &bandersnatch::ring_vrf::RingProver,
+		) -> std::result::Result<Option<bandersnatch::ring_vrf::RingVrfSignature>, TraitError> {
+			Err(TraitError::KeyNotSupported(key_type))
+		}
+	}
+
+	sp_keystore::bls_experimental_enabled! {
+		fn bls381_public_keys(&self, key_type: KeyTypeId) -> Vec<bls381::Public> {
+			self.public_keys(bls381::CRYPTO_ID, key_type)
+		}
+
+		fn bls381_generate_new(
+			&self,
+			key_type: KeyTypeId,
+			seed: Option<&str>,
+		) -> std::result::Result<bls381::Public, TraitError> {
+			self.generate_new(bls381::CRYPTO_ID, key_type, seed)
+		}
+
+		fn bls381_sign(
+			&self,
+			key_type: KeyTypeId,
+			public: &bls381::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<bls381::Signature>, TraitError> {
+			self.sign(bls381::CRYPTO_ID, key_type, public, msg)
+		}
+
+		fn ecdsa_bls381_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa_bls381::Public> {
+			self.public_keys(ecdsa_bls381::CRYPTO_ID, key_type)
+		}
+
+		fn ecdsa_bls381_generate_new(
+			&self,
+			key_type: KeyTypeId,
+			seed: Option<&str>,
+		) -> std::result::Result<ecdsa_bls381::Public, TraitError> {
+			self.generate_new(ecdsa_bls381::CRYPTO_ID, key_type, seed)
+		}
+
+		fn ecdsa_bls381_sign(
+			&self,
+			key_type: KeyTypeId,
+			public: &ecdsa_bls381::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<ecdsa_bls381::Signature>, TraitError> {
+			self.sign(ecdsa_bls381::CRYPTO_ID, key_type, public, msg)
+		}
+
+		fn ecdsa_bls381_sign_with_keccak256(
+			&self,
+			key_type: KeyTypeId,
+			public: &ecdsa_bls381::Public,
+			msg: &[u8],
+		) -> std::result::Result<Option<ecdsa_bls381::Signature>, TraitError> {
+			let request = Request::EcdsaBls381SignWithKeccak256 {
+				key_type,
+				public: public.to_raw_vec(),
+				message: msg.to_vec(),
+			};
+			signature(self.request(request)?)
+		}
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use crate::LocalKeystore;
+	use codec::Encode;
+	use sp_core::{
+		crypto::{key_types, Pair, VrfPublic},
+		testing::{ECDSA, SR25519},
+	};
+	use std::{sync::Arc, thread};
+	use tempfile::TempDir;
+
+	fn remote_keystore(dir: &TempDir) -> RemoteKeystore {
+		remote_keystore_with(dir, |signer| signer)
+	}
+
+	fn remote_keystore_with(
+		dir: &TempDir,
+		configure: impl FnOnce(RemoteSigner<LocalKeystore>) -> RemoteSigner<LocalKeystore>,
+	) -> RemoteKeystore {
+		let address = SignerAddress::Unix(dir.path().join("signer.sock"));
+		let listener = SignerListener::bind(&address).unwrap();
+		let signer = Arc::new(configure(RemoteSigner::new(
+			LocalKeystore::open(dir.path().join("keystore"), None).unwrap(),
+			SlashingProtection::in_memory(),
+		)));
+		thread::spawn(move || signer.serve(listener));
+
+		RemoteKeystore::connect(address).unwrap()
+	}
+
+	#[test]
+	fn requests_are_forwarded_to_the_signer() {
+		let dir = TempDir::new().unwrap();
+		let keystore = remote_keystore(&dir);
+
+		let public = keystore.sr25519_generate_new(SR25519, None).unwrap();
+		assert_eq!(keystore.sr25519_public_keys(SR25519), vec![public]);
+		assert!(keystore.has_keys(&[(public.to_raw_vec(), SR25519)]));
+		assert!(keystore.ecdsa_public_keys(SR25519).is_empty());
+
+		let signature = keystore.sr25519_sign(SR25519, &public, b"message").unwrap().unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"message", &public));
+
+		let data = sr25519::vrf::VrfTranscript::new(b"label", &[(b"domain", b"data")])
+			.into_sign_data()
+			.with_extra(sr25519::vrf::VrfTranscript::new(b"extra", &[]));
+		let signature = keystore.sr25519_vrf_sign(SR25519, &public, &data).unwrap().unwrap();
+		assert!(public.vrf_verify(&data, &signature));
+
+		let unknown = sr25519::Pair::from_seed(&[1; 32]).public();
+		assert_eq!(keystore.sr25519_sign(SR25519, &unknown, b"message").unwrap(), None);
+	}
+
+	#[test]
+	fn concurrent_requests_use_separate_connections() {
+		let dir = TempDir::new().unwrap();
+		let keystore = Arc::new(remote_keystore(&dir));
+		let public = keystore.sr25519_generate_new(SR25519, None).unwrap();
+
+		let signers = (0..8)
+			.map(|i| {
+				let keystore = keystore.clone();
+				thread::spawn(move || {
+					let message = [i; 32];
+					let signature = keystore.sr25519_sign(SR25519, &public, &message).unwrap();
+					assert!(sr25519::Pair::verify(&signature.unwrap(), message, &public));
+				})
+			})
+			.collect::<Vec<_>>();
+		signers.into_iter().for_each(|signer| signer.join().unwrap());
+
+		let idle = keystore.connections.lock().len();
+		assert!((1..=MAX_IDLE_CONNECTIONS).contains(&idle));
+	}
+
+	#[test]
+	fn double_signing_is_refused() {
+		let dir = TempDir::new().unwrap();
+		let keystore = remote_keystore(&dir);
+		let public = keystore.ed25519_generate_new(key_types::GRANDPA, None).unwrap();
+
+		let prevote = |target: u8| (0u8, [target; 32], 10u32, 5u64, 0u64).encode();
+		assert!(keystore.ed25519_sign(key_types::GRANDPA, &public, &prevote(1)).unwrap().is_some());
+		assert!(matches!(
+			keystore.ed25519_sign(key_types::GRANDPA, &public, &prevote(2)),
+			Err(TraitError::ValidationError(_)),
+		));
+	}
+
+	#[test]
+	fn prehashed_messages_are_not_signed_with_consensus_keys() {
+		let dir = TempDir::new().unwrap();
+		let keystore = remote_keystore(&dir);
+
+		let public = keystore.ecdsa_generate_new(key_types::BEEFY, None).unwrap();
+		assert!(matches!(
+			keystore.ecdsa_sign_prehashed(key_types::BEEFY, &public, &[1; 32]),
+			Err(TraitError::ValidationError(_)),
+		));
+
+		let public = keystore.ecdsa_generate_new(ECDSA, None).unwrap();
+		let signature = keystore.ecdsa_sign_prehashed(ECDSA, &public, &[1; 32]).unwrap().unwrap();
+		assert!(ecdsa::Pair::verify_prehashed(&signature, &[1; 32], &public));
+	}
+
+	#[test]
+	fn inserting_keys_requires_opt_in() {
+		let suri = "//Alice";
+		let public = sr25519::Pair::from_string(suri, None).unwrap().public();
+
+		let dir = TempDir::new().unwrap();
+		let keystore = remote_keystore(&dir);
+		assert!(keystore.insert(SR25519, suri, public.as_ref()).is_err());
+		assert!(keystore.sr25519_public_keys(SR25519).is_empty());
+
+		let dir = TempDir::new().unwrap();
+		let keystore = remote_keystore_with(&dir, |signer| signer.with_key_insertion(true));
+		keystore.insert(SR25519, suri, public.as_ref()).unwrap();
+		assert_eq!(keystore.sr25519_public_keys(SR25519), vec![public]);
+	}
+}
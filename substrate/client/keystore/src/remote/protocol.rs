@@ -0,0 +1,211 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The messages exchanged between a [`RemoteKeystore`](super::RemoteKeystore) and a
+//! [`RemoteSigner`](super::RemoteSigner).
+//!
+//! Every message is SCALE encoded and prefixed with its length as a little endian `u32`.
+//! Public keys and signatures are sent in their raw encoding and identified by their
+//! [`CryptoTypeId`], so that both sides agree on the messages independent of the crypto schemes
+//! they were compiled with.
+
+use codec::{Decode, Encode};
+use sp_core::{
+	crypto::{CryptoTypeId, KeyTypeId},
+	sr25519::vrf::{VrfPreOutput, VrfSignature, VrfTranscriptData},
+};
+use sp_keystore::Error as TraitError;
+use std::io::{self, Read, Write};
+
+/// The maximal size of an encoded message.
+const MAX_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// A request sent to the remote signer.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum Request {
+	/// Return the public keys of `crypto` stored under `key_type`.
+	#[codec(index = 0)]
+	PublicKeys { crypto: CryptoTypeId, key_type: KeyTypeId },
+	/// Generate a new key pair of `crypto` and store it under `key_type`.
+	#[codec(index = 1)]
+	GenerateNew { crypto: CryptoTypeId, key_type: KeyTypeId, seed: Option<String> },
+	/// Sign `message` with the key pair of `crypto` matching `public`.
+	#[codec(index = 2)]
+	Sign { crypto: CryptoTypeId, key_type: KeyTypeId, public: Vec<u8>, message: Vec<u8> },
+	/// Sign the pre-hashed `message` with the ECDSA key pair matching `public`.
+	#[codec(index = 3)]
+	EcdsaSignPrehashed { key_type: KeyTypeId, public: Vec<u8>, message: [u8; 32] },
+	/// Sign the keccak256 hash of `message` with the ECDSA key pair matching `public`.
+	#[codec(index = 4)]
+	EcdsaSignWithKeccak256 { key_type: KeyTypeId, public: Vec<u8>, message: Vec<u8> },
+	/// Sign the keccak256 hash of `message` with the (ECDSA,BLS12-381) key pair matching
+	/// `public`.
+	#[codec(index = 5)]
+	EcdsaBls381SignWithKeccak256 { key_type: KeyTypeId, public: Vec<u8>, message: Vec<u8> },
+	/// Generate a sr25519 VRF signature of the transcripts built from `input` and `extra`.
+	#[codec(index = 6)]
+	Sr25519VrfSign {
+		key_type: KeyTypeId,
+		public: Vec<u8>,
+		input: VrfTranscriptData,
+		extra: Option<VrfTranscriptData>,
+	},
+	/// Generate the sr25519 VRF pre-output of the transcript built from `input`.
+	#[codec(index = 7)]
+	Sr25519VrfPreOutput { key_type: KeyTypeId, public: Vec<u8>, input: VrfTranscriptData },
+	/// Insert the key pair derived from `suri`.
+	///
+	/// Only served by signers which allow it, see
+	/// [`RemoteSigner::with_key_insertion`](super::RemoteSigner::with_key_insertion).
+	#[codec(index = 8)]
+	Insert { key_type: KeyTypeId, suri: String, public: Vec<u8> },
+	/// Return all the public keys stored under `key_type`.
+	#[codec(index = 9)]
+	Keys { key_type: KeyTypeId },
+	/// Check if all of the given keys are stored.
+	#[codec(index = 10)]
+	HasKeys { keys: Vec<(Vec<u8>, KeyTypeId)> },
+}
+
+/// The response of the remote signer to a [`Request`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum Response {
+	/// The raw public keys, the answer to [`Request::PublicKeys`] and [`Request::Keys`].
+	#[codec(index = 0)]
+	PublicKeys(Vec<Vec<u8>>),
+	/// The raw public key of a generated key pair.
+	#[codec(index = 1)]
+	Public(Vec<u8>),
+	/// The encoded signature or `None` if the key pair doesn't exist.
+	#[codec(index = 2)]
+	Signature(Option<Vec<u8>>),
+	/// The VRF signature or `None` if the key pair doesn't exist.
+	#[codec(index = 3)]
+	VrfSignature(Option<VrfSignature>),
+	/// The VRF pre-output or `None` if the key pair doesn't exist.
+	#[codec(index = 4)]
+	VrfPreOutput(Option<VrfPreOutput>),
+	/// The key pair was inserted.
+	#[codec(index = 5)]
+	Inserted,
+	/// The answer to [`Request::HasKeys`].
+	#[codec(index = 6)]
+	HasKeys(bool),
+	/// The request failed.
+	#[codec(index = 7)]
+	Error(RemoteError),
+}
+
+/// An error returned by the remote signer.
+///
+/// Mirrors [`sp_keystore::Error`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum RemoteError {
+	/// The key type or crypto scheme isn't supported.
+	#[codec(index = 0)]
+	KeyNotSupported(KeyTypeId),
+	/// The request was refused, e.g. by the slashing protection.
+	#[codec(index = 1)]
+	ValidationError(String),
+	/// The keystore of the signer is unavailable.
+	#[codec(index = 2)]
+	Unavailable,
+	/// Any other error.
+	#[codec(index = 3)]
+	Other(String),
+}
+
+impl From<TraitError> for RemoteError {
+	fn from(error: TraitError) -> Self {
+		match error {
+			TraitError::KeyNotSupported(id) => Self::KeyNotSupported(id),
+			TraitError::ValidationError(e) => Self::ValidationError(e),
+			TraitError::Unavailable => Self::Unavailable,
+			TraitError::Other(e) => Self::Other(e),
+		}
+	}
+}
+
+impl From<RemoteError> for TraitError {
+	fn from(error: RemoteError) -> Self {
+		match error {
+			RemoteError::KeyNotSupported(id) => Self::KeyNotSupported(id),
+			RemoteError::ValidationError(e) => Self::ValidationError(e),
+			RemoteError::Unavailable => Self::Unavailable,
+			RemoteError::Other(e) => Self::Other(e),
+		}
+	}
+}
+
+/// Write the length prefixed encoding of `message` to `stream`.
+pub fn write_message<T: Encode>(stream: &mut impl Write, message: &T) -> io::Result<()> {
+	let encoded = message.encode();
+	let len = u32::try_from(encoded.len())
+		.ok()
+		.filter(|len| *len <= MAX_MESSAGE_SIZE)
+		.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Message is too large"))?;
+
+	stream.write_all(&len.to_le_bytes())?;
+	stream.write_all(&encoded)?;
+	stream.flush()
+}
+
+/// Read a length prefixed message from `stream`.
+pub fn read_message<T: Decode>(stream: &mut impl Read) -> io::Result<T> {
+	let mut len = [0u8; 4];
+	stream.read_exact(&mut len)?;
+	let len = u32::from_le_bytes(len);
+	if len > MAX_MESSAGE_SIZE {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Message is too large"))
+	}
+
+	let mut encoded = vec![0u8; len as usize];
+	stream.read_exact(&mut encoded)?;
+	T::decode(&mut &encoded[..]).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn messages_roundtrip() {
+		let request = Request::Sign {
+			crypto: sp_core::sr25519::CRYPTO_ID,
+			key_type: sp_core::crypto::key_types::BABE,
+			public: vec![1; 32],
+			message: vec![2; 32],
+		};
+
+		let mut stream = Vec::new();
+		write_message(&mut stream, &request).unwrap();
+		write_message(&mut stream, &Response::HasKeys(true)).unwrap();
+
+		let mut stream = &stream[..];
+		assert_eq!(read_message::<Request>(&mut stream).unwrap(), request);
+		assert_eq!(read_message::<Response>(&mut stream).unwrap(), Response::HasKeys(true));
+		assert!(read_message::<Response>(&mut stream).is_err());
+	}
+
+	#[test]
+	fn oversized_messages_are_rejected() {
+		let mut stream = &(MAX_MESSAGE_SIZE + 1).to_le_bytes()[..];
+		let error = read_message::<Request>(&mut stream).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+	}
+}
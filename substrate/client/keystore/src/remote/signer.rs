@@ -0,0 +1,312 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The [`RemoteSigner`] serving the requests of [`RemoteKeystore`](super::RemoteKeystore)s.
+
+use super::{
+	protocol::{read_message, write_message, Request, Response},
+	slashing_protection::SlashingProtection,
+	transport::{SignerListener, Stream},
+	LOG_TARGET,
+};
+use codec::Encode;
+use parking_lot::{const_mutex, Mutex};
+use sp_core::{
+	crypto::{key_types, ByteArray, CryptoTypeId, KeyTypeId},
+	ecdsa, ed25519,
+	sr25519::{
+		self,
+		vrf::{VrfSignData, VrfTranscript, VrfTranscriptData},
+	},
+};
+use sp_keystore::{Error as TraitError, Keystore};
+use std::{collections::BTreeSet, io, sync::Arc, thread};
+
+sp_keystore::bandersnatch_experimental_enabled! {
+use sp_core::bandersnatch;
+}
+
+sp_keystore::bls_experimental_enabled! {
+use sp_core::{bls381, ecdsa_bls381};
+}
+
+sp_keystore::p256_experimental_enabled! {
+use sp_core::p256;
+}
+
+/// The maximal number of distinct VRF transcript labels accepted by the signer.
+const MAX_VRF_LABELS: usize = 256;
+
+/// The key types of consensus keys.
+///
+/// Prehashed messages can't be checked by the [`SlashingProtection`], thus they are never signed
+/// with these keys.
+const CONSENSUS_KEY_TYPES: [KeyTypeId; 5] =
+	[key_types::AURA, key_types::BABE, key_types::SASSAFRAS, key_types::GRANDPA, key_types::BEEFY];
+
+/// Serves the requests of [`RemoteKeystore`](super::RemoteKeystore)s using a local keystore.
+///
+/// Every consensus message is checked by the [`SlashingProtection`] before it is signed.
+pub struct RemoteSigner<K> {
+	keystore: K,
+	slashing_protection: SlashingProtection,
+	/// Whether keys derived from secret URIs sent by the nodes are inserted into the keystore.
+	allow_key_insertion: bool,
+}
+
+impl<K: Keystore + 'static> RemoteSigner<K> {
+	/// Create a new signer using the keys of `keystore`.
+	///
+	/// Inserting keys is refused, see [`Self::with_key_insertion`].
+	pub fn new(keystore: K, slashing_protection: SlashingProtection) -> Self {
+		Self { keystore, slashing_protection, allow_key_insertion: false }
+	}
+
+	/// Enable/disable inserting keys into the keystore, e.g. via the `author_insertKey` RPC of a
+	/// node.
+	///
+	/// The secret URI of the inserted key is sent by the node, so that it is known outside of the
+	/// signer. Disabled by default.
+	pub fn with_key_insertion(mut self, allow: bool) -> Self {
+		self.allow_key_insertion = allow;
+		self
+	}
+
+	/// Serve the connections accepted by `listener`, each one on its own thread.
+	///
+	/// Only returns if accepting a connection failed.
+	pub fn serve(self: Arc<Self>, listener: SignerListener) -> io::Result<()> {
+		loop {
+			let stream = listener.accept()?;
+			let signer = self.clone();
+			thread::Builder::new()
+				.name("remote-signer-connection".into())
+				.spawn(move || signer.serve_connection(stream))?;
+		}
+	}
+
+	/// Answer the requests received on `stream` until it is closed.
+	fn serve_connection(&self, mut stream: Box<dyn Stream>) {
+		loop {
+			let request = match read_message::<Request>(&mut stream) {
+				Ok(request) => request,
+				Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return,
+				Err(e) => {
+					log::debug!(target: LOG_TARGET, "Closing connection after invalid request: {e}");
+					return
+				},
+			};
+
+			if let Err(e) = write_message(&mut stream, &self.handle(request)) {
+				log::debug!(target: LOG_TARGET, "Closing connection after failed response: {e}");
+				return
+			}
+		}
+	}
+
+	/// Answer a single request.
+	pub fn handle(&self, request: Request) -> Response {
+		self.try_handle(request).unwrap_or_else(|e| Response::Error(e.into()))
+	}
+
+	fn try_handle(&self, request: Request) -> Result<Response, TraitError> {
+		let keystore = &self.keystore;
+		let response = match request {
+			Request::PublicKeys { crypto, key_type } =>
+				Response::PublicKeys(self.public_keys(crypto, key_type)?),
+			Request::GenerateNew { crypto, key_type, seed } =>
+				Response::Public(self.generate_new(crypto, key_type, seed.as_deref())?),
+			Request::Sign { crypto, key_type, public, message } => {
+				if !self.has_key(key_type, &public) {
+					return Ok(Response::Signature(None))
+				}
+				self.slashing_protection.check_message(key_type, &public, &message)?;
+				Response::Signature(keystore.sign_with(key_type, crypto, &public, &message)?)
+			},
+			Request::EcdsaSignPrehashed { key_type, public, message } => {
+				if CONSENSUS_KEY_TYPES.contains(&key_type) {
+					return Err(TraitError::ValidationError(
+						"Prehashed messages are not signed with consensus keys".into(),
+					))
+				}
+				let public = public_key::<ecdsa::Public>(&public)?;
+				let signature = keystore.ecdsa_sign_prehashed(key_type, &public, &message)?;
+				Response::Signature(signature.map(|signature| signature.encode()))
+			},
+			Request::EcdsaSignWithKeccak256 { key_type, public, message } => {
+				if !self.has_key(key_type, &public) {
+					return Ok(Response::Signature(None))
+				}
+				self.slashing_protection.check_message(key_type, &public, &message)?;
+				let public = public_key::<ecdsa::Public>(&public)?;
+				let signature = keystore.ecdsa_sign_with_keccak256(key_type, &public, &message)?;
+				Response::Signature(signature.map(|signature| signature.encode()))
+			},
+			Request::EcdsaBls381SignWithKeccak256 { key_type, public, message } => {
+				let signature = self.ecdsa_bls381_sign_with_keccak256(key_type, public, message)?;
+				Response::Signature(signature)
+			},
+			Request::Sr25519VrfSign { key_type, public, input, extra } => {
+				if !self.has_key(key_type, &public) {
+					return Ok(Response::VrfSignature(None))
+				}
+				let mut data = VrfSignData::new(transcript(&input)?);
+				if let Some(extra) = extra {
+					data = data.with_extra(transcript(&extra)?);
+				}
+				self.slashing_protection.check_vrf(key_type, &public, &input)?;
+				let public = public_key::<sr25519::Public>(&public)?;
+				Response::VrfSignature(keystore.sr25519_vrf_sign(key_type, &public, &data)?)
+			},
+			Request::Sr25519VrfPreOutput { key_type, public, input } => {
+				let public = public_key::<sr25519::Public>(&public)?;
+				let input = transcript(&input)?;
+				Response::VrfPreOutput(keystore.sr25519_vrf_pre_output(key_type, &public, &input)?)
+			},
+			Request::Insert { key_type, suri, public } => {
+				if !self.allow_key_insertion {
+					return Err(TraitError::ValidationError("Inserting keys is disabled".into()))
+				}
+				keystore
+					.insert(key_type, &suri, &public)
+					.map_err(|_| TraitError::Other("Failed to insert the key".into()))?;
+				Response::Inserted
+			},
+			Request::Keys { key_type } => Response::PublicKeys(keystore.keys(key_type)?),
+			Request::HasKeys { keys } => Response::HasKeys(keystore.has_keys(&keys)),
+		};
+		Ok(response)
+	}
+
+	fn has_key(&self, key_type: KeyTypeId, public: &[u8]) -> bool {
+		self.keystore.has_keys(&[(public.to_vec(), key_type)])
+	}
+
+	fn public_keys(
+		&self,
+		crypto: CryptoTypeId,
+		key_type: KeyTypeId,
+	) -> Result<Vec<Vec<u8>>, TraitError> {
+		let keystore = &self.keystore;
+		let keys = match crypto {
+			sr25519::CRYPTO_ID => raw_keys(keystore.sr25519_public_keys(key_type)),
+			ed25519::CRYPTO_ID => raw_keys(keystore.ed25519_public_keys(key_type)),
+			ecdsa::CRYPTO_ID => raw_keys(keystore.ecdsa_public_keys(key_type)),
+			#[cfg(feature = "p256-experimental")]
+			p256::CRYPTO_ID => raw_keys(keystore.p256_public_keys(key_type)),
+			#[cfg(feature = "bandersnatch-experimental")]
+			bandersnatch::CRYPTO_ID => raw_keys(keystore.bandersnatch_public_keys(key_type)),
+			#[cfg(feature = "bls-experimental")]
+			bls381::CRYPTO_ID => raw_keys(keystore.bls381_public_keys(key_type)),
+			#[cfg(feature = "bls-experimental")]
+			ecdsa_bls381::CRYPTO_ID => raw_keys(keystore.ecdsa_bls381_public_keys(key_type)),
+			_ => return Err(TraitError::KeyNotSupported(key_type)),
+		};
+		Ok(keys)
+	}
+
+	fn generate_new(
+		&self,
+		crypto: CryptoTypeId,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<Vec<u8>, TraitError> {
+		let keystore = &self.keystore;
+		let public = match crypto {
+			sr25519::CRYPTO_ID => keystore.sr25519_generate_new(key_type, seed)?.to_raw_vec(),
+			ed25519::CRYPTO_ID => keystore.ed25519_generate_new(key_type, seed)?.to_raw_vec(),
+			ecdsa::CRYPTO_ID => keystore.ecdsa_generate_new(key_type, seed)?.to_raw_vec(),
+			#[cfg(feature = "p256-experimental")]
+			p256::CRYPTO_ID => keystore.p256_generate_new(key_type, seed)?.to_raw_vec(),
+			#[cfg(feature = "bandersnatch-experimental")]
+			bandersnatch::CRYPTO_ID => keystore.bandersnatch_generate_new(key_type, seed)?.to_raw_vec(),
+			#[cfg(feature = "bls-experimental")]
+			bls381::CRYPTO_ID => keystore.bls381_generate_new(key_type, seed)?.to_raw_vec(),
+			#[cfg(feature = "bls-experimental")]
+			ecdsa_bls381::CRYPTO_ID => keystore.ecdsa_bls381_generate_new(key_type, seed)?.to_raw_vec(),
+			_ => return Err(TraitError::KeyNotSupported(key_type)),
+		};
+		Ok(public)
+	}
+
+	#[cfg(feature = "bls-experimental")]
+	fn ecdsa_bls381_sign_with_keccak256(
+		&self,
+		key_type: KeyTypeId,
+		public: Vec<u8>,
+		message: Vec<u8>,
+	) -> Result<Option<Vec<u8>>, TraitError> {
+		if !self.has_key(key_type, &public) {
+			return Ok(None)
+		}
+		self.slashing_protection.check_message(key_type, &public, &message)?;
+		let public = public_key::<ecdsa_bls381::Public>(&public)?;
+		let signature =
+			self.keystore.ecdsa_bls381_sign_with_keccak256(key_type, &public, &message)?;
+		Ok(signature.map(|signature| signature.encode()))
+	}
+
+	#[cfg(not(feature = "bls-experimental"))]
+	fn ecdsa_bls381_sign_with_keccak256(
+		&self,
+		key_type: KeyTypeId,
+		_: Vec<u8>,
+		_: Vec<u8>,
+	) -> Result<Option<Vec<u8>>, TraitError> {
+		Err(TraitError::KeyNotSupported(key_type))
+	}
+}
+
+fn raw_keys<T: ByteArray>(keys: Vec<T>) -> Vec<Vec<u8>> {
+	keys.iter().map(ByteArray::to_raw_vec).collect()
+}
+
+fn public_key<T: ByteArray>(public: &[u8]) -> Result<T, TraitError> {
+	T::from_slice(public)
+		.map_err(|_| TraitError::ValidationError("Invalid public key format".into()))
+}
+
+/// Build the transcript described by `data`.
+///
+/// Transcripts require `'static` labels. They are constants of the protocols using VRFs, so the
+/// few distinct labels ever received are leaked once and then reused.
+fn transcript(data: &VrfTranscriptData) -> Result<VrfTranscript, TraitError> {
+	static LABELS: Mutex<BTreeSet<&'static [u8]>> = const_mutex(BTreeSet::new());
+
+	let mut labels = LABELS.lock();
+	let mut intern = |label: &[u8]| -> Result<&'static [u8], TraitError> {
+		if let Some(label) = labels.get(label) {
+			return Ok(*label)
+		}
+		if labels.len() >= MAX_VRF_LABELS {
+			return Err(TraitError::ValidationError("Too many distinct VRF labels".into()))
+		}
+
+		let label: &'static [u8] = Box::leak(label.into());
+		labels.insert(label);
+		Ok(label)
+	};
+
+	let label = intern(&data.label)?;
+	let items = data
+		.items
+		.iter()
+		.map(|(label, message)| Ok((intern(label)?, &message[..])))
+		.collect::<Result<Vec<_>, TraitError>>()?;
+	Ok(VrfTranscript::new(label, &items))
+}
@@ -0,0 +1,352 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Slashing protection of the [`RemoteSigner`](super::RemoteSigner).
+//!
+//! The signer refuses to sign two different consensus messages which could get the validator
+//! slashed for equivocating:
+//!
+//! - GRANDPA: two different votes of the same kind in the same round of an authority set. Votes
+//!   are signed as `(message, round, set_id)`, with the kind of the vote as first byte.
+//! - BEEFY: two different commitments for the same block of a validator set. Commitments signed
+//!   using [`Keystore::ecdsa_sign_prehashed`](sp_keystore::Keystore::ecdsa_sign_prehashed) can
+//!   not be inspected and are not checked.
+//! - BABE: two different headers for the same slot. The seal of a header only signs its hash,
+//!   so it is attributed to the slot of the latest VRF slot claim of the key. BABE tries to claim
+//!   every slot with a VRF first, including the slots it then claims as secondary slot.
+//!
+//! Signing the same message again is always allowed, e.g. after the node restarted.
+
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sp_core::{
+	blake2_256,
+	crypto::{key_types, KeyTypeId},
+	sr25519::vrf::VrfTranscriptData,
+};
+use sp_keystore::Error as TraitError;
+use std::{
+	collections::BTreeMap,
+	fmt::Debug,
+	fs::{self, File},
+	io::{self, Write},
+	path::{Path, PathBuf},
+};
+
+/// The number of signed messages remembered per key and protocol.
+const MAX_HISTORY: usize = 4096;
+
+/// The label of the slot number in the VRF transcript of a BABE slot claim.
+const BABE_SLOT_LABEL: &[u8] = b"slot number";
+
+/// The hashes of the messages signed by a key, by their position in the protocol.
+#[derive(Default, Encode, Decode)]
+struct Signed<P: Ord>(BTreeMap<P, [u8; 32]>);
+
+impl<P: Ord + Debug> Signed<P> {
+	/// Remember that `message` was signed at `position`.
+	///
+	/// Returns whether the message wasn't signed before or an error if a different message was
+	/// signed at the same position.
+	fn record(&mut self, position: P, message: &[u8]) -> Result<bool, String> {
+		let hash = blake2_256(message);
+		if let Some(signed) = self.0.get(&position) {
+			return if *signed == hash {
+				Ok(false)
+			} else {
+				Err(format!("a different message was already signed at {position:?}"))
+			}
+		}
+
+		let is_outdated = self.0.len() >= MAX_HISTORY &&
+			self.0.first_key_value().is_some_and(|(first, _)| position < *first);
+		if is_outdated {
+			return Err(format!("{position:?} is older than the remembered history"))
+		}
+
+		self.0.insert(position, hash);
+		while self.0.len() > MAX_HISTORY {
+			self.0.pop_first();
+		}
+		Ok(true)
+	}
+}
+
+/// The history of a BABE key.
+#[derive(Default, Encode, Decode)]
+struct BabeHistory {
+	/// The slot of the latest VRF slot claim.
+	claimed_slot: Option<u64>,
+	/// The sealed headers by slot.
+	sealed: Signed<u64>,
+}
+
+/// The messages signed by every key, indexed by the raw public key.
+#[derive(Default, Encode, Decode)]
+struct History {
+	babe: BTreeMap<Vec<u8>, BabeHistory>,
+	/// Votes by `(set_id, round, kind)`.
+	grandpa: BTreeMap<Vec<u8>, Signed<(u64, u64, u8)>>,
+	/// Commitments by `(validator_set_id, block_number)`.
+	beefy: BTreeMap<Vec<u8>, Signed<(u64, u64)>>,
+}
+
+/// Remembers the consensus messages signed by a [`RemoteSigner`](super::RemoteSigner) and
+/// refuses to sign conflicting ones.
+pub struct SlashingProtection {
+	path: Option<PathBuf>,
+	history: Mutex<History>,
+}
+
+impl SlashingProtection {
+	/// Create a slashing protection which forgets its history when the signer stops.
+	///
+	/// After a restart conflicting messages could be signed, this should only be used for testing.
+	pub fn in_memory() -> Self {
+		Self { path: None, history: Default::default() }
+	}
+
+	/// Open the history stored at `path`, it is created when the first message is signed.
+	pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+		let path = path.into();
+		let history = match fs::read(&path) {
+			Ok(encoded) => History::decode(&mut &encoded[..])
+				.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => History::default(),
+			Err(e) => return Err(e),
+		};
+
+		Ok(Self { path: Some(path), history: Mutex::new(history) })
+	}
+
+	/// Check that the VRF signature of `input` can be signed by `public`.
+	pub fn check_vrf(
+		&self,
+		key_type: KeyTypeId,
+		public: &[u8],
+		input: &VrfTranscriptData,
+	) -> Result<(), TraitError> {
+		if key_type != key_types::BABE {
+			return Ok(())
+		}
+
+		let Some(slot) = input
+			.items
+			.iter()
+			.find(|(label, _)| label == BABE_SLOT_LABEL)
+			.and_then(|(_, slot)| u64::decode(&mut &slot[..]).ok())
+		else {
+			return Ok(())
+		};
+
+		let mut history = self.history.lock();
+		let claimed_slot = &mut history.babe.entry(public.to_vec()).or_default().claimed_slot;
+		if *claimed_slot == Some(slot) {
+			return Ok(())
+		}
+
+		*claimed_slot = Some(slot);
+		self.persist(&history)
+	}
+
+	/// Check that `message` can be signed by `public`.
+	pub fn check_message(
+		&self,
+		key_type: KeyTypeId,
+		public: &[u8],
+		message: &[u8],
+	) -> Result<(), TraitError> {
+		let mut history = self.history.lock();
+		let recorded = match key_type {
+			key_types::BABE if message.len() == 32 => {
+				let babe = history.babe.entry(public.to_vec()).or_default();
+				match babe.claimed_slot {
+					Some(slot) => babe.sealed.record(slot, message),
+					None => Ok(false),
+				}
+			},
+			key_types::GRANDPA => match grandpa_vote(message) {
+				Some(position) =>
+					history.grandpa.entry(public.to_vec()).or_default().record(position, message),
+				None => Ok(false),
+			},
+			key_types::BEEFY => match beefy_commitment(message) {
+				Some(position) =>
+					history.beefy.entry(public.to_vec()).or_default().record(position, message),
+				None => Ok(false),
+			},
+			_ => Ok(false),
+		};
+
+		match recorded {
+			Ok(true) => self.persist(&history),
+			Ok(false) => Ok(()),
+			Err(e) => {
+				log::warn!(
+					target: "keystore",
+					"Refusing to sign a {} message: {e}",
+					String::from_utf8_lossy(&key_type.0),
+				);
+				Err(TraitError::ValidationError(format!("Slashing protection: {e}")))
+			},
+		}
+	}
+
+	/// Write the history to disk, before the signature is handed out.
+	fn persist(&self, history: &History) -> Result<(), TraitError> {
+		let Some(path) = &self.path else { return Ok(()) };
+
+		write_durably(path, &history.encode()).map_err(|e| {
+			TraitError::Other(format!("Failed to persist the slashing protection: {e}"))
+		})
+	}
+}
+
+/// Atomically replace the file at `path` with `data`.
+///
+/// The data is written to a temporary file, which is synced before it is renamed to `path`.
+/// The parent directory is synced afterwards, so the rename survives a crash as well.
+fn write_durably(path: &Path, data: &[u8]) -> io::Result<()> {
+	let tmp = path.with_extension("tmp");
+	let mut file = File::create(&tmp)?;
+	file.write_all(data)?;
+	file.sync_all()?;
+	drop(file);
+
+	fs::rename(&tmp, path)?;
+
+	#[cfg(unix)]
+	{
+		let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+		File::open(parent.unwrap_or_else(|| Path::new(".")))?.sync_all()?;
+	}
+
+	Ok(())
+}
+
+/// Decode the `(set_id, round, kind)` of a GRANDPA vote.
+///
+/// Votes are signed as `(message, round, set_id)` and the message starts with the kind of the
+/// vote, a prevote, precommit or primary propose.
+fn grandpa_vote(message: &[u8]) -> Option<(u64, u64, u8)> {
+	let kind = *message.first().filter(|kind| **kind <= 2)?;
+	let tail = message.len().checked_sub(16).filter(|tail| *tail > 0)?;
+	let (round, set_id) = <(u64, u64)>::decode(&mut &message[tail..]).ok()?;
+	Some((set_id, round, kind))
+}
+
+/// Decode the `(validator_set_id, block_number)` of a BEEFY commitment.
+///
+/// Commitments are encoded as `(payload, block_number, validator_set_id)`, where the block number
+/// is either a `u32` or a `u64`.
+fn beefy_commitment(message: &[u8]) -> Option<(u64, u64)> {
+	let mut input = message;
+	<Vec<([u8; 2], Vec<u8>)>>::decode(&mut input).ok()?;
+	let block_number = match input.len() {
+		12 => u32::decode(&mut input).ok()?.into(),
+		16 => u64::decode(&mut input).ok()?,
+		_ => return None,
+	};
+	let set_id = u64::decode(&mut input).ok()?;
+	Some((set_id, block_number))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::TempDir;
+
+	fn grandpa_prevote(target: u8, round: u64, set_id: u64) -> Vec<u8> {
+		(0u8, [target; 32], 10u32, round, set_id).encode()
+	}
+
+	fn beefy_commitment(root: u8, block_number: u32, set_id: u64) -> Vec<u8> {
+		(vec![(*b"mh", vec![root; 32])], block_number, set_id).encode()
+	}
+
+	fn babe_claim(slot: u64) -> VrfTranscriptData {
+		sp_core::sr25519::vrf::VrfTranscript::new(
+			b"BABE",
+			&[(b"slot number", &slot.to_le_bytes()), (b"current epoch", &1u64.to_le_bytes())],
+		)
+		.data()
+		.clone()
+	}
+
+	#[test]
+	fn grandpa_double_votes_are_refused() {
+		let protection = SlashingProtection::in_memory();
+		let check = |message: &[u8]| protection.check_message(key_types::GRANDPA, &[1], message);
+
+		assert!(check(&grandpa_prevote(1, 5, 0)).is_ok());
+		assert!(check(&grandpa_prevote(1, 5, 0)).is_ok());
+		assert!(check(&grandpa_prevote(2, 5, 0)).is_err());
+		assert!(check(&grandpa_prevote(2, 6, 0)).is_ok());
+		assert!(check(&grandpa_prevote(2, 5, 1)).is_ok());
+
+		// Other keys are independent.
+		assert!(protection
+			.check_message(key_types::GRANDPA, &[2], &grandpa_prevote(2, 5, 0))
+			.is_ok());
+	}
+
+	#[test]
+	fn beefy_double_votes_are_refused() {
+		let protection = SlashingProtection::in_memory();
+		let check = |message: &[u8]| protection.check_message(key_types::BEEFY, &[1], message);
+
+		assert!(check(&beefy_commitment(1, 10, 0)).is_ok());
+		assert!(check(&beefy_commitment(1, 10, 0)).is_ok());
+		assert!(check(&beefy_commitment(2, 10, 0)).is_err());
+		assert!(check(&beefy_commitment(2, 11, 0)).is_ok());
+		// A pre-hashed commitment can not be checked.
+		assert!(check(&[3; 32]).is_ok());
+	}
+
+	#[test]
+	fn babe_equivocations_are_refused() {
+		let protection = SlashingProtection::in_memory();
+		let seal = |hash: u8| protection.check_message(key_types::BABE, &[1], &[hash; 32]);
+
+		// Seals without a claim can not be attributed to a slot.
+		assert!(seal(1).is_ok());
+
+		protection.check_vrf(key_types::BABE, &[1], &babe_claim(7)).unwrap();
+		assert!(seal(1).is_ok());
+		assert!(seal(1).is_ok());
+		assert!(seal(2).is_err());
+
+		protection.check_vrf(key_types::BABE, &[1], &babe_claim(8)).unwrap();
+		assert!(seal(2).is_ok());
+	}
+
+	#[test]
+	fn history_is_persisted() {
+		let dir = TempDir::new().unwrap();
+		let path = dir.path().join("slashing_protection");
+
+		let protection = SlashingProtection::open(&path).unwrap();
+		protection.check_message(key_types::GRANDPA, &[1], &grandpa_prevote(1, 5, 0)).unwrap();
+		drop(protection);
+
+		let protection = SlashingProtection::open(&path).unwrap();
+		assert!(protection
+			.check_message(key_types::GRANDPA, &[1], &grandpa_prevote(2, 5, 0))
+			.is_err());
+	}
+}
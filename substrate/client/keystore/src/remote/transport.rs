@@ -0,0 +1,258 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The connections between a [`RemoteKeystore`](super::RemoteKeystore) and a
+//! [`RemoteSigner`](super::RemoteSigner).
+//!
+//! The signer either listens on a Unix domain socket, which is protected by the file
+//! permissions of the socket, or on a TCP socket. TCP connections always use mutual TLS, both the
+//! node and the signer have to present a certificate signed by the configured certificate
+//! authority.
+
+use crate::{Error, Result};
+use rustls::{
+	crypto::CryptoProvider,
+	pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+	server::WebPkiClientVerifier,
+	ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::{
+	io::{self, Read, Write},
+	net::{TcpListener, TcpStream},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+
+/// A bidirectional byte stream to the other side.
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// The files used to set up mutual TLS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+	/// The PEM encoded certificate of the authority that signed the certificates of both sides.
+	pub ca_certificate: PathBuf,
+	/// The PEM encoded certificate chain of this side.
+	pub certificate: PathBuf,
+	/// The PEM encoded private key of [`Self::certificate`].
+	pub private_key: PathBuf,
+}
+
+/// The address of a remote signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerAddress {
+	/// The path of a Unix domain socket.
+	#[cfg(unix)]
+	Unix(PathBuf),
+	/// A `<HOST>:<PORT>` TCP address secured using mutual TLS.
+	Tcp {
+		/// The host and port of the signer.
+		address: String,
+		/// The TLS configuration.
+		tls: TlsConfig,
+	},
+}
+
+impl SignerAddress {
+	/// Parse `address` which is either `unix:<PATH>` or `<HOST>:<PORT>`.
+	///
+	/// TCP addresses require `tls`.
+	pub fn new(address: &str, tls: Option<TlsConfig>) -> Result<Self> {
+		if let Some(path) = address.strip_prefix("unix:") {
+			#[cfg(unix)]
+			return Ok(Self::Unix(path.into()));
+			#[cfg(not(unix))]
+			return Err(Error::InvalidSignerAddress(format!(
+				"Unix domain sockets are not supported on this platform: {path}"
+			)));
+		}
+
+		let is_tcp = address
+			.rsplit_once(':')
+			.is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+		if !is_tcp {
+			return Err(Error::InvalidSignerAddress(format!(
+				"Expected `unix:<PATH>` or `<HOST>:<PORT>`, got `{address}`"
+			)))
+		}
+
+		let tls = tls.ok_or_else(|| {
+			Error::InvalidSignerAddress(format!("TCP address `{address}` requires mutual TLS"))
+		})?;
+		Ok(Self::Tcp { address: address.into(), tls })
+	}
+
+	/// Connect to the signer.
+	///
+	/// Reads and writes on the returned stream time out after `timeout`.
+	pub(super) fn connect(&self, timeout: Duration) -> io::Result<Box<dyn Stream>> {
+		match self {
+			#[cfg(unix)]
+			Self::Unix(path) => {
+				let stream = UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(timeout))?;
+				stream.set_write_timeout(Some(timeout))?;
+				Ok(Box::new(stream))
+			},
+			Self::Tcp { address, tls } => {
+				let host = address.rsplit_once(':').map_or(address.as_str(), |(host, _)| host);
+				let server_name = ServerName::try_from(host.trim_matches(&['[', ']'][..]))
+					.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+					.to_owned();
+				let connection = ClientConnection::new(client_config(tls)?, server_name)
+					.map_err(io::Error::other)?;
+
+				let stream = TcpStream::connect(address)?;
+				stream.set_read_timeout(Some(timeout))?;
+				stream.set_write_timeout(Some(timeout))?;
+				stream.set_nodelay(true)?;
+				Ok(Box::new(StreamOwned::new(connection, stream)))
+			},
+		}
+	}
+}
+
+/// Accepts the connections of nodes on a [`SignerAddress`].
+pub enum SignerListener {
+	/// A Unix domain socket.
+	#[cfg(unix)]
+	Unix(UnixListener),
+	/// A TCP socket secured by mutual TLS.
+	Tcp(TcpListener, Arc<ServerConfig>),
+}
+
+impl SignerListener {
+	/// Listen on `address`.
+	///
+	/// A Unix domain socket is only accessible by the owner of the signer process.
+	pub fn bind(address: &SignerAddress) -> io::Result<Self> {
+		match address {
+			#[cfg(unix)]
+			SignerAddress::Unix(path) => {
+				use std::os::unix::fs::PermissionsExt;
+
+				let listener = UnixListener::bind(path)?;
+				std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+				Ok(Self::Unix(listener))
+			},
+			SignerAddress::Tcp { address, tls } =>
+				Ok(Self::Tcp(TcpListener::bind(address)?, server_config(tls)?)),
+		}
+	}
+
+	/// Wait for the next connection.
+	pub fn accept(&self) -> io::Result<Box<dyn Stream>> {
+		match self {
+			#[cfg(unix)]
+			Self::Unix(listener) => Ok(Box::new(listener.accept()?.0)),
+			Self::Tcp(listener, config) => {
+				let (stream, _) = listener.accept()?;
+				stream.set_nodelay(true)?;
+				let connection = ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+				Ok(Box::new(StreamOwned::new(connection, stream)))
+			},
+		}
+	}
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+	Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn client_config(tls: &TlsConfig) -> io::Result<Arc<ClientConfig>> {
+	let config = ClientConfig::builder_with_provider(crypto_provider())
+		.with_safe_default_protocol_versions()
+		.map_err(io::Error::other)?
+		.with_root_certificates(root_store(&tls.ca_certificate)?)
+		.with_client_auth_cert(certificates(&tls.certificate)?, private_key(&tls.private_key)?)
+		.map_err(io::Error::other)?;
+	Ok(Arc::new(config))
+}
+
+fn server_config(tls: &TlsConfig) -> io::Result<Arc<ServerConfig>> {
+	let verifier = WebPkiClientVerifier::builder_with_provider(
+		Arc::new(root_store(&tls.ca_certificate)?),
+		crypto_provider(),
+	)
+	.build()
+	.map_err(io::Error::other)?;
+
+	let config = ServerConfig::builder_with_provider(crypto_provider())
+		.with_safe_default_protocol_versions()
+		.map_err(io::Error::other)?
+		.with_client_cert_verifier(verifier)
+		.with_single_cert(certificates(&tls.certificate)?, private_key(&tls.private_key)?)
+		.map_err(io::Error::other)?;
+	Ok(Arc::new(config))
+}
+
+fn root_store(path: &Path) -> io::Result<RootCertStore> {
+	let mut roots = RootCertStore::empty();
+	for certificate in certificates(path)? {
+		roots.add(certificate).map_err(io::Error::other)?;
+	}
+	Ok(roots)
+}
+
+fn certificates(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+	CertificateDer::pem_file_iter(path)
+		.and_then(|certificates| certificates.collect())
+		.map_err(|e| pem_error(path, e))
+}
+
+fn private_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+	PrivateKeyDer::from_pem_file(path).map_err(|e| pem_error(path, e))
+}
+
+fn pem_error(path: &Path, error: rustls::pki_types::pem::Error) -> io::Error {
+	io::Error::new(
+		io::ErrorKind::InvalidData,
+		format!("Failed to read {}: {error}", path.display()),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signer_addresses_are_parsed() {
+		let tls = TlsConfig {
+			ca_certificate: "ca.pem".into(),
+			certificate: "node.pem".into(),
+			private_key: "node.key".into(),
+		};
+
+		#[cfg(unix)]
+		assert_eq!(
+			SignerAddress::new("unix:/run/signer.sock", None).unwrap(),
+			SignerAddress::Unix("/run/signer.sock".into()),
+		);
+		assert_eq!(
+			SignerAddress::new("signer.local:9955", Some(tls.clone())).unwrap(),
+			SignerAddress::Tcp { address: "signer.local:9955".into(), tls: tls.clone() },
+		);
+		assert!(SignerAddress::new("signer.local:9955", None).is_err());
+		assert!(SignerAddress::new("signer.local", Some(tls.clone())).is_err());
+		assert!(SignerAddress::new(":9955", Some(tls)).is_err());
+	}
+}
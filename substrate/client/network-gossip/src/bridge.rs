@@ -222,11 +222,19 @@ impl<B: BlockT> Future for GossipEngine<B> {
 								peer, handshake, ..
 							} =>
 								if let Some(role) = this.network.peer_role(peer, handshake) {
-									this.state_machine.new_peer(
-										&mut this.notification_service,
-										peer,
-										role,
-									);
+									if this.network.is_sentry_peer(peer) {
+										this.state_machine.new_sentry_peer(
+											&mut this.notification_service,
+											peer,
+											role,
+										);
+									} else {
+										this.state_machine.new_peer(
+											&mut this.notification_service,
+											peer,
+											role,
+										);
+									}
 								} else {
 									log::debug!(target: "gossip", "role for {peer} couldn't be determined");
 								},
@@ -373,7 +381,9 @@ mod tests {
 	use substrate_test_runtime_client::runtime::Block;
 
 	#[derive(Clone, Default)]
-	struct TestNetwork {}
+	struct TestNetwork {
+		sentry_peers: HashSet<PeerId>,
+	}
 
 	#[async_trait::async_trait]
 	impl NetworkPeers for TestNetwork {
@@ -449,6 +459,10 @@ mod tests {
 				.and_then(|role| Some(ObservedRole::from(role)))
 		}
 
+		fn is_sentry_peer(&self, peer_id: PeerId) -> bool {
+			self.sentry_peers.contains(&peer_id)
+		}
+
 		async fn reserved_peers(&self) -> Result<Vec<PeerId>, ()> {
 			unimplemented!();
 		}
@@ -887,4 +901,193 @@ mod tests {
 
 		QuickCheck::new().quickcheck(prop as fn(_, _))
 	}
+
+	/// Inbound notification event senders of the nodes of a test, by peer ID.
+	type Hub = Arc<Mutex<HashMap<PeerId, UnboundedSender<NotificationEvent>>>>;
+
+	/// Notification service delivering the notifications to the other nodes of the [`Hub`].
+	#[derive(Debug)]
+	struct HubNotificationService {
+		local: PeerId,
+		hub: Hub,
+		rx: UnboundedReceiver<NotificationEvent>,
+	}
+
+	#[async_trait::async_trait]
+	impl sc_network::service::traits::NotificationService for HubNotificationService {
+		async fn open_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+			unimplemented!();
+		}
+
+		async fn close_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+			unimplemented!();
+		}
+
+		fn send_sync_notification(&mut self, peer: &PeerId, notification: Vec<u8>) {
+			if let Some(tx) = self.hub.lock().unwrap().get(peer) {
+				let _ = tx.unbounded_send(NotificationEvent::NotificationReceived {
+					peer: self.local,
+					notification,
+				});
+			}
+		}
+
+		async fn send_async_notification(
+			&mut self,
+			_peer: &PeerId,
+			_notification: Vec<u8>,
+		) -> Result<(), sc_network::error::Error> {
+			unimplemented!();
+		}
+
+		async fn set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+			unimplemented!();
+		}
+
+		fn try_set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+			unimplemented!();
+		}
+
+		async fn next_event(&mut self) -> Option<NotificationEvent> {
+			self.rx.next().await
+		}
+
+		fn clone(&mut self) -> Result<Box<dyn NotificationService>, ()> {
+			unimplemented!();
+		}
+
+		fn protocol(&self) -> &ProtocolName {
+			unimplemented!();
+		}
+
+		fn message_sink(&self, _peer: &PeerId) -> Option<Box<dyn MessageSink>> {
+			unimplemented!();
+		}
+	}
+
+	/// Validator with a gossip topology limited to the given peers, relaying all valid messages.
+	struct LuckyPeers(HashSet<PeerId>);
+
+	impl Validator<Block> for LuckyPeers {
+		fn validate(
+			&self,
+			context: &mut dyn ValidatorContext<Block>,
+			_sender: &PeerId,
+			data: &[u8],
+		) -> ValidationResult<H256> {
+			context.broadcast_message(H256::default(), data.to_vec(), false);
+			ValidationResult::ProcessAndKeep(H256::default())
+		}
+
+		fn message_allowed<'a>(
+			&'a self,
+		) -> Box<dyn FnMut(&PeerId, MessageIntent, &H256, &[u8]) -> bool + 'a> {
+			Box::new(move |who, intent, _topic, _data| {
+				intent == MessageIntent::ForcedBroadcast || self.0.contains(who)
+			})
+		}
+	}
+
+	/// Gossip engines of a private validator, its sentry and a public node, connected as
+	/// `validator <-> sentry <-> public`.
+	///
+	/// The validator's topology doesn't include any peer and the sentry's topology only includes
+	/// the public node, so messages can only be relayed between the validator and the sentry if
+	/// both sides know about the sentry topology.
+	fn sentry_topology(
+		with_sentry_peers: bool,
+	) -> (GossipEngine<Block>, GossipEngine<Block>, GossipEngine<Block>, [PeerId; 3]) {
+		let peers @ [validator, sentry, public] =
+			[PeerId::random(), PeerId::random(), PeerId::random()];
+		let hub = Hub::default();
+
+		let engine = |local: PeerId, remotes: &[PeerId], sentry_peers: &[PeerId], lucky| {
+			let (tx, rx) = unbounded();
+			for remote in remotes {
+				tx.unbounded_send(NotificationEvent::NotificationStreamOpened {
+					peer: *remote,
+					direction: Direction::Inbound,
+					negotiated_fallback: None,
+					handshake: Roles::AUTHORITY.encode(),
+				})
+				.unwrap();
+			}
+			hub.lock().unwrap().insert(local, tx);
+
+			let sentry_peers = match with_sentry_peers {
+				true => sentry_peers.iter().copied().collect(),
+				false => HashSet::new(),
+			};
+
+			GossipEngine::<Block>::new(
+				TestNetwork { sentry_peers },
+				Arc::new(TestSync::default()),
+				Box::new(HubNotificationService { local, hub: hub.clone(), rx }),
+				"/my_protocol",
+				Arc::new(LuckyPeers(lucky)),
+				None,
+			)
+		};
+
+		let validator_engine = engine(validator, &[sentry], &[sentry], HashSet::new());
+		let sentry_engine =
+			engine(sentry, &[validator, public], &[validator], HashSet::from([public]));
+		let public_engine = engine(public, &[sentry], &[], HashSet::from([sentry]));
+
+		(validator_engine, sentry_engine, public_engine, peers)
+	}
+
+	/// Poll the engines until all notifications have been delivered.
+	fn drive(mut engines: [&mut GossipEngine<Block>; 3]) {
+		for _ in 0..5 {
+			block_on(poll_fn(|cx| {
+				for engine in engines.iter_mut() {
+					let _ = engine.poll_unpin(cx);
+				}
+				Poll::Ready(())
+			}));
+		}
+	}
+
+	#[test]
+	fn sentry_relays_messages_of_private_validator() {
+		let topic = H256::default();
+		let (mut validator, mut sentry, mut public, [_, sentry_id, _]) = sentry_topology(true);
+		let mut validator_rx = validator.messages_for(topic);
+		let mut public_rx = public.messages_for(topic);
+		drive([&mut validator, &mut sentry, &mut public]);
+
+		// from the validator to the public node
+		validator.gossip_message(topic, vec![1], false);
+		drive([&mut validator, &mut sentry, &mut public]);
+		assert_eq!(
+			public_rx.try_next().unwrap(),
+			Some(TopicNotification { message: vec![1], sender: Some(sentry_id) }),
+		);
+
+		// from the public node to the validator
+		public.gossip_message(topic, vec![2], false);
+		drive([&mut validator, &mut sentry, &mut public]);
+		assert_eq!(
+			validator_rx.try_next().unwrap(),
+			Some(TopicNotification { message: vec![2], sender: Some(sentry_id) }),
+		);
+	}
+
+	#[test]
+	fn gossip_topology_applies_without_sentry_peers() {
+		let topic = H256::default();
+		let (mut validator, mut sentry, mut public, _) = sentry_topology(false);
+		let mut validator_rx = validator.messages_for(topic);
+		let mut public_rx = public.messages_for(topic);
+		drive([&mut validator, &mut sentry, &mut public]);
+
+		validator.gossip_message(topic, vec![1], false);
+		public.gossip_message(topic, vec![2], false);
+		drive([&mut validator, &mut sentry, &mut public]);
+
+		// the validator's topology is empty and the sentry doesn't relay to the validator
+		assert!(public_rx.try_next().is_err());
+		assert!(validator_rx.try_next().is_err());
+	}
 }
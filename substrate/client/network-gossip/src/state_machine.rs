@@ -53,6 +53,12 @@ mod rep {
 
 struct PeerConsensus<H> {
 	known_messages: AHashSet<H>,
+	/// Peer is on the other side of the node's sentry topology.
+	///
+	/// Broadcasts to sentry peers are always forced, so they bypass the validator's gossip
+	/// topology, and sentry peers are not penalised for duplicate messages, which are expected
+	/// when a validator is protected by several sentries.
+	sentry: bool,
 }
 
 /// Topic stream message with sender.
@@ -133,6 +139,10 @@ where
 				},
 				other => other,
 			};
+			let intent = match intent {
+				MessageIntent::Broadcast if peer.sentry => MessageIntent::ForcedBroadcast,
+				other => other,
+			};
 
 			if !message_allowed(id, intent, topic, message) {
 				continue
@@ -196,15 +206,39 @@ impl<B: BlockT> ConsensusGossip<B> {
 		notification_service: &mut Box<dyn NotificationService>,
 		who: PeerId,
 		role: ObservedRole,
+	) {
+		self.register_peer(notification_service, who, role, false);
+	}
+
+	/// Handle new connected peer on the other side of the node's sentry topology.
+	///
+	/// All messages allowed by the validator for the peer are relayed to it, regardless of the
+	/// validator's gossip topology.
+	pub fn new_sentry_peer(
+		&mut self,
+		notification_service: &mut Box<dyn NotificationService>,
+		who: PeerId,
+		role: ObservedRole,
+	) {
+		self.register_peer(notification_service, who, role, true);
+	}
+
+	fn register_peer(
+		&mut self,
+		notification_service: &mut Box<dyn NotificationService>,
+		who: PeerId,
+		role: ObservedRole,
+		sentry: bool,
 	) {
 		tracing::trace!(
 			target:"gossip",
 			%who,
 			protocol = %self.protocol,
 			?role,
+			sentry,
 			"Registering peer",
 		);
-		self.peers.insert(who, PeerConsensus { known_messages: Default::default() });
+		self.peers.insert(who, PeerConsensus { known_messages: Default::default(), sentry });
 
 		let validator = self.validator.clone();
 		let mut context = NetworkContext { gossip: self, notification_service };
@@ -373,7 +407,7 @@ impl<B: BlockT> ConsensusGossip<B> {
 				if self
 					.peers
 					.get_mut(&who)
-					.map_or(false, |p| !p.known_messages.insert(message_hash))
+					.map_or(false, |p| !p.known_messages.insert(message_hash) && !p.sentry)
 				{
 					network.report_peer(who, rep::DUPLICATE_GOSSIP);
 				}
@@ -439,8 +473,11 @@ impl<B: BlockT> ConsensusGossip<B> {
 
 		if let Some(ref mut peer) = self.peers.get_mut(who) {
 			for entry in self.messages.iter().filter(|m| m.topic == topic) {
-				let intent =
-					if force { MessageIntent::ForcedBroadcast } else { MessageIntent::Broadcast };
+				let intent = if force || peer.sentry {
+					MessageIntent::ForcedBroadcast
+				} else {
+					MessageIntent::Broadcast
+				};
 
 				if !force && peer.known_messages.contains(&entry.message_hash) {
 					continue
@@ -924,4 +961,117 @@ mod tests {
 			network.inner.lock().unwrap().peer_reports
 		);
 	}
+
+	#[test]
+	fn sentry_peers_are_not_reported_for_duplicate_gossip() {
+		let mut consensus = ConsensusGossip::<Block>::new(Arc::new(AllowAll), "/foo".into(), None);
+
+		let mut network = NoOpNetwork::default();
+		let mut notification_service: Box<dyn NotificationService> =
+			Box::new(NoOpNotificationService::default());
+
+		let sentry = PeerId::random();
+		consensus.new_sentry_peer(&mut notification_service, sentry, ObservedRole::Authority);
+
+		let message = vec![vec![1, 2, 3]];
+		for _ in 0..2 {
+			consensus.on_incoming(&mut network, &mut notification_service, sentry, message.clone());
+		}
+
+		assert_eq!(vec![(sentry, rep::GOSSIP_SUCCESS)], network.inner.lock().unwrap().peer_reports);
+	}
+
+	#[test]
+	fn broadcasts_to_sentry_peers_are_forced() {
+		// Validator with a gossip topology which doesn't allow any regular broadcast.
+		struct ForcedOnly;
+		impl Validator<Block> for ForcedOnly {
+			fn validate(
+				&self,
+				_context: &mut dyn ValidatorContext<Block>,
+				_sender: &PeerId,
+				_data: &[u8],
+			) -> ValidationResult<H256> {
+				ValidationResult::ProcessAndKeep(H256::default())
+			}
+
+			fn message_allowed<'a>(
+				&'a self,
+			) -> Box<dyn FnMut(&PeerId, MessageIntent, &H256, &[u8]) -> bool + 'a> {
+				Box::new(move |_who, intent, _topic, _data| {
+					intent == MessageIntent::ForcedBroadcast
+				})
+			}
+		}
+
+		#[derive(Debug, Default)]
+		struct SentNotifications(Arc<Mutex<Vec<PeerId>>>);
+
+		#[async_trait::async_trait]
+		impl NotificationService for SentNotifications {
+			async fn open_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+				unimplemented!();
+			}
+
+			async fn close_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+				unimplemented!();
+			}
+
+			fn send_sync_notification(&mut self, peer: &PeerId, _notification: Vec<u8>) {
+				self.0.lock().unwrap().push(*peer);
+			}
+
+			async fn send_async_notification(
+				&mut self,
+				_peer: &PeerId,
+				_notification: Vec<u8>,
+			) -> Result<(), sc_network::error::Error> {
+				unimplemented!();
+			}
+
+			async fn set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+				unimplemented!();
+			}
+
+			fn try_set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+				unimplemented!();
+			}
+
+			async fn next_event(&mut self) -> Option<NotificationEvent> {
+				None
+			}
+
+			fn clone(&mut self) -> Result<Box<dyn NotificationService>, ()> {
+				unimplemented!();
+			}
+
+			fn protocol(&self) -> &ProtocolName {
+				unimplemented!();
+			}
+
+			fn message_sink(&self, _peer: &PeerId) -> Option<Box<dyn MessageSink>> {
+				unimplemented!();
+			}
+		}
+
+		let mut consensus =
+			ConsensusGossip::<Block>::new(Arc::new(ForcedOnly), "/foo".into(), None);
+		let sent = Arc::new(Mutex::new(Vec::new()));
+		let mut notification_service: Box<dyn NotificationService> =
+			Box::new(SentNotifications(sent.clone()));
+
+		let peer = PeerId::random();
+		let sentry = PeerId::random();
+		consensus.new_peer(&mut notification_service, peer, ObservedRole::Authority);
+		consensus.new_sentry_peer(&mut notification_service, sentry, ObservedRole::Authority);
+
+		let topic = H256::random();
+		consensus.multicast(&mut notification_service, topic, vec![1], false);
+		assert_eq!(*sent.lock().unwrap(), vec![sentry]);
+
+		consensus.register_message(topic, vec![2]);
+		consensus.send_topic(&mut notification_service, &peer, topic, false);
+		consensus.send_topic(&mut notification_service, &sentry, topic, false);
+		assert_eq!(*sent.lock().unwrap(), vec![sentry, sentry]);
+	}
 }
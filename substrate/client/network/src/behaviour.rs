@@ -23,7 +23,8 @@ use crate::{
 	peer_store::PeerStoreProvider,
 	protocol::{CustomMessageOutcome, NotificationsSink, Protocol},
 	protocol_controller::SetId,
-	request_responses::{self, IfDisconnected, ProtocolConfig, RequestFailure},
+	recorder::TrafficRecorder,
+	request_responses::{self, IfDisconnected, ProtocolConfig, RateLimiter, RequestFailure},
 	service::traits::Direction,
 	types::ProtocolName,
 	ReputationChange,
@@ -183,6 +184,8 @@ impl<B: BlockT> Behaviour<B> {
 		disco_config: DiscoveryConfig,
 		request_response_protocols: Vec<ProtocolConfig>,
 		peer_store_handle: Arc<dyn PeerStoreProvider>,
+		rate_limiter: RateLimiter,
+		recorder: TrafficRecorder,
 		external_addresses: Arc<Mutex<HashSet<Multiaddr>>>,
 		public_addresses: Vec<Multiaddr>,
 		connection_limits: ConnectionLimits,
@@ -199,6 +202,8 @@ impl<B: BlockT> Behaviour<B> {
 			request_responses: request_responses::RequestResponsesBehaviour::new(
 				request_response_protocols.into_iter(),
 				peer_store_handle,
+				rate_limiter,
+				recorder,
 			)?,
 			connection_limits: libp2p::connection_limits::Behaviour::new(connection_limits),
 		})
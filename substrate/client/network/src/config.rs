@@ -25,8 +25,9 @@ pub use crate::{
 	discovery::DEFAULT_KADEMLIA_REPLICATION_FACTOR,
 	peer_store::PeerStoreProvider,
 	protocol::{notification_service, NotificationsSink, ProtocolHandlePair},
+	recorder::TrafficRecorderConfig,
 	request_responses::{
-		IncomingRequest, OutgoingResponse, ProtocolConfig as RequestResponseConfig,
+		IncomingRequest, OutgoingResponse, ProtocolConfig as RequestResponseConfig, RateLimitConfig,
 	},
 	service::{
 		metrics::NotificationMetrics,
@@ -44,6 +45,7 @@ use sc_network_types::{
 use crate::service::{ensure_addresses_consistent_with_transport, traits::NetworkBackend};
 use codec::Encode;
 use prometheus_endpoint::Registry;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
 use zeroize::Zeroize;
 
 pub use sc_network_common::{
@@ -288,6 +290,94 @@ impl NonReservedPeerMode {
 	}
 }
 
+/// Position of the node in a sentry topology.
+///
+/// In a sentry topology, a validator hides behind a set of sentry nodes. The validator only
+/// connects to its sentries and is never advertised in the DHT, while the sentries keep the
+/// validator connected at all times and relay gossip on its behalf.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SentryTopology {
+	/// The node is not part of a sentry topology. This is the default.
+	#[default]
+	None,
+
+	/// The node is a validator protected by the given sentry nodes.
+	///
+	/// The sentries become the only reserved and bootstrap nodes, non-reserved peers are
+	/// refused, DHT random walks and mDNS are disabled and Kademlia runs in client mode.
+	///
+	/// Only supported by the libp2p network backend.
+	PrivateValidator {
+		/// Sentry nodes protecting the validator.
+		sentries: Vec<MultiaddrWithPeerId>,
+	},
+
+	/// The node is a sentry protecting the given validators.
+	///
+	/// The validators are added to the reserved nodes so that they are always connected and
+	/// don't occupy any slot.
+	Sentry {
+		/// Validators protected by the sentry.
+		validators: Vec<MultiaddrWithPeerId>,
+	},
+}
+
+impl SentryTopology {
+	/// Returns `true` if the node is a validator hidden behind sentry nodes.
+	pub fn is_private_validator(&self) -> bool {
+		matches!(self, SentryTopology::PrivateValidator { .. })
+	}
+
+	/// Nodes on the other side of the topology: the sentries of a private validator or the
+	/// validators of a sentry.
+	///
+	/// These peers are treated as authorities by the gossip protocols, regardless of the role
+	/// advertised in their handshake.
+	pub fn peers(&self) -> &[MultiaddrWithPeerId] {
+		match self {
+			SentryTopology::None => &[],
+			SentryTopology::PrivateValidator { sentries } => sentries,
+			SentryTopology::Sentry { validators } => validators,
+		}
+	}
+}
+
+/// TLS certificate used to accept connections on `/wss` listen addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketTlsConfig {
+	/// Path of the PEM encoded certificate chain, starting with the certificate of the node.
+	pub certificate_chain: PathBuf,
+
+	/// Path of the PEM encoded private key of the certificate.
+	pub private_key: PathBuf,
+}
+
+impl WebSocketTlsConfig {
+	/// Read the DER encoded certificate chain and private key.
+	pub(crate) fn load(
+		&self,
+	) -> io::Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+		let invalid_data = |path: &Path, error: &dyn fmt::Display| {
+			io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("Failed to read {}: {error}", path.display()),
+			)
+		};
+
+		let certificate_chain = CertificateDer::pem_file_iter(&self.certificate_chain)
+			.and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+			.map_err(|e| invalid_data(&self.certificate_chain, &e))?;
+		if certificate_chain.is_empty() {
+			return Err(invalid_data(&self.certificate_chain, &"no certificate found"))
+		}
+
+		let private_key = PrivateKeyDer::from_pem_file(&self.private_key)
+			.map_err(|e| invalid_data(&self.private_key, &e))?;
+
+		Ok((certificate_chain, private_key))
+	}
+}
+
 /// The configuration of a node's secret key, describing the type of key
 /// and how it is obtained. A node's identity keypair is the result of
 /// the evaluation of the node key configuration.
@@ -596,6 +686,11 @@ pub struct NetworkConfiguration {
 	/// Multiaddresses to advertise. Detected automatically if empty.
 	pub public_addresses: Vec<Multiaddr>,
 
+	/// Certificate used to terminate TLS on `/wss` listen addresses.
+	///
+	/// Listening on `/wss` addresses fails if it isn't set.
+	pub websocket_tls: Option<WebSocketTlsConfig>,
+
 	/// List of initial node addresses
 	pub boot_nodes: Vec<MultiaddrWithPeerId>,
 
@@ -650,6 +745,17 @@ pub struct NetworkConfiguration {
 	/// Enable serving block data over IPFS bitswap.
 	pub ipfs_server: bool,
 
+	/// Request rate limits and bandwidth quotas applied to incoming requests.
+	pub request_rate_limits: RateLimitConfig,
+
+	/// Record all notification and request-response traffic to disk.
+	///
+	/// See [`crate::recorder`] for details.
+	pub traffic_recorder: Option<TrafficRecorderConfig>,
+
+	/// Position of the node in a sentry topology.
+	pub sentry_topology: SentryTopology,
+
 	/// Networking backend used for P2P communication.
 	pub network_backend: Option<NetworkBackendType>,
 }
@@ -667,6 +773,7 @@ impl NetworkConfiguration {
 			net_config_path,
 			listen_addresses: Vec::new(),
 			public_addresses: Vec::new(),
+			websocket_tls: None,
 			boot_nodes: Vec::new(),
 			node_key,
 			default_peers_set_num_full: default_peers_set.in_peers + default_peers_set.out_peers,
@@ -683,10 +790,39 @@ impl NetworkConfiguration {
 			kademlia_replication_factor: NonZeroUsize::new(DEFAULT_KADEMLIA_REPLICATION_FACTOR)
 				.expect("value is a constant; constant is non-zero; qed."),
 			ipfs_server: false,
+			request_rate_limits: RateLimitConfig::default(),
+			traffic_recorder: None,
+			sentry_topology: SentryTopology::None,
 			network_backend: None,
 		}
 	}
 
+	/// Adjust the peer set and discovery settings to [`NetworkConfiguration::sentry_topology`].
+	fn apply_sentry_topology(&mut self) {
+		let mut add_reserved = |nodes: &[MultiaddrWithPeerId]| {
+			for node in nodes {
+				if !self.default_peers_set.reserved_nodes.contains(node) {
+					self.default_peers_set.reserved_nodes.push(node.clone());
+				}
+			}
+		};
+
+		match self.sentry_topology.clone() {
+			SentryTopology::None => {},
+			SentryTopology::PrivateValidator { sentries } => {
+				add_reserved(&sentries);
+				self.default_peers_set.non_reserved_mode = NonReservedPeerMode::Deny;
+				self.boot_nodes = sentries;
+				self.enable_dht_random_walk = false;
+
+				if let TransportConfig::Normal { ref mut enable_mdns, .. } = self.transport {
+					*enable_mdns = false;
+				}
+			},
+			SentryTopology::Sentry { validators } => add_reserved(&validators),
+		}
+	}
+
 	/// Create new default configuration for localhost-only connection with random port (useful for
 	/// testing)
 	pub fn new_local() -> NetworkConfiguration {
@@ -776,6 +912,9 @@ pub struct FullNetworkConfiguration<B: BlockT + 'static, H: ExHashT, N: NetworkB
 impl<B: BlockT + 'static, H: ExHashT, N: NetworkBackend<B, H>> FullNetworkConfiguration<B, H, N> {
 	/// Create new [`FullNetworkConfiguration`].
 	pub fn new(network_config: &NetworkConfiguration, metrics_registry: Option<Registry>) -> Self {
+		let mut network_config = network_config.clone();
+		network_config.apply_sentry_topology();
+
 		let bootnodes = network_config.boot_nodes.iter().map(|bootnode| bootnode.peer_id).collect();
 		let peer_store = N::peer_store(bootnodes, metrics_registry.clone());
 		let peer_store_handle = peer_store.handle();
@@ -785,7 +924,7 @@ impl<B: BlockT + 'static, H: ExHashT, N: NetworkBackend<B, H>> FullNetworkConfig
 			peer_store_handle,
 			notification_protocols: Vec::new(),
 			request_response_protocols: Vec::new(),
-			network_config: network_config.clone(),
+			network_config,
 			metrics_registry,
 		}
 	}
@@ -956,4 +1095,45 @@ mod tests {
 		let kp2 = NodeKeyConfig::Ed25519(Secret::New).into_keypair().unwrap();
 		assert!(secret_bytes(kp1) != secret_bytes(kp2));
 	}
+
+	fn node(port: u16) -> MultiaddrWithPeerId {
+		MultiaddrWithPeerId {
+			multiaddr: format!("/ip4/127.0.0.1/tcp/{port}").parse().unwrap(),
+			peer_id: PeerId::random(),
+		}
+	}
+
+	#[test]
+	fn private_validator_only_connects_to_sentries() {
+		let sentries = vec![node(1), node(2)];
+		let mut config = NetworkConfiguration::new_local();
+		config.boot_nodes = vec![node(3)];
+		config.default_peers_set.reserved_nodes = vec![sentries[0].clone()];
+		config.transport = TransportConfig::Normal { enable_mdns: true, allow_private_ip: true };
+		config.sentry_topology = SentryTopology::PrivateValidator { sentries: sentries.clone() };
+
+		config.apply_sentry_topology();
+
+		assert_eq!(config.default_peers_set.reserved_nodes, sentries);
+		assert!(config.default_peers_set.non_reserved_mode.is_reserved_only());
+		assert_eq!(config.boot_nodes, sentries);
+		assert!(!config.enable_dht_random_walk);
+		assert!(matches!(config.transport, TransportConfig::Normal { enable_mdns: false, .. }));
+	}
+
+	#[test]
+	fn sentry_reserves_validators() {
+		let validators = vec![node(1)];
+		let boot_nodes = vec![node(2)];
+		let mut config = NetworkConfiguration::new_local();
+		config.boot_nodes = boot_nodes.clone();
+		config.sentry_topology = SentryTopology::Sentry { validators: validators.clone() };
+
+		config.apply_sentry_topology();
+
+		assert_eq!(config.default_peers_set.reserved_nodes, validators);
+		assert_eq!(config.default_peers_set.non_reserved_mode, NonReservedPeerMode::Accept);
+		assert_eq!(config.boot_nodes, boot_nodes);
+		assert!(config.enable_dht_random_walk);
+	}
 }
@@ -116,6 +116,7 @@ pub struct DiscoveryConfig {
 	kademlia_protocol: Option<StreamProtocol>,
 	kademlia_legacy_protocol: Option<StreamProtocol>,
 	kademlia_replication_factor: NonZeroUsize,
+	kademlia_client_mode: bool,
 }
 
 impl DiscoveryConfig {
@@ -134,6 +135,7 @@ impl DiscoveryConfig {
 			kademlia_legacy_protocol: None,
 			kademlia_replication_factor: NonZeroUsize::new(DEFAULT_KADEMLIA_REPLICATION_FACTOR)
 				.expect("value is a constant; constant is non-zero; qed."),
+			kademlia_client_mode: false,
 		}
 	}
 
@@ -205,6 +207,15 @@ impl DiscoveryConfig {
 		self
 	}
 
+	/// Run Kademlia in client mode.
+	///
+	/// In client mode the node can query the DHT and publish records, but doesn't answer
+	/// Kademlia requests and is therefore never added to the routing tables of other nodes.
+	pub fn with_kademlia_client_mode(&mut self, value: bool) -> &mut Self {
+		self.kademlia_client_mode = value;
+		self
+	}
+
 	/// Create a `DiscoveryBehaviour` from this config.
 	pub fn finish(self) -> DiscoveryBehaviour {
 		let Self {
@@ -219,6 +230,7 @@ impl DiscoveryConfig {
 			kademlia_protocol,
 			kademlia_legacy_protocol: _,
 			kademlia_replication_factor,
+			kademlia_client_mode,
 		} = self;
 
 		let kademlia = if let Some(ref kademlia_protocol) = kademlia_protocol {
@@ -235,7 +247,11 @@ impl DiscoveryConfig {
 			config.disjoint_query_paths(kademlia_disjoint_query_paths);
 			let store = MemoryStore::new(local_peer_id);
 			let mut kad = Kademlia::with_config(local_peer_id, store, config);
-			kad.set_mode(Some(kad::Mode::Server));
+			kad.set_mode(Some(if kademlia_client_mode {
+				kad::Mode::Client
+			} else {
+				kad::Mode::Server
+			}));
 
 			for (peer_id, addr) in &permanent_addresses {
 				kad.add_address(peer_id, addr.clone());
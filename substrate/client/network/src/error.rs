@@ -62,6 +62,20 @@ pub enum Error {
 		/// The invalid addresses.
 		addresses: Vec<Multiaddr>,
 	},
+	/// The listen addresses are not supported by the network backend.
+	#[error("The following listen addresses are not supported ({reason}): {addresses:?}")]
+	UnsupportedListenAddresses {
+		/// Why the addresses are not supported.
+		reason: &'static str,
+		/// The unsupported addresses.
+		addresses: Vec<Multiaddr>,
+	},
+	/// The sentry topology is not supported by the network backend.
+	#[error("Running as a private validator behind sentry nodes is not supported: {reason}")]
+	UnsupportedSentryTopology {
+		/// Why the topology is not supported.
+		reason: &'static str,
+	},
 	/// The same request-response protocol has been registered multiple times.
 	#[error("Request-response protocol registered multiple times: {protocol}")]
 	DuplicateRequestResponseProtocol {
@@ -258,6 +258,7 @@ pub mod network_state;
 pub mod peer_info;
 pub mod peer_store;
 pub mod protocol_controller;
+pub mod recorder;
 pub mod request_responses;
 pub mod service;
 pub mod transport;
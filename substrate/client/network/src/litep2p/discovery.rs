@@ -303,7 +303,9 @@ impl Discovery {
 				address_confirmations: LruMap::new(ByLength::new(MAX_EXTERNAL_ADDRESSES)),
 				allow_non_global_addresses: config.allow_non_globals_in_dht,
 				public_addresses: config.public_addresses.iter().cloned().map(Into::into).collect(),
-				next_kad_query: Some(Delay::new(KADEMLIA_QUERY_INTERVAL)),
+				next_kad_query: config
+					.enable_dht_random_walk
+					.then(|| Delay::new(KADEMLIA_QUERY_INTERVAL)),
 				local_protocols: HashSet::from_iter([kademlia_protocol_name(
 					genesis_hash,
 					fork_id,
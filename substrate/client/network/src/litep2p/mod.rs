@@ -37,8 +37,11 @@ use crate::{
 			},
 			request_response::{RequestResponseConfig, RequestResponseProtocol},
 		},
+		tls::{RemoteAddresses, TlsTerminator},
 	},
 	peer_store::PeerStoreProvider,
+	recorder::TrafficRecorder,
+	request_responses::RateLimiter,
 	service::{
 		metrics::{register_without_sources, MetricSources, Metrics, NotificationMetrics},
 		out_events,
@@ -62,7 +65,7 @@ use litep2p::{
 		request_response::ConfigBuilder as RequestResponseConfigBuilder,
 	},
 	transport::{
-		tcp::config::Config as TcpTransportConfig,
+		tcp::config::Config as TcpTransportConfig, webrtc::config::Config as WebRtcTransportConfig,
 		websocket::config::Config as WebSocketTransportConfig, ConnectionLimitsConfig, Endpoint,
 	},
 	types::{
@@ -98,10 +101,27 @@ mod discovery;
 mod peerstore;
 mod service;
 mod shim;
+mod tls;
 
 /// Timeout for connection waiting new substreams.
 const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Transport used to listen on a configured listen address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListenTransport {
+	/// `/ip4/.../tcp/...`
+	Tcp,
+
+	/// `/ip4/.../tcp/.../ws` or `/ip4/.../tcp/.../wss`
+	WebSocket {
+		/// Whether the address is `/wss`.
+		secure: bool,
+	},
+
+	/// `/ip4/.../udp/.../webrtc-direct`
+	WebRtcDirect,
+}
+
 /// Litep2p bandwidth sink.
 struct Litep2pBandwidthSink {
 	sink: litep2p::BandwidthSink,
@@ -193,6 +213,12 @@ pub struct Litep2pNetworkBackend {
 
 	/// Prometheus metrics.
 	metrics: Option<Metrics>,
+
+	/// Traffic recorder, flushed when the backend is shut down.
+	recorder: TrafficRecorder,
+
+	/// Remote addresses of the peers connected over `/wss`.
+	wss_remote_addresses: RemoteAddresses,
 }
 
 impl Litep2pNetworkBackend {
@@ -267,73 +293,201 @@ impl Litep2pNetworkBackend {
 	}
 
 	/// Configure transport protocols for `Litep2pNetworkBackend`.
+	///
+	/// Returns the [`TlsTerminator`] of the `/wss` listen addresses, if any, which has to be
+	/// started once litep2p listens.
 	fn configure_transport<B: BlockT + 'static, H: ExHashT>(
 		config: &FullNetworkConfiguration<B, H, Self>,
-	) -> ConfigBuilder {
+	) -> Result<(ConfigBuilder, Option<TlsTerminator>), Error> {
 		let _ = match config.network_config.transport {
 			TransportConfig::MemoryOnly => panic!("memory transport not supported"),
 			TransportConfig::Normal { .. } => false,
 		};
 		let config_builder = ConfigBuilder::new();
 
-		let (tcp, websocket): (Vec<Option<_>>, Vec<Option<_>>) = config
-			.network_config
-			.listen_addresses
-			.iter()
-			.filter_map(|address| {
-				use sc_network_types::multiaddr::Protocol;
-
-				let mut iter = address.iter();
-
-				match iter.next() {
-					Some(Protocol::Ip4(_) | Protocol::Ip6(_)) => {},
-					protocol => {
-						log::error!(
-							target: LOG_TARGET,
-							"unknown protocol {protocol:?}, ignoring {address:?}",
-						);
-
-						return None
-					},
-				}
-
-				match iter.next() {
-					Some(Protocol::Tcp(_)) => match iter.next() {
-						Some(Protocol::Ws(_) | Protocol::Wss(_)) =>
-							Some((None, Some(address.clone()))),
-						Some(Protocol::P2p(_)) | None => Some((Some(address.clone()), None)),
-						protocol => {
-							log::error!(
-								target: LOG_TARGET,
-								"unknown protocol {protocol:?}, ignoring {address:?}",
-							);
-							None
-						},
-					},
-					protocol => {
-						log::error!(
-							target: LOG_TARGET,
-							"unknown protocol {protocol:?}, ignoring {address:?}",
-						);
-						None
-					},
-				}
-			})
-			.unzip();
+		let (tcp, mut websocket, secure_websocket, webrtc) =
+			Self::listen_addresses_by_transport(&config.network_config.listen_addresses)?;
+
+		// litep2p doesn't terminate TLS, so `/wss` connections are decrypted by the terminator
+		// and forwarded to an additional `/ws` listener on the loopback interface
+		let tls_terminator = match (&config.network_config.websocket_tls, secure_websocket) {
+			(_, secure_websocket) if secure_websocket.is_empty() => None,
+			(Some(tls), secure_websocket) => {
+				let tls_terminator = TlsTerminator::new(tls, &secure_websocket)?;
+				websocket.push(tls_terminator.upstream_listen_address());
+				Some(tls_terminator)
+			},
+			(None, secure_websocket) =>
+				return Err(Error::UnsupportedListenAddresses {
+					reason: "listening on `/wss` requires a TLS certificate",
+					addresses: secure_websocket.into_iter().map(Into::into).collect(),
+				}),
+		};
 
-		config_builder
+		let config_builder = config_builder
 			.with_websocket(WebSocketTransportConfig {
-				listen_addresses: websocket.into_iter().flatten().map(Into::into).collect(),
+				listen_addresses: websocket,
 				yamux_config: litep2p::yamux::Config::default(),
 				nodelay: true,
 				..Default::default()
 			})
 			.with_tcp(TcpTransportConfig {
-				listen_addresses: tcp.into_iter().flatten().map(Into::into).collect(),
+				listen_addresses: tcp,
 				yamux_config: litep2p::yamux::Config::default(),
 				nodelay: true,
 				..Default::default()
+			});
+
+		if webrtc.is_empty() {
+			return Ok((config_builder, tls_terminator))
+		}
+
+		let config_builder = config_builder
+			.with_webrtc(WebRtcTransportConfig { listen_addresses: webrtc, ..Default::default() });
+		Ok((config_builder, tls_terminator))
+	}
+
+	/// Split `addresses` into TCP, WebSocket, secure WebSocket and WebRTC listen addresses.
+	///
+	/// Fails when more than one WebRTC address is configured, as litep2p can only listen on one.
+	#[allow(clippy::type_complexity)]
+	fn listen_addresses_by_transport(
+		addresses: &[sc_network_types::multiaddr::Multiaddr],
+	) -> Result<(Vec<Multiaddr>, Vec<Multiaddr>, Vec<Multiaddr>, Vec<Multiaddr>), Error> {
+		let mut tcp = Vec::new();
+		let mut websocket = Vec::new();
+		let mut webrtc = Vec::new();
+		let mut secure_websocket = Vec::new();
+
+		for address in addresses {
+			match Self::listen_transport(address) {
+				Some(ListenTransport::Tcp) => tcp.push(address.clone().into()),
+				Some(ListenTransport::WebSocket { secure: false }) =>
+					websocket.push(address.clone().into()),
+				Some(ListenTransport::WebSocket { secure: true }) =>
+					secure_websocket.push(address.clone().into()),
+				Some(ListenTransport::WebRtcDirect) => webrtc.push(address.clone()),
+				None => {},
+			}
+		}
+
+		if webrtc.len() > 1 {
+			return Err(Error::UnsupportedListenAddresses {
+				reason: "litep2p supports only one WebRTC listen address",
+				addresses: webrtc,
+			})
+		}
+
+		Ok((tcp, websocket, secure_websocket, webrtc.into_iter().map(Into::into).collect()))
+	}
+
+	/// Get the transport `address` should be listened on, if any.
+	fn listen_transport(
+		address: &sc_network_types::multiaddr::Multiaddr,
+	) -> Option<ListenTransport> {
+		use sc_network_types::multiaddr::Protocol;
+
+		let mut iter = address.iter();
+
+		match iter.next() {
+			Some(Protocol::Ip4(_) | Protocol::Ip6(_)) => {},
+			protocol => {
+				log::error!(
+					target: LOG_TARGET,
+					"unknown protocol {protocol:?}, ignoring {address:?}",
+				);
+
+				return None
+			},
+		}
+
+		let transport = match (iter.next(), iter.next()) {
+			(Some(Protocol::Tcp(_)), Some(Protocol::Ws(_))) =>
+				ListenTransport::WebSocket { secure: false },
+			(Some(Protocol::Tcp(_)), Some(Protocol::Wss(_))) =>
+				ListenTransport::WebSocket { secure: true },
+			(Some(Protocol::Tcp(_)), Some(Protocol::P2p(_)) | None) => ListenTransport::Tcp,
+			(Some(Protocol::Udp(_)), Some(Protocol::WebRTC)) => ListenTransport::WebRtcDirect,
+			(_, protocol) => {
+				log::error!(
+					target: LOG_TARGET,
+					"unknown protocol {protocol:?}, ignoring {address:?}",
+				);
+
+				return None
+			},
+		};
+
+		match iter.next() {
+			Some(Protocol::P2p(_)) | None => Some(transport),
+			protocol => {
+				log::error!(
+					target: LOG_TARGET,
+					"unknown protocol {protocol:?}, ignoring {address:?}",
+				);
+
+				None
+			},
+		}
+	}
+
+	/// Get the certificate hash of the local WebRTC listener, if WebRTC is enabled.
+	fn webrtc_certhash(litep2p: &Litep2p) -> Option<Protocol<'static>> {
+		litep2p.listen_addresses().find_map(|address| {
+			address.iter().find_map(|protocol| match protocol {
+				Protocol::Certhash(hash) => Some(Protocol::Certhash(hash)),
+				_ => None,
 			})
+		})
+	}
+
+	/// Append the local WebRTC certificate hash to a WebRTC public address.
+	///
+	/// Browsers can't dial a `/webrtc-direct` address without knowing the hash of the
+	/// certificate and as the certificate is generated on startup, operators can't specify
+	/// it in the configured public addresses.
+	fn with_webrtc_certhash(address: Multiaddr, certhash: Option<&Protocol<'static>>) -> Multiaddr {
+		let Some(certhash) = certhash else { return address };
+
+		if !address.iter().any(|protocol| std::matches!(protocol, Protocol::WebRTC)) ||
+			address.iter().any(|protocol| std::matches!(protocol, Protocol::Certhash(_)))
+		{
+			return address
+		}
+
+		let mut iter = address.iter();
+		let mut with_certhash = Multiaddr::empty();
+
+		while let Some(protocol) = iter.next() {
+			let is_webrtc = std::matches!(protocol, Protocol::WebRTC);
+			with_certhash.push(protocol);
+
+			if is_webrtc {
+				with_certhash.push(certhash.clone());
+			}
+		}
+
+		with_certhash
+	}
+
+	/// Replace the loopback address of a connection forwarded by the [`TlsTerminator`] with the
+	/// `/wss` address of the remote peer.
+	fn resolve_wss_endpoint(&self, endpoint: Endpoint) -> Endpoint {
+		match endpoint {
+			Endpoint::Listener { address, connection_id } =>
+				match self.wss_remote_addresses.resolve(&address) {
+					Some(remote) => {
+						log::trace!(
+							target: LOG_TARGET,
+							"connection {connection_id:?} forwarded from {remote} over {address}",
+						);
+
+						Endpoint::Listener { address: remote, connection_id }
+					},
+					None => Endpoint::Listener { address, connection_id },
+				},
+			endpoint => endpoint,
+		}
 	}
 }
 
@@ -383,14 +537,26 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			fs::create_dir_all(path)?;
 		}
 
+		let recorder = match &params.network_config.network_config.traffic_recorder {
+			Some(config) => TrafficRecorder::new(config.clone())?,
+			None => TrafficRecorder::disabled(),
+		};
+
 		log::info!(target: LOG_TARGET, "Local node identity is: {local_peer_id}");
 		log::info!(target: LOG_TARGET, "Running litep2p network backend");
 
 		params.network_config.sanity_check_addresses()?;
 		params.network_config.sanity_check_bootnodes()?;
 
-		let mut config_builder =
-			Self::configure_transport(&params.network_config).with_keypair(keypair.clone());
+		// a private validator must not be added to the routing tables of other nodes
+		if params.network_config.network_config.sentry_topology.is_private_validator() {
+			return Err(Error::UnsupportedSentryTopology {
+				reason: "litep2p can't run Kademlia in client mode, use the libp2p backend",
+			})
+		}
+
+		let (config_builder, tls_terminator) = Self::configure_transport(&params.network_config)?;
+		let mut config_builder = config_builder.with_keypair(keypair.clone());
 		let known_addresses = params.network_config.known_addresses();
 		let peer_store_handle = params.network_config.peer_store_handle();
 		let executor = Arc::new(Litep2pExecutor { executor: params.executor });
@@ -408,6 +574,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 		// to the protocol's `Peerset` together with the protocol name to allow other subsystems
 		// of Polkadot SDK to control connectivity of the notification protocol
 		let block_announce_protocol = params.block_announce_config.protocol_name().clone();
+		params.block_announce_config.handle.recorder.set(recorder.clone());
 		let mut notif_protocols = HashMap::from_iter([(
 			params.block_announce_config.protocol_name().clone(),
 			params.block_announce_config.handle,
@@ -418,6 +585,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			.into_iter()
 			.fold(config_builder, |config_builder, mut config| {
 				config.config.set_handshake(Roles::from(&params.role).encode());
+				config.handle.recorder.set(recorder.clone());
 				notif_protocols.insert(config.protocol_name, config.handle);
 
 				config_builder.with_notification_protocol(config.config)
@@ -430,6 +598,12 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			None => None,
 		};
 
+		// request rate limits and bandwidth quotas are shared by all request-response protocols
+		let rate_limiter = RateLimiter::new(
+			network_config.request_rate_limits.clone(),
+			params.metrics_registry.as_ref(),
+		)?;
+
 		// create channels that are used to send request before initializing protocols so the
 		// senders can be passed onto all request-response protocols
 		//
@@ -467,6 +641,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 						.expect("receiver exists as it was just added and there are no duplicate protocols; qed"),
 					request_response_senders.clone(),
 					metrics.clone(),
+					rate_limiter.clone(),
+					recorder.clone(),
 				);
 
 				executor.run(Box::pin(async move {
@@ -483,8 +659,13 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 				use sc_network_types::multiaddr::Protocol;
 
 				let address = match address.iter().last() {
-					Some(Protocol::Ws(_) | Protocol::Wss(_) | Protocol::Tcp(_)) =>
-						address.with(Protocol::P2p(peer.into())),
+					Some(
+						Protocol::Ws(_) |
+						Protocol::Wss(_) |
+						Protocol::Tcp(_) |
+						Protocol::WebRTC |
+						Protocol::Certhash(_),
+					) => address.with(Protocol::P2p(peer.into())),
 					Some(Protocol::P2p(_)) => address,
 					_ => return acc,
 				};
@@ -520,7 +701,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			// This has the same effect as `libp2p::Swarm::with_idle_connection_timeout` which is
 			// set to 10 seconds as well.
 			.with_keep_alive_timeout(KEEP_ALIVE_TIMEOUT)
-			.with_executor(executor);
+			.with_executor(Arc::clone(&executor));
 
 		if let Some(config) = maybe_mdns_config {
 			config_builder = config_builder.with_mdns(config);
@@ -533,15 +714,56 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 		let litep2p =
 			Litep2p::new(config_builder.build()).map_err(|error| Error::Litep2p(error))?;
 
+		let mut wss_listen_addresses = Vec::new();
+		let mut wss_remote_addresses = RemoteAddresses::default();
+		let tls_upstream = match tls_terminator {
+			Some(tls_terminator) => {
+				let upstream = tls_terminator.upstream();
+				if !litep2p
+					.listen_addresses()
+					.any(|address| TlsTerminator::is_upstream(address, upstream))
+				{
+					return Err(std::io::Error::new(
+						std::io::ErrorKind::AddrNotAvailable,
+						"litep2p doesn't listen on the loopback address reserved for `/wss`",
+					)
+					.into())
+				}
+
+				tls_terminator.listen_addresses().for_each(|address| {
+					log::debug!(target: LOG_TARGET, "listening on: {address}");
+
+					listen_addresses.write().insert(address.clone());
+					wss_listen_addresses.push(address.clone());
+				});
+				wss_remote_addresses = tls_terminator.remote_addresses();
+				tls_terminator.run(executor);
+
+				Some(upstream)
+			},
+			None => None,
+		};
+
 		litep2p.listen_addresses().for_each(|address| {
 			log::debug!(target: LOG_TARGET, "listening on: {address}");
 
+			// the `/ws` listener of the TLS terminator is only reachable locally
+			let is_tls_upstream = tls_upstream
+				.is_some_and(|upstream| TlsTerminator::is_upstream(address, upstream));
+			if is_tls_upstream {
+				return
+			}
+
 			listen_addresses.write().insert(address.clone());
 		});
 
 		let public_addresses = litep2p.public_addresses();
+		let webrtc_certhash = Self::webrtc_certhash(&litep2p);
 		for address in network_config.public_addresses.iter() {
-			if let Err(err) = public_addresses.add_address(address.clone().into()) {
+			let address =
+				Self::with_webrtc_certhash(address.clone().into(), webrtc_certhash.as_ref());
+
+			if let Err(err) = public_addresses.add_address(address.clone()) {
 				log::warn!(
 					target: LOG_TARGET,
 					"failed to add public address {address:?}: {err:?}",
@@ -549,6 +771,32 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			}
 		}
 
+		// litep2p doesn't know about the `/wss` listeners, so it doesn't advertise them
+		for address in wss_listen_addresses {
+			let is_unspecified = address.iter().next().is_some_and(|protocol| match protocol {
+				Protocol::Ip4(ip) => ip.is_unspecified(),
+				Protocol::Ip6(ip) => ip.is_unspecified(),
+				_ => false,
+			});
+			if is_unspecified {
+				log::debug!(
+					target: LOG_TARGET,
+					"not advertising {address}, use `--public-addr` to advertise a `/wss` address",
+				);
+				continue
+			}
+
+			if let Err(err) = public_addresses.add_address(address.clone()) {
+				log::warn!(
+					target: LOG_TARGET,
+					"failed to add public address {address:?}: {err:?}",
+				);
+			}
+		}
+
+		let sentry_peers =
+			network_config.sentry_topology.peers().iter().map(|node| node.peer_id).collect();
+
 		let network_service = Arc::new(Litep2pNetworkService::new(
 			local_peer_id,
 			keypair.clone(),
@@ -559,6 +807,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			request_response_senders,
 			Arc::clone(&listen_addresses),
 			public_addresses,
+			sentry_peers,
 		));
 
 		// register rest of the metrics now that `Litep2p` has been created
@@ -583,6 +832,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 			event_streams: out_events::OutChannels::new(None)?,
 			peers: HashMap::new(),
 			litep2p,
+			recorder,
+			wss_remote_addresses,
 		})
 	}
 
@@ -661,7 +912,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 
 			tokio::select! {
 				command = self.cmd_rx.next() => match command {
-					None => return,
+					None => break,
 					Some(command) => match command {
 						NetworkServiceCommand::GetValue{ key } => {
 							let query_id = self.discovery.get_value(key.clone()).await;
@@ -776,7 +1027,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 					}
 				},
 				event = self.discovery.next() => match event {
-					None => return,
+					None => break,
 					Some(DiscoveryEvent::Discovered { addresses }) => {
 						// if at least one address was added for the peer, report the peer to `Peerstore`
 						for (peer, addresses) in Litep2pNetworkBackend::parse_addresses(addresses.into_iter()) {
@@ -970,7 +1221,10 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 						self.discovery.add_self_reported_address(peer, supported_protocols, listen_addresses).await;
 					}
 					Some(DiscoveryEvent::ExternalAddressDiscovered { address }) => {
-						match self.litep2p.public_addresses().add_address(address.clone().into()) {
+						let certhash = Self::webrtc_certhash(&self.litep2p);
+						let address = Self::with_webrtc_certhash(address, certhash.as_ref());
+
+						match self.litep2p.public_addresses().add_address(address.clone()) {
 							Ok(inserted) => if inserted {
 								log::info!(target: LOG_TARGET, "🔍 Discovered new external address for our node: {address}");
 							},
@@ -984,6 +1238,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 					}
 					Some(DiscoveryEvent::ExternalAddressExpired{ address }) => {
 						let local_peer_id = self.litep2p.local_peer_id();
+						let certhash = Self::webrtc_certhash(&self.litep2p);
+						let address = Self::with_webrtc_certhash(address, certhash.as_ref());
 
 						// Litep2p requires the peer ID to be present in the address.
 						let address = if !std::matches!(address.iter().last(), Some(Protocol::P2p(_))) {
@@ -1026,6 +1282,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 				},
 				event = self.litep2p.next_event() => match event {
 					Some(Litep2pEvent::ConnectionEstablished { peer, endpoint }) => {
+						let endpoint = self.resolve_wss_endpoint(endpoint);
 						let Some(metrics) = &self.metrics else {
 							continue;
 						};
@@ -1134,10 +1391,107 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for Litep2pNetworkBac
 								target: LOG_TARGET,
 								"Litep2p backend terminated"
 						);
-						return
+						break
 					}
 				},
 			}
 		}
+
+		self.recorder.flush();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ListenTransport, Litep2pNetworkBackend};
+	use crate::error::Error;
+	use litep2p::types::{
+		multiaddr::{Multiaddr, Protocol},
+		multihash::Multihash,
+	};
+
+	fn listen_transport(address: &str) -> Option<ListenTransport> {
+		Litep2pNetworkBackend::listen_transport(&address.parse().unwrap())
+	}
+
+	#[test]
+	fn listen_addresses_are_assigned_to_transports() {
+		assert_eq!(listen_transport("/ip4/0.0.0.0/tcp/30333"), Some(ListenTransport::Tcp));
+		assert_eq!(
+			listen_transport("/ip6/::/tcp/30333/ws"),
+			Some(ListenTransport::WebSocket { secure: false }),
+		);
+		assert_eq!(
+			listen_transport("/ip4/0.0.0.0/tcp/443/wss"),
+			Some(ListenTransport::WebSocket { secure: true }),
+		);
+		assert_eq!(
+			listen_transport("/ip4/0.0.0.0/udp/30333/webrtc-direct"),
+			Some(ListenTransport::WebRtcDirect),
+		);
+		assert_eq!(listen_transport("/ip4/0.0.0.0/udp/30333"), None);
+		assert_eq!(listen_transport("/ip4/0.0.0.0/udp/30333/quic-v1"), None);
+		assert_eq!(listen_transport("/dns/example.com/tcp/30333"), None);
+	}
+
+	#[test]
+	fn unsupported_listen_addresses_are_rejected() {
+		let addresses = |addresses: &[&str]| {
+			addresses.iter().map(|address| address.parse().unwrap()).collect::<Vec<_>>()
+		};
+
+		let (tcp, websocket, secure_websocket, webrtc) =
+			Litep2pNetworkBackend::listen_addresses_by_transport(&addresses(&[
+				"/ip4/0.0.0.0/tcp/30333",
+				"/ip4/0.0.0.0/tcp/30334/ws",
+				"/ip4/0.0.0.0/tcp/443/wss",
+				"/ip4/0.0.0.0/udp/30333/webrtc-direct",
+			]))
+			.unwrap();
+		assert_eq!(
+			(tcp.len(), websocket.len(), secure_websocket.len(), webrtc.len()),
+			(1, 1, 1, 1),
+		);
+		assert_eq!(secure_websocket[0], "/ip4/0.0.0.0/tcp/443/wss".parse().unwrap());
+		assert!(matches!(
+			Litep2pNetworkBackend::listen_addresses_by_transport(&addresses(&[
+				"/ip4/0.0.0.0/udp/30333/webrtc-direct",
+				"/ip6/::/udp/30333/webrtc-direct",
+			])),
+			Err(Error::UnsupportedListenAddresses { .. }),
+		));
+	}
+
+	#[test]
+	fn certhash_is_added_to_webrtc_addresses() {
+		let certhash = Protocol::Certhash(Multihash::wrap(0x12, &[1u8; 32]).unwrap());
+
+		let address: Multiaddr = "/dns/example.com/udp/30333/webrtc-direct".parse().unwrap();
+		let expected = address.clone().with(certhash.clone());
+		assert_eq!(
+			Litep2pNetworkBackend::with_webrtc_certhash(address.clone(), Some(&certhash)),
+			expected,
+		);
+
+		// certificate hash is inserted before the peer ID
+		let peer = litep2p::PeerId::random();
+		assert_eq!(
+			Litep2pNetworkBackend::with_webrtc_certhash(
+				address.clone().with(Protocol::P2p(peer.into())),
+				Some(&certhash),
+			),
+			expected.clone().with(Protocol::P2p(peer.into())),
+		);
+
+		// addresses which already have a certificate hash are left as-is
+		assert_eq!(
+			Litep2pNetworkBackend::with_webrtc_certhash(expected.clone(), Some(&certhash)),
+			expected,
+		);
+
+		// non-WebRTC addresses and disabled WebRTC
+		let tcp: Multiaddr = "/ip4/1.2.3.4/tcp/30333".parse().unwrap();
+		assert_eq!(Litep2pNetworkBackend::with_webrtc_certhash(tcp.clone(), Some(&certhash)), tcp);
+		assert_eq!(Litep2pNetworkBackend::with_webrtc_certhash(address.clone(), None), address);
 	}
 }
@@ -209,6 +209,9 @@ pub struct Litep2pNetworkService {
 
 	/// External addresses.
 	external_addresses: PublicAddresses,
+
+	/// Peers on the other side of the sentry topology, reported as authorities.
+	sentry_peers: HashSet<PeerId>,
 }
 
 impl Litep2pNetworkService {
@@ -223,6 +226,7 @@ impl Litep2pNetworkService {
 		request_response_protocols: HashMap<ProtocolName, TracingUnboundedSender<OutboundRequest>>,
 		listen_addresses: Arc<RwLock<HashSet<LiteP2pMultiaddr>>>,
 		external_addresses: PublicAddresses,
+		sentry_peers: HashSet<PeerId>,
 	) -> Self {
 		Self {
 			local_peer_id,
@@ -234,6 +238,7 @@ impl Litep2pNetworkService {
 			request_response_protocols,
 			listen_addresses,
 			external_addresses,
+			sentry_peers,
 		}
 	}
 }
@@ -390,6 +395,16 @@ impl NetworkPeers for Litep2pNetworkService {
 	}
 
 	fn report_peer(&self, peer: PeerId, cost_benefit: ReputationChange) {
+		// Sentry peers must stay connected, they are never banned.
+		if cost_benefit.value < 0 && self.sentry_peers.contains(&peer) {
+			log::trace!(
+				target: LOG_TARGET,
+				"Ignoring reputation change of sentry peer {peer}: {}",
+				cost_benefit.reason,
+			);
+			return
+		}
+
 		self.peer_store_handle.report_peer(peer, cost_benefit);
 	}
 
@@ -471,6 +486,10 @@ impl NetworkPeers for Litep2pNetworkService {
 	}
 
 	fn peer_role(&self, peer: PeerId, handshake: Vec<u8>) -> Option<ObservedRole> {
+		if self.sentry_peers.contains(&peer) {
+			return Some(ObservedRole::Authority)
+		}
+
 		match Roles::decode_all(&mut &handshake[..]) {
 			Ok(role) => Some(role.into()),
 			Err(_) => {
@@ -480,6 +499,10 @@ impl NetworkPeers for Litep2pNetworkService {
 		}
 	}
 
+	fn is_sentry_peer(&self, peer: PeerId) -> bool {
+		self.sentry_peers.contains(&peer)
+	}
+
 	/// Get the list of reserved peers.
 	///
 	/// Returns an error if the `NetworkWorker` is no longer running.
@@ -25,6 +25,7 @@ use crate::{
 		NotificationProtocol,
 	},
 	peer_store::PeerStoreProvider,
+	recorder::LateTrafficRecorder,
 	service::{metrics::NotificationMetrics, traits::NotificationConfig},
 	NotificationService, ProtocolName,
 };
@@ -43,6 +44,9 @@ pub struct ProtocolControlHandle {
 
 	/// Peers currently connected to this protocol.
 	pub connected_peers: Arc<AtomicUsize>,
+
+	/// Slot for the traffic recorder of the network backend the protocol is registered with.
+	pub recorder: LateTrafficRecorder,
 }
 
 impl ProtocolControlHandle {
@@ -50,8 +54,9 @@ impl ProtocolControlHandle {
 	pub fn new(
 		tx: TracingUnboundedSender<PeersetCommand>,
 		connected_peers: Arc<AtomicUsize>,
+		recorder: LateTrafficRecorder,
 	) -> Self {
-		Self { tx, connected_peers }
+		Self { tx, connected_peers, recorder }
 	}
 }
 
@@ -114,7 +119,14 @@ impl NotificationProtocolConfig {
 		// initialize the actual object implementing `NotificationService` and combine the
 		// `litep2p::NotificationHandle` with `Peerset` to implement a full and independent
 		// notification protocol runner
-		let protocol = NotificationProtocol::new(protocol_name.clone(), handle, peerset, metrics);
+		let recorder = LateTrafficRecorder::default();
+		let protocol = NotificationProtocol::new(
+			protocol_name.clone(),
+			handle,
+			peerset,
+			metrics,
+			recorder.clone(),
+		);
 
 		(
 			Self {
@@ -122,7 +134,7 @@ impl NotificationProtocolConfig {
 				max_notification_size,
 				set_config,
 				config,
-				handle: ProtocolControlHandle::new(peerset_tx, connected_peers),
+				handle: ProtocolControlHandle::new(peerset_tx, connected_peers, recorder),
 			},
 			Box::new(protocol),
 		)
@@ -22,6 +22,7 @@
 use crate::{
 	error::Error,
 	litep2p::shim::notification::peerset::{OpenResult, Peerset, PeersetNotificationCommand},
+	recorder::{self, LateTrafficRecorder, MessageKind},
 	service::{
 		metrics::NotificationMetrics,
 		traits::{NotificationEvent as SubstrateNotificationEvent, ValidationResult},
@@ -38,7 +39,10 @@ use tokio::sync::oneshot;
 
 use sc_network_types::PeerId;
 
-use std::{collections::HashSet, fmt};
+use std::{
+	collections::{HashMap, HashSet},
+	fmt,
+};
 
 pub mod config;
 pub mod peerset;
@@ -62,6 +66,9 @@ pub struct Litep2pMessageSink {
 
 	/// Notification metrics.
 	metrics: NotificationMetrics,
+
+	/// Traffic recorder.
+	recorder: LateTrafficRecorder,
 }
 
 impl Litep2pMessageSink {
@@ -71,8 +78,9 @@ impl Litep2pMessageSink {
 		protocol: ProtocolName,
 		sink: NotificationSink,
 		metrics: NotificationMetrics,
+		recorder: LateTrafficRecorder,
 	) -> Self {
-		Self { protocol, peer, sink, metrics }
+		Self { protocol, peer, sink, metrics, recorder }
 	}
 }
 
@@ -81,6 +89,13 @@ impl MessageSink for Litep2pMessageSink {
 	/// Send synchronous `notification` to the peer associated with this [`MessageSink`].
 	fn send_sync_notification(&self, notification: Vec<u8>) {
 		let size = notification.len();
+		self.recorder.record(
+			&self.protocol,
+			&self.peer,
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 
 		match self.sink.send_sync_notification(notification) {
 			Ok(_) => self.metrics.register_notification_sent(&self.protocol, size),
@@ -99,6 +114,13 @@ impl MessageSink for Litep2pMessageSink {
 	/// Returns an error if the peer does not exist.
 	async fn send_async_notification(&self, notification: Vec<u8>) -> Result<(), Error> {
 		let size = notification.len();
+		self.recorder.record(
+			&self.protocol,
+			&self.peer,
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 
 		match self.sink.send_async_notification(notification).await {
 			Ok(_) => {
@@ -142,6 +164,12 @@ pub struct NotificationProtocol {
 
 	/// Notification metrics.
 	metrics: NotificationMetrics,
+
+	/// Traffic recorder, handed over by the network backend.
+	recorder: LateTrafficRecorder,
+
+	/// Directions of the open substreams, tracked for the recorder.
+	substream_directions: HashMap<PeerId, recorder::Direction>,
 }
 
 impl fmt::Debug for NotificationProtocol {
@@ -160,12 +188,15 @@ impl NotificationProtocol {
 		handle: NotificationHandle,
 		peerset: Peerset,
 		metrics: NotificationMetrics,
+		recorder: LateTrafficRecorder,
 	) -> Self {
 		Self {
 			protocol,
 			handle,
 			peerset,
 			metrics,
+			recorder,
+			substream_directions: HashMap::new(),
 			pending_cancels: HashSet::new(),
 			pending_validations: FuturesUnordered::new(),
 		}
@@ -200,6 +231,13 @@ impl NotificationService for NotificationProtocol {
 
 	fn send_sync_notification(&mut self, peer: &PeerId, notification: Vec<u8>) {
 		let size = notification.len();
+		self.recorder.record(
+			&self.protocol,
+			peer,
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 
 		if let Ok(_) = self.handle.send_sync_notification(peer.into(), notification) {
 			self.metrics.register_notification_sent(&self.protocol, size);
@@ -212,6 +250,13 @@ impl NotificationService for NotificationProtocol {
 		notification: Vec<u8>,
 	) -> Result<(), Error> {
 		let size = notification.len();
+		self.recorder.record(
+			&self.protocol,
+			peer,
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 
 		match self.handle.send_async_notification(peer.into(), notification).await {
 			Ok(_) => {
@@ -258,6 +303,7 @@ impl NotificationService for NotificationProtocol {
 				self.protocol.clone(),
 				sink,
 				self.metrics.clone(),
+				self.recorder.clone(),
 			));
 			sink
 		})
@@ -305,6 +351,16 @@ impl NotificationService for NotificationProtocol {
 							}
 							OpenResult::Accept { direction } => {
 								log::trace!(target: LOG_TARGET, "{}: substream opened for {peer:?}", self.protocol);
+								if self.recorder.is_enabled() {
+									self.substream_directions.insert(peer.into(), direction.into());
+								}
+								self.recorder.record(
+									&self.protocol,
+									&peer.into(),
+									direction.into(),
+									MessageKind::SubstreamOpened,
+									&handshake,
+								);
 
 								return Some(SubstrateNotificationEvent::NotificationStreamOpened {
 									peer: peer.into(),
@@ -332,6 +388,17 @@ impl NotificationService for NotificationProtocol {
 							continue
 						}
 
+						let direction = self.substream_directions.remove(&PeerId::from(peer));
+						if let Some(direction) = direction {
+							self.recorder.record(
+								&self.protocol,
+								&peer.into(),
+								direction,
+								MessageKind::SubstreamClosed,
+								&[],
+							);
+						}
+
 						return Some(SubstrateNotificationEvent::NotificationStreamClosed { peer: peer.into() })
 					}
 					NotificationEvent::NotificationStreamOpenFailure {
@@ -348,6 +415,14 @@ impl NotificationService for NotificationProtocol {
 						self.metrics.register_notification_received(&self.protocol, notification.len());
 
 						if !self.pending_cancels.contains(&peer) {
+							self.recorder.record(
+								&self.protocol,
+								&peer.into(),
+								recorder::Direction::Inbound,
+								MessageKind::Notification,
+								&notification,
+							);
+
 							return Some(SubstrateNotificationEvent::NotificationReceived {
 								peer: peer.into(),
 								notification: notification.to_vec(),
@@ -22,7 +22,8 @@
 use crate::{
 	litep2p::shim::request_response::metrics::RequestResponseMetrics,
 	peer_store::PeerStoreProvider,
-	request_responses::{IncomingRequest, OutgoingResponse},
+	recorder::{self, MessageKind, TrafficRecorder},
+	request_responses::{IncomingRequest, OutgoingResponse, RateLimiter},
 	service::{metrics::Metrics, traits::RequestResponseConfig as RequestResponseConfigT},
 	IfDisconnected, OutboundFailure, ProtocolName, RequestFailure,
 };
@@ -196,6 +197,12 @@ pub struct RequestResponseProtocol {
 
 	/// Metrics, if enabled.
 	metrics: RequestResponseMetrics,
+
+	/// Request rate limits and bandwidth quotas of inbound requests.
+	rate_limiter: RateLimiter,
+
+	/// Traffic recorder.
+	recorder: TrafficRecorder,
 }
 
 impl RequestResponseProtocol {
@@ -208,6 +215,8 @@ impl RequestResponseProtocol {
 		request_rx: TracingUnboundedReceiver<OutboundRequest>,
 		request_tx: HashMap<ProtocolName, TracingUnboundedSender<OutboundRequest>>,
 		metrics: Option<Metrics>,
+		rate_limiter: RateLimiter,
+		recorder: TrafficRecorder,
 	) -> Self {
 		Self {
 			handle,
@@ -219,6 +228,8 @@ impl RequestResponseProtocol {
 			pending_inbound_responses: HashMap::new(),
 			pending_outbound_responses: FuturesUnordered::new(),
 			metrics: RequestResponseMetrics::new(metrics, protocol),
+			rate_limiter,
+			recorder,
 		}
 	}
 
@@ -245,6 +256,14 @@ impl RequestResponseProtocol {
 			dial_options,
 		);
 
+		self.recorder.record(
+			&self.protocol,
+			&peer,
+			recorder::Direction::Outbound,
+			MessageKind::Request,
+			&request,
+		);
+
 		match self.handle.try_send_request(peer.into(), request, dial_options) {
 			Ok(request_id) => {
 				self.pending_inbound_responses
@@ -303,6 +322,35 @@ impl RequestResponseProtocol {
 			return;
 		}
 
+		if let Err(throttled) =
+			self.rate_limiter.check_inbound(&self.protocol, peer.into(), request.len())
+		{
+			log::debug!(
+				target: LOG_TARGET,
+				"{}: rejecting inbound request from {peer:?} ({request_id:?}): {}",
+				self.protocol,
+				throttled.reason(),
+			);
+
+			if let Some(change) = throttled.reputation_change() {
+				self.peerstore_handle.report_peer(peer.into(), change);
+			}
+
+			// There is no busy status in the protocol, the remote observes the closed substream as
+			// a refused request.
+			self.handle.reject_request(request_id);
+			self.metrics.register_inbound_request_failure(throttled.reason());
+			return;
+		}
+
+		self.recorder.record(
+			&self.protocol,
+			&peer.into(),
+			recorder::Direction::Inbound,
+			MessageKind::Request,
+			&request,
+		);
+
 		let (tx, rx) = oneshot::channel();
 
 		match inbound_queue.try_send(IncomingRequest {
@@ -349,6 +397,13 @@ impl RequestResponseProtocol {
 					self.protocol,
 					response.len(),
 				);
+				self.recorder.record(
+					&self.protocol,
+					&peer.into(),
+					recorder::Direction::Inbound,
+					MessageKind::Response,
+					&response,
+				);
 
 				let _ = tx.send(Ok((response, self.protocol.clone())));
 				self.metrics.register_outbound_request_success(started.elapsed());
@@ -512,6 +567,15 @@ impl RequestResponseProtocol {
 					response.len(),
 				);
 
+				self.rate_limiter.on_response_sent(&self.protocol, response.len());
+				self.recorder.record(
+					&self.protocol,
+					&peer.into(),
+					recorder::Direction::Outbound,
+					MessageKind::Response,
+					&response,
+				);
+
 				match sent_feedback {
 					None => self.handle.send_response(request_id, response),
 					Some(feedback) =>
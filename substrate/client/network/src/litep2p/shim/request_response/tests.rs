@@ -21,7 +21,8 @@ use crate::{
 		peerstore::peerstore_handle_test,
 		shim::request_response::{OutboundRequest, RequestResponseProtocol},
 	},
-	request_responses::{IfDisconnected, IncomingRequest, OutgoingResponse},
+	recorder::TrafficRecorder,
+	request_responses::{IfDisconnected, IncomingRequest, OutgoingResponse, RateLimiter},
 	ProtocolName, RequestFailure,
 };
 
@@ -110,6 +111,8 @@ async fn dial_failure() {
 		outbound_rx,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol.run());
@@ -146,6 +149,8 @@ async fn send_request_to_disconnected_peer() {
 		outbound_rx,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol.run());
@@ -192,6 +197,8 @@ async fn send_request_to_disconnected_peer_and_dial() {
 		outbound_rx1,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (outbound_tx2, outbound_rx2) = tracing_unbounded("outbound-request", 1000);
@@ -206,6 +213,8 @@ async fn send_request_to_disconnected_peer_and_dial() {
 		outbound_rx2,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol1.run());
@@ -253,6 +262,8 @@ async fn too_many_inbound_requests() {
 		outbound_rx,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol.run());
@@ -311,6 +322,8 @@ async fn feedback_works() {
 		outbound_rx,
 		senders,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol.run());
@@ -428,6 +441,8 @@ async fn fallback_request_compatible_peers() {
 		outbound_rx1,
 		senders1.clone(),
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (tx_fallback, _rx_fallback) = async_channel::bounded(4);
@@ -439,6 +454,8 @@ async fn fallback_request_compatible_peers() {
 		outbound_rx_fallback,
 		senders1,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (outbound_tx2, outbound_rx2) = tracing_unbounded("outbound-request", 1000);
@@ -453,6 +470,8 @@ async fn fallback_request_compatible_peers() {
 		outbound_rx2,
 		senders2,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol1.run());
@@ -570,6 +589,8 @@ async fn fallback_request_old_peer_receives() {
 		outbound_rx1,
 		senders1.clone(),
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (tx_fallback, _rx_fallback) = async_channel::bounded(4);
@@ -581,6 +602,8 @@ async fn fallback_request_old_peer_receives() {
 		outbound_rx_fallback,
 		senders1,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (outbound_tx2, outbound_rx2) = tracing_unbounded("outbound-request", 1000);
@@ -595,6 +618,8 @@ async fn fallback_request_old_peer_receives() {
 		outbound_rx2,
 		senders2,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol1.run());
@@ -712,6 +737,8 @@ async fn fallback_request_old_peer_sends() {
 		outbound_rx1,
 		senders1.clone(),
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (tx_fallback, rx_fallback) = async_channel::bounded(4);
@@ -723,6 +750,8 @@ async fn fallback_request_old_peer_sends() {
 		outbound_rx_fallback,
 		senders1,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (outbound_tx2, outbound_rx2) = tracing_unbounded("outbound-request", 1000);
@@ -737,6 +766,8 @@ async fn fallback_request_old_peer_sends() {
 		outbound_rx2,
 		senders2,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol1.run());
@@ -853,6 +884,8 @@ async fn old_protocol_supported_but_no_fallback_provided() {
 		outbound_rx1,
 		senders1.clone(),
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (tx_fallback, _rx_fallback) = async_channel::bounded(4);
@@ -864,6 +897,8 @@ async fn old_protocol_supported_but_no_fallback_provided() {
 		outbound_rx_fallback,
 		senders1,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	let (outbound_tx2, outbound_rx2) = tracing_unbounded("outbound-request", 1000);
@@ -878,6 +913,8 @@ async fn old_protocol_supported_but_no_fallback_provided() {
 		outbound_rx2,
 		senders2,
 		None,
+		RateLimiter::unlimited(),
+		TrafficRecorder::disabled(),
 	);
 
 	tokio::spawn(protocol1.run());
@@ -20,6 +20,7 @@ use crate::{
 	config, error,
 	peer_store::PeerStoreProvider,
 	protocol_controller::{self, SetId},
+	recorder::TrafficRecorder,
 	service::{metrics::NotificationMetrics, traits::Direction},
 	types::ProtocolName,
 };
@@ -77,6 +78,7 @@ impl<B: BlockT> Protocol<B> {
 	pub(crate) fn new(
 		roles: Roles,
 		notification_metrics: NotificationMetrics,
+		recorder: TrafficRecorder,
 		notification_protocols: Vec<config::NonDefaultSetConfig>,
 		block_announces_protocol: config::NonDefaultSetConfig,
 		peer_store_handle: Arc<dyn PeerStoreProvider>,
@@ -120,6 +122,7 @@ impl<B: BlockT> Protocol<B> {
 
 			handles.iter_mut().for_each(|handle| {
 				handle.set_metrics(notification_metrics.clone());
+				handle.set_recorder(recorder.clone());
 			});
 
 			protocol_configs.iter().enumerate().for_each(|(i, (p, _, _))| {
@@ -131,6 +134,7 @@ impl<B: BlockT> Protocol<B> {
 					protocol_controller_handles,
 					from_protocol_controllers,
 					notification_metrics,
+					recorder,
 					protocol_configs.into_iter(),
 				),
 				installed_protocols,
@@ -24,6 +24,7 @@ use crate::{
 		service::{NotificationCommand, ProtocolHandle, ValidationCallResult},
 	},
 	protocol_controller::{self, IncomingIndex, Message, SetId},
+	recorder::TrafficRecorder,
 	service::{
 		metrics::NotificationMetrics,
 		traits::{Direction, ValidationResult},
@@ -174,6 +175,9 @@ pub struct Notifications {
 
 	/// Metrics for notifications.
 	metrics: NotificationMetrics,
+
+	/// Traffic recorder.
+	recorder: TrafficRecorder,
 }
 
 /// Configuration for a notifications protocol.
@@ -417,6 +421,7 @@ impl Notifications {
 		protocol_controller_handles: Vec<protocol_controller::ProtocolHandle>,
 		from_protocol_controllers: TracingUnboundedReceiver<Message>,
 		metrics: NotificationMetrics,
+		recorder: TrafficRecorder,
 		notif_protocols: impl Iterator<
 			Item = (
 				ProtocolConfig,
@@ -445,6 +450,7 @@ impl Notifications {
 			.enumerate()
 			.map(|(set_id, (mut protocol_handle, command_stream))| {
 				protocol_handle.set_metrics(metrics.clone());
+				protocol_handle.set_recorder(recorder.clone());
 
 				(protocol_handle, (set_id, command_stream))
 			})
@@ -468,6 +474,7 @@ impl Notifications {
 			events: VecDeque::new(),
 			pending_inbound_validations: FuturesUnordered::new(),
 			metrics,
+			recorder,
 		}
 	}
 
@@ -1238,7 +1245,12 @@ impl NetworkBehaviour for Notifications {
 		_local_addr: &Multiaddr,
 		_remote_addr: &Multiaddr,
 	) -> Result<THandler<Self>, ConnectionDenied> {
-		Ok(NotifsHandler::new(peer, self.notif_protocols.clone(), Some(self.metrics.clone())))
+		Ok(NotifsHandler::new(
+			peer,
+			self.notif_protocols.clone(),
+			Some(self.metrics.clone()),
+			self.recorder.clone(),
+		))
 	}
 
 	fn handle_established_outbound_connection(
@@ -1249,7 +1261,12 @@ impl NetworkBehaviour for Notifications {
 		_role_override: Endpoint,
 		_port_use: PortUse,
 	) -> Result<THandler<Self>, ConnectionDenied> {
-		Ok(NotifsHandler::new(peer, self.notif_protocols.clone(), Some(self.metrics.clone())))
+		Ok(NotifsHandler::new(
+			peer,
+			self.notif_protocols.clone(),
+			Some(self.metrics.clone()),
+			self.recorder.clone(),
+		))
 	}
 
 	fn on_swarm_event(&mut self, event: FromSwarm) {
@@ -2449,6 +2466,7 @@ mod tests {
 				vec![handle],
 				from_controller,
 				NotificationMetrics::new(None),
+				TrafficRecorder::disabled(),
 				iter::once((
 					ProtocolConfig {
 						name: "/foo".into(),
@@ -62,6 +62,7 @@ use crate::{
 		NotificationsIn, NotificationsInSubstream, NotificationsOut, NotificationsOutError,
 		NotificationsOutSubstream, UpgradeCollec,
 	},
+	recorder::TrafficRecorder,
 	service::metrics::NotificationMetrics,
 	types::ProtocolName,
 };
@@ -131,6 +132,9 @@ pub struct NotifsHandler {
 
 	/// Metrics.
 	metrics: Option<Arc<NotificationMetrics>>,
+
+	/// Traffic recorder.
+	recorder: TrafficRecorder,
 }
 
 impl NotifsHandler {
@@ -139,6 +143,7 @@ impl NotifsHandler {
 		peer_id: PeerId,
 		protocols: Vec<ProtocolConfig>,
 		metrics: Option<NotificationMetrics>,
+		recorder: TrafficRecorder,
 	) -> Self {
 		Self {
 			protocols: protocols
@@ -162,6 +167,7 @@ impl NotifsHandler {
 			keep_alive_timeout_future: Some(Box::pin(tokio::time::sleep(INITIAL_KEEPALIVE_TIME))),
 			events_queue: VecDeque::with_capacity(16),
 			metrics: metrics.map_or(None, |metrics| Some(Arc::new(metrics))),
+			recorder,
 		}
 	}
 }
@@ -373,6 +379,7 @@ pub enum NotifsHandlerOut {
 pub struct NotificationsSink {
 	inner: Arc<NotificationsSinkInner>,
 	metrics: Option<Arc<NotificationMetrics>>,
+	recorder: TrafficRecorder,
 }
 
 impl NotificationsSink {
@@ -392,6 +399,7 @@ impl NotificationsSink {
 					sync_channel: Mutex::new(Some(sync_tx)),
 				}),
 				metrics: None,
+				recorder: TrafficRecorder::disabled(),
 			},
 			async_rx,
 			sync_rx,
@@ -402,6 +410,11 @@ impl NotificationsSink {
 	pub fn metrics(&self) -> &Option<Arc<NotificationMetrics>> {
 		&self.metrics
 	}
+
+	/// Get reference to the traffic recorder.
+	pub fn recorder(&self) -> &TrafficRecorder {
+		&self.recorder
+	}
 }
 
 #[derive(Debug)]
@@ -598,6 +611,7 @@ impl ConnectionHandler for NotifsHandler {
 								sync_channel: Mutex::new(Some(sync_tx)),
 							}),
 							metrics: self.metrics.clone(),
+							recorder: self.recorder.clone(),
 						};
 
 						self.protocols[protocol_index].state = State::Open {
@@ -973,6 +987,7 @@ pub mod tests {
 					sync_channel: Mutex::new(Some(sync_tx)),
 				}),
 				metrics: None,
+				recorder: TrafficRecorder::disabled(),
 			};
 			let (in_substream, out_substream) = MockSubstream::new();
 
@@ -1144,6 +1159,7 @@ pub mod tests {
 				max_notification_size: u64::MAX,
 			}],
 			None,
+			TrafficRecorder::disabled(),
 		)
 	}
 
@@ -1656,6 +1672,7 @@ pub mod tests {
 				sync_channel: Mutex::new(Some(sync_tx)),
 			}),
 			metrics: None,
+			recorder: TrafficRecorder::disabled(),
 		};
 
 		handler.protocols[0].state = State::Open {
@@ -21,6 +21,7 @@
 use crate::{
 	error,
 	protocol::notifications::handler::NotificationsSink,
+	recorder::{self, MessageKind, TrafficRecorder},
 	service::{
 		metrics::NotificationMetrics,
 		traits::{
@@ -70,6 +71,13 @@ impl MessageSink for NotificationSink {
 		let sink = self.lock();
 
 		metrics::register_notification_sent(sink.0.metrics(), &sink.1, notification.len());
+		sink.0.recorder().record(
+			&sink.1,
+			&(*sink.0.peer_id()).into(),
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 		sink.0.send_sync_notification(notification);
 	}
 
@@ -89,6 +97,13 @@ impl MessageSink for NotificationSink {
 			.await
 			.map_err(|_| error::Error::ConnectionClosed)?;
 
+		sink.0.recorder().record(
+			&sink.1,
+			&(*sink.0.peer_id()).into(),
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
 		permit.send(notification).map_err(|_| error::Error::ChannelClosed).inspect(|_| {
 			metrics::register_notification_sent(sink.0.metrics(), &sink.1, notification_len);
 		})
@@ -239,6 +254,13 @@ impl NotificationService for NotificationHandle {
 				&self.protocol,
 				notification.len(),
 			);
+			info.sink.recorder().record(
+				&self.protocol,
+				peer,
+				recorder::Direction::Outbound,
+				MessageKind::Notification,
+				&notification,
+			);
 
 			let _ = info.sink.send_sync_notification(notification);
 		}
@@ -257,9 +279,17 @@ impl NotificationService for NotificationHandle {
 			.ok_or_else(|| error::Error::PeerDoesntExist((*peer).into()))?
 			.sink;
 
-		sink.reserve_notification()
-			.await
-			.map_err(|_| error::Error::ConnectionClosed)?
+		let permit =
+			sink.reserve_notification().await.map_err(|_| error::Error::ConnectionClosed)?;
+
+		sink.recorder().record(
+			&self.protocol,
+			peer,
+			recorder::Direction::Outbound,
+			MessageKind::Notification,
+			&notification,
+		);
+		permit
 			.send(notification)
 			.map_err(|_| error::Error::ChannelClosed)
 			.inspect(|_| {
@@ -426,6 +456,12 @@ pub(crate) struct ProtocolHandle {
 
 	/// Prometheus metrics.
 	metrics: Option<NotificationMetrics>,
+
+	/// Traffic recorder.
+	recorder: TrafficRecorder,
+
+	/// Directions of the open substreams, recorded when they are closed.
+	substream_directions: HashMap<PeerId, Direction>,
 }
 
 pub(crate) enum ValidationCallResult {
@@ -436,7 +472,15 @@ pub(crate) enum ValidationCallResult {
 impl ProtocolHandle {
 	/// Create new [`ProtocolHandle`].
 	fn new(protocol: ProtocolName, subscribers: Subscribers) -> Self {
-		Self { protocol, subscribers, num_peers: 0usize, metrics: None, delegate_to_peerset: false }
+		Self {
+			protocol,
+			subscribers,
+			num_peers: 0usize,
+			metrics: None,
+			recorder: TrafficRecorder::disabled(),
+			substream_directions: HashMap::new(),
+			delegate_to_peerset: false,
+		}
 	}
 
 	/// Set metrics.
@@ -444,6 +488,11 @@ impl ProtocolHandle {
 		self.metrics = Some(metrics);
 	}
 
+	/// Set traffic recorder.
+	pub fn set_recorder(&mut self, recorder: TrafficRecorder) {
+		self.recorder = recorder;
+	}
+
 	/// Delegate validation to `Peerset`.
 	///
 	/// Protocols that do not do any validation themselves and only rely on `Peerset` handling
@@ -534,6 +583,16 @@ impl ProtocolHandle {
 		sink: NotificationsSink,
 	) -> Result<(), ()> {
 		metrics::register_substream_opened(&self.metrics, &self.protocol);
+		self.recorder.record(
+			&self.protocol,
+			&peer.into(),
+			direction.into(),
+			MessageKind::SubstreamOpened,
+			&handshake,
+		);
+		if self.recorder.is_enabled() {
+			self.substream_directions.insert(peer, direction);
+		}
 
 		let mut subscribers = self.subscribers.lock();
 		log::trace!(target: LOG_TARGET, "{}: substream opened for {peer:?}", self.protocol);
@@ -557,6 +616,15 @@ impl ProtocolHandle {
 	/// Substream was closed.
 	pub fn report_substream_closed(&mut self, peer: PeerId) -> Result<(), ()> {
 		metrics::register_substream_closed(&self.metrics, &self.protocol);
+		if let Some(direction) = self.substream_directions.remove(&peer) {
+			self.recorder.record(
+				&self.protocol,
+				&peer.into(),
+				direction.into(),
+				MessageKind::SubstreamClosed,
+				&[],
+			);
+		}
 
 		let mut subscribers = self.subscribers.lock();
 		log::trace!(target: LOG_TARGET, "{}: substream closed for {peer:?}", self.protocol);
@@ -578,6 +646,13 @@ impl ProtocolHandle {
 		notification: Vec<u8>,
 	) -> Result<(), ()> {
 		metrics::register_notification_received(&self.metrics, &self.protocol, notification.len());
+		self.recorder.record(
+			&self.protocol,
+			&peer.into(),
+			recorder::Direction::Inbound,
+			MessageKind::Notification,
+			&notification,
+		);
 
 		let mut subscribers = self.subscribers.lock();
 		log::trace!(target: LOG_TARGET, "{}: notification received from {peer:?}", self.protocol);
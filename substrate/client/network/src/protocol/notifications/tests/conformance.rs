@@ -22,6 +22,7 @@ use crate::{
 		service::notification_service, Notifications, NotificationsOut, ProtocolConfig,
 	},
 	protocol_controller::{ProtoSetConfig, ProtocolController, SetId},
+	recorder::TrafficRecorder,
 	service::metrics::NotificationMetrics,
 	NotificationService,
 };
@@ -81,6 +82,7 @@ fn setup_libp2p(
 		vec![controller_handle],
 		from_controller,
 		NotificationMetrics::new(None),
+		TrafficRecorder::disabled(),
 		iter::once((
 			ProtocolConfig {
 				name: "/foo".into(),
@@ -92,7 +94,7 @@ fn setup_libp2p(
 			command_stream,
 		)),
 	);
-	let transport = crate::transport::build_transport(local_key.clone().into(), false);
+	let transport = crate::transport::build_transport(local_key.clone().into(), false, None);
 
 	let mut swarm = {
 		struct SpawnImpl {}
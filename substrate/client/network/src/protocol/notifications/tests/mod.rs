@@ -22,6 +22,7 @@ use crate::{
 	peer_store::PeerStore,
 	protocol::notifications::{Notifications, NotificationsOut, ProtocolConfig},
 	protocol_controller::{ProtoSetConfig, ProtocolController, SetId},
+	recorder::TrafficRecorder,
 	service::{
 		metrics::NotificationMetrics,
 		traits::{NotificationEvent, ValidationResult},
@@ -120,6 +121,7 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 					vec![controller_handle],
 					from_controller,
 					NotificationMetrics::new(None),
+					TrafficRecorder::disabled(),
 					iter::once((
 						ProtocolConfig {
 							name: "/foo".into(),
@@ -0,0 +1,659 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Network traffic recorder.
+//!
+//! When enabled through [`TrafficRecorderConfig`], every notification and request-response
+//! message exchanged by the node is appended to a set of rotating files in the configured
+//! directory. The resulting recording can be read back with [`Recording`] and fed to protocol
+//! handlers, which allows turning an incident observed on a live network into a reproducible
+//! test case.
+//!
+//! Each network backend owns a [`TrafficRecorder`] and hands it to its protocols. If recording is
+//! disabled, the recorder is a no-op and the payloads are never copied.
+//!
+//! Each file is a sequence of frames, each frame being a little-endian `u32` length followed by
+//! a SCALE-encoded [`RecordedMessage`]. The file currently being written to is
+//! `traffic.0.rec`; on rotation, older files are shifted to `traffic.1.rec`, `traffic.2.rec`
+//! and so on, and the oldest file is removed once [`TrafficRecorderConfig::max_files`] is
+//! reached. Files are also rotated when the recorder is started, so restarting the node doesn't
+//! overwrite the previous recording.
+//!
+//! Messages are handed over to a dedicated writer thread through a bounded channel, so recording
+//! never blocks the network on disk I/O. If the writer thread falls behind and the channel is
+//! full, messages are dropped from the recording. The writer thread flushes buffered frames to
+//! disk periodically and when the last handle to the recorder is dropped.
+
+use crate::{service::traits, types::ProtocolName};
+
+use codec::{Decode, Encode};
+use sc_network_types::PeerId;
+
+use std::{
+	fs::{self, File},
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::{Path, PathBuf},
+	sync::{
+		mpsc::{self, RecvTimeoutError, SyncSender, TrySendError},
+		Arc, OnceLock,
+	},
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "sub-libp2p::recorder";
+
+/// How often buffered frames are flushed to disk.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of messages waiting to be written by the writer thread.
+const CHANNEL_SIZE: usize = 8192;
+
+/// Upper bound for the size of a single frame when reading a recording back.
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+/// Configuration of the network traffic recorder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrafficRecorderConfig {
+	/// Directory where the recording files are written.
+	pub path: PathBuf,
+
+	/// Size in bytes after which the current file is rotated.
+	pub max_file_size: u64,
+
+	/// Maximum number of files kept in [`TrafficRecorderConfig::path`], including the one
+	/// currently being written to.
+	pub max_files: usize,
+}
+
+impl TrafficRecorderConfig {
+	/// Create a new configuration writing to `path` with the default rotation settings.
+	pub fn new(path: PathBuf) -> Self {
+		Self { path, max_file_size: 64 * 1024 * 1024, max_files: 8 }
+	}
+}
+
+/// Direction of a recorded message, from the point of view of the local node.
+///
+/// For [`MessageKind::SubstreamOpened`] and [`MessageKind::SubstreamClosed`], this is the direction
+/// of the substream instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum Direction {
+	/// Message was received from the remote peer.
+	Inbound,
+
+	/// Message was sent to the remote peer.
+	Outbound,
+}
+
+impl From<traits::Direction> for Direction {
+	fn from(direction: traits::Direction) -> Self {
+		match direction {
+			traits::Direction::Inbound => Direction::Inbound,
+			traits::Direction::Outbound => Direction::Outbound,
+		}
+	}
+}
+
+impl From<Direction> for traits::Direction {
+	fn from(direction: Direction) -> Self {
+		match direction {
+			Direction::Inbound => traits::Direction::Inbound,
+			Direction::Outbound => traits::Direction::Outbound,
+		}
+	}
+}
+
+/// Kind of a recorded message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum MessageKind {
+	/// Notification substream was opened, the payload is the remote handshake.
+	SubstreamOpened,
+
+	/// Notification substream was closed, the payload is empty.
+	SubstreamClosed,
+
+	/// Notification.
+	Notification,
+
+	/// Request of a request-response protocol.
+	Request,
+
+	/// Response of a request-response protocol.
+	Response,
+}
+
+/// Single message of a recording.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RecordedMessage {
+	/// Milliseconds since the UNIX epoch at which the message was recorded.
+	pub timestamp: u64,
+
+	/// Protocol name.
+	pub protocol: String,
+
+	/// Encoded peer ID of the remote.
+	pub peer: Vec<u8>,
+
+	/// Direction of the message.
+	pub direction: Direction,
+
+	/// Kind of the message.
+	pub kind: MessageKind,
+
+	/// Message payload.
+	pub payload: Vec<u8>,
+}
+
+impl RecordedMessage {
+	/// Decode the peer ID of the remote.
+	pub fn peer_id(&self) -> Option<PeerId> {
+		PeerId::from_bytes(&self.peer).ok()
+	}
+}
+
+/// Rotating writer of recorded messages.
+struct Writer {
+	config: TrafficRecorderConfig,
+	file: BufWriter<File>,
+	written: u64,
+}
+
+impl Writer {
+	fn new(config: TrafficRecorderConfig) -> io::Result<Self> {
+		fs::create_dir_all(&config.path)?;
+
+		// Keep the recording of a previous run.
+		if file_path(&config.path, 0).exists() {
+			shift_files(&config)?;
+		}
+		let file = BufWriter::new(File::create(file_path(&config.path, 0))?);
+
+		Ok(Self { config, file, written: 0 })
+	}
+
+	fn write(&mut self, message: &RecordedMessage) -> io::Result<()> {
+		let encoded = message.encode();
+
+		if self.written > 0 && self.written + encoded.len() as u64 + 4 > self.config.max_file_size {
+			self.rotate()?;
+		}
+
+		self.file.write_all(&(encoded.len() as u32).to_le_bytes())?;
+		self.file.write_all(&encoded)?;
+		self.written += encoded.len() as u64 + 4;
+
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		self.file.flush()?;
+		shift_files(&self.config)?;
+
+		self.file = BufWriter::new(File::create(file_path(&self.config.path, 0))?);
+		self.written = 0;
+
+		Ok(())
+	}
+}
+
+impl Drop for Writer {
+	fn drop(&mut self) {
+		let _ = self.file.flush();
+	}
+}
+
+/// Path of the recording file with the given rotation `index`.
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+	dir.join(format!("traffic.{index}.rec"))
+}
+
+/// Shift all recording files by one rotation index, removing the oldest one if needed.
+fn shift_files(config: &TrafficRecorderConfig) -> io::Result<()> {
+	let max_files = config.max_files.max(1);
+	let _ = fs::remove_file(file_path(&config.path, max_files - 1));
+	for index in (0..max_files - 1).rev() {
+		let from = file_path(&config.path, index);
+		if from.exists() {
+			fs::rename(from, file_path(&config.path, index + 1))?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Command sent to the writer thread.
+enum Command {
+	/// Append a message to the recording.
+	Record(RecordedMessage),
+
+	/// Flush buffered frames to disk and acknowledge on the given channel.
+	Flush(SyncSender<()>),
+}
+
+/// Write the messages received on `rx` until all senders are dropped.
+///
+/// Buffered frames are flushed every [`FLUSH_INTERVAL`], even if messages keep arriving.
+fn run_writer(mut writer: Writer, rx: mpsc::Receiver<Command>) {
+	let flush = |writer: &mut Writer| {
+		if let Err(error) = writer.file.flush() {
+			log::warn!(target: LOG_TARGET, "failed to flush network traffic: {error:?}");
+		}
+	};
+	let mut last_flush = Instant::now();
+
+	loop {
+		match rx.recv_timeout(FLUSH_INTERVAL.saturating_sub(last_flush.elapsed())) {
+			Ok(Command::Record(message)) =>
+				if let Err(error) = writer.write(&message) {
+					log::warn!(target: LOG_TARGET, "failed to record network traffic: {error:?}");
+				},
+			Ok(Command::Flush(ack)) => {
+				flush(&mut writer);
+				last_flush = Instant::now();
+				let _ = ack.send(());
+			},
+			Err(RecvTimeoutError::Timeout) => {},
+			// The writer is flushed when dropped.
+			Err(RecvTimeoutError::Disconnected) => return,
+		}
+
+		if last_flush.elapsed() >= FLUSH_INTERVAL {
+			flush(&mut writer);
+			last_flush = Instant::now();
+		}
+	}
+}
+
+/// Handle to the writer thread, shared by all clones of a [`TrafficRecorder`].
+struct Inner {
+	/// Sending half of the writer thread's channel.
+	tx: Option<SyncSender<Command>>,
+
+	/// Writer thread, joined when the last handle is dropped.
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for Inner {
+	fn drop(&mut self) {
+		// Disconnect the channel and wait for the remaining messages to be written, so a recorder
+		// created right afterwards for the same directory doesn't race with this one.
+		self.tx.take();
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Traffic recorder of a network backend.
+///
+/// Cloning the [`TrafficRecorder`] returns a handle to the same recording.
+#[derive(Clone, Default)]
+pub struct TrafficRecorder {
+	inner: Option<Arc<Inner>>,
+}
+
+impl TrafficRecorder {
+	/// Create new [`TrafficRecorder`] writing to the directory of `config`.
+	pub fn new(config: TrafficRecorderConfig) -> io::Result<Self> {
+		let path = config.path.clone();
+		let writer = Writer::new(config)?;
+		let (tx, rx) = mpsc::sync_channel(CHANNEL_SIZE);
+		let thread = thread::Builder::new()
+			.name("traffic-recorder".into())
+			.spawn(move || run_writer(writer, rx))?;
+
+		log::info!(target: LOG_TARGET, "📼 Recording network traffic to {}", path.display());
+
+		Ok(Self { inner: Some(Arc::new(Inner { tx: Some(tx), thread: Some(thread) })) })
+	}
+
+	/// Create [`TrafficRecorder`] which doesn't record anything.
+	pub fn disabled() -> Self {
+		Self { inner: None }
+	}
+
+	/// Returns `true` if the traffic is recorded.
+	pub fn is_enabled(&self) -> bool {
+		self.inner.is_some()
+	}
+
+	/// Get the sending half of the writer thread's channel, if the recorder is enabled.
+	fn sender(&self) -> Option<&SyncSender<Command>> {
+		self.inner.as_ref().and_then(|inner| inner.tx.as_ref())
+	}
+
+	/// Flush buffered messages to disk, waiting for the writer thread to catch up.
+	pub fn flush(&self) {
+		let Some(tx) = self.sender() else { return };
+		let (ack_tx, ack_rx) = mpsc::sync_channel(1);
+
+		if tx.send(Command::Flush(ack_tx)).is_ok() {
+			let _ = ack_rx.recv();
+		}
+	}
+
+	/// Record a message exchanged with `peer`.
+	///
+	/// `payload` is only copied if the recorder is enabled. The message is dropped from the
+	/// recording if the writer thread has fallen behind.
+	pub fn record(
+		&self,
+		protocol: &ProtocolName,
+		peer: &PeerId,
+		direction: Direction,
+		kind: MessageKind,
+		payload: &[u8],
+	) {
+		let Some(tx) = self.sender() else { return };
+
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |duration| duration.as_millis() as u64);
+		let message = RecordedMessage {
+			timestamp,
+			protocol: protocol.to_string(),
+			peer: peer.to_bytes(),
+			direction,
+			kind,
+			payload: payload.to_vec(),
+		};
+
+		match tx.try_send(Command::Record(message)) {
+			Ok(()) => {},
+			Err(TrySendError::Full(_)) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"writer is falling behind, dropping {protocol} message",
+				);
+			},
+			Err(TrySendError::Disconnected(_)) => {
+				log::warn!(target: LOG_TARGET, "writer thread terminated, message not recorded");
+			},
+		}
+	}
+}
+
+impl std::fmt::Debug for TrafficRecorder {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TrafficRecorder").field("enabled", &self.is_enabled()).finish()
+	}
+}
+
+/// [`TrafficRecorder`] handed over by the network backend after the protocol has been created.
+///
+/// Notification services are created before the network backend they are registered with, so
+/// they can't be given the backend's recorder on construction.
+#[derive(Clone, Debug, Default)]
+pub struct LateTrafficRecorder(Arc<OnceLock<TrafficRecorder>>);
+
+impl LateTrafficRecorder {
+	/// Hand over the recorder of the network backend.
+	///
+	/// A protocol is registered with a single backend, so only the first call has an effect.
+	pub fn set(&self, recorder: TrafficRecorder) {
+		let _ = self.0.set(recorder);
+	}
+
+	/// Returns `true` if an enabled recorder has been handed over.
+	pub fn is_enabled(&self) -> bool {
+		self.0.get().map_or(false, TrafficRecorder::is_enabled)
+	}
+
+	/// Record a message exchanged with `peer`, if the recorder has been handed over.
+	pub fn record(
+		&self,
+		protocol: &ProtocolName,
+		peer: &PeerId,
+		direction: Direction,
+		kind: MessageKind,
+		payload: &[u8],
+	) {
+		if let Some(recorder) = self.0.get() {
+			recorder.record(protocol, peer, direction, kind, payload);
+		}
+	}
+}
+
+/// Recording read back from disk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+	/// Recorded messages, in the order they were recorded.
+	pub messages: Vec<RecordedMessage>,
+}
+
+impl Recording {
+	/// Read all files of the recording in directory `dir`, oldest first.
+	pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+		let dir = dir.as_ref();
+		let mut index = 0;
+		while file_path(dir, index).exists() {
+			index += 1;
+		}
+
+		let mut messages = Vec::new();
+		for index in (0..index).rev() {
+			messages.extend(Self::from_file(file_path(dir, index))?.messages);
+		}
+
+		Ok(Self { messages })
+	}
+
+	/// Read a single recording file.
+	///
+	/// A truncated trailing frame, which is left behind if the node is stopped while writing, is
+	/// ignored.
+	pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+		let mut reader = BufReader::new(File::open(path)?);
+		let mut messages = Vec::new();
+
+		loop {
+			let mut len = [0u8; 4];
+			match reader.read_exact(&mut len) {
+				Ok(()) => {},
+				Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+				Err(error) => return Err(error),
+			}
+
+			let len = u32::from_le_bytes(len) as usize;
+			if len > MAX_FRAME_SIZE {
+				return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"))
+			}
+
+			let mut frame = vec![0u8; len];
+			match reader.read_exact(&mut frame) {
+				Ok(()) => {},
+				Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+				Err(error) => return Err(error),
+			}
+
+			let message = RecordedMessage::decode(&mut &frame[..])
+				.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+			messages.push(message);
+		}
+
+		Ok(Self { messages })
+	}
+
+	/// Iterate over the messages recorded for `protocol`.
+	pub fn protocol<'a>(
+		&'a self,
+		protocol: &'a str,
+	) -> impl Iterator<Item = &'a RecordedMessage> + 'a {
+		self.messages.iter().filter(move |message| message.protocol == protocol)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn message(index: u8) -> RecordedMessage {
+		RecordedMessage {
+			timestamp: index as u64,
+			protocol: "/foo/1".into(),
+			peer: PeerId::random().to_bytes(),
+			direction: Direction::Inbound,
+			kind: MessageKind::Notification,
+			payload: vec![index; 100],
+		}
+	}
+
+	#[test]
+	fn recording_roundtrip() {
+		let dir = tempfile::tempdir().unwrap();
+		let mut writer = Writer::new(TrafficRecorderConfig::new(dir.path().into())).unwrap();
+		let messages = (0..10).map(message).collect::<Vec<_>>();
+
+		for message in &messages {
+			writer.write(message).unwrap();
+		}
+		drop(writer);
+
+		assert_eq!(Recording::from_dir(dir.path()).unwrap().messages, messages);
+	}
+
+	#[test]
+	fn files_are_rotated() {
+		let dir = tempfile::tempdir().unwrap();
+		let config =
+			TrafficRecorderConfig { path: dir.path().into(), max_file_size: 500, max_files: 3 };
+		let mut writer = Writer::new(config).unwrap();
+		let messages = (0..20).map(message).collect::<Vec<_>>();
+
+		for message in &messages {
+			writer.write(message).unwrap();
+		}
+		drop(writer);
+
+		assert!(file_path(dir.path(), 2).exists());
+		assert!(!file_path(dir.path(), 3).exists());
+
+		// Only the most recent messages are kept, in order.
+		let recording = Recording::from_dir(dir.path()).unwrap();
+		assert!(recording.messages.len() < messages.len());
+		assert!(messages.ends_with(&recording.messages));
+	}
+
+	#[test]
+	fn truncated_frame_is_ignored() {
+		let dir = tempfile::tempdir().unwrap();
+		let mut writer = Writer::new(TrafficRecorderConfig::new(dir.path().into())).unwrap();
+		let message = message(1);
+		writer.write(&message).unwrap();
+		drop(writer);
+
+		let path = file_path(dir.path(), 0);
+		let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+		file.write_all(&100u32.to_le_bytes()).unwrap();
+		file.write_all(&[0u8; 10]).unwrap();
+
+		assert_eq!(Recording::from_file(path).unwrap().messages, vec![message]);
+	}
+
+	#[test]
+	fn previous_recording_is_kept_on_restart() {
+		let dir = tempfile::tempdir().unwrap();
+		let messages = (0..4).map(message).collect::<Vec<_>>();
+
+		for messages in messages.chunks(2) {
+			let recorder = TrafficRecorder::new(TrafficRecorderConfig::new(dir.path().into()))
+				.unwrap();
+			for message in messages {
+				let peer = message.peer_id().unwrap();
+				recorder.record(
+					&"/foo/1".into(),
+					&peer,
+					message.direction,
+					message.kind,
+					&message.payload,
+				);
+			}
+		}
+
+		assert!(file_path(dir.path(), 1).exists());
+
+		let recording = Recording::from_dir(dir.path()).unwrap();
+		assert_eq!(
+			recording.messages.iter().map(|message| &message.payload).collect::<Vec<_>>(),
+			messages.iter().map(|message| &message.payload).collect::<Vec<_>>(),
+		);
+	}
+
+	#[test]
+	fn recorder_is_flushed_periodically() {
+		let dir = tempfile::tempdir().unwrap();
+		let recorder = TrafficRecorder::new(TrafficRecorderConfig::new(dir.path().into())).unwrap();
+		let message = message(1);
+
+		recorder.record(
+			&"/foo/1".into(),
+			&message.peer_id().unwrap(),
+			message.direction,
+			message.kind,
+			&message.payload,
+		);
+		assert!(Recording::from_dir(dir.path()).unwrap().messages.is_empty());
+
+		std::thread::sleep(FLUSH_INTERVAL * 2);
+		assert_eq!(Recording::from_dir(dir.path()).unwrap().messages.len(), 1);
+	}
+
+	#[test]
+	fn flush_waits_for_pending_messages() {
+		let dir = tempfile::tempdir().unwrap();
+		let recorder = TrafficRecorder::new(TrafficRecorderConfig::new(dir.path().into())).unwrap();
+		let messages = (0..100).map(message).collect::<Vec<_>>();
+
+		for message in &messages {
+			recorder.record(
+				&"/foo/1".into(),
+				&message.peer_id().unwrap(),
+				message.direction,
+				message.kind,
+				&message.payload,
+			);
+		}
+		recorder.flush();
+
+		assert_eq!(Recording::from_dir(dir.path()).unwrap().messages.len(), messages.len());
+	}
+
+	#[test]
+	fn messages_are_dropped_when_writer_falls_behind() {
+		// No writer thread drains the channel.
+		let (tx, rx) = mpsc::sync_channel(1);
+		let recorder =
+			TrafficRecorder { inner: Some(Arc::new(Inner { tx: Some(tx), thread: None })) };
+		let message = message(1);
+
+		for _ in 0..3 {
+			recorder.record(
+				&"/foo/1".into(),
+				&message.peer_id().unwrap(),
+				message.direction,
+				message.kind,
+				&message.payload,
+			);
+		}
+
+		assert!(matches!(rx.try_recv(), Ok(Command::Record(_))));
+		assert!(rx.try_recv().is_err());
+	}
+}
@@ -33,9 +33,13 @@
 //!
 //! - If provided, a ["requests processing"](ProtocolConfig::inbound_queue) channel
 //! is used to handle incoming requests.
+//!
+//! - Incoming requests are subject to the request rate limits and bandwidth quotas of the
+//! [`RateLimiter`].
 
 use crate::{
 	peer_store::{PeerStoreProvider, BANNED_THRESHOLD},
+	recorder::{self, MessageKind, TrafficRecorder},
 	service::traits::RequestResponseConfig as RequestResponseConfigT,
 	types::ProtocolName,
 	ReputationChange,
@@ -53,7 +57,7 @@ use libp2p::{
 };
 
 use std::{
-	collections::{hash_map::Entry, HashMap},
+	collections::{hash_map::Entry, HashMap, HashSet},
 	io, iter,
 	ops::Deref,
 	pin::Pin,
@@ -63,6 +67,9 @@ use std::{
 };
 
 pub use libp2p::request_response::{Config, InboundRequestId, OutboundRequestId};
+pub use rate_limit::{RateLimitConfig, RateLimiter, Throttled};
+
+mod rate_limit;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "sub-libp2p::request-response";
@@ -382,6 +389,18 @@ pub struct RequestResponsesBehaviour {
 	/// Primarily used to get a reputation of a node.
 	peer_store: Arc<dyn PeerStoreProvider>,
 
+	/// Request rate limits and bandwidth quotas of incoming requests.
+	rate_limiter: RateLimiter,
+
+	/// Traffic recorder.
+	recorder: TrafficRecorder,
+
+	/// Incoming requests refused by [`RateLimiter`].
+	///
+	/// The refusal has already been reported, so the events generated by the underlying
+	/// [`Behaviour`] for these requests are ignored.
+	throttled_requests: HashSet<ProtocolRequestId<InboundRequestId>>,
+
 	/// Interval to check that the requests are not taking too long.
 	///
 	/// We had issues in the past where libp2p did not produce a timeout event in due time.
@@ -406,6 +425,8 @@ impl RequestResponsesBehaviour {
 	pub fn new(
 		list: impl Iterator<Item = ProtocolConfig>,
 		peer_store: Arc<dyn PeerStoreProvider>,
+		rate_limiter: RateLimiter,
+		recorder: TrafficRecorder,
 	) -> Result<Self, RegisterError> {
 		let mut protocols = HashMap::new();
 		for protocol in list {
@@ -445,6 +466,9 @@ impl RequestResponsesBehaviour {
 			pending_responses_arrival_time: Default::default(),
 			send_feedback: Default::default(),
 			peer_store,
+			rate_limiter,
+			recorder,
+			throttled_requests: Default::default(),
 			periodic_request_check: tokio::time::interval(PERIODIC_REQUEST_CHECK),
 		})
 	}
@@ -472,6 +496,7 @@ impl RequestResponsesBehaviour {
 			Self::send_request_inner(
 				behaviour,
 				&mut self.pending_requests,
+				&self.recorder,
 				target,
 				protocol_name,
 				request,
@@ -492,6 +517,7 @@ impl RequestResponsesBehaviour {
 	fn send_request_inner(
 		behaviour: &mut Behaviour<GenericCodec>,
 		pending_requests: &mut HashMap<ProtocolRequestId<OutboundRequestId>, PendingRequest>,
+		recorder: &TrafficRecorder,
 		target: &PeerId,
 		protocol_name: ProtocolName,
 		request: Vec<u8>,
@@ -500,6 +526,13 @@ impl RequestResponsesBehaviour {
 		connect: IfDisconnected,
 	) {
 		if behaviour.is_connected(target) || connect.should_connect() {
+			recorder.record(
+				&protocol_name,
+				&(*target).into(),
+				recorder::Direction::Outbound,
+				MessageKind::Request,
+				&request,
+			);
 			let request_id = behaviour.send_request(target, request);
 			let prev_req_id = pending_requests.insert(
 				(protocol_name.to_string().into(), request_id).into(),
@@ -692,6 +725,14 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 						self.protocols.get_mut(&*protocol_name)
 					{
 						log::trace!(target: LOG_TARGET, "send response to {peer} ({protocol_name:?}), {} bytes", payload.len());
+						self.rate_limiter.on_response_sent(&protocol_name, payload.len());
+						self.recorder.record(
+							&protocol_name,
+							&peer.into(),
+							recorder::Direction::Outbound,
+							MessageKind::Response,
+							&payload,
+						);
 
 						if behaviour.send_response(inner_channel, Ok(payload)).is_err() {
 							// Note: Failure is handled further below when receiving
@@ -773,6 +814,47 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 								continue 'poll_protocol
 							}
 
+							if let Err(throttled) = self.rate_limiter.check_inbound(
+								protocol,
+								peer.into(),
+								request.len(),
+							) {
+								log::debug!(
+									target: LOG_TARGET,
+									"Refusing request from {peer} on {protocol:?}: {}",
+									throttled.reason(),
+								);
+
+								if let Some(change) = throttled.reputation_change() {
+									self.peer_store.report_peer(peer.into(), change);
+								}
+
+								// There is no busy status in the protocol. Closing the substream
+								// without a response is observed as `RequestFailure::Refused` on
+								// the other end.
+								if behaviour.send_response(channel, Err(())).is_ok() {
+									self.throttled_requests
+										.insert((protocol.clone(), request_id).into());
+								}
+								self.pending_responses_arrival_time
+									.remove(&(protocol.clone(), request_id).into());
+
+								let out = Event::InboundRequest {
+									peer,
+									protocol: protocol.clone(),
+									result: Err(ResponseFailure::Throttled(throttled)),
+								};
+								return Poll::Ready(ToSwarm::GenerateEvent(out))
+							}
+
+							self.recorder.record(
+								protocol,
+								&peer.into(),
+								recorder::Direction::Inbound,
+								MessageKind::Request,
+								&request,
+							);
+
 							let (tx, rx) = oneshot::channel();
 
 							// Submit the request to the "response builder" passed by the user at
@@ -836,6 +918,16 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 										response.as_ref().map_or(0usize, |response| response.len()),
 									);
 
+									if let Ok(response) = &response {
+										self.recorder.record(
+											&protocol,
+											&peer.into(),
+											recorder::Direction::Inbound,
+											MessageKind::Response,
+											response,
+										);
+									}
+
 									let delivered = response_tx
 										.send(
 											response
@@ -945,6 +1037,13 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 						request_response::Event::InboundFailure {
 							request_id, peer, error, ..
 						} => {
+							if self
+								.throttled_requests
+								.remove(&(protocol.clone(), request_id).into())
+							{
+								continue
+							}
+
 							self.pending_responses_arrival_time
 								.remove(&(protocol.clone(), request_id).into());
 							self.send_feedback.remove(&(protocol.clone(), request_id).into());
@@ -958,6 +1057,13 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
 						// A response to an inbound request has been sent.
 						request_response::Event::ResponseSent { request_id, peer } => {
+							if self
+								.throttled_requests
+								.remove(&(protocol.clone(), request_id).into())
+							{
+								continue
+							}
+
 							let arrival_time = self
 								.pending_responses_arrival_time
 								.remove(&(protocol.clone(), request_id).into())
@@ -994,6 +1100,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 					Self::send_request_inner(
 						behaviour,
 						&mut self.pending_requests,
+						&self.recorder,
 						&peer,
 						protocol,
 						request,
@@ -1026,6 +1133,9 @@ pub enum ResponseFailure {
 	/// Problem on the network.
 	#[error("Problem on the network: {0}")]
 	Network(InboundFailure),
+	/// The request was refused because of a rate limit or bandwidth quota.
+	#[error("Request refused: {}", .0.reason())]
+	Throttled(Throttled),
 }
 
 /// Implements the libp2p [`Codec`] trait. Defines how streams of bytes are turned
@@ -1170,7 +1280,7 @@ mod tests {
 		swarm::{Config as SwarmConfig, Executor, Swarm, SwarmEvent},
 		Multiaddr,
 	};
-	use std::{iter, time::Duration};
+	use std::{iter, num::NonZeroU64, time::Duration};
 
 	struct TokioExecutor;
 	impl Executor for TokioExecutor {
@@ -1181,6 +1291,13 @@ mod tests {
 
 	fn build_swarm(
 		list: impl Iterator<Item = ProtocolConfig>,
+	) -> (Swarm<RequestResponsesBehaviour>, Multiaddr) {
+		build_swarm_with_rate_limiter(list, RateLimiter::unlimited())
+	}
+
+	fn build_swarm_with_rate_limiter(
+		list: impl Iterator<Item = ProtocolConfig>,
+		rate_limiter: RateLimiter,
 	) -> (Swarm<RequestResponsesBehaviour>, Multiaddr) {
 		let keypair = Keypair::generate_ed25519();
 
@@ -1190,7 +1307,13 @@ mod tests {
 			.multiplex(libp2p::yamux::Config::default())
 			.boxed();
 
-		let behaviour = RequestResponsesBehaviour::new(list, Arc::new(MockPeerStore {})).unwrap();
+		let behaviour = RequestResponsesBehaviour::new(
+			list,
+			Arc::new(MockPeerStore {}),
+			rate_limiter,
+			TrafficRecorder::disabled(),
+		)
+		.unwrap();
 
 		let mut swarm = Swarm::new(
 			transport,
@@ -1297,6 +1420,98 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn throttled_requests_are_refused() {
+		let protocol_name = ProtocolName::from("/test/req-resp/1");
+
+		let (tx, mut rx) = async_channel::bounded::<IncomingRequest>(64);
+		tokio::spawn(async move {
+			while let Some(rq) = rx.next().await {
+				let _ = rq.pending_response.send(super::OutgoingResponse {
+					result: Ok(b"this is a response".to_vec()),
+					reputation_changes: Vec::new(),
+					sent_feedback: None,
+				});
+			}
+		});
+
+		let protocol_config = |inbound_queue| ProtocolConfig {
+			name: protocol_name.clone(),
+			fallback_names: Vec::new(),
+			max_request_size: 1024,
+			max_response_size: 1024 * 1024,
+			request_timeout: Duration::from_secs(30),
+			inbound_queue,
+		};
+
+		// The first request overdraws the download quota, refusing the second one.
+		let rate_limiter = RateLimiter::new(
+			RateLimitConfig { max_download_bandwidth: NonZeroU64::new(1), ..Default::default() },
+			None,
+		)
+		.unwrap();
+		let (mut responder, listen_addr) =
+			build_swarm_with_rate_limiter(iter::once(protocol_config(Some(tx))), rate_limiter);
+		let (mut requester, _) = build_swarm(iter::once(protocol_config(None)));
+
+		Swarm::dial(&mut requester, listen_addr).unwrap();
+
+		let (inbound_tx, mut inbound_rx) = futures::channel::mpsc::unbounded();
+		tokio::spawn(async move {
+			loop {
+				match responder.select_next_some().await {
+					SwarmEvent::Behaviour(Event::InboundRequest { result, .. }) => {
+						inbound_tx.unbounded_send(result).unwrap();
+					},
+					_ => {},
+				}
+			}
+		});
+
+		let mut responses = Vec::new();
+		let mut finished = 0;
+
+		// Send the requests one after another.
+		while finished < 2 {
+			let peer = match requester.select_next_some().await {
+				SwarmEvent::ConnectionEstablished { peer_id, .. } => peer_id,
+				SwarmEvent::Behaviour(Event::RequestFinished { peer, result, .. }) => {
+					result.unwrap();
+					finished += 1;
+					peer
+				},
+				_ => continue,
+			};
+
+			if responses.len() == 2 {
+				continue
+			}
+
+			let (sender, receiver) = oneshot::channel();
+			requester.behaviour_mut().send_request(
+				&peer,
+				protocol_name.clone(),
+				b"this is a request".to_vec(),
+				None,
+				sender,
+				IfDisconnected::ImmediateError,
+			);
+			responses.push(receiver);
+		}
+
+		assert_eq!(
+			responses.remove(0).await.unwrap().unwrap(),
+			(b"this is a response".to_vec(), protocol_name.clone())
+		);
+		assert_matches!(responses.remove(0).await.unwrap(), Err(RequestFailure::Refused));
+
+		assert_matches!(inbound_rx.next().await, Some(Ok(_)));
+		assert_matches!(
+			inbound_rx.next().await,
+			Some(Err(ResponseFailure::Throttled(Throttled::DownloadBandwidth)))
+		);
+	}
+
 	#[tokio::test]
 	async fn max_response_size_exceeded() {
 		let protocol_name = ProtocolName::from("/test/req-resp/1");
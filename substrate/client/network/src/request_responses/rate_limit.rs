@@ -0,0 +1,726 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bandwidth quotas and per-peer rate limits for inbound requests.
+//!
+//! Both network backends consult a shared [`RateLimiter`] before handing an inbound request over
+//! to the protocol handler. A request is refused if:
+//!
+//! - the remote peer has exceeded its request rate on the protocol, in which case the peer is
+//!   additionally penalised with [`RATE_LIMITED`], or
+//! - the upload bandwidth spent on responses, either globally or for the protocol, has exceeded its
+//!   quota, or
+//! - the download bandwidth spent on inbound requests, either globally or for the protocol, has
+//!   exceeded its quota.
+//!
+//! Bandwidth quotas are not the remote's fault and don't affect its reputation. They are accounted
+//! using the size of the requests received and responses sent, so a single large message may
+//! temporarily overdraw the quota and cause subsequent requests to be refused until the quota has
+//! been replenished.
+//!
+//! Request-response protocols have no dedicated "busy" status. Refused requests are not answered
+//! at all: the substream is closed without a response, which the remote observes as
+//! [`RequestFailure::Refused`], the same way as any other request the node declined to handle.
+//!
+//! [`RequestFailure::Refused`]: super::RequestFailure::Refused
+
+use crate::{types::ProtocolName, ReputationChange};
+
+use parking_lot::Mutex;
+use prometheus_endpoint::{self as prometheus, CounterVec, Opts, PrometheusError, Registry, U64};
+use sc_network_types::PeerId;
+use schnellru::{ByLength, LruMap};
+
+use std::{
+	collections::HashMap,
+	num::{NonZeroU32, NonZeroU64},
+	sync::Arc,
+	time::Instant,
+};
+
+/// Maximum number of `(protocol, peer)` request buckets kept in memory.
+const MAX_TRACKED_PEERS: u32 = 4096;
+
+/// Reputation change for a peer that exceeded its request rate limit.
+pub const RATE_LIMITED: ReputationChange =
+	ReputationChange::new(-(1 << 10), "Request rate limit exceeded");
+
+/// Request rate limits and bandwidth quotas applied to inbound requests.
+///
+/// All limits are disabled by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+	/// Maximum number of requests per second a single peer can send on one protocol.
+	///
+	/// Peers can send up to twice as many requests in a burst before being rate limited.
+	pub max_requests_per_peer: Option<NonZeroU32>,
+
+	/// Maximum number of requests per second a single peer can send on individual protocols.
+	///
+	/// Overrides [`RateLimitConfig::max_requests_per_peer`] for the given protocols. Keys are
+	/// matched the same way as in [`RateLimitConfig::protocol_upload_bandwidth`].
+	pub protocol_requests_per_peer: HashMap<String, NonZeroU32>,
+
+	/// Maximum number of bytes per second sent in responses, for all protocols combined.
+	pub max_upload_bandwidth: Option<NonZeroU64>,
+
+	/// Maximum number of bytes per second sent in responses of individual protocols.
+	///
+	/// Keys are matched exactly against the main protocol name, which for most request-response
+	/// protocols includes the genesis hash, e.g. `/<genesis-hash>/state/2`.
+	pub protocol_upload_bandwidth: HashMap<String, NonZeroU64>,
+
+	/// Maximum number of bytes per second received in inbound requests, for all protocols
+	/// combined.
+	pub max_download_bandwidth: Option<NonZeroU64>,
+
+	/// Maximum number of bytes per second received in inbound requests of individual protocols.
+	///
+	/// Keys are matched the same way as in [`RateLimitConfig::protocol_upload_bandwidth`].
+	pub protocol_download_bandwidth: HashMap<String, NonZeroU64>,
+}
+
+impl RateLimitConfig {
+	/// Returns `true` if no limit is configured.
+	pub fn is_unlimited(&self) -> bool {
+		self.max_requests_per_peer.is_none() &&
+			self.protocol_requests_per_peer.is_empty() &&
+			self.max_upload_bandwidth.is_none() &&
+			self.protocol_upload_bandwidth.is_empty() &&
+			self.max_download_bandwidth.is_none() &&
+			self.protocol_download_bandwidth.is_empty()
+	}
+
+	/// Get the request rate limit of a single peer on `protocol`, if any.
+	fn requests_per_peer(&self, protocol: &str) -> Option<NonZeroU32> {
+		self.protocol_requests_per_peer.get(protocol).copied().or(self.max_requests_per_peer)
+	}
+
+	/// Get the upload bandwidth quota of `protocol`, if any.
+	fn protocol_upload_bandwidth(&self, protocol: &str) -> Option<NonZeroU64> {
+		self.protocol_upload_bandwidth.get(protocol).copied()
+	}
+
+	/// Get the download bandwidth quota of `protocol`, if any.
+	fn protocol_download_bandwidth(&self, protocol: &str) -> Option<NonZeroU64> {
+		self.protocol_download_bandwidth.get(protocol).copied()
+	}
+}
+
+/// Reason why an inbound request was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttled {
+	/// The remote peer exceeded its request rate limit.
+	RequestRate,
+
+	/// The global or protocol upload bandwidth quota is exhausted.
+	UploadBandwidth,
+
+	/// The global or protocol download bandwidth quota is exhausted.
+	DownloadBandwidth,
+}
+
+impl Throttled {
+	/// Reason reported in the inbound request failure metrics.
+	pub fn reason(&self) -> &'static str {
+		match self {
+			Self::RequestRate => "rate-limited",
+			Self::UploadBandwidth => "upload-bandwidth-limited",
+			Self::DownloadBandwidth => "download-bandwidth-limited",
+		}
+	}
+
+	/// Reputation change to apply to the remote peer.
+	pub fn reputation_change(&self) -> Option<ReputationChange> {
+		match self {
+			Self::RequestRate => Some(RATE_LIMITED),
+			Self::UploadBandwidth | Self::DownloadBandwidth => None,
+		}
+	}
+}
+
+/// Token bucket replenished at a constant rate.
+#[derive(Debug)]
+struct TokenBucket {
+	/// Tokens added per second.
+	rate: f64,
+
+	/// Maximum number of tokens the bucket can hold.
+	capacity: f64,
+
+	/// Tokens currently in the bucket, negative if the bucket has been overdrawn.
+	tokens: f64,
+
+	/// When were the tokens last replenished.
+	last_update: Instant,
+}
+
+impl TokenBucket {
+	/// Create new full [`TokenBucket`].
+	fn new(rate: f64, capacity: f64, now: Instant) -> Self {
+		Self { rate, capacity, tokens: capacity, last_update: now }
+	}
+
+	/// Replenish tokens accrued since the last update.
+	fn replenish(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last_update).as_secs_f64();
+
+		self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+		self.last_update = now;
+	}
+
+	/// Take one token from the bucket, returning `false` if the bucket is empty.
+	fn try_take(&mut self, now: Instant) -> bool {
+		self.replenish(now);
+
+		if self.tokens < 1.0 {
+			return false
+		}
+
+		self.tokens -= 1.0;
+		true
+	}
+
+	/// Check if the bucket has been overdrawn.
+	fn is_exhausted(&mut self, now: Instant) -> bool {
+		self.replenish(now);
+		self.tokens <= 0.0
+	}
+
+	/// Take `amount` tokens from the bucket, allowing it to be overdrawn.
+	fn consume(&mut self, amount: u64, now: Instant) {
+		self.replenish(now);
+		self.tokens -= amount as f64;
+	}
+}
+
+/// Inner state of [`RateLimiter`].
+struct Inner {
+	/// Configuration.
+	config: RateLimitConfig,
+
+	/// Global upload bandwidth quota.
+	upload: Option<TokenBucket>,
+
+	/// Global download bandwidth quota.
+	download: Option<TokenBucket>,
+
+	/// Upload bandwidth quotas of individual protocols.
+	protocol_upload: HashMap<ProtocolName, Option<TokenBucket>>,
+
+	/// Download bandwidth quotas of individual protocols.
+	protocol_download: HashMap<ProtocolName, Option<TokenBucket>>,
+
+	/// Request rate limits of peers, per protocol.
+	requests: LruMap<(ProtocolName, PeerId), TokenBucket>,
+
+	/// Prometheus metrics.
+	metrics: Option<Metrics>,
+}
+
+impl Inner {
+	/// Get the upload bandwidth quota of `protocol`, if any.
+	fn protocol_upload(
+		&mut self,
+		protocol: &ProtocolName,
+		now: Instant,
+	) -> Option<&mut TokenBucket> {
+		let config = &self.config;
+
+		self.protocol_upload
+			.entry(protocol.clone())
+			.or_insert_with(|| {
+				config
+					.protocol_upload_bandwidth(protocol)
+					.map(|limit| TokenBucket::new(limit.get() as f64, limit.get() as f64, now))
+			})
+			.as_mut()
+	}
+
+	/// Get the download bandwidth quota of `protocol`, if any.
+	fn protocol_download(
+		&mut self,
+		protocol: &ProtocolName,
+		now: Instant,
+	) -> Option<&mut TokenBucket> {
+		let config = &self.config;
+
+		self.protocol_download
+			.entry(protocol.clone())
+			.or_insert_with(|| {
+				config
+					.protocol_download_bandwidth(protocol)
+					.map(|limit| TokenBucket::new(limit.get() as f64, limit.get() as f64, now))
+			})
+			.as_mut()
+	}
+
+	/// Check if an inbound request of `size` bytes from `peer` on `protocol` can be handled.
+	fn check_inbound(
+		&mut self,
+		protocol: &ProtocolName,
+		peer: PeerId,
+		size: usize,
+		now: Instant,
+	) -> Result<(), Throttled> {
+		// The request has been received regardless of whether it's handled.
+		if let Some(bucket) = self.download.as_mut() {
+			if bucket.is_exhausted(now) {
+				return Err(Throttled::DownloadBandwidth)
+			}
+			bucket.consume(size as u64, now);
+		}
+
+		if let Some(bucket) = self.protocol_download(protocol, now) {
+			if bucket.is_exhausted(now) {
+				return Err(Throttled::DownloadBandwidth)
+			}
+			bucket.consume(size as u64, now);
+		}
+
+		if self.upload.as_mut().map_or(false, |bucket| bucket.is_exhausted(now)) ||
+			self.protocol_upload(protocol, now).map_or(false, |bucket| bucket.is_exhausted(now))
+		{
+			return Err(Throttled::UploadBandwidth)
+		}
+
+		let Some(limit) = self.config.requests_per_peer(protocol) else { return Ok(()) };
+		let rate = limit.get() as f64;
+		let bucket = self
+			.requests
+			.get_or_insert((protocol.clone(), peer), || TokenBucket::new(rate, 2.0 * rate, now))
+			.expect("`LruMap` is limited by length, insertion always succeeds; qed");
+
+		match bucket.try_take(now) {
+			true => Ok(()),
+			false => Err(Throttled::RequestRate),
+		}
+	}
+}
+
+/// Prometheus metrics of [`RateLimiter`].
+#[derive(Clone)]
+struct Metrics {
+	/// Inbound requests refused by the rate limiter.
+	requests_throttled_total: CounterVec<U64>,
+
+	/// Bytes accounted against the bandwidth quotas.
+	quota_bytes_total: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			requests_throttled_total: prometheus::register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_sub_libp2p_requests_throttled_total",
+						"Total number of incoming requests refused by the request rate limiter",
+					),
+					&["protocol", "reason"],
+				)?,
+				registry,
+			)?,
+			quota_bytes_total: prometheus::register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_sub_libp2p_requests_quota_bytes_total",
+						"Total number of bytes accounted against the request bandwidth quotas",
+					),
+					&["direction"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Enforces [`RateLimitConfig`] for inbound requests of all request-response protocols.
+///
+/// Cloning the [`RateLimiter`] returns a handle to the same quotas.
+#[derive(Clone)]
+pub struct RateLimiter {
+	inner: Option<Arc<Mutex<Inner>>>,
+}
+
+impl RateLimiter {
+	/// Create new [`RateLimiter`], registering its metrics in `registry`, if any.
+	pub fn new(
+		config: RateLimitConfig,
+		registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		if config.is_unlimited() {
+			return Ok(Self::unlimited())
+		}
+
+		let now = Instant::now();
+		let bucket =
+			|limit: NonZeroU64| TokenBucket::new(limit.get() as f64, limit.get() as f64, now);
+		let upload = config.max_upload_bandwidth.map(bucket);
+		let download = config.max_download_bandwidth.map(bucket);
+		let metrics = registry.map(Metrics::register).transpose()?;
+
+		Ok(Self {
+			inner: Some(Arc::new(Mutex::new(Inner {
+				config,
+				upload,
+				download,
+				protocol_upload: HashMap::new(),
+				protocol_download: HashMap::new(),
+				requests: LruMap::new(ByLength::new(MAX_TRACKED_PEERS)),
+				metrics,
+			}))),
+		})
+	}
+
+	/// Create [`RateLimiter`] which doesn't limit anything.
+	pub fn unlimited() -> Self {
+		Self { inner: None }
+	}
+
+	/// Check if an inbound request of `size` bytes from `peer` on `protocol` can be handled.
+	///
+	/// The request is accounted against the download quota even if it is refused.
+	pub fn check_inbound(
+		&self,
+		protocol: &ProtocolName,
+		peer: PeerId,
+		size: usize,
+	) -> Result<(), Throttled> {
+		let Some(inner) = &self.inner else { return Ok(()) };
+		let mut inner = inner.lock();
+		let result = inner.check_inbound(protocol, peer, size, Instant::now());
+
+		if let Some(metrics) = &inner.metrics {
+			metrics.quota_bytes_total.with_label_values(&["in"]).inc_by(size as u64);
+
+			if let Err(throttled) = &result {
+				metrics
+					.requests_throttled_total
+					.with_label_values(&[&**protocol, throttled.reason()])
+					.inc();
+			}
+		}
+
+		result
+	}
+
+	/// Account `size` bytes of a response sent on `protocol` against the upload quotas.
+	pub fn on_response_sent(&self, protocol: &ProtocolName, size: usize) {
+		let Some(inner) = &self.inner else { return };
+		let mut inner = inner.lock();
+		let now = Instant::now();
+
+		if let Some(bucket) = inner.upload.as_mut() {
+			bucket.consume(size as u64, now);
+		}
+
+		if let Some(bucket) = inner.protocol_upload(protocol, now) {
+			bucket.consume(size as u64, now);
+		}
+
+		if let Some(metrics) = &inner.metrics {
+			metrics.quota_bytes_total.with_label_values(&["out"]).inc_by(size as u64);
+		}
+	}
+}
+
+impl Default for RateLimiter {
+	fn default() -> Self {
+		Self::unlimited()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn protocol(name: &'static str) -> ProtocolName {
+		ProtocolName::from(name)
+	}
+
+	#[test]
+	fn unlimited_by_default() {
+		let limiter = RateLimiter::new(RateLimitConfig::default(), None).unwrap();
+		let peer = PeerId::random();
+
+		for _ in 0..1000 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+			limiter.on_response_sent(&protocol("/abcd/sync/2"), 1024 * 1024);
+		}
+	}
+
+	#[test]
+	fn peers_are_rate_limited_per_protocol() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig { max_requests_per_peer: NonZeroU32::new(5), ..Default::default() },
+			None,
+		)
+		.unwrap();
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+
+		// burst of twice the rate is allowed
+		for _ in 0..10 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer1, 0), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/sync/2"), peer1, 0),
+			Err(Throttled::RequestRate),
+		);
+
+		// other peers and other protocols are not affected
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer2, 0), Ok(()));
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/state/2"), peer1, 0), Ok(()));
+	}
+
+	#[test]
+	fn protocol_request_rate_overrides_global_rate() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig {
+				max_requests_per_peer: NonZeroU32::new(5),
+				protocol_requests_per_peer: HashMap::from([
+					("/abcd/state/2".to_string(), NonZeroU32::new(1).unwrap()),
+					("/abcd/sync/2".to_string(), NonZeroU32::new(50).unwrap()),
+				]),
+				..Default::default()
+			},
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		for _ in 0..2 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0),
+			Err(Throttled::RequestRate),
+		);
+
+		for _ in 0..100 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0),
+			Err(Throttled::RequestRate),
+		);
+
+		// protocols without an override use the global rate
+		for _ in 0..10 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/light/2"), peer, 0), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/light/2"), peer, 0),
+			Err(Throttled::RequestRate),
+		);
+	}
+
+	#[test]
+	fn protocol_request_rate_without_global_rate() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig {
+				protocol_requests_per_peer: HashMap::from([(
+					"/abcd/sync/warp".to_string(),
+					NonZeroU32::new(1).unwrap(),
+				)]),
+				..Default::default()
+			},
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		for _ in 0..2 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/warp"), peer, 0), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/sync/warp"), peer, 0),
+			Err(Throttled::RequestRate),
+		);
+
+		for _ in 0..100 {
+			assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+		}
+	}
+
+	#[test]
+	fn protocol_bandwidth_quota() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig {
+				protocol_upload_bandwidth: HashMap::from([(
+					"/abcd/state/2".to_string(),
+					NonZeroU64::new(1024).unwrap(),
+				)]),
+				..Default::default()
+			},
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0), Ok(()));
+		limiter.on_response_sent(&protocol("/abcd/state/2"), 4096);
+
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0),
+			Err(Throttled::UploadBandwidth),
+		);
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+	}
+
+	#[test]
+	fn global_bandwidth_quota() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig { max_upload_bandwidth: NonZeroU64::new(1024), ..Default::default() },
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		limiter.on_response_sent(&protocol("/abcd/sync/2"), 512);
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0), Ok(()));
+
+		limiter.on_response_sent(&protocol("/abcd/state/2"), 4096);
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0),
+			Err(Throttled::UploadBandwidth),
+		);
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/light/2"), peer, 0),
+			Err(Throttled::UploadBandwidth),
+		);
+	}
+
+	#[test]
+	fn protocol_names_are_matched_exactly() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig {
+				protocol_upload_bandwidth: HashMap::from([
+					("/abcd/state/2".to_string(), NonZeroU64::new(1024).unwrap()),
+					("/state/2".to_string(), NonZeroU64::new(1024 * 1024).unwrap()),
+				]),
+				..Default::default()
+			},
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		limiter.on_response_sent(&protocol("/abcd/state/2"), 4096);
+		limiter.on_response_sent(&protocol("/efgh/state/2"), 4096);
+
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0),
+			Err(Throttled::UploadBandwidth),
+		);
+		assert_eq!(limiter.check_inbound(&protocol("/efgh/state/2"), peer, 0), Ok(()));
+	}
+
+	#[test]
+	fn download_bandwidth_quota() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig { max_download_bandwidth: NonZeroU64::new(1024), ..Default::default() },
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 512), Ok(()));
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 4096), Ok(()));
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/state/2"), peer, 0),
+			Err(Throttled::DownloadBandwidth),
+		);
+
+		// responses are not accounted against the download quota
+		let limiter = RateLimiter::new(
+			RateLimitConfig { max_download_bandwidth: NonZeroU64::new(1024), ..Default::default() },
+			None,
+		)
+		.unwrap();
+		limiter.on_response_sent(&protocol("/abcd/sync/2"), 4096);
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+	}
+
+	#[test]
+	fn protocol_download_bandwidth_quota() {
+		let limiter = RateLimiter::new(
+			RateLimitConfig {
+				protocol_download_bandwidth: HashMap::from([(
+					"/abcd/light/2".to_string(),
+					NonZeroU64::new(1024).unwrap(),
+				)]),
+				..Default::default()
+			},
+			None,
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/light/2"), peer, 4096), Ok(()));
+		assert_eq!(
+			limiter.check_inbound(&protocol("/abcd/light/2"), peer, 0),
+			Err(Throttled::DownloadBandwidth),
+		);
+
+		// other protocols are not affected
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 4096), Ok(()));
+		assert_eq!(limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0), Ok(()));
+	}
+
+	#[test]
+	fn throttled_requests_are_counted() {
+		let registry = Registry::new();
+		let limiter = RateLimiter::new(
+			RateLimitConfig { max_requests_per_peer: NonZeroU32::new(1), ..Default::default() },
+			Some(&registry),
+		)
+		.unwrap();
+		let peer = PeerId::random();
+
+		for _ in 0..3 {
+			let _ = limiter.check_inbound(&protocol("/abcd/sync/2"), peer, 0);
+		}
+
+		let metrics = limiter.inner.as_ref().unwrap().lock().metrics.clone().unwrap();
+		assert_eq!(
+			metrics
+				.requests_throttled_total
+				.with_label_values(&["/abcd/sync/2", "rate-limited"])
+				.get(),
+			1,
+		);
+	}
+
+	#[test]
+	fn bucket_is_replenished() {
+		let now = Instant::now();
+		let mut bucket = TokenBucket::new(10.0, 10.0, now);
+
+		bucket.consume(20, now);
+		assert!(bucket.is_exhausted(now));
+
+		let later = now + std::time::Duration::from_secs(1);
+		assert!(bucket.is_exhausted(later));
+
+		let later = now + std::time::Duration::from_secs(3);
+		assert!(!bucket.is_exhausted(later));
+		assert!(bucket.try_take(later));
+	}
+}
@@ -43,7 +43,10 @@ use crate::{
 	peer_store::{PeerStore, PeerStoreProvider},
 	protocol::{self, Protocol, Ready},
 	protocol_controller::{self, ProtoSetConfig, ProtocolController, SetId},
-	request_responses::{IfDisconnected, ProtocolConfig as RequestResponseConfig, RequestFailure},
+	recorder::TrafficRecorder,
+	request_responses::{
+		IfDisconnected, ProtocolConfig as RequestResponseConfig, RateLimiter, RequestFailure,
+	},
 	service::{
 		signature::{Signature, SigningError},
 		traits::{
@@ -154,6 +157,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	sync_protocol_handle: protocol_controller::ProtocolHandle,
 	/// Handle to `PeerStore`.
 	peer_store_handle: Arc<dyn PeerStoreProvider>,
+	/// Peers on the other side of the sentry topology, reported as authorities.
+	sentry_peers: HashSet<sc_network_types::PeerId>,
 	/// Marker to pin the `H` generic. Serves no purpose except to not break backwards
 	/// compatibility.
 	_marker: PhantomData<H>,
@@ -331,6 +336,11 @@ where
 			fs::create_dir_all(path)?;
 		}
 
+		let recorder = match &network_config.traffic_recorder {
+			Some(config) => TrafficRecorder::new(config.clone())?,
+			None => TrafficRecorder::disabled(),
+		};
+
 		info!(
 			target: LOG_TARGET,
 			"🏷  Local node identity is: {}",
@@ -344,7 +354,13 @@ where
 				TransportConfig::Normal { .. } => false,
 			};
 
-			transport::build_transport(local_identity.clone().into(), config_mem)
+			let websocket_tls = network_config
+				.websocket_tls
+				.as_ref()
+				.map(transport::websocket_tls_config)
+				.transpose()?;
+
+			transport::build_transport(local_identity.clone().into(), config_mem, websocket_tls)
 		};
 
 		let (to_notifications, from_protocol_controllers) =
@@ -459,6 +475,7 @@ where
 		let (protocol, notif_protocol_handles) = Protocol::new(
 			From::from(&params.role),
 			params.notification_metrics,
+			recorder.clone(),
 			notification_protocols,
 			params.block_announce_config,
 			Arc::clone(&peer_store_handle),
@@ -466,6 +483,12 @@ where
 			from_protocol_controllers,
 		)?;
 
+		// Request rate limits and bandwidth quotas shared by all request-response protocols.
+		let rate_limiter = RateLimiter::new(
+			network_config.request_rate_limits.clone(),
+			params.metrics_registry.as_ref(),
+		)?;
+
 		// Build the swarm.
 		let (mut swarm, bandwidth): (Swarm<Behaviour<B>>, _) = {
 			let user_agent =
@@ -491,6 +514,9 @@ where
 					network_config.kademlia_disjoint_query_paths,
 				);
 				config.with_kademlia_replication_factor(network_config.kademlia_replication_factor);
+				config.with_kademlia_client_mode(
+					network_config.sentry_topology.is_private_validator(),
+				);
 
 				match network_config.transport {
 					TransportConfig::MemoryOnly => {
@@ -518,6 +544,8 @@ where
 					discovery_config,
 					request_response_protocols,
 					Arc::clone(&peer_store_handle),
+					rate_limiter,
+					recorder.clone(),
 					external_addresses.clone(),
 					network_config.public_addresses.iter().cloned().map(Into::into).collect(),
 					ConnectionLimits::default()
@@ -582,6 +610,8 @@ where
 		}
 
 		let listen_addresses_set = Arc::new(Mutex::new(HashSet::new()));
+		let sentry_peers =
+			network_config.sentry_topology.peers().iter().map(|node| node.peer_id).collect();
 
 		let service = Arc::new(NetworkService {
 			bandwidth,
@@ -595,6 +625,7 @@ where
 			protocol_handles,
 			sync_protocol_handle,
 			peer_store_handle: Arc::clone(&peer_store_handle),
+			sentry_peers,
 			_marker: PhantomData,
 			_block: Default::default(),
 		});
@@ -611,6 +642,7 @@ where
 			reported_invalid_boot_nodes: Default::default(),
 			peer_store_handle: Arc::clone(&peer_store_handle),
 			notif_protocol_handles,
+			recorder,
 			_marker: Default::default(),
 			_block: Default::default(),
 		})
@@ -1010,6 +1042,16 @@ where
 	}
 
 	fn report_peer(&self, peer_id: sc_network_types::PeerId, cost_benefit: ReputationChange) {
+		// Sentry peers must stay connected, they are never banned.
+		if cost_benefit.value < 0 && self.sentry_peers.contains(&peer_id) {
+			log::trace!(
+				target: LOG_TARGET,
+				"Ignoring reputation change of sentry peer {peer_id}: {}",
+				cost_benefit.reason,
+			);
+			return
+		}
+
 		self.peer_store_handle.report_peer(peer_id, cost_benefit);
 	}
 
@@ -1145,6 +1187,10 @@ where
 		peer_id: sc_network_types::PeerId,
 		handshake: Vec<u8>,
 	) -> Option<ObservedRole> {
+		if self.sentry_peers.contains(&peer_id) {
+			return Some(ObservedRole::Authority)
+		}
+
 		match Roles::decode_all(&mut &handshake[..]) {
 			Ok(role) => Some(role.into()),
 			Err(_) => {
@@ -1154,6 +1200,10 @@ where
 		}
 	}
 
+	fn is_sentry_peer(&self, peer_id: sc_network_types::PeerId) -> bool {
+		self.sentry_peers.contains(&peer_id)
+	}
+
 	/// Get the list of reserved peers.
 	///
 	/// Returns an error if the `NetworkWorker` is no longer running.
@@ -1360,6 +1410,8 @@ where
 	peer_store_handle: Arc<dyn PeerStoreProvider>,
 	/// Notification protocol handles.
 	notif_protocol_handles: Vec<protocol::ProtocolHandle>,
+	/// Traffic recorder, flushed when the worker is shut down.
+	recorder: TrafficRecorder,
 	/// Marker to pin the `H` generic. Serves no purpose except to not break backwards
 	/// compatibility.
 	_marker: PhantomData<H>,
@@ -1375,6 +1427,8 @@ where
 	/// Run the network.
 	pub async fn run(mut self) {
 		while self.next_action().await {}
+
+		self.recorder.flush();
 	}
 
 	/// Perform one action on the network.
@@ -1510,6 +1564,7 @@ where
 								ResponseFailure::Network(InboundFailure::ConnectionClosed) =>
 									Some("connection-closed"),
 								ResponseFailure::Network(InboundFailure::Io(_)) => Some("io"),
+								ResponseFailure::Throttled(throttled) => Some(throttled.reason()),
 							};
 
 							if let Some(reason) = reason {
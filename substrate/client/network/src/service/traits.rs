@@ -461,6 +461,15 @@ pub trait NetworkPeers {
 	/// there either, `None` is returned and the peer should be discarded.
 	fn peer_role(&self, peer_id: PeerId, handshake: Vec<u8>) -> Option<ObservedRole>;
 
+	/// Returns `true` if `peer_id` is on the other side of the node's sentry topology, i.e. a
+	/// sentry of this private validator or a validator protected by this sentry.
+	///
+	/// Protocols must relay all messages to and from these peers, regardless of their gossip
+	/// topology or peer set limits. See [`crate::config::SentryTopology`].
+	fn is_sentry_peer(&self, _peer_id: PeerId) -> bool {
+		false
+	}
+
 	/// Get the list of reserved peers.
 	///
 	/// Returns an error if the `NetworkWorker` is no longer running.
@@ -546,6 +555,10 @@ where
 		T::peer_role(self, peer_id, handshake)
 	}
 
+	fn is_sentry_peer(&self, peer_id: PeerId) -> bool {
+		T::is_sentry_peer(self, peer_id)
+	}
+
 	fn reserved_peers<'life0, 'async_trait>(
 		&'life0 self,
 	) -> Pin<Box<dyn Future<Output = Result<Vec<PeerId>, ()>> + Send + 'async_trait>>
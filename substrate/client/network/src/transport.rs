@@ -18,6 +18,8 @@
 
 //! Transport that serves as a common ground for all connections.
 
+use crate::config::WebSocketTlsConfig;
+
 use either::Either;
 use libp2p::{
 	core::{
@@ -27,7 +29,7 @@ use libp2p::{
 	},
 	dns, identity, noise, tcp, websocket, PeerId, Transport, TransportExt,
 };
-use std::{sync::Arc, time::Duration};
+use std::{io, sync::Arc, time::Duration};
 
 // TODO: Create a wrapper similar to upstream `BandwidthTransport` that tracks sent/received bytes
 #[allow(deprecated)]
@@ -38,12 +40,15 @@ pub use libp2p::bandwidth::BandwidthSinks;
 /// If `memory_only` is true, then only communication within the same process are allowed. Only
 /// addresses with the format `/memory/...` are allowed.
 ///
+/// Listening on `/wss` addresses requires `websocket_tls`.
+///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
 /// the connections spawned with this transport.
 #[allow(deprecated)]
 pub fn build_transport(
 	keypair: identity::Keypair,
 	memory_only: bool,
+	websocket_tls: Option<websocket::tls::Config>,
 ) -> (Boxed<(PeerId, StreamMuxerBox)>, Arc<BandwidthSinks>) {
 	// Build the base layer of the transport.
 	let transport = if !memory_only {
@@ -61,12 +66,19 @@ pub fn build_transport(
 			let tcp_trans = tcp::tokio::Transport::new(tcp_config);
 			let dns_for_wss = dns::tokio::Transport::system(tcp_trans)
 				.expect("same system_conf & resolver to work");
-			Either::Left(websocket::WsConfig::new(dns_for_wss).or_transport(dns))
+			let mut ws_trans = websocket::WsConfig::new(dns_for_wss);
+			if let Some(tls) = websocket_tls {
+				ws_trans.set_tls_config(tls);
+			}
+			Either::Left(ws_trans.or_transport(dns))
 		} else {
 			// In case DNS can't be constructed, fallback to TCP + WS (WSS won't work)
 			let tcp_trans = tcp::tokio::Transport::new(tcp_config.clone());
-			let desktop_trans = websocket::WsConfig::new(tcp_trans)
-				.or_transport(tcp::tokio::Transport::new(tcp_config));
+			let mut ws_trans = websocket::WsConfig::new(tcp_trans);
+			if let Some(tls) = websocket_tls {
+				ws_trans.set_tls_config(tls);
+			}
+			let desktop_trans = ws_trans.or_transport(tcp::tokio::Transport::new(tcp_config));
 			Either::Right(desktop_trans)
 		})
 	} else {
@@ -85,3 +97,16 @@ pub fn build_transport(
 
 	transport.with_bandwidth_logging()
 }
+
+/// Build the TLS configuration used to listen on `/wss` addresses.
+pub fn websocket_tls_config(config: &WebSocketTlsConfig) -> io::Result<websocket::tls::Config> {
+	let (certificate_chain, private_key) = config.load()?;
+
+	websocket::tls::Config::new(
+		websocket::tls::PrivateKey::new(private_key.secret_der().to_vec()),
+		certificate_chain
+			.into_iter()
+			.map(|certificate| websocket::tls::Certificate::new(certificate.to_vec())),
+	)
+	.map_err(io::Error::other)
+}
@@ -412,6 +412,17 @@ where
 		))
 	}
 
+	/// Replace the block announce notification service of the engine.
+	///
+	/// Allows driving the engine with recorded network traffic instead of the network backend.
+	pub fn with_notification_service(
+		mut self,
+		notification_service: Box<dyn NotificationService>,
+	) -> Self {
+		self.notification_service = notification_service;
+		self
+	}
+
 	fn update_peer_info(
 		&mut self,
 		peer_id: &PeerId,
@@ -23,6 +23,7 @@ mod block_import;
 mod conformance;
 #[cfg(test)]
 mod fuzz;
+pub mod replay;
 #[cfg(test)]
 mod service;
 #[cfg(test)]
@@ -0,0 +1,597 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Replay of network traffic recorded by [`sc_network::recorder`].
+//!
+//! [`ReplayNotificationService`] implements [`NotificationService`] on top of a [`Recording`]
+//! and can be handed to a notification protocol handler in place of the service returned by the
+//! network backend:
+//!
+//! - `SyncingEngine` with `SyncingEngine::with_notification_service()`,
+//! - the transactions handler with `TransactionsHandlerPrototype::with_notification_service()`,
+//! - GRANDPA gossip through the `notification_service` of `GrandpaParams`.
+//!
+//! The recorded inbound events are delivered one by one in the order they were recorded, without
+//! any timing involved, and the notifications sent by the handler are collected so the test can
+//! inspect them. Once the recording is exhausted, [`NotificationService::next_event()`] returns
+//! `None`, as if the protocol had been shut down, which terminates the handler.
+//!
+//! Recorded inbound requests can be fed to a request-response handler with
+//! [`replay_requests()`]. Responses to the block requests sent by `SyncingEngine` are replayed by
+//! [`ReplayBlockDownloader`], which is given to the syncing strategy in place of the block
+//! downloader returned by `BlockRequestHandler::new()`.
+
+use sc_network::{
+	config::{IncomingRequest, OutgoingResponse},
+	recorder::{Direction, MessageKind, RecordedMessage, Recording},
+	request_responses::RequestFailure,
+	service::traits::{MessageSink, NotificationEvent, NotificationService, ValidationResult},
+	types::ProtocolName,
+	PeerId,
+};
+use sc_network_common::sync::message::{BlockData, BlockRequest};
+use sc_network_sync::block_relay_protocol::{BlockDownloader, BlockResponseError};
+use sp_runtime::traits::Block as BlockT;
+
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	sync::Arc,
+};
+
+/// Notifications sent by the handler under test.
+pub type SentNotifications = Arc<Mutex<Vec<(PeerId, Vec<u8>)>>>;
+
+/// [`NotificationService`] replaying the inbound events of a recording.
+#[derive(Debug)]
+pub struct ReplayNotificationService {
+	/// Protocol name.
+	protocol: ProtocolName,
+
+	/// Recorded messages of the protocol which have not been replayed yet.
+	messages: VecDeque<RecordedMessage>,
+
+	/// Peers whose inbound substream was rejected by the handler.
+	///
+	/// Their messages are skipped until the substream is opened again.
+	rejected: HashSet<PeerId>,
+
+	/// Inbound substream waiting for the handler's validation result.
+	pending_validation: Option<PendingValidation>,
+
+	/// Notifications sent by the handler.
+	sent: SentNotifications,
+}
+
+/// Inbound substream reported to the handler for validation.
+#[derive(Debug)]
+struct PendingValidation {
+	peer: PeerId,
+	handshake: Vec<u8>,
+	result_rx: tokio::sync::oneshot::Receiver<ValidationResult>,
+}
+
+impl ReplayNotificationService {
+	/// Create new [`ReplayNotificationService`] replaying the messages of `protocol` in
+	/// `recording`.
+	pub fn new(recording: &Recording, protocol: impl Into<ProtocolName>) -> Self {
+		let protocol = protocol.into();
+		let messages = recording.protocol(&protocol).cloned().collect();
+
+		Self {
+			protocol,
+			messages,
+			rejected: HashSet::new(),
+			pending_validation: None,
+			sent: Default::default(),
+		}
+	}
+
+	/// Get a handle to the notifications sent by the handler under test.
+	pub fn sent(&self) -> SentNotifications {
+		self.sent.clone()
+	}
+
+	/// Number of recorded messages which have not been replayed yet.
+	pub fn remaining(&self) -> usize {
+		self.messages.len()
+	}
+}
+
+#[async_trait::async_trait]
+impl NotificationService for ReplayNotificationService {
+	async fn open_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+		Ok(())
+	}
+
+	async fn close_substream(&mut self, _peer: PeerId) -> Result<(), ()> {
+		Ok(())
+	}
+
+	fn send_sync_notification(&mut self, peer: &PeerId, notification: Vec<u8>) {
+		self.sent.lock().push((*peer, notification));
+	}
+
+	async fn send_async_notification(
+		&mut self,
+		peer: &PeerId,
+		notification: Vec<u8>,
+	) -> Result<(), sc_network::error::Error> {
+		self.sent.lock().push((*peer, notification));
+		Ok(())
+	}
+
+	async fn set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+		Ok(())
+	}
+
+	fn try_set_handshake(&mut self, _handshake: Vec<u8>) -> Result<(), ()> {
+		Ok(())
+	}
+
+	async fn next_event(&mut self) -> Option<NotificationEvent> {
+		// The handler under test is given a chance to reject an inbound substream, as it would be
+		// on a live network, before the substream is reported open.
+		if let Some(PendingValidation { peer, handshake, result_rx }) =
+			self.pending_validation.take()
+		{
+			match result_rx.await {
+				Ok(ValidationResult::Accept) => {
+					self.rejected.remove(&peer);
+
+					return Some(NotificationEvent::NotificationStreamOpened {
+						peer,
+						direction: Direction::Inbound.into(),
+						handshake,
+						negotiated_fallback: None,
+					})
+				},
+				_ => {
+					self.rejected.insert(peer);
+				},
+			}
+		}
+
+		loop {
+			let message = self.messages.pop_front()?;
+			let Some(peer) = message.peer_id() else {
+				log::warn!(target: "sync", "Skipping recorded message with invalid peer ID");
+				continue
+			};
+
+			match (message.kind, message.direction) {
+				(MessageKind::SubstreamOpened, Direction::Inbound) => {
+					let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+					self.pending_validation = Some(PendingValidation {
+						peer,
+						handshake: message.payload.clone(),
+						result_rx,
+					});
+
+					return Some(NotificationEvent::ValidateInboundSubstream {
+						peer,
+						handshake: message.payload,
+						result_tx,
+					})
+				},
+				(MessageKind::SubstreamOpened, Direction::Outbound) => {
+					self.rejected.remove(&peer);
+
+					return Some(NotificationEvent::NotificationStreamOpened {
+						peer,
+						direction: message.direction.into(),
+						handshake: message.payload,
+						negotiated_fallback: None,
+					})
+				},
+				(MessageKind::SubstreamClosed, _) => {
+					if !self.rejected.remove(&peer) {
+						return Some(NotificationEvent::NotificationStreamClosed { peer })
+					}
+				},
+				(MessageKind::Notification, Direction::Inbound) => {
+					if !self.rejected.contains(&peer) {
+						return Some(NotificationEvent::NotificationReceived {
+							peer,
+							notification: message.payload,
+						})
+					}
+				},
+				_ => {},
+			}
+		}
+	}
+
+	fn clone(&mut self) -> Result<Box<dyn NotificationService>, ()> {
+		Ok(Box::new(Self {
+			protocol: self.protocol.clone(),
+			messages: self.messages.clone(),
+			rejected: self.rejected.clone(),
+			pending_validation: None,
+			sent: self.sent.clone(),
+		}))
+	}
+
+	fn protocol(&self) -> &ProtocolName {
+		&self.protocol
+	}
+
+	fn message_sink(&self, peer: &PeerId) -> Option<Box<dyn MessageSink>> {
+		Some(Box::new(ReplayMessageSink { peer: *peer, sent: self.sent.clone() }))
+	}
+}
+
+/// [`MessageSink`] collecting the notifications sent to `peer`.
+struct ReplayMessageSink {
+	peer: PeerId,
+	sent: SentNotifications,
+}
+
+#[async_trait::async_trait]
+impl MessageSink for ReplayMessageSink {
+	fn send_sync_notification(&self, notification: Vec<u8>) {
+		self.sent.lock().push((self.peer, notification));
+	}
+
+	async fn send_async_notification(
+		&self,
+		notification: Vec<u8>,
+	) -> Result<(), sc_network::error::Error> {
+		self.sent.lock().push((self.peer, notification));
+		Ok(())
+	}
+}
+
+/// Feed the inbound requests of `protocol` in `recording` to a request-response handler, one at a
+/// time, and collect its responses.
+///
+/// `sender` is the sending half of the inbound queue given to the handler, i.e. the receiver
+/// returned by the handler's constructor is expected to be registered with this sender.
+pub async fn replay_requests(
+	recording: &Recording,
+	protocol: &str,
+	sender: &async_channel::Sender<IncomingRequest>,
+) -> Vec<(PeerId, Result<Vec<u8>, ()>)> {
+	let mut responses = Vec::new();
+
+	for message in recording.protocol(protocol) {
+		if message.kind != MessageKind::Request || message.direction != Direction::Inbound {
+			continue
+		}
+		let Some(peer) = message.peer_id() else { continue };
+
+		let (pending_response, rx) = oneshot::channel();
+		if sender
+			.send(IncomingRequest { peer, payload: message.payload.clone(), pending_response })
+			.await
+			.is_err()
+		{
+			break
+		}
+
+		let result = rx.await.map_or(Err(()), |response: OutgoingResponse| response.result);
+		responses.push((peer, result));
+	}
+
+	responses
+}
+
+/// [`BlockDownloader`] answering block requests with the responses of a recording.
+///
+/// Failed requests are not recorded, so the recorded responses of each peer are handed out in the
+/// order they were received, regardless of the request. Requests to peers without any remaining
+/// response fail with [`RequestFailure::Refused`]. Decoding the responses is left to the wrapped
+/// downloader.
+#[derive(Debug)]
+pub struct ReplayBlockDownloader<B: BlockT> {
+	/// Downloader decoding the responses.
+	inner: Arc<dyn BlockDownloader<B>>,
+
+	/// Recorded responses which have not been replayed yet, per peer.
+	responses: Mutex<HashMap<PeerId, VecDeque<Vec<u8>>>>,
+}
+
+impl<B: BlockT> ReplayBlockDownloader<B> {
+	/// Create new [`ReplayBlockDownloader`] replaying the responses recorded for the protocol of
+	/// `inner`.
+	pub fn new(recording: &Recording, inner: Arc<dyn BlockDownloader<B>>) -> Self {
+		let mut responses = HashMap::<_, VecDeque<_>>::new();
+
+		for message in recording.protocol(inner.protocol_name()) {
+			if message.kind != MessageKind::Response || message.direction != Direction::Inbound {
+				continue
+			}
+			let Some(peer) = message.peer_id() else { continue };
+
+			responses.entry(peer).or_default().push_back(message.payload.clone());
+		}
+
+		Self { inner, responses: Mutex::new(responses) }
+	}
+
+	/// Number of recorded responses which have not been replayed yet.
+	pub fn remaining(&self) -> usize {
+		self.responses.lock().values().map(VecDeque::len).sum()
+	}
+}
+
+#[async_trait::async_trait]
+impl<B: BlockT> BlockDownloader<B> for ReplayBlockDownloader<B> {
+	fn protocol_name(&self) -> &ProtocolName {
+		self.inner.protocol_name()
+	}
+
+	async fn download_blocks(
+		&self,
+		who: PeerId,
+		_request: BlockRequest<B>,
+	) -> Result<Result<(Vec<u8>, ProtocolName), RequestFailure>, oneshot::Canceled> {
+		let response =
+			self.responses.lock().get_mut(&who).and_then(|responses| responses.pop_front());
+
+		Ok(response
+			.map(|response| (response, self.inner.protocol_name().clone()))
+			.ok_or(RequestFailure::Refused))
+	}
+
+	fn block_response_into_blocks(
+		&self,
+		request: &BlockRequest<B>,
+		response: Vec<u8>,
+	) -> Result<Vec<BlockData<B>>, BlockResponseError> {
+		self.inner.block_response_into_blocks(request, response)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::PassThroughVerifier;
+	use sc_consensus::{BasicQueue, ImportQueue};
+	use sc_network::{
+		config::{FullNetworkConfiguration, NetworkConfiguration, ProtocolId, Role},
+		NetworkWorker, NotificationMetrics,
+	};
+	use sc_network_common::{
+		role::Roles,
+		sync::message::{
+			BlockAnnouncesHandshake, BlockAttributes, Direction as SyncDirection, FromBlock,
+		},
+	};
+	use sc_network_sync::{
+		block_request_handler::BlockRequestHandler,
+		engine::SyncingEngine,
+		mock::MockBlockDownloader,
+		service::network::NetworkServiceProvider,
+		strategy::polkadot::{PolkadotSyncingStrategy, PolkadotSyncingStrategyConfig},
+	};
+	use sp_blockchain::HeaderBackend;
+	use sp_consensus::block_validation::DefaultBlockAnnounceValidator;
+	use sp_runtime::codec::Encode;
+	use std::time::Duration;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Hash},
+		TestClientBuilder, TestClientBuilderExt as _,
+	};
+
+	fn message(
+		peer: PeerId,
+		direction: Direction,
+		kind: MessageKind,
+		payload: u8,
+	) -> RecordedMessage {
+		RecordedMessage {
+			timestamp: 0,
+			protocol: "/foo/1".into(),
+			peer: peer.to_bytes(),
+			direction,
+			kind,
+			payload: vec![payload],
+		}
+	}
+
+	#[tokio::test]
+	async fn rejected_substream_is_skipped() {
+		let accepted = PeerId::random();
+		let rejected = PeerId::random();
+		let recording = Recording {
+			messages: vec![
+				message(rejected, Direction::Inbound, MessageKind::SubstreamOpened, 0),
+				message(accepted, Direction::Inbound, MessageKind::SubstreamOpened, 1),
+				message(rejected, Direction::Inbound, MessageKind::Notification, 2),
+				message(accepted, Direction::Inbound, MessageKind::Notification, 3),
+				message(accepted, Direction::Outbound, MessageKind::Notification, 4),
+				message(rejected, Direction::Inbound, MessageKind::SubstreamClosed, 0),
+			],
+		};
+		let mut service = ReplayNotificationService::new(&recording, "/foo/1");
+
+		let Some(NotificationEvent::ValidateInboundSubstream { peer, result_tx, .. }) =
+			service.next_event().await
+		else {
+			panic!("expected validation request");
+		};
+		assert_eq!(peer, rejected);
+		result_tx.send(ValidationResult::Reject).unwrap();
+
+		let Some(NotificationEvent::ValidateInboundSubstream { peer, result_tx, .. }) =
+			service.next_event().await
+		else {
+			panic!("expected validation request");
+		};
+		assert_eq!(peer, accepted);
+		result_tx.send(ValidationResult::Accept).unwrap();
+
+		assert!(matches!(
+			service.next_event().await,
+			Some(NotificationEvent::NotificationStreamOpened { peer, handshake, .. })
+				if peer == accepted && handshake == vec![1]
+		));
+		assert!(matches!(
+			service.next_event().await,
+			Some(NotificationEvent::NotificationReceived { peer, notification })
+				if peer == accepted && notification == vec![3]
+		));
+		assert!(service.next_event().await.is_none());
+		assert_eq!(service.remaining(), 0);
+	}
+
+	#[tokio::test]
+	async fn block_responses_are_replayed_per_peer() {
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+		let block_protocol = ProtocolName::from("/abcd/sync/2");
+		let recorded = |peer: PeerId, direction, kind, payload| RecordedMessage {
+			timestamp: 0,
+			protocol: block_protocol.to_string(),
+			peer: peer.to_bytes(),
+			direction,
+			kind,
+			payload,
+		};
+		let recording = Recording {
+			messages: vec![
+				recorded(peer1, Direction::Outbound, MessageKind::Request, vec![0]),
+				recorded(peer2, Direction::Outbound, MessageKind::Request, vec![0]),
+				recorded(peer2, Direction::Inbound, MessageKind::Response, vec![2]),
+				recorded(peer1, Direction::Inbound, MessageKind::Response, vec![1]),
+				recorded(peer1, Direction::Outbound, MessageKind::Request, vec![0]),
+				recorded(peer1, Direction::Inbound, MessageKind::Response, vec![3]),
+				// responses sent by the recording node and other protocols are not replayed
+				recorded(peer2, Direction::Outbound, MessageKind::Response, vec![4]),
+				message(peer2, Direction::Inbound, MessageKind::Response, 5),
+			],
+		};
+
+		let mut inner = MockBlockDownloader::<Block>::new();
+		inner.expect_protocol_name().return_const(block_protocol.clone());
+		let downloader = ReplayBlockDownloader::new(&recording, Arc::new(inner));
+		assert_eq!(downloader.remaining(), 3);
+
+		let request = BlockRequest::<Block> {
+			id: 0,
+			fields: BlockAttributes::HEADER,
+			from: FromBlock::Number(1),
+			direction: SyncDirection::Ascending,
+			max: None,
+		};
+		let download = |peer| downloader.download_blocks(peer, request.clone());
+
+		assert_eq!(download(peer1).await.unwrap().unwrap(), (vec![1], block_protocol.clone()));
+		assert_eq!(download(peer2).await.unwrap().unwrap(), (vec![2], block_protocol.clone()));
+		assert_eq!(download(peer1).await.unwrap().unwrap(), (vec![3], block_protocol.clone()));
+		assert!(matches!(download(peer1).await, Ok(Err(RequestFailure::Refused))));
+		assert!(matches!(download(PeerId::random()).await, Ok(Err(RequestFailure::Refused))));
+		assert_eq!(downloader.remaining(), 0);
+	}
+
+	#[tokio::test]
+	async fn replayed_handshakes_are_validated_by_syncing_engine() {
+		let client =
+			Arc::new(TestClientBuilder::with_default_backend().build_with_longest_chain().0);
+		let genesis_hash = client.info().genesis_hash;
+		let network_config = NetworkConfiguration::new_local();
+		let full_net_config = FullNetworkConfiguration::<_, _, NetworkWorker<Block, Hash>>::new(
+			&network_config,
+			None,
+		);
+		let peer_store_handle = full_net_config.peer_store_handle();
+		let protocol_id = ProtocolId::from("test-protocol-name");
+
+		let network_provider = NetworkServiceProvider::new();
+		let block_relay_params = BlockRequestHandler::new::<NetworkWorker<Block, Hash>>(
+			network_provider.handle(),
+			&protocol_id,
+			None,
+			client.clone(),
+			50,
+		);
+		let import_queue = BasicQueue::new(
+			PassThroughVerifier::new(false),
+			Box::new(client.clone()),
+			None,
+			&sp_core::testing::TaskExecutor::new(),
+			None,
+		);
+		let syncing_config = PolkadotSyncingStrategyConfig {
+			mode: network_config.sync_mode,
+			max_parallel_downloads: network_config.max_parallel_downloads,
+			max_blocks_per_request: network_config.max_blocks_per_request,
+			metrics_registry: None,
+			state_request_protocol_name: "/state/1".into(),
+			block_downloader: block_relay_params.downloader,
+		};
+		let syncing_strategy = Box::new(
+			PolkadotSyncingStrategy::new(syncing_config, client.clone(), None, None).unwrap(),
+		);
+
+		let (engine, sync_service, block_announce_config) = SyncingEngine::new(
+			Roles::from(&Role::Full),
+			client.clone(),
+			None,
+			NotificationMetrics::new(None),
+			&full_net_config,
+			protocol_id,
+			None,
+			Box::new(DefaultBlockAnnounceValidator),
+			syncing_strategy,
+			network_provider.handle(),
+			import_queue.service(),
+			Arc::clone(&peer_store_handle),
+		)
+		.unwrap();
+
+		// One peer on our chain and one on a different chain, both dialed by the recording node.
+		let same_chain = PeerId::random();
+		let other_chain = PeerId::random();
+		let handshake = |genesis_hash| {
+			BlockAnnouncesHandshake::<Block>::build(Roles::FULL, 0, genesis_hash, genesis_hash)
+				.encode()
+		};
+		let protocol = block_announce_config.protocol_name().clone();
+		let opened = |peer: PeerId, genesis_hash| RecordedMessage {
+			timestamp: 0,
+			protocol: protocol.to_string(),
+			peer: peer.to_bytes(),
+			direction: Direction::Outbound,
+			kind: MessageKind::SubstreamOpened,
+			payload: handshake(genesis_hash),
+		};
+		let recording = Recording {
+			messages: vec![
+				opened(other_chain, Hash::repeat_byte(1)),
+				opened(same_chain, genesis_hash),
+			],
+		};
+
+		// The engine terminates once the recording is exhausted.
+		let service = ReplayNotificationService::new(&recording, protocol);
+		tokio::time::timeout(
+			Duration::from_secs(10),
+			engine.with_notification_service(Box::new(service)).run(),
+		)
+		.await
+		.expect("engine terminates when the recording is exhausted");
+
+		assert_eq!(peer_store_handle.peer_reputation(&other_chain), i32::MIN);
+		assert_eq!(peer_store_handle.peer_reputation(&same_chain), 0);
+		assert_eq!(sync_service.num_connected_peers(), 1);
+	}
+}
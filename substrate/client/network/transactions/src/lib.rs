@@ -167,6 +167,17 @@ impl TransactionsHandlerPrototype {
 		(Self { protocol_name, notification_service }, config)
 	}
 
+	/// Replace the notification service of the transactions protocol.
+	///
+	/// Allows driving the handler with recorded network traffic instead of the network backend.
+	pub fn with_notification_service(
+		mut self,
+		notification_service: Box<dyn NotificationService>,
+	) -> Self {
+		self.notification_service = notification_service;
+		self
+	}
+
 	/// Turns the prototype into the actual handler. Returns a controller that allows controlling
 	/// the behaviour of the handler while it's running.
 	///
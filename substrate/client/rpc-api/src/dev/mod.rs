@@ -27,6 +27,7 @@ use error::Error;
 use jsonrpsee::proc_macros::rpc;
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
 
 /// Statistics of a block returned by the `dev_getBlockStats` RPC.
 #[derive(Eq, PartialEq, Clone, Copy, Encode, Decode, Debug, TypeInfo, Serialize, Deserialize)]
@@ -48,6 +49,86 @@ pub struct BlockStats {
 	pub num_extrinsics: u64,
 }
 
+/// A storage key accessed while executing a block.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessedKey {
+	/// The storage key of the child trie the key belongs to, `None` for the main trie.
+	pub child: Option<Bytes>,
+	/// The storage key.
+	pub key: Bytes,
+	/// The pallet the key belongs to, if it could be found in the metadata.
+	pub pallet: Option<String>,
+	/// The storage item of the pallet the key belongs to.
+	pub item: Option<String>,
+}
+
+/// A range of storage keys observed by iterating the storage.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessedRange {
+	/// The first key of the range.
+	pub start: AccessedKey,
+	/// The first key after the range, `None` if the range is unbounded.
+	pub end: Option<Bytes>,
+}
+
+/// The storage accessed by an extrinsic or by the initialization or finalization of a block.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageAccesses {
+	/// The keys which were read.
+	pub reads: Vec<AccessedKey>,
+	/// The ranges of keys which were iterated over.
+	pub range_reads: Vec<AccessedRange>,
+	/// The keys which were written.
+	pub writes: Vec<AccessedKey>,
+	/// The prefixes which were cleared.
+	pub prefix_writes: Vec<AccessedKey>,
+	/// Whether the storage root was calculated, which depends on every key of the storage.
+	pub reads_storage_root: bool,
+}
+
+/// A key accessed by more than one extrinsic of a block.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotKey {
+	/// The key.
+	pub key: AccessedKey,
+	/// The indices of the extrinsics which read the key.
+	pub readers: Vec<u32>,
+	/// The indices of the extrinsics which wrote the key.
+	pub writers: Vec<u32>,
+}
+
+/// An extrinsic which depends on or overwrites the writes of an earlier extrinsic of the block.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contention {
+	/// The index of the later extrinsic.
+	pub extrinsic: u32,
+	/// The index of the earlier extrinsic.
+	pub writer: u32,
+	/// The keys written by the earlier extrinsic and accessed by the later one.
+	pub keys: Vec<AccessedKey>,
+}
+
+/// Storage accesses of a block returned by the `dev_getBlockStorageAccesses` RPC.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStorageAccesses {
+	/// The storage accessed while initializing the block.
+	pub initialization: StorageAccesses,
+	/// The storage accessed by every extrinsic of the block, in order.
+	pub extrinsics: Vec<StorageAccesses>,
+	/// The storage accessed while finalizing the block.
+	pub finalization: StorageAccesses,
+	/// The keys accessed by more than one extrinsic, the most contended ones first.
+	pub hot_keys: Vec<HotKey>,
+	/// The extrinsics which can not be executed in parallel to earlier ones.
+	pub contention: Vec<Contention>,
+}
+
 /// Substrate dev API.
 ///
 /// This API contains unstable and unsafe methods only meant for development nodes. They
@@ -61,4 +142,16 @@ pub trait DevApi<Hash> {
 	/// this function will return `None`.
 	#[method(name = "dev_getBlockStats", with_extensions)]
 	fn block_stats(&self, block_hash: Hash) -> Result<Option<BlockStats>, Error>;
+
+	/// Reexecute the specified `block_hash` and record the storage accessed by each of its
+	/// extrinsics.
+	///
+	/// Keys are annotated with the pallet and storage item they belong to, using the metadata of
+	/// the runtime. Like for `dev_getBlockStats`, `None` is returned if either the specified
+	/// block or its parent is not available at the queried node.
+	#[method(name = "dev_getBlockStorageAccesses", with_extensions)]
+	fn block_storage_accesses(
+		&self,
+		block_hash: Hash,
+	) -> Result<Option<BlockStorageAccesses>, Error>;
 }
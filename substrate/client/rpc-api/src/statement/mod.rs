@@ -57,4 +57,17 @@ pub trait StatementApi {
 	/// Remove a statement from the store.
 	#[method(name = "statement_remove")]
 	fn remove(&self, statement_hash: [u8; 32]) -> RpcResult<()>;
+
+	/// Subscribe to SCALE-encoded statements which include all topics and whose decryption key is
+	/// identified as `dest`, or have no `DecryptionKey` field if `dest` is not given.
+	///
+	/// Matching statements already in the store are sent first, followed by new ones as they are
+	/// accepted into the store.
+	#[subscription(
+		name = "statement_subscribe" => "statement_statement",
+		unsubscribe = "statement_unsubscribe",
+		item = Bytes,
+		with_extensions,
+	)]
+	fn subscribe(&self, match_all_topics: Vec<[u8; 32]>, dest: Option<[u8; 32]>);
 }
@@ -23,9 +23,14 @@
 mod tests;
 
 use jsonrpsee::Extensions;
+use sc_block_builder::{BlockBuilderApi, QualifiedKey};
 use sc_client_api::{BlockBackend, HeaderBackend};
-use sc_rpc_api::{check_if_safe, dev::error::Error};
-use sp_api::{ApiExt, Core, ProvideRuntimeApi};
+use sc_rpc_api::{
+	check_if_safe,
+	dev::{error::Error, AccessedKey, AccessedRange, Contention, HotKey, StorageAccesses},
+};
+use sc_runtime_utilities::StorageKeyNames;
+use sp_api::{ApiExt, Core, Metadata, ProvideRuntimeApi};
 use sp_core::Encode;
 use sp_runtime::{
 	generic::DigestItem,
@@ -36,7 +41,7 @@ use std::{
 	sync::Arc,
 };
 
-pub use sc_rpc_api::dev::{BlockStats, DevApiServer};
+pub use sc_rpc_api::dev::{BlockStats, BlockStorageAccesses, DevApiServer};
 
 type HasherOf<Block> = <<Block as BlockT>::Header as Header>::Hashing;
 
@@ -62,7 +67,7 @@ where
 		+ Send
 		+ Sync
 		+ 'static,
-	Client::Api: Core<Block>,
+	Client::Api: Core<Block> + BlockBuilderApi<Block> + Metadata<Block>,
 {
 	fn block_stats(
 		&self,
@@ -113,4 +118,105 @@ where
 			.encoded_size() as u64;
 		Ok(Some(BlockStats { witness_len, witness_compact_len, block_len, num_extrinsics }))
 	}
+
+	fn block_storage_accesses(
+		&self,
+		ext: &Extensions,
+		hash: Block::Hash,
+	) -> Result<Option<BlockStorageAccesses>, Error> {
+		check_if_safe(ext)?;
+
+		let Some(block) =
+			self.client.block(hash).map_err(|e| Error::BlockQueryError(Box::new(e)))?
+		else {
+			return Ok(None)
+		};
+		let parent_hash = *block.block.header().parent_hash();
+		if self
+			.client
+			.header(parent_hash)
+			.map_err(|e| Error::BlockQueryError(Box::new(e)))?
+			.is_none()
+		{
+			return Ok(None)
+		}
+
+		let accesses = sc_block_builder::record_storage_accesses(&*self.client, block.block)
+			.map_err(|_| Error::BlockExecutionFailed)?;
+
+		// The block is executed by the runtime of its parent.
+		let runtime_api = self.client.runtime_api();
+		let names = runtime_api
+			.metadata_at_version(parent_hash, 15)
+			.ok()
+			.flatten()
+			.or_else(|| runtime_api.metadata(parent_hash).ok())
+			.and_then(|metadata| StorageKeyNames::from_metadata(&metadata).ok())
+			.unwrap_or_default();
+		let key = |(child, key): &QualifiedKey| accessed_key(&names, child, key);
+
+		Ok(Some(BlockStorageAccesses {
+			initialization: storage_accesses(&names, &accesses.initialization),
+			extrinsics: accesses
+				.extrinsics
+				.iter()
+				.map(|accesses| storage_accesses(&names, accesses))
+				.collect(),
+			finalization: storage_accesses(&names, &accesses.finalization),
+			hot_keys: accesses
+				.hot_keys()
+				.into_iter()
+				.map(|(accessed, usage)| HotKey {
+					key: key(&accessed),
+					readers: usage.readers.into_iter().collect(),
+					writers: usage.writers.into_iter().collect(),
+				})
+				.collect(),
+			contention: accesses
+				.contention()
+				.into_iter()
+				.map(|contention| Contention {
+					extrinsic: contention.extrinsic,
+					writer: contention.writer,
+					keys: contention.keys.iter().map(key).collect(),
+				})
+				.collect(),
+		}))
+	}
+}
+
+/// Describe `key` using the storage items of the runtime's metadata.
+fn accessed_key(names: &StorageKeyNames, child: &Option<Vec<u8>>, key: &[u8]) -> AccessedKey {
+	// Keys of child tries are not described by the metadata.
+	let name = child.is_none().then(|| names.name(key)).flatten();
+
+	AccessedKey {
+		child: child.clone().map(Into::into),
+		key: key.to_vec().into(),
+		pallet: name.map(|name| name.pallet.clone()),
+		item: name.map(|name| name.item.clone()),
+	}
+}
+
+/// Convert the recorded `accesses` into their RPC representation.
+fn storage_accesses(
+	names: &StorageKeyNames,
+	accesses: &sc_block_builder::StorageAccesses,
+) -> StorageAccesses {
+	StorageAccesses {
+		reads: accesses.reads().map(|(child, key)| accessed_key(names, child, key)).collect(),
+		range_reads: accesses
+			.range_reads()
+			.map(|(child, start, end)| AccessedRange {
+				start: accessed_key(names, child, start),
+				end: end.cloned().map(Into::into),
+			})
+			.collect(),
+		writes: accesses.writes().map(|(child, key)| accessed_key(names, child, key)).collect(),
+		prefix_writes: accesses
+			.prefix_writes()
+			.map(|(child, prefix)| accessed_key(names, child, prefix))
+			.collect(),
+		reads_storage_root: accesses.reads_storage_root(),
+	}
 }
@@ -21,7 +21,10 @@ use crate::DenyUnsafe;
 use sc_block_builder::BlockBuilderBuilder;
 use sp_blockchain::HeaderBackend;
 use sp_consensus::BlockOrigin;
-use substrate_test_runtime_client::{prelude::*, runtime::Block};
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block, ExtrinsicBuilder},
+};
 
 #[tokio::test]
 async fn block_stats_work() {
@@ -76,6 +79,64 @@ async fn block_stats_work() {
 	);
 }
 
+#[tokio::test]
+async fn block_storage_accesses_work() {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let mut api = <Dev<Block, _>>::new(client.clone()).into_rpc();
+	api.extensions_mut().insert(DenyUnsafe::No);
+
+	let mut block_builder = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(client.chain_info().genesis_hash)
+		.with_parent_block_number(0)
+		.build()
+		.unwrap();
+	block_builder
+		.push(ExtrinsicBuilder::new_storage_change(b"a".to_vec(), Some(b"1".to_vec())).build())
+		.unwrap();
+	block_builder.push(ExtrinsicBuilder::new_storage_change(b"a".to_vec(), None).build()).unwrap();
+	let block = block_builder.build().unwrap().block;
+	client.import(BlockOrigin::Own, block).await.unwrap();
+
+	// Can't record the accesses of a block without a parent.
+	assert_eq!(
+		api.call::<_, Option<BlockStorageAccesses>>(
+			"dev_getBlockStorageAccesses",
+			[client.genesis_hash()]
+		)
+		.await
+		.unwrap(),
+		None
+	);
+
+	let accesses = api
+		.call::<_, Option<BlockStorageAccesses>>(
+			"dev_getBlockStorageAccesses",
+			[client.info().best_hash],
+		)
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(accesses.extrinsics.len(), 2);
+	let key_a = sp_core::Bytes(b"a".to_vec());
+	assert!(accesses.extrinsics.iter().all(|accesses| accesses
+		.writes
+		.iter()
+		.any(|written| written.key == key_a && written.pallet.is_none())));
+
+	// Every extrinsic updates the extrinsic count and the weight of the block.
+	assert!(accesses
+		.hot_keys
+		.iter()
+		.any(|hot_key| hot_key.key.pallet.as_deref() == Some("System")));
+	let contention = accesses
+		.contention
+		.iter()
+		.find(|contention| contention.extrinsic == 1 && contention.writer == 0)
+		.unwrap();
+	assert!(contention.keys.iter().any(|key| key.key == key_a));
+}
+
 #[tokio::test]
 async fn deny_unsafe_works() {
 	let client = Arc::new(substrate_test_runtime_client::new());
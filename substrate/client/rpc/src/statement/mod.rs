@@ -18,26 +18,41 @@
 
 //! Substrate statement store API.
 
+use crate::{
+	utils::{spawn_subscription_task, BoundedVecDeque, Buffer, PendingSubscription},
+	SubscriptionTaskExecutor,
+};
 use codec::{Decode, Encode};
+use futures::channel::mpsc;
+use parking_lot::Mutex;
 use jsonrpsee::{
 	core::{async_trait, RpcResult},
-	Extensions,
+	types::ErrorObject,
+	Extensions, PendingSubscriptionSink,
 };
 /// Re-export the API for backward compatibility.
 pub use sc_rpc_api::statement::{error::Error, StatementApiServer};
 use sp_core::Bytes;
-use sp_statement_store::{StatementSource, SubmitResult};
+use sp_statement_store::{StatementSource, SubmitResult, SubscriptionFilter};
 use std::sync::Arc;
 
+/// Number of new statements buffered for a subscriber before it is dropped for being too slow.
+const SUBSCRIPTION_BUFFER_SIZE: usize = 128;
+
 /// Statement store API
 pub struct StatementStore {
 	store: Arc<dyn sp_statement_store::StatementStore>,
+	/// Executor to spawn subscriptions.
+	executor: SubscriptionTaskExecutor,
 }
 
 impl StatementStore {
 	/// Create new instance of Offchain API.
-	pub fn new(store: Arc<dyn sp_statement_store::StatementStore>) -> Self {
-		StatementStore { store }
+	pub fn new(
+		store: Arc<dyn sp_statement_store::StatementStore>,
+		executor: SubscriptionTaskExecutor,
+	) -> Self {
+		StatementStore { store, executor }
 	}
 }
 
@@ -103,4 +118,62 @@ impl StatementApiServer for StatementStore {
 	fn remove(&self, hash: [u8; 32]) -> RpcResult<()> {
 		Ok(self.store.remove(&hash).map_err(|e| Error::StatementStore(e.to_string()))?)
 	}
+
+	fn subscribe(
+		&self,
+		pending: PendingSubscriptionSink,
+		ext: &Extensions,
+		match_all_topics: Vec<[u8; 32]>,
+		dest: Option<[u8; 32]>,
+	) {
+		if let Err(err) = sc_rpc_api::check_if_safe(ext) {
+			spawn_subscription_task(&self.executor, pending.reject(ErrorObject::from(err)));
+			return
+		}
+
+		let (mut tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER_SIZE);
+		let filter = SubscriptionFilter { match_all_topics, dest };
+		// Statements already in the store are collected here while subscribing, so that they
+		// don't count against the buffer for new statements.
+		let existing = Arc::new(Mutex::new(Some(Vec::new())));
+		let collected = existing.clone();
+		// The store must never wait for a subscriber, slow subscribers are dropped instead.
+		let sink = Box::new(move |statement: &sp_statement_store::Statement| {
+			let statement = Bytes::from(statement.encode());
+			if let Some(existing) = collected.lock().as_mut() {
+				existing.push(statement);
+				return true
+			}
+			match tx.try_send(statement) {
+				Ok(()) => true,
+				Err(e) => {
+					if e.is_full() {
+						log::debug!(
+							target: "rpc",
+							"Dropping statement subscriber which doesn't keep up with new statements",
+						);
+					}
+					false
+				},
+			}
+		});
+
+		if let Err(e) = self.store.subscribe(filter, sink) {
+			let error = ErrorObject::from(Error::StatementStore(e.to_string()));
+			spawn_subscription_task(&self.executor, pending.reject(error));
+			return
+		}
+
+		let existing = existing.lock().take().unwrap_or_default();
+		let mut buffer = BoundedVecDeque::new(existing.len() + SUBSCRIPTION_BUFFER_SIZE);
+		for statement in existing {
+			let _ = buffer.push(statement);
+		}
+
+		let fut = async move {
+			PendingSubscription::from(pending).pipe_from_stream(rx, buffer).await;
+		};
+
+		spawn_subscription_task(&self.executor, fut);
+	}
 }
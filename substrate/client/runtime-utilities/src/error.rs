@@ -30,6 +30,8 @@ pub enum Error {
 	OpaqueMetadataNotFound,
 	#[error("Stable metadata version not found")]
 	StableMetadataVersionNotFound,
+	#[error("Unsupported metadata version {0}")]
+	UnsupportedMetadataVersion(u32),
 	#[error("WASM executor error: {0}")]
 	Executor(#[from] sc_executor_common::error::Error),
 }
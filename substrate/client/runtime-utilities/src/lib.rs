@@ -34,6 +34,9 @@ use sp_wasm_interface::HostFunctions;
 use std::borrow::Cow;
 
 pub mod error;
+mod storage_keys;
+
+pub use storage_keys::{StorageItemName, StorageKeyNames};
 
 /// Fetches the latest metadata from the given runtime blob.
 pub fn fetch_latest_metadata_from_code_blob<HF: HostFunctions>(
@@ -0,0 +1,91 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Naming of storage keys using the metadata of a FRAME runtime.
+
+use crate::error::{Error, Result};
+use codec::Decode;
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use sp_crypto_hashing::twox_128;
+use std::collections::HashMap;
+
+/// A storage item declared by a pallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageItemName {
+	/// The storage prefix of the pallet, usually the name of the pallet.
+	pub pallet: String,
+	/// The name of the storage item.
+	pub item: String,
+}
+
+/// Maps storage keys to the storage items of the pallets they belong to.
+///
+/// FRAME storage keys start with `twox_128(pallet_prefix) ++ twox_128(item_name)`, which is used
+/// to look up the storage item of a key.
+#[derive(Debug, Default, Clone)]
+pub struct StorageKeyNames {
+	items: HashMap<[u8; 32], StorageItemName>,
+}
+
+impl StorageKeyNames {
+	/// Create a new instance from the SCALE encoded metadata of the runtime.
+	///
+	/// Only metadata versions 14 and 15 are supported.
+	pub fn from_metadata(metadata: &[u8]) -> Result<Self> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])?;
+
+		let names: Vec<(String, Vec<String>)> = match metadata.1 {
+			RuntimeMetadata::V14(metadata) => metadata
+				.pallets
+				.into_iter()
+				.filter_map(|pallet| pallet.storage)
+				.map(|storage| {
+					(storage.prefix, storage.entries.into_iter().map(|entry| entry.name).collect())
+				})
+				.collect(),
+			RuntimeMetadata::V15(metadata) => metadata
+				.pallets
+				.into_iter()
+				.filter_map(|pallet| pallet.storage)
+				.map(|storage| {
+					(storage.prefix, storage.entries.into_iter().map(|entry| entry.name).collect())
+				})
+				.collect(),
+			other => return Err(Error::UnsupportedMetadataVersion(other.version())),
+		};
+
+		let mut items = HashMap::new();
+		for (pallet, entries) in names {
+			let pallet_hash = twox_128(pallet.as_bytes());
+			for item in entries {
+				let mut prefix = [0u8; 32];
+				prefix[..16].copy_from_slice(&pallet_hash);
+				prefix[16..].copy_from_slice(&twox_128(item.as_bytes()));
+				items.insert(prefix, StorageItemName { pallet: pallet.clone(), item });
+			}
+		}
+
+		Ok(Self { items })
+	}
+
+	/// Returns the storage item the given `key` belongs to.
+	pub fn name(&self, key: &[u8]) -> Option<&StorageItemName> {
+		let prefix: [u8; 32] = key.get(..32)?.try_into().ok()?;
+		self.items.get(&prefix)
+	}
+}
@@ -40,7 +40,7 @@ use sc_executor::{
 	sp_wasm_interface::HostFunctions, HeapAllocStrategy, NativeExecutionDispatch, RuntimeVersionOf,
 	WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY,
 };
-use sc_keystore::LocalKeystore;
+use sc_keystore::{LocalKeystore, RemoteKeystore};
 use sc_network::{
 	config::{FullNetworkConfiguration, ProtocolId, SyncMode},
 	multiaddr::Protocol,
@@ -109,29 +109,38 @@ pub type TFullCallExecutor<TBl, TExec> = crate::client::LocalCallExecutor<TBl, B
 type TFullParts<TBl, TRtApi, TExec> =
 	(TFullClient<TBl, TRtApi, TExec>, Arc<TFullBackend<TBl>>, KeystoreContainer, TaskManager);
 
-/// Construct a local keystore shareable container
-pub struct KeystoreContainer(Arc<LocalKeystore>);
+/// Construct a keystore shareable container
+pub struct KeystoreContainer {
+	keystore: KeystorePtr,
+	local_keystore: Arc<LocalKeystore>,
+}
 
 impl KeystoreContainer {
 	/// Construct KeystoreContainer
 	pub fn new(config: &KeystoreConfig) -> Result<Self, Error> {
-		let keystore = Arc::new(match config {
+		let local_keystore = Arc::new(match config {
 			KeystoreConfig::Path { path, password } =>
 				LocalKeystore::open(path.clone(), password.clone())?,
-			KeystoreConfig::InMemory => LocalKeystore::in_memory(),
+			KeystoreConfig::InMemory | KeystoreConfig::Remote(_) => LocalKeystore::in_memory(),
 		});
+		let keystore: KeystorePtr = match config {
+			KeystoreConfig::Remote(address) => Arc::new(RemoteKeystore::connect(address.clone())?),
+			_ => local_keystore.clone(),
+		};
 
-		Ok(Self(keystore))
+		Ok(Self { keystore, local_keystore })
 	}
 
 	/// Returns a shared reference to a dynamic `Keystore` trait implementation.
 	pub fn keystore(&self) -> KeystorePtr {
-		self.0.clone()
+		self.keystore.clone()
 	}
 
 	/// Returns a shared reference to the local keystore .
+	///
+	/// When using a remote signer, this is an empty in-memory keystore.
 	pub fn local_keystore(&self) -> Arc<LocalKeystore> {
-		self.0.clone()
+		self.local_keystore.clone()
 	}
 }
 
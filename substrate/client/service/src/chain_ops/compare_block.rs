@@ -0,0 +1,802 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error::Error;
+use codec::Encode;
+use sc_client_api::{backend::Backend, BlockBackend, HeaderBackend};
+use sc_executor::{RuntimeVersion, RuntimeVersionOf};
+use sc_executor_common::runtime_blob::RuntimeBlob;
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, ChildInfo, StateVersion, TrackedStorageKey},
+	traits::{
+		CallContext, CodeExecutor, Externalities, ReadRuntimeVersionExt, RuntimeCode,
+		WrappedRuntimeCode,
+	},
+	Hasher,
+};
+use sp_crypto_hashing::{blake2_256, twox_128};
+use sp_externalities::{Extension, ExtensionStore, Extensions, MultiRemovalResults};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+use sp_state_machine::{Backend as StateBackend, Ext, OverlayedChanges};
+use std::{
+	any::{Any, TypeId},
+	collections::BTreeMap,
+	fmt,
+	sync::Arc,
+};
+
+/// A call from the runtime into the host which accesses the state.
+///
+/// Child tries are identified by their storage key and written values by their `blake2_256`
+/// hash, so that recording a block doesn't keep all the written values around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostCall {
+	/// Read of a storage value.
+	Storage(Vec<u8>),
+	/// Read of the hash of a storage value.
+	StorageHash(Vec<u8>),
+	/// Check whether a storage value exists.
+	ExistsStorage(Vec<u8>),
+	/// Read of a child storage value.
+	ChildStorage(Vec<u8>, Vec<u8>),
+	/// Read of the hash of a child storage value.
+	ChildStorageHash(Vec<u8>, Vec<u8>),
+	/// Check whether a child storage value exists.
+	ExistsChildStorage(Vec<u8>, Vec<u8>),
+	/// Lookup of the storage key following the given one.
+	NextStorageKey(Vec<u8>),
+	/// Lookup of the child storage key following the given one.
+	NextChildStorageKey(Vec<u8>, Vec<u8>),
+	/// Write (`Some`) or removal (`None`) of a storage value.
+	PlaceStorage(Vec<u8>, Option<[u8; 32]>),
+	/// Write (`Some`) or removal (`None`) of a child storage value.
+	PlaceChildStorage(Vec<u8>, Vec<u8>, Option<[u8; 32]>),
+	/// Append of a value to a storage value.
+	StorageAppend(Vec<u8>, [u8; 32]),
+	/// Removal of all storage values with the given prefix.
+	ClearPrefix(Vec<u8>, Option<u32>),
+	/// Removal of all child storage values with the given prefix.
+	ClearChildPrefix(Vec<u8>, Vec<u8>, Option<u32>),
+	/// Removal of a whole child trie.
+	KillChildStorage(Vec<u8>, Option<u32>),
+	/// Calculation of the storage root, together with the resulting root.
+	StorageRoot(Vec<u8>),
+	/// Calculation of a child storage root, together with the resulting root.
+	ChildStorageRoot(Vec<u8>, Vec<u8>),
+	/// Start of a storage transaction.
+	StartTransaction,
+	/// Rollback of a storage transaction.
+	RollbackTransaction,
+	/// Commit of a storage transaction.
+	CommitTransaction,
+	/// Write (`Some`) or removal (`None`) of an offchain storage value.
+	SetOffchainStorage(Vec<u8>, Option<[u8; 32]>),
+	/// Indexing of a transaction.
+	IndexTransaction(u32, Vec<u8>, u32),
+	/// Renewal of an indexed transaction.
+	RenewTransactionIndex(u32, Vec<u8>),
+}
+
+impl fmt::Display for HostCall {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn value(hash: &Option<[u8; 32]>) -> String {
+			match hash {
+				Some(hash) => format!("0x{}", HexDisplay::from(hash)),
+				None => "None".into(),
+			}
+		}
+
+		match self {
+			Self::Storage(key) => write!(f, "storage(0x{})", HexDisplay::from(key)),
+			Self::StorageHash(key) => write!(f, "storage_hash(0x{})", HexDisplay::from(key)),
+			Self::ExistsStorage(key) => write!(f, "exists_storage(0x{})", HexDisplay::from(key)),
+			Self::ChildStorage(child, key) => write!(
+				f,
+				"child_storage(0x{}, 0x{})",
+				HexDisplay::from(child),
+				HexDisplay::from(key)
+			),
+			Self::ChildStorageHash(child, key) => write!(
+				f,
+				"child_storage_hash(0x{}, 0x{})",
+				HexDisplay::from(child),
+				HexDisplay::from(key)
+			),
+			Self::ExistsChildStorage(child, key) => write!(
+				f,
+				"exists_child_storage(0x{}, 0x{})",
+				HexDisplay::from(child),
+				HexDisplay::from(key)
+			),
+			Self::NextStorageKey(key) => write!(f, "next_storage_key(0x{})", HexDisplay::from(key)),
+			Self::NextChildStorageKey(child, key) => write!(
+				f,
+				"next_child_storage_key(0x{}, 0x{})",
+				HexDisplay::from(child),
+				HexDisplay::from(key)
+			),
+			Self::PlaceStorage(key, hash) =>
+				write!(f, "place_storage(0x{}, {})", HexDisplay::from(key), value(hash)),
+			Self::PlaceChildStorage(child, key, hash) => write!(
+				f,
+				"place_child_storage(0x{}, 0x{}, {})",
+				HexDisplay::from(child),
+				HexDisplay::from(key),
+				value(hash)
+			),
+			Self::StorageAppend(key, hash) => write!(
+				f,
+				"storage_append(0x{}, 0x{})",
+				HexDisplay::from(key),
+				HexDisplay::from(hash)
+			),
+			Self::ClearPrefix(prefix, limit) =>
+				write!(f, "clear_prefix(0x{}, {:?})", HexDisplay::from(prefix), limit),
+			Self::ClearChildPrefix(child, prefix, limit) => write!(
+				f,
+				"clear_child_prefix(0x{}, 0x{}, {:?})",
+				HexDisplay::from(child),
+				HexDisplay::from(prefix),
+				limit
+			),
+			Self::KillChildStorage(child, limit) =>
+				write!(f, "kill_child_storage(0x{}, {:?})", HexDisplay::from(child), limit),
+			Self::StorageRoot(root) => write!(f, "storage_root() = 0x{}", HexDisplay::from(root)),
+			Self::ChildStorageRoot(child, root) => write!(
+				f,
+				"child_storage_root(0x{}) = 0x{}",
+				HexDisplay::from(child),
+				HexDisplay::from(root)
+			),
+			Self::StartTransaction => write!(f, "storage_start_transaction()"),
+			Self::RollbackTransaction => write!(f, "storage_rollback_transaction()"),
+			Self::CommitTransaction => write!(f, "storage_commit_transaction()"),
+			Self::SetOffchainStorage(key, hash) =>
+				write!(f, "set_offchain_storage(0x{}, {})", HexDisplay::from(key), value(hash)),
+			Self::IndexTransaction(index, hash, size) => write!(
+				f,
+				"storage_index_transaction({}, 0x{}, {})",
+				index,
+				HexDisplay::from(hash),
+				size
+			),
+			Self::RenewTransactionIndex(index, hash) => write!(
+				f,
+				"storage_renew_transaction_index({}, 0x{})",
+				index,
+				HexDisplay::from(hash)
+			),
+		}
+	}
+}
+
+/// A storage key changed by a block, optionally inside of a child trie.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChangedKey {
+	/// The storage key of the child trie, `None` for the main trie.
+	pub child_storage_key: Option<Vec<u8>>,
+	/// The changed key.
+	pub key: Vec<u8>,
+}
+
+impl fmt::Display for ChangedKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.child_storage_key {
+			Some(child) => write!(
+				f,
+				"0x{} in child trie 0x{}",
+				HexDisplay::from(&self.key),
+				HexDisplay::from(child)
+			),
+			None => write!(f, "0x{}", HexDisplay::from(&self.key)),
+		}
+	}
+}
+
+/// The outcome of executing a block with one build of the runtime.
+#[derive(Debug, Clone)]
+pub struct ExecutionOutcome {
+	/// The version of the runtime that executed the block.
+	pub runtime_version: RuntimeVersion,
+	/// The result of executing the block, with the error message if it failed.
+	pub result: Result<(), String>,
+	/// The storage root after executing the block.
+	pub storage_root: Vec<u8>,
+	/// All storage changes done by the block.
+	///
+	/// If the execution failed, these are the changes done up to the failure.
+	pub storage_changes: BTreeMap<ChangedKey, Option<Vec<u8>>>,
+	/// All host calls accessing the state, in the order they were done by the runtime.
+	pub host_calls: Vec<HostCall>,
+}
+
+impl ExecutionOutcome {
+	fn frame_system_value(&self, item: &[u8]) -> Option<&Vec<u8>> {
+		let mut key = twox_128(b"System").to_vec();
+		key.extend_from_slice(&twox_128(item));
+
+		self.storage_changes
+			.get(&ChangedKey { child_storage_key: None, key })
+			.and_then(Option::as_ref)
+	}
+
+	/// The encoded events deposited by the block, if the runtime uses `frame-system`.
+	pub fn events(&self) -> Option<&Vec<u8>> {
+		self.frame_system_value(b"Events")
+	}
+
+	/// The encoded weight consumed by the block, if the runtime uses `frame-system`.
+	pub fn weight(&self) -> Option<&Vec<u8>> {
+		self.frame_system_value(b"BlockWeight")
+	}
+}
+
+/// The result of executing the same block with the Wasm and the PolkaVM build of a runtime.
+#[derive(Debug, Clone)]
+pub struct BlockExecutionComparison<Hash> {
+	/// The hash of the executed block.
+	pub block_hash: Hash,
+	/// The outcome of executing the Wasm build.
+	pub wasm: ExecutionOutcome,
+	/// The outcome of executing the PolkaVM build.
+	pub polkavm: ExecutionOutcome,
+}
+
+impl<Hash> BlockExecutionComparison<Hash> {
+	/// Returns the first host call both executions disagree on.
+	///
+	/// Returns the index of the call, together with the calls done by the Wasm and the PolkaVM
+	/// build. A `None` means that the respective execution didn't do any further host calls.
+	pub fn first_divergent_host_call(
+		&self,
+	) -> Option<(usize, Option<&HostCall>, Option<&HostCall>)> {
+		let calls = self.wasm.host_calls.len().max(self.polkavm.host_calls.len());
+
+		(0..calls)
+			.map(|index| {
+				(index, self.wasm.host_calls.get(index), self.polkavm.host_calls.get(index))
+			})
+			.find(|(_, wasm, polkavm)| wasm != polkavm)
+	}
+
+	/// Returns the first storage key, in lexicographical order, that was changed differently.
+	pub fn first_divergent_storage_key(&self) -> Option<&ChangedKey> {
+		let wasm = &self.wasm.storage_changes;
+		let polkavm = &self.polkavm.storage_changes;
+
+		wasm.keys().chain(polkavm.keys()).filter(|key| wasm.get(*key) != polkavm.get(*key)).min()
+	}
+
+	/// Returns `true` if both executions are indistinguishable.
+	pub fn is_identical(&self) -> bool {
+		self.wasm.runtime_version == self.polkavm.runtime_version &&
+			self.wasm.result == self.polkavm.result &&
+			self.wasm.storage_root == self.polkavm.storage_root &&
+			self.first_divergent_storage_key().is_none() &&
+			self.first_divergent_host_call().is_none()
+	}
+}
+
+impl<Hash: fmt::Debug> fmt::Display for BlockExecutionComparison<Hash> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn matches(equal: bool) -> &'static str {
+			if equal {
+				"identical"
+			} else {
+				"DIVERGENT"
+			}
+		}
+
+		fn result(result: &Result<(), String>) -> String {
+			match result {
+				Ok(()) => "success".into(),
+				Err(error) => format!("failed: {}", error),
+			}
+		}
+
+		let (wasm, polkavm) = (&self.wasm, &self.polkavm);
+
+		writeln!(f, "Block {:?}:", self.block_hash)?;
+		writeln!(
+			f,
+			"  runtime version: {} (wasm: {}, polkavm: {})",
+			matches(wasm.runtime_version == polkavm.runtime_version),
+			wasm.runtime_version,
+			polkavm.runtime_version,
+		)?;
+		writeln!(
+			f,
+			"  execution: {} (wasm: {}, polkavm: {})",
+			matches(wasm.result == polkavm.result),
+			result(&wasm.result),
+			result(&polkavm.result),
+		)?;
+		writeln!(
+			f,
+			"  storage root: {} (wasm: 0x{}, polkavm: 0x{})",
+			matches(wasm.storage_root == polkavm.storage_root),
+			HexDisplay::from(&wasm.storage_root),
+			HexDisplay::from(&polkavm.storage_root),
+		)?;
+		writeln!(
+			f,
+			"  storage changes: {} (wasm: {} keys, polkavm: {} keys)",
+			matches(self.first_divergent_storage_key().is_none()),
+			wasm.storage_changes.len(),
+			polkavm.storage_changes.len(),
+		)?;
+		writeln!(f, "  events: {}", matches(wasm.events() == polkavm.events()))?;
+		writeln!(f, "  weight: {}", matches(wasm.weight() == polkavm.weight()))?;
+		writeln!(
+			f,
+			"  host calls: {} (wasm: {}, polkavm: {})",
+			matches(self.first_divergent_host_call().is_none()),
+			wasm.host_calls.len(),
+			polkavm.host_calls.len(),
+		)?;
+
+		if let Some(key) = self.first_divergent_storage_key() {
+			writeln!(f, "First divergent storage key: {}", key)?;
+		}
+
+		if let Some((index, wasm, polkavm)) = self.first_divergent_host_call() {
+			let call = |call: Option<&HostCall>| match call {
+				Some(call) => call.to_string(),
+				None => "<none>".into(),
+			};
+
+			writeln!(f, "First divergent host call (#{}):", index)?;
+			writeln!(f, "  wasm:    {}", call(wasm))?;
+			writeln!(f, "  polkavm: {}", call(polkavm))?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Re-execute a known block with the Wasm and the PolkaVM build of its runtime and compare the
+/// outcomes.
+///
+/// Both builds execute the block on top of the state of its parent. The Wasm build defaults to
+/// the `:code` found in that state, but can be overridden with `wasm_code`.
+pub fn compare_block_execution<B, BA, C, E>(
+	client: Arc<C>,
+	backend: Arc<BA>,
+	executor: E,
+	block_id: BlockId<B>,
+	wasm_code: Option<Vec<u8>>,
+	polkavm_code: Vec<u8>,
+) -> Result<BlockExecutionComparison<B::Hash>, Error>
+where
+	C: BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+	B: BlockT,
+	BA: Backend<B>,
+	E: CodeExecutor + RuntimeVersionOf,
+{
+	let block_hash = client.block_hash_from_id(&block_id)?.ok_or("Unknown block")?;
+	let (mut header, extrinsics) =
+		client.block(block_hash)?.ok_or("Unknown block")?.block.deconstruct();
+	let state = backend.state_at(*header.parent_hash())?;
+
+	let storage = |key: &[u8]| state.storage(key).map_err(|e| Error::Other(e.to_string()));
+	let wasm_code = match wasm_code {
+		Some(code) => code,
+		None => storage(well_known_keys::CODE)?.ok_or("`:code` not found in the parent state")?,
+	};
+	let heap_pages = storage(well_known_keys::HEAP_PAGES)?
+		.and_then(|pages| codec::Decode::decode(&mut &pages[..]).ok());
+
+	if is_polkavm_code(&wasm_code)? {
+		return Err("Expected a Wasm runtime, found a PolkaVM runtime".into())
+	}
+	if !is_polkavm_code(&polkavm_code)? {
+		return Err("Expected a PolkaVM runtime, found a Wasm runtime".into())
+	}
+
+	// Remove all `Seal`s as they are added by the consensus engines after building the block.
+	// On import they are normally removed by the consensus engine.
+	header.digest_mut().logs.retain(|d| d.as_seal().is_none());
+	let block = B::new(header, extrinsics).encode();
+	let execute = |code: &[u8]| {
+		let code_fetcher = WrappedRuntimeCode(code.into());
+		let runtime_code = RuntimeCode {
+			code_fetcher: &code_fetcher,
+			heap_pages,
+			hash: blake2_256(code).to_vec(),
+		};
+
+		execute_block(&state, &executor, &runtime_code, &block)
+	};
+
+	Ok(BlockExecutionComparison {
+		block_hash,
+		wasm: execute(&wasm_code)?,
+		polkavm: execute(&polkavm_code)?,
+	})
+}
+
+fn is_polkavm_code(code: &[u8]) -> Result<bool, Error> {
+	let blob = RuntimeBlob::uncompress_if_needed(code)
+		.map_err(|e| Error::Other(format!("Invalid runtime code: {}", e)))?;
+
+	Ok(blob.as_polkavm_blob().is_some())
+}
+
+fn execute_block<S, H, E>(
+	state: &S,
+	executor: &E,
+	runtime_code: &RuntimeCode,
+	block: &[u8],
+) -> Result<ExecutionOutcome, Error>
+where
+	S: StateBackend<H>,
+	H: Hasher,
+	H::Out: Ord + Encode + 'static,
+	E: CodeExecutor + RuntimeVersionOf,
+{
+	let mut overlay = OverlayedChanges::<H>::default();
+	let mut extensions = Extensions::default();
+	extensions.register(ReadRuntimeVersionExt::new(executor.clone()));
+
+	let (runtime_version, result, host_calls) = {
+		let mut ext = Ext::new(&mut overlay, state, Some(&mut extensions));
+
+		let runtime_version = executor
+			.runtime_version(&mut ext, runtime_code)
+			.map_err(|e| Error::Other(format!("Failed to read the runtime version: {}", e)))?;
+
+		let mut recorder = HostCallRecorder { inner: &mut ext, calls: Vec::new() };
+		let (result, _) = executor.call(
+			&mut recorder,
+			runtime_code,
+			"Core_execute_block",
+			block,
+			CallContext::Onchain,
+		);
+
+		(runtime_version, result.map(drop).map_err(|e| e.to_string()), recorder.calls)
+	};
+
+	let changes = overlay
+		.drain_storage_changes(state, runtime_version.state_version())
+		.map_err(|e| Error::Other(format!("Failed to collect the storage changes: {}", e)))?;
+
+	let main_changes = changes
+		.main_storage_changes
+		.into_iter()
+		.map(|(key, value)| (ChangedKey { child_storage_key: None, key }, value));
+	let child_changes = changes.child_storage_changes.into_iter().flat_map(|(child, changes)| {
+		changes.into_iter().map(move |(key, value)| {
+			(ChangedKey { child_storage_key: Some(child.clone()), key }, value)
+		})
+	});
+
+	Ok(ExecutionOutcome {
+		runtime_version,
+		result,
+		storage_root: changes.transaction_storage_root.encode(),
+		storage_changes: main_changes.chain(child_changes).collect(),
+		host_calls,
+	})
+}
+
+/// [`Externalities`] that forward everything to `inner` and record the host calls accessing
+/// the state.
+struct HostCallRecorder<'a> {
+	inner: &'a mut dyn Externalities,
+	calls: Vec<HostCall>,
+}
+
+fn hash_of(value: &Option<impl AsRef<[u8]>>) -> Option<[u8; 32]> {
+	value.as_ref().map(|value| blake2_256(value.as_ref()))
+}
+
+impl Externalities for HostCallRecorder<'_> {
+	fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>) {
+		self.calls.push(HostCall::SetOffchainStorage(key.to_vec(), hash_of(&value)));
+		self.inner.set_offchain_storage(key, value)
+	}
+
+	fn storage(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.calls.push(HostCall::Storage(key.to_vec()));
+		self.inner.storage(key)
+	}
+
+	fn storage_hash(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.calls.push(HostCall::StorageHash(key.to_vec()));
+		self.inner.storage_hash(key)
+	}
+
+	fn child_storage_hash(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::ChildStorageHash(child, key.to_vec()));
+		self.inner.child_storage_hash(child_info, key)
+	}
+
+	fn child_storage(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::ChildStorage(child, key.to_vec()));
+		self.inner.child_storage(child_info, key)
+	}
+
+	fn exists_storage(&mut self, key: &[u8]) -> bool {
+		self.calls.push(HostCall::ExistsStorage(key.to_vec()));
+		self.inner.exists_storage(key)
+	}
+
+	fn exists_child_storage(&mut self, child_info: &ChildInfo, key: &[u8]) -> bool {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::ExistsChildStorage(child, key.to_vec()));
+		self.inner.exists_child_storage(child_info, key)
+	}
+
+	fn next_storage_key(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.calls.push(HostCall::NextStorageKey(key.to_vec()));
+		self.inner.next_storage_key(key)
+	}
+
+	fn next_child_storage_key(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::NextChildStorageKey(child, key.to_vec()));
+		self.inner.next_child_storage_key(child_info, key)
+	}
+
+	fn kill_child_storage(
+		&mut self,
+		child_info: &ChildInfo,
+		maybe_limit: Option<u32>,
+		maybe_cursor: Option<&[u8]>,
+	) -> MultiRemovalResults {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::KillChildStorage(child, maybe_limit));
+		self.inner.kill_child_storage(child_info, maybe_limit, maybe_cursor)
+	}
+
+	fn clear_prefix(
+		&mut self,
+		prefix: &[u8],
+		maybe_limit: Option<u32>,
+		maybe_cursor: Option<&[u8]>,
+	) -> MultiRemovalResults {
+		self.calls.push(HostCall::ClearPrefix(prefix.to_vec(), maybe_limit));
+		self.inner.clear_prefix(prefix, maybe_limit, maybe_cursor)
+	}
+
+	fn clear_child_prefix(
+		&mut self,
+		child_info: &ChildInfo,
+		prefix: &[u8],
+		maybe_limit: Option<u32>,
+		maybe_cursor: Option<&[u8]>,
+	) -> MultiRemovalResults {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::ClearChildPrefix(child, prefix.to_vec(), maybe_limit));
+		self.inner.clear_child_prefix(child_info, prefix, maybe_limit, maybe_cursor)
+	}
+
+	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+		self.calls.push(HostCall::PlaceStorage(key.clone(), hash_of(&value)));
+		self.inner.place_storage(key, value)
+	}
+
+	fn place_child_storage(
+		&mut self,
+		child_info: &ChildInfo,
+		key: Vec<u8>,
+		value: Option<Vec<u8>>,
+	) {
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::PlaceChildStorage(child, key.clone(), hash_of(&value)));
+		self.inner.place_child_storage(child_info, key, value)
+	}
+
+	fn storage_root(&mut self, state_version: StateVersion) -> Vec<u8> {
+		let root = self.inner.storage_root(state_version);
+		self.calls.push(HostCall::StorageRoot(root.clone()));
+		root
+	}
+
+	fn child_storage_root(
+		&mut self,
+		child_info: &ChildInfo,
+		state_version: StateVersion,
+	) -> Vec<u8> {
+		let root = self.inner.child_storage_root(child_info, state_version);
+		let child = child_info.storage_key().to_vec();
+		self.calls.push(HostCall::ChildStorageRoot(child, root.clone()));
+		root
+	}
+
+	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		self.calls.push(HostCall::StorageAppend(key.clone(), blake2_256(&value)));
+		self.inner.storage_append(key, value)
+	}
+
+	fn storage_start_transaction(&mut self) {
+		self.calls.push(HostCall::StartTransaction);
+		self.inner.storage_start_transaction()
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.calls.push(HostCall::RollbackTransaction);
+		self.inner.storage_rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.calls.push(HostCall::CommitTransaction);
+		self.inner.storage_commit_transaction()
+	}
+
+	fn storage_index_transaction(&mut self, index: u32, hash: &[u8], size: u32) {
+		self.calls.push(HostCall::IndexTransaction(index, hash.to_vec(), size));
+		self.inner.storage_index_transaction(index, hash, size)
+	}
+
+	fn storage_renew_transaction_index(&mut self, index: u32, hash: &[u8]) {
+		self.calls.push(HostCall::RenewTransactionIndex(index, hash.to_vec()));
+		self.inner.storage_renew_transaction_index(index, hash)
+	}
+
+	fn wipe(&mut self) {
+		self.inner.wipe()
+	}
+
+	fn commit(&mut self) {
+		self.inner.commit()
+	}
+
+	fn read_write_count(&self) -> (u32, u32, u32, u32) {
+		self.inner.read_write_count()
+	}
+
+	fn reset_read_write_count(&mut self) {
+		self.inner.reset_read_write_count()
+	}
+
+	fn get_whitelist(&self) -> Vec<TrackedStorageKey> {
+		self.inner.get_whitelist()
+	}
+
+	fn set_whitelist(&mut self, new: Vec<TrackedStorageKey>) {
+		self.inner.set_whitelist(new)
+	}
+
+	fn proof_size(&self) -> Option<u32> {
+		self.inner.proof_size()
+	}
+
+	fn get_read_and_written_keys(&self) -> Vec<(Vec<u8>, u32, u32, bool)> {
+		self.inner.get_read_and_written_keys()
+	}
+}
+
+impl ExtensionStore for HostCallRecorder<'_> {
+	fn extension_by_type_id(&mut self, type_id: TypeId) -> Option<&mut dyn Any> {
+		self.inner.extension_by_type_id(type_id)
+	}
+
+	fn register_extension_with_type_id(
+		&mut self,
+		type_id: TypeId,
+		extension: Box<dyn Extension>,
+	) -> Result<(), sp_externalities::Error> {
+		self.inner.register_extension_with_type_id(type_id, extension)
+	}
+
+	fn deregister_extension_by_type_id(
+		&mut self,
+		type_id: TypeId,
+	) -> Result<(), sp_externalities::Error> {
+		self.inner.deregister_extension_by_type_id(type_id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn outcome(host_calls: Vec<HostCall>, storage_changes: Vec<(&str, &str)>) -> ExecutionOutcome {
+		ExecutionOutcome {
+			runtime_version: Default::default(),
+			result: Ok(()),
+			storage_root: vec![0; 32],
+			storage_changes: storage_changes
+				.into_iter()
+				.map(|(key, value)| {
+					let key = ChangedKey { child_storage_key: None, key: key.as_bytes().to_vec() };
+					(key, Some(value.as_bytes().to_vec()))
+				})
+				.collect(),
+			host_calls,
+		}
+	}
+
+	fn comparison(
+		wasm: ExecutionOutcome,
+		polkavm: ExecutionOutcome,
+	) -> BlockExecutionComparison<u64> {
+		BlockExecutionComparison { block_hash: 1, wasm, polkavm }
+	}
+
+	#[test]
+	fn identical_executions_are_detected() {
+		let calls = vec![HostCall::Storage(b"a".to_vec()), HostCall::StartTransaction];
+		let changes = vec![("a", "1")];
+		let comparison =
+			comparison(outcome(calls.clone(), changes.clone()), outcome(calls, changes));
+
+		assert!(comparison.is_identical());
+		assert_eq!(comparison.first_divergent_host_call(), None);
+		assert_eq!(comparison.first_divergent_storage_key(), None);
+		assert!(!comparison.to_string().contains("DIVERGENT"));
+	}
+
+	#[test]
+	fn first_divergence_is_reported() {
+		let wasm = outcome(
+			vec![
+				HostCall::Storage(b"a".to_vec()),
+				HostCall::Storage(b"b".to_vec()),
+				HostCall::CommitTransaction,
+			],
+			vec![("a", "1"), ("c", "3")],
+		);
+		let polkavm = outcome(
+			vec![HostCall::Storage(b"a".to_vec()), HostCall::Storage(b"c".to_vec())],
+			vec![("a", "1"), ("b", "2"), ("c", "4")],
+		);
+		let comparison = comparison(wasm, polkavm);
+
+		assert!(!comparison.is_identical());
+		assert_eq!(
+			comparison.first_divergent_host_call(),
+			Some((
+				1,
+				Some(&HostCall::Storage(b"b".to_vec())),
+				Some(&HostCall::Storage(b"c".to_vec()))
+			)),
+		);
+		assert_eq!(
+			comparison.first_divergent_storage_key(),
+			Some(&ChangedKey { child_storage_key: None, key: b"b".to_vec() }),
+		);
+
+		let report = comparison.to_string();
+		assert!(report.contains("storage changes: DIVERGENT (wasm: 2 keys, polkavm: 3 keys)"));
+		assert!(report.contains("host calls: DIVERGENT (wasm: 3, polkavm: 2)"));
+		assert!(report.contains("First divergent storage key: 0x62"));
+		assert!(report.contains("wasm:    storage(0x62)"));
+		assert!(report.contains("polkavm: storage(0x63)"));
+	}
+
+	#[test]
+	fn missing_host_calls_diverge() {
+		let calls = vec![HostCall::Storage(b"a".to_vec())];
+		let comparison = comparison(outcome(calls, vec![]), outcome(vec![], vec![]));
+
+		assert_eq!(
+			comparison.first_divergent_host_call(),
+			Some((0, Some(&HostCall::Storage(b"a".to_vec())), None)),
+		);
+		assert!(comparison.to_string().contains("polkavm: <none>"));
+	}
+}
@@ -19,12 +19,14 @@
 //! Chain utilities.
 
 mod check_block;
+mod compare_block;
 mod export_blocks;
 mod export_raw_state;
 mod import_blocks;
 mod revert_chain;
 
 pub use check_block::*;
+pub use compare_block::*;
 pub use export_blocks::*;
 pub use export_raw_state::*;
 pub use import_blocks::*;
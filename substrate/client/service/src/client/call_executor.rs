@@ -61,6 +61,11 @@ where
 			execution_extensions: Arc::new(execution_extensions),
 		})
 	}
+
+	/// Returns the executor used to execute the runtime code.
+	pub fn code_executor(&self) -> &E {
+		&self.executor
+	}
 }
 
 impl<Block: BlockT, B, E> Clone for LocalCallExecutor<Block, B, E>
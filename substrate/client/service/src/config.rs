@@ -23,6 +23,7 @@ use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
 pub use sc_client_db::{BlocksPruning, Database, DatabaseSource, PruningMode};
 pub use sc_executor::{WasmExecutionMethod, WasmtimeInstantiationStrategy};
+pub use sc_keystore::remote::{SignerAddress, TlsConfig};
 pub use sc_network::{
 	config::{
 		MultiaddrWithPeerId, NetworkConfiguration, NodeKeyConfig, NonDefaultSetConfig, ProtocolId,
@@ -47,6 +48,9 @@ use std::{
 };
 use tempfile::TempDir;
 
+/// The file in the [`Configuration::data_path`] the trie cache is persisted to.
+const TRIE_CACHE_SNAPSHOT_FILE: &str = "trie_cache_snapshot";
+
 /// Service configuration.
 #[derive(Debug)]
 pub struct Configuration {
@@ -70,6 +74,10 @@ pub struct Configuration {
 	///
 	/// If `None` is given the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Maximum size in bytes of the trie cache entries persisted across restarts.
+	///
+	/// If `None` is given the trie cache isn't persisted.
+	pub trie_cache_persistence_size: Option<usize>,
 	/// State pruning settings.
 	pub state_pruning: Option<PruningMode>,
 	/// Number of blocks to keep in the db.
@@ -136,6 +144,8 @@ pub enum KeystoreConfig {
 	},
 	/// In-memory keystore. Recommended for in-browser nodes.
 	InMemory,
+	/// Keystore forwarding all requests to a remote signer holding the keys.
+	Remote(SignerAddress),
 }
 
 impl KeystoreConfig {
@@ -143,7 +153,7 @@ impl KeystoreConfig {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
 			Self::Path { path, .. } => Some(path),
-			Self::InMemory => None,
+			Self::InMemory | Self::Remote(_) => None,
 		}
 	}
 }
@@ -214,6 +224,12 @@ impl Configuration {
 	pub fn db_config(&self) -> sc_client_db::DatabaseSettings {
 		sc_client_db::DatabaseSettings {
 			trie_cache_maximum_size: self.trie_cache_maximum_size,
+			trie_cache_persistence: self.trie_cache_persistence_size.map(|maximum_size| {
+				sc_client_db::TrieCachePersistence {
+					path: self.data_path.join(TRIE_CACHE_SNAPSHOT_FILE),
+					maximum_size,
+				}
+			}),
 			state_pruning: self.state_pruning.clone(),
 			source: self.database.clone(),
 			blocks_pruning: self.blocks_pruning,
@@ -29,6 +29,7 @@ pub mod error;
 
 mod builder;
 mod metrics;
+mod runtime_precompiler;
 mod task_manager;
 
 use crate::config::Multiaddr;
@@ -67,6 +68,7 @@ pub use self::{
 	client::{ClientConfig, LocalCallExecutor},
 	error::Error,
 	metrics::MetricsService,
+	runtime_precompiler::precompile_runtime_upgrades,
 };
 #[allow(deprecated)]
 pub use builder::new_native_or_wasm_executor;
@@ -40,6 +40,8 @@ struct PrometheusMetrics {
 	// I/O
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
+	state_cache_hits: GaugeVec<U64>,
+	state_cache_fetch_attempts: GaugeVec<U64>,
 }
 
 impl PrometheusMetrics {
@@ -113,6 +115,26 @@ impl PrometheusMetrics {
 				Gauge::new("substrate_state_cache_bytes", "State cache size in bytes")?,
 				registry,
 			)?,
+			state_cache_hits: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_state_cache_hits",
+						"Number of lookups served by the shared state cache since the start",
+					),
+					&["cache"],
+				)?,
+				registry,
+			)?,
+			state_cache_fetch_attempts: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_state_cache_fetch_attempts",
+						"Number of lookups sent to the shared state cache since the start",
+					),
+					&["cache"],
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -247,6 +269,22 @@ impl MetricsService {
 			if let Some(info) = info.usage.as_ref() {
 				metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
 				metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+				metrics
+					.state_cache_hits
+					.with_label_values(&["node"])
+					.set(info.io.trie_cache_node_hits);
+				metrics
+					.state_cache_fetch_attempts
+					.with_label_values(&["node"])
+					.set(info.io.trie_cache_node_fetch_attempts);
+				metrics
+					.state_cache_hits
+					.with_label_values(&["value"])
+					.set(info.io.trie_cache_value_hits);
+				metrics
+					.state_cache_fetch_attempts
+					.with_label_values(&["value"])
+					.set(info.io.trie_cache_value_fetch_attempts);
 			}
 		}
 
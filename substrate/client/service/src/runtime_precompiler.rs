@@ -0,0 +1,448 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Ahead-of-time compilation of pending runtime upgrades.
+//!
+//! Preparing a new runtime, e.g. compiling it with wasmtime, can take a few seconds. Without any
+//! preparation this happens while importing the first block that calls into the new runtime,
+//! delaying the import of that block on every node. The precompiler watches new best blocks for
+//! runtime code that is about to be enacted and prepares it in the background, so that the switch
+//! to the new runtime is instantaneous.
+//!
+//! The following runtime code is picked up:
+//!
+//! - The code stored at [`well_known_keys::PENDING_CODE`].
+//! - The code of an upgrade authorized via `frame_system::authorize_upgrade`, as soon as it was
+//!   noted as a preimage in `pallet-preimage`.
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use log::{debug, info, warn};
+use prometheus_endpoint::{
+	exponential_buckets, register, CounterVec, Histogram, HistogramOpts, Opts, PrometheusError,
+	Registry, U64,
+};
+use sc_client_api::{backend::Backend, BlockchainEvents};
+use sc_executor::PrecompileRuntime;
+use schnellru::{ByLength, LruMap};
+use sp_core::{
+	storage::well_known_keys,
+	traits::{RuntimeCode, WrappedRuntimeCode},
+};
+use sp_crypto_hashing::twox_128;
+use sp_runtime::traits::{Block as BlockT, HashingFor};
+use sp_state_machine::{Backend as StateBackend, BasicExternalities};
+use std::{sync::Arc, time::Instant};
+
+const LOG_TARGET: &str = "runtime-precompiler";
+
+/// The number of runtime code hashes remembered as already handled.
+const HANDLED_CODE_CACHE_SIZE: u32 = 16;
+
+/// The number of times the precompilation of some runtime code is attempted, once per new best
+/// block, before giving up on it.
+const MAX_PRECOMPILE_ATTEMPTS: u32 = 3;
+
+struct Metrics {
+	compilation_time: Histogram,
+	precompilations: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			compilation_time: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_runtime_precompilation_time",
+						"Time in seconds it took to precompile a pending runtime upgrade",
+					)
+					.buckets(exponential_buckets(0.1, 2.0, 10)?),
+				)?,
+				registry,
+			)?,
+			precompilations: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_runtime_precompilations_total",
+						"Number of pending runtime upgrades that were precompiled",
+					),
+					&["result"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Runtime code which is about to be enacted.
+struct PendingCode<Hash> {
+	/// The hash of the code, as used by [`RuntimeCode::hash`].
+	hash: Hash,
+	/// The storage key the code is stored at.
+	key: Vec<u8>,
+	/// Whether the code is stored SCALE encoded, as done by `pallet-preimage`.
+	encoded: bool,
+}
+
+/// The storage key of `frame_system::AuthorizedUpgrade`.
+fn authorized_upgrade_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"AuthorizedUpgrade")].concat()
+}
+
+/// The prefix of all `pallet_preimage::PreimageFor` entries for the given `hash`.
+fn preimage_prefix(hash: &impl Encode) -> Vec<u8> {
+	let mut prefix = [twox_128(b"Preimage"), twox_128(b"PreimageFor")].concat();
+	hash.encode_to(&mut prefix);
+	prefix
+}
+
+/// Returns the runtime code which is about to be enacted in `state`.
+fn pending_code<B, S>(state: &S) -> Result<Vec<PendingCode<B::Hash>>, S::Error>
+where
+	B: BlockT,
+	S: StateBackend<HashingFor<B>>,
+{
+	let mut pending = Vec::new();
+
+	if let Some(hash) = state.storage_hash(well_known_keys::PENDING_CODE)? {
+		pending.push(PendingCode {
+			hash,
+			key: well_known_keys::PENDING_CODE.to_vec(),
+			encoded: false,
+		});
+	}
+
+	// `CodeUpgradeAuthorization` starts with the hash of the authorized code.
+	let authorized = state
+		.storage(&authorized_upgrade_key())?
+		.and_then(|authorization| B::Hash::decode(&mut &authorization[..]).ok());
+	if let Some(hash) = authorized {
+		let prefix = preimage_prefix(&hash);
+
+		if let Some(key) = state.next_storage_key(&prefix)?.filter(|key| key.starts_with(&prefix)) {
+			pending.push(PendingCode { hash, key, encoded: true });
+		}
+	}
+
+	Ok(pending)
+}
+
+/// Precompile pending runtime upgrades in the background.
+///
+/// Watches the new best blocks imported by `client` for runtime code which is about to be
+/// enacted and prepares it with `executor`. To be of any use, `executor` needs to share its
+/// runtime cache with the executor used by `client`, e.g. by being a clone of it.
+///
+/// Preparing a runtime is CPU heavy, so the returned future should be spawned as a blocking
+/// task.
+pub async fn precompile_runtime_upgrades<B, BA, C, E>(
+	client: Arc<C>,
+	backend: Arc<BA>,
+	executor: E,
+	prometheus_registry: Option<Registry>,
+) where
+	B: BlockT,
+	BA: Backend<B>,
+	C: BlockchainEvents<B>,
+	E: PrecompileRuntime,
+{
+	let metrics = prometheus_registry.and_then(|registry| {
+		Metrics::register(&registry)
+			.map_err(|error| {
+				warn!(target: LOG_TARGET, "Failed to register metrics: {}", error);
+			})
+			.ok()
+	});
+	let mut handled = LruMap::new(ByLength::new(HANDLED_CODE_CACHE_SIZE));
+	let mut import_notifications = client.import_notification_stream();
+
+	while let Some(notification) = import_notifications.next().await {
+		if !notification.is_new_best {
+			continue
+		}
+
+		match backend.state_at(notification.hash) {
+			Ok(state) =>
+				precompile_pending::<B, _, _>(&state, &executor, &mut handled, metrics.as_ref()),
+			Err(error) => {
+				debug!(target: LOG_TARGET, "Failed to get state of {}: {}", notification.hash, error);
+			},
+		}
+	}
+}
+
+/// Precompile the runtime code which is about to be enacted in `state`.
+///
+/// `handled` maps the hashes of the code seen so far to the number of failed attempts to
+/// precompile it, code which was precompiled is mapped to [`MAX_PRECOMPILE_ATTEMPTS`].
+fn precompile_pending<B, S, E>(
+	state: &S,
+	executor: &E,
+	handled: &mut LruMap<B::Hash, u32>,
+	metrics: Option<&Metrics>,
+) where
+	B: BlockT,
+	S: StateBackend<HashingFor<B>>,
+	E: PrecompileRuntime,
+{
+	let pending = match pending_code::<B, _>(state) {
+		Ok(pending) => pending,
+		Err(error) => {
+			debug!(target: LOG_TARGET, "Failed to read pending runtime code: {}", error);
+			return
+		},
+	};
+
+	for pending in pending {
+		let attempts = handled.get(&pending.hash).copied().unwrap_or_default();
+		if attempts >= MAX_PRECOMPILE_ATTEMPTS {
+			continue
+		}
+
+		// The code may already be enacted, e.g. if `:pending_code` isn't cleared.
+		if state.storage_hash(well_known_keys::CODE).ok().flatten() == Some(pending.hash) {
+			handled.insert(pending.hash, MAX_PRECOMPILE_ATTEMPTS);
+			continue
+		}
+
+		let hash = pending.hash;
+		let attempts = match precompile::<B, _, _>(state, executor, pending, metrics) {
+			true => MAX_PRECOMPILE_ATTEMPTS,
+			false => attempts + 1,
+		};
+		handled.insert(hash, attempts);
+	}
+}
+
+/// Precompile the given `pending` code, returns `false` if it failed.
+fn precompile<B, S, E>(
+	state: &S,
+	executor: &E,
+	pending: PendingCode<B::Hash>,
+	metrics: Option<&Metrics>,
+) -> bool
+where
+	B: BlockT,
+	S: StateBackend<HashingFor<B>>,
+	E: PrecompileRuntime,
+{
+	let code = match state.storage(&pending.key) {
+		Ok(Some(code)) if pending.encoded => Vec::<u8>::decode(&mut &code[..]).ok(),
+		Ok(code) => code,
+		Err(error) => {
+			debug!(target: LOG_TARGET, "Failed to read pending runtime code: {}", error);
+			None
+		},
+	};
+	let Some(code) = code else { return false };
+
+	let heap_pages = state
+		.storage(well_known_keys::HEAP_PAGES)
+		.ok()
+		.flatten()
+		.and_then(|pages| u64::decode(&mut &pages[..]).ok());
+	let code_fetcher = WrappedRuntimeCode(code.into());
+	let runtime_code =
+		RuntimeCode { code_fetcher: &code_fetcher, heap_pages, hash: pending.hash.encode() };
+
+	let start = Instant::now();
+	let result = executor.precompile_runtime(&mut BasicExternalities::default(), &runtime_code);
+	let elapsed = start.elapsed();
+
+	let label = match &result {
+		Ok(true) => {
+			info!(
+				target: LOG_TARGET,
+				"⚙️  Precompiled pending runtime upgrade {:?} in {} ms",
+				pending.hash,
+				elapsed.as_millis(),
+			);
+			if let Some(metrics) = metrics {
+				metrics.compilation_time.observe(elapsed.as_secs_f64());
+			}
+			"success"
+		},
+		Ok(false) => {
+			debug!(target: LOG_TARGET, "Pending runtime upgrade {:?} is already prepared", pending.hash);
+			"cached"
+		},
+		Err(error) => {
+			warn!(
+				target: LOG_TARGET,
+				"Failed to precompile pending runtime upgrade {:?}: {}",
+				pending.hash,
+				error,
+			);
+			"failure"
+		},
+	};
+
+	if let Some(metrics) = metrics {
+		metrics.precompilations.with_label_values(&[label]).inc();
+	}
+
+	result.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+	use sp_core::{traits::Externalities, H256};
+	use sp_runtime::traits::{BlakeTwo256, Hash};
+	use sp_state_machine::{InMemoryBackend, StateVersion};
+	use std::collections::BTreeMap;
+	use substrate_test_runtime_client::runtime::Block;
+
+	/// Records the hashes and the code of the runtimes it was asked to precompile.
+	#[derive(Default)]
+	struct TestExecutor {
+		precompiled: Mutex<Vec<(Vec<u8>, Vec<u8>)>>,
+		fail: bool,
+	}
+
+	impl PrecompileRuntime for TestExecutor {
+		fn precompile_runtime(
+			&self,
+			_: &mut dyn Externalities,
+			runtime_code: &RuntimeCode,
+		) -> sc_executor::error::Result<bool> {
+			let code = runtime_code.fetch_runtime_code().unwrap_or_default().into_owned();
+			self.precompiled.lock().push((runtime_code.hash.clone(), code));
+
+			match self.fail {
+				true => Err(sc_executor::error::Error::Other("Failed".into())),
+				false => Ok(true),
+			}
+		}
+	}
+
+	fn state(storage: Vec<(Vec<u8>, Vec<u8>)>) -> InMemoryBackend<BlakeTwo256> {
+		let storage: BTreeMap<_, _> = storage.into_iter().collect();
+		InMemoryBackend::<BlakeTwo256>::from((storage, StateVersion::V1))
+	}
+
+	fn authorized_upgrade(hash: H256) -> (Vec<u8>, Vec<u8>) {
+		// `CodeUpgradeAuthorization { code_hash, check_version }`
+		(authorized_upgrade_key(), (hash, true).encode())
+	}
+
+	fn preimage(code: &[u8]) -> (Vec<u8>, Vec<u8>) {
+		let hash = BlakeTwo256::hash(code);
+		let mut key = preimage_prefix(&hash);
+		(code.len() as u32).encode_to(&mut key);
+		(key, code.to_vec().encode())
+	}
+
+	#[test]
+	fn pending_code_is_detected() {
+		let code = b"pending code".to_vec();
+		let state = state(vec![(well_known_keys::PENDING_CODE.to_vec(), code.clone())]);
+
+		let pending = pending_code::<Block, _>(&state).unwrap();
+
+		assert_eq!(pending.len(), 1);
+		assert_eq!(pending[0].hash, BlakeTwo256::hash(&code));
+		assert_eq!(pending[0].key, well_known_keys::PENDING_CODE.to_vec());
+		assert!(!pending[0].encoded);
+	}
+
+	#[test]
+	fn authorized_upgrade_is_detected_once_noted_as_preimage() {
+		let code = b"authorized code".to_vec();
+		let hash = BlakeTwo256::hash(&code);
+
+		let state_without_preimage = state(vec![authorized_upgrade(hash)]);
+		assert!(pending_code::<Block, _>(&state_without_preimage).unwrap().is_empty());
+
+		let (key, value) = preimage(&code);
+		let state =
+			state(vec![authorized_upgrade(hash), preimage(b"other code"), (key.clone(), value)]);
+
+		let pending = pending_code::<Block, _>(&state).unwrap();
+
+		assert_eq!(pending.len(), 1);
+		assert_eq!(pending[0].hash, hash);
+		assert_eq!(pending[0].key, key);
+		assert!(pending[0].encoded);
+	}
+
+	#[test]
+	fn authorized_upgrade_code_is_decoded() {
+		let code = b"authorized code".to_vec();
+		let hash = BlakeTwo256::hash(&code);
+		let state = state(vec![authorized_upgrade(hash), preimage(&code)]);
+		let executor = TestExecutor::default();
+
+		precompile_pending::<Block, _, _>(
+			&state,
+			&executor,
+			&mut LruMap::new(ByLength::new(HANDLED_CODE_CACHE_SIZE)),
+			None,
+		);
+
+		assert_eq!(*executor.precompiled.lock(), vec![(hash.encode(), code)]);
+	}
+
+	#[test]
+	fn failed_precompilations_are_retried() {
+		let code = b"pending code".to_vec();
+		let state = state(vec![(well_known_keys::PENDING_CODE.to_vec(), code)]);
+		let executor = TestExecutor { fail: true, ..Default::default() };
+		let mut handled = LruMap::new(ByLength::new(HANDLED_CODE_CACHE_SIZE));
+
+		for _ in 0..MAX_PRECOMPILE_ATTEMPTS + 2 {
+			precompile_pending::<Block, _, _>(&state, &executor, &mut handled, None);
+		}
+
+		assert_eq!(executor.precompiled.lock().len(), MAX_PRECOMPILE_ATTEMPTS as usize);
+	}
+
+	#[test]
+	fn successful_precompilations_are_not_repeated() {
+		let code = b"pending code".to_vec();
+		let state = state(vec![(well_known_keys::PENDING_CODE.to_vec(), code)]);
+		let executor = TestExecutor::default();
+		let mut handled = LruMap::new(ByLength::new(HANDLED_CODE_CACHE_SIZE));
+
+		precompile_pending::<Block, _, _>(&state, &executor, &mut handled, None);
+		precompile_pending::<Block, _, _>(&state, &executor, &mut handled, None);
+
+		assert_eq!(executor.precompiled.lock().len(), 1);
+	}
+
+	#[test]
+	fn enacted_code_is_not_precompiled() {
+		let code = b"current code".to_vec();
+		let state = state(vec![
+			(well_known_keys::CODE.to_vec(), code.clone()),
+			(well_known_keys::PENDING_CODE.to_vec(), code),
+		]);
+		let executor = TestExecutor::default();
+
+		precompile_pending::<Block, _, _>(
+			&state,
+			&executor,
+			&mut LruMap::new(ByLength::new(HANDLED_CODE_CACHE_SIZE)),
+			None,
+		);
+
+		assert!(executor.precompiled.lock().is_empty());
+	}
+}
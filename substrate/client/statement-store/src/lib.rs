@@ -43,6 +43,22 @@
 //! explicitly with the `remove` function) the statement is marked as expired. Expired statements
 //! can't be added to the store for `Options::purge_after_sec` seconds. This is to prevent old
 //! statements from being propagated on the network.
+//!
+//! Statement retention.
+//!
+//! A retention period may be configured for all statements or for statements posted to a given
+//! channel. Statements which outlive their retention period are expired during periodic store
+//! maintenance. The retention period is counted from the time the statement was inserted, or the
+//! time the store was loaded from the database for statements inserted before a restart.
+//! Nodes expose the retention periods on the command line by flattening `sc_cli`'s
+//! `StatementStoreParams` into their CLI and passing its options to [`Store::new_shared`].
+//!
+//! Subscriptions.
+//!
+//! Subscribers are notified of matching statements once the store has released its index lock.
+//! The subscribers to notify are picked while the index is locked, which guarantees that each
+//! matching statement is delivered exactly once. Concurrent submissions block on a sink for as
+//! long as it takes to deliver a statement, so subscription sinks should not block.
 
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
@@ -52,7 +68,7 @@ mod metrics;
 pub use sp_statement_store::{Error, StatementStore, MAX_TOPICS};
 
 use metrics::MetricsLink as PrometheusMetrics;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use prometheus_endpoint::Registry as PrometheusRegistry;
 use sc_keystore::LocalKeystore;
 use sp_api::ProvideRuntimeApi;
@@ -64,11 +80,14 @@ use sp_statement_store::{
 		InvalidStatement, StatementSource, StatementStoreExt, ValidStatement, ValidateStatement,
 	},
 	AccountId, BlockHash, Channel, DecryptionKey, Hash, NetworkPriority, Proof, Result, Statement,
-	SubmitResult, Topic,
+	SubmitResult, SubscriptionFilter, SubscriptionSink, Topic,
 };
 use std::{
-	collections::{BTreeMap, HashMap, HashSet},
-	sync::Arc,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
 };
 
 const KEY_VERSION: &[u8] = b"version".as_slice();
@@ -137,6 +156,11 @@ pub struct Options {
 	max_total_size: usize,
 	/// Number of seconds for which removed statements won't be allowed to be added back in.
 	purge_after_sec: u64,
+	/// Number of seconds statements are kept in the store for, unless overridden for the channel.
+	/// `None` keeps statements until they are evicted or removed.
+	retention_sec: Option<u64>,
+	/// Per-channel overrides of `retention_sec`.
+	channel_retention_sec: HashMap<Channel, u64>,
 }
 
 impl Default for Options {
@@ -145,10 +169,45 @@ impl Default for Options {
 			max_total_statements: DEFAULT_MAX_TOTAL_STATEMENTS,
 			max_total_size: DEFAULT_MAX_TOTAL_SIZE,
 			purge_after_sec: DEFAULT_PURGE_AFTER_SEC,
+			retention_sec: None,
+			channel_retention_sec: HashMap::new(),
 		}
 	}
 }
 
+impl Options {
+	/// Set the maximum number of statements allowed in the store.
+	pub fn with_max_total_statements(mut self, max_total_statements: usize) -> Self {
+		self.max_total_statements = max_total_statements;
+		self
+	}
+
+	/// Set the maximum total data size allowed in the store.
+	pub fn with_max_total_size(mut self, max_total_size: usize) -> Self {
+		self.max_total_size = max_total_size;
+		self
+	}
+
+	/// Set the number of seconds statements are kept in the store for.
+	pub fn with_retention(mut self, retention_sec: u64) -> Self {
+		self.retention_sec = Some(retention_sec);
+		self
+	}
+
+	/// Set the number of seconds statements posted to `channel` are kept in the store for. This
+	/// takes precedence over [`Self::with_retention`].
+	pub fn with_channel_retention(mut self, channel: Channel, retention_sec: u64) -> Self {
+		self.channel_retention_sec.insert(channel, retention_sec);
+		self
+	}
+
+	fn retention(&self, channel: Option<Channel>) -> Option<u64> {
+		channel
+			.and_then(|channel| self.channel_retention_sec.get(&channel).copied())
+			.or(self.retention_sec)
+	}
+}
+
 #[derive(Default)]
 struct Index {
 	by_topic: HashMap<Topic, HashSet<Hash>>,
@@ -156,6 +215,11 @@ struct Index {
 	topics_and_keys: HashMap<Hash, ([Option<Topic>; MAX_TOPICS], Option<DecryptionKey>)>,
 	entries: HashMap<Hash, (AccountId, Priority, usize)>,
 	expired: HashMap<Hash, u64>, // Value is expiration timestamp.
+	// All statements ordered by priority, used to enforce global constraints.
+	by_global_priority: BTreeSet<PriorityKey>,
+	// Statements with a retention period, ordered by the time they are due to expire.
+	by_retention: BTreeSet<(u64, Hash)>,
+	retain_until: HashMap<Hash, u64>,
 	accounts: HashMap<AccountId, StatementsForAccount>,
 	options: Options,
 	total_size: usize,
@@ -203,11 +267,42 @@ pub struct Store {
 			+ Sync,
 	>,
 	keystore: Arc<LocalKeystore>,
+	// Subscriptions are registered and picked for notification while holding the index lock.
+	subscriptions: Mutex<Vec<Arc<Subscription>>>,
 	// Used for testing
 	time_override: Option<u64>,
 	metrics: PrometheusMetrics,
 }
 
+struct Subscription {
+	filter: SubscriptionFilter,
+	sink: Mutex<SubscriptionSink>,
+	// Set once the sink reported that the subscriber is gone.
+	closed: AtomicBool,
+}
+
+impl Subscription {
+	fn new(filter: SubscriptionFilter, sink: SubscriptionSink) -> Self {
+		Subscription { filter, sink: Mutex::new(sink), closed: AtomicBool::new(false) }
+	}
+
+	fn is_closed(&self) -> bool {
+		self.closed.load(Ordering::Relaxed)
+	}
+
+	// Deliver `statement` to the sink, returns `false` if the subscription is closed.
+	fn send(&self, statement: &Statement) -> bool {
+		if self.is_closed() {
+			return false
+		}
+		let open = (self.sink.lock())(statement);
+		if !open {
+			self.closed.store(true, Ordering::Relaxed);
+		}
+		open
+	}
+}
+
 enum IndexQuery {
 	Unknown,
 	Exists,
@@ -224,7 +319,13 @@ impl Index {
 		Index { options, ..Default::default() }
 	}
 
-	fn insert_new(&mut self, hash: Hash, account: AccountId, statement: &Statement) {
+	fn insert_new(
+		&mut self,
+		hash: Hash,
+		account: AccountId,
+		statement: &Statement,
+		current_time: u64,
+	) {
 		let mut all_topics = [None; MAX_TOPICS];
 		let mut nt = 0;
 		while let Some(t) = statement.topic(nt) {
@@ -239,6 +340,12 @@ impl Index {
 		}
 		let priority = Priority(statement.priority().unwrap_or(0));
 		self.entries.insert(hash, (account, priority, statement.data_len()));
+		self.by_global_priority.insert(PriorityKey { hash, priority });
+		if let Some(retention) = self.options.retention(statement.channel()) {
+			let retain_until = current_time.saturating_add(retention);
+			self.by_retention.insert((retain_until, hash));
+			self.retain_until.insert(hash, retain_until);
+		}
 		self.total_size += statement.data_len();
 		let account_info = self.accounts.entry(account).or_default();
 		account_info.data_size += statement.data_len();
@@ -320,9 +427,21 @@ impl Index {
 		purged
 	}
 
+	fn outdated(&self, current_time: u64) -> Vec<Hash> {
+		self.by_retention
+			.iter()
+			.take_while(|(retain_until, _)| *retain_until <= current_time)
+			.map(|(_, hash)| *hash)
+			.collect()
+	}
+
 	fn make_expired(&mut self, hash: &Hash, current_time: u64) -> bool {
 		if let Some((account, priority, len)) = self.entries.remove(hash) {
 			self.total_size -= len;
+			self.by_global_priority.remove(&PriorityKey { hash: *hash, priority });
+			if let Some(retain_until) = self.retain_until.remove(hash) {
+				self.by_retention.remove(&(retain_until, *hash));
+			}
 			if let Some((topics, key)) = self.topics_and_keys.remove(hash) {
 				for t in topics.into_iter().flatten() {
 					if let std::collections::hash_map::Entry::Occupied(mut set) =
@@ -452,10 +571,24 @@ impl Index {
 				would_free_size += len;
 			}
 		}
-		// Now check global constraints as well.
-		if !((self.total_size - would_free_size + statement_len <= self.options.max_total_size) &&
-			self.entries.len() + 1 - evicted.len() <= self.options.max_total_statements)
-		{
+		// Now check global constraints as well, evicting lower priority statements of any account
+		// if the store is full.
+		let (total_size, total_count) = (self.total_size, self.entries.len());
+		let (max_total_size, max_total_statements) =
+			(self.options.max_total_size, self.options.max_total_statements);
+		let fits = |would_free_size: usize, would_free_count: usize| {
+			total_size - would_free_size + statement_len <= max_total_size &&
+				total_count + 1 - would_free_count <= max_total_statements
+		};
+		for entry in self.by_global_priority.iter() {
+			if fits(would_free_size, evicted.len()) || entry.priority >= priority {
+				break
+			}
+			if evicted.insert(entry.hash) {
+				would_free_size += self.entries.get(&entry.hash).map_or(0, |(_, _, len)| *len);
+			}
+		}
+		if !fits(would_free_size, evicted.len()) {
 			log::debug!(
 				target: LOG_TARGET,
 				"Ignored statement {} because the store is full (size={}, count={})",
@@ -469,7 +602,7 @@ impl Index {
 		for h in &evicted {
 			self.make_expired(h, current_time);
 		}
-		self.insert_new(hash, *account, statement);
+		self.insert_new(hash, *account, statement, current_time);
 		MaybeInserted::Inserted(evicted)
 	}
 }
@@ -571,6 +704,7 @@ impl Store {
 			index: RwLock::new(Index::new(options)),
 			validate_fn,
 			keystore,
+			subscriptions: Mutex::new(Vec::new()),
 			time_override: None,
 			metrics: PrometheusMetrics::new(prometheus),
 		};
@@ -583,6 +717,7 @@ impl Store {
 	// This function should only be used on startup. There should be no other DB operations when
 	// iterating the index.
 	fn populate(&self) -> Result<()> {
+		let current_time = self.timestamp();
 		{
 			let mut index = self.index.write();
 			self.db
@@ -596,7 +731,7 @@ impl Store {
 							HexDisplay::from(&hash)
 						);
 						if let Some(account_id) = statement.account_id() {
-							index.insert_new(hash, account_id, &statement);
+							index.insert_new(hash, account_id, &statement, current_time);
 						} else {
 							log::debug!(
 								target: LOG_TARGET,
@@ -634,10 +769,20 @@ impl Store {
 		&self,
 		key: Option<DecryptionKey>,
 		match_all_topics: &[Topic],
+		f: impl FnMut(Statement) -> Option<R>,
+	) -> Result<Vec<R>> {
+		let index = self.index.read();
+		self.collect_statements_locked(&index, key, match_all_topics, f)
+	}
+
+	fn collect_statements_locked<R>(
+		&self,
+		index: &Index,
+		key: Option<DecryptionKey>,
+		match_all_topics: &[Topic],
 		mut f: impl FnMut(Statement) -> Option<R>,
 	) -> Result<Vec<R>> {
 		let mut result = Vec::new();
-		let index = self.index.read();
 		index.iterate_with(key, match_all_topics, |hash| {
 			match self.db.get(col::STATEMENTS, hash).map_err(|e| Error::Db(e.to_string()))? {
 				Some(entry) => {
@@ -671,24 +816,71 @@ impl Store {
 	/// Perform periodic store maintenance
 	pub fn maintain(&self) {
 		log::trace!(target: LOG_TARGET, "Started store maintenance");
-		let deleted = self.index.write().maintain(self.timestamp());
-		let deleted: Vec<_> =
-			deleted.into_iter().map(|hash| (col::EXPIRED, hash.to_vec(), None)).collect();
+		let current_time = self.timestamp();
+		let mut index = self.index.write();
+		let mut commit = Vec::new();
+		let outdated = index.outdated(current_time);
+		for hash in &outdated {
+			index.make_expired(hash, current_time);
+			commit.push((col::STATEMENTS, hash.to_vec(), None));
+			commit.push((col::EXPIRED, hash.to_vec(), Some((hash, current_time).encode())));
+		}
+		let deleted = index.maintain(current_time);
 		let count = deleted.len() as u64;
-		if let Err(e) = self.db.commit(deleted) {
+		commit.extend(deleted.into_iter().map(|hash| (col::EXPIRED, hash.to_vec(), None)));
+		if let Err(e) = self.db.commit(commit) {
 			log::warn!(target: LOG_TARGET, "Error writing to the statement database: {:?}", e);
 		} else {
-			self.metrics.report(|metrics| metrics.statements_pruned.inc_by(count));
+			self.metrics.report(|metrics| {
+				metrics.statements_pruned.inc_by(count);
+				metrics.statements_outdated.inc_by(outdated.len() as u64);
+			});
 		}
+		self.report_usage(&index);
 		log::trace!(
 			target: LOG_TARGET,
-			"Completed store maintenance. Purged: {}, Active: {}, Expired: {}",
+			"Completed store maintenance. Outdated: {}, Purged: {}, Active: {}, Expired: {}",
+			outdated.len(),
 			count,
-			self.index.read().entries.len(),
-			self.index.read().expired.len()
+			index.entries.len(),
+			index.expired.len()
 		);
 	}
 
+	fn report_usage(&self, index: &Index) {
+		self.metrics.report(|metrics| {
+			metrics.statements_total.set(index.entries.len() as u64);
+			metrics.statements_size.set(index.total_size as u64);
+			metrics.statements_capacity.set(index.options.max_total_statements as u64);
+			metrics.statements_size_capacity.set(index.options.max_total_size as u64);
+		});
+	}
+
+	// Must be called while holding the index lock.
+	fn matching_subscriptions(&self, statement: &Statement) -> Vec<Arc<Subscription>> {
+		self.subscriptions
+			.lock()
+			.iter()
+			.filter(|subscription| subscription.filter.matches(statement))
+			.cloned()
+			.collect()
+	}
+
+	// Must be called without holding the index lock.
+	fn notify_subscribers(&self, subscriptions: Vec<Arc<Subscription>>, statement: &Statement) {
+		let mut closed = false;
+		for subscription in subscriptions {
+			closed |= !subscription.send(statement);
+		}
+		if closed {
+			self.remove_closed_subscriptions();
+		}
+	}
+
+	fn remove_closed_subscriptions(&self) {
+		self.subscriptions.lock().retain(|subscription| !subscription.is_closed());
+	}
+
 	fn timestamp(&self) -> u64 {
 		self.time_override.unwrap_or_else(|| {
 			std::time::SystemTime::now()
@@ -872,16 +1064,20 @@ impl StatementStore for Store {
 
 		let current_time = self.timestamp();
 		let mut commit = Vec::new();
-		{
+		let subscriptions = {
 			let mut index = self.index.write();
 
 			let evicted =
 				match index.insert(hash, &statement, &account_id, &validation, current_time) {
-					MaybeInserted::Ignored => return SubmitResult::Ignored,
+					MaybeInserted::Ignored => {
+						self.metrics.report(|metrics| metrics.statements_ignored.inc());
+						return SubmitResult::Ignored
+					},
 					MaybeInserted::Inserted(evicted) => evicted,
 				};
 
 			commit.push((col::STATEMENTS, hash.to_vec(), Some(statement.encode())));
+			let evicted_count = evicted.len() as u64;
 			for hash in evicted {
 				commit.push((col::STATEMENTS, hash.to_vec(), None));
 				commit.push((col::EXPIRED, hash.to_vec(), Some((hash, current_time).encode())));
@@ -895,7 +1091,11 @@ impl StatementStore for Store {
 				);
 				return SubmitResult::InternalError(Error::Db(e.to_string()))
 			}
-		} // Release index lock
+			self.metrics.report(|metrics| metrics.statements_evicted.inc_by(evicted_count));
+			self.report_usage(&index);
+			self.matching_subscriptions(&statement)
+		}; // Release index lock
+		self.notify_subscribers(subscriptions, &statement);
 		self.metrics.report(|metrics| metrics.submitted_statements.inc());
 		let network_priority = NetworkPriority::High;
 		log::trace!(target: LOG_TARGET, "Statement submitted: {:?}", HexDisplay::from(&hash));
@@ -921,10 +1121,38 @@ impl StatementStore for Store {
 					);
 					return Err(Error::Db(e.to_string()))
 				}
+				self.report_usage(&index);
 			}
 		}
 		Ok(())
 	}
+
+	/// Subscribe to statements matching `filter`. The sink is first called with matching statements
+	/// already in the store and then with each new matching statement.
+	fn subscribe(&self, filter: SubscriptionFilter, sink: SubscriptionSink) -> Result<()> {
+		let subscription = Arc::new(Subscription::new(filter, sink));
+		// Keep new statements from reaching the sink before the existing ones have been delivered.
+		let mut sink = subscription.sink.lock();
+		let existing = {
+			// Hold the index lock so that no statement is inserted between collecting the existing
+			// statements and registering the subscription.
+			let index = self.index.read();
+			let existing = self.collect_statements_locked(
+				&index,
+				subscription.filter.dest,
+				&subscription.filter.match_all_topics,
+				Some,
+			)?;
+			self.subscriptions.lock().push(subscription.clone());
+			existing
+		}; // Release index lock
+		if !existing.iter().all(|statement| sink(statement)) {
+			subscription.closed.store(true, Ordering::Relaxed);
+			drop(sink);
+			self.remove_closed_subscriptions();
+		}
+		Ok(())
+	}
 }
 
 #[cfg(test)]
@@ -935,7 +1163,7 @@ mod tests {
 	use sp_statement_store::{
 		runtime_api::{InvalidStatement, ValidStatement, ValidateStatement},
 		AccountId, Channel, DecryptionKey, NetworkPriority, Proof, SignatureVerificationResult,
-		Statement, StatementSource, StatementStore, SubmitResult, Topic,
+		Statement, StatementSource, StatementStore, SubmitResult, SubscriptionFilter, Topic,
 	};
 
 	type Extrinsic = sp_runtime::OpaqueExtrinsic;
@@ -1293,4 +1521,108 @@ mod tests {
 		let posted_clear = store.posted_clear(&[], public.into()).unwrap();
 		assert_eq!(posted_clear, vec![plain]);
 	}
+
+	#[test]
+	fn full_store_evicts_lower_priority_statements() {
+		let (store, _temp) = test_store();
+		store.index.write().options.max_total_statements = 2;
+		let source = StatementSource::Network;
+		let ok = SubmitResult::New(NetworkPriority::High);
+
+		assert_eq!(store.submit(statement(3, 1, None, 100), source), ok);
+		assert_eq!(store.submit(statement(4, 2, None, 100), source), ok);
+		// Evicts the lowest priority statement of another account.
+		assert_eq!(store.submit(statement(2, 3, None, 100), source), ok);
+		assert_eq!(store.index.read().expired.len(), 1);
+		// No statement with lower priority left to evict.
+		assert_eq!(store.submit(statement(2, 1, None, 100), source), SubmitResult::Ignored);
+
+		let mut expected_statements =
+			vec![statement(4, 2, None, 100).hash(), statement(2, 3, None, 100).hash()];
+		expected_statements.sort();
+		let mut statements: Vec<_> =
+			store.statements().unwrap().into_iter().map(|(hash, _)| hash).collect();
+		statements.sort();
+		assert_eq!(expected_statements, statements);
+	}
+
+	#[test]
+	fn outdated_statements_are_expired() {
+		let (mut store, _temp) = test_store();
+		store.index.write().options =
+			crate::Options::default().with_retention(100).with_channel_retention(channel(1), 10);
+		store.set_time(0);
+		let short_lived = statement(4, 1, Some(1), 100);
+		let long_lived = statement(4, 2, Some(2), 100);
+		store.submit(short_lived.clone(), StatementSource::Network);
+		store.submit(long_lived.clone(), StatementSource::Network);
+
+		store.set_time(10);
+		store.maintain();
+		assert_eq!(store.statement(&short_lived.hash()).unwrap(), None);
+		assert_eq!(store.statement(&long_lived.hash()).unwrap(), Some(long_lived.clone()));
+		assert_eq!(store.submit(short_lived, StatementSource::Network), SubmitResult::KnownExpired);
+
+		store.set_time(100);
+		store.maintain();
+		assert_eq!(store.index.read().entries.len(), 0);
+		assert_eq!(store.index.read().expired.len(), 2);
+	}
+
+	#[test]
+	fn subscription_receives_matching_statements() {
+		let (store, _temp) = test_store();
+		let received = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+		let existing = signed_statement_with_topics(0, &[topic(0)], None);
+		store.submit(existing.clone(), StatementSource::Network);
+
+		let sink_received = received.clone();
+		let filter = SubscriptionFilter { match_all_topics: vec![topic(0)], dest: None };
+		store
+			.subscribe(
+				filter,
+				Box::new(move |statement| {
+					sink_received.lock().push(statement.clone());
+					true
+				}),
+			)
+			.unwrap();
+		assert_eq!(store.subscriptions.lock().len(), 1);
+
+		let matching = signed_statement_with_topics(1, &[topic(0), topic(1)], None);
+		let other_topic = signed_statement_with_topics(2, &[topic(1)], None);
+		let encrypted = signed_statement_with_topics(3, &[topic(0)], Some(dec_key(0)));
+		for statement in [&matching, &other_topic, &encrypted] {
+			store.submit(statement.clone(), StatementSource::Network);
+		}
+		assert_eq!(*received.lock(), vec![existing, matching]);
+
+		// Subscription is dropped once the sink is closed.
+		store.subscribe(SubscriptionFilter::default(), Box::new(|_| false)).unwrap();
+		assert_eq!(store.subscriptions.lock().len(), 1);
+	}
+
+	#[test]
+	fn subscription_sink_is_called_without_index_lock() {
+		let (store, _temp) = test_store();
+		let store = std::sync::Arc::new(store);
+		let weak_store = std::sync::Arc::downgrade(&store);
+		let unlocked = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+		let sink_unlocked = unlocked.clone();
+		store
+			.subscribe(
+				SubscriptionFilter::default(),
+				Box::new(move |_| {
+					let store = weak_store.upgrade().expect("store outlives the subscription");
+					sink_unlocked.lock().push(store.index.try_write().is_some());
+					true
+				}),
+			)
+			.unwrap();
+		store.submit(signed_statement(0), StatementSource::Network);
+		store.submit(signed_statement(1), StatementSource::Network);
+
+		assert_eq!(*unlocked.lock(), vec![true, true]);
+	}
 }
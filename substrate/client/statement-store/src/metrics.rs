@@ -20,7 +20,7 @@
 
 use std::sync::Arc;
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
@@ -48,6 +48,13 @@ pub struct Metrics {
 	pub submitted_statements: Counter<U64>,
 	pub validations_invalid: Counter<U64>,
 	pub statements_pruned: Counter<U64>,
+	pub statements_evicted: Counter<U64>,
+	pub statements_outdated: Counter<U64>,
+	pub statements_ignored: Counter<U64>,
+	pub statements_total: Gauge<U64>,
+	pub statements_size: Gauge<U64>,
+	pub statements_capacity: Gauge<U64>,
+	pub statements_size_capacity: Gauge<U64>,
 }
 
 impl Metrics {
@@ -74,6 +81,55 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			statements_evicted: register(
+				Counter::new(
+					"substrate_sub_statement_store_evicted_statements",
+					"Total number of statements evicted in favour of higher priority statements",
+				)?,
+				registry,
+			)?,
+			statements_outdated: register(
+				Counter::new(
+					"substrate_sub_statement_store_outdated_statements",
+					"Total number of statements expired at the end of their retention period",
+				)?,
+				registry,
+			)?,
+			statements_ignored: register(
+				Counter::new(
+					"substrate_sub_statement_store_ignored_statements",
+					"Total number of statements ignored because of account or store quotas",
+				)?,
+				registry,
+			)?,
+			statements_total: register(
+				Gauge::new(
+					"substrate_sub_statement_store_statements",
+					"Number of statements in the store",
+				)?,
+				registry,
+			)?,
+			statements_size: register(
+				Gauge::new(
+					"substrate_sub_statement_store_statements_size_bytes",
+					"Total data size of statements in the store",
+				)?,
+				registry,
+			)?,
+			statements_capacity: register(
+				Gauge::new(
+					"substrate_sub_statement_store_statements_capacity",
+					"Maximum number of statements allowed in the store",
+				)?,
+				registry,
+			)?,
+			statements_size_capacity: register(
+				Gauge::new(
+					"substrate_sub_statement_store_statements_size_capacity_bytes",
+					"Maximum total data size of statements allowed in the store",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
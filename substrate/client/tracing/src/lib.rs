@@ -30,6 +30,7 @@
 
 pub mod block;
 pub mod logging;
+pub mod profiler;
 
 use rustc_hash::FxHashMap;
 use serde::ser::{Serialize, SerializeMap, Serializer};
@@ -66,6 +67,8 @@ pub struct ProfilingLayer {
 pub enum TracingReceiver {
 	/// Output to logger
 	Log,
+	/// Aggregate into a profile written to the given path, see [`profiler`]
+	Profile(std::path::PathBuf),
 }
 
 impl Default for TracingReceiver {
@@ -80,6 +83,11 @@ pub trait TraceHandler: Send + Sync {
 	fn handle_span(&self, span: &SpanDatum);
 	/// Process a `TraceEvent`.
 	fn handle_event(&self, event: &TraceEvent);
+	/// Process the closing of the span with the given `id`, whose parent is `parent_id`.
+	///
+	/// Spans are closed after all their children were closed, also if they were never passed to
+	/// [`Self::handle_span`], e.g. because they were never entered or filtered by their target.
+	fn handle_span_close(&self, _id: &Id, _parent_id: Option<&Id>) {}
 }
 
 /// Represents a tracing event, complete with values
@@ -229,6 +237,8 @@ impl ProfilingLayer {
 	pub fn new(receiver: TracingReceiver, targets: &str) -> Self {
 		match receiver {
 			TracingReceiver::Log => Self::new_with_handler(Box::new(LogTraceHandler), targets),
+			TracingReceiver::Profile(path) =>
+				Self::new_with_handler(Box::new(profiler::ProfileTraceHandler::new(path)), targets),
 		}
 	}
 
@@ -386,7 +396,12 @@ where
 		}
 	}
 
-	fn on_close(&self, _span: Id, _ctx: Context<S>) {}
+	fn on_close(&self, id: Id, ctx: Context<S>) {
+		let parent_id = ctx.span(&id).and_then(|span| span.parent()).map(|parent| parent.id());
+		self.trace_handlers
+			.iter()
+			.for_each(|handler| handler.handle_span_close(&id, parent_id.as_ref()));
+	}
 }
 
 /// TraceHandler for sending span data to the logger
@@ -0,0 +1,379 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Aggregation of spans into an execution profile.
+//!
+//! [`ProfileTraceHandler`] answers the question where the time of e.g. importing a block goes. It
+//! aggregates the time spent in every span by its stack of parent spans and writes the result as
+//! folded stacks, the format understood by `inferno-flamegraph` and `flamegraph.pl`:
+//!
+//! ```text
+//! runtime_api::Core_execute_block;sp_io::storage::get 1250000
+//! ```
+//!
+//! Each line contains the time in nanoseconds spent in the last frame of the stack, excluding the
+//! time spent in its children. Next to the folded stacks a summary with the number of calls, the
+//! total and the self time of every frame is written, with `summary` as extension.
+//!
+//! Which spans are profiled is controlled by the tracing targets, e.g.
+//! `runtime_api=trace,sp_io=trace,wasm_tracing=trace`. Host functions and runtime API calls are
+//! always instrumented, while spans of the runtime itself, e.g. of dispatched calls, require a
+//! runtime built with the `with-tracing` feature.
+
+use crate::{SpanDatum, TraceEvent, TraceHandler};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::{
+	collections::BTreeMap,
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+	sync::{
+		mpsc::{self, RecvTimeoutError},
+		Arc, Once, Weak,
+	},
+	thread,
+	time::Duration,
+};
+use tracing::span::Id;
+
+/// The default path of the profile written by [`ProfileTraceHandler`].
+pub const DEFAULT_PROFILE_PATH: &str = "profile.folded";
+
+/// The interval in which the profile is written, if it changed.
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The amount of time we'll block on exit until all profiles can be written.
+const ON_EXIT_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The writers of all profiles, flushed when the process exits.
+///
+/// The handler is usually part of the global subscriber, which is never dropped.
+static WRITERS: Mutex<Vec<Weak<ProfileWriter>>> = parking_lot::const_mutex(Vec::new());
+
+/// A span together with its children, waiting to be aggregated.
+struct Frame {
+	name: String,
+	total: Duration,
+	children: Vec<Frame>,
+}
+
+/// Statistics of all spans with the same frame name.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameStats {
+	count: u64,
+	total: Duration,
+	self_time: Duration,
+}
+
+#[derive(Default)]
+struct Profile {
+	/// Exited spans, by the id of their parent span which wasn't handled yet.
+	pending: FxHashMap<u64, Vec<Frame>>,
+	/// The self time in nanoseconds of every folded stack.
+	stacks: BTreeMap<String, u128>,
+	/// Statistics by frame name.
+	frames: BTreeMap<String, FrameStats>,
+	/// Whether the profile changed since it was last written.
+	changed: bool,
+}
+
+impl Profile {
+	fn record(&mut self, frame: &Frame, parent_stack: Option<&str>) {
+		let stack = match parent_stack {
+			Some(parent_stack) => format!("{};{}", parent_stack, frame.name),
+			None => frame.name.clone(),
+		};
+		let children_time = frame.children.iter().map(|child| child.total).sum();
+		let self_time = frame.total.saturating_sub(children_time);
+
+		*self.stacks.entry(stack.clone()).or_default() += self_time.as_nanos();
+
+		let stats = self.frames.entry(frame.name.clone()).or_default();
+		stats.count += 1;
+		stats.total += frame.total;
+		stats.self_time += self_time;
+		self.changed = true;
+
+		for child in &frame.children {
+			self.record(child, Some(&stack));
+		}
+	}
+}
+
+/// Writes the aggregated [`Profile`] to a file.
+struct ProfileWriter {
+	path: PathBuf,
+	profile: Arc<Mutex<Profile>>,
+}
+
+impl ProfileWriter {
+	/// Write the profile if it changed since the last write.
+	///
+	/// The profile is only locked to take a snapshot, so that spans can be recorded while the
+	/// files are written.
+	fn flush(&self) {
+		let (stacks, frames) = {
+			let mut profile = self.profile.lock();
+			if !profile.changed {
+				return
+			}
+			profile.changed = false;
+			(profile.stacks.clone(), profile.frames.clone())
+		};
+
+		if let Err(error) = self.write(&stacks, &frames) {
+			log::warn!("Failed to write the profile to {}: {}", self.path.display(), error);
+		}
+	}
+
+	fn write(
+		&self,
+		stacks: &BTreeMap<String, u128>,
+		frames: &BTreeMap<String, FrameStats>,
+	) -> io::Result<()> {
+		let mut folded = BufWriter::new(File::create(&self.path)?);
+		for (stack, nanos) in stacks {
+			writeln!(folded, "{} {}", stack, nanos)?;
+		}
+		folded.flush()?;
+
+		let mut frames = frames.iter().collect::<Vec<_>>();
+		frames.sort_by(|(_, a), (_, b)| b.total.cmp(&a.total));
+
+		let mut summary = BufWriter::new(File::create(self.path.with_extension("summary"))?);
+		writeln!(summary, "{:>10} {:>16} {:>16}  frame", "count", "total (ns)", "self (ns)")?;
+		for (name, stats) in frames {
+			writeln!(
+				summary,
+				"{:>10} {:>16} {:>16}  {}",
+				stats.count,
+				stats.total.as_nanos(),
+				stats.self_time.as_nanos(),
+				name,
+			)?;
+		}
+		summary.flush()
+	}
+}
+
+/// [`TraceHandler`] aggregating all spans into a profile written to a file.
+///
+/// The profile is rewritten at most once per second by a background thread, when the handler is
+/// dropped and when the process exits, never while handling a span.
+pub struct ProfileTraceHandler {
+	profile: Arc<Mutex<Profile>>,
+	writer: Arc<ProfileWriter>,
+	/// Dropping the sender stops the background thread.
+	shutdown: Option<mpsc::Sender<()>>,
+	background_writer: Option<thread::JoinHandle<()>>,
+}
+
+impl ProfileTraceHandler {
+	/// Create a new instance writing the folded stacks to `path`.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		let profile = Arc::new(Mutex::new(Profile::default()));
+		let writer = Arc::new(ProfileWriter { path: path.into(), profile: profile.clone() });
+
+		let (shutdown, shutdown_rx) = mpsc::channel::<()>();
+		let background_writer = {
+			let writer = writer.clone();
+			thread::Builder::new().name("profile-writer".into()).spawn(move || {
+				while let Err(RecvTimeoutError::Timeout) = shutdown_rx.recv_timeout(WRITE_INTERVAL)
+				{
+					writer.flush();
+				}
+			})
+		};
+		let background_writer = background_writer
+			.map_err(|error| {
+				log::warn!(
+					"Failed to spawn the profile writer, the profile is only written on exit: {}",
+					error,
+				)
+			})
+			.ok();
+
+		flush_on_exit(&writer);
+
+		Self { profile, writer, shutdown: Some(shutdown), background_writer }
+	}
+}
+
+/// Register `writer` to be flushed when the process exits.
+fn flush_on_exit(writer: &Arc<ProfileWriter>) {
+	static ONCE: Once = Once::new();
+	ONCE.call_once(|| {
+		// SAFETY: This is safe since we pass a valid pointer to `atexit`.
+		let errcode = unsafe { libc::atexit(on_exit) };
+		if errcode != 0 {
+			log::warn!("Failed to register the profile to be written on exit: {}", errcode);
+		}
+	});
+
+	let mut writers = WRITERS.lock();
+	writers.retain(|writer| writer.strong_count() > 0);
+	writers.push(Arc::downgrade(writer));
+}
+
+extern "C" fn on_exit() {
+	if let Some(writers) = WRITERS.try_lock_for(ON_EXIT_FLUSH_TIMEOUT) {
+		writers.iter().filter_map(Weak::upgrade).for_each(|writer| writer.flush());
+	}
+}
+
+/// The name of the frame of `span` in the profile.
+///
+/// Spans of runtime API calls record the called `method`, which is more useful than the name of
+/// the span.
+fn frame_name(span: &SpanDatum) -> String {
+	let name = span.values.string_values.get("method").unwrap_or(&span.name);
+	format!("{}::{}", span.target, name).replace(';', ":")
+}
+
+impl TraceHandler for ProfileTraceHandler {
+	fn handle_span(&self, span: &SpanDatum) {
+		let mut profile = self.profile.lock();
+
+		let children = profile.pending.remove(&span.id.into_u64()).unwrap_or_default();
+		let frame = Frame { name: frame_name(span), total: span.overall_time, children };
+
+		match &span.parent_id {
+			Some(parent_id) => profile.pending.entry(parent_id.into_u64()).or_default().push(frame),
+			None => profile.record(&frame, None),
+		}
+	}
+
+	fn handle_event(&self, _: &TraceEvent) {}
+
+	fn handle_span_close(&self, id: &Id, parent_id: Option<&Id>) {
+		let mut profile = self.profile.lock();
+
+		// Spans whose parent wasn't profiled, e.g. because its target was filtered, are moved to the
+		// closest profiled ancestor or recorded as roots.
+		let Some(orphans) = profile.pending.remove(&id.into_u64()) else { return };
+		match parent_id {
+			Some(parent_id) =>
+				profile.pending.entry(parent_id.into_u64()).or_default().extend(orphans),
+			None => orphans.iter().for_each(|frame| profile.record(frame, None)),
+		}
+	}
+}
+
+impl Drop for ProfileTraceHandler {
+	fn drop(&mut self) {
+		self.shutdown.take();
+		if let Some(background_writer) = self.background_writer.take() {
+			let _ = background_writer.join();
+		}
+
+		self.writer.flush();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Values;
+	use std::time::Instant;
+	use tracing::Level;
+
+	fn span(id: u64, parent_id: Option<u64>, name: &str, nanos: u64) -> SpanDatum {
+		SpanDatum {
+			id: Id::from_u64(id),
+			parent_id: parent_id.map(Id::from_u64),
+			name: name.into(),
+			target: "test".into(),
+			level: Level::TRACE,
+			line: 0,
+			start_time: Instant::now(),
+			overall_time: Duration::from_nanos(nanos),
+			values: Values::new(),
+		}
+	}
+
+	#[test]
+	fn spans_are_folded_into_stacks() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("profile.folded");
+		let handler = ProfileTraceHandler::new(&path);
+
+		// Children exit before their parents.
+		handler.handle_span(&span(3, Some(2), "get", 10));
+		handler.handle_span(&span(4, Some(2), "get", 20));
+		handler.handle_span(&span(2, Some(1), "transfer", 50));
+		handler.handle_span(&span(5, Some(1), "set", 30));
+		handler.handle_span(&span(1, None, "execute_block", 100));
+
+		{
+			let profile = handler.profile.lock();
+			assert!(profile.pending.is_empty());
+			assert_eq!(
+				profile.stacks.iter().map(|(k, v)| (k.as_str(), *v)).collect::<Vec<_>>(),
+				vec![
+					("test::execute_block", 20),
+					("test::execute_block;test::set", 30),
+					("test::execute_block;test::transfer", 20),
+					("test::execute_block;test::transfer;test::get", 30),
+				],
+			);
+			assert_eq!(
+				profile.frames["test::get"],
+				FrameStats {
+					count: 2,
+					total: Duration::from_nanos(30),
+					self_time: Duration::from_nanos(30)
+				},
+			);
+		}
+
+		drop(handler);
+
+		let folded = std::fs::read_to_string(&path).unwrap();
+		assert!(folded.contains("test::execute_block;test::transfer;test::get 30\n"));
+		assert!(path.with_extension("summary").exists());
+	}
+
+	#[test]
+	fn spans_of_unprofiled_parents_are_not_lost() {
+		let dir = tempfile::tempdir().unwrap();
+		let handler = ProfileTraceHandler::new(dir.path().join("profile.folded"));
+
+		// `2` is never handled, e.g. a wasm span with a filtered target.
+		handler.handle_span(&span(3, Some(2), "get", 10));
+		handler.handle_span_close(&Id::from_u64(3), Some(&Id::from_u64(2)));
+		handler.handle_span_close(&Id::from_u64(2), Some(&Id::from_u64(1)));
+		handler.handle_span(&span(1, None, "execute_block", 100));
+		handler.handle_span_close(&Id::from_u64(1), None);
+
+		// `5` is never handled and has no parent.
+		handler.handle_span(&span(6, Some(5), "set", 30));
+		handler.handle_span_close(&Id::from_u64(5), None);
+
+		let profile = handler.profile.lock();
+		assert!(profile.pending.is_empty());
+		assert_eq!(
+			profile.stacks.iter().map(|(k, v)| (k.as_str(), *v)).collect::<Vec<_>>(),
+			vec![
+				("test::execute_block", 90),
+				("test::execute_block;test::get", 10),
+				("test::set", 30),
+			],
+		);
+	}
+}
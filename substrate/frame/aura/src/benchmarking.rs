@@ -0,0 +1,75 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the Aura Pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use alloc::vec;
+use frame_benchmarking::v2::*;
+use sp_runtime::{
+	traits::{Hash, Header as _, One},
+	Digest,
+};
+
+/// Creates a header for `slot` sealed by `offender`. The `seed` is used to produce distinct
+/// headers for the same slot.
+fn make_sealed_header<T: Config>(offender: &T::AuthorityId, slot: Slot, seed: u8) -> HeaderFor<T> {
+	let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	let mut header = HeaderFor::<T>::new(
+		One::one(),
+		T::Hashing::hash(&[seed]),
+		Default::default(),
+		Default::default(),
+		digest,
+	);
+
+	let pre_hash = header.hash();
+	let signature = offender.sign(&pre_hash.as_ref()).expect("key was generated above; qed");
+	header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature.encode()));
+	header
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn check_equivocation_proof() {
+		let offender = T::AuthorityId::generate_pair(None);
+		let slot = Slot::from(42);
+
+		let equivocation_proof1 = EquivocationProof::<HeaderFor<T>, T::AuthorityId> {
+			offender: offender.clone(),
+			slot,
+			first_header: make_sealed_header::<T>(&offender, slot, 1),
+			second_header: make_sealed_header::<T>(&offender, slot, 2),
+		};
+
+		let equivocation_proof2 = equivocation_proof1.clone();
+
+		#[block]
+		{
+			sp_consensus_aura::check_equivocation_proof(equivocation_proof1);
+		}
+
+		assert!(sp_consensus_aura::check_equivocation_proof(equivocation_proof2));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::build_ext(vec![0, 1, 2, 3]), crate::mock::Test);
+}
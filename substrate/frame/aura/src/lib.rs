@@ -24,13 +24,43 @@
 //!
 //! The Aura module extends Aura consensus by managing offline reporting.
 //!
+//! Equivocations, i.e. authors producing more than one block for the same slot, can be reported
+//! through [`Call::report_equivocation`] and [`Call::report_equivocation_unsigned`]. Offences are
+//! handled by the [`Config::EquivocationReportSystem`], see [`EquivocationReportSystem`].
+//!
 //! ## Interface
 //!
+//! ### Dispatchable Functions
+//!
+//! - `report_equivocation` - Report an authority for producing two blocks for the same slot.
+//! - `report_equivocation_unsigned` - Same as `report_equivocation`, but submitted by the block
+//!   author as an unsigned extrinsic.
+//!
 //! ### Public Functions
 //!
 //! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module
 //!   configuration.
 //!
+//! ## Migration
+//!
+//! Equivocation reporting added four items to [`Config`]. Runtimes that don't report
+//! equivocations, e.g. because they have no session history to prove key ownership with, can
+//! disable it with:
+//!
+//! ```ignore
+//! impl pallet_aura::Config for Runtime {
+//! 	// ...
+//! 	type WeightInfo = ();
+//! 	type MaxNominators = ConstU32<0>;
+//! 	type KeyOwnerProof = sp_core::Void;
+//! 	type EquivocationReportSystem = ();
+//! }
+//! ```
+//!
+//! Runtimes that do report them should use [`weights::SubstrateWeight`] or their own benchmarked
+//! weights, the session historical pallet's `Proof` as the key owner proof and
+//! [`EquivocationReportSystem`] as the report system.
+//!
 //! ## Related Modules
 //!
 //! - [Timestamp](../pallet_timestamp/index.html): The Timestamp module is used in Aura to track
@@ -40,28 +70,73 @@
 
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 use codec::{Decode, Encode, MaxEncodedLen};
 use frame_support::{
+	dispatch::{DispatchResultWithPostInfo, Pays},
 	traits::{DisabledValidators, FindAuthor, Get, OnTimestampSet, OneSessionHandler},
+	weights::{
+		constants::{
+			RocksDbWeight as DbWeight, WEIGHT_REF_TIME_PER_MICROS, WEIGHT_REF_TIME_PER_NANOS,
+		},
+		Weight,
+	},
 	BoundedSlice, BoundedVec, ConsensusEngineId, Parameter,
 };
+use frame_system::pallet_prelude::HeaderFor;
 use log;
-use sp_consensus_aura::{AuthorityIndex, ConsensusLog, Slot, AURA_ENGINE_ID};
+use sp_consensus_aura::{AuthorityIndex, ConsensusLog, EquivocationProof, Slot, AURA_ENGINE_ID};
 use sp_runtime::{
 	generic::DigestItem,
 	traits::{IsMember, Member, SaturatedConversion, Saturating, Zero},
 	RuntimeAppPublic,
 };
+use sp_session::{GetSessionNumber, GetValidatorCount};
+use sp_staking::offence::OffenceReportSystem;
 
+mod equivocation;
 pub mod migrations;
 mod mock;
 mod tests;
+pub mod weights;
 
+#[cfg(any(feature = "runtime-benchmarks", test))]
+mod benchmarking;
+
+pub use equivocation::{EquivocationOffence, EquivocationReportSystem};
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 const LOG_TARGET: &str = "runtime::aura";
 
+pub(crate) trait WeightInfoExt: WeightInfo {
+	fn report_equivocation(validator_count: u32, max_nominators_per_validator: u32) -> Weight {
+		// we take the validator set count from the membership proof to
+		// calculate the weight but we set a floor of 100 validators.
+		let validator_count = validator_count.max(100) as u64;
+
+		// checking membership proof
+		Weight::from_parts(35u64 * WEIGHT_REF_TIME_PER_MICROS, 0)
+			.saturating_add(
+				Weight::from_parts(175u64 * WEIGHT_REF_TIME_PER_NANOS, 0)
+					.saturating_mul(validator_count),
+			)
+			.saturating_add(DbWeight::get().reads(5))
+			// check equivocation proof
+			.saturating_add(Self::check_equivocation_proof())
+			// report offence
+			.saturating_add(Weight::from_parts(110u64 * WEIGHT_REF_TIME_PER_MICROS, 0))
+			.saturating_add(Weight::from_parts(
+				25u64 * WEIGHT_REF_TIME_PER_MICROS * max_nominators_per_validator as u64,
+				0,
+			))
+			.saturating_add(DbWeight::get().reads(14 + 3 * max_nominators_per_validator as u64))
+			.saturating_add(DbWeight::get().writes(10 + 3 * max_nominators_per_validator as u64))
+	}
+}
+
+impl<T> WeightInfoExt for T where T: WeightInfo {}
+
 /// A slot duration provider which infers the slot duration from the
 /// [`pallet_timestamp::Config::MinimumPeriod`] by multiplying it by two, to ensure
 /// that authors have the majority of their slot to author within.
@@ -118,6 +193,44 @@ pub mod pallet {
 		/// For backwards compatibility either use [`MinimumPeriodTimesTwo`] or a const.
 		#[pallet::constant]
 		type SlotDuration: Get<<Self as pallet_timestamp::Config>::Moment>;
+
+		/// Helper for weights computations
+		///
+		/// Use `()` if equivocations aren't reported, see [`migration`](crate#migration).
+		type WeightInfo: WeightInfo;
+
+		/// The maximum number of nominators for each validator.
+		///
+		/// Use `ConstU32<0>` if equivocations aren't reported.
+		#[pallet::constant]
+		type MaxNominators: Get<u32>;
+
+		/// The proof of key ownership, used for validating equivocation reports.
+		/// The proof must include the session index and validator count of the
+		/// session at which the equivocation occurred.
+		///
+		/// Use `sp_core::Void` if equivocations aren't reported.
+		type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+		/// The equivocation handling subsystem, defines methods to check/report an
+		/// offence and for submitting a transaction to report an equivocation
+		/// (from an offchain context).
+		///
+		/// Use `()` if equivocations aren't reported.
+		type EquivocationReportSystem: OffenceReportSystem<
+			Option<Self::AccountId>,
+			(EquivocationProof<HeaderFor<Self>, Self::AuthorityId>, Self::KeyOwnerProof),
+		>;
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
 	}
 
 	#[pallet::pallet]
@@ -185,6 +298,70 @@ pub mod pallet {
 			Pallet::<T>::initialize_authorities(&self.authorities);
 		}
 	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::report_equivocation(
+			key_owner_proof.validator_count(),
+			T::MaxNominators::get(),
+		))]
+		pub fn report_equivocation(
+			origin: OriginFor<T>,
+			equivocation_proof: Box<EquivocationProof<HeaderFor<T>, T::AuthorityId>>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			let reporter = ensure_signed(origin)?;
+			T::EquivocationReportSystem::process_evidence(
+				Some(reporter),
+				(*equivocation_proof, key_owner_proof),
+			)?;
+			// Waive the fee since the report is valid and beneficial
+			Ok(Pays::No.into())
+		}
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		/// This extrinsic must be called unsigned and it is expected that only
+		/// block authors will call it (validated in `ValidateUnsigned`), as such
+		/// if the block author is defined it will be defined as the equivocation
+		/// reporter.
+		#[pallet::call_index(1)]
+		#[pallet::weight(<T as Config>::WeightInfo::report_equivocation(
+			key_owner_proof.validator_count(),
+			T::MaxNominators::get(),
+		))]
+		pub fn report_equivocation_unsigned(
+			origin: OriginFor<T>,
+			equivocation_proof: Box<EquivocationProof<HeaderFor<T>, T::AuthorityId>>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			T::EquivocationReportSystem::process_evidence(
+				None,
+				(*equivocation_proof, key_owner_proof),
+			)?;
+			Ok(Pays::No.into())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+		fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			Self::validate_unsigned(source, call)
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			Self::pre_dispatch(call)
+		}
+	}
 }
 
 impl<T: Config> Pallet<T> {
@@ -247,6 +424,17 @@ impl<T: Config> Pallet<T> {
 		T::SlotDuration::get()
 	}
 
+	/// Submits an extrinsic to report an equivocation. This method will create
+	/// an unsigned extrinsic with a call to `report_equivocation_unsigned` and
+	/// will push the transaction to the pool. Only useful in an offchain
+	/// context.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<HeaderFor<T>, T::AuthorityId>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::EquivocationReportSystem::publish_evidence((equivocation_proof, key_owner_proof)).ok()
+	}
+
 	/// Ensure the correctness of the state of this pallet.
 	///
 	/// This should be valid before or after each state transition of this pallet.
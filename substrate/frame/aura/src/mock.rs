@@ -19,15 +19,29 @@
 
 #![cfg(test)]
 
-use crate as pallet_aura;
+use crate::{self as pallet_aura, EquivocationOffence};
+use codec::Encode;
 use frame_support::{
 	derive_impl, parameter_types,
-	traits::{ConstU32, ConstU64, DisabledValidators},
+	traits::{ConstU32, ConstU64, DisabledValidators, KeyOwnerProofSystem, OnInitialize},
 };
-use sp_consensus_aura::{ed25519::AuthorityId, AuthorityIndex};
-use sp_runtime::{testing::UintAuthorityId, BuildStorage};
+use sp_consensus_aura::{
+	digests::CompatibleDigestItem,
+	ed25519::{AuthorityId, AuthorityPair},
+	AuthorityIndex, EquivocationProof, Slot, AURA_ENGINE_ID,
+};
+use sp_core::Pair;
+use sp_keystore::{testing::MemoryKeystore, KeystoreExt};
+use sp_runtime::{
+	testing::{TestXt, UintAuthorityId},
+	traits::Header as _,
+	BuildStorage, Digest, DigestItem, KeyTypeId,
+};
+use sp_session::MembershipProof;
+use sp_staking::offence::{OffenceError, ReportOffence};
 
 type Block = frame_system::mocking::MockBlock<Test>;
+type Header = frame_system::pallet_prelude::HeaderFor<Test>;
 
 const SLOT_DURATION: u64 = 2;
 
@@ -36,6 +50,7 @@ frame_support::construct_runtime!(
 	{
 		System: frame_system,
 		Timestamp: pallet_timestamp,
+		Authorship: pallet_authorship,
 		Aura: pallet_aura,
 	}
 );
@@ -45,6 +60,28 @@ impl frame_system::Config for Test {
 	type Block = Block;
 }
 
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type RuntimeCall = RuntimeCall;
+	type Extrinsic = TestXt<RuntimeCall, ()>;
+}
+
+impl<C> frame_system::offchain::CreateInherent<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_inherent(call: Self::RuntimeCall) -> Self::Extrinsic {
+		TestXt::new_bare(call)
+	}
+}
+
+impl pallet_authorship::Config for Test {
+	type FindAuthor = ();
+	type EventHandler = ();
+}
+
 impl pallet_timestamp::Config for Test {
 	type Moment = u64;
 	type OnTimestampSet = Aura;
@@ -79,22 +116,87 @@ impl DisabledValidators for MockDisabledValidators {
 	}
 }
 
+/// The session index reported by [`MockKeyOwnerProofSystem`].
+pub const SESSION_INDEX: u32 = 0;
+
+/// A key ownership proof system which proves membership in the current Aura authority set.
+pub struct MockKeyOwnerProofSystem;
+
+impl KeyOwnerProofSystem<(KeyTypeId, AuthorityId)> for MockKeyOwnerProofSystem {
+	type Proof = MembershipProof;
+	type IdentificationTuple = AuthorityId;
+
+	fn prove((_, key): (KeyTypeId, AuthorityId)) -> Option<Self::Proof> {
+		let authorities = pallet_aura::Authorities::<Test>::get();
+		authorities.contains(&key).then(|| MembershipProof {
+			session: SESSION_INDEX,
+			trie_nodes: Vec::new(),
+			validator_count: authorities.len() as u32,
+		})
+	}
+
+	fn check_proof(key: (KeyTypeId, AuthorityId), proof: Self::Proof) -> Option<AuthorityId> {
+		(Self::prove(key.clone())? == proof).then_some(key.1)
+	}
+}
+
+parameter_types! {
+	pub static Offences: Vec<(Vec<u64>, Slot, AuthorityId)> = Vec::new();
+}
+
+/// An offence handler which records all reported offences in [`Offences`].
+pub struct OffenceHandler;
+
+impl ReportOffence<u64, AuthorityId, EquivocationOffence<AuthorityId>> for OffenceHandler {
+	fn report_offence(
+		reporters: Vec<u64>,
+		offence: EquivocationOffence<AuthorityId>,
+	) -> Result<(), OffenceError> {
+		if Self::is_known_offence(&[offence.offender.clone()], &offence.slot) {
+			return Err(OffenceError::DuplicateReport)
+		}
+
+		Offences::mutate(|offences| offences.push((reporters, offence.slot, offence.offender)));
+		Ok(())
+	}
+
+	fn is_known_offence(offenders: &[AuthorityId], time_slot: &Slot) -> bool {
+		Offences::get()
+			.iter()
+			.any(|(_, slot, offender)| slot == time_slot && offenders.contains(offender))
+	}
+}
+
 impl pallet_aura::Config for Test {
 	type AuthorityId = AuthorityId;
 	type DisabledValidators = MockDisabledValidators;
 	type MaxAuthorities = ConstU32<10>;
 	type AllowMultipleBlocksPerSlot = AllowMultipleBlocksPerSlot;
 	type SlotDuration = ConstU64<SLOT_DURATION>;
+	type WeightInfo = ();
+	type MaxNominators = ConstU32<100>;
+	type KeyOwnerProof = MembershipProof;
+	type EquivocationReportSystem = pallet_aura::EquivocationReportSystem<
+		Self,
+		OffenceHandler,
+		MockKeyOwnerProofSystem,
+		ConstU64<5>,
+	>;
+}
+
+pub fn build_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
+	build_ext_with_authorities(
+		authorities.into_iter().map(|a| UintAuthorityId(a).to_public_key()).collect(),
+	)
 }
 
-fn build_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
+pub fn build_ext_with_authorities(authorities: Vec<AuthorityId>) -> sp_io::TestExternalities {
 	let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
-	pallet_aura::GenesisConfig::<Test> {
-		authorities: authorities.into_iter().map(|a| UintAuthorityId(a).to_public_key()).collect(),
-	}
-	.assimilate_storage(&mut storage)
-	.unwrap();
-	storage.into()
+	pallet_aura::GenesisConfig::<Test> { authorities }.assimilate_storage(&mut storage).unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.register_extension(KeystoreExt::new(MemoryKeystore::new()));
+	ext
 }
 
 pub fn build_ext_and_execute_test(authorities: Vec<u64>, test: impl FnOnce() -> ()) {
@@ -104,3 +206,54 @@ pub fn build_ext_and_execute_test(authorities: Vec<u64>, test: impl FnOnce() ->
 		Aura::do_try_state().expect("Storage invariants should hold")
 	});
 }
+
+/// Creates the authority key pairs used by [`build_ext_with_authorities`] in the equivocation
+/// tests.
+pub fn authority_pairs(count: u8) -> Vec<AuthorityPair> {
+	(0..count).map(|i| AuthorityPair::from_seed(&[i; 32])).collect()
+}
+
+/// Initializes a block at `slot` so that equivocation reports for earlier slots are accepted.
+pub fn start_slot(slot: Slot) {
+	let pre_digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	System::reset_events();
+	System::initialize(&1, &System::parent_hash(), &pre_digest);
+	Aura::on_initialize(1);
+}
+
+/// Signs the header prehash and adds the signature to the header as the seal digest item.
+pub fn seal_header(offender: &AuthorityPair, mut header: Header) -> Header {
+	let prehash = header.hash();
+	let seal = <DigestItem as CompatibleDigestItem<_>>::aura_seal(offender.sign(prehash.as_ref()));
+	header.digest_mut().push(seal);
+	header
+}
+
+/// Generates two headers for `slot` sealed by `offender` and packs them into an equivocation
+/// proof.
+pub fn generate_equivocation_proof(
+	offender: &AuthorityPair,
+	slot: Slot,
+) -> EquivocationProof<Header, AuthorityId> {
+	let make_header = |state_root: u8| {
+		let pre_digest = <DigestItem as CompatibleDigestItem<
+			sp_consensus_aura::ed25519::AuthoritySignature,
+		>>::aura_pre_digest(slot);
+		let header = Header::new(
+			1,
+			Default::default(),
+			[state_root; 32].into(),
+			System::parent_hash(),
+			Digest { logs: vec![pre_digest] },
+		);
+
+		seal_header(offender, header)
+	};
+
+	EquivocationProof {
+		offender: offender.public(),
+		slot,
+		first_header: make_header(1),
+		second_header: make_header(2),
+	}
+}
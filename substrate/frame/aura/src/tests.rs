@@ -20,11 +20,27 @@
 #![cfg(test)]
 
 use super::pallet;
-use crate::mock::{build_ext_and_execute_test, Aura, MockDisabledValidators, System, Test};
+use crate::{
+	mock::{
+		authority_pairs, build_ext_and_execute_test, build_ext_with_authorities,
+		generate_equivocation_proof, seal_header, start_slot, AllowMultipleBlocksPerSlot, Aura,
+		MockDisabledValidators, MockKeyOwnerProofSystem, Offences, RuntimeOrigin, System, Test,
+	},
+	Call, Error,
+};
 use codec::Encode;
-use frame_support::traits::OnInitialize;
-use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
-use sp_runtime::{Digest, DigestItem};
+use frame_support::{
+	assert_err, assert_ok,
+	traits::{KeyOwnerProofSystem, OnInitialize},
+	unsigned::ValidateUnsigned,
+};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID, KEY_TYPE};
+use sp_core::Pair;
+use sp_runtime::{
+	traits::Header as _,
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	Digest, DigestItem,
+};
 
 #[test]
 fn initial_values() {
@@ -117,3 +133,248 @@ fn pallet_always_rejects_decreasing_slot() {
 		Aura::on_initialize(43);
 	});
 }
+
+#[test]
+fn report_equivocation_works() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		start_slot(10.into());
+
+		let offender = &pairs[1];
+		let equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+
+		assert_ok!(Aura::report_equivocation_unsigned(
+			RuntimeOrigin::none(),
+			Box::new(equivocation_proof),
+			key_owner_proof,
+		));
+
+		assert_eq!(Offences::get(), vec![(vec![], 9.into(), offender.public())]);
+	});
+}
+
+#[test]
+fn report_equivocation_signed_uses_reporter() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		start_slot(10.into());
+
+		let offender = &pairs[2];
+		let equivocation_proof = generate_equivocation_proof(offender, 10.into());
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+
+		assert_ok!(Aura::report_equivocation(
+			RuntimeOrigin::signed(42),
+			Box::new(equivocation_proof),
+			key_owner_proof,
+		));
+
+		assert_eq!(Offences::get(), vec![(vec![42], 10.into(), offender.public())]);
+	});
+}
+
+#[test]
+fn report_equivocation_invalid_equivocation_proof() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		start_slot(10.into());
+
+		let offender = &pairs[1];
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+
+		let assert_invalid_equivocation_proof = |equivocation_proof| {
+			assert_err!(
+				Aura::report_equivocation_unsigned(
+					RuntimeOrigin::none(),
+					Box::new(equivocation_proof),
+					key_owner_proof.clone(),
+				),
+				Error::<Test>::InvalidEquivocationProof,
+			);
+		};
+
+		// both headers are the same
+		let mut equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		equivocation_proof.second_header = equivocation_proof.first_header.clone();
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the headers target different slots
+		let mut equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		equivocation_proof.second_header =
+			generate_equivocation_proof(offender, 8.into()).second_header;
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the proof slot doesn't match the headers
+		let mut equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		equivocation_proof.slot = 8.into();
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the headers weren't signed by the offender
+		let mut equivocation_proof = generate_equivocation_proof(&pairs[2], 9.into());
+		equivocation_proof.offender = offender.public();
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the slot didn't start yet
+		let equivocation_proof = generate_equivocation_proof(offender, 11.into());
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		assert!(Offences::get().is_empty());
+	});
+}
+
+#[test]
+fn report_equivocation_requires_same_parent_with_multiple_blocks_per_slot() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		AllowMultipleBlocksPerSlot::set(true);
+		start_slot(10.into());
+
+		let offender = &pairs[1];
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+
+		// the second block was authored on top of the first one in the same slot
+		let mut equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		let mut second_header = equivocation_proof.first_header.clone();
+		second_header.digest_mut().pop();
+		second_header.set_parent_hash(equivocation_proof.first_header.hash());
+		second_header.set_number(2);
+		equivocation_proof.second_header = seal_header(offender, second_header);
+
+		assert_err!(
+			Aura::report_equivocation_unsigned(
+				RuntimeOrigin::none(),
+				Box::new(equivocation_proof),
+				key_owner_proof.clone(),
+			),
+			Error::<Test>::InvalidEquivocationProof,
+		);
+
+		// competing blocks built on the same parent are still an equivocation
+		let equivocation_proof = generate_equivocation_proof(offender, 9.into());
+		assert_ok!(Aura::report_equivocation_unsigned(
+			RuntimeOrigin::none(),
+			Box::new(equivocation_proof),
+			key_owner_proof,
+		));
+
+		assert_eq!(Offences::get(), vec![(vec![], 9.into(), offender.public())]);
+	});
+}
+
+#[test]
+fn report_equivocation_invalid_key_owner_proof() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		start_slot(10.into());
+
+		let offender = &pairs[1];
+		let equivocation_proof = generate_equivocation_proof(offender, 9.into());
+
+		// the key owner proof doesn't match the current session
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+		let mut wrong_session = key_owner_proof.clone();
+		wrong_session.session += 1;
+		let mut wrong_validator_count = key_owner_proof.clone();
+		wrong_validator_count.validator_count += 1;
+
+		for key_owner_proof in [wrong_session, wrong_validator_count] {
+			assert_err!(
+				Aura::report_equivocation_unsigned(
+					RuntimeOrigin::none(),
+					Box::new(equivocation_proof.clone()),
+					key_owner_proof,
+				),
+				Error::<Test>::InvalidKeyOwnershipProof,
+			);
+		}
+
+		// an authority outside of the current set can't be reported
+		let outsider = &authority_pairs(5)[4];
+		let equivocation_proof = generate_equivocation_proof(outsider, 9.into());
+		assert!(MockKeyOwnerProofSystem::prove((KEY_TYPE, outsider.public())).is_none());
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+		assert_err!(
+			Aura::report_equivocation_unsigned(
+				RuntimeOrigin::none(),
+				Box::new(equivocation_proof),
+				key_owner_proof,
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+	});
+}
+
+#[test]
+fn report_equivocation_validate_unsigned_prevents_duplicates() {
+	let pairs = authority_pairs(4);
+	let authorities = pairs.iter().map(|pair| pair.public()).collect();
+
+	build_ext_with_authorities(authorities).execute_with(|| {
+		start_slot(10.into());
+
+		let offender = &pairs[3];
+		let equivocation_proof = generate_equivocation_proof(offender, 7.into());
+		let key_owner_proof =
+			MockKeyOwnerProofSystem::prove((KEY_TYPE, offender.public())).unwrap();
+
+		let inner = Call::report_equivocation_unsigned {
+			equivocation_proof: Box::new(equivocation_proof.clone()),
+			key_owner_proof: key_owner_proof.clone(),
+		};
+
+		// only local/inblock reports are allowed
+		assert_eq!(
+			<Aura as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &inner),
+			InvalidTransaction::Call.into(),
+		);
+
+		// the transaction is valid when passed as local
+		let tx_tag = (offender.public(), 7u64);
+		let tx = <Aura as ValidateUnsigned>::validate_unsigned(TransactionSource::Local, &inner)
+			.unwrap();
+		assert_eq!(tx.priority, u64::MAX);
+		assert_eq!(tx.requires, Vec::<Vec<u8>>::new());
+		assert_eq!(tx.provides, vec![("AuraEquivocation", tx_tag).encode()]);
+		assert_eq!(tx.longevity, 5);
+		assert!(!tx.propagate);
+
+		// the pre dispatch checks should also pass
+		assert_ok!(<Aura as ValidateUnsigned>::pre_dispatch(&inner));
+
+		// we submit the report
+		assert_ok!(Aura::report_equivocation_unsigned(
+			RuntimeOrigin::none(),
+			Box::new(equivocation_proof),
+			key_owner_proof,
+		));
+
+		// the report should now be considered stale and the transaction is invalid.
+		// the check for staleness should be done on both `validate_unsigned` and on `pre_dispatch`
+		assert_err!(
+			<Aura as ValidateUnsigned>::validate_unsigned(TransactionSource::Local, &inner),
+			InvalidTransaction::Stale,
+		);
+
+		assert_err!(
+			<Aura as ValidateUnsigned>::pre_dispatch(&inner),
+			TransactionValidityError::Invalid(InvalidTransaction::Stale),
+		);
+	});
+}
@@ -42,8 +42,9 @@ use sp_runtime::{
 };
 
 use alloc::vec::Vec;
-use codec::Decode;
+use codec::{Decode, Encode};
 use pallet_mmr::{primitives::AncestryProof, LeafDataProvider, NodesUtils, ParentNumberAndHash};
+use scale_info::TypeInfo;
 use sp_consensus_beefy::{
 	known_payloads,
 	mmr::{BeefyAuthoritySet, BeefyDataProvider, BeefyNextAuthoritySet, MmrLeaf, MmrLeafVersion},
@@ -99,6 +100,21 @@ impl Convert<sp_consensus_beefy::ecdsa_crypto::AuthorityId, Vec<u8>> for BeefyEc
 
 type MerkleRootOf<T> = <<T as pallet_mmr::Config>::Hashing as sp_runtime::traits::Hash>::Output;
 
+/// A merkle proof of a single BEEFY authority being part of the `keyset_commitment` of a
+/// [`BeefyAuthoritySet`].
+///
+/// The root and the number of leaves are not part of the proof, they are given by the
+/// `keyset_commitment` and `len` of the authority set the proof was generated for.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+pub struct AuthorityMerkleProof<H> {
+	/// Index of the authority in the set.
+	pub leaf_index: u32,
+	/// The merkle leaf of the authority, see [`Config::BeefyAuthorityToMerkleLeaf`].
+	pub leaf: Vec<u8>,
+	/// Proof items (does not contain the leaf hash, nor the root).
+	pub proof: Vec<H>,
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	#![allow(missing_docs)]
@@ -339,6 +355,41 @@ impl<T: Config> Pallet<T> {
 		BeefyNextAuthorities::<T>::get()
 	}
 
+	/// Return merkle proofs of the authorities at the given indices being part of the currently
+	/// active BEEFY authority set, i.e. the one returned by [`Self::authority_set_proof`].
+	///
+	/// Indices outside of the authority set are skipped.
+	pub fn authority_set_merkle_proofs(
+		authority_indices: Vec<u32>,
+	) -> Vec<AuthorityMerkleProof<MerkleRootOf<T>>> {
+		let Some(validator_set) = pallet_beefy::Pallet::<T>::validator_set() else {
+			return Vec::new()
+		};
+		let leaves = validator_set
+			.validators()
+			.iter()
+			.cloned()
+			.map(T::BeefyAuthorityToMerkleLeaf::convert)
+			.collect::<Vec<_>>();
+
+		authority_indices
+			.into_iter()
+			.filter(|index| (*index as usize) < leaves.len())
+			.map(|index| {
+				let proof = binary_merkle_tree::merkle_proof::<
+					<T as pallet_mmr::Config>::Hashing,
+					_,
+					_,
+				>(leaves.iter(), index);
+				AuthorityMerkleProof {
+					leaf_index: index,
+					leaf: proof.leaf.to_vec(),
+					proof: proof.proof,
+				}
+			})
+			.collect()
+	}
+
 	/// Returns details of a BEEFY authority set.
 	///
 	/// Details contain authority set id, authority set length and a merkle root,
@@ -379,6 +430,7 @@ impl<T: Config> Pallet<T> {
 
 sp_api::decl_runtime_apis! {
 	/// API useful for BEEFY light clients.
+	#[api_version(2)]
 	pub trait BeefyMmrApi<H>
 	where
 		BeefyAuthoritySet<H>: Decode,
@@ -388,5 +440,10 @@ sp_api::decl_runtime_apis! {
 
 		/// Return the next/queued BEEFY authority set proof.
 		fn next_authority_set_proof() -> BeefyNextAuthoritySet<H>;
+
+		/// Return merkle proofs of the authorities at the given indices being part of the
+		/// currently active BEEFY authority set.
+		#[api_version(2)]
+		fn authority_set_merkle_proofs(authority_indices: Vec<u32>) -> Vec<AuthorityMerkleProof<H>>;
 	}
 }
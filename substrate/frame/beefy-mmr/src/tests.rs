@@ -202,6 +202,30 @@ fn should_update_authorities() {
 	});
 }
 
+#[test]
+fn should_generate_authority_set_merkle_proofs() {
+	new_test_ext(vec![1, 2, 3, 4]).execute_with(|| {
+		init_block(1, None);
+		let auth_set = BeefyMmr::authority_set_proof();
+
+		let proofs = BeefyMmr::authority_set_merkle_proofs(vec![0, 1, 2]);
+
+		// the index outside of the authority set is skipped
+		assert_eq!(proofs.len(), auth_set.len as usize);
+
+		for (index, proof) in proofs.into_iter().enumerate() {
+			assert_eq!(proof.leaf_index, index as u32);
+			assert!(binary_merkle_tree::verify_proof::<Keccak256, _, _>(
+				&auth_set.keyset_commitment,
+				proof.proof,
+				auth_set.len,
+				proof.leaf_index,
+				&proof.leaf,
+			));
+		}
+	});
+}
+
 #[test]
 fn extract_validation_context_should_work_correctly() {
 	let mut ext = new_test_ext(vec![1, 2]);
@@ -72,6 +72,16 @@ mod benchmarks {
 		assert!(Stalled::<T>::get().is_some());
 	}
 
+	#[benchmark]
+	fn set_finality_delay() {
+		let delay = Some(10u32.into());
+
+		#[extrinsic_call]
+		_(RawOrigin::Root, delay);
+
+		assert_eq!(FinalityDelay::<T>::get(), delay);
+	}
+
 	impl_benchmark_test_suite!(
 		Pallet,
 		crate::mock::new_test_ext(vec![(1, 1), (2, 1), (3, 1)]),
@@ -54,4 +54,9 @@ impl crate::WeightInfo for () {
 		Weight::from_parts(3u64 * WEIGHT_REF_TIME_PER_MICROS, 0)
 			.saturating_add(DbWeight::get().writes(1))
 	}
+
+	fn set_finality_delay() -> Weight {
+		Weight::from_parts(3u64 * WEIGHT_REF_TIME_PER_MICROS, 0)
+			.saturating_add(DbWeight::get().writes(1))
+	}
 }
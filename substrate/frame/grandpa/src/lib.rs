@@ -263,6 +263,25 @@ pub mod pallet {
 			Self::on_stalled(delay, best_finalized_block_number);
 			Ok(())
 		}
+
+		/// Set the number of blocks GRANDPA voters should stay behind the best block.
+		///
+		/// This is exposed to nodes through the `GrandpaFinalityDelayApi` runtime API and only
+		/// affects voters that opted into the corresponding voting rule. Passing `None` clears
+		/// the delay, in which case votes are not restricted.
+		///
+		/// Only callable by root.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::set_finality_delay())]
+		pub fn set_finality_delay(
+			origin: OriginFor<T>,
+			delay: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			FinalityDelay::<T>::set(delay);
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
@@ -319,6 +338,10 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type Stalled<T: Config> = StorageValue<_, (BlockNumberFor<T>, BlockNumberFor<T>)>;
 
+	/// The number of blocks voters should stay behind the best block, if any.
+	#[pallet::storage]
+	pub type FinalityDelay<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
 	/// The number of changes (both in terms of keys and underlying economic responsibilities)
 	/// in the "set" of Grandpa validators from genesis.
 	#[pallet::storage]
@@ -375,6 +398,7 @@ pub mod pallet {
 pub trait WeightInfo {
 	fn report_equivocation(validator_count: u32, max_nominators_per_validator: u32) -> Weight;
 	fn note_stalled() -> Weight;
+	fn set_finality_delay() -> Weight;
 }
 
 /// Bounded version of `AuthorityList`, `Limit` being the bound
@@ -446,6 +470,11 @@ impl<T: Config> Pallet<T> {
 		Stalled::<T>::get()
 	}
 
+	/// The number of blocks voters should stay behind the best block, if any.
+	pub fn finality_delay() -> Option<BlockNumberFor<T>> {
+		FinalityDelay::<T>::get()
+	}
+
 	/// The number of changes (both in terms of keys and underlying economic responsibilities)
 	/// in the "set" of Grandpa validators from genesis.
 	pub fn current_set_id() -> SetId {
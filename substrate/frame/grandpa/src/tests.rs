@@ -917,3 +917,19 @@ fn valid_equivocation_reports_dont_pay_fees() {
 		assert_eq!(post_info.pays_fee, Pays::Yes);
 	})
 }
+
+#[test]
+fn set_finality_delay_requires_root() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		assert_noop!(
+			Grandpa::set_finality_delay(RuntimeOrigin::signed(1), Some(10)),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+
+		assert_ok!(Grandpa::set_finality_delay(RuntimeOrigin::root(), Some(10)));
+		assert_eq!(Grandpa::finality_delay(), Some(10));
+
+		assert_ok!(Grandpa::set_finality_delay(RuntimeOrigin::root(), None));
+		assert_eq!(Grandpa::finality_delay(), None);
+	});
+}
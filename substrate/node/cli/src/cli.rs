@@ -20,8 +20,9 @@
 //! This module defines the structure of the CLI and its subcommands.
 
 use sc_cli::{
-    BuildSpecCmd, ChainInfoCmd, CheckBlockCmd, ExportBlocksCmd, ExportStateCmd, ImportBlocksCmd,
-    KeySubcommand, PurgeChainCmd, RevertCmd, RunCmd, SignCmd, SubstrateCli, VanityCmd, VerifyCmd,
+    BuildSpecCmd, ChainInfoCmd, CheckBlockCmd, CheckHostFunctionsCmd, CompareBlockCmd,
+    ExportBlocksCmd, ExportStateCmd, ImportBlocksCmd, KeySubcommand, PurgeChainCmd, RevertCmd,
+    RunCmd, SignCmd, SubstrateCli, VanityCmd, VerifyCmd,
 };
 use kitchensink_runtime::VERSION;
 use sc_service::ChainSpec;
@@ -52,6 +53,13 @@ pub struct Cli {
     /// telemetry, if telemetry is enabled.
     #[arg(long)]
     pub no_hardware_benchmarks: bool,
+
+    /// Don't vote to finalize blocks produced less than this many seconds ago.
+    ///
+    /// The age of a block is derived from the slot of its Aura pre-digest, giving operators
+    /// a window to react before blocks are finalized.
+    #[arg(long, value_name = "SECONDS")]
+    pub grandpa_min_block_age: Option<u64>,
 }
 
 /// Enum defining all possible subcommands that can be executed.
@@ -83,6 +91,14 @@ pub enum Subcommand {
     /// This command checks if blocks in the chain are valid according to the consensus rules.
     CheckBlock(CheckBlockCmd),
 
+    /// Re-execute a block with the Wasm and the PolkaVM runtime.
+    /// This command compares both executions and reports the first divergence.
+    CompareBlock(CompareBlockCmd),
+
+    /// Check whether a runtime is compatible with the host functions of this node.
+    /// This command reports imported host functions which are missing or deprecated.
+    CheckHostFunctions(CheckHostFunctionsCmd),
+
     /// Export blocks from the chain.
     /// This command exports blocks from the chain to a file.
     ExportBlocks(ExportBlocksCmd),
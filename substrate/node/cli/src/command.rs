@@ -28,6 +28,7 @@ use kitchensink_runtime::RuntimeGenesisConfig;
 use sc_network::config::MultiaddrWithPeerId;
 use sc_telemetry::TelemetryEndpoints;
 use std::collections::BTreeMap;
+use std::time::Duration;
 use sp_runtime::{Storage, BuildStorage};
 
 
@@ -193,6 +194,19 @@ pub fn run() -> sc_cli::Result<()> {
                 Ok((cmd.run(client, import_queue), task_manager))
             })
         }
+        Some(Subcommand::CompareBlock(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.async_run(|config| {
+                let PartialComponents { client, task_manager, backend, .. } =
+                    service::new_partial(&config)?;
+                let executor =
+                    sc_service::new_wasm_executor::<sp_io::SubstrateHostFunctions>(&config.executor);
+                Ok((cmd.run(client, backend, executor), task_manager))
+            })
+        }
+        Some(Subcommand::CheckHostFunctions(cmd)) => {
+            cmd.run::<sp_io::SubstrateHostFunctions>()
+        }
         Some(Subcommand::ExportBlocks(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.async_run(|config| {
@@ -241,8 +255,11 @@ pub fn run() -> sc_cli::Result<()> {
         }
         None => {
             let runner = cli.create_runner(&cli.run)?;
+            let grandpa_min_block_age = cli.grandpa_min_block_age.map(Duration::from_secs);
             runner.run_node_until_exit(|config| async move {
-                service::new_full(config).await.map_err(sc_cli::Error::Service)
+                service::new_full(config, grandpa_min_block_age)
+                    .await
+                    .map_err(sc_cli::Error::Service)
             })
         }
     }
@@ -5,19 +5,21 @@ use kitchensink_runtime::{self, opaque::Block, RuntimeApi};
 use sc_executor::WasmExecutor;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
-use sc_consensus_aura::{SlotProportion, StartAuraParams};
-use sc_client_api::BlockBackend;
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sc_consensus_aura::{SlotDiagnostics, SlotProportion, StartAuraParams};
+use sc_client_api::{BlockBackend, ExecutorProvider};
+use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair, AuthoritySignature as AuraSignature};
 use sc_transaction_pool::FullChainApi;
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging;
 use sc_consensus_grandpa::{
-    SharedVoterState, Config as GrandpaConfig, GrandpaParams, VotingRulesBuilder,
+    SharedVoterState, Config as GrandpaConfig, FinalityStallConfig, GrandpaParams,
+    MinimumBlockAge, RuntimeFinalityDelay, SharedFinalityStallStatus, VotingRulesBuilder,
 };
 use sc_network::config::FullNetworkConfiguration;
 use sc_network_sync::SyncingService;
 use sc_service::build_network;
 use sc_utils::mpsc::tracing_unbounded;
+use sp_runtime::traits::Block as BlockT;
 
 /// The full client type definition.
 pub type FullClient = sc_service::TFullClient<Block, RuntimeApi, WasmExecutor>;
@@ -91,6 +93,8 @@ pub fn new_partial(
 
     let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 
+    // Equivocations are only logged: the runtime has no session history to prove key ownership
+    // with, so reporting through `sc_consensus_aura::ReportingAuraVerifier` would be a no-op.
     let import_queue = sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _>(
         sc_consensus_aura::ImportQueueParams {
             block_import: grandpa_block_import.clone(),
@@ -125,7 +129,10 @@ pub fn new_partial(
 }
 
 /// Creates a new full service with all components initialized and running.
-pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
+pub async fn new_full(
+    config: Configuration,
+    grandpa_min_block_age: Option<Duration>,
+) -> Result<TaskManager, ServiceError> {
     let sc_service::PartialComponents {
         client,
         backend,
@@ -142,18 +149,33 @@ pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError
     let (sync_tx, _) = tracing_unbounded("sync", 1000); // Provide a buffer size
     let sync_service = SyncingService::new(sync_tx, Arc::new(AtomicUsize::new(0)), Arc::new(AtomicBool::new(false)));
 
-    let (network, _, _, _) = build_network(sc_service::BuildNetworkParams {
-        config: &config,
-        client: client.clone(),
-        transaction_pool: transaction_pool.clone(),
-        spawn_handle: task_manager.spawn_handle(),
-        import_queue,
-        block_announce_validator_builder: None,
-        warp_sync_config: None,
-        block_relay: None,
-        metrics: sc_network::NotificationMetrics::new(config.prometheus_registry()),
-        net_config,
-    })?;
+    let (network, system_rpc_tx, tx_handler_controller, network_sync_service) =
+        build_network(sc_service::BuildNetworkParams {
+            config: &config,
+            client: client.clone(),
+            transaction_pool: transaction_pool.clone(),
+            spawn_handle: task_manager.spawn_handle(),
+            import_queue,
+            block_announce_validator_builder: None,
+            warp_sync_config: None,
+            block_relay: None,
+            metrics: sc_network::NotificationMetrics::new(config.prometheus_registry()),
+            net_config,
+        })?;
+
+    task_manager.spawn_handle().spawn_blocking(
+        "runtime-precompiler",
+        None,
+        sc_service::precompile_runtime_upgrades(
+            client.clone(),
+            backend.clone(),
+            client.executor().code_executor().clone(),
+            config.prometheus_registry().cloned(),
+        ),
+    );
+
+    // The slot diagnostics are recorded by the Aura worker and served over RPC.
+    let slot_diagnostics = SlotDiagnostics::new(config.prometheus_registry())?;
 
     if config.role.is_authority() {
         let proposer = sc_basic_authorship::ProposerFactory::new(
@@ -166,6 +188,12 @@ pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError
 
         let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 
+        task_manager.spawn_handle().spawn(
+            "slot-diagnostics",
+            None,
+            slot_diagnostics.clone().run_informant(Duration::from_secs(60)),
+        );
+
         let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
             StartAuraParams {
                 slot_duration,
@@ -190,12 +218,48 @@ pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError
                 max_block_proposal_slot_portion: None,
                 telemetry: telemetry.as_ref().map(|x| x.handle()),
                 compatibility_mode: Default::default(),
+                slot_diagnostics: Some(slot_diagnostics.clone()),
             },
         )?;
 
         task_manager.spawn_essential_handle().spawn_blocking("aura", None, aura);
     }
 
+    // The stall detector and the GRANDPA RPC share the same status handle, so that
+    // `grandpa_finalityStall` serves the detector's diagnosis.
+    let shared_voter_state = SharedVoterState::empty();
+    let finality_stall_status = SharedFinalityStallStatus::empty();
+
+    let rpc_builder = {
+        let shared_voter_state = shared_voter_state.clone();
+        let shared_authority_set = grandpa_link.shared_authority_set().clone();
+        let justification_stream = grandpa_link.justification_stream();
+        let finality_provider = sc_consensus_grandpa::FinalityProofProvider::new_for_service(
+            backend.clone(),
+            Some(shared_authority_set.clone()),
+        );
+        let finality_stall_status = finality_stall_status.clone();
+        let slot_diagnostics = slot_diagnostics.clone();
+
+        Box::new(move |subscription_executor: node_rpc::SubscriptionTaskExecutor| {
+            let grandpa = node_rpc::GrandpaDeps {
+                shared_voter_state: shared_voter_state.clone(),
+                shared_authority_set: shared_authority_set.clone(),
+                justification_stream: justification_stream.clone(),
+                subscription_executor,
+                finality_provider: finality_provider.clone(),
+                finality_stall_status: finality_stall_status.clone(),
+            };
+
+            node_rpc::create_grandpa(grandpa)
+                .and_then(|mut io| {
+                    io.merge(node_rpc::create_slots(slot_diagnostics.clone())?)?;
+                    Ok(io)
+                })
+                .map_err(Into::into)
+        })
+    };
+
     if !config.disable_grandpa {
         let grandpa_config = GrandpaConfig {
             gossip_duration: Duration::from_millis(1000),
@@ -211,13 +275,43 @@ pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError
             ),
         };
 
+        task_manager.spawn_handle().spawn(
+            "grandpa-stall-detector",
+            None,
+            sc_consensus_grandpa::run_finality_stall_detector(
+                client.clone(),
+                grandpa_link.shared_authority_set().clone(),
+                shared_voter_state.clone(),
+                finality_stall_status,
+                FinalityStallConfig::default(),
+                config.prometheus_registry().cloned(),
+            ),
+        );
+
+        // The finality delay can be adjusted on-chain by governance, while the minimum block
+        // age is a local policy of this node.
+        let mut voting_rule =
+            VotingRulesBuilder::default().add(RuntimeFinalityDelay::new(client.clone()));
+        if let Some(min_age) = grandpa_min_block_age {
+            let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+            voting_rule = voting_rule.add(MinimumBlockAge::new(
+                min_age,
+                move |header: &<Block as BlockT>::Header| {
+                    sc_consensus_aura::find_pre_digest::<Block, AuraSignature>(header)
+                        .ok()
+                        .and_then(|slot| slot.timestamp(slot_duration))
+                        .map(|timestamp| timestamp.as_duration())
+                },
+            ));
+        }
+
         let grandpa_params = GrandpaParams {
             config: grandpa_config,
             link: grandpa_link,
             telemetry: telemetry.as_ref().map(|x| x.handle()),
-            voting_rule: VotingRulesBuilder::default().build(),
+            voting_rule: voting_rule.build(),
             prometheus_registry: config.prometheus_registry().cloned(),
-            shared_voter_state: SharedVoterState::empty(),
+            shared_voter_state,
             offchain_tx_pool_factory: OffchainTransactionPoolFactory::new(transaction_pool.clone()),
             sync: sync_service.clone(),
             network: network.clone(),
@@ -231,5 +325,20 @@ pub async fn new_full(config: Configuration) -> Result<TaskManager, ServiceError
         );
     }
 
+    sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+        config,
+        client,
+        backend,
+        task_manager: &mut task_manager,
+        keystore: keystore_container.keystore(),
+        transaction_pool,
+        rpc_builder,
+        network,
+        system_rpc_tx,
+        tx_handler_controller,
+        sync_service: network_sync_service,
+        telemetry: telemetry.as_mut(),
+    })?;
+
     Ok(task_manager)
 }
\ No newline at end of file
@@ -41,11 +41,13 @@ use sc_consensus_beefy::communication::notification::{
 	BeefyBestBlockStream, BeefyVersionedFinalityProofStream,
 };
 use sc_consensus_grandpa::{
-	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
+	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet,
+	SharedFinalityStallStatus, SharedVoterState,
 };
+use sc_consensus_slots::SlotDiagnostics;
 pub use sc_rpc::SubscriptionTaskExecutor;
 use sc_transaction_pool_api::TransactionPool;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{Metadata, ProvideRuntimeApi};
 use sp_application_crypto::RuntimeAppPublic;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
@@ -74,6 +76,8 @@ pub struct GrandpaDeps<B> {
 	pub subscription_executor: SubscriptionTaskExecutor,
 	/// Finality proof provider.
 	pub finality_provider: Arc<FinalityProofProvider<B, Block>>,
+	/// Finality stall info.
+	pub finality_stall_status: SharedFinalityStallStatus<Block>,
 }
 
 /// Dependencies for BEEFY
@@ -139,6 +143,7 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: Metadata<Block>,
 	P: TransactionPool + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -150,7 +155,6 @@ where
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use sc_consensus_babe_rpc::{Babe, BabeApiServer};
 	use sc_consensus_beefy_rpc::{Beefy, BeefyApiServer};
-	use sc_consensus_grandpa_rpc::{Grandpa, GrandpaApiServer};
 	use sc_rpc::{
 		dev::{Dev, DevApiServer},
 		mixnet::MixnetApiServer,
@@ -163,13 +167,8 @@ where
 	let mut io = RpcModule::new(());
 
 	let BabeDeps { keystore, babe_worker_handle } = babe;
-	let GrandpaDeps {
-		shared_voter_state,
-		shared_authority_set,
-		justification_stream,
-		subscription_executor,
-		finality_provider,
-	} = grandpa;
+	let shared_authority_set = grandpa.shared_authority_set.clone();
+	let subscription_executor = grandpa.subscription_executor.clone();
 
 	io.merge(System::new(client.clone(), pool).into_rpc())?;
 	// Making synchronous calls in light client freezes the browser currently,
@@ -188,16 +187,7 @@ where
 	io.merge(
 		Babe::new(client.clone(), babe_worker_handle.clone(), keystore, select_chain).into_rpc(),
 	)?;
-	io.merge(
-		Grandpa::new(
-			subscription_executor,
-			shared_authority_set.clone(),
-			shared_voter_state,
-			justification_stream,
-			finality_provider,
-		)
-		.into_rpc(),
-	)?;
+	io.merge(create_grandpa(grandpa)?)?;
 
 	io.merge(
 		SyncState::new(chain_spec, client.clone(), shared_authority_set, babe_worker_handle)?
@@ -206,7 +196,8 @@ where
 
 	io.merge(StateMigration::new(client.clone(), backend).into_rpc())?;
 	io.merge(Dev::new(client).into_rpc())?;
-	let statement_store = sc_rpc::statement::StatementStore::new(statement_store).into_rpc();
+	let statement_store =
+		sc_rpc::statement::StatementStore::new(statement_store, subscription_executor).into_rpc();
 	io.merge(statement_store)?;
 
 	if let Some(mixnet_api) = mixnet_api {
@@ -225,3 +216,50 @@ where
 
 	Ok(io)
 }
+
+/// Instantiate the GRANDPA RPC extensions.
+pub fn create_grandpa<B>(
+	GrandpaDeps {
+		shared_voter_state,
+		shared_authority_set,
+		justification_stream,
+		subscription_executor,
+		finality_provider,
+		finality_stall_status,
+	}: GrandpaDeps<B>,
+) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
+where
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
+	B::State: sc_client_api::backend::StateBackend<sp_runtime::traits::HashingFor<Block>>,
+{
+	use sc_consensus_grandpa_rpc::{Grandpa, GrandpaApiServer};
+
+	let mut io = RpcModule::new(());
+
+	io.merge(
+		Grandpa::new(
+			subscription_executor,
+			shared_authority_set,
+			shared_voter_state,
+			justification_stream,
+			finality_provider,
+			finality_stall_status,
+		)
+		.into_rpc(),
+	)?;
+
+	Ok(io)
+}
+
+/// Instantiate the RPC extensions serving the diagnostics of the local slot worker.
+pub fn create_slots(
+	slot_diagnostics: SlotDiagnostics<Hash>,
+) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>> {
+	use sc_consensus_slots_rpc::{Slots, SlotsApiServer};
+
+	let mut io = RpcModule::new(());
+
+	io.merge(Slots::new(slot_diagnostics).into_rpc())?;
+
+	Ok(io)
+}
@@ -364,6 +364,13 @@ fn generate_runtime_api_base_structures() -> Result<TokenStream> {
 				fn register_extension<E: #crate_::Extension>(&mut self, extension: E) {
 					std::cell::RefCell::borrow_mut(&self.extensions).register(extension);
 				}
+
+				fn with_overlayed_changes<R>(
+					&self,
+					f: impl FnOnce(&mut #crate_::OverlayedChanges<#crate_::HashingFor<Block>>) -> R,
+				) -> Option<R> where Self: Sized {
+					Some(f(&mut std::cell::RefCell::borrow_mut(&self.changes)))
+				}
 			}
 
 			#[automatically_derived]
@@ -645,6 +645,23 @@ pub trait ApiExt<Block: BlockT> {
 
 	/// Register an [`Extension`] that will be accessible while executing a runtime api call.
 	fn register_extension<E: Extension>(&mut self, extension: E);
+
+	/// Run `f` with the overlayed changes collected by this instance.
+	///
+	/// This can be used to snapshot or replace the changes between runtime api calls. It must not
+	/// be called from within [`Self::execute_in_transaction`].
+	///
+	/// Returns `None` without calling `f` if the instance doesn't expose its changes, which is the
+	/// default, e.g. for runtime api mocks.
+	fn with_overlayed_changes<R>(
+		&self,
+		_f: impl FnOnce(&mut OverlayedChanges<HashingFor<Block>>) -> R,
+	) -> Option<R>
+	where
+		Self: Sized,
+	{
+		None
+	}
 }
 
 /// Parameters for [`CallApiAt::call_api_at`].
@@ -52,6 +52,8 @@ pub mod ecdsa;
 #[cfg(feature = "bls-experimental")]
 pub mod ecdsa_bls381;
 pub mod ed25519;
+#[cfg(feature = "p256-experimental")]
+pub mod p256;
 pub mod sr25519;
 mod traits;
 
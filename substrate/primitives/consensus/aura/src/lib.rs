@@ -23,7 +23,8 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 use codec::{Codec, Decode, Encode};
-use sp_runtime::ConsensusEngineId;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Header, ConsensusEngineId, KeyTypeId, RuntimeAppPublic};
 
 pub mod digests;
 pub mod inherents;
@@ -66,6 +67,9 @@ pub mod ed25519 {
 
 pub use sp_consensus_slots::{Slot, SlotDuration};
 
+/// Key type for AuRa module.
+pub const KEY_TYPE: KeyTypeId = sp_application_crypto::key_types::AURA;
+
 /// The `ConsensusEngineId` of AuRa.
 pub const AURA_ENGINE_ID: ConsensusEngineId = [b'a', b'u', b'r', b'a'];
 
@@ -83,6 +87,103 @@ pub enum ConsensusLog<AuthorityId: Codec> {
 	OnDisabled(AuthorityIndex),
 }
 
+/// An equivocation proof for multiple block authorships on the same slot (i.e. double vote).
+pub type EquivocationProof<H, AuthorityId> = sp_consensus_slots::EquivocationProof<H, AuthorityId>;
+
+/// Verifies the equivocation proof by making sure that: both headers have
+/// different hashes, are targetting the same slot, and have valid signatures by
+/// the same authority.
+pub fn check_equivocation_proof<H, AuthorityId>(proof: EquivocationProof<H, AuthorityId>) -> bool
+where
+	H: Header,
+	AuthorityId: RuntimeAppPublic,
+{
+	use digests::CompatibleDigestItem;
+
+	let find_pre_digest = |header: &H| {
+		let mut logs = header.digest().logs().iter();
+		logs.find_map(CompatibleDigestItem::<AuthorityId::Signature>::as_aura_pre_digest)
+	};
+
+	let verify_seal_signature = |mut header: H, offender: &AuthorityId| {
+		let seal: AuthorityId::Signature = header.digest_mut().pop()?.as_aura_seal()?;
+		let pre_hash = header.hash();
+
+		if !offender.verify(&pre_hash.as_ref(), &seal) {
+			return None
+		}
+
+		Some(())
+	};
+
+	let verify_proof = || {
+		// we must have different headers for the equivocation to be valid
+		if proof.first_header.hash() == proof.second_header.hash() {
+			return None
+		}
+
+		let first_slot = find_pre_digest(&proof.first_header)?;
+		let second_slot = find_pre_digest(&proof.second_header)?;
+
+		// both headers must be targeting the same slot and it must
+		// be the same as the one in the proof.
+		if proof.slot != first_slot || first_slot != second_slot {
+			return None
+		}
+
+		// aura pre-digests don't carry the author, we verify that the offender
+		// has signed both headers and that the signatures are valid.
+		verify_seal_signature(proof.first_header, &proof.offender)?;
+		verify_seal_signature(proof.second_header, &proof.offender)?;
+
+		Some(())
+	};
+
+	// NOTE: we isolate the verification code into an helper function that
+	// returns `Option<()>` so that we can use `?` to deal with any intermediate
+	// errors and discard the proof as invalid.
+	verify_proof().is_some()
+}
+
+/// Verifies the equivocation proof like [`check_equivocation_proof`], additionally requiring
+/// both headers to be built on the same parent.
+///
+/// This must be used instead when authorities are allowed to author multiple blocks per slot,
+/// since blocks authored on top of each other in the same slot are expected then, and only
+/// competing blocks are an equivocation.
+pub fn check_sibling_equivocation_proof<H, AuthorityId>(
+	proof: EquivocationProof<H, AuthorityId>,
+) -> bool
+where
+	H: Header,
+	AuthorityId: RuntimeAppPublic,
+{
+	proof.first_header.parent_hash() == proof.second_header.parent_hash() &&
+		check_equivocation_proof(proof)
+}
+
+/// An opaque type used to represent the key ownership proof at the runtime API
+/// boundary. The inner value is an encoded representation of the actual key
+/// ownership proof which will be parameterized when defining the runtime. At
+/// the runtime API boundary this type is unknown and as such we keep this
+/// opaque representation, implementors of the runtime API will have to make
+/// sure that all usages of `OpaqueKeyOwnershipProof` refer to the same type.
+#[derive(Decode, Encode, PartialEq, TypeInfo)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+impl OpaqueKeyOwnershipProof {
+	/// Create a new `OpaqueKeyOwnershipProof` using the given encoded
+	/// representation.
+	pub fn new(inner: Vec<u8>) -> OpaqueKeyOwnershipProof {
+		OpaqueKeyOwnershipProof(inner)
+	}
+
+	/// Try to decode this `OpaqueKeyOwnershipProof` into the given concrete key
+	/// ownership proof type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		Decode::decode(&mut &self.0[..]).ok()
+	}
+}
+
 sp_api::decl_runtime_apis! {
 	/// API necessary for block authorship with aura.
 	pub trait AuraApi<AuthorityId: Codec> {
@@ -94,4 +195,37 @@ sp_api::decl_runtime_apis! {
 		/// Return the current set of authorities.
 		fn authorities() -> Vec<AuthorityId>;
 	}
+
+	/// API for reporting Aura slot equivocations.
+	///
+	/// This is kept separate from [`AuraApi`] so that runtimes which don't
+	/// support equivocation reporting don't need to implement it.
+	pub trait AuraEquivocationApi<AuthorityId: Codec> {
+		/// Generates a proof of key ownership for the given authority at the
+		/// given slot. An example usage of this module is coupled with the
+		/// session historical module to prove that a given authority key is
+		/// tied to a given staking identity during a specific session. Proofs
+		/// of key ownership are necessary for submitting equivocation reports.
+		/// NOTE: even though the API takes a `slot` as parameter the current
+		/// implementations ignore this parameter and instead rely on this
+		/// method being called at the correct block height, i.e. any point at
+		/// which the given authority is part of the authority set.
+		fn generate_key_ownership_proof(
+			slot: Slot,
+			authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof>;
+
+		/// Submits an unsigned extrinsic to report an equivocation. The caller
+		/// must provide the equivocation proof and a key ownership proof
+		/// (should be obtained using `generate_key_ownership_proof`). The
+		/// extrinsic will be unsigned and should only be accepted for local
+		/// authorship (not to be broadcast to the network). This method returns
+		/// `None` when creation of the extrinsic fails, e.g. if equivocation
+		/// reporting is disabled for the given runtime (i.e. this method is
+		/// hardcoded to return `None`). Only useful in an offchain context.
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<Block::Header, AuthorityId>,
+			key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<()>;
+	}
 }
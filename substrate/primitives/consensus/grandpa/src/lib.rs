@@ -545,4 +545,16 @@ sp_api::decl_runtime_apis! {
 		/// Get current GRANDPA authority set id.
 		fn current_set_id() -> SetId;
 	}
+
+	/// API allowing the runtime to control how far behind the best block GRANDPA voters
+	/// should vote.
+	///
+	/// This is only taken into account by voters that opted into the corresponding voting
+	/// rule, and allows governance to adjust the finality lag (e.g. during incidents)
+	/// without requiring node upgrades.
+	pub trait GrandpaFinalityDelayApi {
+		/// Get the number of blocks voters should stay behind the best block, or `None` if
+		/// votes should not be restricted.
+		fn finality_delay() -> Option<NumberFor<Block>>;
+	}
 }
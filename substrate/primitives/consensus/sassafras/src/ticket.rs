@@ -17,7 +17,7 @@
 
 //! Primitives related to tickets.
 
-use crate::vrf::RingVrfSignature;
+use crate::vrf::{RingVrfSignature, VrfSignature};
 use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
 
@@ -64,6 +64,9 @@ pub struct TicketEnvelope {
 pub struct TicketClaim {
 	/// Signature verified via `TicketBody::erased_public`.
 	pub erased_signature: EphemeralSignature,
+	/// Signature of [`crate::vrf::ticket_claim_sign_data`] produced with the claiming
+	/// authority key. Proves that the claimed ticket was generated by the claiming authority.
+	pub ticket_signature: VrfSignature,
 }
 
 /// Computes a boundary for [`TicketId`] maximum allowed value for a given epoch.
@@ -56,6 +56,23 @@ pub fn ticket_id_input(randomness: &Randomness, attempt: u32, epoch: u64) -> Vrf
 	VrfInput::new(&v[..])
 }
 
+/// Signing-data to prove ownership of a ticket when claiming its slot.
+///
+/// The VRF input is the ticket identifier input, thus [`make_ticket_id`] applied to the
+/// signature pre-output yields the claimed ticket identifier only if the ticket was
+/// generated using the VRF key of the signer.
+pub fn ticket_claim_sign_data(
+	randomness: &Randomness,
+	attempt: u32,
+	epoch: u64,
+	slot: Slot,
+) -> VrfSignData {
+	VrfSignData {
+		vrf_input: ticket_id_input(randomness, attempt, epoch),
+		aux_data: [b"sassafras-claim".as_slice(), &slot.to_le_bytes()].concat(),
+	}
+}
+
 /// Data to be signed via ring-vrf.
 pub fn ticket_body_sign_data(ticket_body: &TicketBody, ticket_id_input: VrfInput) -> VrfSignData {
 	VrfSignData { vrf_input: ticket_id_input, aux_data: ticket_body.encode() }
@@ -77,6 +77,8 @@ pub mod bls;
 pub mod crypto_bytes;
 pub mod ecdsa;
 pub mod ed25519;
+#[cfg(feature = "p256-experimental")]
+pub mod p256;
 pub mod paired_crypto;
 pub mod sr25519;
 
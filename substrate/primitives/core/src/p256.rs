@@ -0,0 +1,453 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simple ECDSA secp256r1 (NIST P-256) API.
+//!
+//! Signatures are ECDSA over the SHA-256 digest of the message (the `ES256` algorithm), encoded
+//! as `r || s` with a low `s`. Only low-S signatures are valid, as both `s` and `n - s` would
+//! verify otherwise.
+//!
+//! WebAuthn authenticators (passkeys) use the same algorithm, but sign
+//! `authenticatorData || sha256(clientDataJSON)` and return a DER encoded signature whose `s`
+//! isn't normalized. Such a signature has to be converted to `r || s` and normalized with
+//! [`Signature::normalize_s`], and verified against `authenticatorData || sha256(clientDataJSON)`
+//! as the message.
+
+use crate::crypto::{
+	CryptoType, CryptoTypeId, DeriveError, DeriveJunction, Pair as TraitPair, PublicBytes,
+	SecretStringError, SignatureBytes,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "full_crypto")]
+use p256::ecdsa::signature::{hazmat::PrehashSigner, Signer};
+use ecdsa_core::RecoveryId;
+use p256::ecdsa::{
+	signature::{hazmat::PrehashVerifier, Verifier},
+	Signature as RawSignature, SigningKey as SecretKey, VerifyingKey,
+};
+
+/// An identifier used to match public keys against p256 keys
+pub const CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"p256");
+
+/// The byte length of public key
+pub const PUBLIC_KEY_SERIALIZED_SIZE: usize = 33;
+
+/// The byte length of signature
+pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
+
+#[doc(hidden)]
+pub struct P256Tag;
+
+/// The secret seed.
+///
+/// The raw secret seed, which can be used to create the `Pair`.
+type Seed = [u8; 32];
+
+/// The P-256 compressed public key.
+pub type Public = PublicBytes<PUBLIC_KEY_SERIALIZED_SIZE, P256Tag>;
+
+impl Public {
+	/// Create a new instance from the given full public key.
+	///
+	/// Accepts a SEC1 encoded key, either compressed or uncompressed, as well as the raw
+	/// 64 bytes `x || y` coordinates (e.g. as found in a WebAuthn COSE key). The key is
+	/// converted into the compressed format.
+	pub fn from_full(full: &[u8]) -> Result<Self, ()> {
+		let mut tagged_full = [0u8; 65];
+		let full = if full.len() == 64 {
+			// Tag it as uncompressed public key.
+			tagged_full[0] = 0x04;
+			tagged_full[1..].copy_from_slice(full);
+			&tagged_full
+		} else {
+			full
+		};
+		VerifyingKey::from_sec1_bytes(full).map(|k| k.into()).map_err(|_| ())
+	}
+}
+
+impl From<VerifyingKey> for Public {
+	fn from(pubkey: VerifyingKey) -> Self {
+		Self::try_from(pubkey.to_encoded_point(true).as_bytes())
+			.expect("Valid key is serializable to [u8; 33]. qed.")
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl From<Pair> for Public {
+	fn from(x: Pair) -> Self {
+		x.public()
+	}
+}
+
+/// A signature (the 256-bit `r` value followed by the 256-bit `s` value).
+pub type Signature = SignatureBytes<SIGNATURE_SERIALIZED_SIZE, P256Tag>;
+
+impl Signature {
+	/// Returns `true` if the `s` value of the signature is low, which is required for it to be
+	/// valid.
+	pub fn is_low_s(&self) -> bool {
+		parse_signature(self).is_some()
+	}
+
+	/// Normalize the signature to low-S.
+	///
+	/// Signatures which aren't created by a [`Pair`], e.g. by WebAuthn authenticators, need to be
+	/// normalized before they can be verified.
+	pub fn normalize_s(&self) -> Self {
+		match RawSignature::from_slice(&self.0[..]) {
+			Ok(sig) => sig.normalize_s().map_or(*self, Into::into),
+			Err(_) => *self,
+		}
+	}
+
+	/// Recover the public keys this signature could have been created with from a message.
+	///
+	/// As the signature carries no recovery id, up to two public keys are returned.
+	pub fn recover<M: AsRef<[u8]>>(&self, message: M) -> Vec<Public> {
+		self.recover_prehashed(&sp_crypto_hashing::sha2_256(message.as_ref()))
+	}
+
+	/// Recover the public keys this signature could have been created with from a pre-hashed
+	/// message.
+	///
+	/// As the signature carries no recovery id, up to two public keys are returned.
+	pub fn recover_prehashed(&self, message: &[u8; 32]) -> Vec<Public> {
+		let Some(sig) = parse_signature(self) else { return Vec::new() };
+		[false, true]
+			.into_iter()
+			.filter_map(|is_y_odd| {
+				let rid = RecoveryId::new(is_y_odd, false);
+				VerifyingKey::recover_from_prehash(message, &sig, rid).ok()
+			})
+			.map(Public::from)
+			.collect()
+	}
+}
+
+impl From<RawSignature> for Signature {
+	fn from(sig: RawSignature) -> Signature {
+		Self::from_raw(sig.to_bytes().into())
+	}
+}
+
+/// Parse the signature into its backend representation.
+///
+/// Signatures with a high `s` value are rejected, to make signatures non-malleable.
+fn parse_signature(sig: &Signature) -> Option<RawSignature> {
+	RawSignature::from_slice(&sig.0[..]).ok().filter(|sig| sig.normalize_s().is_none())
+}
+
+/// Parse the signature and public key into their backend representations.
+fn parse(sig: &Signature, public: &Public) -> Option<(RawSignature, VerifyingKey)> {
+	let sig = parse_signature(sig)?;
+	let public = VerifyingKey::from_sec1_bytes(&public.0[..]).ok()?;
+	Some((sig, public))
+}
+
+/// Derive a single hard junction.
+fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
+	use codec::Encode;
+	("P256HDKD", secret_seed, cc).using_encoded(sp_crypto_hashing::blake2_256)
+}
+
+/// A key pair.
+#[derive(Clone)]
+pub struct Pair {
+	public: Public,
+	secret: SecretKey,
+}
+
+impl TraitPair for Pair {
+	type Public = Public;
+	type Seed = Seed;
+	type Signature = Signature;
+
+	/// Make a new key pair from secret seed material. The slice must be 32 bytes long and
+	/// represent a valid scalar or it will return an error.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed_slice(seed_slice: &[u8]) -> Result<Pair, SecretStringError> {
+		let secret =
+			SecretKey::from_slice(seed_slice).map_err(|_| SecretStringError::InvalidSeedLength)?;
+		Ok(Pair { public: (*secret.verifying_key()).into(), secret })
+	}
+
+	/// Derive a child key from a series of given junctions.
+	fn derive<Iter: Iterator<Item = DeriveJunction>>(
+		&self,
+		path: Iter,
+		_seed: Option<Seed>,
+	) -> Result<(Pair, Option<Seed>), DeriveError> {
+		let mut acc = self.seed();
+		for j in path {
+			match j {
+				DeriveJunction::Soft(_cc) => return Err(DeriveError::SoftKeyInPath),
+				DeriveJunction::Hard(cc) => acc = derive_hard_junction(&acc, &cc),
+			}
+		}
+		Ok((Self::from_seed(&acc), Some(acc)))
+	}
+
+	/// Get the public key.
+	fn public(&self) -> Public {
+		self.public
+	}
+
+	/// Sign a message.
+	///
+	/// The message is hashed with SHA-256 and the produced signature is normalized to low-S.
+	#[cfg(feature = "full_crypto")]
+	fn sign(&self, message: &[u8]) -> Signature {
+		let sig: RawSignature = self.secret.sign(message);
+		sig.normalize_s().unwrap_or(sig).into()
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	fn verify<M: AsRef<[u8]>>(sig: &Signature, message: M, public: &Public) -> bool {
+		match parse(sig, public) {
+			Some((sig, public)) => public.verify(message.as_ref(), &sig).is_ok(),
+			None => false,
+		}
+	}
+
+	/// Return a vec filled with raw data.
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.seed().to_vec()
+	}
+}
+
+impl Pair {
+	/// Get the seed for this key.
+	pub fn seed(&self) -> Seed {
+		self.secret.to_bytes().into()
+	}
+
+	/// Sign a pre-hashed message.
+	///
+	/// The produced signature is normalized to low-S.
+	#[cfg(feature = "full_crypto")]
+	pub fn sign_prehashed(&self, message: &[u8; 32]) -> Signature {
+		// Signing fails only if the `message` number of bytes is less than half the field
+		// length (unfallible as we're using a fixed message length of 32).
+		let sig: RawSignature = self
+			.secret
+			.sign_prehash(message)
+			.expect("Signing can't fail when using 32 bytes message hash. qed.");
+		sig.normalize_s().unwrap_or(sig).into()
+	}
+
+	/// Verify a signature on a pre-hashed message. Return `true` if the signature is valid
+	/// and thus matches the given `public` key.
+	pub fn verify_prehashed(sig: &Signature, message: &[u8; 32], public: &Public) -> bool {
+		match parse(sig, public) {
+			Some((sig, public)) => public.verify_prehash(message, &sig).is_ok(),
+			None => false,
+		}
+	}
+}
+
+impl CryptoType for Public {
+	type Pair = Pair;
+}
+
+impl CryptoType for Signature {
+	type Pair = Pair;
+}
+
+impl CryptoType for Pair {
+	type Pair = Pair;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::crypto::{Ss58Codec, DEV_PHRASE};
+	use serde_json;
+
+	// Key pair and "sample" message from RFC 6979, A.2.5.
+	const RFC6979_SEED: &str = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+	const RFC6979_PUBLIC: &str = "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299";
+	const RFC6979_SIGNATURE: &str = "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8";
+
+	#[test]
+	fn default_phrase_should_be_used() {
+		assert_eq!(
+			Pair::from_string("//Alice///password", None).unwrap().public(),
+			Pair::from_string(&format!("{}//Alice", DEV_PHRASE), Some("password"))
+				.unwrap()
+				.public(),
+		);
+	}
+
+	#[test]
+	fn seed_and_derive_should_work() {
+		let seed = array_bytes::hex2array_unchecked(RFC6979_SEED);
+		let pair = Pair::from_seed(&seed);
+		assert_eq!(pair.seed(), seed);
+		let path = vec![DeriveJunction::Hard([0u8; 32])];
+		let derived = pair.derive(path.into_iter(), None).ok().unwrap();
+		assert_eq!(derived.0.seed(), derive_hard_junction(&seed, &[0u8; 32]));
+		assert_ne!(derived.0.public(), pair.public());
+	}
+
+	#[test]
+	fn soft_derivation_should_fail() {
+		let pair = Pair::from_seed(&array_bytes::hex2array_unchecked(RFC6979_SEED));
+		let path = vec![DeriveJunction::soft(1)];
+		assert!(matches!(pair.derive(path.into_iter(), None), Err(DeriveError::SoftKeyInPath)));
+	}
+
+	#[test]
+	fn test_vector_should_work() {
+		let pair = Pair::from_seed(&array_bytes::hex2array_unchecked(RFC6979_SEED));
+		let public = pair.public();
+		assert_eq!(
+			public,
+			Public::from_full(&array_bytes::hex2bytes_unchecked(RFC6979_PUBLIC)).unwrap(),
+		);
+		assert_eq!(public.0[0], 0x03);
+
+		// The reference signature has a high `s`, we produce its low-S counterpart.
+		let message = b"sample";
+		let reference = Signature::from_raw(array_bytes::hex2array_unchecked(RFC6979_SIGNATURE));
+		let signature = pair.sign(&message[..]);
+		assert_eq!(signature.0[..32], reference.0[..32]);
+		assert_eq!(
+			signature.0[32..],
+			array_bytes::hex2bytes_unchecked(
+				"0834e36ad29a83bf2bc9385e491d6099c8fdf9d1ed67aa7ea5f51f93782857a9"
+			)[..],
+		);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(!Pair::verify(&reference, &message[..], &public));
+		assert!(!Pair::verify(&signature, b"test", &public));
+
+		assert!(!reference.is_low_s());
+		assert!(signature.is_low_s());
+		assert_eq!(reference.normalize_s(), signature);
+		assert_eq!(signature.normalize_s(), signature);
+	}
+
+	#[test]
+	fn from_full_should_accept_all_encodings() {
+		let full = array_bytes::hex2bytes_unchecked(RFC6979_PUBLIC);
+		let public = Public::from_full(&full).unwrap();
+
+		let mut tagged = vec![0x04];
+		tagged.extend_from_slice(&full);
+		assert_eq!(Public::from_full(&tagged).unwrap(), public);
+		assert_eq!(Public::from_full(&public.0[..]).unwrap(), public);
+
+		let mut invalid = full.clone();
+		invalid[63] ^= 1;
+		assert!(Public::from_full(&invalid).is_err());
+		assert!(Public::from_full(&full[..32]).is_err());
+	}
+
+	#[test]
+	fn generated_pair_should_work() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(!Pair::verify(&signature, b"Something else", &public));
+	}
+
+	#[test]
+	fn recover_works() {
+		let pair = Pair::from_seed(&array_bytes::hex2array_unchecked(RFC6979_SEED));
+		let reference = Signature::from_raw(array_bytes::hex2array_unchecked(RFC6979_SIGNATURE));
+		assert!(reference.recover(b"sample").is_empty());
+		assert!(reference.normalize_s().recover(b"sample").contains(&pair.public()));
+		assert!(pair.sign(b"sample").recover(b"sample").contains(&pair.public()));
+		assert!(!pair.sign(b"sample").recover(b"test").contains(&pair.public()));
+		assert!(Signature::default().recover(b"sample").is_empty());
+	}
+
+	#[test]
+	fn invalid_signature_should_not_verify() {
+		let (pair, _) = Pair::generate();
+		let message = b"Something important";
+		assert!(!Pair::verify(&Signature::default(), &message[..], &pair.public()));
+		assert!(!Pair::verify(&pair.sign(&message[..]), &message[..], &Public::default()));
+	}
+
+	#[test]
+	fn generate_with_phrase_should_be_recoverable_with_from_string() {
+		let (pair, phrase, seed) = Pair::generate_with_phrase(None);
+		let repair_seed = Pair::from_seed_slice(seed.as_ref()).expect("seed slice is valid");
+		assert_eq!(pair.public(), repair_seed.public());
+		assert_eq!(pair.seed(), repair_seed.seed());
+		let (repair_phrase, reseed) =
+			Pair::from_phrase(phrase.as_ref(), None).expect("seed slice is valid");
+		assert_eq!(seed, reseed);
+		assert_eq!(pair.public(), repair_phrase.public());
+		let repair_string = Pair::from_string(phrase.as_str(), None).expect("seed slice is valid");
+		assert_eq!(pair.public(), repair_string.public());
+	}
+
+	#[test]
+	fn ss58check_roundtrip_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let public = pair.public();
+		let s = public.to_ss58check();
+		let cmp = Public::from_ss58check(&s).unwrap();
+		assert_eq!(cmp, public);
+	}
+
+	#[test]
+	fn signature_serialization_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		let serialized_signature = serde_json::to_string(&signature).unwrap();
+		// Signature is 64 bytes, so 128 chars + 2 quote chars
+		assert_eq!(serialized_signature.len(), SIGNATURE_SERIALIZED_SIZE * 2 + 2);
+		let signature = serde_json::from_str(&serialized_signature).unwrap();
+		assert!(Pair::verify(&signature, &message[..], &pair.public()));
+	}
+
+	#[test]
+	fn sign_prehashed_works() {
+		let (pair, _, _) = Pair::generate_with_phrase(Some("password"));
+
+		// using a SHA-256 pre-hashed `msg` is the same as signing the message
+		let msg = b"this should be hashed";
+		let sig1 = pair.sign_prehashed(&sp_crypto_hashing::sha2_256(msg));
+		let sig2 = pair.sign(msg);
+		assert_eq!(sig1, sig2);
+	}
+
+	#[test]
+	fn verify_prehashed_works() {
+		let (pair, _, _) = Pair::generate_with_phrase(Some("password"));
+
+		// `msg` and `sig` match
+		let msg = sp_crypto_hashing::sha2_256(b"this should be hashed");
+		let sig = pair.sign_prehashed(&msg);
+		assert!(Pair::verify_prehashed(&sig, &msg, &pair.public()));
+		assert!(Pair::verify(&sig, b"this should be hashed", &pair.public()));
+
+		// `msg` and `sig` don't match
+		let msg = sp_crypto_hashing::sha2_256(b"this is a different message");
+		assert!(!Pair::verify_prehashed(&sig, &msg, &pair.public()));
+	}
+}
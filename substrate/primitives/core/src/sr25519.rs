@@ -314,7 +314,19 @@ pub mod vrf {
 
 	/// Transcript ready to be used for VRF related operations.
 	#[derive(Clone)]
-	pub struct VrfTranscript(pub merlin::Transcript);
+	pub struct VrfTranscript(pub merlin::Transcript, VrfTranscriptData);
+
+	/// The label and the `(domain, message)` items a [`VrfTranscript`] was built from.
+	///
+	/// The transcript itself can not be encoded, this allows to build an equal transcript in
+	/// another process, e.g. a remote signer.
+	#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+	pub struct VrfTranscriptData {
+		/// The label of the transcript.
+		pub label: Vec<u8>,
+		/// The `(domain, message)` items appended to the transcript.
+		pub items: Vec<(Vec<u8>, Vec<u8>)>,
+	}
 
 	impl VrfTranscript {
 		/// Build a new transcript instance.
@@ -323,7 +335,16 @@ pub mod vrf {
 		pub fn new(label: &'static [u8], data: &[(&'static [u8], &[u8])]) -> Self {
 			let mut transcript = merlin::Transcript::new(label);
 			data.iter().for_each(|(l, b)| transcript.append_message(l, b));
-			VrfTranscript(transcript)
+			let data = VrfTranscriptData {
+				label: label.to_vec(),
+				items: data.iter().map(|(l, b)| (l.to_vec(), b.to_vec())).collect(),
+			};
+			VrfTranscript(transcript, data)
+		}
+
+		/// The data the transcript was built from.
+		pub fn data(&self) -> &VrfTranscriptData {
+			&self.1
 		}
 
 		/// Map transcript to `VrfSignData`.
@@ -374,6 +395,11 @@ pub mod vrf {
 			self.extra = Some(extra);
 			self
 		}
+
+		/// The extra data to be signed, if any.
+		pub fn extra(&self) -> Option<&VrfTranscript> {
+			self.extra.as_ref()
+		}
 	}
 
 	/// VRF signature data
@@ -856,6 +882,11 @@ mod tests {
 		let data = VrfTranscript::new(b"label", &[(b"domain1", b"data1")])
 			.into_sign_data()
 			.with_extra(extra);
+		assert_eq!(data.as_ref().data().label, b"label".to_vec());
+		assert_eq!(
+			data.extra().map(|extra| extra.data().items.clone()),
+			Some(vec![(b"domain2".to_vec(), b"data2".to_vec())]),
+		);
 
 		let signature = pair.vrf_sign(&data);
 
@@ -110,6 +110,9 @@ use sp_core::{
 #[cfg(feature = "bls-experimental")]
 use sp_core::{bls381, ecdsa_bls381};
 
+#[cfg(feature = "p256-experimental")]
+use sp_core::p256;
+
 #[cfg(feature = "std")]
 use sp_trie::{LayoutV0, LayoutV1, TrieConfiguration};
 
@@ -1256,6 +1259,75 @@ pub trait Crypto {
 			.bandersnatch_generate_new(id, seed)
 			.expect("`bandernatch_generate` failed")
 	}
+
+	/// Returns all `p256` public keys for the given key id from the keystore.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_public_keys(&mut self, id: KeyTypeId) -> Vec<p256::Public> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.p256_public_keys(id)
+	}
+
+	/// Generate a `p256` key for the given key type using an optional `seed` and
+	/// store it in the keystore.
+	///
+	/// The `seed` needs to be a valid utf8.
+	///
+	/// Returns the public key.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_generate(&mut self, id: KeyTypeId, seed: Option<Vec<u8>>) -> p256::Public {
+		let seed = seed.as_ref().map(|s| std::str::from_utf8(s).expect("Seed is valid utf8!"));
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.p256_generate_new(id, seed)
+			.expect("`p256_generate` failed")
+	}
+
+	/// Sign the given `msg` with the `p256` key that corresponds to the given public key and
+	/// key type in the keystore.
+	///
+	/// Returns the signature.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_sign(
+		&mut self,
+		id: KeyTypeId,
+		pub_key: &p256::Public,
+		msg: &[u8],
+	) -> Option<p256::Signature> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.p256_sign(id, pub_key, msg)
+			.ok()
+			.flatten()
+	}
+
+	/// Verify a `p256` signature.
+	///
+	/// The `msg` is hashed using SHA-256 before verification. Signatures with a high `s` are
+	/// rejected.
+	///
+	/// Returns `true` when the verification was successful.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_verify(sig: &p256::Signature, msg: &[u8], pub_key: &p256::Public) -> bool {
+		p256::Pair::verify(sig, msg, pub_key)
+	}
+
+	/// Recover the public keys of a `p256` signature.
+	///
+	/// - `sig` is passed in `r || s` format.
+	/// - `msg` is the SHA-256 hash of the message.
+	///
+	/// As the signature carries no recovery id, this returns up to two 33-byte compressed
+	/// public keys for which the signature is valid. An empty list is returned if the
+	/// signature is bad or has a high `s`.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_recover_compressed(sig: &[u8; 64], msg: &[u8; 32]) -> Vec<[u8; 33]> {
+		p256::Signature::from_raw(*sig)
+			.recover_prehashed(msg)
+			.into_iter()
+			.map(|public| public.0)
+			.collect()
+	}
 }
 
 /// Interface that provides functions for hashing with different algorithms.
@@ -28,6 +28,8 @@ pub mod testing;
 use sp_core::bandersnatch;
 #[cfg(feature = "bls-experimental")]
 use sp_core::{bls381, ecdsa_bls381};
+#[cfg(feature = "p256-experimental")]
+use sp_core::p256;
 use sp_core::{
 	crypto::{ByteArray, CryptoTypeId, KeyTypeId},
 	ecdsa, ed25519, sr25519,
@@ -191,6 +193,64 @@ pub trait Keystore: Send + Sync {
 		msg: &[u8; 32],
 	) -> Result<Option<ecdsa::Signature>, Error>;
 
+	/// Hashes the `message` using keccak256 and then signs it using ECDSA.
+	///
+	/// Unlike [`Self::ecdsa_sign_prehashed`] this hands the actual message to the keystore, which
+	/// can then inspect what it signs. By default the message is hashed and passed on to
+	/// [`Self::ecdsa_sign_prehashed`].
+	///
+	/// Returns an [`ecdsa::Signature`] or `None` in case the given `key_type`
+	/// and `public` combination doesn't exist in the keystore.
+	/// An `Err` will be returned if generating the signature itself failed.
+	fn ecdsa_sign_with_keccak256(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		self.ecdsa_sign_prehashed(key_type, public, &sp_core::keccak_256(msg))
+	}
+
+	/// Returns all p256 public keys for the given key type.
+	///
+	/// By default no keys are returned.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_public_keys(&self, _key_type: KeyTypeId) -> Vec<p256::Public> {
+		Vec::new()
+	}
+
+	/// Generate a new p256 key pair for the given key type and an optional seed.
+	///
+	/// Returns a `p256::Public` key of the generated key pair or an `Err` if
+	/// something failed during key generation. By default the key type is not supported.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		_seed: Option<&str>,
+	) -> Result<p256::Public, Error> {
+		Err(Error::KeyNotSupported(key_type))
+	}
+
+	/// Generate a p256 signature for a given message.
+	///
+	/// Receives [`KeyTypeId`] and a [`p256::Public`] key to be able to map
+	/// them to a private key that exists in the keystore.
+	///
+	/// Returns a [`p256::Signature`] or `None` in case the given `key_type`
+	/// and `public` combination doesn't exist in the keystore.
+	/// An `Err` will be returned if generating the signature itself failed. By default the key
+	/// type is not supported.
+	#[cfg(feature = "p256-experimental")]
+	fn p256_sign(
+		&self,
+		key_type: KeyTypeId,
+		_public: &p256::Public,
+		_msg: &[u8],
+	) -> Result<Option<p256::Signature>, Error> {
+		Err(Error::KeyNotSupported(key_type))
+	}
+
 	/// Returns all the bandersnatch public keys for the given key type.
 	#[cfg(feature = "bandersnatch-experimental")]
 	fn bandersnatch_public_keys(&self, key_type: KeyTypeId) -> Vec<bandersnatch::Public>;
@@ -378,6 +438,7 @@ pub trait Keystore: Send + Sync {
 	/// - sr25519
 	/// - ed25519
 	/// - ecdsa
+	/// - p256
 	/// - bandersnatch
 	/// - bls381
 	/// - (ecdsa,bls381) paired keys
@@ -412,6 +473,12 @@ pub trait Keystore: Send + Sync {
 
 				self.ecdsa_sign(id, &public, msg)?.map(|s| s.encode())
 			},
+			#[cfg(feature = "p256-experimental")]
+			p256::CRYPTO_ID => {
+				let public = p256::Public::from_slice(public)
+					.map_err(|_| Error::ValidationError("Invalid public key format".into()))?;
+				self.p256_sign(id, &public, msg)?.map(|s| s.encode())
+			},
 			#[cfg(feature = "bandersnatch-experimental")]
 			bandersnatch::CRYPTO_ID => {
 				let public = bandersnatch::Public::from_slice(public)
@@ -527,6 +594,39 @@ impl<T: Keystore + ?Sized> Keystore for Arc<T> {
 		(**self).ecdsa_sign_prehashed(key_type, public, msg)
 	}
 
+	fn ecdsa_sign_with_keccak256(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		(**self).ecdsa_sign_with_keccak256(key_type, public, msg)
+	}
+
+	#[cfg(feature = "p256-experimental")]
+	fn p256_public_keys(&self, key_type: KeyTypeId) -> Vec<p256::Public> {
+		(**self).p256_public_keys(key_type)
+	}
+
+	#[cfg(feature = "p256-experimental")]
+	fn p256_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<p256::Public, Error> {
+		(**self).p256_generate_new(key_type, seed)
+	}
+
+	#[cfg(feature = "p256-experimental")]
+	fn p256_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &p256::Public,
+		msg: &[u8],
+	) -> Result<Option<p256::Signature>, Error> {
+		(**self).p256_sign(key_type, public, msg)
+	}
+
 	#[cfg(feature = "bandersnatch-experimental")]
 	fn bandersnatch_public_keys(&self, key_type: KeyTypeId) -> Vec<bandersnatch::Public> {
 		(**self).bandersnatch_public_keys(key_type)
@@ -686,3 +786,9 @@ sp_core::generate_feature_enabled_macro!(
 	feature = "bls-experimental",
 	$
 );
+
+sp_core::generate_feature_enabled_macro!(
+	p256_experimental_enabled,
+	feature = "p256-experimental",
+	$
+);
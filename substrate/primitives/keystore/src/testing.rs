@@ -21,6 +21,8 @@ use crate::{Error, Keystore, KeystorePtr};
 
 #[cfg(feature = "bandersnatch-experimental")]
 use sp_core::bandersnatch;
+#[cfg(feature = "p256-experimental")]
+use sp_core::p256;
 #[cfg(feature = "bls-experimental")]
 use sp_core::{bls381, ecdsa_bls381, KeccakHasher};
 use sp_core::{
@@ -216,6 +218,30 @@ impl Keystore for MemoryKeystore {
 		Ok(sig)
 	}
 
+	#[cfg(feature = "p256-experimental")]
+	fn p256_public_keys(&self, key_type: KeyTypeId) -> Vec<p256::Public> {
+		self.public_keys::<p256::Pair>(key_type)
+	}
+
+	#[cfg(feature = "p256-experimental")]
+	fn p256_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<p256::Public, Error> {
+		self.generate_new::<p256::Pair>(key_type, seed)
+	}
+
+	#[cfg(feature = "p256-experimental")]
+	fn p256_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &p256::Public,
+		msg: &[u8],
+	) -> Result<Option<p256::Signature>, Error> {
+		self.sign::<p256::Pair>(key_type, public, msg)
+	}
+
 	#[cfg(feature = "bandersnatch-experimental")]
 	fn bandersnatch_public_keys(&self, key_type: KeyTypeId) -> Vec<bandersnatch::Public> {
 		self.public_keys::<bandersnatch::Pair>(key_type)
@@ -482,6 +508,31 @@ mod tests {
 		assert!(res.is_some());
 	}
 
+	#[test]
+	#[cfg(feature = "p256-experimental")]
+	fn p256_sign_works() {
+		use sp_core::testing::P256;
+
+		let store = MemoryKeystore::new();
+
+		let public = store.p256_generate_new(P256, None).expect("Generates key");
+		assert!(store.p256_public_keys(P256).contains(&public));
+
+		let msg = b"this should be signed";
+		let sig = store.p256_sign(P256, &public, msg).unwrap().expect("Key is in the store");
+		assert!(p256::Pair::verify(&sig, msg, &public));
+
+		// same through the generic interface
+		let sig = store
+			.sign_with(P256, p256::CRYPTO_ID, public.as_slice(), msg)
+			.unwrap()
+			.expect("Key is in the store");
+		assert_eq!(sig.len(), p256::SIGNATURE_SERIALIZED_SIZE);
+
+		// unknown key type
+		assert!(store.p256_sign(ECDSA, &public, msg).unwrap().is_none());
+	}
+
 	#[test]
 	#[cfg(feature = "bls-experimental")]
 	fn ecdsa_bls381_sign_with_keccak_works() {
@@ -72,6 +72,8 @@ pub use sp_core::storage::StateVersion;
 #[cfg(feature = "std")]
 pub use sp_core::storage::{Storage, StorageChild};
 
+#[cfg(feature = "p256-experimental")]
+use sp_core::p256;
 use sp_core::{
 	crypto::{self, ByteArray, FromEntropy},
 	ecdsa, ed25519,
@@ -290,6 +292,16 @@ pub enum MultiSignature {
 	Sr25519(sr25519::Signature),
 	/// An ECDSA/SECP256k1 signature.
 	Ecdsa(ecdsa::Signature),
+	/// An ECDSA/P-256 (secp256r1) signature of the SHA-256 hash of the payload, with a low `s`.
+	///
+	/// WebAuthn assertions sign `authenticatorData || sha256(clientDataJSON)` and are DER
+	/// encoded, so they can't be used as is.
+	///
+	/// Verifying it requires the `p256_recover_compressed` host function, thus it is only
+	/// available with the `p256-experimental` feature, which must only be enabled once all nodes
+	/// of the chain provide that host function.
+	#[cfg(feature = "p256-experimental")]
+	P256(p256::Signature),
 }
 
 impl From<ed25519::Signature> for MultiSignature {
@@ -343,6 +355,25 @@ impl TryFrom<MultiSignature> for ecdsa::Signature {
 	}
 }
 
+#[cfg(feature = "p256-experimental")]
+impl From<p256::Signature> for MultiSignature {
+	fn from(x: p256::Signature) -> Self {
+		Self::P256(x)
+	}
+}
+
+#[cfg(feature = "p256-experimental")]
+impl TryFrom<MultiSignature> for p256::Signature {
+	type Error = ();
+	fn try_from(m: MultiSignature) -> Result<Self, Self::Error> {
+		if let MultiSignature::P256(x) = m {
+			Ok(x)
+		} else {
+			Err(())
+		}
+	}
+}
+
 /// Public key for any known crypto algorithm.
 #[derive(
 	Eq,
@@ -364,14 +395,20 @@ pub enum MultiSigner {
 	Sr25519(sr25519::Public),
 	/// An SECP256k1/ECDSA identity (actually, the Blake2 hash of the compressed pub key).
 	Ecdsa(ecdsa::Public),
+	/// A P-256 (secp256r1) identity (actually, the Blake2 hash of the compressed pub key).
+	#[cfg(feature = "p256-experimental")]
+	P256(p256::Public),
 }
 
 impl FromEntropy for MultiSigner {
 	fn from_entropy(input: &mut impl codec::Input) -> Result<Self, codec::Error> {
-		Ok(match input.read_byte()? % 3 {
+		let variants = if cfg!(feature = "p256-experimental") { 4 } else { 3 };
+		Ok(match input.read_byte()? % variants {
 			0 => Self::Ed25519(FromEntropy::from_entropy(input)?),
 			1 => Self::Sr25519(FromEntropy::from_entropy(input)?),
-			2.. => Self::Ecdsa(FromEntropy::from_entropy(input)?),
+			#[cfg(feature = "p256-experimental")]
+			3.. => Self::P256(FromEntropy::from_entropy(input)?),
+			_ => Self::Ecdsa(FromEntropy::from_entropy(input)?),
 		})
 	}
 }
@@ -390,6 +427,8 @@ impl AsRef<[u8]> for MultiSigner {
 			Self::Ed25519(ref who) => who.as_ref(),
 			Self::Sr25519(ref who) => who.as_ref(),
 			Self::Ecdsa(ref who) => who.as_ref(),
+			#[cfg(feature = "p256-experimental")]
+			Self::P256(ref who) => who.as_ref(),
 		}
 	}
 }
@@ -401,6 +440,8 @@ impl traits::IdentifyAccount for MultiSigner {
 			Self::Ed25519(who) => <[u8; 32]>::from(who).into(),
 			Self::Sr25519(who) => <[u8; 32]>::from(who).into(),
 			Self::Ecdsa(who) => sp_io::hashing::blake2_256(who.as_ref()).into(),
+			#[cfg(feature = "p256-experimental")]
+			Self::P256(who) => sp_io::hashing::blake2_256(who.as_ref()).into(),
 		}
 	}
 }
@@ -456,6 +497,25 @@ impl TryFrom<MultiSigner> for ecdsa::Public {
 	}
 }
 
+#[cfg(feature = "p256-experimental")]
+impl From<p256::Public> for MultiSigner {
+	fn from(x: p256::Public) -> Self {
+		Self::P256(x)
+	}
+}
+
+#[cfg(feature = "p256-experimental")]
+impl TryFrom<MultiSigner> for p256::Public {
+	type Error = ();
+	fn try_from(m: MultiSigner) -> Result<Self, Self::Error> {
+		if let MultiSigner::P256(x) = m {
+			Ok(x)
+		} else {
+			Err(())
+		}
+	}
+}
+
 #[cfg(feature = "std")]
 impl std::fmt::Display for MultiSigner {
 	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -463,6 +523,8 @@ impl std::fmt::Display for MultiSigner {
 			Self::Ed25519(who) => write!(fmt, "ed25519: {}", who),
 			Self::Sr25519(who) => write!(fmt, "sr25519: {}", who),
 			Self::Ecdsa(who) => write!(fmt, "ecdsa: {}", who),
+			#[cfg(feature = "p256-experimental")]
+			Self::P256(who) => write!(fmt, "p256: {}", who),
 		}
 	}
 }
@@ -479,6 +541,16 @@ impl Verify for MultiSignature {
 				sp_io::crypto::secp256k1_ecdsa_recover_compressed(sig.as_ref(), &m)
 					.map_or(false, |pubkey| sp_io::hashing::blake2_256(&pubkey) == who)
 			},
+			// Both `s` and `n - s` would be valid otherwise, making the signature malleable.
+			#[cfg(feature = "p256-experimental")]
+			Self::P256(sig) if !sig.is_low_s() => false,
+			#[cfg(feature = "p256-experimental")]
+			Self::P256(sig) => {
+				let m = sp_io::hashing::sha2_256(msg.get());
+				sp_io::crypto::p256_recover_compressed(sig.as_ref(), &m)
+					.iter()
+					.any(|pubkey| sp_io::hashing::blake2_256(pubkey) == who)
+			},
 		}
 	}
 }
@@ -1198,6 +1270,43 @@ mod tests {
 		assert!(multi_sig.verify(msg, &multi_signer.into_account()));
 	}
 
+	#[test]
+	#[cfg(feature = "p256-experimental")]
+	fn multi_signature_p256_verify_works() {
+		let msg = &b"test-message"[..];
+		let (pair, _) = p256::Pair::generate();
+
+		let signature = pair.sign(&msg);
+		assert!(p256::Pair::verify(&signature, msg, &pair.public()));
+
+		let multi_sig = MultiSignature::from(signature);
+		let account = MultiSigner::from(pair.public()).into_account();
+		assert!(multi_sig.verify(msg, &account));
+		assert!(!multi_sig.verify(&b"other-message"[..], &account));
+
+		let (other, _) = p256::Pair::generate();
+		let other_account = MultiSigner::from(other.public()).into_account();
+		assert!(!multi_sig.verify(msg, &other_account));
+
+		// Replace `s` by `n - s`, which is an equally valid but high-S signature.
+		let order: [u8; 32] = [
+			0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+			0xff, 0xff, 0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2,
+			0xfc, 0x63, 0x25, 0x51,
+		];
+		let mut high_s = signature;
+		let mut borrow = 0;
+		for ((high, low), n) in high_s.0[32..].iter_mut().zip(&signature.0[32..]).zip(order).rev() {
+			let (diff, overflow) = n.overflowing_sub(*low);
+			let (diff, overflow_borrow) = diff.overflowing_sub(borrow);
+			*high = diff;
+			borrow = u8::from(overflow || overflow_borrow);
+		}
+		assert!(!high_s.is_low_s());
+		assert_eq!(high_s.normalize_s(), signature);
+		assert!(!MultiSignature::from(high_s).verify(msg, &account));
+	}
+
 	#[test]
 	fn execute_and_generate_proof_works() {
 		use codec::Encode;
@@ -105,6 +105,14 @@ impl IdentifyAccount for sp_core::ecdsa::Public {
 	}
 }
 
+#[cfg(feature = "p256-experimental")]
+impl IdentifyAccount for sp_core::p256::Public {
+	type AccountId = Self;
+	fn into_account(self) -> Self {
+		self
+	}
+}
+
 /// Means of signature verification.
 pub trait Verify {
 	/// Type of the signer.
@@ -148,6 +156,14 @@ impl Verify for sp_core::ecdsa::Signature {
 	}
 }
 
+#[cfg(feature = "p256-experimental")]
+impl Verify for sp_core::p256::Signature {
+	type Signer = sp_core::p256::Public;
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &sp_core::p256::Public) -> bool {
+		sp_io::crypto::p256_verify(self, msg.get(), signer)
+	}
+}
+
 /// Means of signature verification of an application key.
 pub trait AppVerify {
 	/// Type of the signer.
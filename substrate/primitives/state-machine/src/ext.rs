@@ -295,82 +295,15 @@ where
 	}
 
 	fn next_storage_key(&mut self, key: &[u8]) -> Option<StorageKey> {
-		let mut next_backend_key =
-			self.backend.next_storage_key(key).expect(EXT_NOT_ALLOWED_TO_FAIL);
-		let mut overlay_changes = self.overlay.iter_after(key).peekable();
-
-		match (&next_backend_key, overlay_changes.peek()) {
-			(_, None) => next_backend_key,
-			(Some(_), Some(_)) => {
-				for overlay_key in overlay_changes {
-					let cmp = next_backend_key.as_deref().map(|v| v.cmp(overlay_key.0));
-
-					// If `backend_key` is less than the `overlay_key`, we found out next key.
-					if cmp == Some(Ordering::Less) {
-						return next_backend_key
-					} else if overlay_key.1.value().is_some() {
-						// If there exists a value for the `overlay_key` in the overlay
-						// (aka the key is still valid), it means we have found our next key.
-						return Some(overlay_key.0.to_vec())
-					} else if cmp == Some(Ordering::Equal) {
-						// If the `backend_key` and `overlay_key` are equal, it means that we need
-						// to search for the next backend key, because the overlay has overwritten
-						// this key.
-						next_backend_key = self
-							.backend
-							.next_storage_key(overlay_key.0)
-							.expect(EXT_NOT_ALLOWED_TO_FAIL);
-					}
-				}
-
-				next_backend_key
-			},
-			(None, Some(_)) => {
-				// Find the next overlay key that has a value attached.
-				overlay_changes.find_map(|k| k.1.value().as_ref().map(|_| k.0.to_vec()))
-			},
-		}
+		let next_key = self.find_next_storage_key(key);
+		self.overlay.record_range_read(None, key, next_key.as_deref());
+		next_key
 	}
 
 	fn next_child_storage_key(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<StorageKey> {
-		let mut next_backend_key = self
-			.backend
-			.next_child_storage_key(child_info, key)
-			.expect(EXT_NOT_ALLOWED_TO_FAIL);
-		let mut overlay_changes =
-			self.overlay.child_iter_after(child_info.storage_key(), key).peekable();
-
-		match (&next_backend_key, overlay_changes.peek()) {
-			(_, None) => next_backend_key,
-			(Some(_), Some(_)) => {
-				for overlay_key in overlay_changes {
-					let cmp = next_backend_key.as_deref().map(|v| v.cmp(overlay_key.0));
-
-					// If `backend_key` is less than the `overlay_key`, we found out next key.
-					if cmp == Some(Ordering::Less) {
-						return next_backend_key
-					} else if overlay_key.1.value().is_some() {
-						// If there exists a value for the `overlay_key` in the overlay
-						// (aka the key is still valid), it means we have found our next key.
-						return Some(overlay_key.0.to_vec())
-					} else if cmp == Some(Ordering::Equal) {
-						// If the `backend_key` and `overlay_key` are equal, it means that we need
-						// to search for the next backend key, because the overlay has overwritten
-						// this key.
-						next_backend_key = self
-							.backend
-							.next_child_storage_key(child_info, overlay_key.0)
-							.expect(EXT_NOT_ALLOWED_TO_FAIL);
-					}
-				}
-
-				next_backend_key
-			},
-			(None, Some(_)) => {
-				// Find the next overlay key that has a value attached.
-				overlay_changes.find_map(|k| k.1.value().as_ref().map(|_| k.0.to_vec()))
-			},
-		}
+		let next_key = self.find_next_child_storage_key(child_info, key);
+		self.overlay.record_range_read(Some(child_info), key, next_key.as_deref());
+		next_key
 	}
 
 	fn place_storage(&mut self, key: StorageKey, value: Option<StorageValue>) {
@@ -657,6 +590,89 @@ where
 	H::Out: Ord + 'static + codec::Codec,
 	B: Backend<H>,
 {
+	fn find_next_storage_key(&mut self, key: &[u8]) -> Option<StorageKey> {
+		let mut next_backend_key =
+			self.backend.next_storage_key(key).expect(EXT_NOT_ALLOWED_TO_FAIL);
+		let mut overlay_changes = self.overlay.iter_after(key).peekable();
+
+		match (&next_backend_key, overlay_changes.peek()) {
+			(_, None) => next_backend_key,
+			(Some(_), Some(_)) => {
+				for overlay_key in overlay_changes {
+					let cmp = next_backend_key.as_deref().map(|v| v.cmp(overlay_key.0));
+
+					// If `backend_key` is less than the `overlay_key`, we found out next key.
+					if cmp == Some(Ordering::Less) {
+						return next_backend_key
+					} else if overlay_key.1.value().is_some() {
+						// If there exists a value for the `overlay_key` in the overlay
+						// (aka the key is still valid), it means we have found our next key.
+						return Some(overlay_key.0.to_vec())
+					} else if cmp == Some(Ordering::Equal) {
+						// If the `backend_key` and `overlay_key` are equal, it means that we need
+						// to search for the next backend key, because the overlay has overwritten
+						// this key.
+						next_backend_key = self
+							.backend
+							.next_storage_key(overlay_key.0)
+							.expect(EXT_NOT_ALLOWED_TO_FAIL);
+					}
+				}
+
+				next_backend_key
+			},
+			(None, Some(_)) => {
+				// Find the next overlay key that has a value attached.
+				overlay_changes.find_map(|k| k.1.value().as_ref().map(|_| k.0.to_vec()))
+			},
+		}
+	}
+
+	fn find_next_child_storage_key(
+		&mut self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Option<StorageKey> {
+		let mut next_backend_key = self
+			.backend
+			.next_child_storage_key(child_info, key)
+			.expect(EXT_NOT_ALLOWED_TO_FAIL);
+		let mut overlay_changes =
+			self.overlay.child_iter_after(child_info.storage_key(), key).peekable();
+
+		match (&next_backend_key, overlay_changes.peek()) {
+			(_, None) => next_backend_key,
+			(Some(_), Some(_)) => {
+				for overlay_key in overlay_changes {
+					let cmp = next_backend_key.as_deref().map(|v| v.cmp(overlay_key.0));
+
+					// If `backend_key` is less than the `overlay_key`, we found out next key.
+					if cmp == Some(Ordering::Less) {
+						return next_backend_key
+					} else if overlay_key.1.value().is_some() {
+						// If there exists a value for the `overlay_key` in the overlay
+						// (aka the key is still valid), it means we have found our next key.
+						return Some(overlay_key.0.to_vec())
+					} else if cmp == Some(Ordering::Equal) {
+						// If the `backend_key` and `overlay_key` are equal, it means that we need
+						// to search for the next backend key, because the overlay has overwritten
+						// this key.
+						next_backend_key = self
+							.backend
+							.next_child_storage_key(child_info, overlay_key.0)
+							.expect(EXT_NOT_ALLOWED_TO_FAIL);
+					}
+				}
+
+				next_backend_key
+			},
+			(None, Some(_)) => {
+				// Find the next overlay key that has a value attached.
+				overlay_changes.find_map(|k| k.1.value().as_ref().map(|_| k.0.to_vec()))
+			},
+		}
+	}
+
 	fn limit_remove_from_backend(
 		&mut self,
 		child_info: Option<&ChildInfo>,
@@ -664,6 +680,8 @@ where
 		maybe_limit: Option<u32>,
 		start_at: Option<&[u8]>,
 	) -> (Option<Vec<u8>>, u32, u32) {
+		self.overlay.record_prefix_read(child_info, prefix.unwrap_or_default());
+
 		let iter = match self.backend.keys(IterArgs {
 			child_info: child_info.cloned(),
 			prefix,
@@ -896,6 +914,53 @@ mod tests {
 		assert_eq!(ext.next_storage_key(&[40]), Some(vec![50]));
 	}
 
+	#[test]
+	fn storage_accesses_are_recorded() {
+		let mut overlay = OverlayedChanges::default();
+		overlay.start_recording_accesses();
+		let backend = (
+			Storage {
+				top: map![
+					vec![10] => vec![10],
+					vec![20] => vec![20],
+					vec![40] => vec![40]
+				],
+				children_default: map![],
+			},
+			StateVersion::default(),
+		)
+			.into();
+
+		let mut ext = TestExt::new(&mut overlay, &backend, None);
+		assert_eq!(ext.storage(&[10]), Some(vec![10]));
+		assert_eq!(ext.next_storage_key(&[10]), Some(vec![20]));
+		ext.place_storage(vec![30], Some(vec![30]));
+		ext.clear_prefix(&[40], None, None);
+		drop(ext);
+
+		let accesses = overlay.take_recorded_accesses().unwrap();
+		assert_eq!(
+			accesses.reads().collect::<Vec<_>>(),
+			vec![&(None, vec![10]), &(None, vec![40])]
+		);
+		assert_eq!(
+			accesses.writes().collect::<Vec<_>>(),
+			vec![&(None, vec![30]), &(None, vec![40])]
+		);
+		assert_eq!(accesses.prefix_writes().collect::<Vec<_>>(), vec![(&None, &vec![40])]);
+
+		let write = |key: Vec<u8>| {
+			let mut overlay = OverlayedChanges::<Blake2Hasher>::default();
+			overlay.start_recording_accesses();
+			overlay.set_storage(key, None);
+			overlay.take_recorded_accesses().unwrap()
+		};
+		// Observed by iterating from `10` to `20`.
+		assert!(accesses.conflicts_with(&write(vec![15])));
+		assert!(!accesses.conflicts_with(&write(vec![25])));
+		assert!(accesses.conflicts_with(&write(vec![40, 1])));
+	}
+
 	#[test]
 	fn next_storage_key_works_with_a_lot_empty_values_in_overlay() {
 		let mut overlay = OverlayedChanges::default();
@@ -134,8 +134,8 @@ pub use crate::{
 	ext::Ext,
 	overlayed_changes::{
 		ChildStorageCollection, IndexOperation, OffchainChangesCollection,
-		OffchainOverlayedChanges, OverlayedChanges, StorageChanges, StorageCollection, StorageKey,
-		StorageValue,
+		OffchainOverlayedChanges, OverlayedChanges, QualifiedKey, StorageAccesses, StorageChanges,
+		StorageCollection, StorageKey, StorageValue,
 	},
 	stats::{StateMachineStats, UsageInfo, UsageUnit},
 	trie_backend::{TrieBackend, TrieBackendBuilder},
@@ -0,0 +1,284 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recording of the storage keys accessed through the overlay.
+
+use super::StorageKey;
+use alloc::{collections::btree_set::BTreeSet, vec::Vec};
+
+/// A storage key qualified by the child trie it belongs to.
+///
+/// The first element is the storage key of the child trie, or `None` for the top trie.
+pub type QualifiedKey = (Option<StorageKey>, StorageKey);
+
+/// A range of keys of the top trie or of a child trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyRange {
+	child: Option<StorageKey>,
+	/// The first key of the range.
+	start: StorageKey,
+	/// The first key after the range, `None` if the range is unbounded.
+	end: Option<StorageKey>,
+}
+
+impl KeyRange {
+	fn contains(&self, child: &Option<StorageKey>, key: &[u8]) -> bool {
+		&self.child == child &&
+			key >= self.start.as_slice() &&
+			self.end.as_ref().map_or(true, |end| key < end.as_slice())
+	}
+
+	fn overlaps(&self, other: &KeyRange) -> bool {
+		self.child == other.child &&
+			other.end.as_ref().map_or(true, |end| self.start < *end) &&
+			self.end.as_ref().map_or(true, |end| other.start < *end)
+	}
+
+	fn prefix(child: Option<StorageKey>, prefix: &[u8]) -> Self {
+		Self { child, start: prefix.to_vec(), end: prefix_end(prefix) }
+	}
+}
+
+/// The first key which is greater than all keys starting with `prefix`.
+fn prefix_end(prefix: &[u8]) -> Option<StorageKey> {
+	let mut end = prefix.to_vec();
+	while let Some(last) = end.pop() {
+		if last != u8::MAX {
+			end.push(last + 1);
+			return Some(end)
+		}
+	}
+	None
+}
+
+/// The storage keys read and written while recording was enabled.
+///
+/// See [`OverlayedChanges::start_recording_accesses`](super::OverlayedChanges).
+///
+/// The recorded accesses are conservative: accesses made in transactions which were rolled back
+/// are kept and iterations record the whole range which could have been observed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageAccesses {
+	reads: BTreeSet<QualifiedKey>,
+	range_reads: Vec<KeyRange>,
+	reads_storage_root: bool,
+	writes: BTreeSet<QualifiedKey>,
+	prefix_writes: Vec<KeyRange>,
+	has_unrecorded_writes: bool,
+}
+
+impl StorageAccesses {
+	/// The keys which were read.
+	///
+	/// This doesn't include the keys observed by iterating the storage.
+	pub fn reads(&self) -> impl Iterator<Item = &QualifiedKey> {
+		self.reads.iter()
+	}
+
+	/// The keys which were written.
+	///
+	/// This doesn't include the keys removed by clearing a prefix, see [`Self::prefix_writes`].
+	pub fn writes(&self) -> impl Iterator<Item = &QualifiedKey> {
+		self.writes.iter()
+	}
+
+	/// The prefixes which were cleared, qualified by the child trie they belong to.
+	pub fn prefix_writes(&self) -> impl Iterator<Item = (&Option<StorageKey>, &StorageKey)> {
+		self.prefix_writes.iter().map(|range| (&range.child, &range.start))
+	}
+
+	/// Whether the storage root was calculated, which depends on every key of the storage.
+	pub fn reads_storage_root(&self) -> bool {
+		self.reads_storage_root
+	}
+
+	/// Whether the offchain storage or the transaction index were written.
+	///
+	/// These writes are not part of the recorded keys.
+	pub fn has_unrecorded_writes(&self) -> bool {
+		self.has_unrecorded_writes
+	}
+
+	/// Whether anything was written.
+	pub fn has_writes(&self) -> bool {
+		!self.writes.is_empty() || !self.prefix_writes.is_empty() || self.has_unrecorded_writes
+	}
+
+	/// Whether `self` depends on or overwrites any of the writes of `other`.
+	///
+	/// Assume `self` and `other` were recorded while executing on top of the same state. If this
+	/// returns `false`, executing `self` after `other` has the same outcome as executing it on top
+	/// of the original state, and the values written by `self` can be applied on top of the values
+	/// written by `other`.
+	pub fn conflicts_with(&self, other: &Self) -> bool {
+		if self.reads_storage_root && other.has_writes() {
+			return true
+		}
+
+		let accessed_keys = || self.reads.iter().chain(&self.writes);
+		let accessed_ranges = || self.range_reads.iter().chain(&self.prefix_writes);
+
+		let accesses_prefix = |prefix: &KeyRange| {
+			accessed_keys().any(|(child, key)| prefix.contains(child, key)) ||
+				accessed_ranges().any(|range| range.overlaps(prefix))
+		};
+
+		other.writes.iter().any(|key| self.accesses_key(key)) ||
+			other.prefix_writes.iter().any(accesses_prefix)
+	}
+
+	/// The keys written by `other` which `self` depends on or overwrites.
+	///
+	/// Unlike [`Self::conflicts_with`] this only reports single keys, conflicts caused by cleared
+	/// prefixes or by calculating the storage root are not part of the result.
+	pub fn conflicting_keys<'a>(
+		&'a self,
+		other: &'a Self,
+	) -> impl Iterator<Item = &'a QualifiedKey> + 'a {
+		other.writes.iter().filter(|key| self.accesses_key(key))
+	}
+
+	/// The ranges of keys observed by iterating the storage, qualified by the child trie they
+	/// belong to.
+	///
+	/// The second element is the first key of the range and the third element is the first key
+	/// after the range, `None` if the range is unbounded.
+	pub fn range_reads(
+		&self,
+	) -> impl Iterator<Item = (&Option<StorageKey>, &StorageKey, Option<&StorageKey>)> {
+		self.range_reads.iter().map(|range| (&range.child, &range.start, range.end.as_ref()))
+	}
+
+	/// Whether `key` was read, written, iterated over or cleared.
+	fn accesses_key(&self, key: &QualifiedKey) -> bool {
+		let mut accessed_ranges = self.range_reads.iter().chain(&self.prefix_writes);
+
+		self.reads.contains(key) ||
+			self.writes.contains(key) ||
+			accessed_ranges.any(|range| range.contains(&key.0, &key.1))
+	}
+
+	/// Merge the accesses recorded in `other` into `self`.
+	pub fn extend(&mut self, other: Self) {
+		self.reads.extend(other.reads);
+		self.range_reads.extend(other.range_reads);
+		self.reads_storage_root |= other.reads_storage_root;
+		self.writes.extend(other.writes);
+		self.prefix_writes.extend(other.prefix_writes);
+		self.has_unrecorded_writes |= other.has_unrecorded_writes;
+	}
+
+	pub(super) fn record_read(&mut self, child: Option<&[u8]>, key: &[u8]) {
+		self.reads.insert((child.map(Into::into), key.to_vec()));
+	}
+
+	/// Record the iteration from `start` up to and including `last`.
+	pub(super) fn record_range_read(
+		&mut self,
+		child: Option<&[u8]>,
+		start: &[u8],
+		last: Option<&[u8]>,
+	) {
+		let end = last.map(|last| {
+			let mut end = last.to_vec();
+			end.push(0);
+			end
+		});
+		self.range_reads.push(KeyRange {
+			child: child.map(Into::into),
+			start: start.to_vec(),
+			end,
+		});
+	}
+
+	pub(super) fn record_prefix_read(&mut self, child: Option<&[u8]>, prefix: &[u8]) {
+		self.range_reads.push(KeyRange::prefix(child.map(Into::into), prefix));
+	}
+
+	pub(super) fn record_storage_root_read(&mut self) {
+		self.reads_storage_root = true;
+	}
+
+	pub(super) fn record_write(&mut self, child: Option<&[u8]>, key: &[u8]) {
+		self.writes.insert((child.map(Into::into), key.to_vec()));
+	}
+
+	pub(super) fn record_prefix_write(&mut self, child: Option<&[u8]>, prefix: &[u8]) {
+		self.prefix_writes.push(KeyRange::prefix(child.map(Into::into), prefix));
+	}
+
+	pub(super) fn record_unrecorded_write(&mut self) {
+		self.has_unrecorded_writes = true;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn prefix_end_works() {
+		assert_eq!(prefix_end(b"ab"), Some(b"ac".to_vec()));
+		assert_eq!(prefix_end(&[1, 255, 255]), Some(vec![2]));
+		assert_eq!(prefix_end(&[255]), None);
+		assert_eq!(prefix_end(&[]), None);
+	}
+
+	#[test]
+	fn conflicts_are_detected() {
+		let mut writer = StorageAccesses::default();
+		writer.record_write(None, b"balance_alice");
+		writer.record_prefix_write(Some(b"child"), b"old");
+
+		let mut independent = StorageAccesses::default();
+		independent.record_read(None, b"balance_bob");
+		independent.record_read(Some(b"child"), b"balance_alice");
+		independent.record_range_read(None, b"balance_b", Some(b"balance_c"));
+		independent.record_prefix_read(Some(b"child"), b"new");
+		assert!(!independent.conflicts_with(&writer));
+
+		let mut reads_key = StorageAccesses::default();
+		reads_key.record_read(None, b"balance_alice");
+		assert!(reads_key.conflicts_with(&writer));
+
+		let mut iterates = StorageAccesses::default();
+		iterates.record_range_read(None, b"balance", None);
+		assert!(iterates.conflicts_with(&writer));
+
+		let mut reads_cleared = StorageAccesses::default();
+		reads_cleared.record_read(Some(b"child"), b"old_key");
+		assert!(reads_cleared.conflicts_with(&writer));
+
+		let mut iterates_child = StorageAccesses::default();
+		iterates_child.record_prefix_read(Some(b"child"), b"");
+		assert!(iterates_child.conflicts_with(&writer));
+
+		let mut overwrites = StorageAccesses::default();
+		overwrites.record_write(Some(b"child"), b"old_key");
+		assert!(overwrites.conflicts_with(&writer));
+
+		let mut reads_root = StorageAccesses::default();
+		reads_root.record_storage_root_read();
+		assert!(reads_root.conflicts_with(&writer));
+		assert!(!reads_root.conflicts_with(&StorageAccesses::default()));
+		assert_eq!(reads_root.conflicting_keys(&writer).count(), 0);
+
+		let conflicting = reads_key.conflicting_keys(&writer).collect::<Vec<_>>();
+		assert_eq!(conflicting, vec![&(None, b"balance_alice".to_vec())]);
+		assert_eq!(independent.conflicting_keys(&writer).count(), 0);
+	}
+}
@@ -17,6 +17,7 @@
 
 //! The overlayed changes to state.
 
+mod accesses;
 mod changeset;
 mod offchain;
 
@@ -45,7 +46,10 @@ use std::{
 	boxed::Box,
 };
 
-pub use self::changeset::{AlreadyInRuntime, NoOpenTransaction, NotInRuntime, OverlayedValue};
+pub use self::{
+	accesses::{QualifiedKey, StorageAccesses},
+	changeset::{AlreadyInRuntime, NoOpenTransaction, NotInRuntime, OverlayedValue},
+};
 
 /// Changes that are made outside of extrinsics are marked with this index;
 pub const NO_EXTRINSIC_INDEX: u32 = 0xffffffff;
@@ -108,6 +112,8 @@ pub struct OverlayedChanges<H: Hasher> {
 	///
 	/// This transaction can be applied to the backend to persist the state changes.
 	storage_transaction_cache: Option<StorageTransactionCache<H>>,
+	/// The storage keys accessed since recording was started.
+	accesses: Option<StorageAccesses>,
 }
 
 impl<H: Hasher> Default for OverlayedChanges<H> {
@@ -120,6 +126,7 @@ impl<H: Hasher> Default for OverlayedChanges<H> {
 			collect_extrinsics: Default::default(),
 			stats: Default::default(),
 			storage_transaction_cache: None,
+			accesses: None,
 		}
 	}
 }
@@ -134,6 +141,7 @@ impl<H: Hasher> Clone for OverlayedChanges<H> {
 			collect_extrinsics: self.collect_extrinsics,
 			stats: self.stats.clone(),
 			storage_transaction_cache: self.storage_transaction_cache.clone(),
+			accesses: self.accesses.clone(),
 		}
 	}
 }
@@ -148,6 +156,7 @@ impl<H: Hasher> core::fmt::Debug for OverlayedChanges<H> {
 			.field("collect_extrinsics", &self.collect_extrinsics)
 			.field("stats", &self.stats)
 			.field("storage_transaction_cache", &self.storage_transaction_cache)
+			.field("accesses", &self.accesses)
 			.finish()
 	}
 }
@@ -290,6 +299,10 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
 	pub fn storage(&mut self, key: &[u8]) -> Option<Option<&[u8]>> {
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_read(None, key);
+		}
+
 		self.top.get(key).map(|x| {
 			let value = x.value();
 			let size_read = value.map(|x| x.len() as u64).unwrap_or(0);
@@ -308,6 +321,10 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
 	pub fn child_storage(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Option<&[u8]>> {
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_read(Some(child_info.storage_key()), key);
+		}
+
 		let map = self.children.get_mut(child_info.storage_key())?;
 		let value = map.0.get(key)?.value();
 		let size_read = value.map(|x| x.len() as u64).unwrap_or(0);
@@ -323,6 +340,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 
 		let size_write = val.as_ref().map(|x| x.len() as u64).unwrap_or(0);
 		self.stats.tally_write_overlay(size_write);
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_write(None, &key);
+		}
 		let extrinsic_index = self.extrinsic_index();
 		self.top.set(key, val, extrinsic_index);
 	}
@@ -337,6 +357,11 @@ impl<H: Hasher> OverlayedChanges<H> {
 		let extrinsic_index = self.extrinsic_index();
 		let size_write = element.len() as u64;
 		self.stats.tally_write_overlay(size_write);
+		if let Some(accesses) = &mut self.accesses {
+			// Appending depends on the current value.
+			accesses.record_read(None, &key);
+			accesses.record_write(None, &key);
+		}
 		self.top.append_storage(key, element, init, extrinsic_index);
 	}
 
@@ -356,6 +381,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 		let extrinsic_index = self.extrinsic_index();
 		let size_write = val.as_ref().map(|x| x.len() as u64).unwrap_or(0);
 		self.stats.tally_write_overlay(size_write);
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_write(Some(child_info.storage_key()), &key);
+		}
 		let storage_key = child_info.storage_key().to_vec();
 		let top = &self.top;
 		let (changeset, info) = self
@@ -372,6 +400,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// Can be rolled back or committed when called inside a transaction.
 	pub fn clear_child_storage(&mut self, child_info: &ChildInfo) -> u32 {
 		self.mark_dirty();
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_prefix_write(Some(child_info.storage_key()), &[]);
+		}
 
 		let extrinsic_index = self.extrinsic_index();
 		let storage_key = child_info.storage_key().to_vec();
@@ -390,6 +421,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// Can be rolled back or committed when called inside a transaction.
 	pub fn clear_prefix(&mut self, prefix: &[u8]) -> u32 {
 		self.mark_dirty();
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_prefix_write(None, prefix);
+		}
 
 		let extrinsic_index = self.extrinsic_index();
 		self.top.clear_where(|key, _| key.starts_with(prefix), extrinsic_index)
@@ -400,6 +434,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// Can be rolled back or committed when called inside a transaction
 	pub fn clear_child_prefix(&mut self, child_info: &ChildInfo, prefix: &[u8]) -> u32 {
 		self.mark_dirty();
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_prefix_write(Some(child_info.storage_key()), prefix);
+		}
 
 		let extrinsic_index = self.extrinsic_index();
 		let storage_key = child_info.storage_key().to_vec();
@@ -650,6 +687,10 @@ impl<H: Hasher> OverlayedChanges<H> {
 	where
 		H::Out: Ord + Encode,
 	{
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_storage_root_read();
+		}
+
 		if let Some(cache) = &self.storage_transaction_cache {
 			return (cache.transaction_storage_root, true)
 		}
@@ -685,6 +726,10 @@ impl<H: Hasher> OverlayedChanges<H> {
 		let storage_key = child_info.storage_key();
 		let prefixed_storage_key = child_info.prefixed_storage_key();
 
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_prefix_read(Some(storage_key), &[]);
+		}
+
 		if self.storage_transaction_cache.is_some() {
 			let root = self
 				.storage(prefixed_storage_key.as_slice())
@@ -759,6 +804,9 @@ impl<H: Hasher> OverlayedChanges<H> {
 	/// Write a key value pair to the offchain storage overlay.
 	pub fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>) {
 		use sp_core::offchain::STORAGE_PREFIX;
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_unrecorded_write();
+		}
 		match value {
 			Some(value) => self.offchain.set(STORAGE_PREFIX, key, value),
 			None => self.offchain.remove(STORAGE_PREFIX, key),
@@ -767,8 +815,44 @@ impl<H: Hasher> OverlayedChanges<H> {
 
 	/// Add transaction index operation.
 	pub fn add_transaction_index(&mut self, op: IndexOperation) {
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_unrecorded_write();
+		}
 		self.transaction_index_ops.push(op)
 	}
+
+	/// Start recording the storage keys accessed through this overlay.
+	///
+	/// Any accesses recorded before are discarded.
+	pub fn start_recording_accesses(&mut self) {
+		self.accesses = Some(Default::default());
+	}
+
+	/// Stop recording the accessed storage keys and return the recorded accesses.
+	///
+	/// Returns `None` if recording was not started.
+	pub fn take_recorded_accesses(&mut self) -> Option<StorageAccesses> {
+		self.accesses.take()
+	}
+
+	/// Record the iteration over the keys after `key` up to and including `last`.
+	pub(crate) fn record_range_read(
+		&mut self,
+		child_info: Option<&ChildInfo>,
+		key: &[u8],
+		last: Option<&[u8]>,
+	) {
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_range_read(child_info.map(ChildInfo::storage_key), key, last);
+		}
+	}
+
+	/// Record the iteration over all keys starting with `prefix`.
+	pub(crate) fn record_prefix_read(&mut self, child_info: Option<&ChildInfo>, prefix: &[u8]) {
+		if let Some(accesses) = &mut self.accesses {
+			accesses.record_prefix_read(child_info.map(ChildInfo::storage_key), prefix);
+		}
+	}
 }
 
 #[cfg(feature = "std")]
@@ -49,6 +49,7 @@ pub const MAX_TOPICS: usize = 4;
 #[cfg(feature = "std")]
 pub use store_api::{
 	Error, NetworkPriority, Result, StatementSource, StatementStore, SubmitResult,
+	SubscriptionFilter, SubscriptionSink,
 };
 
 #[cfg(feature = "std")]
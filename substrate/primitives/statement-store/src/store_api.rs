@@ -16,7 +16,7 @@
 // limitations under the License.
 
 pub use crate::runtime_api::StatementSource;
-use crate::{Hash, Statement, Topic};
+use crate::{DecryptionKey, Hash, Statement, Topic};
 
 /// Statement store error.
 #[derive(Debug, Eq, PartialEq, thiserror::Error)]
@@ -30,6 +30,9 @@ pub enum Error {
 	/// Error making runtime call.
 	#[error("Error calling into the runtime")]
 	Runtime,
+	/// The operation is not supported by the statement store.
+	#[error("Operation not supported by the statement store")]
+	Unsupported,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -61,6 +64,31 @@ pub enum SubmitResult {
 /// Result type for `Error`
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Filter of a statement subscription.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionFilter {
+	/// Only statements which include all of these topics are matched.
+	pub match_all_topics: Vec<Topic>,
+	/// Only statements whose decryption key is identified as `dest` are matched. If `None`, only
+	/// statements with no `DecryptionKey` field are matched.
+	pub dest: Option<DecryptionKey>,
+}
+
+impl SubscriptionFilter {
+	/// Returns `true` if `statement` is matched by the filter.
+	pub fn matches(&self, statement: &Statement) -> bool {
+		statement.decryption_key() == self.dest &&
+			self.match_all_topics.iter().all(|topic| {
+				(0..crate::MAX_TOPICS).any(|index| statement.topic(index) == Some(*topic))
+			})
+	}
+}
+
+/// Receiver of the statements of a subscription.
+///
+/// Returns `false` once the subscriber is gone, which ends the subscription.
+pub type SubscriptionSink = Box<dyn FnMut(&Statement) -> bool + Send>;
+
 /// Statement store API.
 pub trait StatementStore: Send + Sync {
 	/// Return all statements.
@@ -87,4 +115,14 @@ pub trait StatementStore: Send + Sync {
 
 	/// Remove a statement from the store.
 	fn remove(&self, hash: &Hash) -> Result<()>;
+
+	/// Subscribe to the statements matched by `filter`.
+	///
+	/// `sink` is first called with all matching statements already in the store, then with each
+	/// matching statement accepted into the store afterwards.
+	///
+	/// Returns [`Error::Unsupported`] by default.
+	fn subscribe(&self, _filter: SubscriptionFilter, _sink: SubscriptionSink) -> Result<()> {
+		Err(Error::Unsupported)
+	}
 }
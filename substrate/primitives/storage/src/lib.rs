@@ -202,6 +202,14 @@ pub mod well_known_keys {
 	/// Encodes to `0x307833413633364636343635`
 	pub const HEAP_PAGES: &[u8] = b":heappages";
 
+	/// Wasm code of a runtime that is scheduled to replace [`CODE`].
+	///
+	/// Stored as a raw byte vector. Optional, runtimes which delay the enactment of a runtime
+	/// upgrade can put the new code here, so that nodes are able to prepare it ahead of time.
+	///
+	/// Encodes to `0x3a70656e64696e675f636f6465`.
+	pub const PENDING_CODE: &[u8] = b":pending_code";
+
 	/// Current extrinsic index (u32) is stored under this key.
 	///
 	/// Encodes to `0x3a65787472696e7369635f696e646578`.
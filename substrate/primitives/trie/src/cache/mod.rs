@@ -51,8 +51,10 @@ use std::{
 use trie_db::{node::NodeOwned, CachedValue};
 
 mod shared_cache;
+mod snapshot;
 
-pub use shared_cache::SharedTrieCache;
+pub use shared_cache::{SharedTrieCache, SharedTrieCacheStats};
+pub use snapshot::{SnapshotError, TrieCacheSnapshot};
 
 use self::shared_cache::ValueCacheKeyHash;
 
@@ -292,6 +294,17 @@ struct TrieHitStats {
 	value_cache: HitStats,
 }
 
+impl TrieHitStats {
+	/// Add the shared cache lookups of this instance to the cumulative `stats`.
+	fn accumulate_shared(&self, stats: &mut SharedTrieCacheStats) {
+		stats.node_hits += self.node_cache.shared_hits.load(Ordering::Relaxed);
+		stats.node_fetch_attempts += self.node_cache.shared_fetch_attempts.load(Ordering::Relaxed);
+		stats.value_hits += self.value_cache.shared_hits.load(Ordering::Relaxed);
+		stats.value_fetch_attempts +=
+			self.value_cache.shared_fetch_attempts.load(Ordering::Relaxed);
+	}
+}
+
 /// An internal struct to store the cached trie nodes.
 pub(crate) struct NodeCached<H> {
 	/// The cached node.
@@ -414,6 +427,8 @@ impl<H: Hasher> Drop for LocalTrieCache<H> {
 			},
 		};
 
+		self.stats.accumulate_shared(shared_inner.stats_mut());
+
 		shared_inner.node_cache_mut().update(self.node_cache.get_mut().drain());
 
 		shared_inner.value_cache_mut().update(
@@ -727,6 +742,57 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn snapshot_export_and_import_works() {
+		let (db, root) = create_trie();
+
+		let shared_cache = Cache::new(CACHE_SIZE);
+		{
+			let local_cache = shared_cache.local_cache();
+			let mut cache = local_cache.as_trie_db_cache(root);
+			let trie = TrieDBBuilder::<Layout>::new(&db, &root).with_cache(&mut cache).build();
+			for (key, value) in TEST_DATA {
+				assert_eq!(value.to_vec(), trie.get(key).unwrap().unwrap());
+			}
+			assert!(trie.get(b"missing").unwrap().is_none());
+		}
+
+		let snapshot = shared_cache.export_snapshot(root, CACHE_SIZE_RAW);
+		assert_eq!(snapshot.nodes_len(), shared_cache.read_lock_inner().node_cache().lru.len());
+		let snapshot = TrieCacheSnapshot::from_bytes::<sp_core::Blake2Hasher>(
+			&snapshot.to_bytes::<sp_core::Blake2Hasher>(),
+		)
+		.unwrap();
+
+		// A snapshot taken at another block is ignored.
+		let restarted_cache = Cache::new(CACHE_SIZE);
+		let snapshot_bytes = snapshot.to_bytes::<sp_core::Blake2Hasher>();
+		assert_eq!((0, 0), restarted_cache.import_snapshot(snapshot, &Default::default()));
+
+		let snapshot =
+			TrieCacheSnapshot::from_bytes::<sp_core::Blake2Hasher>(&snapshot_bytes).unwrap();
+		let (nodes, values) = restarted_cache.import_snapshot(snapshot, &root);
+		assert_eq!(nodes, shared_cache.read_lock_inner().node_cache().lru.len());
+		assert!(values > 0);
+
+		{
+			let local_cache = restarted_cache.local_cache();
+			let mut cache = local_cache.as_trie_db_cache(root);
+			let trie = TrieDBBuilder::<Layout>::new(&db, &root).with_cache(&mut cache).build();
+			for (key, value) in TEST_DATA {
+				assert_eq!(value.to_vec(), trie.get(key).unwrap().unwrap());
+			}
+			assert!(trie.get(b"missing").unwrap().is_none());
+		}
+
+		// All nodes were preloaded, inline values are only found through their nodes.
+		let stats = restarted_cache.stats();
+		assert!(stats.node_hits > 0);
+		assert_eq!(stats.node_hits, stats.node_fetch_attempts);
+		assert!(stats.value_hits > 0);
+		assert!(stats.value_hits < stats.value_fetch_attempts);
+	}
+
 	#[test]
 	fn trie_db_mut_cache_works() {
 		let (mut db, root) = create_trie();
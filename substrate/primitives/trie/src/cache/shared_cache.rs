@@ -17,7 +17,12 @@
 
 ///! Provides the [`SharedNodeCache`], the [`SharedValueCache`] and the [`SharedTrieCache`]
 ///! that combines both caches and is exported to the outside.
-use super::{CacheSize, NodeCached};
+use super::{
+	snapshot::{SnapshotNode, SnapshotValue, TrieCacheSnapshot},
+	CacheSize, NodeCached,
+};
+use crate::{LayoutV1, NodeCodec};
+use codec::{Decode, Encode};
 use hash_db::Hasher;
 use nohash_hasher::BuildNoHashHasher;
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
@@ -27,7 +32,7 @@ use std::{
 	hash::{BuildHasher, Hasher as _},
 	sync::{Arc, LazyLock},
 };
-use trie_db::{node::NodeOwned, CachedValue};
+use trie_db::{node::NodeOwned, CachedValue, NodeCodec as _};
 
 static RANDOM_STATE: LazyLock<ahash::RandomState> = LazyLock::new(|| {
 	use rand::Rng;
@@ -401,7 +406,6 @@ where
 
 impl<H> ValueCacheKey<H> {
 	/// Constructs [`Self::Value`].
-	#[cfg(test)] // Only used in tests.
 	pub fn new_value(storage_key: impl Into<Arc<[u8]>>, storage_root: H) -> Self
 	where
 		H: AsRef<[u8]>,
@@ -565,10 +569,27 @@ impl<H: Eq + std::hash::Hash + Clone + Copy + AsRef<[u8]>> SharedValueCache<H> {
 	}
 }
 
+/// Cumulative hit statistics of a [`SharedTrieCache`].
+///
+/// Only the lookups that could not be served by a [`LocalTrieCache`](super::LocalTrieCache) are
+/// sent to the shared cache and are accounted here.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SharedTrieCacheStats {
+	/// The number of nodes found in the shared cache.
+	pub node_hits: u64,
+	/// The number of nodes looked up in the shared cache.
+	pub node_fetch_attempts: u64,
+	/// The number of values found in the shared cache.
+	pub value_hits: u64,
+	/// The number of values looked up in the shared cache.
+	pub value_fetch_attempts: u64,
+}
+
 /// The inner of [`SharedTrieCache`].
 pub(super) struct SharedTrieCacheInner<H: Hasher> {
 	node_cache: SharedNodeCache<H::Out>,
 	value_cache: SharedValueCache<H::Out>,
+	stats: SharedTrieCacheStats,
 }
 
 impl<H: Hasher> SharedTrieCacheInner<H> {
@@ -593,6 +614,11 @@ impl<H: Hasher> SharedTrieCacheInner<H> {
 	pub(super) fn node_cache_mut(&mut self) -> &mut SharedNodeCache<H::Out> {
 		&mut self.node_cache
 	}
+
+	/// Returns a mutable reference to the [`SharedTrieCacheStats`].
+	pub(super) fn stats_mut(&mut self) -> &mut SharedTrieCacheStats {
+		&mut self.stats
+	}
 }
 
 /// The shared trie cache.
@@ -659,6 +685,7 @@ impl<H: Hasher> SharedTrieCache<H> {
 					value_cache_max_inline_size,
 					value_cache_max_heap_size,
 				),
+				stats: Default::default(),
 			})),
 		}
 	}
@@ -717,6 +744,158 @@ impl<H: Hasher> SharedTrieCache<H> {
 		node_cache_size + value_cache_size
 	}
 
+	/// Returns the cumulative hit statistics of this cache.
+	pub fn stats(&self) -> SharedTrieCacheStats {
+		self.inner.read().stats
+	}
+
+	/// Export the hottest entries of this cache into a [`TrieCacheSnapshot`].
+	///
+	/// Entries are taken from the most to the least recently used one until `maximum_size` bytes
+	/// are reached. The budget is split between the node and the value cache in the same way as
+	/// the budget of the cache itself. `storage_root` should be the storage root of the current
+	/// best block, it is checked by [`Self::import_snapshot`].
+	///
+	/// Values that are stored inline in their trie node are skipped, as they can not be looked
+	/// up by their hash.
+	pub fn export_snapshot(
+		&self,
+		storage_root: H::Out,
+		maximum_size: usize,
+	) -> TrieCacheSnapshot<H::Out>
+	where
+		H::Out: Encode + Decode,
+	{
+		let inner = self.inner.read();
+		let mut snapshot = TrieCacheSnapshot::new(storage_root);
+
+		let mut value_budget = (maximum_size as f32 * 0.20) as usize;
+		let mut node_budget = maximum_size - value_budget;
+
+		for (_, node) in inner.node_cache.lru.iter() {
+			let node = match node {
+				NodeOwned::Value(data, _) => SnapshotNode::Value(data.to_vec()),
+				node => SnapshotNode::Node(node.to_encoded::<NodeCodec<H>>()),
+			};
+
+			match node_budget.checked_sub(node.size()) {
+				Some(remaining) => node_budget = remaining,
+				None => break,
+			}
+
+			snapshot.nodes.push(node);
+		}
+
+		for (key, value) in inner.value_cache.lru.iter() {
+			let hash = match value {
+				CachedValue::NonExisting => None,
+				CachedValue::ExistingHash(hash) => Some(*hash),
+				CachedValue::Existing { hash, .. } => match inner.node_cache.lru.peek(hash) {
+					Some(NodeOwned::Value(..)) => Some(*hash),
+					_ => continue,
+				},
+			};
+
+			let size = key.storage_key.len() + 2 * H::LENGTH;
+			match value_budget.checked_sub(size) {
+				Some(remaining) => value_budget = remaining,
+				None => break,
+			}
+
+			snapshot.values.push(SnapshotValue {
+				storage_root: key.storage_root,
+				storage_key: key.storage_key.to_vec(),
+				hash,
+			});
+		}
+
+		snapshot
+	}
+
+	/// Import the entries of the given `snapshot` into this cache.
+	///
+	/// The snapshot is only imported when it was taken at the given `storage_root`, which should
+	/// be the storage root of the current best block. Imported entries are still subject to the
+	/// size limits of this cache.
+	///
+	/// Returns the number of imported node and value cache entries.
+	pub fn import_snapshot(
+		&self,
+		snapshot: TrieCacheSnapshot<H::Out>,
+		storage_root: &H::Out,
+	) -> (usize, usize)
+	where
+		H::Out: Encode + Decode,
+	{
+		if snapshot.storage_root() != storage_root {
+			tracing::debug!(
+				target: super::LOG_TARGET,
+				"Not importing trie cache snapshot taken at {:?}, best storage root is {:?}",
+				snapshot.storage_root(),
+				storage_root,
+			);
+			return (0, 0)
+		}
+
+		let mut inner = self.inner.write();
+		let inner = &mut *inner;
+
+		let node_cache = &mut inner.node_cache.lru;
+		node_cache.limiter_mut().items_evicted = 0;
+		node_cache.limiter_mut().max_items_evicted = usize::MAX;
+
+		let mut imported_nodes = 0;
+		// Insert the least recently used entries first to restore the order of the cache.
+		for node in snapshot.nodes.into_iter().rev() {
+			let (hash, node) = match node {
+				SnapshotNode::Value(data) => {
+					let hash = H::hash(&data);
+					(hash, NodeOwned::Value(data.into(), hash))
+				},
+				SnapshotNode::Node(encoded) => {
+					let node = NodeCodec::<H>::decode(&encoded)
+						.ok()
+						.and_then(|node| node.to_owned_node::<LayoutV1<H>>().ok());
+
+					match node {
+						Some(node) => (H::hash(&encoded), node),
+						None => continue,
+					}
+				},
+			};
+
+			if node_cache.insert(hash, node) {
+				imported_nodes += 1;
+			}
+		}
+
+		let value_cache = &mut inner.value_cache.lru;
+		value_cache.limiter_mut().items_evicted = 0;
+		value_cache.limiter_mut().max_items_evicted = usize::MAX;
+
+		let mut imported_values = 0;
+		for value in snapshot.values.into_iter().rev() {
+			let cached_value = match value.hash {
+				Some(hash) => CachedValue::ExistingHash(hash),
+				None => CachedValue::NonExisting,
+			};
+			let key = ValueCacheKey::new_value(value.storage_key, value.storage_root);
+
+			if value_cache.insert(key, cached_value) {
+				imported_values += 1;
+			}
+		}
+
+		tracing::debug!(
+			target: super::LOG_TARGET,
+			"Imported trie cache snapshot: {} nodes, {} values",
+			imported_nodes,
+			imported_values,
+		);
+
+		(imported_nodes, imported_values)
+	}
+
 	/// Reset the node cache.
 	pub fn reset_node_cache(&self) {
 		self.inner.write().node_cache.reset();
@@ -0,0 +1,167 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the [`TrieCacheSnapshot`] that is used to persist the hottest entries of the
+//! [`SharedTrieCache`](super::SharedTrieCache) across restarts of the node.
+
+use codec::{Decode, DecodeAll, Encode};
+use hash_db::Hasher;
+
+/// The version of the encoding produced by [`TrieCacheSnapshot::to_bytes`].
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// Error returned when decoding a [`TrieCacheSnapshot`].
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+	#[error("Unsupported trie cache snapshot version {0}")]
+	UnsupportedVersion(u8),
+	#[error("Trie cache snapshot checksum mismatch")]
+	ChecksumMismatch,
+	#[error("Failed to decode trie cache snapshot: {0}")]
+	Decode(#[from] codec::Error),
+}
+
+/// A node cache entry of a [`TrieCacheSnapshot`].
+///
+/// The hash of the entry isn't stored, it is recomputed when importing the snapshot.
+#[derive(Encode, Decode)]
+pub(super) enum SnapshotNode {
+	/// An encoded trie node.
+	Node(Vec<u8>),
+	/// The data of a value node.
+	Value(Vec<u8>),
+}
+
+impl SnapshotNode {
+	/// Returns the number of bytes this entry takes up in the snapshot.
+	pub(super) fn size(&self) -> usize {
+		match self {
+			Self::Node(data) | Self::Value(data) => data.len(),
+		}
+	}
+}
+
+/// A value cache entry of a [`TrieCacheSnapshot`].
+#[derive(Encode, Decode)]
+pub(super) struct SnapshotValue<H> {
+	/// The storage root of the trie the value was read from.
+	pub storage_root: H,
+	/// The storage key of the value.
+	pub storage_key: Vec<u8>,
+	/// The hash of the value node or `None` if the value doesn't exist.
+	pub hash: Option<H>,
+}
+
+/// The hottest entries of a [`SharedTrieCache`](super::SharedTrieCache).
+///
+/// The entries are ordered from the most to the least recently used one. All entries are content
+/// addressed and are therefore never invalidated, but they are only worth being preloaded while
+/// the node is still at the block the snapshot was taken at. This block is identified by its
+/// [`storage_root`](Self::storage_root).
+#[derive(Encode, Decode)]
+pub struct TrieCacheSnapshot<H> {
+	storage_root: H,
+	pub(super) nodes: Vec<SnapshotNode>,
+	pub(super) values: Vec<SnapshotValue<H>>,
+}
+
+impl<H: Encode + Decode> TrieCacheSnapshot<H> {
+	/// Create a new empty snapshot for the given `storage_root`.
+	pub(super) fn new(storage_root: H) -> Self {
+		Self { storage_root, nodes: Vec::new(), values: Vec::new() }
+	}
+
+	/// The storage root of the best block at the time the snapshot was taken.
+	pub fn storage_root(&self) -> &H {
+		&self.storage_root
+	}
+
+	/// Returns the number of node cache entries.
+	pub fn nodes_len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	/// Returns the number of value cache entries.
+	pub fn values_len(&self) -> usize {
+		self.values.len()
+	}
+
+	/// Encode the snapshot.
+	///
+	/// The encoding is prefixed with a version and suffixed with a checksum calculated using
+	/// `Hashing`, to detect truncated or otherwise corrupted snapshots when reading them back.
+	pub fn to_bytes<Hashing: Hasher>(&self) -> Vec<u8> {
+		let mut bytes = vec![SNAPSHOT_VERSION];
+		self.encode_to(&mut bytes);
+		let checksum = Hashing::hash(&bytes);
+		bytes.extend_from_slice(checksum.as_ref());
+		bytes
+	}
+
+	/// Decode a snapshot previously encoded with [`Self::to_bytes`].
+	pub fn from_bytes<Hashing: Hasher>(bytes: &[u8]) -> Result<Self, SnapshotError> {
+		let checksum_len = Hashing::LENGTH;
+		if bytes.len() < checksum_len + 1 {
+			return Err(SnapshotError::ChecksumMismatch)
+		}
+
+		let (data, checksum) = bytes.split_at(bytes.len() - checksum_len);
+		if Hashing::hash(data).as_ref() != checksum {
+			return Err(SnapshotError::ChecksumMismatch)
+		}
+
+		if data[0] != SNAPSHOT_VERSION {
+			return Err(SnapshotError::UnsupportedVersion(data[0]))
+		}
+
+		Ok(Self::decode_all(&mut &data[1..])?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{Blake2Hasher, H256};
+
+	#[test]
+	fn snapshot_encoding_roundtrips_and_detects_corruption() {
+		let mut snapshot = TrieCacheSnapshot::new(H256::repeat_byte(1));
+		snapshot.nodes.push(SnapshotNode::Value(vec![1, 2, 3]));
+		snapshot.values.push(SnapshotValue {
+			storage_root: H256::repeat_byte(1),
+			storage_key: b"key".to_vec(),
+			hash: None,
+		});
+
+		let mut bytes = snapshot.to_bytes::<Blake2Hasher>();
+		let decoded = TrieCacheSnapshot::<H256>::from_bytes::<Blake2Hasher>(&bytes).unwrap();
+		assert_eq!(decoded.storage_root(), &H256::repeat_byte(1));
+		assert_eq!(decoded.nodes_len(), 1);
+		assert_eq!(decoded.values_len(), 1);
+
+		bytes[3] ^= 0xff;
+		assert!(matches!(
+			TrieCacheSnapshot::<H256>::from_bytes::<Blake2Hasher>(&bytes),
+			Err(SnapshotError::ChecksumMismatch)
+		));
+
+		assert!(matches!(
+			TrieCacheSnapshot::<H256>::from_bytes::<Blake2Hasher>(&bytes[..10]),
+			Err(SnapshotError::ChecksumMismatch)
+		));
+	}
+}
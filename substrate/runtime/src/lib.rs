@@ -238,6 +238,12 @@ impl pallet_aura::Config for Runtime {
 	type MaxAuthorities = MaxAuthorities;
 	type AllowMultipleBlocksPerSlot = ConstBool<false>;
 	type SlotDuration = ConstU64<{ SLOT_DURATION }>;
+	// Equivocation reporting is disabled: without session history there is no way to prove
+	// that an equivocating key belonged to an authority, so reports could never be checked.
+	type WeightInfo = ();
+	type MaxNominators = ConstU32<0>;
+	type KeyOwnerProof = sp_core::Void;
+	type EquivocationReportSystem = ();
 }
 
 impl pallet_grandpa::Config for Runtime {
@@ -343,6 +349,29 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_consensus_aura::AuraEquivocationApi<Block, AuraId> for Runtime {
+		fn generate_key_ownership_proof(
+			_slot: sp_consensus_aura::Slot,
+			_authority_id: AuraId,
+		) -> Option<sp_consensus_aura::OpaqueKeyOwnershipProof> {
+			// NOTE: this implementation doesn't have any session historical data,
+			// therefore no key ownership proofs can be generated.
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: sp_consensus_aura::EquivocationProof<
+				<Block as BlockT>::Header,
+				AuraId,
+			>,
+			key_owner_proof: sp_consensus_aura::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Aura::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+		}
+	}
+
 	impl sp_consensus_grandpa::GrandpaApi<Block> for Runtime {
 		fn grandpa_authorities() -> sp_consensus_grandpa::AuthorityList {
 			Grandpa::grandpa_authorities()
@@ -369,6 +398,12 @@ impl_runtime_apis! {
 			None
 		}
 	}
+
+	impl sp_consensus_grandpa::GrandpaFinalityDelayApi<Block> for Runtime {
+		fn finality_delay() -> Option<sp_runtime::traits::NumberFor<Block>> {
+			Grandpa::finality_delay()
+		}
+	}
 }
 
 
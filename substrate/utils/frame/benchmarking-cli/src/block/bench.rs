@@ -20,12 +20,13 @@
 use codec::DecodeAll;
 use frame_support::weights::constants::WEIGHT_REF_TIME_PER_NANOS;
 use frame_system::ConsumedWeight;
-use sc_block_builder::BlockBuilderApi;
+use sc_block_builder::{BlockBuilderApi, BlockStorageAccesses, QualifiedKey};
 use sc_cli::{Error, Result};
 use sc_client_api::{
 	Backend as ClientBackend, BlockBackend, HeaderBackend, StorageProvider, UsageProvider,
 };
-use sp_api::{ApiExt, Core, ProvideRuntimeApi};
+use sc_runtime_utilities::StorageKeyNames;
+use sp_api::{ApiExt, Core, Metadata, ProvideRuntimeApi};
 use sp_blockchain::Error::RuntimeApiError;
 use sp_runtime::{
 	generic::BlockId,
@@ -37,7 +38,7 @@ use sp_storage::StorageKey;
 use clap::Args;
 use log::{info, warn};
 use serde::Serialize;
-use std::{fmt::Debug, marker::PhantomData, sync::Arc, time::Instant};
+use std::{collections::BTreeSet, fmt::Debug, marker::PhantomData, sync::Arc, time::Instant};
 use thousands::Separable;
 
 use crate::shared::{StatSelect, Stats};
@@ -45,6 +46,12 @@ use crate::shared::{StatSelect, Stats};
 /// Log target for printing block weight info.
 const LOG_TARGET: &'static str = "benchmark::block::weight";
 
+/// Log target for printing the storage accesses of a block.
+const ACCESSES_LOG_TARGET: &'static str = "benchmark::block::accesses";
+
+/// Maximal number of hot keys and contended extrinsics that are printed per block.
+const MAX_LOGGED_ACCESSES: usize = 10;
+
 /// Parameters for modifying the benchmark behaviour.
 #[derive(Debug, Default, Serialize, Clone, PartialEq, Args)]
 pub struct BenchmarkParams {
@@ -59,6 +66,13 @@ pub struct BenchmarkParams {
 	/// Number of times that the benchmark should be repeated for each block.
 	#[arg(long, default_value_t = 10)]
 	pub repeat: u32,
+
+	/// Record the storage accessed by each extrinsic instead of measuring the execution time.
+	///
+	/// Prints the keys that are accessed by several extrinsics of a block and the extrinsics
+	/// which conflict with earlier ones and could therefore not be executed in parallel to them.
+	#[arg(long)]
+	pub storage_accesses: bool,
 }
 
 /// Convenience closure for the [`Benchmark::run()`] function.
@@ -80,7 +94,7 @@ where
 		+ UsageProvider<Block>
 		+ BlockBackend<Block>
 		+ HeaderBackend<Block>,
-	C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
+	C::Api: ApiExt<Block> + BlockBuilderApi<Block> + Metadata<Block>,
 {
 	/// Returns a new [`Self`] from the arguments.
 	pub fn new(client: Arc<C>, params: BenchmarkParams) -> Self {
@@ -96,9 +110,17 @@ where
 		for i in self.params.from..=self.params.to {
 			let block_num = BlockId::Number(i.into());
 			let hash = self.client.expect_block_hash_from_id(&block_num)?;
-			let consumed = self.consumed_weight(hash)?;
-
 			let block = self.client.block(hash)?.ok_or(format!("Block {} not found", block_num))?;
+
+			if self.params.storage_accesses {
+				let parent_hash = *block.block.header().parent_hash();
+				let accesses =
+					sc_block_builder::record_storage_accesses(&*self.client, block.block)?;
+				self.log_storage_accesses(i, &accesses, &self.storage_key_names(parent_hash));
+				continue
+			}
+
+			let consumed = self.consumed_weight(hash)?;
 			let block = self.unsealed(block.block);
 			let took = self.measure_block(&block, *block.header().parent_hash())?;
 
@@ -175,6 +197,71 @@ where
 		}
 	}
 
+	/// Returns the names of the storage items of the runtime at `block_hash`.
+	///
+	/// Falls back to no names if the metadata is not available.
+	fn storage_key_names(&self, block_hash: Block::Hash) -> StorageKeyNames {
+		let runtime_api = self.client.runtime_api();
+		runtime_api
+			.metadata_at_version(block_hash, 15)
+			.ok()
+			.flatten()
+			.or_else(|| runtime_api.metadata(block_hash).ok())
+			.and_then(|metadata| StorageKeyNames::from_metadata(&metadata).ok())
+			.unwrap_or_default()
+	}
+
+	/// Prints the hot keys and the contention between the extrinsics of a block to the console.
+	fn log_storage_accesses(
+		&self,
+		num: u32,
+		accesses: &BlockStorageAccesses,
+		names: &StorageKeyNames,
+	) {
+		let hot_keys = accesses.hot_keys();
+		let contention = accesses.contention();
+		let contended =
+			contention.iter().map(|contention| contention.extrinsic).collect::<BTreeSet<_>>();
+
+		info!(
+			target: ACCESSES_LOG_TARGET,
+			"Block {} with {: >5} tx has {} hot keys and {} tx conflicting with earlier ones",
+			num,
+			accesses.extrinsics.len(),
+			hot_keys.len(),
+			contended.len(),
+		);
+
+		for (key, usage) in hot_keys.iter().take(MAX_LOGGED_ACCESSES) {
+			info!(
+				target: ACCESSES_LOG_TARGET,
+				"  {} read by {} and written by {} tx",
+				describe_key(names, key),
+				usage.readers.len(),
+				usage.writers.len(),
+			);
+		}
+
+		for extrinsic in contended.iter().take(MAX_LOGGED_ACCESSES) {
+			let conflicts =
+				contention.iter().filter(|contention| contention.extrinsic == *extrinsic);
+			let writers = conflicts.clone().map(|contention| contention.writer).collect::<Vec<_>>();
+			let mut keys = conflicts
+				.flat_map(|contention| contention.keys.iter().map(|key| describe_key(names, key)))
+				.collect::<Vec<_>>();
+			keys.sort();
+			keys.dedup();
+
+			info!(
+				target: ACCESSES_LOG_TARGET,
+				"  tx {} conflicts with tx {:?} on [{}]",
+				extrinsic,
+				writers,
+				keys.join(", "),
+			);
+		}
+	}
+
 	/// Removes the consensus seal from the block.
 	fn unsealed(&self, block: Block) -> Block {
 		let (mut header, exts) = block.deconstruct();
@@ -182,3 +269,16 @@ where
 		Block::new(header, exts)
 	}
 }
+
+/// Describe `key` by the storage item it belongs to or by its hex encoding.
+fn describe_key(names: &StorageKeyNames, (child, key): &QualifiedKey) -> String {
+	match child {
+		// Keys of child tries are not described by the metadata.
+		Some(child) =>
+			format!("{}/{}", array_bytes::bytes2hex("0x", child), array_bytes::bytes2hex("0x", key)),
+		None => match names.name(key) {
+			Some(name) => format!("{}::{}", name.pallet, name.item),
+			None => array_bytes::bytes2hex("0x", key),
+		},
+	}
+}
@@ -21,7 +21,7 @@
 use sc_block_builder::BlockBuilderApi;
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_client_api::{Backend as ClientBackend, BlockBackend, StorageProvider, UsageProvider};
-use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_api::{ApiExt, Metadata, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_runtime::{traits::Block as BlockT, OpaqueExtrinsic};
 
@@ -55,6 +55,9 @@ use super::bench::{Benchmark, BenchmarkParams};
 /// The percent number is important and indicates how much weight
 /// was used as compared to the consumed weight.
 /// This number should be below 100% for reference hardware.
+///
+/// With `--storage-accesses` the blocks are not timed. Instead the storage keys accessed by
+/// more than one extrinsic and the extrinsics conflicting with earlier ones are printed.
 #[derive(Debug, Parser)]
 pub struct BlockCmd {
 	#[allow(missing_docs)]
@@ -89,7 +92,7 @@ impl BlockCmd {
 			+ StorageProvider<Block, BA>
 			+ UsageProvider<Block>
 			+ HeaderBackend<Block>,
-		C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
+		C::Api: ApiExt<Block> + BlockBuilderApi<Block> + Metadata<Block>,
 	{
 		// Put everything in the benchmark type to have the generic types handy.
 		Benchmark::new(client, self.params.clone()).run()
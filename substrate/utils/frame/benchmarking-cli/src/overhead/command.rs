@@ -487,6 +487,7 @@ impl OverheadCmd {
 
 		let backend = new_db_backend(DatabaseSettings {
 			trie_cache_maximum_size: self.trie_cache_maximum_size()?,
+			trie_cache_persistence: None,
 			state_pruning: None,
 			blocks_pruning: BlocksPruning::KeepAll,
 			source: database_source,